@@ -3,10 +3,12 @@
 //! This module provides functionality to:
 //! - Parse AMENT_PREFIX_PATH environment variable
 //! - Discover ROS 2 packages in the ament index
-//! - Locate interface files (.msg, .srv, .action) within packages
+//! - Locate interface files (.msg, .srv, .action, or their .idl) within packages
 
 use eyre::{eyre, Result, WrapErr};
-use std::collections::HashMap;
+use rosidl_parser::Message;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
 
 /// Represents a ROS 2 package discovered in the ament index
@@ -23,11 +25,11 @@ pub struct Package {
 /// Interface files discovered in a package
 #[derive(Debug, Clone, Default)]
 pub struct InterfaceFiles {
-    /// .msg files (relative to share_dir/msg/)
+    /// .msg files, or .idl without a .msg (relative to share_dir/msg/)
     pub messages: Vec<String>,
-    /// .srv files (relative to share_dir/srv/)
+    /// .srv files, or .idl without a .srv (relative to share_dir/srv/)
     pub services: Vec<String>,
-    /// .action files (relative to share_dir/action/)
+    /// .action files, or .idl without an .action (relative to share_dir/action/)
     pub actions: Vec<String>,
 }
 
@@ -68,20 +70,28 @@ impl Package {
     }
 
     /// Get the absolute path to a message file
+    ///
+    /// Falls back to the `.idl` file for messages installed without a `.msg`.
     pub fn get_message_path(&self, name: &str) -> PathBuf {
-        self.share_dir.join("msg").join(format!("{}.msg", name))
+        interface_path(&self.share_dir, "msg", name)
     }
 
     /// Get the absolute path to a service file
     pub fn get_service_path(&self, name: &str) -> PathBuf {
-        self.share_dir.join("srv").join(format!("{}.srv", name))
+        interface_path(&self.share_dir, "srv", name)
     }
 
     /// Get the absolute path to an action file
     pub fn get_action_path(&self, name: &str) -> PathBuf {
-        self.share_dir
-            .join("action")
-            .join(format!("{}.action", name))
+        interface_path(&self.share_dir, "action", name)
+    }
+
+    /// Read and parse a message from its `.msg` or `.idl` file
+    ///
+    /// Every error in a `.msg` file is reported, not only the first.
+    pub fn load_message(&self, name: &str) -> Result<Message> {
+        rosidl_parser::parse_message_file(&self.get_message_path(name))
+            .wrap_err_with(|| format!("Failed to parse message: {}", name))
     }
 
     /// Check if package has any interface files
@@ -90,14 +100,202 @@ impl Package {
             || !self.interfaces.services.is_empty()
             || !self.interfaces.actions.is_empty()
     }
+
+    /// Metadata from the installed package.xml (empty if there is none)
+    pub fn manifest(&self) -> PackageManifest {
+        std::fs::read_to_string(self.share_dir.join("package.xml"))
+            .map(|xml| PackageManifest::parse(&xml))
+            .unwrap_or_default()
+    }
+
+    /// SHA256 of the package's interface files, as a hex string
+    ///
+    /// Files are hashed in name order (messages, then services, then actions),
+    /// so the checksum is the same on every machine.
+    pub fn interface_checksum(&self) -> Result<String> {
+        let paths = self
+            .interfaces
+            .messages
+            .iter()
+            .map(|name| self.get_message_path(name))
+            .chain(
+                self.interfaces
+                    .services
+                    .iter()
+                    .map(|name| self.get_service_path(name)),
+            )
+            .chain(
+                self.interfaces
+                    .actions
+                    .iter()
+                    .map(|name| self.get_action_path(name)),
+            );
+
+        let mut hasher = Sha256::new();
+        for path in paths {
+            let content = std::fs::read(&path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+            hasher.update(&content);
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+/// Package metadata declared in package.xml
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PackageManifest {
+    /// `<version>`
+    pub version: Option<String>,
+    /// `<description>`, with whitespace collapsed
+    pub description: Option<String>,
+    /// `<license>` entries, in declaration order
+    pub licenses: Vec<String>,
+    /// `<author>` entries as `Name <email>`, or the maintainers if no author is listed
+    pub authors: Vec<String>,
+    /// `<url type="repository">`
+    pub repository: Option<String>,
+    /// Packages of the `<depend>`, then `<build_depend>` and `<exec_depend>`
+    /// entries, without repeats; build tools and runtime packages are listed
+    /// alongside interface packages
+    pub dependencies: Vec<String>,
+}
+
+impl PackageManifest {
+    /// Extract the metadata from package.xml content
+    ///
+    /// This is a lenient scan for the few elements needed, not a full XML
+    /// parser; missing or malformed elements are left empty.
+    pub fn parse(xml: &str) -> Self {
+        let text = |tag| {
+            xml_elements(xml, tag)
+                .into_iter()
+                .map(|(_, text)| text)
+                .find(|text| !text.is_empty())
+        };
+        let people = |tag| -> Vec<String> {
+            xml_elements(xml, tag)
+                .into_iter()
+                .filter(|(_, name)| !name.is_empty())
+                .map(|(attrs, name)| match xml_attribute(attrs, "email") {
+                    Some(email) => format!("{} <{}>", name, email),
+                    None => name,
+                })
+                .collect()
+        };
+
+        let mut authors = people("author");
+        if authors.is_empty() {
+            authors = people("maintainer");
+        }
+
+        let mut dependencies = Vec::new();
+        for tag in ["depend", "build_depend", "exec_depend"] {
+            for (_, dep) in xml_elements(xml, tag) {
+                if !dep.is_empty() && !dependencies.contains(&dep) {
+                    dependencies.push(dep);
+                }
+            }
+        }
+
+        PackageManifest {
+            version: text("version"),
+            description: text("description")
+                .map(|d| d.split_whitespace().collect::<Vec<_>>().join(" ")),
+            licenses: xml_elements(xml, "license")
+                .into_iter()
+                .map(|(_, license)| license)
+                .filter(|license| !license.is_empty())
+                .collect(),
+            authors,
+            repository: xml_elements(xml, "url")
+                .into_iter()
+                .find(|(attrs, _)| xml_attribute(attrs, "type") == Some("repository"))
+                .map(|(_, url)| url),
+            dependencies,
+        }
+    }
+}
+
+/// Attributes and trimmed, unescaped text of every `<tag>` element
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, String)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        // Skip longer tag names sharing the prefix (e.g. <url> vs <urls>)
+        if !rest.starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            continue;
+        }
+        let Some(attrs_end) = rest.find('>') else {
+            break;
+        };
+        let attrs = &rest[..attrs_end];
+        rest = &rest[attrs_end + 1..];
+        if attrs.ends_with('/') {
+            continue;
+        }
+        let Some(text_end) = rest.find(&close) else {
+            break;
+        };
+        elements.push((attrs, xml_unescape(rest[..text_end].trim())));
+        rest = &rest[text_end + close.len()..];
+    }
+
+    elements
+}
+
+/// Value of `name="..."` in an element's attributes
+fn xml_attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!("{}=", name);
+    let mut search = attrs;
+    while let Some(position) = search.find(&pattern) {
+        let preceded_by_space = search[..position]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        let value = &search[position + pattern.len()..];
+        if preceded_by_space {
+            let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let value = &value[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        search = value;
+    }
+    None
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Path of the `kind` interface `name` in `share_dir`, preferring the original
+/// source over the `.idl` generated from it
+fn interface_path(share_dir: &Path, kind: &str, name: &str) -> PathBuf {
+    let path = share_dir.join(kind).join(format!("{}.{}", name, kind));
+    let idl_path = path.with_extension("idl");
+    if !path.exists() && idl_path.exists() {
+        idl_path
+    } else {
+        path
+    }
 }
 
 /// Discover interface files in a directory with a specific extension
+///
+/// Interfaces installed only as `.idl` (without the original source) are included.
 fn discover_interface_files(dir: &Path, extension: &str) -> Result<Vec<String>> {
-    let mut files = Vec::new();
+    let mut files = BTreeSet::new();
 
     if !dir.exists() {
-        return Ok(files);
+        return Ok(Vec::new());
     }
 
     for entry in std::fs::read_dir(dir)
@@ -108,17 +306,16 @@ fn discover_interface_files(dir: &Path, extension: &str) -> Result<Vec<String>>
 
         if path.is_file() {
             if let Some(ext) = path.extension() {
-                if ext == extension {
+                if ext == extension || ext == "idl" {
                     if let Some(name) = path.file_stem() {
-                        files.push(name.to_string_lossy().to_string());
+                        files.insert(name.to_string_lossy().to_string());
                     }
                 }
             }
         }
     }
 
-    files.sort();
-    Ok(files)
+    Ok(files.into_iter().collect())
 }
 
 /// Ament index for discovering ROS 2 packages
@@ -190,7 +387,6 @@ impl AmentIndex {
     }
 
     /// Get all discovered packages
-    #[allow(dead_code)] // Used by cargo-ros2 crate
     pub fn packages(&self) -> &HashMap<String, Package> {
         &self.packages
     }
@@ -338,4 +534,128 @@ mod tests {
         assert!(index.find_package("pkg1").is_some());
         assert!(index.find_package("pkg2").is_some());
     }
+
+    #[test]
+    fn test_package_version_and_checksum() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let prefix = create_test_prefix(temp_dir.path(), "test_ws");
+        create_test_package(&prefix, "test_msgs", &["Point"], &["AddTwoInts"], &[]);
+        let share_dir = prefix.join("share/test_msgs");
+
+        let pkg = Package::from_share_dir(share_dir.clone()).unwrap();
+        assert_eq!(pkg.manifest(), PackageManifest::default());
+        let checksum = pkg.interface_checksum().unwrap();
+        assert_eq!(checksum.len(), 64);
+
+        fs::write(
+            share_dir.join("package.xml"),
+            "<package format=\"3\">\n  <name>test_msgs</name>\n  <version>4.2.3</version>\n</package>\n",
+        )
+        .unwrap();
+        assert_eq!(pkg.manifest().version.as_deref(), Some("4.2.3"));
+
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\n").unwrap();
+        assert_ne!(pkg.interface_checksum().unwrap(), checksum);
+    }
+
+    #[test]
+    fn test_idl_only_interfaces() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share_dir = temp_dir.path().join("share/test_msgs");
+        let msg_dir = share_dir.join("msg");
+        fs::create_dir_all(&msg_dir).unwrap();
+        fs::write(msg_dir.join("Point.msg"), "float64 x\n").unwrap();
+        fs::write(msg_dir.join("Point.idl"), "").unwrap();
+        fs::write(
+            msg_dir.join("Flag.idl"),
+            "module test_msgs {\n  module msg {\n    struct Flag {\n      boolean value;\n    };\n  };\n};\n",
+        )
+        .unwrap();
+
+        let pkg = Package::from_share_dir(share_dir).unwrap();
+        assert_eq!(pkg.interfaces.messages, ["Flag", "Point"]);
+        // The original source wins over the generated .idl
+        assert_eq!(pkg.get_message_path("Point"), msg_dir.join("Point.msg"));
+        assert_eq!(pkg.get_message_path("Flag"), msg_dir.join("Flag.idl"));
+        assert_eq!(pkg.load_message("Flag").unwrap().fields[0].name, "value");
+        assert_eq!(pkg.load_message("Point").unwrap().fields[0].name, "x");
+    }
+
+    #[test]
+    fn test_load_message_reports_all_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let prefix = create_test_prefix(temp_dir.path(), "test_ws");
+        create_test_package(&prefix, "test_msgs", &["Point"], &[], &[]);
+        let share_dir = prefix.join("share/test_msgs");
+        fs::write(
+            share_dir.join("msg/Point.msg"),
+            "float64 = 1\nfloat64 y\nint8[ z\n",
+        )
+        .unwrap();
+
+        let pkg = Package::from_share_dir(share_dir).unwrap();
+        let report = format!("{:?}", pkg.load_message("Point").unwrap_err());
+        assert!(report.contains("Failed to parse message: Point"));
+        assert!(report.contains("at line 1, column 9"));
+        assert!(report.contains("at line 3, column 7"));
+    }
+
+    #[test]
+    fn test_parse_package_manifest() {
+        let manifest = PackageManifest::parse(
+            r#"<?xml version="1.0"?>
+<package format="3">
+  <name>geometry_msgs</name>
+  <version>4.2.3</version>
+  <description>
+    A package containing some geometry related message definitions.
+  </description>
+  <maintainer email="tfoote@osrfoundation.org">Tully Foote</maintainer>
+  <license>Apache License 2.0</license>
+  <license>BSD &amp; MIT</license>
+  <url type="website">https://index.ros.org</url>
+  <url type="repository">https://github.com/ros2/common_interfaces</url>
+  <author email="dthomas@osrfoundation.org">Dirk Thomas</author>
+  <author/>
+  <buildtool_depend>ament_cmake</buildtool_depend>
+  <depend>builtin_interfaces</depend>
+  <depend>std_msgs</depend>
+  <build_depend>rosidl_default_generators</build_depend>
+  <exec_depend>rosidl_default_runtime</exec_depend>
+  <exec_depend>std_msgs</exec_depend>
+  <test_depend>ament_lint_common</test_depend>
+</package>
+"#,
+        );
+
+        assert_eq!(manifest.version.as_deref(), Some("4.2.3"));
+        assert_eq!(
+            manifest.description.as_deref(),
+            Some("A package containing some geometry related message definitions.")
+        );
+        assert_eq!(manifest.licenses, ["Apache License 2.0", "BSD & MIT"]);
+        assert_eq!(
+            manifest.authors,
+            ["Dirk Thomas <dthomas@osrfoundation.org>"]
+        );
+        assert_eq!(
+            manifest.repository.as_deref(),
+            Some("https://github.com/ros2/common_interfaces")
+        );
+        assert_eq!(
+            manifest.dependencies,
+            [
+                "builtin_interfaces",
+                "std_msgs",
+                "rosidl_default_generators",
+                "rosidl_default_runtime"
+            ]
+        );
+
+        // Maintainers stand in for missing authors
+        let manifest =
+            PackageManifest::parse("<package><maintainer>Jane Doe</maintainer></package>");
+        assert_eq!(manifest.authors, ["Jane Doe"]);
+        assert_eq!(manifest.repository, None);
+    }
 }
@@ -0,0 +1,213 @@
+//! Comparison of rendered bindings against an output directory
+//!
+//! `--dry-run` renders the generated crates in memory, through an in-memory
+//! [`Output`](crate::output::Output), and reports how their files differ from
+//! the ones already in the output directory, optionally as unified diffs,
+//! without touching it.
+
+use eyre::{Result, WrapErr};
+use serde::Serialize;
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Entries of a crate directory left out of the comparison: build output and
+/// the lock file of a crate built in place
+const IGNORED: &[&str] = &["target", "Cargo.lock"];
+
+/// How a file of the output directory would change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    /// Generated, not in the output directory yet
+    Created,
+    /// Generated with a different content
+    Changed,
+    /// In the output directory, no longer generated
+    Removed,
+}
+
+impl Change {
+    fn label(self) -> &'static str {
+        match self {
+            Change::Created => "created",
+            Change::Changed => "changed",
+            Change::Removed => "removed",
+        }
+    }
+}
+
+/// A file that differs, by its path relative to the output directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub change: Change,
+    /// Unified diff of the file, when asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// Files that differ between the `rendered` files, by their path relative to
+/// the output directory, and the crate directories of `output` they were
+/// rendered into, in path order
+pub fn compare(rendered: &BTreeMap<PathBuf, String>, output: &Path) -> Result<Vec<FileChange>> {
+    let crates: BTreeSet<&Path> = rendered
+        .keys()
+        .filter_map(|path| path.components().next())
+        .map(|name| Path::new(name.as_os_str()))
+        .collect();
+    let mut changes = Vec::new();
+    for name in crates {
+        let output_files = crate_files(&output.join(name))?;
+        let rendered_files: BTreeSet<PathBuf> = rendered
+            .keys()
+            .filter_map(|path| path.strip_prefix(name).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        for file in rendered_files.union(&output_files) {
+            let path = name.join(file);
+            let change = match (rendered.get(&path), output_files.contains(file)) {
+                (Some(_), false) => Change::Created,
+                (None, _) => Change::Removed,
+                (Some(new), true) => {
+                    if new.as_bytes() == read(&output.join(&path))? {
+                        continue;
+                    }
+                    Change::Changed
+                }
+            };
+            changes.push(FileChange {
+                path,
+                change,
+                diff: None,
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// One `<change> <path>` line per file
+pub fn summary(changes: &[FileChange]) -> String {
+    changes
+        .iter()
+        .map(|file| format!("{} {}\n", file.change.label(), file.path.display()))
+        .collect()
+}
+
+/// Unified diff of a file from its content in `output` to the rendered one, a
+/// created or removed file diffing against nothing
+pub fn unified_diff(
+    rendered: &BTreeMap<PathBuf, String>,
+    output: &Path,
+    file: &FileChange,
+) -> Result<String> {
+    let old = match file.change {
+        Change::Created => String::new(),
+        _ => text(&output.join(&file.path))?,
+    };
+    let new = rendered.get(&file.path).cloned().unwrap_or_default();
+    let path = file.path.display().to_string().replace('\\', "/");
+    Ok(TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string())
+}
+
+/// Paths of the files under a crate directory, relative to it
+fn crate_files(dir: &Path) -> Result<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    if dir.is_dir() {
+        collect_files(dir, Path::new(""), &mut files)?;
+    }
+    Ok(files)
+}
+
+fn collect_files(root: &Path, relative: &Path, files: &mut BTreeSet<PathBuf>) -> Result<()> {
+    let dir = root.join(relative);
+    for entry in
+        std::fs::read_dir(&dir).wrap_err_with(|| format!("Failed to read {}", dir.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name();
+        if relative.as_os_str().is_empty() && IGNORED.iter().any(|ignored| name == *ignored) {
+            continue;
+        }
+        let path = relative.join(&name);
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            files.insert(path);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).wrap_err_with(|| format!("Failed to read {}", path.display()))
+}
+
+fn text(path: &Path) -> Result<String> {
+    Ok(String::from_utf8_lossy(&read(path)?).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_compare() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output = temp_dir.path();
+        let rendered = BTreeMap::from([
+            (
+                PathBuf::from("test_msgs/build.rs"),
+                "fn main() {}\n".to_string(),
+            ),
+            (
+                PathBuf::from("test_msgs/Cargo.toml"),
+                "a\nb = 2\n".to_string(),
+            ),
+            (
+                PathBuf::from("test_msgs/src/msg/point.rs"),
+                "x\n".to_string(),
+            ),
+        ]);
+        fs::create_dir_all(output.join("test_msgs/src/msg")).unwrap();
+        fs::write(output.join("test_msgs/build.rs"), "fn main() {}\n").unwrap();
+        fs::write(output.join("test_msgs/Cargo.toml"), "a\nb = 1\n").unwrap();
+        fs::write(output.join("test_msgs/src/msg/pose.rs"), "y\n").unwrap();
+        // Crates that were not rendered are not compared
+        fs::create_dir_all(output.join("other_msgs")).unwrap();
+        fs::write(output.join("other_msgs/build.rs"), "").unwrap();
+        // Build output is not compared
+        fs::create_dir_all(output.join("test_msgs/target/debug")).unwrap();
+        fs::write(output.join("test_msgs/target/debug/libtest_msgs.rlib"), "").unwrap();
+        fs::write(output.join("test_msgs/Cargo.lock"), "").unwrap();
+
+        let changes = compare(&rendered, output).unwrap();
+        assert_eq!(
+            summary(&changes),
+            "changed test_msgs/Cargo.toml\n\
+             created test_msgs/src/msg/point.rs\n\
+             removed test_msgs/src/msg/pose.rs\n"
+        );
+
+        let diff = unified_diff(&rendered, output, &changes[0]).unwrap();
+        assert_eq!(
+            diff,
+            "--- a/test_msgs/Cargo.toml\n+++ b/test_msgs/Cargo.toml\n\
+             @@ -1,2 +1,2 @@\n a\n-b = 1\n+b = 2\n"
+        );
+        let diff = unified_diff(&rendered, output, &changes[2]).unwrap();
+        assert!(diff.ends_with("@@ -1 +0,0 @@\n-y\n"));
+
+        // A crate missing from the output directory is created as a whole
+        let rendered = BTreeMap::from([(PathBuf::from("new_msgs/build.rs"), String::new())]);
+        assert_eq!(
+            summary(&compare(&rendered, output).unwrap()),
+            "created new_msgs/build.rs\n"
+        );
+    }
+}
@@ -5,31 +5,54 @@
 //! - Generate Rust code for messages, services, and actions
 //! - Write generated code to output directory with proper structure
 
-use crate::ament::Package;
-use eyre::{Result, WrapErr};
+use crate::ament::{AmentIndex, Package, PackageManifest};
+use crate::output::Output;
+use eyre::{eyre, Result, WrapErr};
+use rayon::prelude::*;
 use rosidl_codegen::{
-    generate_action_package, generate_message_package, generate_service_package,
-    utils::{extract_dependencies, needs_big_array, to_snake_case},
-    GeneratedPackage,
+    cxx::{cxx_dependencies, generate_cxx_bridge},
+    generate_interface,
+    protobuf::{generate_proto_conversions, generate_proto_file, proto_dependencies},
+    python::{generate_python_module, python_dependencies},
+    ros1::{generate_ros1_module, type_mapping_report, Ros1Resolver, TypeMappingNote},
+    type_description::{generate_type_description_module, TypeDescriptionResolver},
+    types::comment_docs,
+    utils::{closest_name, extract_dependencies, extract_local_types, to_snake_case},
+    GeneratedArtifacts, GeneratorOptions, Interface, FFI_MODULE, RCLRS_RUNTIME_VERSION,
 };
-use std::collections::HashSet;
+use rosidl_parser::{Action, Message, ParsedPackage, Service};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Module layout of a generated crate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    /// cargo-ros2 layout (`pkg::msg::point::Point`, `pkg::ffi::msg::point::Point`)
+    #[default]
+    Default,
+    /// Paths of colcon-generated crates from rosidl_generator_rs
+    /// (`pkg::msg::Point`, `pkg::msg::rmw::Point`, `pkg::srv::AddTwoInts_Request`)
+    Ros2Rust,
+}
 
-/// Top-level module name for C-compatible FFI layer (Foreign Function Interface).
-/// This is placed at the crate root level to avoid conflicts with any message/service/action names.
-///
-/// The dual-layer architecture is:
-/// - `pkg::ffi::msg::Type` - C-compatible FFI structs for interop with ROS C libraries
-/// - `pkg::msg::Type` - Idiomatic Rust wrappers with safe types (String, Vec, etc.)
-///
-/// By placing `ffi` at the package root (not nested in msg/srv/action), it cannot conflict
-/// with any message names (e.g., ffi.msg, rmw.msg, etc.)
-const FFI_MODULE: &str = "ffi";
+/// How a generated crate refers to the generated crates of its dependencies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DependencyStyle {
+    /// `std_msgs = { path = "../std_msgs", version = "5.3.2" }`, so the output
+    /// directory is a self-consistent set of crates
+    #[default]
+    Path,
+    /// `std_msgs = "5.3.2"`, resolved from a registry or `[patch]` entries
+    Version,
+}
 
 /// Generated Rust package structure
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct GeneratedRustPackage {
     /// Package name
+    #[serde(rename = "package")]
     pub name: String,
     /// Output directory where code was written
     pub output_dir: PathBuf,
@@ -39,15 +62,28 @@ pub struct GeneratedRustPackage {
     pub service_count: usize,
     /// Number of actions generated
     pub action_count: usize,
+    /// Generated interfaces as `msg/Point`, `srv/AddTwoInts`, ..., messages
+    /// first and each kind in name order
+    pub interfaces: Vec<String>,
+    /// ROS packages the generated crate depends on, in name order
+    pub dependencies: Vec<String>,
 }
 
+/// Directory of the bundled runtime crate in the output directory, which rclrs
+/// bindings take from crates.io instead
+pub const RUNTIME_CRATE: &str = "rosidl_runtime_rs";
+
 /// Ensure rosidl_runtime_rs crate exists in the output directory
 /// This shared crate is generated once and used by all packages
-fn ensure_rosidl_runtime_rs(output_dir: &Path) -> Result<()> {
-    let runtime_rs_dir = output_dir.join("rosidl_runtime_rs");
+pub fn ensure_rosidl_runtime_rs(output: &Output) -> Result<()> {
+    // Packages generated in parallel must not copy the crate over each other
+    static COPY: Mutex<()> = Mutex::new(());
+    let _guard = COPY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let runtime_rs_dir = output.dir().join(RUNTIME_CRATE);
 
     // If it already exists, no need to copy
-    if runtime_rs_dir.exists() {
+    if output.dir_exists(&runtime_rs_dir) {
         return Ok(());
     }
 
@@ -114,7 +150,7 @@ fn ensure_rosidl_runtime_rs(output_dir: &Path) -> Result<()> {
         })?;
 
     // Copy the entire directory
-    copy_dir_all(&source_dir, &runtime_rs_dir).wrap_err_with(|| {
+    copy_dir_all(&source_dir, &runtime_rs_dir, output).wrap_err_with(|| {
         format!(
             "Failed to copy rosidl_runtime_rs from {} to {}",
             source_dir.display(),
@@ -123,16 +159,15 @@ fn ensure_rosidl_runtime_rs(output_dir: &Path) -> Result<()> {
     })?;
 
     // Fix Cargo.toml to remove workspace inheritance
-    fix_cargo_toml_workspace_inheritance(&runtime_rs_dir)?;
+    fix_cargo_toml_workspace_inheritance(&runtime_rs_dir, output)?;
 
     Ok(())
 }
 
 /// Fix Cargo.toml by replacing workspace inheritance with explicit values
-fn fix_cargo_toml_workspace_inheritance(crate_dir: &Path) -> Result<()> {
+fn fix_cargo_toml_workspace_inheritance(crate_dir: &Path, output: &Output) -> Result<()> {
     let cargo_toml_path = crate_dir.join("Cargo.toml");
-    let content =
-        std::fs::read_to_string(&cargo_toml_path).wrap_err("Failed to read Cargo.toml")?;
+    let content = output.read(&cargo_toml_path)?;
 
     // Replace workspace inheritance with explicit values
     // Also fix package name to use underscore (rosidl_runtime_rs) instead of dash
@@ -153,15 +188,13 @@ fn fix_cargo_toml_workspace_inheritance(crate_dir: &Path) -> Result<()> {
             "repository = \"https://github.com/your-org/cargo-ros2\"",
         );
 
-    std::fs::write(&cargo_toml_path, fixed_content).wrap_err("Failed to write fixed Cargo.toml")?;
+    output.write(&cargo_toml_path, fixed_content)?;
 
     Ok(())
 }
 
 /// Recursively copy a directory
-fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
-    std::fs::create_dir_all(dst)?;
-
+fn copy_dir_all(src: &Path, dst: &Path, output: &Output) -> Result<()> {
     for entry in std::fs::read_dir(src)? {
         let entry = entry?;
         let file_type = entry.file_type()?;
@@ -176,9 +209,9 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
         }
 
         if file_type.is_dir() {
-            copy_dir_all(&src_path, &dst_path)?;
+            copy_dir_all(&src_path, &dst_path, output)?;
         } else {
-            std::fs::copy(&src_path, &dst_path)?;
+            output.copy(&src_path, &dst_path)?;
         }
     }
 
@@ -186,225 +219,696 @@ fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
 }
 
 /// Generate Rust bindings for a ROS 2 package
-pub fn generate_package(package: &Package, output_dir: &Path) -> Result<GeneratedRustPackage> {
+///
+/// With `rclrs_compat`, the package depends on the upstream `rosidl_runtime_rs`
+/// crate from crates.io (the one rclrs uses) instead of the bundled copy.
+/// `layout` selects the module paths the generated crate exposes, and
+/// `dependency_style` how it refers to the crates of its dependencies, whose
+/// versions are looked up in `index`.
+pub fn generate_package(
+    package: &Package,
+    output: &Output,
+    mut options: GeneratorOptions,
+    layout: Layout,
+    dependency_style: DependencyStyle,
+    index: Option<&AmentIndex>,
+) -> Result<GeneratedRustPackage> {
     // Ensure rosidl_runtime_rs crate is available (generate once per workspace)
-    ensure_rosidl_runtime_rs(output_dir)?;
+    if !options.rclrs_compat {
+        ensure_rosidl_runtime_rs(output)?;
+    }
 
-    let package_output = output_dir.join(&package.name);
-    std::fs::create_dir_all(&package_output).wrap_err_with(|| {
-        format!(
-            "Failed to create output directory: {}",
-            package_output.display()
-        )
+    let package_output = output.dir().join(&package.name);
+
+    // Packages the interfaces may refer to; without an index, references are
+    // not checked
+    let known_packages: HashSet<String> = index
+        .map(|index| index.packages().keys().cloned().collect())
+        .unwrap_or_default();
+
+    // Types-only and embedded bindings cover messages; services and actions need the RMW layer
+    let parsed = parse_interfaces(package, options.messages_only())?;
+    // Errors name the package, message and field
+    options.cyclic_fields = rosidl_codegen::cyclic_fields(&package.name, &parsed.messages)?;
+
+    let manifest = package.manifest();
+    let mut dependencies = interface_dependencies(&package.name, &parsed, &options);
+    dependencies.extend(declared_dependencies(package, &manifest, index));
+
+    // Interfaces are generated and written in parallel, each to files of its own
+    let generate = |name: &str, interface: Interface| {
+        let generated = generate_interface(
+            &package.name,
+            name,
+            interface,
+            &known_packages,
+            options.clone(),
+        )?;
+        write_artifacts(&generated, &package_output, output)
+    };
+    let message_count = for_each_interface(&parsed.messages, |name, parsed_msg| {
+        generate(name, Interface::Message(parsed_msg))
     })?;
+    let service_count = for_each_interface(&parsed.services, |name, parsed_srv| {
+        generate(name, Interface::Service(parsed_srv))
+    })?;
+    let action_count = for_each_interface(&parsed.actions, |name, parsed_action| {
+        generate(name, Interface::Action(parsed_action))
+    })?;
+
+    let dependencies: BTreeMap<_, _> = dependencies
+        .into_iter()
+        .map(|dep| {
+            let version = index
+                .and_then(|index| index.find_package(&dep))
+                .map(|dep_package| crate_version(&dep_package.manifest()));
+            (dep, version)
+        })
+        .collect();
 
-    let mut message_count = 0;
-    let mut service_count = 0;
-    let mut action_count = 0;
-    let mut all_dependencies = HashSet::new();
-    let mut package_needs_big_array = false;
+    // Generate lib.rs that re-exports all generated code
+    generate_lib_rs(&package_output, output, package, &parsed, &options, layout)?;
+    generate_provenance_rs(&package_output, output, package)?;
+    output.write(
+        &package_output.join("src").join("serde_array.rs"),
+        rosidl_codegen::generate_serde_array_rs()?,
+    )?;
+    // The upstream runtime used with --rclrs has no ParameterValue enum, the
+    // no_std runtime of embedded bindings neither
+    if !options.rclrs_compat
+        && options.embedded.is_none()
+        && package.name == PARAMETER_PACKAGE
+        && package
+            .interfaces
+            .messages
+            .iter()
+            .any(|m| m == "ParameterValue")
+    {
+        generate_parameter_rs(&package_output, output, options.interface_features)?;
+    }
 
-    // For dependency tracking (cross-package references)
-    let known_packages = HashSet::new(); // TODO: populate from ament index
+    // Generate Cargo.toml for the package
+    generate_cargo_toml(
+        output,
+        &package.name,
+        &manifest,
+        &dependencies,
+        &options,
+        layout,
+        dependency_style,
+    )?;
+    if options.interface_features {
+        add_interface_features(
+            &package_output,
+            output,
+            &interface_features(&package.name, &parsed),
+        )?;
+    }
 
-    // Generate messages
-    for msg_name in &package.interfaces.messages {
-        let msg_path = package.get_message_path(msg_name);
-        let content = std::fs::read_to_string(&msg_path)
-            .wrap_err_with(|| format!("Failed to read message file: {}", msg_path.display()))?;
+    // Generate build.rs for FFI linking
+    if options.messages_only() {
+        // Nothing to link, but extras like --protobuf extend the build script
+        output.write(
+            &package_output.join("build.rs"),
+            "fn main() {\n    // Types-only bindings link no ROS C libraries\n}\n",
+        )?;
+    } else {
+        generate_build_rs(&package_output, output, &package.name)?;
+    }
 
-        let parsed_msg = rosidl_parser::parse_message(&content)
-            .wrap_err_with(|| format!("Failed to parse message: {}", msg_name))?;
+    let interfaces = parsed
+        .messages
+        .keys()
+        .map(|name| format!("msg/{}", name))
+        .chain(parsed.services.keys().map(|name| format!("srv/{}", name)))
+        .chain(parsed.actions.keys().map(|name| format!("action/{}", name)))
+        .collect();
+    Ok(GeneratedRustPackage {
+        name: package.name.clone(),
+        output_dir: package_output,
+        message_count,
+        service_count,
+        action_count,
+        interfaces,
+        dependencies: dependencies.into_keys().collect(),
+    })
+}
 
-        // Extract dependencies from this message
-        let msg_deps = extract_dependencies(&parsed_msg);
-        all_dependencies.extend(msg_deps);
+/// ROS packages the generated crate of a package depends on: the ones its
+/// interfaces refer to and the interface packages its package.xml declares
+pub fn package_dependencies(
+    package: &Package,
+    options: &GeneratorOptions,
+    index: Option<&AmentIndex>,
+) -> Result<BTreeSet<String>> {
+    let parsed = parse_interfaces(package, options.messages_only())?;
+    let mut dependencies = interface_dependencies(&package.name, &parsed, options);
+    dependencies.extend(declared_dependencies(package, &package.manifest(), index));
+    Ok(dependencies)
+}
 
-        // Check if this message needs big_array support
-        if needs_big_array(&parsed_msg) {
-            package_needs_big_array = true;
+/// `packages` followed by every package they depend on, directly or not, as
+/// found in `index`
+///
+/// Dependencies come in breadth-first order, the dependencies of each package
+/// in name order, so the result is the same from run to run.
+pub fn dependency_closure(
+    packages: Vec<Package>,
+    index: &AmentIndex,
+    options: &GeneratorOptions,
+) -> Result<Vec<Package>> {
+    let mut seen: HashSet<String> = packages.iter().map(|p| p.name.clone()).collect();
+    let mut closure = packages;
+    let mut next = 0;
+    while next < closure.len() {
+        let package = &closure[next];
+        let mut found = Vec::new();
+        for dep in package_dependencies(package, options, Some(index))? {
+            if seen.contains(&dep) {
+                continue;
+            }
+            let dep_package = index.find_package(&dep).ok_or_else(|| {
+                let suggestion = closest_name(&dep, index.packages().keys().map(String::as_str))
+                    .map(|name| format!(", did you mean {}?", name))
+                    .unwrap_or_default();
+                eyre!(
+                    "Package '{}' (a dependency of {}) not found in ament index{}",
+                    dep,
+                    package.name,
+                    suggestion
+                )
+            })?;
+            seen.insert(dep);
+            found.push(dep_package.clone());
         }
+        closure.extend(found);
+        next += 1;
+    }
+    Ok(closure)
+}
 
-        let generated =
-            generate_message_package(&package.name, msg_name, &parsed_msg, &known_packages)
-                .wrap_err_with(|| format!("Failed to generate message: {}", msg_name))?;
+/// Dependencies declared in the package.xml of a package that are interface
+/// packages of `index`
+///
+/// Interfaces may need a package without naming it in a field, such as one
+/// whose constants they mirror. Build tools and runtime packages are told apart
+/// by not being in the index, so without one nothing is declared.
+fn declared_dependencies(
+    package: &Package,
+    manifest: &PackageManifest,
+    index: Option<&AmentIndex>,
+) -> BTreeSet<String> {
+    let Some(index) = index else {
+        return BTreeSet::new();
+    };
+    manifest
+        .dependencies
+        .iter()
+        .filter(|dep| **dep != package.name && index.find_package(dep).is_some())
+        .cloned()
+        .collect()
+}
 
-        write_generated_package(&generated, &package_output, msg_name)?;
-        message_count += 1;
+/// ROS packages the parsed interfaces of `package_name` refer to, including
+/// the ones of the implicit service event and action messages
+fn interface_dependencies(
+    package_name: &str,
+    parsed: &ParsedPackage,
+    options: &GeneratorOptions,
+) -> BTreeSet<String> {
+    let mut dependencies = BTreeSet::new();
+    for parsed_msg in parsed.messages.values() {
+        dependencies.extend(extract_dependencies(parsed_msg));
     }
+    for parsed_srv in parsed.services.values() {
+        dependencies.extend(extract_dependencies(&parsed_srv.request));
+        dependencies.extend(extract_dependencies(&parsed_srv.response));
+        if options.service_events {
+            // The event message embeds service_msgs/ServiceEventInfo
+            dependencies.insert(rosidl_codegen::SERVICE_MSGS_PACKAGE.to_string());
+        }
+    }
+    for parsed_action in parsed.actions.values() {
+        dependencies.extend(extract_dependencies(&parsed_action.spec.goal));
+        dependencies.extend(extract_dependencies(&parsed_action.spec.result));
+        dependencies.extend(extract_dependencies(&parsed_action.spec.feedback));
+        // The implicit messages embed unique_identifier_msgs/UUID and builtin_interfaces/Time
+        dependencies.insert(rosidl_codegen::UNIQUE_IDENTIFIER_MSGS_PACKAGE.to_string());
+        dependencies.insert(rosidl_codegen::BUILTIN_INTERFACES_PACKAGE.to_string());
+    }
+    // Remove self-dependency (package shouldn't depend on itself)
+    dependencies.remove(package_name);
+    dependencies
+}
+
+/// Run `generate` on every interface in parallel, returning how many there are
+///
+/// Fails with the error of the first failing interface in name order, so the
+/// outcome does not depend on scheduling.
+fn for_each_interface<T: Sync>(
+    interfaces: &BTreeMap<String, T>,
+    generate: impl Fn(&str, &T) -> Result<()> + Sync,
+) -> Result<usize> {
+    let results: Vec<Result<()>> = interfaces
+        .par_iter()
+        .map(|(name, interface)| generate(name, interface))
+        .collect();
+    results.into_iter().collect::<Result<Vec<()>>>()?;
+    Ok(interfaces.len())
+}
 
-    // Generate services
-    for srv_name in &package.interfaces.services {
-        let srv_path = package.get_service_path(srv_name);
-        let content = std::fs::read_to_string(&srv_path)
-            .wrap_err_with(|| format!("Failed to read service file: {}", srv_path.display()))?;
+/// Parsed interfaces of a package, as printed by `--emit ast`
+#[derive(Debug, Serialize)]
+pub struct PackageAst {
+    pub package: String,
+    pub messages: BTreeMap<String, Message>,
+    pub services: BTreeMap<String, Service>,
+    pub actions: BTreeMap<String, Action>,
+}
 
-        let parsed_srv = rosidl_parser::parse_service(&content)
-            .wrap_err_with(|| format!("Failed to parse service: {}", srv_name))?;
+/// Parse every interface of a package without generating code
+pub fn package_ast(package: &Package) -> Result<PackageAst> {
+    let parsed = parse_interfaces(package, false)?;
+    Ok(PackageAst {
+        package: package.name.clone(),
+        messages: parsed.messages,
+        services: parsed.services,
+        actions: parsed.actions,
+    })
+}
 
-        // Extract dependencies from request and response messages
-        let req_deps = extract_dependencies(&parsed_srv.request);
-        let resp_deps = extract_dependencies(&parsed_srv.response);
-        all_dependencies.extend(req_deps);
-        all_dependencies.extend(resp_deps);
+/// Parse the interfaces of a package, failing with the errors of every broken file
+///
+/// With `messages_only`, services and actions are left out (and so are their errors).
+fn parse_interfaces(package: &Package, messages_only: bool) -> Result<ParsedPackage> {
+    let mut parsed = rosidl_parser::parse_package(&package.share_dir)
+        .wrap_err_with(|| format!("Failed to read interfaces of {}", package.name))?;
+    if messages_only {
+        let msg_dir = package.share_dir.join("msg");
+        parsed.services.clear();
+        parsed.actions.clear();
+        parsed.errors.retain(|err| err.path().starts_with(&msg_dir));
+    }
+    if !parsed.errors.is_empty() {
+        let report: Vec<_> = parsed.errors.iter().map(ToString::to_string).collect();
+        return Err(eyre!("{}", report.join("\n\n")))
+            .wrap_err_with(|| format!("Failed to parse interfaces of {}", package.name));
+    }
+    Ok(parsed)
+}
 
-        // Check if request or response needs big_array support
-        if needs_big_array(&parsed_srv.request) || needs_big_array(&parsed_srv.response) {
-            package_needs_big_array = true;
+/// Generate ROS 1 compatibility metadata for an already generated package
+///
+/// Writes `src/ros1.rs` with `DATATYPE`, `MD5SUM` and `MESSAGE_DEFINITION` constants
+/// per message, and a `ros1_type_mapping.txt` report of fields whose type differs in
+/// ROS 1. Messages from other packages are resolved through `index`.
+pub fn generate_ros1_metadata(
+    package: &Package,
+    index: Option<&AmentIndex>,
+    output: &Output,
+) -> Result<Vec<TypeMappingNote>> {
+    let package_output = output.dir().join(&package.name);
+
+    let mut resolver = Ros1Resolver::new(|dep_package: &str, name: &str| {
+        if dep_package == package.name {
+            package.load_message(name).ok()
+        } else {
+            index?.find_package(dep_package)?.load_message(name).ok()
         }
+    });
+
+    let mut messages = Vec::new();
+    let mut notes = Vec::new();
+    for msg_name in &package.interfaces.messages {
+        let parsed_msg = package.load_message(msg_name)?;
 
-        let generated =
-            generate_service_package(&package.name, srv_name, &parsed_srv, &known_packages)
-                .wrap_err_with(|| format!("Failed to generate service: {}", srv_name))?;
+        let info = resolver
+            .message_info(&package.name, msg_name, &parsed_msg)
+            .wrap_err_with(|| format!("Failed to compute ROS 1 metadata: {}", msg_name))?;
+        notes.extend(info.mapping_notes.iter().cloned());
+        messages.push((to_snake_case(msg_name), info));
+    }
+
+    let src_dir = package_output.join("src");
+    output.write(
+        &src_dir.join("ros1.rs"),
+        generate_ros1_module(&package.name, &messages),
+    )?;
 
-        write_generated_service(&generated, &package_output, srv_name)?;
-        service_count += 1;
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path).unwrap_or_default();
+    if !lib_rs.contains("pub mod ros1;") {
+        lib_rs.push_str(
+            "\n// ROS 1 compatibility metadata (md5sums, full definitions)\npub mod ros1;\n",
+        );
+        output.write(&lib_rs_path, lib_rs)?;
     }
 
-    // Generate actions
-    for action_name in &package.interfaces.actions {
-        let action_path = package.get_action_path(action_name);
-        let content = std::fs::read_to_string(&action_path)
-            .wrap_err_with(|| format!("Failed to read action file: {}", action_path.display()))?;
+    output.write(
+        &package_output.join("ros1_type_mapping.txt"),
+        type_mapping_report(&notes),
+    )?;
 
-        let parsed_action = rosidl_parser::parse_action(&content)
-            .wrap_err_with(|| format!("Failed to parse action: {}", action_name))?;
+    Ok(notes)
+}
 
-        // Extract dependencies from goal, result, and feedback messages
-        let goal_deps = extract_dependencies(&parsed_action.spec.goal);
-        let result_deps = extract_dependencies(&parsed_action.spec.result);
-        let feedback_deps = extract_dependencies(&parsed_action.spec.feedback);
-        all_dependencies.extend(goal_deps);
-        all_dependencies.extend(result_deps);
-        all_dependencies.extend(feedback_deps);
+/// Generate the type descriptions and hashes of an already generated package
+///
+/// Writes `src/type_description.rs` with `TYPE_NAME`, `TYPE_HASH` and the type
+/// descriptions of each message. Messages of other packages are resolved through
+/// `index`; messages referring to a type that cannot be resolved are left out and
+/// returned with the error.
+pub fn generate_type_descriptions(
+    package: &Package,
+    index: Option<&AmentIndex>,
+    output: &Output,
+    rclrs_compat: bool,
+) -> Result<Vec<(String, String)>> {
+    let package_output = output.dir().join(&package.name);
+
+    let mut resolver = TypeDescriptionResolver::new(|dep_package: &str, name: &str| {
+        if dep_package == package.name {
+            package.load_message(name).ok()
+        } else {
+            index?.find_package(dep_package)?.load_message(name).ok()
+        }
+    });
 
-        // Check if goal, result, or feedback needs big_array support
-        if needs_big_array(&parsed_action.spec.goal)
-            || needs_big_array(&parsed_action.spec.result)
-            || needs_big_array(&parsed_action.spec.feedback)
-        {
-            package_needs_big_array = true;
+    let mut messages = Vec::new();
+    let mut skipped = Vec::new();
+    for msg_name in &package.interfaces.messages {
+        let parsed_msg = package.load_message(msg_name)?;
+        match resolver.message_info(&package.name, msg_name, &parsed_msg) {
+            Ok(info) => messages.push((to_snake_case(msg_name), info)),
+            Err(err) => skipped.push((msg_name.clone(), err.to_string())),
         }
+    }
 
-        let generated =
-            generate_action_package(&package.name, action_name, &parsed_action, &known_packages)
-                .wrap_err_with(|| format!("Failed to generate action: {}", action_name))?;
+    let src_dir = package_output.join("src");
+    output.write(
+        &src_dir.join("type_description.rs"),
+        generate_type_description_module(&package.name, &messages, rclrs_compat),
+    )?;
 
-        write_generated_action(&generated, &package_output, action_name)?;
-        action_count += 1;
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path).unwrap_or_default();
+    if !lib_rs.contains("pub mod type_description;") {
+        lib_rs.push_str(
+            "\n// ROS 2 type descriptions and type hashes (REP-2011)\npub mod type_description;\n",
+        );
+        output.write(&lib_rs_path, lib_rs)?;
     }
 
-    // Remove self-dependency (package shouldn't depend on itself)
-    all_dependencies.remove(&package.name);
+    Ok(skipped)
+}
 
-    // Generate lib.rs that re-exports all generated code
-    generate_lib_rs(&package_output, package, &all_dependencies)?;
+/// Generate the Protobuf mapping for an already generated package
+///
+/// Writes `proto/<package>.proto` and `src/proto.rs` (prost types plus `From`
+/// conversions, compiled behind the crate's `protobuf` feature) and adds the
+/// optional prost/prost-build dependencies. Messages of other packages are
+/// imported from the sibling `../<dep>/proto` directories, so dependencies must
+/// be generated with `--protobuf` as well; the feature is forwarded to them.
+pub fn generate_protobuf_mapping(package: &Package, output: &Output) -> Result<()> {
+    let package_output = output.dir().join(&package.name);
+
+    let mut messages = Vec::new();
+    for msg_name in &package.interfaces.messages {
+        let parsed_msg = package.load_message(msg_name)?;
+        messages.push((msg_name.clone(), parsed_msg));
+    }
+    let deps = proto_dependencies(&package.name, &messages);
 
-    // Generate Cargo.toml for the package
-    generate_cargo_toml(
-        &package_output,
-        &package.name,
-        &all_dependencies,
-        package_needs_big_array,
+    output.write(
+        &package_output
+            .join("proto")
+            .join(format!("{}.proto", package.name)),
+        generate_proto_file(&package.name, &messages),
     )?;
 
-    // Generate build.rs for FFI linking
-    generate_build_rs(&package_output, &package.name)?;
+    let src_dir = package_output.join("src");
+    output.write(
+        &src_dir.join("proto.rs"),
+        generate_proto_conversions(&package.name, &messages),
+    )?;
 
-    Ok(GeneratedRustPackage {
-        name: package.name.clone(),
-        output_dir: package_output,
-        message_count,
-        service_count,
-        action_count,
-    })
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path).unwrap_or_default();
+    if !lib_rs.contains("pub mod proto;") {
+        lib_rs.push_str(
+            "\n// Protobuf mapping (prost types and From conversions)\n\
+             #[cfg(feature = \"protobuf\")]\npub mod proto;\n",
+        );
+        output.write(&lib_rs_path, lib_rs)?;
+    }
+
+    let cargo_toml_path = package_output.join("Cargo.toml");
+    let mut cargo_toml = output.read(&cargo_toml_path)?;
+    if !cargo_toml.contains("prost-build") {
+        let mut features = vec![
+            "\"dep:prost\"".to_string(),
+            "\"dep:prost-build\"".to_string(),
+        ];
+        for dep in &deps {
+            features.push(format!("\"{}/protobuf\"", dep.replace('-', "_")));
+        }
+        cargo_toml = cargo_toml
+            .replacen(
+                "[dependencies]\n",
+                "[dependencies]\nprost = { version = \"0.13\", optional = true }\n",
+                1,
+            )
+            .replacen(
+                "default = []\n",
+                &format!("default = []\nprotobuf = [{}]\n", features.join(", ")),
+                1,
+            );
+        cargo_toml.push_str("prost-build = { version = \"0.13\", optional = true }\n");
+        output.write(&cargo_toml_path, cargo_toml)?;
+    }
+
+    let build_rs_path = package_output.join("build.rs");
+    let build_rs = output.read(&build_rs_path)?;
+    if !build_rs.contains("prost_build") {
+        let mut compile = String::from(
+            "\n    // Compile the Protobuf mapping (requires protoc)\n    \
+             #[cfg(feature = \"protobuf\")]\n    \
+             {\n        \
+             let mut config = prost_build::Config::new();\n",
+        );
+        let mut includes = vec!["\"proto\"".to_string()];
+        for dep in &deps {
+            compile.push_str(&format!(
+                "        config.extern_path(\".{dep}\", \"::{dep}::proto\");\n"
+            ));
+            includes.push(format!("\"../{}/proto\"", dep));
+        }
+        compile.push_str(&format!(
+            "        config\n            .compile_protos(&[\"proto/{}.proto\"], &[{}])\n            \
+             .expect(\"Failed to compile Protobuf mapping\");\n    }}\n",
+            package.name,
+            includes.join(", ")
+        ));
+
+        let end = build_rs
+            .rfind('}')
+            .ok_or_else(|| eyre::eyre!("Unexpected build.rs layout"))?;
+        let mut patched = build_rs[..end].to_string();
+        patched.push_str(&compile);
+        patched.push_str(&build_rs[end..]);
+        output.write(&build_rs_path, patched)?;
+    }
+
+    Ok(())
 }
 
-/// Write generated message package to files
-fn write_generated_package(
-    generated: &GeneratedPackage,
-    output_dir: &Path,
-    name: &str,
-) -> Result<()> {
-    // Create idiomatic message directory: src/msg/
-    let msg_dir = output_dir.join("src").join("msg");
-    std::fs::create_dir_all(&msg_dir)?;
+/// Add PyO3 bindings to an already generated package
+///
+/// Writes `src/python.rs` (compiled behind the crate's `python` feature) and adds
+/// the optional `pyo3` dependency. The feature is forwarded to the packages
+/// whose messages are nested in this one.
+pub fn generate_python_bindings(package: &Package, output: &Output) -> Result<()> {
+    let package_output = output.dir().join(&package.name);
+
+    let mut messages = Vec::new();
+    for msg_name in &package.interfaces.messages {
+        let parsed_msg = package.load_message(msg_name)?;
+        messages.push((msg_name.clone(), parsed_msg));
+    }
 
-    // Create FFI message directory: src/ffi/msg/
-    let ffi_msg_dir = output_dir.join("src").join(FFI_MODULE).join("msg");
-    std::fs::create_dir_all(&ffi_msg_dir)?;
+    let src_dir = package_output.join("src");
+    output.write(
+        &src_dir.join("python.rs"),
+        generate_python_module(&package.name, &messages),
+    )?;
 
-    // Write FFI message to src/ffi/msg/
-    let rmw_file = ffi_msg_dir.join(format!("{}_rmw.rs", to_snake_case(name)));
-    std::fs::write(&rmw_file, &generated.message_rmw)?;
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path).unwrap_or_default();
+    if !lib_rs.contains("pub mod python;") {
+        lib_rs.push_str(
+            "\n// Python bindings (PyO3 classes and rclpy-compatible dicts)\n\
+             #[cfg(feature = \"python\")]\npub mod python;\n",
+        );
+        output.write(&lib_rs_path, lib_rs)?;
+    }
 
-    // Write idiomatic message to src/msg/
-    let idiomatic_file = msg_dir.join(format!("{}_idiomatic.rs", to_snake_case(name)));
-    std::fs::write(&idiomatic_file, &generated.message_idiomatic)?;
+    let cargo_toml_path = package_output.join("Cargo.toml");
+    let mut cargo_toml = output.read(&cargo_toml_path)?;
+    if !cargo_toml.contains("pyo3") {
+        let mut features = vec!["\"dep:pyo3\"".to_string()];
+        for dep in python_dependencies(&package.name, &messages) {
+            features.push(format!("\"{}/python\"", dep.replace('-', "_")));
+        }
+        cargo_toml = cargo_toml
+            .replacen(
+                "[dependencies]\n",
+                "[dependencies]\npyo3 = { version = \"0.22\", optional = true }\n",
+                1,
+            )
+            .replacen(
+                "default = []\n",
+                &format!("default = []\npython = [{}]\n", features.join(", ")),
+                1,
+            );
+        output.write(&cargo_toml_path, cargo_toml)?;
+    }
 
     Ok(())
 }
 
-/// Write generated service package to files
-fn write_generated_service(
-    generated: &rosidl_codegen::GeneratedServicePackage,
-    output_dir: &Path,
-    name: &str,
-) -> Result<()> {
-    // Create idiomatic service directory: src/srv/
-    let srv_dir = output_dir.join("src").join("srv");
-    std::fs::create_dir_all(&srv_dir)?;
+/// Add a cxx bridge to an already generated package
+///
+/// Writes `src/cxx_bridge.rs` (compiled behind the crate's `cxx` feature), adds the
+/// optional `cxx`/`cxx-build` dependencies and a `links` key so dependent bridges
+/// can include the header, and has build.rs generate the header and C++ glue.
+pub fn generate_cxx_bindings(package: &Package, output: &Output) -> Result<()> {
+    let package_output = output.dir().join(&package.name);
+
+    let mut messages = Vec::new();
+    for msg_name in &package.interfaces.messages {
+        let parsed_msg = package.load_message(msg_name)?;
+        messages.push((msg_name.clone(), parsed_msg));
+    }
+
+    let src_dir = package_output.join("src");
+    output.write(
+        &src_dir.join("cxx_bridge.rs"),
+        generate_cxx_bridge(&package.name, &messages),
+    )?;
 
-    // Create FFI service directory: src/ffi/srv/
-    let ffi_srv_dir = output_dir.join("src").join(FFI_MODULE).join("srv");
-    std::fs::create_dir_all(&ffi_srv_dir)?;
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path).unwrap_or_default();
+    if !lib_rs.contains("pub mod cxx_bridge;") {
+        lib_rs.push_str(
+            "\n// C++ interop (cxx shared structs and From conversions)\n\
+             #[cfg(feature = \"cxx\")]\npub mod cxx_bridge;\n",
+        );
+        output.write(&lib_rs_path, lib_rs)?;
+    }
+
+    let cargo_toml_path = package_output.join("Cargo.toml");
+    let mut cargo_toml = output.read(&cargo_toml_path)?;
+    if !cargo_toml.contains("cxx-build") {
+        let mut features = vec!["\"dep:cxx\"".to_string(), "\"dep:cxx-build\"".to_string()];
+        for dep in cxx_dependencies(&package.name, &messages) {
+            features.push(format!("\"{}/cxx\"", dep.replace('-', "_")));
+        }
+        cargo_toml = cargo_toml
+            .replacen(
+                "edition = \"2021\"\n",
+                &format!(
+                    "edition = \"2021\"\nlinks = \"{}_cxx_bridge\"\n",
+                    package.name
+                ),
+                1,
+            )
+            .replacen(
+                "[dependencies]\n",
+                "[dependencies]\ncxx = { version = \"1.0\", optional = true }\n",
+                1,
+            )
+            .replacen(
+                "default = []\n",
+                &format!("default = []\ncxx = [{}]\n", features.join(", ")),
+                1,
+            );
+        cargo_toml.push_str(
+            "# Compiles the C++ side of the cxx bridge\ncxx-build = { version = \"1.0\", optional = true }\n",
+        );
+        output.write(&cargo_toml_path, cargo_toml)?;
+    }
 
-    // Write FFI service to src/ffi/srv/
-    let rmw_file = ffi_srv_dir.join(format!("{}_rmw.rs", to_snake_case(name)));
-    std::fs::write(&rmw_file, &generated.service_rmw)?;
+    let build_rs_path = package_output.join("build.rs");
+    let build_rs = output.read(&build_rs_path)?;
+    if !build_rs.contains("cxx_build") {
+        let compile = format!(
+            "\n    // Generate the C++ header and glue code for the cxx bridge\n    \
+             #[cfg(feature = \"cxx\")]\n    \
+             {{\n        \
+             println!(\"cargo:rerun-if-changed=src/cxx_bridge.rs\");\n        \
+             cxx_build::bridge(\"src/cxx_bridge.rs\")\n            \
+             .std(\"c++17\")\n            \
+             .compile(\"{}_cxx_bridge\");\n    \
+             }}\n",
+            package.name
+        );
 
-    // Write idiomatic service to src/srv/
-    let idiomatic_file = srv_dir.join(format!("{}_idiomatic.rs", to_snake_case(name)));
-    std::fs::write(&idiomatic_file, &generated.service_idiomatic)?;
+        let end = build_rs
+            .rfind('}')
+            .ok_or_else(|| eyre::eyre!("Unexpected build.rs layout"))?;
+        let mut patched = build_rs[..end].to_string();
+        patched.push_str(&compile);
+        patched.push_str(&build_rs[end..]);
+        output.write(&build_rs_path, patched)?;
+    }
 
     Ok(())
 }
 
-/// Write generated action package to files
-fn write_generated_action(
-    generated: &rosidl_codegen::GeneratedActionPackage,
+/// Write the files generated for an interface under `src/`
+fn write_artifacts(
+    artifacts: &GeneratedArtifacts,
     output_dir: &Path,
-    name: &str,
+    output: &Output,
 ) -> Result<()> {
-    // Create idiomatic action directory: src/action/
-    let action_dir = output_dir.join("src").join("action");
-    std::fs::create_dir_all(&action_dir)?;
-
-    // Create FFI action directory: src/ffi/action/
-    let ffi_action_dir = output_dir.join("src").join(FFI_MODULE).join("action");
-    std::fs::create_dir_all(&ffi_action_dir)?;
-
-    // Write FFI action to src/ffi/action/
-    let rmw_file = ffi_action_dir.join(format!("{}_rmw.rs", to_snake_case(name)));
-    std::fs::write(&rmw_file, &generated.action_rmw)?;
-
-    // Write idiomatic action to src/action/
-    let idiomatic_file = action_dir.join(format!("{}_idiomatic.rs", to_snake_case(name)));
-    std::fs::write(&idiomatic_file, &generated.action_idiomatic)?;
-
+    for (relative, content) in &artifacts.files {
+        output.write(&output_dir.join("src").join(relative), content.as_str())?;
+    }
     Ok(())
 }
 
 /// Generate lib.rs that re-exports all generated modules
+///
+/// With `rclrs_compat` or the ros2-rust layout, each interface type is also
+/// re-exported at the `msg`/`srv`/`action` level (`pkg::msg::Point`), as rclrs
+/// code expects. Types-only and embedded bindings get neither the `ffi` module
+/// nor services and actions; embedded bindings are `#![no_std]`.
 fn generate_lib_rs(
     output_dir: &Path,
+    output: &Output,
     package: &Package,
-    dependencies: &HashSet<String>,
+    parsed: &ParsedPackage,
+    options: &GeneratorOptions,
+    layout: Layout,
 ) -> Result<()> {
-    let src_dir = output_dir.join("src");
-    std::fs::create_dir_all(&src_dir)?;
+    let (services, actions): (&[String], &[String]) = if options.messages_only() {
+        (&[], &[])
+    } else {
+        (&package.interfaces.services, &package.interfaces.actions)
+    };
 
     let mut lib_rs = String::new();
+    if options.embedded.is_some() {
+        lib_rs.push_str("#![no_std]\n\n");
+    }
+    // Inner docs go before the first item
+    lib_rs.push_str(&crate_docs(package, parsed, options));
+    if options.embedded.is_some() {
+        // Sequences of messages nesting their own message live on the heap
+        if !options.cyclic_fields.is_empty() {
+            lib_rs.push_str("extern crate alloc;\n\n");
+        }
+    }
     lib_rs.push_str("// Auto-generated Rust bindings for ROS 2 interface package\n");
     lib_rs.push_str(&format!("// Package: {}\n\n", package.name));
 
@@ -412,12 +916,17 @@ fn generate_lib_rs(
     lib_rs.push_str("// Import shared runtime library for ROS 2 types and traits\n");
     lib_rs.push_str("use rosidl_runtime_rs;\n\n");
 
+    lib_rs.push_str("// Interface definitions and tools these bindings were generated from\n");
+    lib_rs.push_str("pub mod provenance;\n\n");
+
+    // Serde for arrays of more than 32 elements, beyond serde's own impls
+    lib_rs.push_str("#[cfg(feature = \"serde\")]\nmod serde_array;\n\n");
+
     // Add top-level FFI module containing all FFI types
-    let has_any_interfaces = !package.interfaces.messages.is_empty()
-        || !package.interfaces.services.is_empty()
-        || !package.interfaces.actions.is_empty();
+    let has_any_interfaces =
+        !package.interfaces.messages.is_empty() || !services.is_empty() || !actions.is_empty();
 
-    if has_any_interfaces {
+    if has_any_interfaces && !options.messages_only() {
         lib_rs.push_str(&format!("pub mod {} {{\n", FFI_MODULE));
         lib_rs.push_str("    use super::rosidl_runtime_rs;\n\n");
 
@@ -427,6 +936,12 @@ fn generate_lib_rs(
             lib_rs.push_str("        use super::*;\n");
             for msg_name in &package.interfaces.messages {
                 let module_name = to_snake_case(msg_name);
+                lib_rs.push_str(&feature_gate(
+                    "msg",
+                    msg_name,
+                    options.interface_features,
+                    "        ",
+                ));
                 // Files are in src/ffi/msg/, inline module context is also ffi/msg/
                 lib_rs.push_str(&format!("        #[path = \"{}_rmw.rs\"]\n", module_name));
                 lib_rs.push_str(&format!("        pub mod {};\n", module_name));
@@ -435,11 +950,17 @@ fn generate_lib_rs(
         }
 
         // FFI services
-        if !package.interfaces.services.is_empty() {
+        if !services.is_empty() {
             lib_rs.push_str("    pub mod srv {\n");
             lib_rs.push_str("        use super::*;\n");
-            for srv_name in &package.interfaces.services {
+            for srv_name in services {
                 let module_name = to_snake_case(srv_name);
+                lib_rs.push_str(&feature_gate(
+                    "srv",
+                    srv_name,
+                    options.interface_features,
+                    "        ",
+                ));
                 // Files are in src/ffi/srv/, inline module context is also ffi/srv/
                 lib_rs.push_str(&format!("        #[path = \"{}_rmw.rs\"]\n", module_name));
                 lib_rs.push_str(&format!("        pub mod {};\n", module_name));
@@ -448,11 +969,17 @@ fn generate_lib_rs(
         }
 
         // FFI actions
-        if !package.interfaces.actions.is_empty() {
+        if !actions.is_empty() {
             lib_rs.push_str("    pub mod action {\n");
             lib_rs.push_str("        use super::*;\n");
-            for action_name in &package.interfaces.actions {
+            for action_name in actions {
                 let module_name = to_snake_case(action_name);
+                lib_rs.push_str(&feature_gate(
+                    "action",
+                    action_name,
+                    options.interface_features,
+                    "        ",
+                ));
                 // Files are in src/ffi/action/, inline module context is also ffi/action/
                 lib_rs.push_str(&format!("        #[path = \"{}_rmw.rs\"]\n", module_name));
                 lib_rs.push_str(&format!("        pub mod {};\n", module_name));
@@ -463,91 +990,620 @@ fn generate_lib_rs(
         lib_rs.push_str("}\n\n");
     }
 
-    // Add idiomatic message modules
-    if !package.interfaces.messages.is_empty() {
-        lib_rs.push_str("pub mod msg {\n");
-        lib_rs.push_str("    use super::rosidl_runtime_rs;\n\n");
-        for msg_name in &package.interfaces.messages {
-            let module_name = to_snake_case(msg_name);
-            // Files are in src/msg/, inline module context is also msg/
-            lib_rs.push_str(&format!("    #[path = \"{}_idiomatic.rs\"]\n", module_name));
-            lib_rs.push_str(&format!("    pub mod {};\n", module_name));
+    // Add idiomatic interface modules
+    let type_name = |kind: &str, name: &str| options.naming.type_name(&package.name, kind, name);
+    let kinds: [(&str, &[String]); 3] = [
+        ("msg", &package.interfaces.messages),
+        ("srv", services),
+        ("action", actions),
+    ];
+    for (kind, names) in kinds {
+        if !names.is_empty() {
+            lib_rs.push_str(&interface_module(
+                kind,
+                names,
+                layout,
+                options.interface_features,
+                &type_name,
+            ));
         }
-        lib_rs.push_str("}\n\n");
     }
 
-    // Add idiomatic service modules
-    if !package.interfaces.services.is_empty() {
-        lib_rs.push_str("pub mod srv {\n");
-        lib_rs.push_str("    use super::rosidl_runtime_rs;\n\n");
-        for srv_name in &package.interfaces.services {
-            let module_name = to_snake_case(srv_name);
-            // Files are in src/srv/, inline module context is also srv/
-            lib_rs.push_str(&format!("    #[path = \"{}_idiomatic.rs\"]\n", module_name));
-            lib_rs.push_str(&format!("    pub mod {};\n", module_name));
+    if has_any_interfaces {
+        lib_rs.push_str(&prelude(
+            &package.interfaces.messages,
+            services,
+            actions,
+            options.interface_features,
+            &type_name,
+        ));
+    }
+
+    output.write(&output_dir.join("src").join("lib.rs"), lib_rs)?;
+    Ok(())
+}
+
+/// Idiomatic `pub mod msg`, `srv` or `action` of the generated crate
+///
+/// Each interface lives in a module of its own, hidden from the docs, and its
+/// types are re-exported as `<package>::<kind>::<Type>`, like rclrs does. A type
+/// an earlier interface already exported is skipped, as two imports of the same
+/// name would not compile. With `features`, each interface is behind its feature.
+/// `type_name` gives the name an interface of a kind is exported under.
+fn interface_module(
+    kind: &str,
+    names: &[String],
+    layout: Layout,
+    features: bool,
+    type_name: &dyn Fn(&str, &str) -> String,
+) -> String {
+    let mut module = format!("pub mod {} {{\n    use super::rosidl_runtime_rs;\n\n", kind);
+    let mut seen = BTreeSet::new();
+    for name in names {
+        let module_name = to_snake_case(name);
+        let gate = feature_gate(kind, name, features, "    ");
+        // Files are in src/<kind>/, inline module context is also <kind>/
+        module.push_str(&gate);
+        module.push_str("    #[doc(hidden)]\n");
+        module.push_str(&format!("    #[path = \"{}_idiomatic.rs\"]\n", module_name));
+        module.push_str(&format!("    pub mod {};\n", module_name));
+        let types = exported_types(kind, name, &type_name(kind, name));
+        if let Some(items) = use_items(types, &mut seen) {
+            module.push_str(&gate);
+            module.push_str(&format!("    pub use {}::{};\n", module_name, items));
         }
-        lib_rs.push_str("}\n\n");
     }
+    if layout == Layout::Ros2Rust {
+        module.push_str(&ros2_rust_exports(kind, names, features));
+    }
+    module.push_str("}\n\n");
+    module
+}
 
-    // Add idiomatic action modules
-    if !package.interfaces.actions.is_empty() {
-        lib_rs.push_str("pub mod action {\n");
-        lib_rs.push_str("    use super::rosidl_runtime_rs;\n\n");
-        for action_name in &package.interfaces.actions {
-            let module_name = to_snake_case(action_name);
-            // Files are in src/action/, inline module context is also action/
-            lib_rs.push_str(&format!("    #[path = \"{}_idiomatic.rs\"]\n", module_name));
-            lib_rs.push_str(&format!("    pub mod {};\n", module_name));
+/// `pub mod prelude` re-exporting the idiomatic types of all interfaces
+///
+/// A name already exported by an earlier interface is skipped, as two imports of
+/// the same name would not compile.
+fn prelude(
+    messages: &[String],
+    services: &[String],
+    actions: &[String],
+    features: bool,
+    type_name: &dyn Fn(&str, &str) -> String,
+) -> String {
+    let mut seen = BTreeSet::new();
+    let mut prelude = String::from(
+        "// Idiomatic types of all interfaces, for `use <package>::prelude::*;`\npub mod prelude {\n",
+    );
+    let groups: [(&str, &[String]); 3] =
+        [("msg", messages), ("srv", services), ("action", actions)];
+    for (kind, names) in groups {
+        for name in names {
+            // The kind modules export them under their new names already
+            let types = exported_types(kind, name, &type_name(kind, name))
+                .into_iter()
+                .map(|(_, exported)| (exported.clone(), exported));
+            if let Some(items) = use_items(types.collect(), &mut seen) {
+                prelude.push_str(&feature_gate(kind, name, features, "    "));
+                prelude.push_str(&format!("    pub use crate::{}::{};\n", kind, items));
+            }
+        }
+    }
+    prelude.push_str("}\n");
+    prelude
+}
+
+/// Idiomatic types of an interface: the message, or the service or action with
+/// its request and response or goal, result and feedback structs, each with the
+/// name it is exported under when the interface is exported as `exported`
+fn exported_types(kind: &str, name: &str, exported: &str) -> Vec<(String, String)> {
+    let parts: &[&str] = match kind {
+        "srv" => &["", "Request", "Response"],
+        "action" => &["", "Goal", "Result", "Feedback"],
+        _ => &[""],
+    };
+    parts
+        .iter()
+        .map(|part| (format!("{}{}", name, part), format!("{}{}", exported, part)))
+        .collect()
+}
+
+/// Items of a `use` of the types whose exported name is not in `seen` yet, such
+/// as `Point`, `String as StringMsg` or `{AddTwoInts, AddTwoIntsRequest}`;
+/// `None` if all were
+fn use_items(types: Vec<(String, String)>, seen: &mut BTreeSet<String>) -> Option<String> {
+    let mut types: Vec<String> = types
+        .into_iter()
+        .filter(|(_, exported)| seen.insert(exported.clone()))
+        .map(|(name, exported)| {
+            if name == exported {
+                name
+            } else {
+                format!("{} as {}", name, exported)
+            }
+        })
+        .collect();
+    match types.len() {
+        0 => None,
+        1 => types.pop(),
+        _ => Some(format!("{{{}}}", types.join(", "))),
+    }
+}
+
+/// Crate-level docs of a generated crate: the package description, then an index
+/// of its interfaces linking to their types, each with the first line of its
+/// leading comment
+///
+/// With interface features, an entry is only listed when its feature is on, so
+/// rustdoc finds every link.
+fn crate_docs(package: &Package, parsed: &ParsedPackage, options: &GeneratorOptions) -> String {
+    let mut docs = format!("//! ROS 2 interfaces of the `{}` package\n", package.name);
+    if let Some(description) = package.manifest().description {
+        docs.push_str(&format!("//!\n//! {}\n", description));
+    }
+
+    // The leading comment of a service or action is that of its first section
+    let sections = [
+        (
+            "Messages",
+            "msg",
+            parsed.messages.iter().collect::<Vec<_>>(),
+        ),
+        (
+            "Services",
+            "srv",
+            parsed
+                .services
+                .iter()
+                .map(|(name, service)| (name, &service.request))
+                .collect(),
+        ),
+        (
+            "Actions",
+            "action",
+            parsed
+                .actions
+                .iter()
+                .map(|(name, action)| (name, &action.spec.goal))
+                .collect(),
+        ),
+    ];
+    for (title, kind, interfaces) in sections {
+        if interfaces.is_empty() {
+            continue;
+        }
+        docs.push_str(&format!("//!\n//! # {}\n//!\n", title));
+        for (name, leading) in interfaces {
+            let exported = options.naming.type_name(&package.name, kind, name);
+            let mut entry = format!("- [`{}`]({}::{})", exported, kind, exported);
+            let summary = comment_docs(&leading.comments)
+                .into_iter()
+                .find(|line| !line.trim().is_empty());
+            if let Some(summary) = summary {
+                entry.push_str(&format!(": {}", summary));
+            }
+            if options.interface_features {
+                docs.push_str(&format!(
+                    "#![cfg_attr(feature = \"{}\", doc = {:?})]\n",
+                    interface_feature(kind, name),
+                    format!(" {}", entry)
+                ));
+            } else {
+                docs.push_str(&format!("//! {}\n", entry));
+            }
+        }
+    }
+    docs.push('\n');
+    docs
+}
+
+/// Cargo feature of an interface with `--interface-features`, such as `msg_point`
+fn interface_feature(kind: &str, name: &str) -> String {
+    format!("{}_{}", kind, to_snake_case(name))
+}
+
+/// `#[cfg(feature = ...)]` line, indented by `indent`, gating an interface when the
+/// crate has interface `features`; empty otherwise
+fn feature_gate(kind: &str, name: &str, features: bool, indent: &str) -> String {
+    if !features {
+        return String::new();
+    }
+    format!(
+        "{}#[cfg(feature = \"{}\")]\n",
+        indent,
+        interface_feature(kind, name)
+    )
+}
+
+/// Feature of each interface, with the features of the messages of the package it
+/// nests; nested messages of other packages come with their crate
+fn interface_features(
+    package_name: &str,
+    parsed: &ParsedPackage,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let nested = |structs: &[&Message]| -> BTreeSet<String> {
+        structs
+            .iter()
+            .flat_map(|message| extract_local_types(message, package_name))
+            .map(|name| interface_feature("msg", &name))
+            .collect()
+    };
+    let messages = parsed
+        .messages
+        .iter()
+        .map(|(name, message)| (interface_feature("msg", name), nested(&[message])));
+    let services = parsed.services.iter().map(|(name, service)| {
+        (
+            interface_feature("srv", name),
+            nested(&[&service.request, &service.response]),
+        )
+    });
+    let actions = parsed.actions.iter().map(|(name, action)| {
+        let spec = &action.spec;
+        (
+            interface_feature("action", name),
+            nested(&[&spec.goal, &spec.result, &spec.feedback]),
+        )
+    });
+    messages
+        .chain(services)
+        .chain(actions)
+        .map(|(feature, mut requires)| {
+            // A message nesting itself needs no feature of its own
+            requires.remove(&feature);
+            (feature, requires)
+        })
+        .collect()
+}
+
+/// Declare the interface `features` in the Cargo.toml of a generated crate, all
+/// enabled by the default `full` feature
+fn add_interface_features(
+    output_dir: &Path,
+    output: &Output,
+    features: &BTreeMap<String, BTreeSet<String>>,
+) -> Result<()> {
+    let quoted = |names: &mut dyn Iterator<Item = &String>| {
+        names
+            .map(|name| format!("\"{}\"", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut declared = format!(
+        "default = [\"full\"]\n# Every interface; disable default features to pick them one by one\nfull = [{}]\n",
+        quoted(&mut features.keys())
+    );
+    for (feature, requires) in features {
+        declared.push_str(&format!(
+            "{} = [{}]\n",
+            feature,
+            quoted(&mut requires.iter())
+        ));
+    }
+
+    let cargo_toml_path = output_dir.join("Cargo.toml");
+    let cargo_toml = output.read(&cargo_toml_path)?;
+    output.write(
+        &cargo_toml_path,
+        cargo_toml.replacen("default = []\n", &declared, 1),
+    )?;
+    Ok(())
+}
+
+/// Generate `src/provenance.rs` describing what the bindings were built from
+///
+/// Deployed binaries can report these constants to identify the exact
+/// interface definitions they were compiled against.
+fn generate_provenance_rs(output_dir: &Path, output: &Output, package: &Package) -> Result<()> {
+    let checksum = package
+        .interface_checksum()
+        .wrap_err_with(|| format!("Failed to calculate checksum for {}", package.name))?;
+    let distro = std::env::var("ROS_DISTRO").ok().filter(|d| !d.is_empty());
+    let version = package.manifest().version;
+
+    let optional = |value: Option<String>| match value {
+        Some(value) => format!("Some({:?})", value),
+        None => "None".to_string(),
+    };
+
+    let provenance_rs = format!(
+        r#"//! Build provenance of these bindings
+
+/// ROS 2 interface package the bindings were generated from
+pub const PACKAGE_NAME: &str = {name:?};
+
+/// Version of the interface package (from its package.xml)
+pub const PACKAGE_VERSION: Option<&str> = {version};
+
+/// ROS 2 distribution the bindings were generated in (`$ROS_DISTRO`)
+pub const ROS_DISTRO: Option<&str> = {distro};
+
+/// SHA256 of the package's .msg/.srv/.action files
+pub const SOURCE_CHECKSUM: &str = {checksum:?};
+
+/// Version of the code generator that generated the bindings
+pub const GENERATOR_VERSION: &str = {generator:?};
+"#,
+        name = package.name,
+        version = optional(version),
+        distro = optional(distro),
+        checksum = checksum,
+        generator = rosidl_codegen::VERSION,
+    );
+
+    output.write(&output_dir.join("src").join("provenance.rs"), provenance_rs)?;
+    Ok(())
+}
+
+/// Package whose `ParameterValue` message gets conversions to the runtime enum
+const PARAMETER_PACKAGE: &str = "rcl_interfaces";
+
+/// Write `src/parameter.rs`, converting between `msg::ParameterValue` and
+/// `rosidl_runtime_rs::parameter::ParameterValue`
+fn generate_parameter_rs(
+    output_dir: &Path,
+    output: &Output,
+    interface_features: bool,
+) -> Result<()> {
+    let parameter_rs = r#"//! Conversions between `msg::ParameterValue` and the runtime `ParameterValue` enum
+
+pub use crate::rosidl_runtime_rs::parameter::{ParameterError, ParameterValue};
+use crate::rosidl_runtime_rs::parameter::*;
+
+type ParameterValueMsg = crate::msg::parameter_value::ParameterValue;
+
+impl From<ParameterValue> for ParameterValueMsg {
+    fn from(value: ParameterValue) -> Self {
+        let mut msg = ParameterValueMsg {
+            type_: value.type_id(),
+            bool_value: false,
+            integer_value: 0,
+            double_value: 0.0,
+            string_value: String::new(),
+            byte_array_value: Vec::new(),
+            bool_array_value: Vec::new(),
+            integer_array_value: Vec::new(),
+            double_array_value: Vec::new(),
+            string_array_value: Vec::new(),
+        };
+        match value {
+            ParameterValue::NotSet => {}
+            ParameterValue::Bool(v) => msg.bool_value = v,
+            ParameterValue::Integer(v) => msg.integer_value = v,
+            ParameterValue::Double(v) => msg.double_value = v,
+            ParameterValue::String(v) => msg.string_value = v,
+            ParameterValue::ByteArray(v) => msg.byte_array_value = v,
+            ParameterValue::BoolArray(v) => msg.bool_array_value = v,
+            ParameterValue::IntegerArray(v) => msg.integer_array_value = v,
+            ParameterValue::DoubleArray(v) => msg.double_array_value = v,
+            ParameterValue::StringArray(v) => msg.string_array_value = v,
         }
-        lib_rs.push_str("}\n");
+        msg
+    }
+}
+
+impl TryFrom<ParameterValueMsg> for ParameterValue {
+    type Error = ParameterError;
+
+    fn try_from(msg: ParameterValueMsg) -> Result<Self, Self::Error> {
+        Ok(match msg.type_ {
+            PARAMETER_NOT_SET => ParameterValue::NotSet,
+            PARAMETER_BOOL => ParameterValue::Bool(msg.bool_value),
+            PARAMETER_INTEGER => ParameterValue::Integer(msg.integer_value),
+            PARAMETER_DOUBLE => ParameterValue::Double(msg.double_value),
+            PARAMETER_STRING => ParameterValue::String(msg.string_value),
+            PARAMETER_BYTE_ARRAY => ParameterValue::ByteArray(msg.byte_array_value),
+            PARAMETER_BOOL_ARRAY => ParameterValue::BoolArray(msg.bool_array_value),
+            PARAMETER_INTEGER_ARRAY => ParameterValue::IntegerArray(msg.integer_array_value),
+            PARAMETER_DOUBLE_ARRAY => ParameterValue::DoubleArray(msg.double_array_value),
+            PARAMETER_STRING_ARRAY => ParameterValue::StringArray(msg.string_array_value),
+            other => return Err(ParameterError::UnknownType(other)),
+        })
     }
+}
+"#;
 
-    std::fs::write(src_dir.join("lib.rs"), lib_rs)?;
+    let src_dir = output_dir.join("src");
+    output.write(&src_dir.join("parameter.rs"), parameter_rs)?;
+
+    let lib_rs_path = src_dir.join("lib.rs");
+    let mut lib_rs = output.read(&lib_rs_path)?;
+    if !lib_rs.contains("pub mod parameter;") {
+        lib_rs.push_str("\n// Conversions to the runtime ParameterValue enum\n");
+        lib_rs.push_str(&feature_gate(
+            "msg",
+            "ParameterValue",
+            interface_features,
+            "",
+        ));
+        lib_rs.push_str("pub mod parameter;\n");
+        output.write(&lib_rs_path, lib_rs)?;
+    }
     Ok(())
 }
 
+/// Re-exports replicating the rosidl_generator_rs layout inside one of the
+/// `msg`/`srv`/`action` modules
+///
+/// Service and action parts get their upstream names (`AddTwoInts_Request`,
+/// `Fibonacci_Goal`), and the FFI types are mirrored in an `rmw` submodule.
+fn ros2_rust_exports(kind: &str, names: &[String], features: bool) -> String {
+    let parts: &[&str] = match kind {
+        "srv" => &["Request", "Response"],
+        "action" => &["Goal", "Result", "Feedback"],
+        _ => &[],
+    };
+
+    let mut idiomatic = String::new();
+    let mut rmw = String::new();
+    for name in names {
+        let module_name = to_snake_case(name);
+        let renamed: Vec<String> = parts
+            .iter()
+            .map(|part| format!("{0}{1} as {0}_{1}", name, part))
+            .collect();
+
+        if !renamed.is_empty() {
+            idiomatic.push_str(&feature_gate(kind, name, features, "    "));
+            idiomatic.push_str(&format!(
+                "    pub use {}::{{{}}};\n",
+                module_name,
+                renamed.join(", ")
+            ));
+        }
+
+        // Messages and services have a type-level struct in the FFI layer too
+        let mut rmw_items = Vec::new();
+        if kind != "action" {
+            rmw_items.push(name.clone());
+        }
+        rmw_items.extend(renamed);
+        let items = if rmw_items.len() == 1 {
+            rmw_items.remove(0)
+        } else {
+            format!("{{{}}}", rmw_items.join(", "))
+        };
+        rmw.push_str(&feature_gate(kind, name, features, "        "));
+        rmw.push_str(&format!(
+            "        pub use crate::{}::{}::{}::{};\n",
+            FFI_MODULE, kind, module_name, items
+        ));
+    }
+
+    format!(
+        "{}\n    // C-compatible types under their rosidl_generator_rs paths\n    pub mod rmw {{\n{}    }}\n",
+        idiomatic, rmw
+    )
+}
+
 /// Generate Cargo.toml for the generated package
+///
+/// `dependencies` maps the packages it depends on to their crate version, if
+/// they are installed.
 fn generate_cargo_toml(
-    output_dir: &Path,
+    output: &Output,
     package_name: &str,
-    dependencies: &HashSet<String>,
-    needs_big_array: bool,
+    manifest: &PackageManifest,
+    dependencies: &BTreeMap<String, Option<String>>,
+    options: &GeneratorOptions,
+    layout: Layout,
+    dependency_style: DependencyStyle,
 ) -> Result<()> {
+    let runtime_dependency = if options.rclrs_compat {
+        // Same crate as rclrs, so generated types satisfy its trait bounds
+        format!(
+            "rosidl_runtime_rs = \"{}\"",
+            options
+                .rclrs_runtime_version
+                .as_deref()
+                .unwrap_or(RCLRS_RUNTIME_VERSION)
+        )
+    } else if options.embedded.is_some() {
+        // Only the no_std bounded containers
+        "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\", default-features = false }"
+            .to_string()
+    } else if options.types_only {
+        // No rosidl_runtime_c to link against (e.g. on wasm32)
+        "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\", features = [\"mock-ffi\"] }"
+            .to_string()
+    } else {
+        "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\" }".to_string()
+    };
+    // Embedded bindings are no_std, so serde must not pull in std (but needs
+    // alloc for the heap-allocated sequences of cyclic messages)
+    let serde_options = match &options.embedded {
+        Some(_) if !options.cyclic_fields.is_empty() => {
+            ", default-features = false, features = [\"derive\", \"alloc\"]"
+        }
+        Some(_) => ", default-features = false, features = [\"derive\"]",
+        None => ", features = [\"derive\"]",
+    };
     let mut cargo_toml = format!(
         r#"[package]
 name = "{}"
-version = "0.1.0"
+version = "{}"
 edition = "2021"
-
-# Standalone package (not part of parent workspace)
-[workspace]
-
-[dependencies]
+{}
+{}[dependencies]
 # Shared runtime library for ROS 2 types and traits
-rosidl_runtime_rs = {{ path = "../rosidl_runtime_rs" }}
-serde = {{ version = "1.0", features = ["derive"], optional = true }}
+{}
+serde = {{ version = "1.0"{}, optional = true }}
 "#,
-        package_name
+        options.naming.crate_name(package_name),
+        crate_version(manifest),
+        package_metadata(package_name, manifest),
+        crate::workspace::STANDALONE_TABLE,
+        runtime_dependency,
+        serde_options
     );
-
-    // Add serde-big-array if needed for arrays > 32 elements
-    if needs_big_array {
-        cargo_toml.push_str("serde-big-array = { version = \"0.5\", optional = true }\n");
+    // Zero-copy archives of the idiomatic structs; embedded bindings store their
+    // strings and sequences in containers rkyv cannot archive
+    if options.embedded.is_none() {
+        cargo_toml.push_str("rkyv = { version = \"0.8\", optional = true }\n");
     }
 
     // Add cross-package dependencies
-    for dep in dependencies {
+    for (dep, version) in dependencies {
         // Convert package name to valid crate name (replace - with _)
         let crate_name = dep.replace('-', "_");
-        cargo_toml.push_str(&format!("{} = {{ path = \"../{}\" }}\n", crate_name, dep));
+        // Code refers to the dependency by package name, whatever its crate is called
+        let renamed = options.naming.crate_name(dep);
+        let package = if renamed == crate_name {
+            String::new()
+        } else {
+            format!("package = \"{}\", ", renamed)
+        };
+        let spec = match (dependency_style, version) {
+            (DependencyStyle::Path, Some(version)) => format!(
+                "{{ {}path = \"../{}\", version = \"{}\" }}",
+                package, dep, version
+            ),
+            (DependencyStyle::Path, None) => format!("{{ {}path = \"../{}\" }}", package, dep),
+            (DependencyStyle::Version, version) if !package.is_empty() => format!(
+                "{{ {}version = \"{}\" }}",
+                package,
+                version.as_deref().unwrap_or("*")
+            ),
+            (DependencyStyle::Version, version) => {
+                format!("\"{}\"", version.as_deref().unwrap_or("*"))
+            }
+        };
+        cargo_toml.push_str(&format!("{} = {}\n", crate_name, spec));
     }
 
     // Add features section
     cargo_toml.push_str("\n[features]\ndefault = []\n");
-    if needs_big_array {
-        cargo_toml.push_str("serde = [\"dep:serde\", \"dep:serde-big-array\"]\n");
-    } else {
-        cargo_toml.push_str("serde = [\"dep:serde\"]\n");
+    let mut serde_features = vec!["\"dep:serde\"".to_string()];
+    if layout == Layout::Ros2Rust || options.embedded.is_some() {
+        // Like colcon-generated crates, forward serde to the runtime and dependencies
+        // (embedded bindings need it for the bounded containers)
+        serde_features.push("\"rosidl_runtime_rs/serde\"".to_string());
+        for dep in dependencies.keys() {
+            serde_features.push(format!("\"{}/serde\"", dep.replace('-', "_")));
+        }
+    }
+    cargo_toml.push_str(&format!("serde = [{}]\n", serde_features.join(", ")));
+
+    // Nested messages of other packages must be archivable as well
+    if options.embedded.is_none() {
+        let mut rkyv_features = vec!["\"dep:rkyv\"".to_string()];
+        for dep in dependencies.keys() {
+            rkyv_features.push(format!("\"{}/rkyv\"", dep.replace('-', "_")));
+        }
+        cargo_toml.push_str(&format!("rkyv = [{}]\n", rkyv_features.join(", ")));
+    }
+
+    // Arbitrary impls for `cargo ros2 fuzz-interfaces`, built on the runtime's
+    // fuzz helpers (absent from the upstream and no_std runtimes)
+    if !options.rclrs_compat && options.embedded.is_none() {
+        let mut arbitrary_features = vec!["\"rosidl_runtime_rs/arbitrary\"".to_string()];
+        for dep in dependencies.keys() {
+            arbitrary_features.push(format!("\"{}/arbitrary\"", dep.replace('-', "_")));
+        }
+        cargo_toml.push_str(&format!(
+            "arbitrary = [{}]\n",
+            arbitrary_features.join(", ")
+        ));
     }
 
     cargo_toml.push_str(
@@ -557,12 +1613,102 @@ serde = {{ version = "1.0", features = ["derive"], optional = true }}
 "#,
     );
 
-    std::fs::write(output_dir.join("Cargo.toml"), cargo_toml)?;
+    output.write(
+        &output.dir().join(package_name).join("Cargo.toml"),
+        cargo_toml,
+    )?;
     Ok(())
 }
 
+/// Crate version of a package: its package.xml version, which ROS requires to be
+/// `MAJOR.MINOR.PATCH`, or 0.1.0 when it has none
+fn crate_version(manifest: &PackageManifest) -> String {
+    manifest
+        .version
+        .as_deref()
+        .filter(|version| {
+            let parts: Vec<_> = version.split('.').collect();
+            parts.len() == 3
+                && parts
+                    .iter()
+                    .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        })
+        .unwrap_or("0.1.0")
+        .to_string()
+}
+
+/// `[package]` metadata fields carried over from package.xml, followed by a
+/// `[package.metadata.ros]` table naming the source package and its version as
+/// written, which the crate version cannot always keep
+fn package_metadata(package_name: &str, manifest: &PackageManifest) -> String {
+    let mut metadata = String::new();
+    if let Some(description) = &manifest.description {
+        metadata.push_str(&format!("description = {}\n", toml_string(description)));
+    }
+    if !manifest.licenses.is_empty() {
+        let licenses: Vec<_> = manifest.licenses.iter().map(|l| spdx_license(l)).collect();
+        metadata.push_str(&format!(
+            "license = {}\n",
+            toml_string(&licenses.join(" AND "))
+        ));
+    }
+    if !manifest.authors.is_empty() {
+        let authors: Vec<_> = manifest.authors.iter().map(|a| toml_string(a)).collect();
+        metadata.push_str(&format!("authors = [{}]\n", authors.join(", ")));
+    }
+    if let Some(repository) = &manifest.repository {
+        metadata.push_str(&format!("repository = {}\n", toml_string(repository)));
+    }
+    metadata.push_str(&format!(
+        "\n[package.metadata.ros]\npackage = {}\n",
+        toml_string(package_name)
+    ));
+    if let Some(version) = &manifest.version {
+        metadata.push_str(&format!("version = {}\n", toml_string(version)));
+    }
+    metadata
+}
+
+/// SPDX identifier for common package.xml license spellings
+///
+/// Anything not recognized is passed through unchanged.
+fn spdx_license(license: &str) -> &str {
+    match license {
+        "Apache License 2.0"
+        | "Apache License, Version 2.0"
+        | "Apache 2.0"
+        | "Apache2.0"
+        | "Apache 2" => "Apache-2.0",
+        "BSD 3-Clause" | "BSD-3" | "3-Clause BSD" => "BSD-3-Clause",
+        "BSD 2-Clause" | "2-Clause BSD" => "BSD-2-Clause",
+        "MIT License" => "MIT",
+        "LGPLv3" | "LGPL-3" => "LGPL-3.0-only",
+        "GPLv3" => "GPL-3.0-only",
+        "Mozilla Public License Version 2.0" | "MPL 2.0" => "MPL-2.0",
+        other => other,
+    }
+}
+
+/// Quote a TOML basic string
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 /// Generate build.rs for linking against ROS 2 C libraries
-fn generate_build_rs(output_dir: &Path, package_name: &str) -> Result<()> {
+fn generate_build_rs(output_dir: &Path, output: &Output, package_name: &str) -> Result<()> {
     let build_rs = format!(
         r#"fn main() {{
     // Add ROS library search paths from AMENT_PREFIX_PATH (for system packages)
@@ -614,7 +1760,7 @@ fn generate_build_rs(output_dir: &Path, package_name: &str) -> Result<()> {
         package = package_name
     );
 
-    std::fs::write(output_dir.join("build.rs"), build_rs)?;
+    output.write(&output_dir.join("build.rs"), build_rs)?;
     Ok(())
 }
 
@@ -622,6 +1768,7 @@ fn generate_build_rs(output_dir: &Path, package_name: &str) -> Result<()> {
 mod tests {
     use super::*;
     use crate::ament::Package;
+    use rosidl_codegen::EmbeddedCapacity;
     use std::fs;
 
     /// Helper to create a test package with interface files
@@ -660,82 +1807,1103 @@ mod tests {
         let package = create_test_package(temp_dir.path());
         let output_dir = temp_dir.path().join("output");
 
-        let result = generate_package(&package, &output_dir);
+        let result = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        );
         assert!(result.is_ok());
 
         let generated = result.unwrap();
         assert_eq!(generated.message_count, 1);
         assert_eq!(generated.service_count, 1);
         assert_eq!(generated.action_count, 1);
+        assert_eq!(
+            generated.interfaces,
+            ["msg/Point", "srv/AddTwoInts", "action/Fibonacci"]
+        );
+        // The implicit action messages
+        assert_eq!(
+            generated.dependencies,
+            ["builtin_interfaces", "unique_identifier_msgs"]
+        );
+        let json = serde_json::to_value(&generated).unwrap();
+        assert_eq!(json["package"], "test_pkg");
+        assert_eq!(json["interfaces"][1], "srv/AddTwoInts");
 
         // Check that files were created
         let pkg_dir = output_dir.join("test_pkg");
         assert!(pkg_dir.join("Cargo.toml").exists());
         assert!(pkg_dir.join("build.rs").exists());
         assert!(pkg_dir.join("src").join("lib.rs").exists());
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert!(lib_rs.contains("#[cfg(feature = \"serde\")]\nmod serde_array;"));
+        assert!(lib_rs.contains("    pub use point::Point;\n"));
+        assert!(lib_rs.contains(
+            "pub mod prelude {\n    pub use crate::msg::Point;\n    \
+             pub use crate::srv::{AddTwoInts, AddTwoIntsRequest, AddTwoIntsResponse};\n"
+        ));
+        let serde_array = fs::read_to_string(pkg_dir.join("src").join("serde_array.rs")).unwrap();
+        assert!(serde_array.contains("pub fn serialize<S, T, const N: usize>"));
+
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        // The implicit action messages depend on unique_identifier_msgs and builtin_interfaces
+        assert!(cargo_toml.contains("arbitrary = [\"rosidl_runtime_rs/arbitrary\", \"builtin_interfaces/arbitrary\", \"unique_identifier_msgs/arbitrary\"]"));
+        assert!(cargo_toml.contains("rkyv = { version = \"0.8\", optional = true }"));
+        assert!(cargo_toml.contains(
+            "rkyv = [\"dep:rkyv\", \"builtin_interfaces/rkyv\", \"unique_identifier_msgs/rkyv\"]"
+        ));
+    }
+
+    #[test]
+    fn test_generation_is_deterministic() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        fs::write(
+            package.share_dir.join("msg").join("Pose.msg"),
+            "std_msgs/Header header\ngeometry_msgs/Point position\nbuiltin_interfaces/Time stamp\n",
+        )
+        .unwrap();
+        let package = Package::from_share_dir(package.share_dir.clone()).unwrap();
+
+        fn read_tree(dir: &Path, files: &mut BTreeMap<PathBuf, Vec<u8>>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    read_tree(&path, files);
+                } else {
+                    files.insert(path.clone(), fs::read(&path).unwrap());
+                }
+            }
+        }
+
+        let mut outputs = Vec::new();
+        for run in ["first", "second"] {
+            let output_dir = temp_dir.path().join(run);
+            generate_package(
+                &package,
+                &Output::disk(&output_dir),
+                GeneratorOptions::default(),
+                Layout::Default,
+                DependencyStyle::Path,
+                None,
+            )
+            .unwrap();
+            let mut files = BTreeMap::new();
+            read_tree(&output_dir, &mut files);
+            let files: BTreeMap<_, _> = files
+                .into_iter()
+                .map(|(path, content)| {
+                    (path.strip_prefix(&output_dir).unwrap().to_owned(), content)
+                })
+                .collect();
+            outputs.push(files);
+        }
+        assert!(outputs[0].len() > 5);
+        assert_eq!(outputs[0], outputs[1]);
+    }
+
+    #[test]
+    fn test_generate_in_memory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let generate = |output: &Output| {
+            generate_package(
+                &package,
+                output,
+                GeneratorOptions::default(),
+                Layout::Default,
+                DependencyStyle::Path,
+                None,
+            )
+            .unwrap();
+            generate_type_descriptions(&package, None, output, false).unwrap();
+            generate_ros1_metadata(&package, None, output).unwrap();
+            generate_protobuf_mapping(&package, output).unwrap();
+            generate_python_bindings(&package, output).unwrap();
+            generate_cxx_bindings(&package, output).unwrap();
+        };
+
+        let output_dir = temp_dir.path().join("output");
+        let in_memory = Output::in_memory(&output_dir);
+        generate(&in_memory);
+        assert!(!output_dir.exists());
+        let rendered = in_memory.into_files();
+
+        // The same files as a regular run, the runtime crate included
+        generate(&Output::disk(&output_dir));
+        let mut written = BTreeMap::new();
+        let mut dirs = vec![output_dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    let relative = path.strip_prefix(&output_dir).unwrap().to_owned();
+                    written.insert(relative, fs::read_to_string(&path).unwrap());
+                }
+            }
+        }
+        assert!(rendered.contains_key(Path::new("test_pkg/src/proto.rs")));
+        assert!(rendered.contains_key(Path::new("rosidl_runtime_rs/Cargo.toml")));
+        assert_eq!(rendered, written);
+
+        // A runtime crate already in the output directory is left out
+        let in_memory = Output::in_memory(&output_dir);
+        generate(&in_memory);
+        assert!(in_memory
+            .into_files()
+            .keys()
+            .all(|path| path.starts_with("test_pkg")));
+    }
+
+    #[test]
+    fn test_dependency_closure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share = temp_dir.path().join("share");
+        for (package, msg) in [
+            (
+                "nav_msgs",
+                "geometry_msgs/Pose pose\nstd_msgs/Header header\n",
+            ),
+            ("geometry_msgs", "std_msgs/Header header\nfloat64 x\n"),
+            ("std_msgs", "string frame_id\n"),
+            ("unused_msgs", "bool flag\n"),
+        ] {
+            fs::create_dir_all(share.join(package).join("msg")).unwrap();
+            fs::write(share.join(package).join("msg/Data.msg"), msg).unwrap();
+        }
+        let index = AmentIndex::from_path_string(temp_dir.path().to_str().unwrap()).unwrap();
+        let requested = vec![index.find_package("nav_msgs").unwrap().clone()];
+
+        let closure =
+            dependency_closure(requested.clone(), &index, &GeneratorOptions::default()).unwrap();
+        let names: Vec<_> = closure.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["nav_msgs", "geometry_msgs", "std_msgs"]);
+
+        fs::remove_dir_all(share.join("std_msgs")).unwrap();
+        let index = AmentIndex::from_path_string(temp_dir.path().to_str().unwrap()).unwrap();
+        let err = dependency_closure(requested, &index, &GeneratorOptions::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Package 'std_msgs' (a dependency of nav_msgs) not found in ament index"
+        );
+    }
+
+    #[test]
+    fn test_declared_dependencies() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share = temp_dir.path().join("share");
+        for package in ["limits_msgs", "std_msgs"] {
+            fs::create_dir_all(share.join(package).join("msg")).unwrap();
+        }
+        // Mirrors constants of std_msgs without a std_msgs field
+        fs::write(share.join("limits_msgs/msg/Limits.msg"), "int32 MAX=10\n").unwrap();
+        fs::write(share.join("std_msgs/msg/Empty.msg"), "").unwrap();
+        fs::write(
+            share.join("limits_msgs/package.xml"),
+            "<package><version>1.2.0</version>\
+             <buildtool_depend>ament_cmake</buildtool_depend>\
+             <depend>std_msgs</depend>\
+             <exec_depend>rosidl_default_runtime</exec_depend></package>",
+        )
+        .unwrap();
+        let index = AmentIndex::from_path_string(temp_dir.path().to_str().unwrap()).unwrap();
+        let package = index.find_package("limits_msgs").unwrap();
+
+        let output_dir = temp_dir.path().join("out");
+        generate_package(
+            package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            Some(&index),
+        )
+        .unwrap();
+        let cargo_toml = fs::read_to_string(output_dir.join("limits_msgs/Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("std_msgs = { path = \"../std_msgs\""));
+        assert!(!cargo_toml.contains("rosidl_default_runtime"));
+        assert!(!cargo_toml.contains("ament_cmake"));
+
+        let closure =
+            dependency_closure(vec![package.clone()], &index, &GeneratorOptions::default())
+                .unwrap();
+        let names: Vec<_> = closure.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["limits_msgs", "std_msgs"]);
+
+        // Without an index, only referenced packages are dependencies
+        let dependencies =
+            package_dependencies(package, &GeneratorOptions::default(), None).unwrap();
+        assert!(dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_for_each_interface_fails_in_name_order() {
+        let interfaces: BTreeMap<_, _> = (0..64).map(|i| (format!("Msg{:02}", i), i)).collect();
+        assert_eq!(for_each_interface(&interfaces, |_, _| Ok(())).unwrap(), 64);
+        for _ in 0..8 {
+            let err = for_each_interface(&interfaces, |name, i| {
+                if i % 7 == 3 {
+                    Err(eyre!("{} failed", name))
+                } else {
+                    Ok(())
+                }
+            })
+            .unwrap_err();
+            assert_eq!(err.to_string(), "Msg03 failed");
+        }
+    }
+
+    #[test]
+    fn test_generate_from_idl() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share_dir = temp_dir.path().join("idl_pkg");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::create_dir_all(share_dir.join("srv")).unwrap();
+        // Installed without the .msg/.srv sources
+        fs::write(
+            share_dir.join("msg/Point.idl"),
+            "module idl_pkg {\n  module msg {\n    struct Point {\n      double x;\n      double y;\n    };\n  };\n};\n",
+        )
+        .unwrap();
+        fs::write(
+            share_dir.join("srv/AddTwoInts.idl"),
+            "module idl_pkg {\n  module srv {\n    struct AddTwoInts_Request {\n      int64 a;\n    };\n    struct AddTwoInts_Response {\n      int64 sum;\n    };\n  };\n};\n",
+        )
+        .unwrap();
+
+        let package = Package::from_share_dir(share_dir).unwrap();
+        assert_eq!(package.interfaces.messages, ["Point"]);
+        let output_dir = temp_dir.path().join("output");
+        let generated = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        assert_eq!(generated.message_count, 1);
+        assert_eq!(generated.service_count, 1);
+
+        let point =
+            fs::read_to_string(output_dir.join("idl_pkg/src/msg/point_idiomatic.rs")).unwrap();
+        assert!(point.contains("pub y: f64"));
+    }
+
+    #[test]
+    fn test_generate_reports_broken_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        fs::write(package.share_dir.join("msg/Bad.msg"), "int32 = 1\n").unwrap();
+        fs::write(
+            package.share_dir.join("srv/AddTwoInts.srv"),
+            "int64[ a\n---\n",
+        )
+        .unwrap();
+        let output_dir = temp_dir.path().join("output");
+
+        let err = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap_err();
+        let report = format!("{:?}", err);
+        assert!(report.contains("msg/Bad.msg"));
+        assert!(report.contains("srv/AddTwoInts.srv"));
+
+        // Types-only bindings skip services, including their errors
+        fs::remove_file(package.share_dir.join("msg/Bad.msg")).unwrap();
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        assert!(generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_package_ast() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+
+        let ast = package_ast(&package).unwrap();
+        let json = serde_json::to_value(&ast).unwrap();
+        assert_eq!(json["package"], "test_pkg");
+        assert_eq!(json["messages"]["Point"]["fields"][1]["name"], "y");
+        assert_eq!(
+            json["services"]["AddTwoInts"]["response"]["fields"][0]["name"],
+            "sum"
+        );
+        assert_eq!(
+            json["actions"]["Fibonacci"]["spec"]["goal"]["fields"][0]["field_type"]["Primitive"],
+            "Int32"
+        );
+    }
+
+    #[test]
+    fn test_generate_types_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let generated = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        assert_eq!(generated.message_count, 1);
+        assert_eq!(generated.service_count, 0);
+        assert_eq!(generated.action_count, 0);
+
+        let pkg_dir = output_dir.join("test_pkg");
+        assert!(pkg_dir.join("src/msg/point_idiomatic.rs").exists());
+        assert!(!pkg_dir.join("src").join(FFI_MODULE).exists());
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert!(lib_rs.contains("pub mod msg {"));
+        assert!(!lib_rs.contains(&format!("pub mod {} {{", FFI_MODULE)));
+        assert!(!lib_rs.contains("pub mod srv"));
+        assert!(!lib_rs.contains("pub mod action"));
+
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains(
+            "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\", features = [\"mock-ffi\"] }"
+        ));
+        let build_rs = fs::read_to_string(pkg_dir.join("build.rs")).unwrap();
+        assert!(!build_rs.contains("rustc-link-lib"));
+    }
+
+    #[test]
+    fn test_generate_embedded() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity::default()),
+            ..Default::default()
+        };
+        let generated = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        assert_eq!(generated.message_count, 1);
+        assert_eq!(generated.service_count, 0);
+        assert_eq!(generated.action_count, 0);
+
+        let pkg_dir = output_dir.join("test_pkg");
+        assert!(!pkg_dir.join("src").join(FFI_MODULE).exists());
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert!(lib_rs.starts_with("#![no_std]\n"));
+        assert!(!lib_rs.contains("pub mod srv"));
+
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains(
+            "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\", default-features = false }"
+        ));
+        assert!(cargo_toml.contains(
+            "serde = { version = \"1.0\", default-features = false, features = [\"derive\"], optional = true }"
+        ));
+        assert!(cargo_toml.contains("default = []"));
+        assert!(cargo_toml.contains("\"rosidl_runtime_rs/serde\""));
+        assert!(!cargo_toml.contains("rkyv"));
+        let build_rs = fs::read_to_string(pkg_dir.join("build.rs")).unwrap();
+        assert!(!build_rs.contains("rustc-link-lib"));
+    }
+
+    #[test]
+    fn test_generate_ros1_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        let notes = generate_ros1_metadata(&package, None, &Output::disk(&output_dir)).unwrap();
+        assert!(notes.is_empty());
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let ros1_rs = fs::read_to_string(pkg_dir.join("src").join("ros1.rs")).unwrap();
+        assert!(ros1_rs.contains("pub mod point {"));
+        assert!(ros1_rs.contains("pub const DATATYPE: &str = \"test_pkg/Point\";"));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert_eq!(lib_rs.matches("pub mod ros1;").count(), 1);
+        assert!(pkg_dir.join("ros1_type_mapping.txt").exists());
+    }
+
+    #[test]
+    fn test_generate_type_descriptions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        let skipped =
+            generate_type_descriptions(&package, None, &Output::disk(&output_dir), false).unwrap();
+        assert!(skipped.is_empty());
+        generate_type_descriptions(&package, None, &Output::disk(&output_dir), false).unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let module = fs::read_to_string(pkg_dir.join("src").join("type_description.rs")).unwrap();
+        assert!(module.contains("pub mod point {"));
+        assert!(module.contains("pub const TYPE_NAME: &str = \"test_pkg/msg/Point\";"));
+        assert!(module.contains("pub const TYPE_HASH: &str = \"RIHS01_"));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert_eq!(lib_rs.matches("pub mod type_description;").count(), 1);
+    }
+
+    #[test]
+    fn test_generate_protobuf_mapping() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        generate_protobuf_mapping(&package, &Output::disk(&output_dir)).unwrap();
+        generate_protobuf_mapping(&package, &Output::disk(&output_dir)).unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let proto = fs::read_to_string(pkg_dir.join("proto").join("test_pkg.proto")).unwrap();
+        assert!(proto.contains("message Point {\n  double x = 1;\n  double y = 2;\n}"));
+        assert!(pkg_dir.join("src").join("proto.rs").exists());
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert_eq!(
+            lib_rs
+                .matches("#[cfg(feature = \"protobuf\")]\npub mod proto;")
+                .count(),
+            1
+        );
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert_eq!(
+            cargo_toml
+                .matches("prost = { version = \"0.13\", optional = true }")
+                .count(),
+            1
+        );
+        assert_eq!(
+            cargo_toml
+                .matches("protobuf = [\"dep:prost\", \"dep:prost-build\"]")
+                .count(),
+            1
+        );
+        assert!(cargo_toml
+            .trim_end()
+            .ends_with("prost-build = { version = \"0.13\", optional = true }"));
+        let build_rs = fs::read_to_string(pkg_dir.join("build.rs")).unwrap();
+        assert_eq!(build_rs.matches("compile_protos").count(), 1);
+        assert_eq!(
+            build_rs.matches("#[cfg(feature = \"protobuf\")]").count(),
+            1
+        );
+        assert!(build_rs.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_generate_service_events() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        let options = GeneratorOptions {
+            service_events: true,
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("service_msgs = { path = \"../service_msgs\" }"));
+        let srv = fs::read_to_string(pkg_dir.join("src/ffi/srv/add_two_ints_rmw.rs")).unwrap();
+        assert!(srv.contains("pub use event::AddTwoIntsEvent;"));
+    }
+
+    #[test]
+    fn test_generate_parameter_conversions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share_dir = temp_dir.path().join("rcl_interfaces");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::write(
+            share_dir.join("msg/ParameterValue.msg"),
+            "uint8 type\nbool bool_value\nint64 integer_value\nfloat64 double_value\n\
+             string string_value\nbyte[] byte_array_value\nbool[] bool_array_value\n\
+             int64[] integer_array_value\nfloat64[] double_array_value\n\
+             string[] string_array_value\n",
+        )
+        .unwrap();
+        let package = Package::from_share_dir(share_dir).unwrap();
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        let pkg_dir = output_dir.join("rcl_interfaces");
+        let lib_rs = fs::read_to_string(pkg_dir.join("src/lib.rs")).unwrap();
+        assert_eq!(lib_rs.matches("pub mod parameter;").count(), 1);
+        let parameter_rs = fs::read_to_string(pkg_dir.join("src/parameter.rs")).unwrap();
+        assert!(parameter_rs.contains("impl TryFrom<ParameterValueMsg> for ParameterValue"));
+
+        // Not available against the upstream runtime
+        let rclrs_output = temp_dir.path().join("rclrs_output");
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&rclrs_output),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        assert!(!rclrs_output
+            .join("rcl_interfaces/src/parameter.rs")
+            .exists());
+    }
+
+    #[test]
+    fn test_generate_python_bindings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        generate_python_bindings(&package, &Output::disk(&output_dir)).unwrap();
+        generate_python_bindings(&package, &Output::disk(&output_dir)).unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let python_rs = fs::read_to_string(pkg_dir.join("src").join("python.rs")).unwrap();
+        assert!(python_rs.contains("pub struct PyPoint(pub crate::msg::point::Point);"));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert_eq!(
+            lib_rs
+                .matches("#[cfg(feature = \"python\")]\npub mod python;")
+                .count(),
+            1
+        );
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert_eq!(
+            cargo_toml
+                .matches("pyo3 = { version = \"0.22\", optional = true }")
+                .count(),
+            1
+        );
+        assert_eq!(cargo_toml.matches("python = [\"dep:pyo3\"]").count(), 1);
+    }
+
+    #[test]
+    fn test_generate_cxx_bindings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        generate_cxx_bindings(&package, &Output::disk(&output_dir)).unwrap();
+        generate_cxx_bindings(&package, &Output::disk(&output_dir)).unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let bridge = fs::read_to_string(pkg_dir.join("src").join("cxx_bridge.rs")).unwrap();
+        assert!(bridge.contains("#[cxx::bridge(namespace = \"test_pkg::msg\")]"));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src").join("lib.rs")).unwrap();
+        assert_eq!(
+            lib_rs
+                .matches("#[cfg(feature = \"cxx\")]\npub mod cxx_bridge;")
+                .count(),
+            1
+        );
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert_eq!(
+            cargo_toml
+                .matches("links = \"test_pkg_cxx_bridge\"")
+                .count(),
+            1
+        );
+        assert_eq!(
+            cargo_toml
+                .matches("cxx = [\"dep:cxx\", \"dep:cxx-build\"]")
+                .count(),
+            1
+        );
+        assert!(cargo_toml
+            .trim_end()
+            .ends_with("cxx-build = { version = \"1.0\", optional = true }"));
+        let build_rs = fs::read_to_string(pkg_dir.join("build.rs")).unwrap();
+        assert_eq!(build_rs.matches("cxx_build::bridge").count(), 1);
+        assert!(build_rs.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn test_generate_rclrs_compat() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        assert!(!output_dir.join(RUNTIME_CRATE).exists());
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("rosidl_runtime_rs = \"0.4\""));
+        assert!(!cargo_toml.contains("../rosidl_runtime_rs"));
+
+        let point = fs::read_to_string(pkg_dir.join("src/msg/point_idiomatic.rs")).unwrap();
+        assert!(!point.contains("SequenceElement"));
+        let lib_rs = fs::read_to_string(pkg_dir.join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains("pub use point::Point;"));
+        let action = fs::read_to_string(pkg_dir.join("src/action/fibonacci_idiomatic.rs")).unwrap();
+        assert!(action.contains("impl crate::rosidl_runtime_rs::Action for Fibonacci"));
+        assert!(action
+            .contains("partial_sequence: idiomatic.partial_sequence.iter().copied().collect()"));
+
+        // The runtime of another rclrs release
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            rclrs_runtime_version: Some("0.5".to_string()),
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("rosidl_runtime_rs = \"0.5\""));
+    }
+
+    #[test]
+    fn test_generate_ros2_rust_layout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
+
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Ros2Rust,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let lib_rs = fs::read_to_string(pkg_dir.join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains("pub use point::Point;"));
+        assert!(lib_rs.contains("pub use crate::ffi::msg::point::Point;"));
+        assert!(lib_rs.contains("pub use add_two_ints::{AddTwoIntsRequest as AddTwoInts_Request, AddTwoIntsResponse as AddTwoInts_Response};"));
+        assert!(lib_rs.contains("pub use crate::ffi::srv::add_two_ints::{AddTwoInts, AddTwoIntsRequest as AddTwoInts_Request, AddTwoIntsResponse as AddTwoInts_Response};"));
+        assert!(lib_rs.contains("pub use fibonacci::{FibonacciGoal as Fibonacci_Goal, FibonacciResult as Fibonacci_Result, FibonacciFeedback as Fibonacci_Feedback};"));
+        assert!(lib_rs.contains(
+            "pub use fibonacci::{Fibonacci, FibonacciGoal, FibonacciResult, FibonacciFeedback};"
+        ));
+
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("serde = [\"dep:serde\", \"rosidl_runtime_rs/serde\", \"builtin_interfaces/serde\", \"unique_identifier_msgs/serde\"]"));
+    }
+
+    #[test]
+    fn test_generate_interface_features() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        fs::write(
+            package.share_dir.join("msg/Segment.msg"),
+            "Point start\nPoint end\n",
+        )
+        .unwrap();
+        let package = Package::from_share_dir(package.share_dir).unwrap();
+        let output_dir = temp_dir.path().join("output");
+
+        let options = GeneratorOptions {
+            interface_features: true,
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        )
+        .unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains(
+            "default = [\"full\"]\n# Every interface; disable default features to pick them one by one\nfull = [\"action_fibonacci\", \"msg_point\", \"msg_segment\", \"srv_add_two_ints\"]\n"
+        ));
+        // A message brings along the messages of the package it nests
+        assert!(cargo_toml.contains("msg_segment = [\"msg_point\"]\n"));
+        assert!(cargo_toml.contains("srv_add_two_ints = []\n"));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains(
+            "        #[cfg(feature = \"msg_point\")]\n        #[path = \"point_rmw.rs\"]\n"
+        ));
+        assert!(lib_rs.contains(
+            "    #[cfg(feature = \"srv_add_two_ints\")]\n    pub use add_two_ints::{AddTwoInts, AddTwoIntsRequest, AddTwoIntsResponse};"
+        ));
+        assert!(lib_rs
+            .contains("    #[cfg(feature = \"action_fibonacci\")]\n    pub use crate::action::{"));
+        // The docs only link to interfaces that are compiled
+        assert!(lib_rs.contains(
+            "#![cfg_attr(feature = \"msg_point\", doc = \" - [`Point`](msg::Point)\")]\n"
+        ));
     }
 
     #[test]
     fn test_generate_lib_rs_structure() {
         let temp_dir = tempfile::tempdir().unwrap();
         let package = create_test_package(temp_dir.path());
+        fs::write(
+            package.share_dir.join("msg/Pose.msg"),
+            "# A position\n# in space\n\nPoint position\n",
+        )
+        .unwrap();
+        let package = Package::from_share_dir(package.share_dir).unwrap();
         let output_dir = temp_dir.path().join("output");
         std::fs::create_dir_all(&output_dir).unwrap();
 
-        let deps = HashSet::new();
-        generate_lib_rs(&output_dir, &package, &deps).unwrap();
+        let parsed = parse_interfaces(&package, false).unwrap();
+        generate_lib_rs(
+            &output_dir,
+            &Output::disk(&output_dir),
+            &package,
+            &parsed,
+            &GeneratorOptions::default(),
+            Layout::Default,
+        )
+        .unwrap();
 
         let lib_rs_content =
             std::fs::read_to_string(output_dir.join("src").join("lib.rs")).unwrap();
         assert!(lib_rs_content.contains("pub mod msg"));
         assert!(lib_rs_content.contains("pub mod srv"));
         assert!(lib_rs_content.contains("pub mod action"));
+
+        // Types at `<package>::<kind>::<Type>`, their own modules hidden from the docs
+        assert!(lib_rs_content.contains(
+            "    #[doc(hidden)]\n    #[path = \"point_idiomatic.rs\"]\n    pub mod point;\n    pub use point::Point;\n"
+        ));
+        assert!(lib_rs_content.contains(
+            "pub use add_two_ints::{AddTwoInts, AddTwoIntsRequest, AddTwoIntsResponse};"
+        ));
+
+        // An index of the interfaces, with the first line of their leading comment
+        assert!(lib_rs_content.starts_with(
+            "//! ROS 2 interfaces of the `test_pkg` package\n//!\n//! # Messages\n//!\n\
+             //! - [`Point`](msg::Point)\n//! - [`Pose`](msg::Pose): A position\n"
+        ));
+        assert!(
+            lib_rs_content.contains("//! # Services\n//!\n//! - [`AddTwoInts`](srv::AddTwoInts)\n")
+        );
+        assert!(lib_rs_content.contains("//! - [`Fibonacci`](action::Fibonacci)\n"));
+    }
+
+    #[test]
+    fn test_generate_provenance() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        fs::write(
+            package.share_dir.join("package.xml"),
+            "<package>\n  <name>test_pkg</name>\n  <version>1.2.0</version>\n</package>\n",
+        )
+        .unwrap();
+        let output_dir = temp_dir.path().join("output");
+        std::fs::create_dir_all(output_dir.join("src")).unwrap();
+
+        generate_provenance_rs(&output_dir, &Output::disk(&output_dir), &package).unwrap();
+
+        let provenance_rs =
+            fs::read_to_string(output_dir.join("src").join("provenance.rs")).unwrap();
+        assert!(provenance_rs.contains("pub const PACKAGE_NAME: &str = \"test_pkg\";"));
+        assert!(
+            provenance_rs.contains("pub const PACKAGE_VERSION: Option<&str> = Some(\"1.2.0\");")
+        );
+        assert!(provenance_rs.contains(&format!(
+            "pub const SOURCE_CHECKSUM: &str = \"{}\";",
+            package.interface_checksum().unwrap()
+        )));
+        assert!(provenance_rs.contains(&format!(
+            "pub const GENERATOR_VERSION: &str = \"{}\";",
+            rosidl_codegen::VERSION
+        )));
     }
 
     #[test]
     fn test_cargo_toml_generation() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let deps = HashSet::new();
-        generate_cargo_toml(temp_dir.path(), "test_pkg", &deps, false).unwrap();
+        let deps = BTreeMap::new();
+        generate_cargo_toml(
+            &Output::disk(temp_dir.path()),
+            "test_pkg",
+            &PackageManifest::default(),
+            &deps,
+            &GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+        )
+        .unwrap();
 
-        let cargo_toml = std::fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap();
+        let cargo_toml =
+            std::fs::read_to_string(temp_dir.path().join("test_pkg/Cargo.toml")).unwrap();
         assert!(cargo_toml.contains("name = \"test_pkg\""));
         assert!(cargo_toml.contains("serde"));
         assert!(!cargo_toml.contains("serde-big-array"));
     }
 
     #[test]
-    fn test_cargo_toml_with_dependencies() {
+    fn test_cargo_toml_package_metadata() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let mut deps = HashSet::new();
-        deps.insert("std_msgs".to_string());
-        deps.insert("geometry_msgs".to_string());
+        let manifest = PackageManifest {
+            version: Some("4.2.3".to_string()),
+            description: Some("Messages with \"quotes\"".to_string()),
+            licenses: vec!["Apache License 2.0".to_string(), "BSD".to_string()],
+            authors: vec!["Jane Doe <jane@example.com>".to_string()],
+            repository: Some("https://github.com/ros2/common_interfaces".to_string()),
+            dependencies: Vec::new(),
+        };
+
+        generate_cargo_toml(
+            &Output::disk(temp_dir.path()),
+            "test_pkg",
+            &manifest,
+            &BTreeMap::new(),
+            &GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+        )
+        .unwrap();
+
+        let cargo_toml =
+            std::fs::read_to_string(temp_dir.path().join("test_pkg/Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("description = \"Messages with \\\"quotes\\\"\"\n"));
+        assert!(cargo_toml.contains("license = \"Apache-2.0 AND BSD\"\n"));
+        assert!(cargo_toml.contains("authors = [\"Jane Doe <jane@example.com>\"]\n"));
+        assert!(cargo_toml.contains("repository = \"https://github.com/ros2/common_interfaces\"\n"));
+        assert!(cargo_toml.contains("version = \"4.2.3\"\n"));
+        assert!(cargo_toml
+            .contains("[package.metadata.ros]\npackage = \"test_pkg\"\nversion = \"4.2.3\"\n"));
+    }
 
-        generate_cargo_toml(temp_dir.path(), "test_pkg", &deps, false).unwrap();
+    #[test]
+    fn test_cargo_toml_with_dependencies() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut deps = BTreeMap::new();
+        deps.insert("std_msgs".to_string(), Some("5.3.2".to_string()));
+        deps.insert("geometry_msgs".to_string(), None);
+
+        generate_cargo_toml(
+            &Output::disk(temp_dir.path()),
+            "test_pkg",
+            &PackageManifest::default(),
+            &deps,
+            &GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+        )
+        .unwrap();
 
-        let cargo_toml = std::fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap();
+        let cargo_toml =
+            std::fs::read_to_string(temp_dir.path().join("test_pkg/Cargo.toml")).unwrap();
         assert!(cargo_toml.contains("name = \"test_pkg\""));
+        assert!(cargo_toml.contains("version = \"0.1.0\"\n"));
         assert!(cargo_toml.contains("serde"));
-        assert!(cargo_toml.contains("std_msgs = \"*\""));
-        assert!(cargo_toml.contains("geometry_msgs = \"*\""));
+        assert!(cargo_toml.contains("std_msgs = { path = \"../std_msgs\", version = \"5.3.2\" }"));
+        assert!(cargo_toml.contains("geometry_msgs = { path = \"../geometry_msgs\" }"));
+
+        generate_cargo_toml(
+            &Output::disk(temp_dir.path()),
+            "test_pkg",
+            &PackageManifest::default(),
+            &deps,
+            &GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Version,
+        )
+        .unwrap();
+
+        let cargo_toml =
+            std::fs::read_to_string(temp_dir.path().join("test_pkg/Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("std_msgs = \"5.3.2\"\n"));
+        assert!(cargo_toml.contains("geometry_msgs = \"*\"\n"));
     }
 
     #[test]
-    fn test_cargo_toml_with_big_array() {
+    fn test_generate_naming() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let deps = HashSet::new();
-        generate_cargo_toml(temp_dir.path(), "test_pkg", &deps, true).unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("output");
 
-        let cargo_toml = std::fs::read_to_string(temp_dir.path().join("Cargo.toml")).unwrap();
-        assert!(cargo_toml.contains("name = \"test_pkg\""));
-        assert!(cargo_toml.contains("serde"));
-        assert!(cargo_toml.contains("serde-big-array"));
+        let options = GeneratorOptions {
+            naming: rosidl_codegen::Naming {
+                crate_prefix: "ros2_".to_string(),
+                crate_names: BTreeMap::from([(
+                    "builtin_interfaces".to_string(),
+                    "ros_time".to_string(),
+                )]),
+                type_renames: BTreeMap::from([
+                    ("test_pkg/msg/Point".to_string(), "Point2".to_string()),
+                    ("test_pkg/srv/AddTwoInts".to_string(), "Add".to_string()),
+                ]),
+                serde_rename_all: None,
+            },
+            ..Default::default()
+        };
+        generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            options,
+            Layout::Default,
+            DependencyStyle::Version,
+            None,
+        )
+        .unwrap();
+
+        let pkg_dir = output_dir.join("test_pkg");
+        let cargo_toml = fs::read_to_string(pkg_dir.join("Cargo.toml")).unwrap();
+        assert!(cargo_toml.contains("name = \"ros2_test_pkg\"\n"));
+        // Dependencies keep their package name in code
+        assert!(cargo_toml
+            .contains("builtin_interfaces = { package = \"ros_time\", version = \"*\" }\n"));
+        assert!(cargo_toml.contains(
+            "unique_identifier_msgs = { package = \"ros2_unique_identifier_msgs\", version = \"*\" }\n"
+        ));
+
+        let lib_rs = fs::read_to_string(pkg_dir.join("src/lib.rs")).unwrap();
+        assert!(lib_rs.contains("    pub use point::Point as Point2;\n"));
+        assert!(lib_rs.contains(
+            "    pub use add_two_ints::{AddTwoInts as Add, AddTwoIntsRequest as AddRequest, AddTwoIntsResponse as AddResponse};\n"
+        ));
+        assert!(lib_rs.contains("    pub use crate::msg::Point2;\n"));
+        assert!(lib_rs.contains("//! - [`Point2`](msg::Point2)\n"));
+    }
+
+    #[test]
+    fn test_crate_version() {
+        let version = |v: &str| {
+            crate_version(&PackageManifest {
+                version: Some(v.to_string()),
+                ..Default::default()
+            })
+        };
+        assert_eq!(version("5.3.2"), "5.3.2");
+        assert_eq!(version("1.2"), "0.1.0");
+        assert_eq!(version("1.2.3-beta"), "0.1.0");
+        assert_eq!(crate_version(&PackageManifest::default()), "0.1.0");
     }
 
     #[test]
     fn test_build_rs_generation() {
         let temp_dir = tempfile::tempdir().unwrap();
-        generate_build_rs(temp_dir.path(), "test_pkg").unwrap();
+        generate_build_rs(temp_dir.path(), &Output::disk(temp_dir.path()), "test_pkg").unwrap();
 
         let build_rs = std::fs::read_to_string(temp_dir.path().join("build.rs")).unwrap();
         assert!(build_rs.contains("test_pkg__rosidl_typesupport_c"));
@@ -753,7 +2921,14 @@ mod tests {
         let package = Package::from_share_dir(share_dir).unwrap();
         let output_dir = temp_dir.path().join("output");
 
-        let result = generate_package(&package, &output_dir);
+        let result = generate_package(
+            &package,
+            &Output::disk(&output_dir),
+            GeneratorOptions::default(),
+            Layout::Default,
+            DependencyStyle::Path,
+            None,
+        );
         assert!(result.is_err());
     }
 }
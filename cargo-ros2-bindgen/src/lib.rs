@@ -4,3 +4,5 @@
 
 pub mod ament;
 pub mod generator;
+pub mod output;
+pub mod workspace;
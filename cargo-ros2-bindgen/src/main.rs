@@ -1,49 +1,266 @@
 mod ament;
+mod dry_run;
 mod generator;
+mod output;
+mod validate;
+mod watch;
+mod workspace;
 
 use clap::Parser;
 use eyre::{eyre, Result, WrapErr};
-use std::path::PathBuf;
+use output::Output;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 /// Generate Rust bindings for ROS 2 interface packages
 #[derive(Parser, Debug)]
 #[command(name = "cargo-ros2-bindgen")]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Name of the ROS 2 package to generate bindings for
-    #[arg(short, long)]
-    package: String,
+    /// Name of the ROS 2 package to generate bindings for; repeat to generate
+    /// several packages in parallel
+    #[arg(short, long, required = true)]
+    package: Vec<String>,
 
-    /// Output directory for generated bindings
+    /// Output directory for generated bindings (required unless `--emit ast`)
     #[arg(short, long)]
-    output: PathBuf,
+    output: Option<PathBuf>,
+
+    /// What to produce
+    #[arg(long, value_enum, default_value_t)]
+    emit: Emit,
+
+    /// How to report the generated bindings on stdout
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+
+    /// Generate in memory and print which files of the output
+    /// directory would be created, changed or removed, leaving it untouched
+    #[arg(long)]
+    dry_run: bool,
+
+    /// With --dry-run, also print a unified diff of each file
+    #[arg(long, requires = "dry_run")]
+    diff: bool,
+
+    /// Keep running, regenerating the packages whose interface files change and
+    /// the packages depending on them
+    #[arg(long, conflicts_with = "dry_run")]
+    watch: bool,
+
+    /// Run `cargo check` on the generated crates, with the crates of the output
+    /// directory patched in, and report compiler errors by interface file
+    #[arg(long, conflicts_with_all = ["dry_run", "watch"])]
+    validate: bool,
+
+    /// Also write a Cargo.toml at the root of the output directory making every
+    /// crate in it a member of one workspace
+    #[arg(long, conflicts_with = "dry_run")]
+    workspace: bool,
 
     /// Direct path to package share directory (bypasses ament index)
     #[arg(long)]
     package_path: Option<PathBuf>,
 
+    /// Also generate every package the requested ones depend on, directly or
+    /// not, as found in the ament index
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Also emit ROS 1 md5sums and definitions (for ros1_bridge)
+    #[arg(long)]
+    ros1_compat: bool,
+
+    /// Also emit a .proto mapping and From conversions to prost types behind a
+    /// `protobuf` feature
+    #[arg(long)]
+    protobuf: bool,
+
+    /// Also emit PyO3 classes for messages behind a `python` feature
+    #[arg(long)]
+    python: bool,
+
+    /// Also emit a cxx bridge exposing messages to C++ behind a `cxx` feature
+    #[arg(long)]
+    cxx: bool,
+
+    /// Generate against upstream rosidl_runtime_rs for direct use with rclrs
+    #[arg(long)]
+    rclrs: bool,
+
+    /// Generate only pure-Rust message types (serde + CDR), without the FFI layer,
+    /// services and actions; the crate builds for wasm32-unknown-unknown
+    #[arg(long, conflicts_with = "rclrs")]
+    types_only: bool,
+
+    /// Generate no_std message types with fixed-capacity strings and sequences
+    /// (micro-ROS / embedded targets); serde is behind the `serde` feature
+    #[arg(long, conflicts_with_all = ["rclrs", "types_only", "protobuf", "python", "cxx"])]
+    embedded: bool,
+
+    /// Capacity in bytes of unbounded strings with --embedded
+    #[arg(long, requires = "embedded", default_value_t = rosidl_codegen::EmbeddedCapacity::default().string)]
+    embedded_string_capacity: usize,
+
+    /// Capacity of unbounded sequences with --embedded
+    #[arg(long, requires = "embedded", default_value_t = rosidl_codegen::EmbeddedCapacity::default().sequence)]
+    embedded_sequence_capacity: usize,
+
+    /// Also bind the introspection type support of each message, for runtime
+    /// reflection through rosidl_runtime_rs::introspection
+    #[arg(long, conflicts_with_all = ["rclrs", "types_only", "embedded"])]
+    introspection: bool,
+
+    /// Also alias the associated constants of the generated structs as
+    /// module-level constants, as in bindings of earlier versions
+    #[arg(long)]
+    module_constants: bool,
+
+    /// Gate each message, service and action behind a cargo feature of its own
+    /// (`msg_point`, `srv_add_two_ints`, ...), all enabled by a default `full` feature
+    #[arg(long, conflicts_with_all = ["protobuf", "python", "cxx"])]
+    interface_features: bool,
+
+    /// Name keyword fields with raw identifiers (`r#type`) instead of appending
+    /// an underscore (`type_`)
+    #[arg(long, conflicts_with_all = ["protobuf", "python", "cxx"])]
+    raw_identifiers: bool,
+
+    /// Skip the service event messages even when service_msgs is installed
+    #[arg(long)]
+    no_service_events: bool,
+
+    /// Module layout of the generated crate
+    #[arg(long, value_enum, default_value_t)]
+    layout: generator::Layout,
+
+    /// How the generated crate refers to the crates of its dependencies:
+    /// `path = "../<dep>"` dependencies, or plain version requirements
+    #[arg(long, value_enum, default_value_t)]
+    dependencies: generator::DependencyStyle,
+
+    /// TOML file with extra derives and attributes of the generated structs, and
+    /// whether to generate enums for groups of constants
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
 }
 
+/// Output of a bindgen run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum Emit {
+    /// Generated binding crate in the output directory
+    #[default]
+    Bindings,
+    /// Parsed interfaces as JSON on stdout, without generating code
+    Ast,
+}
+
+/// Report of a bindgen run on stdout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    /// One line per generated package
+    #[default]
+    Text,
+    /// A `{"packages": [...]}` object with the package, output directory,
+    /// interfaces, dependencies and warnings of each generated package, the
+    /// `changes` of a dry run and the compiler `errors` of a validation
+    Json,
+}
+
+/// Generation settings of a `--config` file
+///
+/// ```toml
+/// derives = ["Eq", "Hash"]
+/// attributes = ["#[non_exhaustive]"]
+/// constant_enums = true
+/// crate_prefix = "ros2_"
+/// serde_rename_all = "camelCase"
+/// rclrs_runtime_version = "0.4"
+///
+/// [type_renames]
+/// "std_msgs/msg/String" = "StringMsg"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /// Extra derives, applied to each idiomatic struct whose fields support them
+    derives: Vec<String>,
+    /// Extra attributes of the idiomatic structs
+    attributes: Vec<String>,
+    /// Enums for groups of integer constants sharing a name prefix
+    constant_enums: bool,
+    /// Prefix of the crate names of the generated crates
+    crate_prefix: String,
+    /// Crate names of single packages, instead of the prefixed package name
+    crate_names: BTreeMap<String, String>,
+    /// Names the types of interfaces (`<package>/<kind>/<Name>`) are exported under
+    type_renames: BTreeMap<String, String>,
+    /// serde `rename_all` rule of the fields of the idiomatic structs
+    serde_rename_all: Option<String>,
+    /// Version requirement of the upstream rosidl_runtime_rs with --rclrs
+    rclrs_runtime_version: Option<String>,
+}
+
+impl Config {
+    fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let config: Self =
+            toml::from_str(&text).wrap_err_with(|| format!("Invalid config {}", path.display()))?;
+        config
+            .naming()
+            .validate()
+            .map_err(|err| eyre!("Invalid config {}: {}", path.display(), err))?;
+        Ok(config)
+    }
+
+    fn naming(&self) -> rosidl_codegen::Naming {
+        rosidl_codegen::Naming {
+            crate_prefix: self.crate_prefix.clone(),
+            crate_names: self.crate_names.clone(),
+            type_renames: self.type_renames.clone(),
+            serde_rename_all: self.serde_rename_all.clone(),
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
     if args.verbose {
         eprintln!("cargo-ros2-bindgen starting...");
-        eprintln!("  Package: {}", args.package);
-        eprintln!("  Output: {}", args.output.display());
+        eprintln!("  Package: {}", args.package.join(", "));
+        if let Some(output) = &args.output {
+            eprintln!("  Output: {}", output.display());
+        }
+    }
+    if args.package.len() > 1 && (args.package_path.is_some() || args.emit == Emit::Ast) {
+        return Err(eyre!(
+            "--package-path and --emit ast take a single --package"
+        ));
+    }
+    if (args.recursive || args.watch || args.validate || args.workspace) && args.emit == Emit::Ast {
+        return Err(eyre!(
+            "--recursive, --watch, --validate and --workspace generate bindings, not --emit ast"
+        ));
     }
 
-    // Get the package
-    let package = if let Some(package_path) = args.package_path {
+    // Get the packages (keeping the index to resolve dependency versions and
+    // cross-package ROS 1 md5sums)
+    let (packages, index) = if let Some(package_path) = &args.package_path {
         // Direct path mode
         if args.verbose {
             eprintln!("  Using direct path: {}", package_path.display());
         }
-        ament::Package::from_share_dir(package_path)
-            .wrap_err("Failed to load package from direct path")?
+        let package = ament::Package::from_share_dir(package_path.clone())
+            .wrap_err("Failed to load package from direct path")?;
+        (vec![package], ament::AmentIndex::from_env().ok())
     } else {
         // Ament index mode
         if args.verbose {
@@ -55,41 +272,381 @@ fn main() -> Result<()> {
             eprintln!("  Found {} packages in ament index", index.package_count());
         }
 
-        index
-            .find_package(&args.package)
-            .ok_or_else(|| eyre!("Package '{}' not found in ament index", args.package))?
-            .clone()
+        let packages = args
+            .package
+            .iter()
+            .map(|name| {
+                index
+                    .find_package(name)
+                    .cloned()
+                    .ok_or_else(|| eyre!("Package '{}' not found in ament index", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        (packages, Some(index))
     };
 
     if args.verbose {
-        eprintln!("  Package share dir: {}", package.share_dir.display());
-        eprintln!("  Messages: {}", package.interfaces.messages.len());
-        eprintln!("  Services: {}", package.interfaces.services.len());
-        eprintln!("  Actions: {}", package.interfaces.actions.len());
+        for package in &packages {
+            eprintln!("  Package share dir: {}", package.share_dir.display());
+            eprintln!("  Messages: {}", package.interfaces.messages.len());
+            eprintln!("  Services: {}", package.interfaces.services.len());
+            eprintln!("  Actions: {}", package.interfaces.actions.len());
+        }
+    }
+
+    if args.emit == Emit::Ast {
+        let ast = generator::package_ast(&packages[0]).wrap_err("Failed to parse interfaces")?;
+        println!("{}", serde_json::to_string_pretty(&ast)?);
+        return Ok(());
     }
+    let output = args
+        .output
+        .clone()
+        .ok_or_else(|| eyre!("--output is required unless --emit ast is given"))?;
 
     // Generate bindings
     if args.verbose {
         eprintln!("Generating Rust bindings...");
     }
 
-    let generated = generator::generate_package(&package, &args.output)
-        .wrap_err("Failed to generate package")?;
+    // Service event messages (Iron+) need service_msgs to be installed
+    let has_service_msgs = index.as_ref().is_some_and(|index| {
+        index
+            .find_package(rosidl_codegen::SERVICE_MSGS_PACKAGE)
+            .is_some()
+    });
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let options = rosidl_codegen::GeneratorOptions {
+        rclrs_compat: args.rclrs,
+        rclrs_runtime_version: config.rclrs_runtime_version.clone(),
+        service_events: has_service_msgs
+            && !args.no_service_events
+            && !args.types_only
+            && !args.embedded,
+        types_only: args.types_only,
+        embedded: args.embedded.then_some(rosidl_codegen::EmbeddedCapacity {
+            string: args.embedded_string_capacity,
+            sequence: args.embedded_sequence_capacity,
+        }),
+        naming: config.naming(),
+        derives: config.derives,
+        attributes: config.attributes,
+        constant_enums: config.constant_enums,
+        introspection: args.introspection,
+        module_constants: args.module_constants,
+        // Found by generate_package among the parsed messages
+        cyclic_fields: Default::default(),
+        interface_features: args.interface_features,
+        raw_identifiers: args.raw_identifiers,
+    };
 
-    if args.verbose {
-        eprintln!("Generation complete!");
-        eprintln!("  Output directory: {}", generated.output_dir.display());
-        eprintln!("  Messages generated: {}", generated.message_count);
-        eprintln!("  Services generated: {}", generated.service_count);
-        eprintln!("  Actions generated: {}", generated.action_count);
+    let packages = if args.recursive {
+        let index = index.as_ref().ok_or_else(|| {
+            eyre!("--recursive resolves dependencies in the ament index, set AMENT_PREFIX_PATH")
+        })?;
+        let closure = generator::dependency_closure(packages, index, &options)
+            .wrap_err("Failed to resolve dependencies")?;
+        if args.verbose {
+            let names: Vec<_> = closure.iter().map(|p| p.name.as_str()).collect();
+            eprintln!("  With dependencies: {}", names.join(", "));
+        }
+        closure
     } else {
-        // Minimal output for non-verbose mode
-        println!(
-            "Generated bindings for '{}' to {}",
-            generated.name,
-            generated.output_dir.display()
-        );
+        packages
+    };
+
+    if args.watch {
+        return watch(&args, packages, index.as_ref(), &options, &output);
     }
 
+    // A dry run renders the files in memory, compared with the output
+    // directory afterwards
+    let target = if args.dry_run {
+        Output::in_memory(&output)
+    } else {
+        Output::disk(&output)
+    };
+    // Packages are generated in parallel; their reports are printed in the
+    // order the packages were given, and the first failing one is the error
+    let reports: Vec<Result<Report>> = packages
+        .par_iter()
+        .map(|package| generate(package, &args, &target, options.clone(), index.as_ref()))
+        .collect();
+    let reports = reports.into_iter().collect::<Result<Vec<_>>>()?;
+
+    let changes = if args.dry_run {
+        Some(dry_run_changes(&args, &target.into_files(), &output)?)
+    } else {
+        None
+    };
+    if args.workspace {
+        write_workspace(&args, &output)?;
+    }
+    // Every package is generated first, as the crates depend on each other
+    let errors = if args.validate {
+        if args.verbose {
+            eprintln!("Checking generated crates...");
+        }
+        Some(validate::check(&output, &packages).wrap_err("Failed to validate bindings")?)
+    } else {
+        None
+    };
+    print_reports(&args, reports, changes, errors.as_deref(), &output)?;
+
+    let failed = errors.as_ref().map_or(0, Vec::len);
+    if failed > 0 {
+        return Err(eyre!(
+            "{} compilation error(s) in the generated bindings",
+            failed
+        ));
+    }
+    Ok(())
+}
+
+/// Print the reports of the generated packages, the changes of a dry run and
+/// the compiler errors of a validation
+fn print_reports(
+    args: &Args,
+    reports: Vec<Report>,
+    changes: Option<Vec<dry_run::FileChange>>,
+    errors: Option<&[validate::CompileError]>,
+    output: &Path,
+) -> Result<()> {
+    if args.format == Format::Json {
+        let json = JsonReport {
+            packages: &reports,
+            changes: changes.as_deref(),
+            errors,
+        };
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+    for report in reports {
+        for warning in &report.warnings {
+            eprintln!("  {}", warning);
+        }
+
+        let generated = report.generated;
+        if args.verbose {
+            eprintln!("Generation complete!");
+            eprintln!("  Output directory: {}", generated.output_dir.display());
+            eprintln!("  Messages generated: {}", generated.message_count);
+            eprintln!("  Services generated: {}", generated.service_count);
+            eprintln!("  Actions generated: {}", generated.action_count);
+        } else if !args.dry_run {
+            // Minimal output for non-verbose mode
+            println!(
+                "Generated bindings for '{}' to {}",
+                generated.name,
+                generated.output_dir.display()
+            );
+        }
+    }
+
+    if let Some(changes) = changes {
+        if changes.is_empty() {
+            println!("Bindings in {} are up to date", output.display());
+        }
+        print!("{}", dry_run::summary(&changes));
+        for change in &changes {
+            if let Some(diff) = &change.diff {
+                print!("{}", diff);
+            }
+        }
+    }
+
+    match errors {
+        Some([]) => println!("Bindings in {} compile", output.display()),
+        Some(errors) => {
+            for error in errors {
+                eprintln!("Compilation error in {}:", error.location());
+                eprintln!("{}\n", error.message);
+            }
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Generate the packages, then regenerate the ones whose interface files change
+/// and the packages depending on them, until interrupted
+///
+/// Failures are reported and the watch goes on, so fixing the interface file
+/// is enough to regenerate.
+fn watch(
+    args: &Args,
+    mut packages: Vec<ament::Package>,
+    index: Option<&ament::AmentIndex>,
+    options: &rosidl_codegen::GeneratorOptions,
+    output: &Path,
+) -> Result<()> {
+    let mut snapshots: Vec<_> = packages
+        .iter()
+        .map(|package| watch::Snapshot::take(&package.share_dir))
+        .collect();
+    let mut changed: BTreeSet<String> = packages.iter().map(|p| p.name.clone()).collect();
+    loop {
+        if !changed.is_empty() {
+            let affected = watch::affected(&packages, &changed, |package| {
+                // A package that fails to parse is reported by its generation
+                generator::package_dependencies(package, options, index).unwrap_or_default()
+            });
+            let target = Output::disk(output);
+            let reports: Vec<Result<Report>> = affected
+                .par_iter()
+                .map(|package| generate(package, args, &target, options.clone(), index))
+                .collect();
+            // Regenerated crates are standalone again until the workspace is
+            // rewritten
+            let reports = reports.into_iter().collect::<Result<Vec<_>>>();
+            match reports.and_then(|reports| {
+                if args.workspace {
+                    write_workspace(args, output)?;
+                }
+                Ok(reports)
+            }) {
+                Ok(reports) => print_reports(args, reports, None, None, output)?,
+                Err(err) => eprintln!("Error: {:#}", err),
+            }
+            eprintln!(
+                "Watching {} package(s) for interface changes...",
+                packages.len()
+            );
+        }
+
+        std::thread::sleep(watch::POLL_INTERVAL);
+        changed.clear();
+        for (package, snapshot) in packages.iter_mut().zip(&mut snapshots) {
+            let current = watch::Snapshot::take(&package.share_dir);
+            if current == *snapshot {
+                continue;
+            }
+            *snapshot = current;
+            // Interfaces may have been added or removed
+            match ament::Package::from_share_dir(package.share_dir.clone()) {
+                Ok(reloaded) => *package = reloaded,
+                Err(err) => {
+                    eprintln!("Error: {:#}", err);
+                    continue;
+                }
+            }
+            changed.insert(package.name.clone());
+        }
+    }
+}
+
+/// Make the crates of the output directory members of one workspace
+fn write_workspace(args: &Args, output: &Path) -> Result<()> {
+    let workspace =
+        workspace::write_workspace(output).wrap_err("Failed to write the workspace manifest")?;
+    if args.verbose {
+        eprintln!(
+            "  Workspace: {} ({} members)",
+            output.join("Cargo.toml").display(),
+            workspace.members.len()
+        );
+        for excluded in &workspace.excluded {
+            eprintln!("  Excluded from the workspace: {}", excluded);
+        }
+    }
     Ok(())
 }
+
+/// Files of the output directory a dry run would change, with their diffs if
+/// asked for
+fn dry_run_changes(
+    args: &Args,
+    rendered: &BTreeMap<PathBuf, String>,
+    output: &Path,
+) -> Result<Vec<dry_run::FileChange>> {
+    let mut changes = dry_run::compare(rendered, output)?;
+    if args.diff {
+        for change in &mut changes {
+            change.diff = Some(dry_run::unified_diff(rendered, output, change)?);
+        }
+    }
+    Ok(changes)
+}
+
+/// Outcome of generating the bindings of one package
+#[derive(Serialize)]
+struct Report {
+    #[serde(flatten)]
+    generated: generator::GeneratedRustPackage,
+    /// Messages without a type hash and fields that differ in ROS 1
+    warnings: Vec<String>,
+}
+
+/// Report printed by `--format json`
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    packages: &'a [Report],
+    /// Files a dry run would change
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changes: Option<&'a [dry_run::FileChange]>,
+    /// Compiler errors of a validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<&'a [validate::CompileError]>,
+}
+
+/// Generate the bindings of a package and the extras requested by `args`
+fn generate(
+    package: &ament::Package,
+    args: &Args,
+    output: &Output,
+    options: rosidl_codegen::GeneratorOptions,
+    index: Option<&ament::AmentIndex>,
+) -> Result<Report> {
+    let generated = generator::generate_package(
+        package,
+        output,
+        options,
+        args.layout,
+        args.dependencies,
+        index,
+    )
+    .wrap_err_with(|| format!("Failed to generate package {}", package.name))?;
+
+    let mut warnings = Vec::new();
+    let skipped = generator::generate_type_descriptions(package, index, output, args.rclrs)
+        .wrap_err("Failed to generate type descriptions")?;
+    for (msg_name, reason) in &skipped {
+        warnings.push(format!("No type hash for {}: {}", msg_name, reason));
+    }
+
+    if args.ros1_compat {
+        let notes = generator::generate_ros1_metadata(package, index, output)
+            .wrap_err("Failed to generate ROS 1 metadata")?;
+        if !notes.is_empty() {
+            warnings.push(format!(
+                "{} field(s) differ in ROS 1, see {}",
+                notes.len(),
+                generated.output_dir.join("ros1_type_mapping.txt").display()
+            ));
+        }
+    }
+
+    if args.protobuf {
+        generator::generate_protobuf_mapping(package, output)
+            .wrap_err("Failed to generate Protobuf mapping")?;
+    }
+
+    if args.python {
+        generator::generate_python_bindings(package, output)
+            .wrap_err("Failed to generate Python bindings")?;
+    }
+
+    if args.cxx {
+        generator::generate_cxx_bindings(package, output)
+            .wrap_err("Failed to generate cxx bridge")?;
+    }
+
+    Ok(Report {
+        generated,
+        warnings,
+    })
+}
@@ -0,0 +1,169 @@
+//! Destination of the generated files
+//!
+//! The generator writes every file through an [`Output`]: to the output
+//! directory itself, or to memory for `--dry-run`, which compares the rendered
+//! files with the output directory without touching it. The extras (type
+//! descriptions, ROS 1 metadata, `--protobuf`/`--python`/`--cxx`) read back and
+//! patch the `Cargo.toml`, `build.rs` and `lib.rs` written before them, so
+//! reads go through it as well.
+
+use eyre::{eyre, Result, WrapErr};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Output directory of the generated crates, on disk or rendered in memory
+#[derive(Debug)]
+pub struct Output {
+    dir: PathBuf,
+    /// Rendered files by their path relative to `dir`; `None` writes to disk
+    memory: Option<Mutex<BTreeMap<PathBuf, String>>>,
+}
+
+impl Output {
+    /// Write the generated files to `dir`
+    pub fn disk(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            memory: None,
+        }
+    }
+
+    /// Keep the generated files in memory, standing in for `dir`, which is only
+    /// read to tell whether the runtime crate is there
+    pub fn in_memory(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            memory: Some(Mutex::default()),
+        }
+    }
+
+    /// The output directory, which generated paths are joined to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write `content` to `path`, creating its parent directories on disk
+    pub fn write(&self, path: &Path, content: impl Into<String>) -> Result<()> {
+        let content = content.into();
+        match &self.memory {
+            Some(memory) => {
+                let relative = self.relative(path)?;
+                lock(memory).insert(relative, content);
+            }
+            None => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).wrap_err_with(|| {
+                        format!("Failed to create directory {}", parent.display())
+                    })?;
+                }
+                std::fs::write(path, content)
+                    .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy the file `from` outside the output directory to `to`
+    pub fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        if self.memory.is_some() {
+            let content = std::fs::read_to_string(from)
+                .wrap_err_with(|| format!("Failed to read {}", from.display()))?;
+            return self.write(to, content);
+        }
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        std::fs::copy(from, to)
+            .wrap_err_with(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+        Ok(())
+    }
+
+    /// Content of a file written before
+    pub fn read(&self, path: &Path) -> Result<String> {
+        match &self.memory {
+            Some(memory) => lock(memory)
+                .get(&self.relative(path)?)
+                .cloned()
+                .ok_or_else(|| eyre!("Failed to read {}: not generated", path.display())),
+            None => std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Whether the directory `path` is in the output directory or has files
+    /// rendered under it
+    pub fn dir_exists(&self, path: &Path) -> bool {
+        if path.exists() {
+            return true;
+        }
+        let (Some(memory), Ok(relative)) = (&self.memory, self.relative(path)) else {
+            return false;
+        };
+        lock(memory).keys().any(|file| file.starts_with(&relative))
+    }
+
+    /// Files rendered in memory by their path relative to the output directory;
+    /// empty for an output on disk
+    pub fn into_files(self) -> BTreeMap<PathBuf, String> {
+        self.memory
+            .map(|memory| memory.into_inner().unwrap_or_else(|p| p.into_inner()))
+            .unwrap_or_default()
+    }
+
+    fn relative(&self, path: &Path) -> Result<PathBuf> {
+        path.strip_prefix(&self.dir)
+            .map(Path::to_path_buf)
+            .map_err(|_| eyre!("{} is outside {}", path.display(), self.dir.display()))
+    }
+}
+
+/// Packages are generated in parallel; a panicking one leaves complete files
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output = Output::in_memory(temp_dir.path());
+        let lib_rs = temp_dir.path().join("test_msgs/src/lib.rs");
+        output.write(&lib_rs, "pub mod msg;\n").unwrap();
+        assert_eq!(output.read(&lib_rs).unwrap(), "pub mod msg;\n");
+        assert!(output.dir_exists(&temp_dir.path().join("test_msgs")));
+        assert!(!output.dir_exists(&temp_dir.path().join("other_msgs")));
+        assert!(output
+            .read(&temp_dir.path().join("test_msgs/build.rs"))
+            .is_err());
+        assert!(output.write(Path::new("/elsewhere/lib.rs"), "").is_err());
+
+        // Nothing reaches the disk
+        assert!(!lib_rs.exists());
+        assert_eq!(
+            output.into_files(),
+            BTreeMap::from([(
+                PathBuf::from("test_msgs/src/lib.rs"),
+                "pub mod msg;\n".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_disk() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output = Output::disk(temp_dir.path());
+        let lib_rs = temp_dir.path().join("test_msgs/src/lib.rs");
+        output.write(&lib_rs, "pub mod msg;\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&lib_rs).unwrap(), "pub mod msg;\n");
+        assert_eq!(output.read(&lib_rs).unwrap(), "pub mod msg;\n");
+        assert!(output.dir_exists(&temp_dir.path().join("test_msgs")));
+        assert!(output.into_files().is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+//! Compilation check of generated crates for `--validate`
+//!
+//! Each generated crate is checked with `cargo check`, the crates of the output
+//! directory patching the crates.io ones as `cargo ros2 build` patches a
+//! project, and the compiler errors are attributed to the interface files the
+//! failing sources were generated from.
+
+use crate::ament::Package;
+use eyre::{eyre, Result, WrapErr};
+use rosidl_codegen::utils::to_snake_case;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A compiler error in a generated crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompileError {
+    /// Crate directory the error is in, relative to the output directory
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// Source file of the error, relative to the crate directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// Interface file the source was generated from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<PathBuf>,
+    /// Error as rendered by rustc
+    pub message: String,
+}
+
+impl CompileError {
+    /// `<interface> (<crate>/<source>)` or `<crate>/<source>`
+    pub fn location(&self) -> String {
+        let source = match &self.source {
+            Some(source) => Path::new(&self.krate).join(source),
+            None => PathBuf::from(&self.krate),
+        };
+        match &self.interface {
+            Some(interface) => format!("{} ({})", interface.display(), source.display()),
+            None => source.display().to_string(),
+        }
+    }
+}
+
+/// Check the crates of `packages` in `output`, in order
+///
+/// An error of a crate another one depends on is reported once. Build output
+/// goes to the `target` directory of `output`.
+pub fn check(output: &Path, packages: &[Package]) -> Result<Vec<CompileError>> {
+    let output = output
+        .canonicalize()
+        .wrap_err_with(|| format!("Failed to read {}", output.display()))?;
+    let patches = patches(&output)?;
+    let mut errors = Vec::new();
+    for package in packages {
+        let manifest = output.join(&package.name).join("Cargo.toml");
+        let result = Command::new("cargo")
+            .arg("check")
+            .arg("--message-format=json")
+            .arg("--manifest-path")
+            .arg(&manifest)
+            .arg("--target-dir")
+            .arg(output.join("target"))
+            .args(&patches)
+            .output()
+            .wrap_err("Failed to execute cargo check")?;
+        let found = compile_errors(&String::from_utf8_lossy(&result.stdout), packages);
+        if !result.status.success() && found.is_empty() {
+            // Failed before compiling, such as when resolving dependencies
+            return Err(eyre!(
+                "cargo check failed for {}:\n{}",
+                manifest.display(),
+                String::from_utf8_lossy(&result.stderr).trim_end()
+            ));
+        }
+        for error in found {
+            if !errors.contains(&error) {
+                errors.push(error);
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// `--config` arguments patching the crates.io crate of each crate directory
+/// in `output` with it
+fn patches(output: &Path) -> Result<Vec<String>> {
+    let mut dirs: Vec<_> = std::fs::read_dir(output)
+        .wrap_err_with(|| format!("Failed to read {}", output.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    let mut patches = Vec::new();
+    for dir in dirs {
+        let manifest = dir.join("Cargo.toml");
+        let Ok(text) = std::fs::read_to_string(&manifest) else {
+            continue;
+        };
+        let manifest: toml::Table = toml::from_str(&text)
+            .wrap_err_with(|| format!("Invalid manifest {}", manifest.display()))?;
+        let Some(name) = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(|name| name.as_str())
+        else {
+            continue;
+        };
+        let path = toml::Value::String(dir.to_string_lossy().into_owned());
+        patches.push("--config".to_string());
+        patches.push(format!("patch.crates-io.{}.path={}", name, path));
+    }
+    Ok(patches)
+}
+
+/// Errors among the `--message-format=json` messages of cargo
+fn compile_errors(messages: &str, packages: &[Package]) -> Vec<CompileError> {
+    let mut errors = Vec::new();
+    for line in messages.lines() {
+        let Ok(message) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if message["reason"] != "compiler-message" || message["message"]["level"] != "error" {
+            continue;
+        }
+        let Some(rendered) = message["message"]["rendered"].as_str() else {
+            continue;
+        };
+        // The summary closing the errors of a crate
+        if rendered.starts_with("error: aborting due to") {
+            continue;
+        }
+        let crate_dir = message["manifest_path"]
+            .as_str()
+            .and_then(|manifest| Path::new(manifest).parent())
+            .unwrap_or(Path::new(""));
+        let krate = crate_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let source = message["message"]["spans"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|span| span["is_primary"] == true)
+            .and_then(|span| span["file_name"].as_str())
+            .map(|file| {
+                let file = crate_dir.join(file);
+                file.strip_prefix(crate_dir)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(file)
+            });
+        let interface = source.as_deref().and_then(|source| {
+            let package = packages.iter().find(|package| package.name == krate)?;
+            interface_file(package, source)
+        });
+        errors.push(CompileError {
+            krate,
+            source,
+            interface,
+            message: rendered.trim_end().to_string(),
+        });
+    }
+    errors
+}
+
+/// Interface file a source of the generated crate of `package` was generated
+/// from: `src/<kind>/<name>_idiomatic.rs` or `src/ffi/<kind>/<name>_rmw.rs`
+fn interface_file(package: &Package, source: &Path) -> Option<PathBuf> {
+    let parts: Vec<_> = source
+        .iter()
+        .map(|part| part.to_str())
+        .collect::<Option<_>>()?;
+    let (kind, module) = match parts.as_slice() {
+        ["src", kind, file] => (*kind, file.strip_suffix("_idiomatic.rs")?),
+        ["src", "ffi", kind, file] => (*kind, file.strip_suffix("_rmw.rs")?),
+        _ => return None,
+    };
+    let names = match kind {
+        "msg" => &package.interfaces.messages,
+        "srv" => &package.interfaces.services,
+        "action" => &package.interfaces.actions,
+        _ => return None,
+    };
+    let name = names.iter().find(|name| to_snake_case(name) == module)?;
+    Some(match kind {
+        "msg" => package.get_message_path(name),
+        "srv" => package.get_service_path(name),
+        _ => package.get_action_path(name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_compile_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share_dir = temp_dir.path().join("test_msgs");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::create_dir_all(share_dir.join("srv")).unwrap();
+        fs::write(share_dir.join("msg/GeoPoint.msg"), "float64 x\n").unwrap();
+        fs::write(share_dir.join("srv/AddTwoInts.srv"), "---\n").unwrap();
+        let packages = [Package::from_share_dir(share_dir.clone()).unwrap()];
+
+        let message = |file: &str, rendered: &str| {
+            serde_json::json!({
+                "reason": "compiler-message",
+                "manifest_path": "/out/test_msgs/Cargo.toml",
+                "message": {
+                    "level": "error",
+                    "rendered": rendered,
+                    "spans": [
+                        { "file_name": "src/lib.rs", "is_primary": false },
+                        { "file_name": file, "is_primary": true },
+                    ],
+                },
+            })
+            .to_string()
+        };
+        let messages = [
+            message("src/msg/geo_point_idiomatic.rs", "error[E0412]: a\n"),
+            message("src/ffi/srv/add_two_ints_rmw.rs", "error[E0412]: b\n"),
+            message("src/lib.rs", "error[E0432]: c\n"),
+            message("src/lib.rs", "error: aborting due to 3 previous errors\n"),
+            r#"{"reason":"build-finished","success":false}"#.to_string(),
+        ]
+        .join("\n");
+
+        let errors = compile_errors(&messages, &packages);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].message, "error[E0412]: a");
+        assert_eq!(
+            errors[0].interface.as_deref(),
+            Some(share_dir.join("msg/GeoPoint.msg").as_path())
+        );
+        assert_eq!(
+            errors[1].interface.as_deref(),
+            Some(share_dir.join("srv/AddTwoInts.srv").as_path())
+        );
+        assert_eq!(errors[2].interface, None);
+        assert_eq!(errors[2].location(), "test_msgs/src/lib.rs");
+
+        // Sources of crates outside the run are not attributed
+        assert_eq!(compile_errors(&messages, &[])[0].interface, None);
+    }
+}
@@ -0,0 +1,147 @@
+//! Change detection of interface packages for `--watch`
+//!
+//! Packages are polled: each round takes a [`Snapshot`] of the interface files
+//! and package.xml of every watched package, and the packages whose snapshot
+//! changed are regenerated along with the watched packages depending on them,
+//! whose type hashes cover the changed definitions.
+
+use crate::ament::Package;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Time between two polls of the watched packages
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Interface directories of a package and the extensions of the files in them
+const INTERFACE_DIRS: &[(&str, &str)] = &[("msg", "msg"), ("srv", "srv"), ("action", "action")];
+
+/// Modification time and size of the files a package is generated from
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Snapshot(BTreeMap<PathBuf, (Option<SystemTime>, u64)>);
+
+impl Snapshot {
+    /// Interface files (`.msg`/`.srv`/`.action` and `.idl`) and package.xml of
+    /// the package in `share_dir`; unreadable files are left out
+    pub fn take(share_dir: &Path) -> Self {
+        let mut files = BTreeMap::new();
+        let mut add = |path: PathBuf| {
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                files.insert(path, (metadata.modified().ok(), metadata.len()));
+            }
+        };
+        add(share_dir.join("package.xml"));
+        for (dir, extension) in INTERFACE_DIRS {
+            let Ok(entries) = std::fs::read_dir(share_dir.join(dir)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path
+                    .extension()
+                    .is_some_and(|ext| ext == *extension || ext == "idl")
+                {
+                    add(path);
+                }
+            }
+        }
+        Snapshot(files)
+    }
+}
+
+/// Packages to regenerate after `changed` changed: those and the packages
+/// depending on them through `dependencies`, directly or not, in the order of
+/// `packages`
+pub fn affected<'a>(
+    packages: &'a [Package],
+    changed: &BTreeSet<String>,
+    dependencies: impl Fn(&Package) -> BTreeSet<String>,
+) -> Vec<&'a Package> {
+    let dependencies: Vec<_> = packages.iter().map(dependencies).collect();
+    let mut affected = changed.clone();
+    loop {
+        let dependents: Vec<_> = packages
+            .iter()
+            .zip(&dependencies)
+            .filter(|(package, deps)| {
+                !affected.contains(&package.name) && deps.iter().any(|dep| affected.contains(dep))
+            })
+            .map(|(package, _)| package.name.clone())
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        affected.extend(dependents);
+    }
+    packages
+        .iter()
+        .filter(|package| affected.contains(&package.name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_snapshot() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let share_dir = temp_dir.path().join("test_msgs");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\n").unwrap();
+        fs::write(share_dir.join("msg/README.md"), "docs\n").unwrap();
+        let before = Snapshot::take(&share_dir);
+        assert_eq!(before.0.len(), 1);
+
+        // Other files do not count
+        fs::write(share_dir.join("msg/README.md"), "more docs\n").unwrap();
+        assert_eq!(Snapshot::take(&share_dir), before);
+
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\nfloat64 y\n").unwrap();
+        let edited = Snapshot::take(&share_dir);
+        assert_ne!(edited, before);
+
+        fs::create_dir_all(share_dir.join("srv")).unwrap();
+        fs::write(share_dir.join("srv/Reset.srv"), "---\n").unwrap();
+        fs::write(share_dir.join("package.xml"), "<package/>").unwrap();
+        let added = Snapshot::take(&share_dir);
+        assert_eq!(added.0.len(), 3);
+        assert_ne!(added, edited);
+    }
+
+    #[test]
+    fn test_affected() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let packages: Vec<_> = ["nav_msgs", "geometry_msgs", "std_msgs", "tf2_msgs"]
+            .iter()
+            .map(|name| Package::from_share_dir(temp_dir.path().join(name)).unwrap())
+            .collect();
+        let dependencies = |package: &Package| -> BTreeSet<String> {
+            let deps: &[&str] = match package.name.as_str() {
+                "nav_msgs" => &["geometry_msgs", "std_msgs"],
+                "geometry_msgs" => &["std_msgs"],
+                "tf2_msgs" => &["geometry_msgs"],
+                _ => &[],
+            };
+            deps.iter().map(|dep| dep.to_string()).collect()
+        };
+        let names = |changed: &[&str]| -> Vec<String> {
+            let changed = changed.iter().map(|name| name.to_string()).collect();
+            affected(&packages, &changed, dependencies)
+                .into_iter()
+                .map(|package| package.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(&["nav_msgs"]), ["nav_msgs"]);
+        assert_eq!(
+            names(&["std_msgs"]),
+            ["nav_msgs", "geometry_msgs", "std_msgs", "tf2_msgs"]
+        );
+        assert_eq!(
+            names(&["geometry_msgs"]),
+            ["nav_msgs", "geometry_msgs", "tf2_msgs"]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Workspace manifest of an output directory
+//!
+//! Generated crates are standalone packages, with a `[workspace]` table of their
+//! own, as they usually live under `target/` of a project that may be a
+//! workspace itself. A `Cargo.toml` at the root of the output directory makes
+//! them the members of one workspace instead, so the whole set of bindings can
+//! be checked, documented and vendored as a unit.
+
+use crate::generator::RUNTIME_CRATE;
+use eyre::{eyre, Result, WrapErr};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Table of a generated crate making it a workspace of its own
+pub const STANDALONE_TABLE: &str =
+    "# Standalone package (not part of parent workspace)\n[workspace]\n\n";
+
+/// First line of the workspace manifest, telling it from a manifest of a project
+const HEADER: &str = "# Workspace of the generated ROS 2 bindings, written by cargo-ros2-bindgen";
+
+/// Crate directories of a workspace manifest
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Workspace {
+    /// Crate directories in the workspace
+    pub members: Vec<String>,
+    /// Crate directories with a `[workspace]` table other than the generated one,
+    /// or a path dependency outside the output directory
+    pub excluded: Vec<String>,
+    /// Directories of the members the others depend on by version, by crate name
+    pub patches: BTreeMap<String, String>,
+    /// Members whose standalone table was just removed, changing their manifest
+    pub joined: Vec<String>,
+}
+
+impl Workspace {
+    /// Text of the `Cargo.toml` at the root of the output directory
+    pub fn manifest(&self) -> String {
+        let list = |dirs: &[String]| -> String {
+            dirs.iter()
+                .map(|dir| format!("    \"{}\",\n", dir))
+                .collect()
+        };
+        let mut manifest = format!(
+            "{}\n[workspace]\nresolver = \"2\"\nmembers = [\n{}]\n",
+            HEADER,
+            list(&self.members)
+        );
+        if !self.excluded.is_empty() {
+            manifest.push_str(&format!("exclude = [\n{}]\n", list(&self.excluded)));
+        }
+        if !self.patches.is_empty() {
+            // Crates generated with `--dependencies version` resolve each other
+            // here, as cargo-ros2 patches them into a project
+            manifest.push_str("\n[patch.crates-io]\n");
+            for (name, dir) in &self.patches {
+                manifest.push_str(&format!("{} = {{ path = \"{}\" }}\n", name, dir));
+            }
+        }
+        manifest
+    }
+}
+
+/// Make the crates in `output_dir` the members of a workspace rooted there
+///
+/// The standalone table of each generated crate is removed and `Cargo.toml` is
+/// (re)written with every crate directory. A crate with a `[workspace]` table
+/// of its own is excluded, and so is a crate with a missing path dependency, as
+/// cargo loads every dependency of a member, optional or not (the `dynamic`
+/// feature of the bundled runtime refers to rosidl-parser of the cargo-ros2
+/// sources). A `Cargo.toml` not written here is never replaced.
+pub fn write_workspace(output_dir: &Path) -> Result<Workspace> {
+    let root_manifest = output_dir.join("Cargo.toml");
+    if let Ok(text) = std::fs::read_to_string(&root_manifest) {
+        if !text.starts_with(HEADER) {
+            return Err(eyre!(
+                "{} exists and is not a workspace manifest of generated bindings",
+                root_manifest.display()
+            ));
+        }
+    }
+
+    let mut dirs: Vec<_> = std::fs::read_dir(output_dir)
+        .wrap_err_with(|| format!("Failed to read {}", output_dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join("Cargo.toml").is_file())
+        .collect();
+    dirs.sort();
+
+    let mut workspace = Workspace::default();
+    // Directories by crate name, and the crates depended on by version
+    let mut crates = BTreeMap::new();
+    let mut version_dependencies = BTreeSet::new();
+    for dir in dirs {
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let path = dir.join("Cargo.toml");
+        let mut text = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        if text.contains(STANDALONE_TABLE) {
+            text = text.replacen(STANDALONE_TABLE, "", 1);
+            std::fs::write(&path, &text)
+                .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+            workspace.joined.push(name.clone());
+        }
+        let manifest: toml::Table = toml::from_str(&text)
+            .wrap_err_with(|| format!("Invalid manifest {}", path.display()))?;
+        if manifest.contains_key("workspace")
+            || path_dependencies(&manifest)
+                .iter()
+                .any(|path| !dir.join(path).join("Cargo.toml").is_file())
+        {
+            workspace.excluded.push(name);
+            continue;
+        }
+        if let Some(crate_name) = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(|name| name.as_str())
+        {
+            crates.insert(crate_name.to_string(), name.clone());
+        }
+        version_dependencies.extend(by_version(&manifest));
+        workspace.members.push(name);
+    }
+    workspace.patches = crates
+        .into_iter()
+        .filter(|(crate_name, _)| {
+            crate_name != RUNTIME_CRATE && version_dependencies.contains(crate_name)
+        })
+        .collect();
+
+    std::fs::write(&root_manifest, workspace.manifest())
+        .wrap_err_with(|| format!("Failed to write {}", root_manifest.display()))?;
+    Ok(workspace)
+}
+
+/// Dependencies of a manifest, by name, in every dependency table
+fn dependencies(manifest: &toml::Table) -> impl Iterator<Item = (&String, &toml::Value)> {
+    ["dependencies", "build-dependencies", "dev-dependencies"]
+        .into_iter()
+        .filter_map(|table| manifest.get(table).and_then(|table| table.as_table()))
+        .flatten()
+}
+
+/// Paths of the path dependencies of a manifest
+fn path_dependencies(manifest: &toml::Table) -> Vec<&str> {
+    dependencies(manifest)
+        .filter_map(|(_, spec)| spec.get("path").and_then(|path| path.as_str()))
+        .collect()
+}
+
+/// Crates a manifest depends on by version only, neither by path nor by git
+fn by_version(manifest: &toml::Table) -> Vec<String> {
+    dependencies(manifest)
+        .filter_map(|(name, spec)| match spec {
+            toml::Value::String(_) => Some(name.as_str()),
+            toml::Value::Table(spec) if !spec.contains_key("path") && !spec.contains_key("git") => {
+                Some(
+                    spec.get("package")
+                        .and_then(|package| package.as_str())
+                        .unwrap_or(name),
+                )
+            }
+            _ => None,
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_write_workspace() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output = temp_dir.path();
+        let write = |dir: &str, manifest: &str| {
+            fs::create_dir_all(output.join(dir)).unwrap();
+            fs::write(output.join(dir).join("Cargo.toml"), manifest).unwrap();
+        };
+        write(
+            "geometry_msgs",
+            &format!(
+                "[package]\nname = \"geometry_msgs\"\n\n{}[dependencies]\n\
+                 rosidl_runtime_rs = {{ path = \"../rosidl_runtime_rs\" }}\n\
+                 std_msgs = \"5.3.2\"\nserde = {{ version = \"1.0\", optional = true }}\n",
+                STANDALONE_TABLE
+            ),
+        );
+        write(
+            "std_msgs",
+            &format!("[package]\nname = \"std_msgs\"\n\n{}", STANDALONE_TABLE),
+        );
+        write(
+            "rosidl_runtime_rs",
+            "[package]\nname = \"rosidl_runtime_rs\"\n",
+        );
+        // Optional or not, a path dependency must be there
+        write(
+            "runtime_copy",
+            "[package]\nname = \"runtime_copy\"\n\n[dependencies]\n\
+             rosidl-parser = { path = \"../rosidl-parser\", optional = true }\n",
+        );
+        write(
+            "vendored",
+            "[package]\nname = \"vendored\"\n\n[workspace]\nmembers = []\n",
+        );
+        fs::create_dir_all(output.join("target/debug")).unwrap();
+
+        let workspace = write_workspace(output).unwrap();
+        assert_eq!(
+            workspace.members,
+            ["geometry_msgs", "rosidl_runtime_rs", "std_msgs"]
+        );
+        assert_eq!(workspace.excluded, ["runtime_copy", "vendored"]);
+        assert_eq!(
+            fs::read_to_string(output.join("Cargo.toml")).unwrap(),
+            format!(
+                "{}\n[workspace]\nresolver = \"2\"\nmembers = [\n    \"geometry_msgs\",\n    \
+                 \"rosidl_runtime_rs\",\n    \"std_msgs\",\n]\nexclude = [\n    \"runtime_copy\",\n    \"vendored\",\n]\n\n\
+                 [patch.crates-io]\nstd_msgs = {{ path = \"std_msgs\" }}\n",
+                HEADER
+            )
+        );
+        assert_eq!(
+            fs::read_to_string(output.join("std_msgs/Cargo.toml")).unwrap(),
+            "[package]\nname = \"std_msgs\"\n\n"
+        );
+
+        assert_eq!(workspace.joined, ["geometry_msgs", "std_msgs"]);
+
+        // Rewriting gives the same workspace, with no crate to join
+        let rewritten = write_workspace(output).unwrap();
+        assert!(rewritten.joined.is_empty());
+        assert_eq!(rewritten.manifest(), workspace.manifest());
+
+        // The manifest of a project is left alone
+        fs::write(output.join("Cargo.toml"), "[package]\nname = \"robot\"\n").unwrap();
+        assert!(write_workspace(output).is_err());
+    }
+}
@@ -3,7 +3,7 @@
 //! These tests verify end-to-end functionality with real ROS 2 packages
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Helper to create a test ROS 2 package
@@ -96,14 +96,14 @@ fn test_end_to_end_package_generation() {
     let srv_dir = pkg_dir.join("src").join("srv");
     assert!(srv_dir.exists(), "srv directory should exist");
     assert!(
-        srv_dir.join("addtwoints_idiomatic.rs").exists(),
+        srv_dir.join("add_two_ints_idiomatic.rs").exists(),
         "Idiomatic service should exist"
     );
 
     let ffi_srv_dir = pkg_dir.join("src").join("ffi").join("srv");
     assert!(ffi_srv_dir.exists(), "ffi/srv directory should exist");
     assert!(
-        ffi_srv_dir.join("addtwoints_rmw.rs").exists(),
+        ffi_srv_dir.join("add_two_ints_rmw.rs").exists(),
         "FFI service should exist in ffi/srv subdirectory"
     );
 
@@ -158,6 +158,68 @@ fn test_generated_package_compiles() {
     );
 }
 
+/// Generate a package with `flag` and check it with `feature` enabled
+///
+/// The package only has messages, so that it has no dependencies, nesting its
+/// own messages directly, in arrays and in sequences. Checking does not link,
+/// so no ROS install is needed.
+fn check_generated_feature(package_name: &str, flag: &str, feature: &str) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let share_dir = temp_dir.path().join("share").join(package_name);
+    let msg_dir = share_dir.join("msg");
+    fs::create_dir_all(&msg_dir).unwrap();
+    fs::write(msg_dir.join("Point.msg"), "float64 x\nfloat64 y\n").unwrap();
+    fs::write(
+        msg_dir.join("Shape.msg"),
+        "Point center\nPoint[2] corners\nPoint[] path\nstring label\nint32 class\n",
+    )
+    .unwrap();
+    let output_dir = temp_dir.path().join("output");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-ros2-bindgen"))
+        .arg("--package")
+        .arg(package_name)
+        .arg("--output")
+        .arg(&output_dir)
+        .arg("--package-path")
+        .arg(&share_dir)
+        .arg(flag)
+        .output()
+        .expect("Failed to run cargo-ros2-bindgen");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let check_output = Command::new("cargo")
+        .arg("check")
+        .arg("--manifest-path")
+        .arg(output_dir.join(package_name).join("Cargo.toml"))
+        .arg("--features")
+        .arg(feature)
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("feature_check"))
+        .output()
+        .expect("Failed to run cargo check");
+    assert!(
+        check_output.status.success(),
+        "Generated {} code failed to compile:\n{}",
+        feature,
+        String::from_utf8_lossy(&check_output.stderr)
+    );
+}
+
+#[test]
+fn test_generated_python_bindings_compile() {
+    check_generated_feature("python_test", "--python", "python");
+}
+
+#[test]
+fn test_generated_cxx_bridge_compiles() {
+    check_generated_feature("cxx_test", "--cxx", "cxx");
+}
+
 #[test]
 fn test_verbose_output() {
     let temp_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,370 @@
+//! Codegen benchmarking
+//!
+//! Runs the generator over interface packages and records how long each phase
+//! takes (parsing `.msg`/`.srv`/`.action` files, rendering the templates, and
+//! writing the output), plus the generated size and peak memory. Reports are
+//! stored as JSON so later runs can be compared against a baseline.
+
+use cargo_ros2_bindgen::ament::Package;
+use eyre::{Result, WrapErr};
+use rosidl_codegen::{GeneratorOptions, Interface};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Time spent in each generation phase, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTimings {
+    pub parse_ms: f64,
+    pub render_ms: f64,
+    pub write_ms: f64,
+}
+
+impl PhaseTimings {
+    /// Sum of all phases
+    pub fn total_ms(&self) -> f64 {
+        self.parse_ms + self.render_ms + self.write_ms
+    }
+
+    fn add(&mut self, other: &PhaseTimings) {
+        self.parse_ms += other.parse_ms;
+        self.render_ms += other.render_ms;
+        self.write_ms += other.write_ms;
+    }
+}
+
+/// Benchmark result for one package
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PackageBench {
+    /// Number of interface files generated
+    pub interfaces: usize,
+    /// Bytes of Rust code written
+    pub generated_bytes: usize,
+    pub timings: PhaseTimings,
+    /// Set if generation failed part-way (timings cover the work done until then)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Benchmark result for a full run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    /// cargo-ros2 version that produced the report
+    pub generator_version: String,
+    /// Per-package results, keyed by package name
+    pub packages: BTreeMap<String, PackageBench>,
+    /// Sum of all package timings
+    pub total: PhaseTimings,
+    /// Peak resident set size of the process in KiB (Linux only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_rss_kib: Option<u64>,
+}
+
+impl BenchReport {
+    /// Load a report from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read benchmark report {}", path.display()))?;
+        serde_json::from_str(&content)
+            .wrap_err_with(|| format!("Failed to parse benchmark report {}", path.display()))
+    }
+
+    /// Save the report as JSON, creating parent directories as needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("Failed to write benchmark report {}", path.display()))
+    }
+
+    /// Number of packages that failed to generate
+    pub fn failure_count(&self) -> usize {
+        self.packages.values().filter(|p| p.error.is_some()).count()
+    }
+
+    /// Compare the phase totals against `baseline`
+    ///
+    /// Only packages present in both reports are summed, so adding or removing
+    /// packages from the index does not show up as a regression.
+    pub fn compare(&self, baseline: &BenchReport) -> Vec<PhaseComparison> {
+        let mut current = PhaseTimings::default();
+        let mut previous = PhaseTimings::default();
+        for (name, bench) in &self.packages {
+            if let Some(old) = baseline.packages.get(name) {
+                current.add(&bench.timings);
+                previous.add(&old.timings);
+            }
+        }
+
+        vec![
+            PhaseComparison::new("parse", previous.parse_ms, current.parse_ms),
+            PhaseComparison::new("render", previous.render_ms, current.render_ms),
+            PhaseComparison::new("write", previous.write_ms, current.write_ms),
+            PhaseComparison::new("total", previous.total_ms(), current.total_ms()),
+        ]
+    }
+}
+
+/// Baseline vs. current time of one phase
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseComparison {
+    pub phase: &'static str,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+}
+
+impl PhaseComparison {
+    fn new(phase: &'static str, baseline_ms: f64, current_ms: f64) -> Self {
+        PhaseComparison {
+            phase,
+            baseline_ms,
+            current_ms,
+        }
+    }
+
+    /// Relative change in percent (positive means slower)
+    pub fn change_percent(&self) -> f64 {
+        if self.baseline_ms == 0.0 {
+            return 0.0;
+        }
+        (self.current_ms - self.baseline_ms) / self.baseline_ms * 100.0
+    }
+}
+
+impl fmt::Display for PhaseComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<8} {:>10.1} ms {:>10.1} ms {:>+8.1}%",
+            self.phase,
+            self.baseline_ms,
+            self.current_ms,
+            self.change_percent()
+        )
+    }
+}
+
+/// Benchmark every package, writing generated code below `output_dir`
+pub fn run(packages: &[&Package], output_dir: &Path) -> BenchReport {
+    let mut report = BenchReport {
+        generator_version: crate::cache::GENERATOR_VERSION.to_string(),
+        ..Default::default()
+    };
+
+    for package in packages {
+        let mut bench = PackageBench::default();
+        if let Err(e) = bench_package(package, &output_dir.join(&package.name), &mut bench) {
+            bench.error = Some(format!("{:#}", e));
+        }
+        report.total.add(&bench.timings);
+        report.packages.insert(package.name.clone(), bench);
+    }
+
+    report.peak_rss_kib = peak_rss_kib();
+    report
+}
+
+/// Generate one package, accumulating timings into `bench`
+fn bench_package(package: &Package, output_dir: &Path, bench: &mut PackageBench) -> Result<()> {
+    // Rendered files of this package as (relative path, content)
+    let mut files = Vec::new();
+    let known_packages = HashSet::new();
+
+    for name in &package.interfaces.messages {
+        let content = fs::read_to_string(package.get_message_path(name))?;
+        let message = timed(&mut bench.timings.parse_ms, || {
+            rosidl_parser::parse_message(&content)
+        })
+        .wrap_err_with(|| format!("Failed to parse message {}", name))?;
+        let generated = timed(&mut bench.timings.render_ms, || {
+            rosidl_codegen::generate_interface(
+                &package.name,
+                name,
+                Interface::Message(&message),
+                &known_packages,
+                GeneratorOptions::default(),
+            )
+        })?;
+        files.extend(generated.files);
+        bench.interfaces += 1;
+    }
+
+    for name in &package.interfaces.services {
+        let content = fs::read_to_string(package.get_service_path(name))?;
+        let service = timed(&mut bench.timings.parse_ms, || {
+            rosidl_parser::parse_service(&content)
+        })
+        .wrap_err_with(|| format!("Failed to parse service {}", name))?;
+        let generated = timed(&mut bench.timings.render_ms, || {
+            rosidl_codegen::generate_interface(
+                &package.name,
+                name,
+                Interface::Service(&service),
+                &known_packages,
+                GeneratorOptions::default(),
+            )
+        })?;
+        files.extend(generated.files);
+        bench.interfaces += 1;
+    }
+
+    for name in &package.interfaces.actions {
+        let content = fs::read_to_string(package.get_action_path(name))?;
+        let action = timed(&mut bench.timings.parse_ms, || {
+            rosidl_parser::parse_action(&content)
+        })
+        .wrap_err_with(|| format!("Failed to parse action {}", name))?;
+        let generated = timed(&mut bench.timings.render_ms, || {
+            rosidl_codegen::generate_interface(
+                &package.name,
+                name,
+                Interface::Action(&action),
+                &known_packages,
+                GeneratorOptions::default(),
+            )
+        })?;
+        files.extend(generated.files);
+        bench.interfaces += 1;
+    }
+
+    timed(&mut bench.timings.write_ms, || -> Result<()> {
+        for (relative, content) in &files {
+            let path = output_dir.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)
+                .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(())
+    })?;
+    bench.generated_bytes = files.iter().map(|(_, content)| content.len()).sum();
+
+    Ok(())
+}
+
+/// Run `f`, adding its wall-clock time to `total_ms`
+fn timed<T>(total_ms: &mut f64, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *total_ms += duration_ms(start.elapsed());
+    result
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Peak resident set size of this process (`VmHWM`), if available
+fn peak_rss_kib() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn create_test_package(dir: &Path) -> Package {
+        let share_dir = dir.join("bench_msgs");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\nfloat64 y\n").unwrap();
+        fs::create_dir_all(share_dir.join("srv")).unwrap();
+        fs::write(
+            share_dir.join("srv/AddTwoInts.srv"),
+            "int64 a\nint64 b\n---\nint64 sum\n",
+        )
+        .unwrap();
+        Package::from_share_dir(share_dir).unwrap()
+    }
+
+    fn report_with(name: &str, timings: PhaseTimings) -> BenchReport {
+        let mut report = BenchReport::default();
+        report.packages.insert(
+            name.to_string(),
+            PackageBench {
+                timings,
+                ..Default::default()
+            },
+        );
+        report.total = timings;
+        report
+    }
+
+    #[test]
+    fn test_run_generates_and_times_package() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+        let output_dir = temp_dir.path().join("out");
+
+        let report = run(&[&package], &output_dir);
+        let bench = &report.packages["bench_msgs"];
+        assert_eq!(bench.error, None);
+        assert_eq!(bench.interfaces, 2);
+        assert!(bench.generated_bytes > 0);
+        assert!(output_dir.join("bench_msgs/ffi/msg/point_rmw.rs").exists());
+        assert!(output_dir
+            .join("bench_msgs/srv/add_two_ints_idiomatic.rs")
+            .exists());
+
+        let saved = temp_dir.path().join("baseline.json");
+        report.save(&saved).unwrap();
+        // Timings may lose the last bit in the JSON round-trip
+        let loaded = BenchReport::load(&saved).unwrap();
+        assert_eq!(
+            loaded.packages["bench_msgs"].generated_bytes,
+            bench.generated_bytes
+        );
+        assert_eq!(loaded.peak_rss_kib, report.peak_rss_kib);
+    }
+
+    #[test]
+    fn test_compare_only_counts_shared_packages() {
+        let baseline = report_with(
+            "std_msgs",
+            PhaseTimings {
+                parse_ms: 10.0,
+                render_ms: 20.0,
+                write_ms: 10.0,
+            },
+        );
+        let mut current = report_with(
+            "std_msgs",
+            PhaseTimings {
+                parse_ms: 10.0,
+                render_ms: 30.0,
+                write_ms: 10.0,
+            },
+        );
+        current.packages.insert(
+            "new_msgs".to_string(),
+            PackageBench {
+                timings: PhaseTimings {
+                    parse_ms: 100.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let comparison = current.compare(&baseline);
+        assert_eq!(comparison[0].change_percent(), 0.0);
+        assert_eq!(comparison[1].phase, "render");
+        assert_eq!(comparison[1].change_percent(), 50.0);
+        assert_eq!(comparison[3].baseline_ms, 40.0);
+        assert_eq!(comparison[3].current_ms, 50.0);
+    }
+
+    #[test]
+    fn test_load_missing_report_fails() {
+        assert!(BenchReport::load(&PathBuf::from("/nonexistent/bench.json")).is_err());
+    }
+}
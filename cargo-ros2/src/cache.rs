@@ -3,6 +3,7 @@
 //! This module provides SHA256-based caching to avoid regenerating bindings
 //! when interface files haven't changed.
 
+use cargo_ros2_bindgen::ament::Package;
 use eyre::{Result, WrapErr};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -13,6 +14,9 @@ use std::path::{Path, PathBuf};
 /// Cache file name
 pub const CACHE_FILE_NAME: &str = ".ros2_bindgen_cache";
 
+/// Version of the code generator recorded in new cache entries
+pub const GENERATOR_VERSION: &str = rosidl_codegen::VERSION;
+
 /// Cache entry for a single package
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CacheEntry {
@@ -28,6 +32,12 @@ pub struct CacheEntry {
     pub timestamp: u64,
     /// Output directory where bindings were generated
     pub output_dir: PathBuf,
+    /// Generator version that produced the bindings (absent in older caches)
+    #[serde(default)]
+    pub generator_version: Option<String>,
+    /// SHA256 checksum of the generated crate (absent in older caches)
+    #[serde(default)]
+    pub output_checksum: Option<String>,
 }
 
 /// Cache for ROS 2 bindings
@@ -124,6 +134,16 @@ impl Cache {
     pub fn entries(&self) -> impl Iterator<Item = &CacheEntry> {
         self.entries.values()
     }
+
+    /// Get entries generated by a different generator version than `current_version`
+    pub fn outdated_entries(&self, current_version: &str) -> Vec<&CacheEntry> {
+        let mut outdated: Vec<_> = self
+            .entries()
+            .filter(|e| e.generator_version.as_deref() != Some(current_version))
+            .collect();
+        outdated.sort_by_key(|e| &e.package_name);
+        outdated
+    }
 }
 
 impl Default for Cache {
@@ -133,52 +153,56 @@ impl Default for Cache {
 }
 
 /// Calculate SHA256 checksum of a directory of interface files
+///
+/// This is the `SOURCE_CHECKSUM` embedded in the generated crate's
+/// `provenance` module.
 pub fn calculate_package_checksum(package_share_dir: &Path) -> Result<String> {
-    let mut all_content = Vec::new();
-
-    // Collect all .msg files
-    if let Ok(msg_dir) = fs::read_dir(package_share_dir.join("msg")) {
-        for entry in msg_dir.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("msg") {
-                let content = fs::read(&path)
-                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
-                all_content.extend_from_slice(&content);
-            }
-        }
+    Package::from_share_dir(package_share_dir.to_path_buf())?.interface_checksum()
+}
+
+/// Calculate SHA256 checksum of a generated crate directory
+///
+/// Covers the relative path and content of every file, in path order, so that
+/// renamed, added and removed files change the checksum too. Build output
+/// (`target/`) and hidden files are skipped.
+pub fn calculate_output_checksum(crate_dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_files(crate_dir, crate_dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for relative in &files {
+        let path = crate_dir.join(relative);
+        let content =
+            fs::read(&path).wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+        hasher.update([0]);
+        hasher.update(&content);
+        hasher.update([0]);
     }
 
-    // Collect all .srv files
-    if let Ok(srv_dir) = fs::read_dir(package_share_dir.join("srv")) {
-        for entry in srv_dir.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("srv") {
-                let content = fs::read(&path)
-                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
-                all_content.extend_from_slice(&content);
-            }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).wrap_err_with(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || (dir == root && name == "target") {
+            continue;
         }
-    }
 
-    // Collect all .action files
-    if let Ok(action_dir) = fs::read_dir(package_share_dir.join("action")) {
-        for entry in action_dir.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("action") {
-                let content = fs::read(&path)
-                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
-                all_content.extend_from_slice(&content);
-            }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_path_buf());
         }
     }
-
-    // Calculate SHA256 of all content
-    let mut hasher = Sha256::new();
-    hasher.update(&all_content);
-    let result = hasher.finalize();
-
-    // Convert to hex string
-    Ok(format!("{:x}", result))
+    Ok(())
 }
 
 #[cfg(test)]
@@ -203,6 +227,8 @@ mod tests {
             package_version: Some("1.0.0".to_string()),
             timestamp: 1234567890,
             output_dir: PathBuf::from("/tmp/test"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry.clone());
@@ -222,6 +248,8 @@ mod tests {
             package_version: None,
             timestamp: 1234567890,
             output_dir: PathBuf::from("/tmp/test"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry);
@@ -245,6 +273,8 @@ mod tests {
             package_version: Some("2.0.0".to_string()),
             timestamp: 9876543210,
             output_dir: PathBuf::from("/tmp/test2"),
+            generator_version: None,
+            output_checksum: None,
         });
 
         cache.save(&cache_file).unwrap();
@@ -304,6 +334,25 @@ mod tests {
         assert_ne!(checksum1, checksum2);
     }
 
+    #[test]
+    fn test_output_checksum_detects_modification() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let crate_dir = temp_dir.path();
+        fs::create_dir_all(crate_dir.join("src/msg")).unwrap();
+        fs::write(crate_dir.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(crate_dir.join("src/msg/point_idiomatic.rs"), "pub x: f64").unwrap();
+
+        let checksum = calculate_output_checksum(crate_dir).unwrap();
+
+        // Build output is not part of the crate
+        fs::create_dir_all(crate_dir.join("target")).unwrap();
+        fs::write(crate_dir.join("target/out"), "artifact").unwrap();
+        assert_eq!(calculate_output_checksum(crate_dir).unwrap(), checksum);
+
+        fs::write(crate_dir.join("src/msg/point_idiomatic.rs"), "pub x: f32").unwrap();
+        assert_ne!(calculate_output_checksum(crate_dir).unwrap(), checksum);
+    }
+
     #[test]
     fn test_is_valid_no_entry() {
         let cache = Cache::new();
@@ -321,6 +370,8 @@ mod tests {
             package_version: None,
             timestamp: 1234567890,
             output_dir: temp_dir.path().to_path_buf(),
+            generator_version: None,
+            output_checksum: None,
         });
 
         assert!(!cache.is_valid("test_msgs", "different_checksum"));
@@ -336,8 +387,52 @@ mod tests {
             package_version: None,
             timestamp: 1234567890,
             output_dir: PathBuf::from("/nonexistent/path"),
+            generator_version: None,
+            output_checksum: None,
         });
 
         assert!(!cache.is_valid("test_msgs", "abc123"));
     }
+
+    #[test]
+    fn test_outdated_entries() {
+        let mut cache = Cache::new();
+        for (name, version) in [
+            ("a_msgs", Some("0.1.0")),
+            ("b_msgs", None),
+            ("c_msgs", Some("0.2.0")),
+        ] {
+            cache.insert(CacheEntry {
+                package_name: name.to_string(),
+                checksum: "abc123".to_string(),
+                ros_distro: None,
+                package_version: None,
+                timestamp: 1234567890,
+                output_dir: PathBuf::from("/tmp/test"),
+                generator_version: version.map(String::from),
+                output_checksum: None,
+            });
+        }
+
+        let outdated: Vec<_> = cache
+            .outdated_entries("0.2.0")
+            .into_iter()
+            .map(|e| e.package_name.as_str())
+            .collect();
+        assert_eq!(outdated, vec!["a_msgs", "b_msgs"]);
+    }
+
+    #[test]
+    fn test_load_cache_without_generator_version() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_file = temp_dir.path().join(CACHE_FILE_NAME);
+        fs::write(
+            &cache_file,
+            r#"{"version":1,"entries":{"std_msgs":{"package_name":"std_msgs","checksum":"abc","ros_distro":null,"package_version":null,"timestamp":0,"output_dir":"/tmp"}}}"#,
+        )
+        .unwrap();
+
+        let cache = Cache::load(&cache_file).unwrap();
+        assert_eq!(cache.get("std_msgs").unwrap().generator_version, None);
+    }
 }
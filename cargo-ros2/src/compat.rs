@@ -0,0 +1,332 @@
+//! Interface evolution checks
+//!
+//! Compares two versions of an interface package, either two directories laid out
+//! like an ament share dir (`msg/`, `srv/`, `action/`) or a git revision of a local
+//! package against its working tree, and reports the semantic differences found by
+//! `rosidl_parser::diff`.
+
+use eyre::{eyre, Result, WrapErr};
+use rosidl_parser::{Action, Change, Message, Service};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Interface kinds and their file extensions (also the subdirectory names)
+const INTERFACE_KINDS: &[&str] = &["msg", "srv", "action"];
+
+/// Parsed interfaces of one package version, keyed by `msg/Name`, `srv/Name`, ...
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceSet {
+    pub messages: BTreeMap<String, Message>,
+    pub services: BTreeMap<String, Service>,
+    pub actions: BTreeMap<String, Action>,
+}
+
+impl InterfaceSet {
+    /// Load interfaces from a package directory
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(eyre!("Interface directory not found: {}", dir.display()));
+        }
+
+        let mut set = InterfaceSet::default();
+        for kind in INTERFACE_KINDS {
+            let kind_dir = dir.join(kind);
+            if !kind_dir.exists() {
+                continue;
+            }
+            let entries = fs::read_dir(&kind_dir)
+                .wrap_err_with(|| format!("Failed to read directory: {}", kind_dir.display()))?;
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(*kind) {
+                    continue;
+                }
+                let content = fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+                set.insert_file(&format!("{}/{}", kind, file_name(&path)), &content)?;
+            }
+        }
+        Ok(set)
+    }
+
+    /// Load interfaces from `dir` as of a git revision
+    pub fn from_git(dir: &Path, revision: &str) -> Result<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["ls-tree", "-r", "--name-only", revision, "--"])
+            .args(INTERFACE_KINDS)
+            .output()
+            .wrap_err("Failed to run git ls-tree")?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "git ls-tree failed for revision '{}': {}",
+                revision,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let mut set = InterfaceSet::default();
+        for relative in String::from_utf8_lossy(&output.stdout).lines() {
+            // Only files directly inside msg/, srv/ or action/
+            let Some((kind, file)) = relative.split_once('/') else {
+                continue;
+            };
+            if file.contains('/') || !file.ends_with(&format!(".{}", kind)) {
+                continue;
+            }
+
+            let show = Command::new("git")
+                .arg("-C")
+                .arg(dir)
+                .arg("show")
+                .arg(format!("{}:./{}", revision, relative))
+                .output()
+                .wrap_err("Failed to run git show")?;
+            if !show.status.success() {
+                return Err(eyre!(
+                    "git show failed for {}:{}: {}",
+                    revision,
+                    relative,
+                    String::from_utf8_lossy(&show.stderr).trim()
+                ));
+            }
+            set.insert_file(relative, &String::from_utf8_lossy(&show.stdout))?;
+        }
+        Ok(set)
+    }
+
+    /// Parse one interface file given its path relative to the package (`msg/Foo.msg`)
+    pub fn insert_file(&mut self, relative: &str, content: &str) -> Result<()> {
+        let (kind, file) = relative
+            .split_once('/')
+            .ok_or_else(|| eyre!("Unexpected interface path: {}", relative))?;
+        let name = file.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(file);
+        let key = format!("{}/{}", kind, name);
+
+        match kind {
+            "msg" => {
+                let message = rosidl_parser::parse_message(content)
+                    .wrap_err_with(|| format!("Failed to parse {}", relative))?;
+                self.messages.insert(key, message);
+            }
+            "srv" => {
+                let service = rosidl_parser::parse_service(content)
+                    .wrap_err_with(|| format!("Failed to parse {}", relative))?;
+                self.services.insert(key, service);
+            }
+            "action" => {
+                let action = rosidl_parser::parse_action(content)
+                    .wrap_err_with(|| format!("Failed to parse {}", relative))?;
+                self.actions.insert(key, action);
+            }
+            other => return Err(eyre!("Unknown interface kind '{}'", other)),
+        }
+        Ok(())
+    }
+
+    /// Total number of interfaces
+    pub fn len(&self) -> usize {
+        self.messages.len() + self.services.len() + self.actions.len()
+    }
+
+    /// Whether no interfaces were found
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// One reported difference
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompatEntry {
+    InterfaceAdded(String),
+    InterfaceRemoved(String),
+    Changed {
+        /// Interface key, with the section for services/actions (`srv/Foo request`)
+        interface: String,
+        change: Change,
+    },
+}
+
+impl CompatEntry {
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            CompatEntry::InterfaceAdded(_) => false,
+            CompatEntry::InterfaceRemoved(_) => true,
+            CompatEntry::Changed { change, .. } => change.is_breaking(),
+        }
+    }
+}
+
+impl fmt::Display for CompatEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let marker = if self.is_breaking() { "BREAKING" } else { "ok" };
+        match self {
+            CompatEntry::InterfaceAdded(name) => {
+                write!(f, "[{}] {}: interface added", marker, name)
+            }
+            CompatEntry::InterfaceRemoved(name) => {
+                write!(f, "[{}] {}: interface removed", marker, name)
+            }
+            CompatEntry::Changed { interface, change } => {
+                write!(f, "[{}] {}: {}", marker, interface, change)
+            }
+        }
+    }
+}
+
+/// Result of comparing two interface sets
+#[derive(Debug, Clone, Default)]
+pub struct CompatReport {
+    pub entries: Vec<CompatEntry>,
+}
+
+impl CompatReport {
+    /// Number of breaking entries
+    pub fn breaking_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_breaking()).count()
+    }
+}
+
+/// Compare an old and a new version of a package's interfaces
+pub fn compare(old: &InterfaceSet, new: &InterfaceSet) -> CompatReport {
+    let mut report = CompatReport::default();
+
+    diff_maps(&old.messages, &new.messages, &mut report, |o, n| {
+        rosidl_parser::diff_messages(o, n)
+            .into_iter()
+            .map(|c| (None, c))
+            .collect()
+    });
+    diff_maps(&old.services, &new.services, &mut report, |o, n| {
+        rosidl_parser::diff_services(o, n)
+            .into_iter()
+            .map(|(s, c)| (Some(s), c))
+            .collect()
+    });
+    diff_maps(&old.actions, &new.actions, &mut report, |o, n| {
+        rosidl_parser::diff_actions(o, n)
+            .into_iter()
+            .map(|(s, c)| (Some(s), c))
+            .collect()
+    });
+
+    report
+}
+
+fn diff_maps<T>(
+    old: &BTreeMap<String, T>,
+    new: &BTreeMap<String, T>,
+    report: &mut CompatReport,
+    diff: impl Fn(&T, &T) -> Vec<(Option<&'static str>, Change)>,
+) {
+    for (name, old_item) in old {
+        let Some(new_item) = new.get(name) else {
+            report
+                .entries
+                .push(CompatEntry::InterfaceRemoved(name.clone()));
+            continue;
+        };
+        for (section, change) in diff(old_item, new_item) {
+            let interface = match section {
+                Some(section) => format!("{} {}", name, section),
+                None => name.clone(),
+            };
+            report
+                .entries
+                .push(CompatEntry::Changed { interface, change });
+        }
+    }
+    for name in new.keys() {
+        if !old.contains_key(name) {
+            report
+                .entries
+                .push(CompatEntry::InterfaceAdded(name.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_package(dir: &Path, files: &[(&str, &str)]) {
+        for (relative, content) in files {
+            let path = dir.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compare_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_dir = temp_dir.path().join("old");
+        let new_dir = temp_dir.path().join("new");
+        write_package(
+            &old_dir,
+            &[
+                ("msg/Status.msg", "int32 code\nstring<=64 text\n"),
+                ("msg/Legacy.msg", "bool flag\n"),
+                ("srv/Reset.srv", "bool hard\n---\nbool ok\n"),
+            ],
+        );
+        write_package(
+            &new_dir,
+            &[
+                (
+                    "msg/Status.msg",
+                    "int32 code\nstring<=32 text\nfloat64 stamp\n",
+                ),
+                ("msg/Extra.msg", "int8 value\n"),
+                ("srv/Reset.srv", "bool hard\n---\nbool ok\n"),
+            ],
+        );
+
+        let old = InterfaceSet::from_dir(&old_dir).unwrap();
+        let new = InterfaceSet::from_dir(&new_dir).unwrap();
+        assert_eq!(old.len(), 3);
+
+        let report = compare(&old, &new);
+        let lines: Vec<String> = report.entries.iter().map(|e| e.to_string()).collect();
+
+        assert!(lines.contains(&"[BREAKING] msg/Legacy: interface removed".to_string()));
+        assert!(lines.contains(&"[ok] msg/Extra: interface added".to_string()));
+        assert!(lines.contains(
+            &"[BREAKING] msg/Status: bound shrunk: text (string<=64 -> string<=32)".to_string()
+        ));
+        assert!(lines.contains(&"[ok] msg/Status: field added: float64 stamp".to_string()));
+        assert_eq!(report.breaking_count(), 2);
+    }
+
+    #[test]
+    fn test_service_section_labels() {
+        let mut old = InterfaceSet::default();
+        old.insert_file("srv/Add.srv", "int32 a\n---\nint32 sum\n")
+            .unwrap();
+        let mut new = InterfaceSet::default();
+        new.insert_file("srv/Add.srv", "int32 a\n---\nint64 sum\n")
+            .unwrap();
+
+        let report = compare(&old, &new);
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(
+            report.entries[0].to_string(),
+            "[BREAKING] srv/Add response: type changed: sum (int32 -> int64)"
+        );
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        assert!(InterfaceSet::from_dir(Path::new("/nonexistent/interfaces")).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! In-memory code generation for `cargo ros2 expand`
+//!
+//! Runs the generator for a single interface and returns the RMW and idiomatic
+//! Rust it produces, without writing anything to `target/ros2_bindings`. This
+//! is meant for debugging templates, so the output is exactly what
+//! cargo-ros2-bindgen would write.
+
+use cargo_ros2_bindgen::ament::Package;
+use eyre::{eyre, Result, WrapErr};
+use rosidl_codegen::{
+    generate_action_package_with_options, generate_message_package_with_options,
+    generate_service_package_with_options, GeneratorOptions,
+};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Interface kinds, in the order they are searched for `pkg/Type`
+const KINDS: &[&str] = &["msg", "srv", "action"];
+
+/// Keywords highlighted by [`highlight`]
+const KEYWORDS: &[&str] = &[
+    "as", "const", "crate", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in",
+    "let", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+    "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// Generated code for one interface
+#[derive(Debug, Clone)]
+pub struct Expansion {
+    /// Fully qualified name (`pkg/kind/Name`)
+    pub type_name: String,
+    /// Interface kind (`msg`, `srv` or `action`)
+    pub kind: String,
+    /// C-compatible layer (`pkg::ffi::<kind>::<module>`)
+    pub rmw: String,
+    /// Idiomatic layer (`pkg::<kind>::<module>`)
+    pub idiomatic: String,
+}
+
+/// Split `pkg/Type` or `pkg/<kind>/Type` into package, optional kind and name
+pub fn parse_interface_name(name: &str) -> Result<(String, Option<String>, String)> {
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [package, interface] if !package.is_empty() && !interface.is_empty() => {
+            Ok((package.to_string(), None, interface.to_string()))
+        }
+        [package, kind, interface]
+            if !package.is_empty() && !interface.is_empty() && KINDS.contains(kind) =>
+        {
+            Ok((
+                package.to_string(),
+                Some(kind.to_string()),
+                interface.to_string(),
+            ))
+        }
+        _ => Err(eyre!(
+            "Expected an interface like 'pkg/Type' or 'pkg/msg/Type', got '{}'",
+            name
+        )),
+    }
+}
+
+/// Generate the code for `name` in `package`
+///
+/// Without `kind`, messages are searched first, then services and actions.
+pub fn expand(
+    package: &Package,
+    kind: Option<&str>,
+    name: &str,
+    options: GeneratorOptions,
+) -> Result<Expansion> {
+    let kind = match kind {
+        Some(kind) => kind,
+        None => KINDS
+            .iter()
+            .copied()
+            .find(|kind| interface_path(package, kind, name).exists())
+            .ok_or_else(|| eyre!("No interface '{}' in package '{}'", name, package.name))?,
+    };
+
+    let path = interface_path(package, kind, name);
+    let content = std::fs::read_to_string(&path)
+        .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+    let known_packages = HashSet::new();
+
+    let (rmw, idiomatic) = match kind {
+        "msg" => {
+            let message = rosidl_parser::parse_message(&content)
+                .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+            let generated = generate_message_package_with_options(
+                &package.name,
+                name,
+                &message,
+                &known_packages,
+                options,
+            )?;
+            (generated.message_rmw, generated.message_idiomatic)
+        }
+        "srv" => {
+            let service = rosidl_parser::parse_service(&content)
+                .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+            let generated = generate_service_package_with_options(
+                &package.name,
+                name,
+                &service,
+                &known_packages,
+                options,
+            )?;
+            (generated.service_rmw, generated.service_idiomatic)
+        }
+        _ => {
+            let action = rosidl_parser::parse_action(&content)
+                .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+            let generated = generate_action_package_with_options(
+                &package.name,
+                name,
+                &action,
+                &known_packages,
+                options,
+            )?;
+            (generated.action_rmw, generated.action_idiomatic)
+        }
+    };
+
+    Ok(Expansion {
+        type_name: format!("{}/{}/{}", package.name, kind, name),
+        kind: kind.to_string(),
+        rmw,
+        idiomatic,
+    })
+}
+
+fn interface_path(package: &Package, kind: &str, name: &str) -> std::path::PathBuf {
+    match kind {
+        "msg" => package.get_message_path(name),
+        "srv" => package.get_service_path(name),
+        _ => package.get_action_path(name),
+    }
+}
+
+/// Add ANSI colors to Rust source
+///
+/// A lexical approximation, good enough for generated code: comments,
+/// string literals, attributes, keywords, numbers and capitalized
+/// identifiers (types) are colored.
+pub fn highlight(code: &str) -> String {
+    const RESET: &str = "\x1b[0m";
+    const COMMENT: &str = "\x1b[90m";
+    const STRING: &str = "\x1b[32m";
+    const ATTRIBUTE: &str = "\x1b[33m";
+    const KEYWORD: &str = "\x1b[1;35m";
+    const NUMBER: &str = "\x1b[36m";
+    const TYPE: &str = "\x1b[34m";
+
+    fn colored(out: &mut String, color: &str, text: &[char]) {
+        out.push_str(color);
+        out.extend(text);
+        out.push_str(RESET);
+    }
+
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            colored(&mut out, COMMENT, &chars[start..i]);
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+            colored(&mut out, STRING, &chars[start..i]);
+        } else if c == '#' && matches!(chars.get(i + 1), Some('[') | Some('!')) {
+            let mut depth = 0;
+            while i < chars.len() {
+                match chars[i] {
+                    '[' => depth += 1,
+                    ']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            i += 1;
+                            break;
+                        }
+                    }
+                    '\n' => break,
+                    _ => {}
+                }
+                i += 1;
+            }
+            colored(&mut out, ATTRIBUTE, &chars[start..i]);
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            colored(&mut out, NUMBER, &chars[start..i]);
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                colored(&mut out, KEYWORD, &chars[start..i]);
+            } else if c.is_uppercase() {
+                colored(&mut out, TYPE, &chars[start..i]);
+            } else {
+                out.push_str(&word);
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+
+    out
+}
+
+/// Render an expansion with a banner comment before each layer
+pub fn render(expansion: &Expansion, rmw: bool, idiomatic: bool) -> String {
+    let mut out = String::new();
+    let mut section = |title: &str, code: &str| {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "// ===== {} ({}) =====", expansion.type_name, title);
+        out.push_str(code.trim_end());
+        out.push('\n');
+    };
+
+    if rmw {
+        section("RMW layer", &expansion.rmw);
+    }
+    if idiomatic {
+        section("idiomatic layer", &expansion.idiomatic);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_test_package(dir: &std::path::Path) -> Package {
+        let share_dir = dir.join("test_pkg");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::create_dir_all(share_dir.join("srv")).unwrap();
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\nfloat64 y\n").unwrap();
+        fs::write(
+            share_dir.join("srv/AddTwoInts.srv"),
+            "int64 a\nint64 b\n---\nint64 sum\n",
+        )
+        .unwrap();
+        Package::from_share_dir(share_dir).unwrap()
+    }
+
+    #[test]
+    fn test_parse_interface_name() {
+        assert_eq!(
+            parse_interface_name("std_msgs/Header").unwrap(),
+            ("std_msgs".to_string(), None, "Header".to_string())
+        );
+        assert_eq!(
+            parse_interface_name("example_interfaces/srv/AddTwoInts").unwrap(),
+            (
+                "example_interfaces".to_string(),
+                Some("srv".to_string()),
+                "AddTwoInts".to_string()
+            )
+        );
+        assert!(parse_interface_name("std_msgs/idl/Header").is_err());
+        assert!(parse_interface_name("Header").is_err());
+    }
+
+    #[test]
+    fn test_expand_finds_kind() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let package = create_test_package(temp_dir.path());
+
+        let expansion = expand(&package, None, "Point", GeneratorOptions::default()).unwrap();
+        assert_eq!(expansion.type_name, "test_pkg/msg/Point");
+        assert!(expansion.rmw.contains("pub struct Point"));
+        assert!(expansion.idiomatic.contains("pub struct Point"));
+
+        let expansion = expand(&package, None, "AddTwoInts", GeneratorOptions::default()).unwrap();
+        assert_eq!(expansion.kind, "srv");
+
+        let rendered = render(&expansion, false, true);
+        assert!(rendered.starts_with("// ===== test_pkg/srv/AddTwoInts (idiomatic layer) ====="));
+        assert!(!rendered.contains("RMW layer"));
+
+        assert!(expand(
+            &package,
+            Some("msg"),
+            "AddTwoInts",
+            GeneratorOptions::default()
+        )
+        .is_err());
+        assert!(expand(&package, None, "Missing", GeneratorOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_highlight() {
+        let highlighted = highlight("pub struct Point { x: f64 } // 2 fields\n");
+        assert!(highlighted.contains("\x1b[1;35mpub\x1b[0m"));
+        assert!(highlighted.contains("\x1b[34mPoint\x1b[0m"));
+        assert!(highlighted.contains("\x1b[90m// 2 fields\x1b[0m"));
+
+        let plain = highlight("let s = \"a \\\" b\"; #[derive(Debug)]");
+        assert!(plain.contains("\x1b[32m\"a \\\" b\"\x1b[0m"));
+        assert!(plain.contains("\x1b[33m#[derive(Debug)]\x1b[0m"));
+    }
+}
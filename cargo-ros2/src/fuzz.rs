@@ -0,0 +1,327 @@
+//! Randomized round-trip testing for `cargo ros2 fuzz-interfaces`
+//!
+//! Generated messages implement `Arbitrary` behind their crate's `arbitrary`
+//! feature. This module writes a small harness crate that builds random instances
+//! of every message in a package and checks that they survive a round trip:
+//! idiomatic → RMW → idiomatic for regular bindings, CDR encoding and decoding for
+//! types-only bindings (which have no RMW layer). Conversion panics count as
+//! failures, and every case is reproducible from the seed and case number.
+
+use cargo_ros2_bindgen::generator::RUNTIME_CRATE;
+use eyre::{eyre, Result, WrapErr};
+use rosidl_codegen::types::to_snake_case;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Round trip the harness checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundTrip {
+    /// idiomatic → RMW → idiomatic
+    Rmw,
+    /// CDR encoding and decoding (types-only bindings)
+    Cdr,
+}
+
+impl RoundTrip {
+    /// Round trip supported by the generated crate in `bindings_dir`
+    ///
+    /// Fails for bindings without `Arbitrary` impls (generated with `--rclrs` or
+    /// `--embedded`).
+    pub fn detect(bindings_dir: &Path) -> Result<Self> {
+        let manifest_path = bindings_dir.join("Cargo.toml");
+        let manifest: toml::Value = fs::read_to_string(&manifest_path)
+            .wrap_err_with(|| format!("Failed to read {}", manifest_path.display()))?
+            .parse()
+            .wrap_err_with(|| format!("Failed to parse {}", manifest_path.display()))?;
+        let has_arbitrary = manifest
+            .get("features")
+            .and_then(|features| features.get("arbitrary"))
+            .is_some();
+        if !has_arbitrary {
+            return Err(eyre!(
+                "{} has no `arbitrary` feature (bindings generated with --rclrs or --embedded, or by an older cargo-ros2)",
+                bindings_dir.display()
+            ));
+        }
+
+        // Types-only bindings have no ffi module
+        if bindings_dir.join("src").join("ffi").exists() {
+            Ok(RoundTrip::Rmw)
+        } else {
+            Ok(RoundTrip::Cdr)
+        }
+    }
+}
+
+/// Harness code shared by every package
+const HARNESS_PRELUDE: &str = r#"// Generated by cargo ros2 fuzz-interfaces
+
+use rosidl_runtime_rs::fuzz::{Arbitrary, Unstructured};
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Random input of one case
+fn case_bytes(seed: u64, case: u64) -> Vec<u8> {
+    // splitmix64
+    let mut state = seed ^ case.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    (0..128)
+        .flat_map(|_| {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            (z ^ (z >> 31)).to_le_bytes()
+        })
+        .collect()
+}
+
+fn truncated(s: String) -> String {
+    if s.chars().count() > 400 {
+        format!("{}...", s.chars().take(400).collect::<String>())
+    } else {
+        s
+    }
+}
+
+/// Check `cases` random instances of `T`, returning whether all passed
+fn check<T>(name: &str, cases: u64, seed: u64, round_trip: fn(&T) -> T) -> bool
+where
+    T: for<'a> Arbitrary<'a> + Debug,
+{
+    let mut failures = 0;
+    let mut first_failure = None;
+    for case in 0..cases {
+        let data = case_bytes(seed, case);
+        let Ok(msg) = T::arbitrary(&mut Unstructured::new(&data)) else {
+            continue;
+        };
+        // Debug output compares NaN payloads and signed zeros as expected
+        let error = match panic::catch_unwind(AssertUnwindSafe(|| round_trip(&msg))) {
+            Ok(back) if format!("{:?}", back) == format!("{:?}", msg) => continue,
+            Ok(back) => format!(
+                "round trip changed the message\n      sent:     {}\n      received: {}",
+                truncated(format!("{:?}", msg)),
+                truncated(format!("{:?}", back))
+            ),
+            Err(panic) => format!(
+                "conversion panicked: {}\n      message: {}",
+                panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default(),
+                truncated(format!("{:?}", msg))
+            ),
+        };
+        failures += 1;
+        first_failure.get_or_insert((case, error));
+    }
+
+    match first_failure {
+        None => {
+            println!("  ok      {} ({} cases)", name, cases);
+            true
+        }
+        Some((case, error)) => {
+            println!(
+                "  FAILED  {}: {} of {} cases, first at case {}\n    {}",
+                name, failures, cases, case, error
+            );
+            false
+        }
+    }
+}
+"#;
+
+/// Generate the harness `src/main.rs` checking `messages` of `package`
+pub fn harness_main(package: &str, messages: &[String], round_trip: RoundTrip) -> String {
+    let mut out = HARNESS_PRELUDE.to_string();
+
+    match round_trip {
+        RoundTrip::Rmw => out.push_str(
+            r#"
+fn round_trip<T>(msg: &T) -> T
+where
+    T: rosidl_runtime_rs::Message + Clone,
+    T::RmwMsg: Clone,
+{
+    let rmw = T::into_rmw_message(std::borrow::Cow::Borrowed(msg)).into_owned();
+    T::from_rmw_message(rmw)
+}
+"#,
+        ),
+        RoundTrip::Cdr => out.push_str(
+            r#"
+fn round_trip<T>(msg: &T) -> T
+where
+    T: rosidl_runtime_rs::cdr::CdrSerialize + rosidl_runtime_rs::cdr::CdrDeserialize,
+{
+    let bytes = rosidl_runtime_rs::cdr::to_cdr(msg);
+    rosidl_runtime_rs::cdr::from_cdr(&bytes).expect("CDR decoding failed")
+}
+"#,
+        ),
+    }
+
+    out.push_str(
+        r#"
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let cases: u64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(1000);
+    let seed: u64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(0);
+    // Panics are reported per message
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut passed = true;
+"#,
+    );
+    for name in messages {
+        out.push_str(&format!(
+            "    passed &= check::<{pkg}::msg::{module}::{name}>(\"{pkg}/msg/{name}\", cases, seed, round_trip);\n",
+            pkg = package,
+            module = to_snake_case(name),
+            name = name,
+        ));
+    }
+    out.push_str(
+        r#"
+    if !passed {
+        std::process::exit(1);
+    }
+}
+"#,
+    );
+    out
+}
+
+/// Generate the harness `Cargo.toml`, depending on the bindings in `bindings_dir`
+pub fn harness_manifest(package: &str, bindings_dir: &Path) -> String {
+    // Generated crates find the runtime next to them
+    let runtime_dir = bindings_dir.with_file_name(RUNTIME_CRATE);
+    format!(
+        r#"[package]
+name = "{package}_fuzz"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+# Standalone package (not part of parent workspace)
+[workspace]
+
+[dependencies]
+{package} = {{ path = {bindings}, features = ["arbitrary"] }}
+rosidl_runtime_rs = {{ path = {runtime}, features = ["arbitrary"] }}
+"#,
+        package = package,
+        bindings = toml::Value::String(bindings_dir.display().to_string()),
+        runtime = toml::Value::String(runtime_dir.display().to_string()),
+    )
+}
+
+/// Write the harness crate for `package` into `harness_dir`
+pub fn write_harness(
+    harness_dir: &Path,
+    package: &str,
+    bindings_dir: &Path,
+    messages: &[String],
+) -> Result<RoundTrip> {
+    let round_trip = RoundTrip::detect(bindings_dir)?;
+    let src_dir = harness_dir.join("src");
+    fs::create_dir_all(&src_dir)
+        .wrap_err_with(|| format!("Failed to create {}", src_dir.display()))?;
+    fs::write(
+        harness_dir.join("Cargo.toml"),
+        harness_manifest(package, bindings_dir),
+    )?;
+    fs::write(
+        src_dir.join("main.rs"),
+        harness_main(package, messages, round_trip),
+    )?;
+    Ok(round_trip)
+}
+
+/// Build and run the harness in `harness_dir`; fails if any message failed
+pub fn run_harness(harness_dir: &Path, cases: u64, seed: u64) -> Result<()> {
+    let status = Command::new("cargo")
+        .args(["run", "--release", "--quiet", "--"])
+        .arg(cases.to_string())
+        .arg(seed.to_string())
+        .current_dir(harness_dir)
+        .status()
+        .wrap_err("Failed to execute cargo run")?;
+    if !status.success() {
+        return Err(eyre!("Fuzzing found round-trip failures (seed {})", seed));
+    }
+    Ok(())
+}
+
+/// Default harness directory for `package` under the project's target directory
+pub fn harness_dir(project_root: &Path, package: &str) -> PathBuf {
+    project_root.join("target").join("ros2_fuzz").join(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harness_main() {
+        let messages = vec!["Point".to_string(), "PoseStamped".to_string()];
+        let rust = harness_main("geometry_msgs", &messages, RoundTrip::Rmw);
+        assert!(rust.contains(
+            "passed &= check::<geometry_msgs::msg::pose_stamped::PoseStamped>(\"geometry_msgs/msg/PoseStamped\", cases, seed, round_trip);"
+        ));
+        assert!(rust.contains("T::into_rmw_message"));
+        assert!(!rust.contains("to_cdr"));
+
+        let rust = harness_main("geometry_msgs", &messages, RoundTrip::Cdr);
+        assert!(rust.contains("rosidl_runtime_rs::cdr::to_cdr(msg)"));
+    }
+
+    #[test]
+    fn test_write_harness() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bindings_dir = temp_dir.path().join("ros2_bindings").join("std_msgs");
+        fs::create_dir_all(bindings_dir.join("src")).unwrap();
+        fs::write(
+            bindings_dir.join("Cargo.toml"),
+            "[package]\nname = \"std_msgs\"\n\n[features]\narbitrary = [\"rosidl_runtime_rs/arbitrary\"]\n",
+        )
+        .unwrap();
+
+        let harness = temp_dir.path().join("harness");
+        let messages = vec!["Empty".to_string()];
+        // No src/ffi: types-only bindings
+        let round_trip = write_harness(&harness, "std_msgs", &bindings_dir, &messages).unwrap();
+        assert_eq!(round_trip, RoundTrip::Cdr);
+
+        let manifest: toml::Value = fs::read_to_string(harness.join("Cargo.toml"))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let deps = &manifest["dependencies"];
+        assert_eq!(
+            deps["std_msgs"]["path"].as_str(),
+            Some(bindings_dir.to_str().unwrap())
+        );
+        assert_eq!(
+            deps["rosidl_runtime_rs"]["path"].as_str(),
+            temp_dir
+                .path()
+                .join("ros2_bindings")
+                .join("rosidl_runtime_rs")
+                .to_str()
+        );
+
+        fs::create_dir_all(bindings_dir.join("src").join("ffi")).unwrap();
+        assert_eq!(RoundTrip::detect(&bindings_dir).unwrap(), RoundTrip::Rmw);
+
+        fs::write(
+            bindings_dir.join("Cargo.toml"),
+            "[package]\nname = \"std_msgs\"\n",
+        )
+        .unwrap();
+        assert!(RoundTrip::detect(&bindings_dir).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! IDE integration for generated ROS 2 bindings
+//!
+//! This module produces the rust-analyzer settings needed for code completion
+//! on generated message types, and can merge them into `.vscode/settings.json`.
+
+use eyre::{eyre, Result, WrapErr};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variables forwarded to rust-analyzer so generated build scripts can link
+const FORWARDED_ENV_VARS: &[&str] = &["AMENT_PREFIX_PATH", "ROS_DISTRO"];
+
+/// Build rust-analyzer settings for a project and its generated bindings
+///
+/// The project manifest comes first in `linkedProjects`, followed by every
+/// generated binding crate so they are indexed even before the first build.
+pub fn rust_analyzer_settings(project_root: &Path, bindings: &BTreeMap<String, PathBuf>) -> Value {
+    let mut linked_projects = vec![Value::String(
+        project_root.join("Cargo.toml").display().to_string(),
+    )];
+    for crate_dir in bindings.values() {
+        linked_projects.push(Value::String(
+            crate_dir.join("Cargo.toml").display().to_string(),
+        ));
+    }
+
+    let mut extra_env = Map::new();
+    for var in FORWARDED_ENV_VARS {
+        if let Ok(value) = std::env::var(var) {
+            extra_env.insert(var.to_string(), Value::String(value));
+        }
+    }
+
+    let mut settings = Map::new();
+    settings.insert(
+        "rust-analyzer.linkedProjects".to_string(),
+        Value::Array(linked_projects),
+    );
+    settings.insert(
+        "rust-analyzer.cargo.extraEnv".to_string(),
+        Value::Object(extra_env),
+    );
+
+    Value::Object(settings)
+}
+
+/// Outcome of [`write_vscode_settings`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VscodeSettings {
+    /// The merged settings were written to the file
+    Written(PathBuf),
+    /// The file has comments, which rewriting it would drop, so it was left
+    /// alone; `settings` are the merged values of the managed keys to add by hand
+    HasComments { path: PathBuf, settings: Value },
+}
+
+/// Merge settings into `<project_root>/.vscode/settings.json`
+///
+/// Existing keys not managed by cargo-ros2 are preserved. The linked projects
+/// and environment variables are added to the ones already configured, and
+/// linked projects under `bindings_dir` whose manifest is gone are dropped.
+/// Trailing commas are accepted, as VS Code allows them, but a file with
+/// comments is not rewritten.
+pub fn write_vscode_settings(
+    project_root: &Path,
+    bindings_dir: &Path,
+    settings: &Value,
+) -> Result<VscodeSettings> {
+    let vscode_dir = project_root.join(".vscode");
+    let settings_path = vscode_dir.join("settings.json");
+
+    let (mut merged, has_comments) = if settings_path.exists() {
+        let content = fs::read_to_string(&settings_path)
+            .wrap_err_with(|| format!("Failed to read {}", settings_path.display()))?;
+        let (json, has_comments) = strip_jsonc(&content);
+        let merged = serde_json::from_str::<Value>(&json)
+            .wrap_err_with(|| format!("Failed to parse {}", settings_path.display()))?;
+        (merged, has_comments)
+    } else {
+        (Value::Object(Map::new()), false)
+    };
+
+    let merged_map = merged
+        .as_object_mut()
+        .ok_or_else(|| eyre!("{} is not a JSON object", settings_path.display()))?;
+
+    let mut managed = Map::new();
+    if let Some(new_settings) = settings.as_object() {
+        for (key, value) in new_settings {
+            match (merged_map.get_mut(key), value) {
+                (Some(Value::Array(existing)), Value::Array(values)) => {
+                    // Binding crates removed since the last setup
+                    existing.retain(|entry| !is_removed_binding(entry, project_root, bindings_dir));
+                    for value in values {
+                        if !existing.contains(value) {
+                            existing.push(value.clone());
+                        }
+                    }
+                }
+                (Some(Value::Object(existing)), Value::Object(values)) => {
+                    for (name, value) in values {
+                        existing.insert(name.clone(), value.clone());
+                    }
+                }
+                _ => {
+                    merged_map.insert(key.clone(), value.clone());
+                }
+            }
+            managed.insert(key.clone(), merged_map[key].clone());
+        }
+    }
+
+    if has_comments {
+        return Ok(VscodeSettings::HasComments {
+            path: settings_path,
+            settings: Value::Object(managed),
+        });
+    }
+
+    fs::create_dir_all(&vscode_dir)
+        .wrap_err_with(|| format!("Failed to create directory: {}", vscode_dir.display()))?;
+    fs::write(&settings_path, serde_json::to_string_pretty(&merged)?)
+        .wrap_err_with(|| format!("Failed to write {}", settings_path.display()))?;
+
+    Ok(VscodeSettings::Written(settings_path))
+}
+
+/// Whether a linked project is a manifest under `bindings_dir` that no longer
+/// exists; relative paths are relative to the project root, as in VS Code
+fn is_removed_binding(entry: &Value, project_root: &Path, bindings_dir: &Path) -> bool {
+    let Some(path) = entry.as_str() else {
+        return false;
+    };
+    let path = project_root.join(path);
+    path.starts_with(bindings_dir) && !path.exists()
+}
+
+/// JSON text of a JSON with comments document, without its trailing commas and
+/// `//` and `/* */` comments, and whether it had comments
+fn strip_jsonc(text: &str) -> (String, bool) {
+    let mut json = String::with_capacity(text.len());
+    let mut has_comments = false;
+    let mut chars = text.chars().peekable();
+    // Index in `json` of a comma that may turn out to be trailing
+    let mut comma = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                json.push(c);
+                while let Some(c) = chars.next() {
+                    json.push(c);
+                    match c {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+                comma = None;
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                has_comments = true;
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                has_comments = true;
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                json.push(' ');
+            }
+            ',' => {
+                comma = Some(json.len());
+                json.push(c);
+            }
+            ']' | '}' => {
+                if let Some(index) = comma.take() {
+                    json.replace_range(index..index + 1, " ");
+                }
+                json.push(c);
+            }
+            c if c.is_whitespace() => json.push(c),
+            c => {
+                comma = None;
+                json.push(c);
+            }
+        }
+    }
+    (json, has_comments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_analyzer_settings_linked_projects() {
+        let project_root = PathBuf::from("/ws/my_robot");
+        let mut bindings = BTreeMap::new();
+        bindings.insert(
+            "std_msgs".to_string(),
+            project_root.join("target/ros2_bindings/std_msgs"),
+        );
+
+        let settings = rust_analyzer_settings(&project_root, &bindings);
+        let linked = settings["rust-analyzer.linkedProjects"].as_array().unwrap();
+
+        assert_eq!(linked.len(), 2);
+        assert_eq!(linked[0], "/ws/my_robot/Cargo.toml");
+        assert_eq!(
+            linked[1],
+            "/ws/my_robot/target/ros2_bindings/std_msgs/Cargo.toml"
+        );
+    }
+
+    #[test]
+    fn test_write_vscode_settings_preserves_existing_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let vscode_dir = temp_dir.path().join(".vscode");
+        fs::create_dir_all(&vscode_dir).unwrap();
+        fs::write(
+            vscode_dir.join("settings.json"),
+            r#"{ "editor.formatOnSave": true }"#,
+        )
+        .unwrap();
+
+        let settings = rust_analyzer_settings(temp_dir.path(), &BTreeMap::new());
+        let bindings_dir = temp_dir.path().join("target/ros2_bindings");
+        let VscodeSettings::Written(path) =
+            write_vscode_settings(temp_dir.path(), &bindings_dir, &settings).unwrap()
+        else {
+            panic!("settings without comments are written");
+        };
+
+        let written: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written["editor.formatOnSave"], true);
+        assert!(written["rust-analyzer.linkedProjects"].is_array());
+    }
+
+    #[test]
+    fn test_write_vscode_settings_merges_managed_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let vscode_dir = temp_dir.path().join(".vscode");
+        fs::create_dir_all(&vscode_dir).unwrap();
+        let bindings_dir = temp_dir.path().join("target/ros2_bindings");
+        fs::create_dir_all(bindings_dir.join("std_msgs")).unwrap();
+        fs::write(bindings_dir.join("std_msgs/Cargo.toml"), "").unwrap();
+        let settings_path = vscode_dir.join("settings.json");
+        fs::write(
+            &settings_path,
+            r#"{
+                "rust-analyzer.linkedProjects": [
+                    "tools/Cargo.toml",
+                    "target/ros2_bindings/std_msgs/Cargo.toml",
+                    "target/ros2_bindings/removed_msgs/Cargo.toml",
+                ],
+                "rust-analyzer.cargo.extraEnv": { "RUSTFLAGS": "-g", },
+            }"#,
+        )
+        .unwrap();
+
+        let settings = rust_analyzer_settings(temp_dir.path(), &BTreeMap::new());
+        let project = settings["rust-analyzer.linkedProjects"][0].clone();
+        write_vscode_settings(temp_dir.path(), &bindings_dir, &settings).unwrap();
+        // Writing again adds nothing
+        write_vscode_settings(temp_dir.path(), &bindings_dir, &settings).unwrap();
+
+        let written: Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(
+            written["rust-analyzer.linkedProjects"],
+            serde_json::json!([
+                "tools/Cargo.toml",
+                "target/ros2_bindings/std_msgs/Cargo.toml",
+                project
+            ])
+        );
+        assert_eq!(written["rust-analyzer.cargo.extraEnv"]["RUSTFLAGS"], "-g");
+    }
+
+    #[test]
+    fn test_write_vscode_settings_keeps_commented_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let vscode_dir = temp_dir.path().join(".vscode");
+        fs::create_dir_all(&vscode_dir).unwrap();
+        let settings_path = vscode_dir.join("settings.json");
+        let content = r#"{
+            // Other crates of the repository
+            "rust-analyzer.linkedProjects": ["tools/Cargo.toml", /* vendored */ "a//b/Cargo.toml"],
+            "editor.formatOnSave": true,
+        }"#;
+        fs::write(&settings_path, content).unwrap();
+
+        let settings = rust_analyzer_settings(temp_dir.path(), &BTreeMap::new());
+        let project = settings["rust-analyzer.linkedProjects"][0].clone();
+        let result = write_vscode_settings(
+            temp_dir.path(),
+            &temp_dir.path().join("bindings"),
+            &settings,
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&settings_path).unwrap(), content);
+        let VscodeSettings::HasComments { path, settings } = result else {
+            panic!("a file with comments is not rewritten");
+        };
+        assert_eq!(path, settings_path);
+        assert_eq!(
+            settings["rust-analyzer.linkedProjects"],
+            serde_json::json!(["tools/Cargo.toml", "a//b/Cargo.toml", project])
+        );
+        assert!(settings.get("editor.formatOnSave").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+//! Interface inspection for `cargo ros2 msg show`
+//!
+//! Renders a message type as its raw `.msg` definition, the resolved field tree
+//! (nested types expanded across packages) and the idiomatic Rust struct that
+//! cargo-ros2-bindgen generates for it.
+
+use crate::schema::{namespaced_type, type_name, InterfaceRegistry, MessageDefinition};
+use eyre::{eyre, Result};
+use rosidl_codegen::types::{constant_value_to_rust, escape_keyword, rust_type_for_field};
+use std::fmt::Write;
+
+/// Nesting limit for the field tree (guards against malformed cyclic definitions)
+const MAX_DEPTH: usize = 16;
+
+/// Split `pkg/msg/Name` or `pkg/Name` into package and name
+pub fn parse_type_name(name: &str) -> Result<(String, String)> {
+    match name.split('/').collect::<Vec<_>>().as_slice() {
+        [package, message] | [package, "msg", message]
+            if !package.is_empty() && !message.is_empty() =>
+        {
+            Ok((package.to_string(), message.to_string()))
+        }
+        [_, kind, _] => Err(eyre!(
+            "Only messages can be shown, got '{}' interface '{}'",
+            kind,
+            name
+        )),
+        _ => Err(eyre!("Expected a type like 'pkg/msg/Type', got '{}'", name)),
+    }
+}
+
+/// Render the full `msg show` report for a loaded message type
+pub fn show_message(registry: &InterfaceRegistry, package: &str, name: &str) -> Result<String> {
+    let definition = registry.get(&type_name(package, name))?;
+
+    let mut out = String::new();
+    writeln!(out, "# {}", type_name(package, name))?;
+    writeln!(out)?;
+    writeln!(out, "## Definition")?;
+    out.push_str(definition.text.trim_end());
+    out.push_str("\n\n");
+
+    writeln!(out, "## Fields")?;
+    if definition.message.fields.is_empty() {
+        writeln!(out, "(no fields)")?;
+    }
+    field_tree(registry, definition, 0, &mut out)?;
+    writeln!(out)?;
+
+    writeln!(out, "## Rust")?;
+    out.push_str(&rust_signature(definition));
+    Ok(out)
+}
+
+/// Append the fields of `definition`, expanding nested message types
+fn field_tree(
+    registry: &InterfaceRegistry,
+    definition: &MessageDefinition,
+    depth: usize,
+    out: &mut String,
+) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    for constant in &definition.message.constants {
+        writeln!(
+            out,
+            "{}{} {}={}",
+            indent, constant.constant_type, constant.name, constant.value
+        )?;
+    }
+    for field in &definition.message.fields {
+        write!(out, "{}{}: {}", indent, field.name, field.field_type)?;
+        if let Some(default) = &field.default_value {
+            write!(out, " = {}", default)?;
+        }
+        writeln!(out)?;
+
+        if let Some((package, name)) = namespaced_type(&definition.package, &field.field_type) {
+            if depth + 1 >= MAX_DEPTH {
+                writeln!(out, "{}  ...", indent)?;
+                continue;
+            }
+            let nested = registry.get(&type_name(&package, &name))?;
+            field_tree(registry, nested, depth + 1, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Idiomatic struct and constants as generated by cargo-ros2-bindgen
+fn rust_signature(definition: &MessageDefinition) -> String {
+    let package = definition.package.as_str();
+    // Generated code refers to its own crate as `crate::` and to messages through
+    // their module; show the flat path users write
+    let user_path = |ty: String| {
+        let ty = ty.replace("crate::", &format!("{}::", package));
+        let segments: Vec<&str> = ty.split("::").collect();
+        segments
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !(i > 0 && segments[i - 1] == "msg" && i + 1 < segments.len()))
+            .map(|(_, segment)| *segment)
+            .collect::<Vec<_>>()
+            .join("::")
+    };
+
+    let mut out = format!("// {}::msg::{}\n", package, definition.name);
+    out.push_str(&format!("pub struct {} {{\n", definition.name));
+    for field in &definition.message.fields {
+        out.push_str(&format!(
+            "    pub {}: {},\n",
+            escape_keyword(&field.name),
+            user_path(rust_type_for_field(&field.field_type, false, Some(package)))
+        ));
+    }
+    out.push_str("}\n");
+
+    if definition.message.constants.is_empty() {
+        return out;
+    }
+    out.push_str(&format!("\nimpl {} {{\n", definition.name));
+    for constant in &definition.message.constants {
+        out.push_str(&format!(
+            "    pub const {}: {} = {};\n",
+            constant.name,
+            user_path(rust_type_for_field(
+                &constant.constant_type,
+                false,
+                Some(package)
+            )),
+            constant_value_to_rust(&constant.value)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> InterfaceRegistry {
+        let mut registry = InterfaceRegistry::default();
+        registry
+            .insert("builtin_interfaces", "Time", "int32 sec\nuint32 nanosec\n")
+            .unwrap();
+        registry
+            .insert(
+                "std_msgs",
+                "Header",
+                "builtin_interfaces/Time stamp\nstring frame_id\n",
+            )
+            .unwrap();
+        registry
+            .insert(
+                "my_msgs",
+                "Track",
+                "# A tracked object\nuint8 STATE_LOST=0\nstd_msgs/Header header\nPoint2[] path\nfloat32 speed 1.5\n",
+            )
+            .unwrap();
+        registry
+            .insert("my_msgs", "Point2", "float64 x\nfloat64 y\n")
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_show_message() {
+        let out = show_message(&registry(), "my_msgs", "Track").unwrap();
+
+        assert!(out.starts_with("# my_msgs/msg/Track\n\n## Definition\n# A tracked object\n"));
+        assert!(out.contains(
+            "## Fields\nuint8 STATE_LOST=0\nheader: std_msgs/Header\n  stamp: builtin_interfaces/Time\n    sec: int32\n    nanosec: uint32\n  frame_id: string\npath: Point2[]\n  x: float64\n  y: float64\nspeed: float32 = 1.5\n"
+        ));
+        assert!(out.contains("// my_msgs::msg::Track\n"));
+        assert!(out.contains("impl Track {\n    pub const STATE_LOST: u8 = 0;\n}\n"));
+        assert!(out.contains("    pub header: std_msgs::msg::Header,\n"));
+        assert!(out.contains("    pub path: std::vec::Vec<my_msgs::msg::Point2>,\n"));
+        assert!(out.contains("    pub speed: f32,\n"));
+    }
+
+    #[test]
+    fn test_missing_nested_type() {
+        let mut registry = InterfaceRegistry::default();
+        registry
+            .insert("my_msgs", "Wrapper", "geometry_msgs/Point point\n")
+            .unwrap();
+        assert!(show_message(&registry, "my_msgs", "Wrapper").is_err());
+    }
+
+    #[test]
+    fn test_parse_type_name() {
+        assert_eq!(
+            parse_type_name("std_msgs/msg/String").unwrap(),
+            ("std_msgs".to_string(), "String".to_string())
+        );
+        assert_eq!(
+            parse_type_name("std_msgs/String").unwrap(),
+            ("std_msgs".to_string(), "String".to_string())
+        );
+        assert!(parse_type_name("example_interfaces/srv/AddTwoInts").is_err());
+        assert!(parse_type_name("String").is_err());
+    }
+}
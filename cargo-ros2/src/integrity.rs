@@ -0,0 +1,302 @@
+//! Integrity verification of generated bindings
+//!
+//! The cache records a checksum of each package's interface files and of the
+//! crate generated from them. `cargo ros2 build --locked` uses this module to
+//! refuse building when either no longer matches, e.g. because generated code
+//! committed to a repository was edited by hand.
+//!
+//! The cache file itself can additionally be signed with an SSH key
+//! (`ssh-keygen -Y sign`); verifying that signature against an allowed signers
+//! file transitively authenticates every recorded checksum.
+
+use crate::cache::{self, Cache};
+use eyre::{eyre, Result, WrapErr};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Signature namespace passed to `ssh-keygen -Y`
+pub const SIGNATURE_NAMESPACE: &str = "cargo-ros2";
+
+/// A reason locked bindings cannot be trusted
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityIssue {
+    /// A dependency has no cache entry
+    NotRecorded { package: String },
+    /// The entry predates output checksums, so the crate cannot be verified
+    NoOutputChecksum { package: String },
+    /// The interface package is not in the ament index
+    SourceMissing { package: String },
+    /// The interface files differ from the ones the bindings were generated from
+    SourceChanged { package: String },
+    /// The generated crate is gone
+    OutputMissing { package: String, path: PathBuf },
+    /// The generated crate differs from what was generated
+    OutputModified { package: String, path: PathBuf },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::NotRecorded { package } => {
+                write!(f, "{}: no recorded bindings", package)
+            }
+            IntegrityIssue::NoOutputChecksum { package } => {
+                write!(
+                    f,
+                    "{}: no recorded checksum of the generated crate",
+                    package
+                )
+            }
+            IntegrityIssue::SourceMissing { package } => {
+                write!(f, "{}: package not found in the ament index", package)
+            }
+            IntegrityIssue::SourceChanged { package } => {
+                write!(f, "{}: interface files changed since generation", package)
+            }
+            IntegrityIssue::OutputMissing { package, path } => {
+                write!(
+                    f,
+                    "{}: generated crate missing at {}",
+                    package,
+                    path.display()
+                )
+            }
+            IntegrityIssue::OutputModified { package, path } => {
+                write!(
+                    f,
+                    "{}: generated crate modified at {}",
+                    package,
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+/// Check every cache entry against its interface files and generated crate
+///
+/// `ament_packages` maps package names to share directories (see
+/// `WorkflowContext::discover_ament_packages`). Issues are sorted by package.
+pub fn verify_cache(
+    cache: &Cache,
+    ament_packages: &HashMap<String, PathBuf>,
+) -> Result<Vec<IntegrityIssue>> {
+    let mut entries: Vec<_> = cache.entries().collect();
+    entries.sort_by_key(|e| &e.package_name);
+
+    let mut issues = Vec::new();
+    for entry in entries {
+        let package = entry.package_name.clone();
+
+        match ament_packages.get(&package) {
+            Some(share_dir) => {
+                let checksum = cache::calculate_package_checksum(share_dir)
+                    .wrap_err_with(|| format!("Failed to calculate checksum for {}", package))?;
+                if checksum != entry.checksum {
+                    issues.push(IntegrityIssue::SourceChanged {
+                        package: package.clone(),
+                    });
+                }
+            }
+            None => issues.push(IntegrityIssue::SourceMissing {
+                package: package.clone(),
+            }),
+        }
+
+        if !entry.output_dir.exists() {
+            issues.push(IntegrityIssue::OutputMissing {
+                package,
+                path: entry.output_dir.clone(),
+            });
+            continue;
+        }
+        match &entry.output_checksum {
+            Some(recorded) => {
+                let checksum = cache::calculate_output_checksum(&entry.output_dir)?;
+                if &checksum != recorded {
+                    issues.push(IntegrityIssue::OutputModified {
+                        package,
+                        path: entry.output_dir.clone(),
+                    });
+                }
+            }
+            None => issues.push(IntegrityIssue::NoOutputChecksum { package }),
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Path of the detached signature for a cache file (`<cache>.sig`)
+pub fn signature_path(cache_file: &Path) -> PathBuf {
+    let mut path = cache_file.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Sign the cache file with an SSH private key, writing `<cache>.sig`
+pub fn sign_cache(cache_file: &Path, key: &Path) -> Result<PathBuf> {
+    if !cache_file.exists() {
+        return Err(eyre!("No cache file at {}", cache_file.display()));
+    }
+
+    // ssh-keygen refuses to overwrite an existing signature
+    let signature = signature_path(cache_file);
+    if signature.exists() {
+        std::fs::remove_file(&signature)?;
+    }
+
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "sign", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(key)
+        .arg(cache_file)
+        .output()
+        .wrap_err("Failed to run ssh-keygen")?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "ssh-keygen failed to sign {}: {}",
+            cache_file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(signature)
+}
+
+/// Verify the cache file's signature against an allowed signers file
+///
+/// Returns the principal that signed it.
+pub fn verify_signature(cache_file: &Path, allowed_signers: &Path) -> Result<String> {
+    let signature = signature_path(cache_file);
+    if !signature.exists() {
+        return Err(eyre!(
+            "No signature for {} (expected {})",
+            cache_file.display(),
+            signature.display()
+        ));
+    }
+
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "find-principals", "-s"])
+        .arg(&signature)
+        .arg("-f")
+        .arg(allowed_signers)
+        .output()
+        .wrap_err("Failed to run ssh-keygen")?;
+    let principal = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if !output.status.success() || principal.is_empty() {
+        return Err(eyre!(
+            "{} is not signed by any key in {}",
+            cache_file.display(),
+            allowed_signers.display()
+        ));
+    }
+
+    let content = std::fs::File::open(cache_file)
+        .wrap_err_with(|| format!("Failed to read {}", cache_file.display()))?;
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "verify", "-n", SIGNATURE_NAMESPACE, "-f"])
+        .arg(allowed_signers)
+        .arg("-I")
+        .arg(&principal)
+        .arg("-s")
+        .arg(&signature)
+        .stdin(content)
+        .output()
+        .wrap_err("Failed to run ssh-keygen")?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "Invalid signature for {}: {}",
+            cache_file.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(principal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheEntry;
+    use std::fs;
+
+    fn setup(dir: &Path) -> (Cache, HashMap<String, PathBuf>) {
+        let share_dir = dir.join("share/test_msgs");
+        fs::create_dir_all(share_dir.join("msg")).unwrap();
+        fs::write(share_dir.join("msg/Point.msg"), "float64 x\n").unwrap();
+
+        let output_dir = dir.join("bindings/test_msgs");
+        fs::create_dir_all(output_dir.join("src")).unwrap();
+        fs::write(output_dir.join("src/lib.rs"), "pub mod msg;\n").unwrap();
+
+        let mut cache = Cache::new();
+        cache.insert(CacheEntry {
+            package_name: "test_msgs".to_string(),
+            checksum: cache::calculate_package_checksum(&share_dir).unwrap(),
+            ros_distro: None,
+            package_version: None,
+            timestamp: 0,
+            output_dir: output_dir.clone(),
+            generator_version: None,
+            output_checksum: Some(cache::calculate_output_checksum(&output_dir).unwrap()),
+        });
+
+        let mut packages = HashMap::new();
+        packages.insert("test_msgs".to_string(), share_dir);
+        (cache, packages)
+    }
+
+    #[test]
+    fn test_verify_unmodified_bindings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (cache, packages) = setup(temp_dir.path());
+        assert!(verify_cache(&cache, &packages).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_verify_detects_modifications() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (cache, packages) = setup(temp_dir.path());
+
+        fs::write(
+            temp_dir.path().join("bindings/test_msgs/src/lib.rs"),
+            "pub mod msg;\npub mod injected;\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("share/test_msgs/msg/Point.msg"),
+            "float64 x\nfloat64 y\n",
+        )
+        .unwrap();
+
+        let output_dir = temp_dir.path().join("bindings/test_msgs");
+        assert_eq!(
+            verify_cache(&cache, &packages).unwrap(),
+            vec![
+                IntegrityIssue::SourceChanged {
+                    package: "test_msgs".to_string()
+                },
+                IntegrityIssue::OutputModified {
+                    package: "test_msgs".to_string(),
+                    path: output_dir
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signature_path() {
+        assert_eq!(
+            signature_path(Path::new("/ws/.ros2_bindgen_cache")),
+            PathBuf::from("/ws/.ros2_bindgen_cache.sig")
+        );
+    }
+}
@@ -3,8 +3,21 @@
 //! This library provides modules for building ROS 2 Rust projects.
 
 pub mod ament_installer;
+pub mod bench;
 pub mod cache;
+pub mod compat;
 pub mod config_patcher;
 pub mod dependency_parser;
+pub mod expand;
+pub mod fuzz;
+pub mod ide;
+pub mod inspect;
+pub mod integrity;
+pub mod licenses;
+pub mod lint;
 pub mod package_discovery;
+pub mod publish;
+pub mod remote_cache;
+pub mod schema;
+pub mod upgrade;
 pub mod workflow;
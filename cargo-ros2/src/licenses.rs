@@ -0,0 +1,165 @@
+//! License report for `cargo ros2 licenses`
+//!
+//! Reads the `[package]` metadata that cargo-ros2-bindgen copies from each
+//! package.xml into the generated Cargo.toml, and groups the generated crates
+//! by license for compliance reviews.
+
+use eyre::{Result, WrapErr};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Shown for crates whose package.xml declares no license
+pub const UNKNOWN_LICENSE: &str = "UNKNOWN";
+
+/// License metadata of one generated crate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrateLicense {
+    /// Crate (ROS package) name
+    pub name: String,
+    /// License expression, if declared
+    pub license: Option<String>,
+    /// Authors, if declared
+    pub authors: Vec<String>,
+    /// Source repository, if declared
+    pub repository: Option<String>,
+    /// Generated crate directory
+    pub path: PathBuf,
+}
+
+/// Read license metadata from every generated crate
+///
+/// `bindings` maps package names to crate directories, as returned by
+/// `WorkflowContext::generated_bindings`.
+pub fn collect(bindings: &BTreeMap<String, PathBuf>) -> Result<Vec<CrateLicense>> {
+    bindings
+        .iter()
+        .map(|(name, path)| read_crate_license(name, path))
+        .collect()
+}
+
+fn read_crate_license(name: &str, crate_dir: &Path) -> Result<CrateLicense> {
+    let manifest_path = crate_dir.join("Cargo.toml");
+    let content = std::fs::read_to_string(&manifest_path)
+        .wrap_err_with(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: toml::Table = content
+        .parse()
+        .wrap_err_with(|| format!("Failed to parse {}", manifest_path.display()))?;
+
+    let package = manifest.get("package").and_then(|p| p.as_table());
+    let string = |key| {
+        package
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let authors = package
+        .and_then(|p| p.get("authors"))
+        .and_then(|v| v.as_array())
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|a| a.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(CrateLicense {
+        name: name.to_string(),
+        license: string("license"),
+        authors,
+        repository: string("repository"),
+        path: crate_dir.to_path_buf(),
+    })
+}
+
+/// Crate names grouped by license expression
+pub fn group_by_license(crates: &[CrateLicense]) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for krate in crates {
+        let license = krate.license.as_deref().unwrap_or(UNKNOWN_LICENSE);
+        groups
+            .entry(license.to_string())
+            .or_default()
+            .push(krate.name.clone());
+    }
+    groups
+}
+
+/// Render the human-readable report: one line per crate, then a summary
+pub fn render_report(crates: &[CrateLicense]) -> Result<String> {
+    let mut out = String::new();
+    writeln!(out, "{:<30} {:<30} Repository", "Package", "License")?;
+    writeln!(out, "{}", "-".repeat(100))?;
+    for krate in crates {
+        writeln!(
+            out,
+            "{:<30} {:<30} {}",
+            krate.name,
+            krate.license.as_deref().unwrap_or(UNKNOWN_LICENSE),
+            krate.repository.as_deref().unwrap_or("-")
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "Summary:")?;
+    for (license, names) in group_by_license(crates) {
+        writeln!(out, "  {} ({}): {}", license, names.len(), names.join(", "))?;
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_crate(dir: &Path, name: &str, package_metadata: &str) -> PathBuf {
+        let crate_dir = dir.join(name);
+        fs::create_dir_all(&crate_dir).unwrap();
+        fs::write(
+            crate_dir.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n{}\n[dependencies]\n",
+                name, package_metadata
+            ),
+        )
+        .unwrap();
+        crate_dir
+    }
+
+    #[test]
+    fn test_collect_and_group() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut bindings = BTreeMap::new();
+        for (name, metadata) in [
+            (
+                "geometry_msgs",
+                "license = \"Apache-2.0\"\nauthors = [\"Tully Foote\"]\nrepository = \"https://github.com/ros2/common_interfaces\"",
+            ),
+            ("std_msgs", "license = \"Apache-2.0\""),
+            ("my_msgs", ""),
+        ] {
+            bindings.insert(
+                name.to_string(),
+                write_crate(temp_dir.path(), name, metadata),
+            );
+        }
+
+        let crates = collect(&bindings).unwrap();
+        assert_eq!(crates.len(), 3);
+        assert_eq!(crates[0].name, "geometry_msgs");
+        assert_eq!(crates[0].authors, ["Tully Foote"]);
+        assert_eq!(crates[1].license, None);
+
+        let groups = group_by_license(&crates);
+        assert_eq!(groups["Apache-2.0"], ["geometry_msgs", "std_msgs"]);
+        assert_eq!(groups[UNKNOWN_LICENSE], ["my_msgs"]);
+
+        let report = render_report(&crates).unwrap();
+        assert!(report.contains("  Apache-2.0 (2): geometry_msgs, std_msgs\n"));
+        assert!(report.contains("  UNKNOWN (1): my_msgs\n"));
+    }
+}
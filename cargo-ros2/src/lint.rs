@@ -0,0 +1,106 @@
+//! Style lints for the interface files of ROS packages
+//!
+//! `cargo ros2 build --lint-interfaces` runs the naming lints of
+//! [`rosidl_parser::lint`] over the `.msg`, `.srv` and `.action` files of the
+//! project's ROS dependencies and prints them as warnings.
+
+use eyre::{Result, WrapErr};
+use rosidl_parser::Lint;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Lint of one interface file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLint {
+    pub path: PathBuf,
+    pub lint: Lint,
+}
+
+/// Formats as `path:line:column: message`, or `path: message` without a span
+impl fmt::Display for FileLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.lint.span {
+            Some(span) => write!(
+                f,
+                "{}:{}:{}: {}",
+                self.path.display(),
+                span.line,
+                span.column,
+                self.lint.message
+            ),
+            None => write!(f, "{}: {}", self.path.display(), self.lint.message),
+        }
+    }
+}
+
+/// Lint the interface files of the package installed in `share_dir`
+///
+/// Files that fail to parse are skipped; generating bindings reports them.
+pub fn lint_package(share_dir: &Path) -> Result<Vec<FileLint>> {
+    let mut lints = Vec::new();
+    for kind in ["msg", "srv", "action"] {
+        let dir = share_dir.join(kind);
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut paths = std::fs::read_dir(&dir)
+            .wrap_err_with(|| format!("Failed to read {}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == kind));
+        paths.sort();
+
+        for path in paths {
+            let content = std::fs::read_to_string(&path)
+                .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if let Ok(file_lints) = rosidl_parser::lint_interface(&name, &content) {
+                lints.extend(file_lints.into_iter().map(|lint| FileLint {
+                    path: path.clone(),
+                    lint,
+                }));
+            }
+        }
+    }
+    Ok(lints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_lint_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let share = dir.path();
+        fs::create_dir_all(share.join("msg")).unwrap();
+        fs::create_dir_all(share.join("srv")).unwrap();
+        fs::write(share.join("msg/Status.msg"), "uint8 ok=0\nuint8 level\n").unwrap();
+        fs::write(share.join("msg/Status.idl"), "not linted").unwrap();
+        fs::write(share.join("msg/Broken.msg"), "int32 = 1\n").unwrap();
+        fs::write(share.join("srv/get_map.srv"), "---\nbool success\n").unwrap();
+
+        let lints: Vec<String> = lint_package(share)
+            .unwrap()
+            .iter()
+            .map(|lint| lint.to_string())
+            .collect();
+        assert_eq!(
+            lints,
+            [
+                format!(
+                    "{}:1:7: constant name `ok` is not SCREAMING_CASE",
+                    share.join("msg/Status.msg").display()
+                ),
+                format!(
+                    "{}: interface name `get_map` is not CamelCase",
+                    share.join("srv/get_map.srv").display()
+                ),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use cargo_ros2::remote_cache::RemoteCache;
+use cargo_ros2::schema::SchemaFormat;
 use cargo_ros2::workflow::WorkflowContext;
-use clap::{Parser, Subcommand};
+use clap::{ColorChoice, Parser, Subcommand};
 use eyre::{eyre, Result, WrapErr};
 use std::env;
 use std::path::{Path, PathBuf};
@@ -31,6 +33,22 @@ enum Ros2Command {
         /// Generate bindings only (don't run cargo build)
         #[arg(long)]
         bindings_only: bool,
+
+        /// Don't regenerate; fail if recorded bindings were modified or are stale
+        #[arg(long)]
+        locked: bool,
+
+        /// Also require the cache to be signed by a key in this allowed signers file
+        #[arg(long, value_name = "FILE", requires = "locked")]
+        allowed_signers: Option<PathBuf>,
+
+        /// Pull prebuilt bindings from (and push new ones to) a remote cache (http(s):// or s3://)
+        #[arg(long, value_name = "URL", conflicts_with = "locked")]
+        remote_cache: Option<String>,
+
+        /// Warn about interface names that break the ROS naming conventions
+        #[arg(long)]
+        lint_interfaces: bool,
     },
 
     /// Check the project with ROS 2 bindings
@@ -38,6 +56,24 @@ enum Ros2Command {
         /// Generate bindings only (don't run cargo check)
         #[arg(long)]
         bindings_only: bool,
+
+        /// Don't regenerate; fail if recorded bindings were modified or are stale
+        #[arg(long)]
+        locked: bool,
+
+        /// Also require the cache to be signed by a key in this allowed signers file
+        #[arg(long, value_name = "FILE", requires = "locked")]
+        allowed_signers: Option<PathBuf>,
+
+        /// Pull prebuilt bindings from (and push new ones to) a remote cache (http(s):// or s3://)
+        #[arg(long, value_name = "URL", conflicts_with = "locked")]
+        remote_cache: Option<String>,
+    },
+
+    /// Regenerate bindings whenever the interface files of their packages change
+    Watch {
+        /// Packages to watch (defaults to the project's ROS dependencies)
+        packages: Vec<String>,
     },
 
     /// Clean generated bindings and cache
@@ -49,6 +85,143 @@ enum Ros2Command {
         cache_command: CacheCommand,
     },
 
+    /// Locate generated binding crates
+    Bindings {
+        #[command(subcommand)]
+        bindings_command: BindingsCommand,
+    },
+
+    /// Report the licenses of all generated binding crates
+    Licenses {
+        /// Print the crates and their metadata as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Stage generated crates for a registry and optionally publish them
+    PublishBindings {
+        /// Registry to publish to (as configured in .cargo/config.toml)
+        #[arg(long)]
+        registry: String,
+
+        /// Run cargo publish after staging and verifying
+        #[arg(long)]
+        publish: bool,
+
+        /// Skip building the staged crates
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Staging directory (defaults to target/ros2_publish)
+        #[arg(long)]
+        staging_dir: Option<PathBuf>,
+    },
+
+    /// Regenerate bindings produced by an older cargo-ros2 and summarize API changes
+    UpgradeBindings {
+        /// Only report which packages would be regenerated
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Prepare the project for rust-analyzer (bindings, patches, IDE settings)
+    IdeSetup {
+        /// Merge the settings into .vscode/settings.json instead of printing them
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Export message schemas for visualization tools
+    Schema {
+        #[command(subcommand)]
+        schema_command: SchemaCommand,
+    },
+
+    /// Report breaking changes between two versions of an interface package
+    Compat {
+        /// Old version: a package directory, or a git revision with --git
+        old: String,
+
+        /// New version directory (defaults to the current directory)
+        new: Option<PathBuf>,
+
+        /// Treat OLD as a git revision of the NEW directory
+        #[arg(long)]
+        git: bool,
+    },
+
+    /// Inspect message definitions
+    Msg {
+        #[command(subcommand)]
+        msg_command: MsgCommand,
+    },
+
+    /// Print the code generated for a single interface
+    Expand {
+        /// Interface (e.g. geometry_msgs/Point or example_interfaces/srv/AddTwoInts)
+        type_name: String,
+
+        /// Only print the C-compatible RMW layer
+        #[arg(long, conflicts_with = "idiomatic")]
+        rmw: bool,
+
+        /// Only print the idiomatic layer
+        #[arg(long)]
+        idiomatic: bool,
+
+        /// Generate as for rclrs compatibility mode
+        #[arg(long)]
+        rclrs: bool,
+
+        /// When to use syntax highlighting
+        #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+    },
+
+    /// Emit OMG IDL files for the package's .msg/.srv/.action interfaces
+    Idl {
+        /// Package directory (defaults to the current directory)
+        dir: Option<PathBuf>,
+
+        /// Package name used for the IDL modules (defaults to the directory name)
+        #[arg(long)]
+        package: Option<String>,
+
+        /// Output directory (defaults to target/idl/<package>)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Benchmark code generation over every package in the ament index
+    BenchCodegen {
+        /// Baseline report to compare against
+        /// (defaults to target/ros2_bench/codegen-baseline.json)
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+
+        /// Store this run as the new baseline
+        #[arg(long)]
+        save_baseline: bool,
+
+        /// Slowdown of a phase, in percent, that fails the run
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+    },
+
+    /// Round-trip random instances of every message in a package
+    FuzzInterfaces {
+        /// Package whose messages to fuzz (bindings must be generated)
+        package: String,
+
+        /// Random instances per message
+        #[arg(long, default_value_t = 1000)]
+        cases: u64,
+
+        /// Seed of the random instances (rerun with the same seed to reproduce)
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+
     /// Show information about a ROS 2 package
     Info {
         /// Package name to show information about
@@ -75,6 +248,43 @@ enum Ros2Command {
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum BindingsCommand {
+    /// Print the generated crate path for a package
+    Path {
+        /// Package name (may be omitted with --json to list all packages)
+        package: Option<String>,
+
+        /// Print a JSON object mapping package names to crate paths
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MsgCommand {
+    /// Print a message's definition, resolved field tree and Rust struct
+    Show {
+        /// Message type (e.g. geometry_msgs/msg/PoseStamped)
+        type_name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SchemaCommand {
+    /// Emit schemas for every message, service and action section reachable from
+    /// the project's ROS dependencies
+    Export {
+        /// Schema format
+        #[arg(long, value_enum, default_value_t = SchemaFormat::Jsonschema)]
+        format: SchemaFormat,
+
+        /// Write the schemas to a file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[derive(Debug, Subcommand)]
 enum CacheCommand {
     /// List cached package bindings
@@ -88,6 +298,13 @@ enum CacheCommand {
 
     /// Clean all cached bindings
     Clean,
+
+    /// Sign the cache with an SSH key for `build --locked --allowed-signers`
+    Sign {
+        /// SSH private key (or public key held by ssh-agent)
+        #[arg(long)]
+        key: PathBuf,
+    },
 }
 
 fn main() -> Result<()> {
@@ -97,10 +314,23 @@ fn main() -> Result<()> {
     let project_root = env::current_dir()?;
 
     // Create workflow context
-    let ctx = WorkflowContext::new(project_root, args.verbose);
+    let mut ctx = WorkflowContext::new(project_root, args.verbose);
 
     match args.command {
-        Ros2Command::Build { bindings_only } => {
+        Ros2Command::Build {
+            bindings_only,
+            locked,
+            allowed_signers,
+            remote_cache,
+            lint_interfaces,
+        } => {
+            ctx.locked = locked;
+            ctx.lint_interfaces = lint_interfaces;
+            ctx.allowed_signers = allowed_signers;
+            ctx.remote_cache = remote_cache
+                .as_deref()
+                .map(RemoteCache::parse)
+                .transpose()?;
             ctx.run(bindings_only)?;
             if !bindings_only {
                 println!("✓ Build complete!");
@@ -109,9 +339,20 @@ fn main() -> Result<()> {
             }
         }
 
-        Ros2Command::Check { bindings_only } => {
+        Ros2Command::Check {
+            bindings_only,
+            locked,
+            allowed_signers,
+            remote_cache,
+        } => {
             // For check, we run the same workflow but would invoke cargo check instead of build
             // For now, we just run the workflow
+            ctx.locked = locked;
+            ctx.allowed_signers = allowed_signers;
+            ctx.remote_cache = remote_cache
+                .as_deref()
+                .map(RemoteCache::parse)
+                .transpose()?;
             ctx.run(bindings_only)?;
             if !bindings_only {
                 println!("✓ Check complete!");
@@ -120,6 +361,10 @@ fn main() -> Result<()> {
             }
         }
 
+        Ros2Command::Watch { packages } => {
+            watch_bindings(&ctx, packages)?;
+        }
+
         Ros2Command::Clean => {
             clean_bindings(&ctx)?;
             println!("✓ Cleaned bindings and cache!");
@@ -129,6 +374,89 @@ fn main() -> Result<()> {
             handle_cache_command(&ctx, &cache_command)?;
         }
 
+        Ros2Command::Bindings { bindings_command } => {
+            handle_bindings_command(&ctx, &bindings_command)?;
+        }
+
+        Ros2Command::Licenses { json } => {
+            let bindings = ctx.generated_bindings()?;
+            if bindings.is_empty() {
+                return Err(eyre!(
+                    "No generated bindings found. Run 'cargo ros2 build --bindings-only' first."
+                ));
+            }
+
+            let crates = cargo_ros2::licenses::collect(&bindings)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&crates)?);
+            } else {
+                print!("{}", cargo_ros2::licenses::render_report(&crates)?);
+            }
+        }
+
+        Ros2Command::PublishBindings {
+            registry,
+            publish,
+            no_verify,
+            staging_dir,
+        } => {
+            publish_bindings(&ctx, &registry, publish, !no_verify, staging_dir)?;
+        }
+
+        Ros2Command::UpgradeBindings { dry_run } => {
+            upgrade_bindings(&ctx, dry_run)?;
+        }
+
+        Ros2Command::IdeSetup { write } => {
+            ide_setup(&ctx, write)?;
+        }
+
+        Ros2Command::Schema { schema_command } => {
+            handle_schema_command(&ctx, &schema_command)?;
+        }
+
+        Ros2Command::Compat { old, new, git } => {
+            check_compat(&ctx, &old, new.as_deref(), git)?;
+        }
+
+        Ros2Command::Msg { msg_command } => {
+            handle_msg_command(&ctx, &msg_command)?;
+        }
+
+        Ros2Command::Expand {
+            type_name,
+            rmw,
+            idiomatic,
+            rclrs,
+            color,
+        } => {
+            expand_interface(&ctx, &type_name, rmw, idiomatic, rclrs, color)?;
+        }
+
+        Ros2Command::Idl {
+            dir,
+            package,
+            output,
+        } => {
+            emit_idl(&ctx, dir.as_deref(), package, output)?;
+        }
+
+        Ros2Command::BenchCodegen {
+            baseline,
+            save_baseline,
+            threshold,
+        } => {
+            bench_codegen(&ctx, baseline, save_baseline, threshold)?;
+        }
+
+        Ros2Command::FuzzInterfaces {
+            package,
+            cases,
+            seed,
+        } => {
+            fuzz_interfaces(&ctx, &package, cases, seed)?;
+        }
+
         Ros2Command::Info { package } => {
             show_package_info(&ctx, &package)?;
         }
@@ -169,6 +497,12 @@ fn clean_bindings(ctx: &WorkflowContext) -> Result<()> {
         }
     }
 
+    // Remove the cache signature, which no longer matches anything
+    let signature = cargo_ros2::integrity::signature_path(&ctx.cache_file);
+    if signature.exists() {
+        std::fs::remove_file(&signature)?;
+    }
+
     // Remove .cargo/config.toml patches (TODO: only remove ROS patches, not entire file)
     let cargo_config = ctx.project_root.join(".cargo").join("config.toml");
     if cargo_config.exists() && ctx.verbose {
@@ -237,6 +571,583 @@ fn handle_cache_command(ctx: &WorkflowContext, command: &CacheCommand) -> Result
             clean_bindings(ctx)?;
             println!("✓ Cache cleaned!");
         }
+
+        CacheCommand::Sign { key } => {
+            let signature = cargo_ros2::integrity::sign_cache(&ctx.cache_file, key)?;
+            println!("✓ Signed cache: {}", signature.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_bindings_command(ctx: &WorkflowContext, command: &BindingsCommand) -> Result<()> {
+    match command {
+        BindingsCommand::Path { package, json } => {
+            let mut bindings = ctx.generated_bindings()?;
+
+            if let Some(package) = package {
+                let path = bindings.remove(package).ok_or_else(|| {
+                    eyre!(
+                        "No bindings found for '{}'. Run 'cargo ros2 build --bindings-only' first.",
+                        package
+                    )
+                })?;
+
+                if *json {
+                    let value = serde_json::json!({ package.as_str(): path });
+                    println!("{}", serde_json::to_string_pretty(&value)?);
+                } else {
+                    println!("{}", path.display());
+                }
+            } else if *json {
+                println!("{}", serde_json::to_string_pretty(&bindings)?);
+            } else {
+                return Err(eyre!(
+                    "A package name is required (or pass --json to list all packages)"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_msg_command(ctx: &WorkflowContext, command: &MsgCommand) -> Result<()> {
+    use cargo_ros2::inspect::{parse_type_name, show_message};
+    use cargo_ros2::schema::InterfaceRegistry;
+
+    match command {
+        MsgCommand::Show { type_name } => {
+            let (package, name) = parse_type_name(type_name)?;
+            let ament_packages = ctx.discover_ament_packages()?;
+            let registry =
+                InterfaceRegistry::load(std::slice::from_ref(&package), &ament_packages)?;
+            print!("{}", show_message(&registry, &package, &name)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn expand_interface(
+    ctx: &WorkflowContext,
+    type_name: &str,
+    rmw: bool,
+    idiomatic: bool,
+    rclrs: bool,
+    color: ColorChoice,
+) -> Result<()> {
+    use cargo_ros2::expand::{expand, highlight, parse_interface_name, render};
+    use cargo_ros2_bindgen::ament::Package;
+    use std::io::IsTerminal;
+
+    let (package, kind, name) = parse_interface_name(type_name)?;
+    let ament_packages = ctx.discover_ament_packages()?;
+    let share_dir = ament_packages
+        .get(&package)
+        .ok_or_else(|| eyre!("Package '{}' not found in ament index", package))?;
+    let package = Package::from_share_dir(share_dir.clone())?;
+
+    // Match cargo-ros2-bindgen, which emits service events when service_msgs is installed
+    let options = rosidl_codegen::GeneratorOptions {
+        rclrs_compat: rclrs,
+        service_events: ament_packages.contains_key(rosidl_codegen::SERVICE_MSGS_PACKAGE),
+        ..Default::default()
+    };
+    let expansion = expand(&package, kind.as_deref(), &name, options)?;
+    // With neither flag, print both layers
+    let output = render(&expansion, rmw || !idiomatic, idiomatic || !rmw);
+
+    let colorize = match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::io::stdout().is_terminal(),
+    };
+    if colorize {
+        print!("{}", highlight(&output));
+    } else {
+        print!("{}", output);
+    }
+
+    Ok(())
+}
+
+fn fuzz_interfaces(ctx: &WorkflowContext, package: &str, cases: u64, seed: u64) -> Result<()> {
+    use cargo_ros2::fuzz::{self, RoundTrip};
+    use cargo_ros2_bindgen::ament::Package;
+
+    let bindings = ctx.generated_bindings()?;
+    let bindings_dir = bindings.get(package).ok_or_else(|| {
+        eyre!(
+            "No bindings found for '{}'. Run 'cargo ros2 build --bindings-only' first.",
+            package
+        )
+    })?;
+
+    let ament_packages = ctx.discover_ament_packages()?;
+    let share_dir = ament_packages
+        .get(package)
+        .ok_or_else(|| eyre!("Package '{}' not found in ament index", package))?;
+    let messages = Package::from_share_dir(share_dir.clone())?
+        .interfaces
+        .messages;
+    if messages.is_empty() {
+        return Err(eyre!("Package '{}' has no messages", package));
+    }
+
+    let harness_dir = fuzz::harness_dir(&ctx.project_root, package);
+    let round_trip = fuzz::write_harness(&harness_dir, package, bindings_dir, &messages)?;
+    println!(
+        "Fuzzing {} message(s) of {} through {}, {} case(s) each (seed {})...",
+        messages.len(),
+        package,
+        match round_trip {
+            RoundTrip::Rmw => "idiomatic ↔ RMW",
+            RoundTrip::Cdr => "CDR",
+        },
+        cases,
+        seed
+    );
+    if ctx.verbose {
+        eprintln!("Harness: {}", harness_dir.display());
+    }
+
+    fuzz::run_harness(&harness_dir, cases, seed)?;
+    println!("✓ All messages round-tripped!");
+    Ok(())
+}
+
+fn handle_schema_command(ctx: &WorkflowContext, command: &SchemaCommand) -> Result<()> {
+    use cargo_ros2::schema::InterfaceRegistry;
+
+    match command {
+        SchemaCommand::Export { format, output } => {
+            let dependencies = ctx.discover_ros_dependencies()?;
+            if dependencies.is_empty() {
+                return Err(eyre!("No ROS 2 dependencies found in Cargo.toml"));
+            }
+
+            let roots: Vec<String> = dependencies.into_iter().map(|d| d.name).collect();
+            let ament_packages = ctx.discover_ament_packages()?;
+            let registry = InterfaceRegistry::load(&roots, &ament_packages)?;
+            let schemas = serde_json::to_string_pretty(&registry.export(*format)?)?;
+
+            match output {
+                Some(path) => {
+                    std::fs::write(path, schemas)?;
+                    println!(
+                        "✓ Exported {} message schema(s) to {}",
+                        registry.type_names().count(),
+                        path.display()
+                    );
+                }
+                None => println!("{}", schemas),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_compat(ctx: &WorkflowContext, old: &str, new: Option<&Path>, git: bool) -> Result<()> {
+    use cargo_ros2::compat::{compare, InterfaceSet};
+
+    let new_dir = new.unwrap_or(&ctx.project_root);
+    let old_set = if git {
+        InterfaceSet::from_git(new_dir, old)?
+    } else {
+        InterfaceSet::from_dir(Path::new(old))?
+    };
+    let new_set = InterfaceSet::from_dir(new_dir)?;
+
+    if old_set.is_empty() && new_set.is_empty() {
+        return Err(eyre!("No .msg, .srv or .action files found to compare"));
+    }
+
+    let report = compare(&old_set, &new_set);
+    if report.entries.is_empty() {
+        println!("✓ No interface changes");
+        return Ok(());
+    }
+
+    for entry in &report.entries {
+        println!("{}", entry);
+    }
+    println!();
+
+    let breaking = report.breaking_count();
+    if breaking > 0 {
+        return Err(eyre!("{} breaking interface change(s) found", breaking));
+    }
+
+    println!("✓ {} compatible interface change(s)", report.entries.len());
+    Ok(())
+}
+
+fn emit_idl(
+    ctx: &WorkflowContext,
+    dir: Option<&Path>,
+    package: Option<String>,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    use cargo_ros2::compat::InterfaceSet;
+    use rosidl_codegen::idl::{action_to_idl, message_to_idl, service_to_idl};
+
+    let dir = dir.unwrap_or(&ctx.project_root);
+    let package = match package {
+        Some(package) => package,
+        None => dir
+            .canonicalize()
+            .ok()
+            .and_then(|d| d.file_name().map(|n| n.to_string_lossy().to_string()))
+            .ok_or_else(|| eyre!("Cannot infer package name, pass --package"))?,
+    };
+    let set = InterfaceSet::from_dir(dir)?;
+    if set.is_empty() {
+        return Err(eyre!(
+            "No .msg, .srv or .action files found in {}",
+            dir.display()
+        ));
+    }
+
+    let output = output.unwrap_or_else(|| ctx.project_root.join("target/idl").join(&package));
+    let mut files = Vec::new();
+    for (key, message) in &set.messages {
+        let name = key.trim_start_matches("msg/");
+        files.push((key, message_to_idl(&package, name, message)));
+    }
+    for (key, service) in &set.services {
+        let name = key.trim_start_matches("srv/");
+        files.push((key, service_to_idl(&package, name, service)));
+    }
+    for (key, action) in &set.actions {
+        let name = key.trim_start_matches("action/");
+        files.push((key, action_to_idl(&package, name, action)));
+    }
+
+    for (key, content) in &files {
+        let path = output.join(format!("{}.idl", key));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .wrap_err_with(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&path, content)
+            .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+        if ctx.verbose {
+            eprintln!("  wrote {}", path.display());
+        }
+    }
+
+    println!(
+        "✓ Wrote {} IDL file(s) to {}",
+        files.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn bench_codegen(
+    ctx: &WorkflowContext,
+    baseline: Option<PathBuf>,
+    save_baseline: bool,
+    threshold: f64,
+) -> Result<()> {
+    use cargo_ros2::bench::{self, BenchReport};
+    use cargo_ros2_bindgen::ament::AmentIndex;
+
+    let bench_dir = ctx.project_root.join("target").join("ros2_bench");
+    let baseline_path = baseline.unwrap_or_else(|| bench_dir.join("codegen-baseline.json"));
+
+    let index =
+        AmentIndex::from_env().wrap_err("Failed to load ament index (is ROS 2 sourced?)")?;
+    let mut packages: Vec<_> = index
+        .packages()
+        .values()
+        .filter(|p| p.has_interfaces())
+        .collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    if packages.is_empty() {
+        return Err(eyre!("No interface packages found in the ament index"));
+    }
+
+    println!("Benchmarking codegen for {} package(s)...", packages.len());
+    let output_dir = bench_dir.join("output");
+    let report = bench::run(&packages, &output_dir);
+    std::fs::remove_dir_all(&output_dir).ok();
+
+    if ctx.verbose {
+        for (name, package) in &report.packages {
+            eprintln!(
+                "  {:<32} {:>4} interface(s) {:>10.1} ms",
+                name,
+                package.interfaces,
+                package.timings.total_ms()
+            );
+        }
+    }
+    for (name, package) in &report.packages {
+        if let Some(error) = &package.error {
+            eprintln!("  ⚠ {}: {}", name, error);
+        }
+    }
+
+    let interfaces: usize = report.packages.values().map(|p| p.interfaces).sum();
+    let bytes: usize = report.packages.values().map(|p| p.generated_bytes).sum();
+    println!(
+        "  {} interface(s), {:.1} MiB generated",
+        interfaces,
+        bytes as f64 / (1024.0 * 1024.0)
+    );
+    println!(
+        "  parse {:.1} ms, render {:.1} ms, write {:.1} ms",
+        report.total.parse_ms, report.total.render_ms, report.total.write_ms
+    );
+    if let Some(rss) = report.peak_rss_kib {
+        println!("  peak RSS {:.1} MiB", rss as f64 / 1024.0);
+    }
+
+    let mut regressions = 0;
+    if baseline_path.exists() {
+        let previous = BenchReport::load(&baseline_path)?;
+        println!();
+        println!(
+            "Compared to {} (cargo-ros2 {}):",
+            baseline_path.display(),
+            previous.generator_version
+        );
+        println!(
+            "{:<8} {:>13} {:>13} {:>9}",
+            "phase", "baseline", "current", "change"
+        );
+        for phase in report.compare(&previous) {
+            println!("{}", phase);
+            if phase.change_percent() > threshold {
+                regressions += 1;
+            }
+        }
+    } else if !save_baseline {
+        println!(
+            "No baseline at {} (store one with --save-baseline)",
+            baseline_path.display()
+        );
+    }
+
+    if save_baseline {
+        report.save(&baseline_path)?;
+        println!("✓ Saved baseline to {}", baseline_path.display());
+    }
+
+    if report.failure_count() > 0 {
+        eprintln!("⚠ {} package(s) failed to generate", report.failure_count());
+    }
+    if regressions > 0 {
+        return Err(eyre!(
+            "{} phase(s) regressed by more than {}%",
+            regressions,
+            threshold
+        ));
+    }
+    Ok(())
+}
+
+fn publish_bindings(
+    ctx: &WorkflowContext,
+    registry: &str,
+    publish: bool,
+    verify: bool,
+    staging_dir: Option<PathBuf>,
+) -> Result<()> {
+    use cargo_ros2_bindgen::ament::Package;
+    use std::collections::BTreeMap;
+
+    let bindings = ctx.generated_bindings()?;
+    if bindings.is_empty() {
+        return Err(eyre!(
+            "No generated bindings found. Run 'cargo ros2 build --bindings-only' first."
+        ));
+    }
+
+    // Publish under the interface package versions; crates not from the
+    // ament index (the bundled runtime) keep their own version
+    let ament_packages = ctx.discover_ament_packages()?;
+    let mut versions = BTreeMap::new();
+    for name in bindings.keys() {
+        let Some(share_dir) = ament_packages.get(name) else {
+            continue;
+        };
+        match Package::from_share_dir(share_dir.clone())?
+            .manifest()
+            .version
+        {
+            Some(version) => {
+                versions.insert(name.clone(), version);
+            }
+            None => eprintln!("Warning: {} has no package.xml version", name),
+        }
+    }
+
+    let staging_dir =
+        staging_dir.unwrap_or_else(|| ctx.project_root.join("target").join("ros2_publish"));
+    let staged = cargo_ros2::publish::stage(&bindings, &versions, registry, &staging_dir)?;
+
+    println!(
+        "Staged {} crate(s) in {}:",
+        staged.len(),
+        staging_dir.display()
+    );
+    for krate in &staged {
+        println!("  {} {}", krate.name, krate.version);
+    }
+
+    if verify {
+        for krate in &staged {
+            if ctx.verbose {
+                eprintln!("Verifying {}...", krate.name);
+            }
+            cargo_ros2::publish::verify(krate)?;
+        }
+        println!("✓ All staged crates build");
+    }
+
+    if publish {
+        for krate in &staged {
+            cargo_ros2::publish::publish(krate, registry)?;
+            println!("✓ Published {} {}", krate.name, krate.version);
+        }
+    } else {
+        println!("Run again with --publish to publish to '{}'.", registry);
+    }
+
+    Ok(())
+}
+
+fn watch_bindings(ctx: &WorkflowContext, mut packages: Vec<String>) -> Result<()> {
+    if packages.is_empty() {
+        packages = ctx
+            .discover_ros_dependencies()?
+            .into_iter()
+            .map(|dep| dep.name)
+            .collect();
+    }
+    if packages.is_empty() {
+        return Err(eyre!(
+            "No ROS dependencies found in Cargo.toml; name the packages to watch"
+        ));
+    }
+    ctx.watch_bindings(&packages)
+}
+
+fn upgrade_bindings(ctx: &WorkflowContext, dry_run: bool) -> Result<()> {
+    use cargo_ros2::cache::{Cache, GENERATOR_VERSION};
+    use cargo_ros2::upgrade::ApiSurface;
+
+    let cache = Cache::load(&ctx.cache_file)?;
+    let outdated: Vec<_> = cache
+        .outdated_entries(GENERATOR_VERSION)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    if outdated.is_empty() {
+        println!(
+            "All cached bindings were generated by code generator {}.",
+            GENERATOR_VERSION
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} package(s) generated by a different code generator version:",
+        outdated.len()
+    );
+    for entry in &outdated {
+        println!(
+            "  {} ({} -> {})",
+            entry.package_name,
+            entry.generator_version.as_deref().unwrap_or("unknown"),
+            GENERATOR_VERSION
+        );
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    let ament_packages = ctx.discover_ament_packages()?;
+    let mut total_changes = 0;
+
+    for entry in &outdated {
+        let share_dir = ament_packages
+            .get(&entry.package_name)
+            .ok_or_else(|| eyre!("Package '{}' not found in ament index", entry.package_name))?;
+
+        let old_api = ApiSurface::from_crate(&entry.output_dir)?;
+        let output_dir = ctx.generate_bindings(&entry.package_name)?;
+        ctx.update_cache(&entry.package_name, share_dir, output_dir.clone())?;
+        let new_api = ApiSurface::from_crate(&output_dir)?;
+
+        let changes = old_api.diff(&new_api);
+        println!();
+        if changes.is_empty() {
+            println!("{}: no API changes", entry.package_name);
+        } else {
+            println!("{}: {} API change(s)", entry.package_name, changes.len());
+            for change in &changes {
+                println!("  {}", change);
+            }
+        }
+        total_changes += changes.len();
+    }
+    ctx.write_workspace_manifest()?;
+
+    println!();
+    println!(
+        "✓ Upgraded {} package(s), {} API change(s)",
+        outdated.len(),
+        total_changes
+    );
+
+    Ok(())
+}
+
+fn ide_setup(ctx: &WorkflowContext, write: bool) -> Result<()> {
+    use cargo_ros2::ide::{rust_analyzer_settings, write_vscode_settings, VscodeSettings};
+
+    // Generate bindings without building
+    ctx.run(true)?;
+
+    // Patch every generated crate, including ones that were already cached
+    let bindings = ctx.generated_bindings()?;
+    let patches: Vec<(String, PathBuf)> = bindings
+        .iter()
+        .filter(|(name, _)| name.as_str() != cargo_ros2_bindgen::generator::RUNTIME_CRATE)
+        .map(|(name, path)| (name.clone(), path.clone()))
+        .collect();
+    if !patches.is_empty() {
+        ctx.patch_cargo_config(&patches)?;
+    }
+    ctx.write_workspace_manifest()?;
+
+    let settings = rust_analyzer_settings(&ctx.project_root, &bindings);
+
+    if write {
+        match write_vscode_settings(&ctx.project_root, &ctx.output_dir, &settings)? {
+            VscodeSettings::Written(settings_path) => {
+                println!("✓ IDE setup complete!");
+                println!("  Settings written to {}", settings_path.display());
+            }
+            VscodeSettings::HasComments { path, settings } => {
+                // Rewriting the file would drop its comments
+                eprintln!(
+                    "{} has comments, so it was left unchanged; set these keys in it:",
+                    path.display()
+                );
+                println!("{}", serde_json::to_string_pretty(&settings)?);
+            }
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&settings)?);
     }
 
     Ok(())
@@ -0,0 +1,400 @@
+//! Registry publishing for `cargo ros2 publish-bindings`
+//!
+//! Generated crates reference each other (and the shared runtime) through
+//! `path` dependencies, which registries reject.
+//! Publishing works on copies staged in a separate directory:
+//! 1. Copy each generated crate, leaving the originals used by builds intact
+//! 2. Set the version from package.xml and restrict `publish` to the registry
+//! 3. Replace path and wildcard dependencies on other generated crates with
+//!    versioned dependencies on the registry
+//! 4. Verify each crate builds, with the registry patched to the staged copies
+//! 5. Optionally `cargo publish` them in dependency order
+
+use eyre::{eyre, Result, WrapErr};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml::Value;
+
+/// Dependency tables rewritten in staged manifests
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "build-dependencies", "dev-dependencies"];
+
+/// A generated crate copied and rewritten for publishing
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedCrate {
+    /// Crate name
+    pub name: String,
+    /// Version it will be published as
+    pub version: String,
+    /// Staged crate directory
+    pub path: PathBuf,
+    /// Other generated crates it depends on
+    pub dependencies: BTreeSet<String>,
+}
+
+/// Stage every generated crate for publishing to `registry`
+///
+/// `bindings` maps crate names to generated crate directories; `versions`
+/// overrides the version of a crate (normally its package.xml version). The
+/// staged crates are returned in publish order, dependencies first.
+pub fn stage(
+    bindings: &BTreeMap<String, PathBuf>,
+    versions: &BTreeMap<String, String>,
+    registry: &str,
+    staging_dir: &Path,
+) -> Result<Vec<StagedCrate>> {
+    // Versions of all crates, so dependencies can be pinned before staging them
+    let mut crate_versions = BTreeMap::new();
+    for (name, path) in bindings {
+        let version = match versions.get(name) {
+            Some(version) => version.clone(),
+            None => manifest_version(&path.join("Cargo.toml"))?,
+        };
+        crate_versions.insert(name.clone(), version);
+    }
+
+    if staging_dir.exists() {
+        fs::remove_dir_all(staging_dir)
+            .wrap_err_with(|| format!("Failed to clear {}", staging_dir.display()))?;
+    }
+
+    let mut staged = Vec::new();
+    for (name, path) in bindings {
+        let staged_path = staging_dir.join(name);
+        copy_crate(path, &staged_path)?;
+
+        let manifest_path = staged_path.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path)
+            .wrap_err_with(|| format!("Failed to read {}", manifest_path.display()))?;
+        let (manifest, dependencies) =
+            rewrite_manifest(&manifest, &crate_versions[name], &crate_versions, registry)
+                .wrap_err_with(|| format!("Failed to rewrite the manifest of {}", name))?;
+        fs::write(&manifest_path, manifest)?;
+
+        staged.push(StagedCrate {
+            name: name.clone(),
+            version: crate_versions[name].clone(),
+            path: staged_path,
+            dependencies,
+        });
+    }
+
+    write_registry_patch(&staged, registry, staging_dir)?;
+    publish_order(staged)
+}
+
+/// Rewrite a generated Cargo.toml for publishing
+///
+/// Returns the new manifest and the generated crates it depends on. Path
+/// dependencies must point at another generated crate (`crate_versions` is
+/// keyed by crate directory name), and wildcard versions are only resolved for
+/// generated crates.
+pub fn rewrite_manifest(
+    manifest: &str,
+    version: &str,
+    crate_versions: &BTreeMap<String, String>,
+    registry: &str,
+) -> Result<(String, BTreeSet<String>)> {
+    let mut manifest: toml::Table = manifest.parse().wrap_err("Failed to parse Cargo.toml")?;
+
+    let package = manifest
+        .get_mut("package")
+        .and_then(Value::as_table_mut)
+        .ok_or_else(|| eyre!("Cargo.toml has no [package] table"))?;
+    package.insert("version".to_string(), Value::String(version.to_string()));
+    package.insert(
+        "publish".to_string(),
+        Value::Array(vec![Value::String(registry.to_string())]),
+    );
+
+    // Staged crates are checked in place, under a project that may be a
+    // workspace; crates of a bindings workspace lost their own table
+    manifest
+        .entry("workspace")
+        .or_insert_with(|| Value::Table(toml::Table::new()));
+
+    let mut generated_dependencies = BTreeSet::new();
+    for table_name in DEPENDENCY_TABLES {
+        let Some(table) = manifest.get_mut(*table_name).and_then(Value::as_table_mut) else {
+            continue;
+        };
+
+        for (name, spec) in table.iter_mut() {
+            let Some(target) = generated_dependency(name, spec) else {
+                continue;
+            };
+            let dep_version = crate_versions.get(&target).ok_or_else(|| {
+                eyre!(
+                    "Dependency '{}' points at '{}', which is not a generated crate",
+                    name,
+                    target
+                )
+            })?;
+
+            let mut new_spec = match spec {
+                Value::Table(table) => table.clone(),
+                _ => toml::Table::new(),
+            };
+            new_spec.remove("path");
+            new_spec.insert("version".to_string(), Value::String(dep_version.clone()));
+            new_spec.insert("registry".to_string(), Value::String(registry.to_string()));
+            *spec = Value::Table(new_spec);
+
+            generated_dependencies.insert(target);
+        }
+    }
+
+    let manifest = toml::to_string(&manifest).wrap_err("Failed to serialize Cargo.toml")?;
+    Ok((manifest, generated_dependencies))
+}
+
+/// The generated crate a dependency refers to, for path and wildcard dependencies
+fn generated_dependency(name: &str, spec: &Value) -> Option<String> {
+    if let Some(path) = spec.get("path").and_then(Value::as_str) {
+        return Path::new(path)
+            .file_name()
+            .map(|dir| dir.to_string_lossy().to_string());
+    }
+    if is_wildcard(spec) {
+        let package = spec.get("package").and_then(Value::as_str).unwrap_or(name);
+        return Some(package.to_string());
+    }
+    None
+}
+
+fn is_wildcard(spec: &Value) -> bool {
+    match spec {
+        Value::String(version) => version == "*",
+        Value::Table(table) => table.get("version").and_then(Value::as_str) == Some("*"),
+        _ => false,
+    }
+}
+
+fn manifest_version(manifest_path: &Path) -> Result<String> {
+    let content = fs::read_to_string(manifest_path)
+        .wrap_err_with(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: toml::Table = content
+        .parse()
+        .wrap_err_with(|| format!("Failed to parse {}", manifest_path.display()))?;
+    manifest
+        .get("package")
+        .and_then(|p| p.get("version"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| eyre!("{} has no package version", manifest_path.display()))
+}
+
+/// Sort crates so every crate comes after the generated crates it depends on
+pub fn publish_order(crates: Vec<StagedCrate>) -> Result<Vec<StagedCrate>> {
+    let mut remaining: BTreeMap<_, _> = crates.into_iter().map(|c| (c.name.clone(), c)).collect();
+    let mut ordered: Vec<StagedCrate> = Vec::new();
+
+    while !remaining.is_empty() {
+        let ready: Vec<String> = remaining
+            .values()
+            .filter(|c| {
+                c.dependencies
+                    .iter()
+                    .all(|dep| ordered.iter().any(|o| &o.name == dep))
+            })
+            .map(|c| c.name.clone())
+            .collect();
+        if ready.is_empty() {
+            let names: Vec<_> = remaining.keys().cloned().collect();
+            return Err(eyre!(
+                "Dependency cycle or missing crate among: {}",
+                names.join(", ")
+            ));
+        }
+        for name in ready {
+            ordered.extend(remaining.remove(&name));
+        }
+    }
+
+    Ok(ordered)
+}
+
+/// Patch the registry with the staged crates, for verifying them before publishing
+fn write_registry_patch(crates: &[StagedCrate], registry: &str, staging_dir: &Path) -> Result<()> {
+    let mut patches = toml::Table::new();
+    for krate in crates {
+        let mut entry = toml::Table::new();
+        entry.insert(
+            "path".to_string(),
+            Value::String(krate.path.to_string_lossy().to_string()),
+        );
+        patches.insert(krate.name.clone(), Value::Table(entry));
+    }
+    let mut patch = toml::Table::new();
+    patch.insert(registry.to_string(), Value::Table(patches));
+    let mut config = toml::Table::new();
+    config.insert("patch".to_string(), Value::Table(patch));
+
+    let cargo_dir = staging_dir.join(".cargo");
+    fs::create_dir_all(&cargo_dir)?;
+    fs::write(cargo_dir.join("config.toml"), toml::to_string(&config)?)?;
+    Ok(())
+}
+
+/// Copy a crate, leaving out build output and the lock file
+fn copy_crate(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src).wrap_err_with(|| format!("Failed to read {}", src.display()))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "target" || name == "Cargo.lock" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_crate(&entry.path(), &dst.join(&name))?;
+        } else {
+            fs::copy(entry.path(), dst.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that a staged crate builds against the other staged crates
+pub fn verify(krate: &StagedCrate) -> Result<()> {
+    let status = Command::new("cargo")
+        .arg("check")
+        .current_dir(&krate.path)
+        .status()
+        .wrap_err("Failed to execute cargo check")?;
+    if !status.success() {
+        return Err(eyre!("{} {} does not build", krate.name, krate.version));
+    }
+    Ok(())
+}
+
+/// Publish a staged crate to the registry
+pub fn publish(krate: &StagedCrate, registry: &str) -> Result<()> {
+    let status = Command::new("cargo")
+        .args(["publish", "--registry", registry])
+        .current_dir(&krate.path)
+        .status()
+        .wrap_err("Failed to execute cargo publish")?;
+    if !status.success() {
+        return Err(eyre!("Failed to publish {} {}", krate.name, krate.version));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions() -> BTreeMap<String, String> {
+        [
+            ("rosidl_runtime_rs", "0.1.0"),
+            ("builtin_interfaces", "1.2.1"),
+            ("std_msgs", "4.2.3"),
+        ]
+        .into_iter()
+        .map(|(name, version)| (name.to_string(), version.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_rewrite_manifest() {
+        let manifest = r#"[package]
+name = "std_msgs"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+rosidl_runtime_rs = { path = "../rosidl_runtime_rs" }
+serde = { version = "1.0", features = ["derive"], optional = true }
+builtin_interfaces = "*"
+"#;
+
+        let (rewritten, deps) =
+            rewrite_manifest(manifest, "4.2.3", &versions(), "ros-internal").unwrap();
+        let table: toml::Table = rewritten.parse().unwrap();
+
+        assert_eq!(table["package"]["version"].as_str(), Some("4.2.3"));
+        assert!(table["workspace"].as_table().unwrap().is_empty());
+        assert_eq!(
+            table["package"]["publish"].as_array().unwrap(),
+            &vec![Value::String("ros-internal".to_string())]
+        );
+        let runtime = &table["dependencies"]["rosidl_runtime_rs"];
+        assert_eq!(runtime.get("path"), None);
+        assert_eq!(runtime["version"].as_str(), Some("0.1.0"));
+        assert_eq!(runtime["registry"].as_str(), Some("ros-internal"));
+        assert_eq!(
+            table["dependencies"]["builtin_interfaces"]["version"].as_str(),
+            Some("1.2.1")
+        );
+        assert_eq!(
+            table["dependencies"]["serde"]["version"].as_str(),
+            Some("1.0")
+        );
+        assert_eq!(
+            deps.into_iter().collect::<Vec<_>>(),
+            ["builtin_interfaces", "rosidl_runtime_rs"]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_manifest_rejects_foreign_deps() {
+        let path_dep = "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nother = { path = \"../../vendor/other\" }\n";
+        assert!(rewrite_manifest(path_dep, "1.0.0", &versions(), "reg").is_err());
+
+        let wildcard =
+            "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[dependencies]\nrand = \"*\"\n";
+        assert!(rewrite_manifest(wildcard, "1.0.0", &versions(), "reg").is_err());
+    }
+
+    #[test]
+    fn test_stage_orders_dependencies_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let generated = temp_dir.path().join("ros2_bindings");
+        let write = |name: &str, deps: &str| {
+            let dir = generated.join(name);
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::create_dir_all(dir.join("target")).unwrap();
+            fs::write(dir.join("src/lib.rs"), "").unwrap();
+            fs::write(
+                dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\n{}",
+                    name, deps
+                ),
+            )
+            .unwrap();
+            (name.to_string(), dir)
+        };
+        let bindings: BTreeMap<_, _> = [
+            write(
+                "std_msgs",
+                "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\" }\nbuiltin_interfaces = { path = \"../builtin_interfaces\" }\n",
+            ),
+            write(
+                "builtin_interfaces",
+                "rosidl_runtime_rs = { path = \"../rosidl_runtime_rs\" }\n",
+            ),
+            write("rosidl_runtime_rs", ""),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut package_versions = versions();
+        package_versions.remove("rosidl_runtime_rs");
+        let staging_dir = temp_dir.path().join("publish");
+        let staged = stage(&bindings, &package_versions, "reg", &staging_dir).unwrap();
+
+        let order: Vec<_> = staged.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            order,
+            ["rosidl_runtime_rs", "builtin_interfaces", "std_msgs"]
+        );
+        assert_eq!(staged[2].version, "4.2.3");
+        assert!(staging_dir.join("std_msgs/src/lib.rs").exists());
+        assert!(!staging_dir.join("std_msgs/target").exists());
+
+        let config = fs::read_to_string(staging_dir.join(".cargo/config.toml")).unwrap();
+        assert!(config.contains("[patch.reg.std_msgs]"));
+    }
+}
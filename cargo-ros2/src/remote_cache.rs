@@ -0,0 +1,596 @@
+//! Remote cache for generated binding crates
+//!
+//! Binding crates are stored as `.tar.gz` archives addressed by a hash of the
+//! package name, the checksum of its interface files, the ROS distro and the
+//! code generator version, so any build with the same inputs can reuse them.
+//! The shared runtime crate is not stored: it is copied from the bundled sources.
+//! `cargo ros2 build --remote-cache <url>` pulls the bindings it would otherwise
+//! generate and pushes the ones it had to generate.
+//!
+//! Two kinds of store are supported, both through `curl`:
+//! - `http://` / `https://`: objects are fetched with GET and stored with PUT
+//!   (e.g. nginx WebDAV or bazel-remote). A bearer token is sent from
+//!   `CARGO_ROS2_REMOTE_CACHE_TOKEN` when set.
+//! - `s3://<bucket>[/<prefix>]`: S3-compatible object stores, with requests
+//!   signed from the usual `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`
+//!   (`AWS_SESSION_TOKEN`), `AWS_REGION` and `AWS_ENDPOINT_URL` variables.
+//!
+//! Each archive is stored with a `.sha256` object next to it, holding the
+//! [output checksum](crate::cache::calculate_output_checksum) of the crate, and
+//! a pulled crate is only used if it matches. That catches corrupted and partial
+//! uploads, but the digest comes from the same store: when the local cache
+//! records bindings generated from the same inputs, such as a cache file
+//! committed without its crates, the recorded checksum is expected instead.
+//! Otherwise anyone who can write to the store controls the bindings of every
+//! build pulling from it. Archives are unpacked into a staging directory, and
+//! only regular files and directories under `<package>/` are accepted.
+
+use crate::cache::{calculate_output_checksum, GENERATOR_VERSION};
+use eyre::{eyre, Result, WrapErr};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Component, Path};
+use std::process::{Command, Stdio};
+
+/// Environment variable holding a bearer token for HTTP caches
+pub const TOKEN_ENV: &str = "CARGO_ROS2_REMOTE_CACHE_TOKEN";
+
+/// Location of a remote cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteCache {
+    /// Plain HTTP(S) store
+    Http { base_url: String },
+    /// S3-compatible object store
+    S3 { bucket: String, prefix: String },
+}
+
+impl RemoteCache {
+    /// Parse a `--remote-cache` URL
+    pub fn parse(url: &str) -> Result<Self> {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return Ok(RemoteCache::Http {
+                base_url: url.trim_end_matches('/').to_string(),
+            });
+        }
+        if let Some(path) = url.strip_prefix("s3://") {
+            let path = path.trim_matches('/');
+            let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+            if bucket.is_empty() {
+                return Err(eyre!("Missing bucket in remote cache URL '{}'", url));
+            }
+            return Ok(RemoteCache::S3 {
+                bucket: bucket.to_string(),
+                prefix: prefix.to_string(),
+            });
+        }
+        Err(eyre!(
+            "Unsupported remote cache URL '{}' (expected http://, https:// or s3://)",
+            url
+        ))
+    }
+
+    /// URL of the archive for a package's bindings
+    pub fn object_url(&self, package: &str, checksum: &str) -> String {
+        let key = format!("{}/{}.tar.gz", package, cache_key(package, checksum));
+        match self {
+            RemoteCache::Http { base_url } => format!("{}/{}", base_url, key),
+            RemoteCache::S3 { bucket, prefix } => {
+                // Path-style addressing works with AWS as well as MinIO & co.
+                let mut url = format!("{}/{}", s3_endpoint(), bucket);
+                if !prefix.is_empty() {
+                    url.push('/');
+                    url.push_str(prefix);
+                }
+                format!("{}/{}", url, key)
+            }
+        }
+    }
+
+    /// URL of the output checksum stored next to the archive of a package
+    pub fn digest_url(&self, package: &str, checksum: &str) -> String {
+        format!("{}.sha256", self.object_url(package, checksum))
+    }
+
+    /// Download and unpack a package's bindings into `<output_dir>/<package>`
+    ///
+    /// The crate is unpacked into a staging directory and only moved into place
+    /// if it has the output checksum `expected`, or the one stored next to the
+    /// archive if `None`. Returns `false` if the cache has no bindings for these
+    /// inputs.
+    pub fn fetch(
+        &self,
+        package: &str,
+        checksum: &str,
+        output_dir: &Path,
+        expected: Option<&str>,
+    ) -> Result<bool> {
+        std::fs::create_dir_all(output_dir)
+            .wrap_err_with(|| format!("Failed to create {}", output_dir.display()))?;
+
+        let expected = match expected {
+            Some(expected) => expected.to_string(),
+            // An archive without its digest is still being uploaded, or was not
+            // uploaded by this version
+            None => match self.fetch_digest(package, checksum, output_dir)? {
+                Some(digest) => digest,
+                None => return Ok(false),
+            },
+        };
+
+        // Next to the output so that the crate can be renamed into place
+        let staging = tempfile::Builder::new()
+            .prefix(&format!(".{}.remote", package))
+            .tempdir_in(output_dir)
+            .wrap_err_with(|| {
+                format!("Failed to create a directory in {}", output_dir.display())
+            })?;
+        let archive = staging.path().join("bindings.tar.gz");
+
+        let url = self.object_url(package, checksum);
+        let status = self.request(Command::new("curl").arg("--output").arg(&archive).arg(&url))?;
+        if status == 404 {
+            return Ok(false);
+        }
+        if !(200..300).contains(&status) {
+            return Err(eyre!("GET {} returned HTTP {}", url, status));
+        }
+
+        check_archive(&archive, package).wrap_err_with(|| format!("Rejected {}", url))?;
+        run_tar(
+            Command::new("tar")
+                .arg("-xzf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(staging.path()),
+        )
+        .wrap_err_with(|| format!("Failed to unpack {}", url))?;
+
+        let unpacked = staging.path().join(package);
+        if !unpacked.join("Cargo.toml").exists() {
+            return Err(eyre!("Archive {} does not contain {}/", url, package));
+        }
+        let actual = calculate_output_checksum(&unpacked)?;
+        if actual != expected {
+            return Err(eyre!(
+                "Bindings in {} have checksum {}, expected {}",
+                url,
+                actual,
+                expected
+            ));
+        }
+
+        let crate_dir = output_dir.join(package);
+        if crate_dir.exists() {
+            std::fs::remove_dir_all(&crate_dir)
+                .wrap_err_with(|| format!("Failed to remove {}", crate_dir.display()))?;
+        }
+        std::fs::rename(&unpacked, &crate_dir)
+            .wrap_err_with(|| format!("Failed to move bindings to {}", crate_dir.display()))?;
+        Ok(true)
+    }
+
+    /// Download the output checksum stored next to the archive of a package
+    fn fetch_digest(
+        &self,
+        package: &str,
+        checksum: &str,
+        output_dir: &Path,
+    ) -> Result<Option<String>> {
+        let file = output_dir.join(format!(".{}.remote.sha256", package));
+        let url = self.digest_url(package, checksum);
+        let status = self.request(Command::new("curl").arg("--output").arg(&file).arg(&url));
+        let digest = std::fs::read_to_string(&file);
+        let _ = std::fs::remove_file(&file);
+        match status? {
+            404 => Ok(None),
+            200..=299 => Ok(Some(
+                digest
+                    .wrap_err_with(|| format!("Failed to read {}", url))?
+                    .trim()
+                    .to_string(),
+            )),
+            status => Err(eyre!("GET {} returned HTTP {}", url, status)),
+        }
+    }
+
+    /// Pack and upload the bindings in `crate_dir` (named after the package)
+    pub fn push(&self, package: &str, checksum: &str, crate_dir: &Path) -> Result<()> {
+        let parent = crate_dir
+            .parent()
+            .ok_or_else(|| eyre!("{} has no parent directory", crate_dir.display()))?;
+        let dir_name = crate_dir
+            .file_name()
+            .ok_or_else(|| eyre!("Invalid bindings directory {}", crate_dir.display()))?;
+        if dir_name != package {
+            return Err(eyre!(
+                "Bindings of {} must be in a directory named after the package, not {}",
+                package,
+                crate_dir.display()
+            ));
+        }
+
+        let archive = parent.join(format!(".{}.remote.tar.gz", package));
+        run_tar(
+            Command::new("tar")
+                .arg("-czf")
+                .arg(&archive)
+                .arg(format!("--exclude={}/target", package))
+                .arg("-C")
+                .arg(parent)
+                .arg(dir_name),
+        )
+        .wrap_err_with(|| format!("Failed to pack {}", crate_dir.display()))?;
+
+        let digest = parent.join(format!(".{}.remote.sha256", package));
+        let result = calculate_output_checksum(crate_dir).and_then(|output_checksum| {
+            std::fs::write(&digest, output_checksum)
+                .wrap_err_with(|| format!("Failed to write {}", digest.display()))?;
+            // The digest goes last, so the archive is complete once it is there
+            self.upload(&archive, &self.object_url(package, checksum))?;
+            self.upload(&digest, &self.digest_url(package, checksum))
+        });
+        let _ = std::fs::remove_file(&archive);
+        let _ = std::fs::remove_file(&digest);
+        result
+    }
+
+    /// Upload a file with PUT
+    fn upload(&self, file: &Path, url: &str) -> Result<()> {
+        let status = self.request(Command::new("curl").arg("--upload-file").arg(file).arg(url))?;
+        if !(200..300).contains(&status) {
+            return Err(eyre!("PUT {} returned HTTP {}", url, status));
+        }
+        Ok(())
+    }
+
+    /// Run a curl request and return its HTTP status
+    ///
+    /// Credentials are passed on stdin so they do not show up in process lists.
+    fn request(&self, cmd: &mut Command) -> Result<u16> {
+        let mut config = String::new();
+        match self {
+            RemoteCache::Http { .. } => {
+                if let Ok(token) = std::env::var(TOKEN_ENV) {
+                    config.push_str(&curl_option(
+                        "header",
+                        &format!("Authorization: Bearer {}", token),
+                    ));
+                }
+            }
+            RemoteCache::S3 { .. } => {
+                if let (Ok(key), Ok(secret)) = (
+                    std::env::var("AWS_ACCESS_KEY_ID"),
+                    std::env::var("AWS_SECRET_ACCESS_KEY"),
+                ) {
+                    config.push_str(&curl_option(
+                        "aws-sigv4",
+                        &format!("aws:amz:{}:s3", s3_region()),
+                    ));
+                    config.push_str(&curl_option("user", &format!("{}:{}", key, secret)));
+                    if let Ok(token) = std::env::var("AWS_SESSION_TOKEN") {
+                        config.push_str(&curl_option(
+                            "header",
+                            &format!("x-amz-security-token: {}", token),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut child = cmd
+            .args(["--silent", "--show-error", "--location"])
+            .args(["--write-out", "%{http_code}"])
+            .args(["--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err("Failed to run curl")?;
+        child
+            .stdin
+            .take()
+            .expect("curl stdin is piped")
+            .write_all(config.as_bytes())?;
+        let output = child.wait_with_output().wrap_err("Failed to run curl")?;
+
+        if !output.status.success() {
+            return Err(eyre!(
+                "curl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let code = String::from_utf8_lossy(&output.stdout);
+        code.trim()
+            .parse()
+            .map_err(|_| eyre!("Unexpected curl output '{}'", code.trim()))
+    }
+}
+
+/// Content address of a package's bindings
+///
+/// Covers everything the generated crate depends on: the interface files (via
+/// their checksum), the code generator version (which changes with its sources
+/// and templates), and the ROS distro, which decides optional parts such as
+/// service event messages.
+pub fn cache_key(package: &str, checksum: &str) -> String {
+    let distro = std::env::var("ROS_DISTRO").unwrap_or_default();
+    let mut hasher = Sha256::new();
+    for part in [package, checksum, &distro, GENERATOR_VERSION] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn s3_region() -> String {
+    std::env::var("AWS_REGION")
+        .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_string())
+}
+
+fn s3_endpoint() -> String {
+    std::env::var("AWS_ENDPOINT_URL")
+        .map(|url| url.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", s3_region()))
+}
+
+/// One line of a curl config file
+fn curl_option(name: &str, value: &str) -> String {
+    format!(
+        "{} = \"{}\"\n",
+        name,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+/// Check that an archive only holds regular files and directories under `<package>/`
+///
+/// Paths that are absolute or go through `..`, and links of any kind, could
+/// write outside the directory the archive is unpacked into.
+fn check_archive(archive: &Path, package: &str) -> Result<()> {
+    let names = run_tar(Command::new("tar").arg("-tzf").arg(archive))?;
+    // Verbose listings start with the entry type, as in `ls -l`
+    let entries = run_tar(Command::new("tar").arg("-tvzf").arg(archive))?;
+    if names.lines().count() != entries.lines().count() {
+        return Err(eyre!("Unexpected archive listing"));
+    }
+
+    for (name, entry) in names.lines().zip(entries.lines()) {
+        let mut components = Path::new(name).components();
+        let inside = components.next() == Some(Component::Normal(package.as_ref()))
+            && components.all(|component| matches!(component, Component::Normal(_)));
+        if !inside {
+            return Err(eyre!("Entry {} is outside {}/", name, package));
+        }
+        if !entry.starts_with(['-', 'd']) {
+            return Err(eyre!("Entry {} is not a regular file or directory", name));
+        }
+    }
+    Ok(())
+}
+
+/// Run tar and return its output
+fn run_tar(cmd: &mut Command) -> Result<String> {
+    let output = cmd.output().wrap_err("Failed to run tar")?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Minimal HTTP object store answering GET and PUT
+    fn serve(objects: Arc<Mutex<HashMap<String, Vec<u8>>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut parts = request_line.split_whitespace();
+                let (method, path) = (parts.next().unwrap(), parts.next().unwrap().to_string());
+
+                let mut content_length = 0;
+                let mut expect_continue = false;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_ascii_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    expect_continue |= line == "expect: 100-continue";
+                }
+
+                let response = if method == "PUT" {
+                    if expect_continue {
+                        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    objects.lock().unwrap().insert(path, body);
+                    b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n".to_vec()
+                } else if let Some(body) = objects.lock().unwrap().get(&path) {
+                    let mut response =
+                        format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+                            .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                } else {
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec()
+                };
+                stream.write_all(&response).unwrap();
+            }
+        });
+        format!("http://{}/cache/", addr)
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            RemoteCache::parse("https://cache.example.com/ros2/").unwrap(),
+            RemoteCache::Http {
+                base_url: "https://cache.example.com/ros2".to_string()
+            }
+        );
+        assert_eq!(
+            RemoteCache::parse("s3://ci-cache/bindings/ros2").unwrap(),
+            RemoteCache::S3 {
+                bucket: "ci-cache".to_string(),
+                prefix: "bindings/ros2".to_string()
+            }
+        );
+        assert_eq!(
+            RemoteCache::parse("s3://ci-cache").unwrap(),
+            RemoteCache::S3 {
+                bucket: "ci-cache".to_string(),
+                prefix: String::new()
+            }
+        );
+        assert!(RemoteCache::parse("s3://").is_err());
+        assert!(RemoteCache::parse("ftp://cache").is_err());
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let key = cache_key("std_msgs", "abc");
+        assert_eq!(key, cache_key("std_msgs", "abc"));
+        assert_ne!(key, cache_key("std_msgs", "abd"));
+        assert_ne!(key, cache_key("geometry_msgs", "abc"));
+
+        let remote = RemoteCache::parse("https://cache.example.com").unwrap();
+        assert_eq!(
+            remote.object_url("std_msgs", "abc"),
+            format!("https://cache.example.com/std_msgs/{}.tar.gz", key)
+        );
+    }
+
+    #[test]
+    fn test_push_and_fetch() {
+        let objects = Arc::new(Mutex::new(HashMap::new()));
+        let remote = RemoteCache::parse(&serve(objects.clone())).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let local = temp_dir.path().join("local");
+        fs::create_dir_all(local.join("std_msgs/src")).unwrap();
+        fs::create_dir_all(local.join("std_msgs/target/debug")).unwrap();
+        fs::write(local.join("std_msgs/Cargo.toml"), "[package]\n").unwrap();
+        fs::write(local.join("std_msgs/src/lib.rs"), "pub mod msg {}\n").unwrap();
+        fs::write(local.join("std_msgs/target/debug/junk"), "").unwrap();
+
+        let other = temp_dir.path().join("other");
+        assert!(!remote.fetch("std_msgs", "abc", &other, None).unwrap());
+
+        remote
+            .push("std_msgs", "abc", &local.join("std_msgs"))
+            .unwrap();
+        assert_eq!(objects.lock().unwrap().len(), 2);
+
+        assert!(remote.fetch("std_msgs", "abc", &other, None).unwrap());
+        assert_eq!(
+            fs::read_to_string(other.join("std_msgs/src/lib.rs")).unwrap(),
+            "pub mod msg {}\n"
+        );
+        assert!(!other.join("std_msgs/target").exists());
+        assert!(!remote.fetch("std_msgs", "changed", &other, None).unwrap());
+
+        // A crate other than the recorded one is rejected
+        let recorded = calculate_output_checksum(&local.join("std_msgs")).unwrap();
+        assert!(remote
+            .fetch("std_msgs", "abc", &other, Some(&recorded))
+            .unwrap());
+        fs::write(other.join("std_msgs/src/lib.rs"), "// local\n").unwrap();
+        assert!(remote
+            .fetch("std_msgs", "abc", &other, Some("0000"))
+            .is_err());
+        // without touching the crate already there
+        assert_eq!(
+            fs::read_to_string(other.join("std_msgs/src/lib.rs")).unwrap(),
+            "// local\n"
+        );
+        assert_eq!(fs::read_dir(&other).unwrap().count(), 1);
+        fs::remove_dir_all(other.join("std_msgs")).unwrap();
+
+        // And so is a crate not matching the digest next to it
+        let digest_path = remote
+            .digest_url("std_msgs", "abc")
+            .split_once("/cache/")
+            .map(|(_, key)| format!("/cache/{}", key))
+            .unwrap();
+        objects
+            .lock()
+            .unwrap()
+            .insert(digest_path, b"0000\n".to_vec());
+        assert!(remote.fetch("std_msgs", "abc", &other, None).is_err());
+        assert!(!other.join("std_msgs").exists());
+    }
+
+    #[test]
+    fn test_fetch_rejects_unsafe_archives() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("std_msgs")).unwrap();
+        fs::create_dir_all(src.join("other")).unwrap();
+        fs::write(src.join("std_msgs/Cargo.toml"), "[package]\n").unwrap();
+        fs::write(src.join("other/Cargo.toml"), "[package]\n").unwrap();
+        std::os::unix::fs::symlink("/etc", src.join("std_msgs/etc")).unwrap();
+
+        let pack = |name: &str, args: &[&str]| {
+            let archive = temp_dir.path().join(name);
+            run_tar(
+                Command::new("tar")
+                    .arg("-czPf")
+                    .arg(&archive)
+                    .arg("-C")
+                    .arg(&src)
+                    .args(args),
+            )
+            .unwrap();
+            archive
+        };
+        let absolute = src.join("std_msgs/Cargo.toml");
+
+        assert!(check_archive(&pack("ok.tar.gz", &["std_msgs/Cargo.toml"]), "std_msgs").is_ok());
+        for archive in [
+            pack("other.tar.gz", &["std_msgs/Cargo.toml", "other"]),
+            pack("link.tar.gz", &["std_msgs"]),
+            pack("absolute.tar.gz", &[absolute.to_str().unwrap()]),
+        ] {
+            assert!(check_archive(&archive, "std_msgs").is_err());
+        }
+
+        // Nothing is unpacked from a rejected archive
+        let objects = Arc::new(Mutex::new(HashMap::new()));
+        let remote = RemoteCache::parse(&serve(objects.clone())).unwrap();
+        let key = |url: String| {
+            url.split_once("/cache/")
+                .map(|(_, key)| format!("/cache/{}", key))
+                .unwrap()
+        };
+        objects.lock().unwrap().insert(
+            key(remote.object_url("std_msgs", "abc")),
+            fs::read(pack("link.tar.gz", &["std_msgs"])).unwrap(),
+        );
+        let output = temp_dir.path().join("output");
+        assert!(remote
+            .fetch("std_msgs", "abc", &output, Some("0000"))
+            .is_err());
+        assert_eq!(fs::read_dir(&output).unwrap().count(), 0);
+    }
+}
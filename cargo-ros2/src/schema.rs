@@ -0,0 +1,543 @@
+//! Schema export for ROS 2 message types
+//!
+//! This module loads `.msg`, `.srv` and `.action` definitions (and everything
+//! they reference) from the ament index and converts the parsed AST into schemas
+//! consumed by web visualization tools. Each section of a service or action is a
+//! message of its own, named as in ROS 2 (`pkg/srv/Name_Request`,
+//! `pkg/action/Name_Goal`):
+//! - **foxglove**: `ros2msg` text with dependent definitions appended, as used in
+//!   MCAP schema records
+//! - **jsonschema**: JSON Schema (draft 2020-12) with nested types under `$defs`
+
+use eyre::{eyre, Result, WrapErr};
+use rosidl_parser::{FieldType, Message, PrimitiveType};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Separator between definitions in a concatenated `ros2msg` schema
+const DEFINITION_SEPARATOR: &str =
+    "================================================================================";
+
+/// Output format for `cargo ros2 schema export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaFormat {
+    /// Concatenated ros2msg definitions (Foxglove/MCAP)
+    Foxglove,
+    /// JSON Schema draft 2020-12
+    Jsonschema,
+}
+
+/// Sections of each interface kind, with the suffix of their message names
+const INTERFACE_SECTIONS: [(&str, &[&str]); 3] = [
+    ("msg", &[""]),
+    ("srv", &["_Request", "_Response"]),
+    ("action", &["_Goal", "_Result", "_Feedback"]),
+];
+
+/// A parsed message definition
+#[derive(Debug, Clone)]
+pub struct MessageDefinition {
+    /// Package the message belongs to
+    pub package: String,
+    /// Message name (e.g. `Point`, or `AddTwoInts_Request` for a service section)
+    pub name: String,
+    /// Original `.msg` text, or the text of its section
+    pub text: String,
+    /// Parsed AST
+    pub message: Message,
+}
+
+/// All message definitions reachable from a set of packages
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceRegistry {
+    /// Keyed by fully qualified type name (`pkg/msg/Name`, `pkg/srv/Name_Request`, ...)
+    messages: BTreeMap<String, MessageDefinition>,
+}
+
+impl InterfaceRegistry {
+    /// Load the messages of `roots` and every package their fields reference
+    ///
+    /// `packages` maps package names to share directories (see
+    /// `WorkflowContext::discover_ament_packages`).
+    pub fn load(roots: &[String], packages: &HashMap<String, PathBuf>) -> Result<Self> {
+        let mut registry = InterfaceRegistry::default();
+        let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+        let mut visited = BTreeSet::new();
+
+        while let Some(package) = queue.pop_front() {
+            if !visited.insert(package.clone()) {
+                continue;
+            }
+
+            let share_dir = packages
+                .get(&package)
+                .ok_or_else(|| eyre!("Package '{}' not found in ament index", package))?;
+
+            for referenced in registry.load_package(&package, share_dir)? {
+                if !visited.contains(&referenced) {
+                    queue.push_back(referenced);
+                }
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Parse the `.msg`, `.srv` and `.action` files of a package, returning the
+    /// packages they reference
+    fn load_package(&mut self, package: &str, share_dir: &Path) -> Result<BTreeSet<String>> {
+        let mut referenced = BTreeSet::new();
+        for (kind, _) in INTERFACE_SECTIONS {
+            let dir = share_dir.join(kind);
+            if !dir.exists() {
+                continue;
+            }
+
+            let entries = fs::read_dir(&dir)
+                .wrap_err_with(|| format!("Failed to read directory: {}", dir.display()))?;
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(kind) {
+                    continue;
+                }
+                let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                    continue;
+                };
+
+                let text = fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+                let inserted = self
+                    .insert_interface(package, kind, &name, &text)
+                    .wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+
+                for key in inserted {
+                    for field in &self.messages[&key].message.fields {
+                        if let Some((dep_package, _)) = namespaced_type(package, &field.field_type)
+                        {
+                            if dep_package != package {
+                                referenced.insert(dep_package);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(referenced)
+    }
+
+    /// Parse and register a single message definition
+    pub fn insert(&mut self, package: &str, name: &str, text: &str) -> Result<()> {
+        self.insert_interface(package, "msg", name, text)?;
+        Ok(())
+    }
+
+    /// Parse and register a message, service or action definition, one message
+    /// per section, returning the type names registered
+    pub fn insert_interface(
+        &mut self,
+        package: &str,
+        kind: &str,
+        name: &str,
+        text: &str,
+    ) -> Result<Vec<String>> {
+        let (_, suffixes) = INTERFACE_SECTIONS
+            .iter()
+            .find(|(section_kind, _)| *section_kind == kind)
+            .ok_or_else(|| eyre!("Unknown interface kind '{}'", kind))?;
+        let sections = split_sections(text);
+        if sections.len() != suffixes.len() {
+            return Err(eyre!(
+                "Expected {} section(s) separated by '---', found {}",
+                suffixes.len(),
+                sections.len()
+            ));
+        }
+
+        let mut inserted = Vec::new();
+        for (suffix, section) in suffixes.iter().zip(sections) {
+            let message = rosidl_parser::parse_message(&section).map_err(|e| eyre!("{}", e))?;
+            let section_name = format!("{}{}", name, suffix);
+            let key = format!("{}/{}/{}", package, kind, section_name);
+            self.messages.insert(
+                key.clone(),
+                MessageDefinition {
+                    package: package.to_string(),
+                    name: section_name,
+                    text: section,
+                    message,
+                },
+            );
+            inserted.push(key);
+        }
+        Ok(inserted)
+    }
+
+    /// Fully qualified names of all loaded messages, sorted
+    pub fn type_names(&self) -> impl Iterator<Item = &String> {
+        self.messages.keys()
+    }
+
+    /// Definition of a loaded message type (`pkg/msg/Name`)
+    pub fn get(&self, type_name: &str) -> Result<&MessageDefinition> {
+        self.messages
+            .get(type_name)
+            .ok_or_else(|| eyre!("Unknown message type '{}'", type_name))
+    }
+
+    /// Referenced message types of `root` in depth-first order, without `root` itself
+    fn dependencies(&self, root: &str) -> Result<Vec<String>> {
+        let mut order = Vec::new();
+        let mut seen = BTreeSet::new();
+        seen.insert(root.to_string());
+        self.collect_dependencies(root, &mut seen, &mut order)?;
+        Ok(order)
+    }
+
+    fn collect_dependencies(
+        &self,
+        current: &str,
+        seen: &mut BTreeSet<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        let definition = self.get(current)?;
+        for field in &definition.message.fields {
+            if let Some((package, name)) = namespaced_type(&definition.package, &field.field_type) {
+                let dep = type_name(&package, &name);
+                if seen.insert(dep.clone()) {
+                    order.push(dep.clone());
+                    self.collect_dependencies(&dep, seen, order)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Foxglove/MCAP `ros2msg` schema for a message type
+    pub fn foxglove_schema(&self, root: &str) -> Result<Value> {
+        let mut data = self.get(root)?.text.trim_end().to_string();
+        for dep in self.dependencies(root)? {
+            data.push_str(&format!(
+                "\n{}\nMSG: {}\n{}",
+                DEFINITION_SEPARATOR,
+                dep,
+                self.get(&dep)?.text.trim_end()
+            ));
+        }
+        data.push('\n');
+
+        Ok(json!({
+            "name": root,
+            "encoding": "ros2msg",
+            "data": data,
+        }))
+    }
+
+    /// JSON Schema for a message type, with referenced types under `$defs`
+    pub fn json_schema(&self, root: &str) -> Result<Value> {
+        let mut schema = self.object_schema(root)?;
+        let object = schema.as_object_mut().expect("object schema");
+
+        let mut defs = Map::new();
+        for dep in self.dependencies(root)? {
+            defs.insert(dep.clone(), self.object_schema(&dep)?);
+        }
+
+        let mut root_schema = Map::new();
+        root_schema.insert(
+            "$schema".to_string(),
+            json!("https://json-schema.org/draft/2020-12/schema"),
+        );
+        root_schema.insert("title".to_string(), json!(root));
+        root_schema.append(object);
+        if !defs.is_empty() {
+            root_schema.insert("$defs".to_string(), Value::Object(defs));
+        }
+
+        Ok(Value::Object(root_schema))
+    }
+
+    fn object_schema(&self, type_name: &str) -> Result<Value> {
+        let definition = self.get(type_name)?;
+        let mut properties = Map::new();
+        for field in &definition.message.fields {
+            properties.insert(
+                field.name.clone(),
+                field_schema(&definition.package, &field.field_type),
+            );
+        }
+        let required: Vec<&str> = definition
+            .message
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+
+        Ok(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        }))
+    }
+
+    /// Export schemas for every loaded message, keyed by type name
+    pub fn export(&self, format: SchemaFormat) -> Result<Value> {
+        let mut schemas = Map::new();
+        for name in self.type_names() {
+            let schema = match format {
+                SchemaFormat::Foxglove => self.foxglove_schema(name)?,
+                SchemaFormat::Jsonschema => self.json_schema(name)?,
+            };
+            schemas.insert(name.clone(), schema);
+        }
+        Ok(Value::Object(schemas))
+    }
+}
+
+/// Split interface text at its `---` separator lines
+fn split_sections(text: &str) -> Vec<String> {
+    let mut sections = vec![String::new()];
+    for line in text.lines() {
+        if line.trim() == "---" {
+            sections.push(String::new());
+        } else {
+            let section = sections.last_mut().expect("at least one section");
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+    sections
+}
+
+/// Fully qualified message type name
+pub(crate) fn type_name(package: &str, name: &str) -> String {
+    format!("{}/msg/{}", package, name)
+}
+
+/// Resolve the (package, name) of a namespaced field type, looking through arrays
+pub(crate) fn namespaced_type(
+    current_package: &str,
+    field_type: &FieldType,
+) -> Option<(String, String)> {
+    match field_type {
+        FieldType::NamespacedType { package, name } => Some((
+            package
+                .clone()
+                .unwrap_or_else(|| current_package.to_string()),
+            name.clone(),
+        )),
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => {
+            namespaced_type(current_package, element_type)
+        }
+        _ => None,
+    }
+}
+
+/// JSON Schema for a single field type
+fn field_schema(current_package: &str, field_type: &FieldType) -> Value {
+    match field_type {
+        FieldType::Primitive(primitive) => primitive_schema(*primitive),
+        FieldType::String | FieldType::WString => json!({ "type": "string" }),
+        FieldType::BoundedString(max) | FieldType::BoundedWString(max) => {
+            json!({ "type": "string", "maxLength": max })
+        }
+        FieldType::Array { element_type, size } => json!({
+            "type": "array",
+            "items": field_schema(current_package, element_type),
+            "minItems": size,
+            "maxItems": size,
+        }),
+        FieldType::Sequence { element_type } => json!({
+            "type": "array",
+            "items": field_schema(current_package, element_type),
+        }),
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => json!({
+            "type": "array",
+            "items": field_schema(current_package, element_type),
+            "maxItems": max_size,
+        }),
+        FieldType::NamespacedType { package, name } => {
+            let package = package.as_deref().unwrap_or(current_package);
+            // JSON pointer escaping: '/' becomes '~1'
+            let pointer = type_name(package, name).replace('/', "~1");
+            json!({ "$ref": format!("#/$defs/{}", pointer) })
+        }
+    }
+}
+
+fn primitive_schema(primitive: PrimitiveType) -> Value {
+    match primitive {
+        PrimitiveType::Bool => json!({ "type": "boolean" }),
+        PrimitiveType::Float32 | PrimitiveType::Float64 => json!({ "type": "number" }),
+        PrimitiveType::Byte | PrimitiveType::Char | PrimitiveType::UInt8 => {
+            json!({ "type": "integer", "minimum": u8::MIN, "maximum": u8::MAX })
+        }
+        PrimitiveType::Int8 => json!({ "type": "integer", "minimum": i8::MIN, "maximum": i8::MAX }),
+        PrimitiveType::Int16 => {
+            json!({ "type": "integer", "minimum": i16::MIN, "maximum": i16::MAX })
+        }
+        PrimitiveType::UInt16 => {
+            json!({ "type": "integer", "minimum": u16::MIN, "maximum": u16::MAX })
+        }
+        PrimitiveType::Int32 => {
+            json!({ "type": "integer", "minimum": i32::MIN, "maximum": i32::MAX })
+        }
+        PrimitiveType::UInt32 => {
+            json!({ "type": "integer", "minimum": u32::MIN, "maximum": u32::MAX })
+        }
+        PrimitiveType::Int64 => {
+            json!({ "type": "integer", "minimum": i64::MIN, "maximum": i64::MAX })
+        }
+        PrimitiveType::UInt64 => {
+            json!({ "type": "integer", "minimum": u64::MIN, "maximum": u64::MAX })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_registry() -> InterfaceRegistry {
+        let mut registry = InterfaceRegistry::default();
+        registry
+            .insert(
+                "geometry_msgs",
+                "Point",
+                "float64 x\nfloat64 y\nfloat64 z\n",
+            )
+            .unwrap();
+        registry
+            .insert(
+                "my_msgs",
+                "Path",
+                "string<=32 frame_id\ngeometry_msgs/Point[] points\nuint8[4] flags\n",
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_foxglove_schema_appends_dependencies() {
+        let registry = sample_registry();
+        let schema = registry.foxglove_schema("my_msgs/msg/Path").unwrap();
+
+        assert_eq!(schema["encoding"], "ros2msg");
+        let data = schema["data"].as_str().unwrap();
+        assert!(data.starts_with("string<=32 frame_id\n"));
+        assert!(data.contains(&format!(
+            "{}\nMSG: geometry_msgs/msg/Point\nfloat64 x",
+            DEFINITION_SEPARATOR
+        )));
+    }
+
+    #[test]
+    fn test_json_schema_fields_and_defs() {
+        let registry = sample_registry();
+        let schema = registry.json_schema("my_msgs/msg/Path").unwrap();
+
+        assert_eq!(schema["title"], "my_msgs/msg/Path");
+        assert_eq!(schema["properties"]["frame_id"]["maxLength"], 32);
+        assert_eq!(
+            schema["properties"]["points"]["items"]["$ref"],
+            "#/$defs/geometry_msgs~1msg~1Point"
+        );
+        assert_eq!(schema["properties"]["flags"]["minItems"], 4);
+        assert_eq!(
+            schema["$defs"]["geometry_msgs/msg/Point"]["properties"]["x"]["type"],
+            "number"
+        );
+    }
+
+    #[test]
+    fn test_load_follows_referenced_packages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut packages = HashMap::new();
+        for (package, name, text) in [
+            ("geometry_msgs", "Point", "float64 x\n"),
+            ("my_msgs", "Waypoint", "geometry_msgs/Point position\n"),
+        ] {
+            let msg_dir = temp_dir.path().join(package).join("msg");
+            fs::create_dir_all(&msg_dir).unwrap();
+            fs::write(msg_dir.join(format!("{}.msg", name)), text).unwrap();
+            packages.insert(package.to_string(), temp_dir.path().join(package));
+        }
+
+        let registry = InterfaceRegistry::load(&["my_msgs".to_string()], &packages).unwrap();
+        let names: Vec<_> = registry.type_names().cloned().collect();
+        assert_eq!(
+            names,
+            vec!["geometry_msgs/msg/Point", "my_msgs/msg/Waypoint"]
+        );
+
+        let exported = registry.export(SchemaFormat::Jsonschema).unwrap();
+        assert!(exported["my_msgs/msg/Waypoint"]["$defs"]["geometry_msgs/msg/Point"].is_object());
+    }
+
+    #[test]
+    fn test_load_services_and_actions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut packages = HashMap::new();
+        for (package, path, text) in [
+            ("geometry_msgs", "msg/Point.msg", "float64 x\n"),
+            (
+                "nav_msgs",
+                "srv/GetPlan.srv",
+                "geometry_msgs/Point goal\n---\ngeometry_msgs/Point[] plan\n",
+            ),
+            (
+                "nav_msgs",
+                "action/Follow.action",
+                "int32 id\n---\nbool done\n---\nfloat32 progress\n",
+            ),
+        ] {
+            let path = temp_dir.path().join(package).join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+            packages.insert(package.to_string(), temp_dir.path().join(package));
+        }
+
+        let registry = InterfaceRegistry::load(&["nav_msgs".to_string()], &packages).unwrap();
+        let names: Vec<_> = registry.type_names().cloned().collect();
+        assert_eq!(
+            names,
+            vec![
+                "geometry_msgs/msg/Point",
+                "nav_msgs/action/Follow_Feedback",
+                "nav_msgs/action/Follow_Goal",
+                "nav_msgs/action/Follow_Result",
+                "nav_msgs/srv/GetPlan_Request",
+                "nav_msgs/srv/GetPlan_Response",
+            ]
+        );
+
+        let response = registry
+            .foxglove_schema("nav_msgs/srv/GetPlan_Response")
+            .unwrap();
+        let data = response["data"].as_str().unwrap();
+        assert!(data.starts_with("geometry_msgs/Point[] plan\n"));
+        assert!(data.contains("MSG: geometry_msgs/msg/Point\nfloat64 x"));
+        assert_eq!(
+            registry.get("nav_msgs/action/Follow_Result").unwrap().text,
+            "bool done\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_interface_checks_sections() {
+        let mut registry = InterfaceRegistry::default();
+        let err = registry
+            .insert_interface("my_msgs", "srv", "Ping", "int32 id\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("Expected 2 section(s)"));
+    }
+}
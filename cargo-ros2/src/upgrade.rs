@@ -0,0 +1,269 @@
+//! Binding upgrade support
+//!
+//! When cargo-ros2 itself is upgraded, codegen changes can alter the public API of
+//! generated crates. This module snapshots the API surface (structs and their fields)
+//! of a generated crate so the surfaces before and after regeneration can be compared.
+
+use eyre::{Result, WrapErr};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Public structs and fields of a generated crate
+///
+/// Keys are module-qualified struct paths (e.g. `msg::Point`, `ffi::msg::Point`),
+/// values map field names to their Rust types.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiSurface {
+    pub structs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// A single API-visible difference between two surfaces
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiChange {
+    StructAdded(String),
+    StructRemoved(String),
+    FieldAdded {
+        struct_path: String,
+        field: String,
+        rust_type: String,
+    },
+    FieldRemoved {
+        struct_path: String,
+        field: String,
+    },
+    FieldTypeChanged {
+        struct_path: String,
+        field: String,
+        old_type: String,
+        new_type: String,
+    },
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiChange::StructAdded(path) => write!(f, "+ struct {}", path),
+            ApiChange::StructRemoved(path) => write!(f, "- struct {}", path),
+            ApiChange::FieldAdded {
+                struct_path,
+                field,
+                rust_type,
+            } => write!(f, "+ {}::{}: {}", struct_path, field, rust_type),
+            ApiChange::FieldRemoved { struct_path, field } => {
+                write!(f, "- {}::{}", struct_path, field)
+            }
+            ApiChange::FieldTypeChanged {
+                struct_path,
+                field,
+                old_type,
+                new_type,
+            } => write!(
+                f,
+                "~ {}::{}: {} -> {}",
+                struct_path, field, old_type, new_type
+            ),
+        }
+    }
+}
+
+impl ApiSurface {
+    /// Collect the API surface of a generated crate directory
+    ///
+    /// Returns an empty surface if the crate has no `src/` directory.
+    pub fn from_crate(crate_dir: &Path) -> Result<Self> {
+        let mut surface = ApiSurface::default();
+        let src_dir = crate_dir.join("src");
+        if src_dir.exists() {
+            surface.collect_dir(&src_dir, &src_dir)?;
+        }
+        Ok(surface)
+    }
+
+    fn collect_dir(&mut self, src_root: &Path, dir: &Path) -> Result<()> {
+        let entries =
+            fs::read_dir(dir).wrap_err_with(|| format!("Failed to read {}", dir.display()))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                self.collect_dir(src_root, &path)?;
+            } else if path.extension().and_then(|e| e.to_str()) == Some("rs") {
+                let content = fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+
+                // Module prefix from the directory layout (src/ffi/msg/x.rs -> ffi::msg)
+                let module_prefix = path
+                    .parent()
+                    .and_then(|p| p.strip_prefix(src_root).ok())
+                    .map(|p| {
+                        p.components()
+                            .map(|c| c.as_os_str().to_string_lossy().to_string())
+                            .collect::<Vec<_>>()
+                            .join("::")
+                    })
+                    .unwrap_or_default();
+
+                self.collect_source(&module_prefix, &content);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collect `pub struct` definitions and their `pub` fields from Rust source
+    pub fn collect_source(&mut self, module_prefix: &str, content: &str) {
+        let mut current: Option<String> = None;
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+
+            if let Some(rest) = trimmed.strip_prefix("pub struct ") {
+                let name: String = rest
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                let path = if module_prefix.is_empty() {
+                    name
+                } else {
+                    format!("{}::{}", module_prefix, name)
+                };
+                self.structs.entry(path.clone()).or_default();
+                if trimmed.ends_with('{') {
+                    current = Some(path);
+                }
+                continue;
+            }
+
+            if let Some(struct_path) = &current {
+                if trimmed.starts_with('}') {
+                    current = None;
+                } else if let Some(field) = trimmed.strip_prefix("pub ") {
+                    if let Some((name, rust_type)) = field.split_once(':') {
+                        let rust_type = rust_type.trim().trim_end_matches(',').trim();
+                        self.structs
+                            .entry(struct_path.clone())
+                            .or_default()
+                            .insert(name.trim().to_string(), rust_type.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compute the changes needed to go from `self` (old) to `new`
+    pub fn diff(&self, new: &ApiSurface) -> Vec<ApiChange> {
+        let mut changes = Vec::new();
+
+        for (path, old_fields) in &self.structs {
+            let Some(new_fields) = new.structs.get(path) else {
+                changes.push(ApiChange::StructRemoved(path.clone()));
+                continue;
+            };
+
+            for (field, old_type) in old_fields {
+                match new_fields.get(field) {
+                    None => changes.push(ApiChange::FieldRemoved {
+                        struct_path: path.clone(),
+                        field: field.clone(),
+                    }),
+                    Some(new_type) if new_type != old_type => {
+                        changes.push(ApiChange::FieldTypeChanged {
+                            struct_path: path.clone(),
+                            field: field.clone(),
+                            old_type: old_type.clone(),
+                            new_type: new_type.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for (field, rust_type) in new_fields {
+                if !old_fields.contains_key(field) {
+                    changes.push(ApiChange::FieldAdded {
+                        struct_path: path.clone(),
+                        field: field.clone(),
+                        rust_type: rust_type.clone(),
+                    });
+                }
+            }
+        }
+
+        for path in new.structs.keys() {
+            if !self.structs.contains_key(path) {
+                changes.push(ApiChange::StructAdded(path.clone()));
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_source() {
+        let mut surface = ApiSurface::default();
+        surface.collect_source(
+            "msg",
+            "#[derive(Debug)]\npub struct Point {\n    pub x: f64,\n    pub y: f64,\n}\n",
+        );
+
+        let fields = surface.structs.get("msg::Point").unwrap();
+        assert_eq!(fields.get("x").map(String::as_str), Some("f64"));
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn test_diff_reports_changes() {
+        let mut old = ApiSurface::default();
+        old.collect_source(
+            "msg",
+            "pub struct Point {\n    pub x: f64,\n    pub y: f32,\n}\npub struct Old {\n}\n",
+        );
+
+        let mut new = ApiSurface::default();
+        new.collect_source(
+            "msg",
+            "pub struct Point {\n    pub y: f64,\n    pub z: f64,\n}\npub struct New {\n}\n",
+        );
+
+        let changes = old.diff(&new);
+        assert!(changes.contains(&ApiChange::StructRemoved("msg::Old".to_string())));
+        assert!(changes.contains(&ApiChange::StructAdded("msg::New".to_string())));
+        assert!(changes.contains(&ApiChange::FieldRemoved {
+            struct_path: "msg::Point".to_string(),
+            field: "x".to_string(),
+        }));
+        assert!(changes.contains(&ApiChange::FieldTypeChanged {
+            struct_path: "msg::Point".to_string(),
+            field: "y".to_string(),
+            old_type: "f32".to_string(),
+            new_type: "f64".to_string(),
+        }));
+        assert!(changes.contains(&ApiChange::FieldAdded {
+            struct_path: "msg::Point".to_string(),
+            field: "z".to_string(),
+            rust_type: "f64".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_from_crate_uses_module_layout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ffi_dir = temp_dir.path().join("src").join("ffi").join("msg");
+        fs::create_dir_all(&ffi_dir).unwrap();
+        fs::write(
+            ffi_dir.join("point_rmw.rs"),
+            "pub struct Point {\n    pub x: f64,\n}\n",
+        )
+        .unwrap();
+
+        let surface = ApiSurface::from_crate(temp_dir.path()).unwrap();
+        assert!(surface.structs.contains_key("ffi::msg::Point"));
+    }
+}
@@ -3,7 +3,7 @@
 //! This module coordinates the entire process:
 //! 1. Discover ROS dependencies from Cargo.toml
 //! 2. Check cache for each package
-//! 3. Generate missing/stale bindings
+//! 3. Pull missing/stale bindings from the remote cache, or generate them
 //! 4. Update cache
 //! 5. Patch .cargo/config.toml
 //! 6. Invoke cargo build
@@ -11,9 +11,13 @@
 use crate::cache::{self, Cache, CacheEntry, CACHE_FILE_NAME};
 use crate::config_patcher::ConfigPatcher;
 use crate::dependency_parser::{DependencyParser, RosDependency};
+use crate::integrity::{self, IntegrityIssue};
+use crate::lint;
+use crate::remote_cache::RemoteCache;
 use cargo_ros2_bindgen::ament::AmentIndex;
+use cargo_ros2_bindgen::output::Output;
 use eyre::{eyre, Result, WrapErr};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -28,6 +32,14 @@ pub struct WorkflowContext {
     pub cache_file: PathBuf,
     /// Verbose output
     pub verbose: bool,
+    /// Refuse to (re)generate; verify recorded bindings instead (`--locked`)
+    pub locked: bool,
+    /// Allowed signers file the cache signature must verify against
+    pub allowed_signers: Option<PathBuf>,
+    /// Remote cache to pull bindings from and push new ones to (`--remote-cache`)
+    pub remote_cache: Option<RemoteCache>,
+    /// Print style lints for the interfaces of the ROS dependencies (`--lint-interfaces`)
+    pub lint_interfaces: bool,
 }
 
 impl WorkflowContext {
@@ -41,6 +53,10 @@ impl WorkflowContext {
             output_dir,
             cache_file,
             verbose,
+            locked: false,
+            allowed_signers: None,
+            remote_cache: None,
+            lint_interfaces: false,
         }
     }
 
@@ -67,6 +83,10 @@ impl WorkflowContext {
             output_dir,
             cache_file,
             verbose,
+            locked: false,
+            allowed_signers: None,
+            remote_cache: None,
+            lint_interfaces: false,
         }
     }
 
@@ -163,6 +183,34 @@ impl WorkflowContext {
         Ok(output_path.join(package_name))
     }
 
+    /// Run cargo-ros2-bindgen in watch mode on `packages` until it is interrupted
+    ///
+    /// The bindings are regenerated in place, without updating the cache.
+    pub fn watch_bindings(&self, packages: &[String]) -> Result<()> {
+        let bindgen_binary = self.find_cargo_ros2_bindgen()?;
+
+        let mut cmd = Command::new(&bindgen_binary);
+        for package in packages {
+            cmd.arg("--package").arg(package);
+        }
+        cmd.arg("--output")
+            .arg(&self.output_dir)
+            .arg("--watch")
+            .arg("--workspace");
+
+        if self.verbose {
+            cmd.arg("--verbose");
+        }
+
+        let status = cmd
+            .status()
+            .wrap_err_with(|| format!("Failed to execute {}", bindgen_binary.display()))?;
+        if !status.success() {
+            return Err(eyre!("cargo-ros2-bindgen --watch failed ({})", status));
+        }
+        Ok(())
+    }
+
     /// Find cargo-ros2-bindgen binary
     fn find_cargo_ros2_bindgen(&self) -> Result<PathBuf> {
         // Try to find in target directory (development)
@@ -203,6 +251,8 @@ impl WorkflowContext {
         // Calculate checksum of the source package
         let checksum = cache::calculate_package_checksum(package_share_dir)
             .wrap_err_with(|| format!("Failed to calculate checksum for {}", package_name))?;
+        let output_checksum = cache::calculate_output_checksum(&output_dir)
+            .wrap_err_with(|| format!("Failed to checksum bindings for {}", package_name))?;
 
         let entry = CacheEntry {
             package_name: package_name.to_string(),
@@ -214,6 +264,8 @@ impl WorkflowContext {
                 .unwrap()
                 .as_secs(),
             output_dir,
+            generator_version: Some(cache::GENERATOR_VERSION.to_string()),
+            output_checksum: Some(output_checksum),
         };
 
         cache.insert(entry);
@@ -222,6 +274,43 @@ impl WorkflowContext {
         Ok(())
     }
 
+    /// Get the generated crate path for a package
+    ///
+    /// Prefers the output directory recorded in the cache and falls back to
+    /// `<output_dir>/<package>`. Returns `None` if no bindings exist on disk.
+    pub fn bindings_path(&self, package_name: &str) -> Result<Option<PathBuf>> {
+        Ok(self.generated_bindings()?.remove(package_name))
+    }
+
+    /// List all generated binding crates (package name -> crate directory)
+    pub fn generated_bindings(&self) -> Result<BTreeMap<String, PathBuf>> {
+        let mut bindings = BTreeMap::new();
+
+        // Crates present in the output directory
+        if self.output_dir.exists() {
+            let entries = std::fs::read_dir(&self.output_dir)
+                .wrap_err_with(|| format!("Failed to read {}", self.output_dir.display()))?;
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.join("Cargo.toml").exists() {
+                    let package_name = entry.file_name().to_string_lossy().to_string();
+                    bindings.insert(package_name, path);
+                }
+            }
+        }
+
+        // Cache entries take precedence (they record where bindgen actually wrote)
+        let cache = Cache::load(&self.cache_file)?;
+        for entry in cache.entries() {
+            if entry.output_dir.join("Cargo.toml").exists() {
+                bindings.insert(entry.package_name.clone(), entry.output_dir.clone());
+            }
+        }
+
+        Ok(bindings)
+    }
+
     /// Patch .cargo/config.toml with binding paths
     pub fn patch_cargo_config(&self, packages: &[(String, PathBuf)]) -> Result<()> {
         let mut patcher = ConfigPatcher::new(&self.project_root)?;
@@ -241,6 +330,44 @@ impl WorkflowContext {
         Ok(())
     }
 
+    /// Make the generated crates members of one workspace, with its manifest at
+    /// the root of the output directory
+    ///
+    /// Joining the workspace changes the manifest of a crate, so the checksums
+    /// of the crates that joined are recorded again for `--locked` builds.
+    pub fn write_workspace_manifest(&self) -> Result<()> {
+        if !self.output_dir.is_dir() {
+            return Ok(());
+        }
+        let workspace = cargo_ros2_bindgen::workspace::write_workspace(&self.output_dir)?;
+        if !workspace.joined.is_empty() {
+            let mut cache = Cache::load(&self.cache_file)?;
+            let joined: Vec<_> = cache
+                .entries()
+                .filter(|entry| {
+                    workspace
+                        .joined
+                        .iter()
+                        .any(|name| entry.output_dir == self.output_dir.join(name))
+                })
+                .cloned()
+                .collect();
+            for mut entry in joined {
+                entry.output_checksum = Some(cache::calculate_output_checksum(&entry.output_dir)?);
+                cache.insert(entry);
+            }
+            cache.save(&self.cache_file)?;
+        }
+        if self.verbose {
+            eprintln!(
+                "  Workspace of {} binding crate(s): {}",
+                workspace.members.len(),
+                self.output_dir.join("Cargo.toml").display()
+            );
+        }
+        Ok(())
+    }
+
     /// Generate bindings for multiple packages in parallel
     fn generate_bindings_parallel(
         &self,
@@ -337,6 +464,8 @@ impl WorkflowContext {
         // Calculate checksum of the source package
         let checksum = cache::calculate_package_checksum(package_share_dir)
             .wrap_err_with(|| format!("Failed to calculate checksum for {}", package_name))?;
+        let output_checksum = cache::calculate_output_checksum(&output_dir)
+            .wrap_err_with(|| format!("Failed to checksum bindings for {}", package_name))?;
 
         let entry = CacheEntry {
             package_name: package_name.to_string(),
@@ -348,6 +477,8 @@ impl WorkflowContext {
                 .unwrap()
                 .as_secs(),
             output_dir,
+            generator_version: Some(cache::GENERATOR_VERSION.to_string()),
+            output_checksum: Some(output_checksum),
         };
 
         cache.insert(entry);
@@ -390,6 +521,18 @@ impl WorkflowContext {
             eprintln!("  Found {} ROS dependencies", dependencies.len());
         }
 
+        if self.lint_interfaces {
+            self.print_interface_lints(&dependencies, &ament_packages)?;
+        }
+
+        if self.locked {
+            self.verify_locked(&dependencies, &ament_packages)?;
+            if !bindings_only {
+                self.invoke_cargo_build()?;
+            }
+            return Ok(());
+        }
+
         // Step 3: Iteratively discover and generate transitive dependencies
         let mut all_generated = Vec::new();
         let mut seen_packages = std::collections::HashSet::new();
@@ -412,8 +555,18 @@ impl WorkflowContext {
                 eprintln!("  {} packages need generation", to_generate.len());
             }
 
+            // Pull what the remote cache already has
+            let mut generated_packages = match &self.remote_cache {
+                Some(remote) => self.fetch_remote(remote, &to_generate, &ament_packages)?,
+                None => Vec::new(),
+            };
+            let to_generate: Vec<String> = to_generate
+                .into_iter()
+                .filter(|name| !generated_packages.iter().any(|(pulled, _)| pulled == name))
+                .collect();
+
             // Generate bindings (in parallel if multiple packages)
-            let generated_packages = if to_generate.len() > 1 {
+            let newly_generated = if to_generate.len() > 1 {
                 self.generate_bindings_parallel(&to_generate, &ament_packages)?
             } else {
                 let mut generated_packages = Vec::new();
@@ -429,6 +582,10 @@ impl WorkflowContext {
                 }
                 generated_packages
             };
+            if let Some(remote) = &self.remote_cache {
+                self.push_remote(remote, &newly_generated, &ament_packages);
+            }
+            generated_packages.extend(newly_generated);
 
             all_generated.extend(generated_packages.clone());
 
@@ -439,7 +596,7 @@ impl WorkflowContext {
 
             // Discover transitive dependencies from generated packages
             let mut new_deps = Vec::new();
-            for (pkg_name, pkg_path) in &generated_packages {
+            for (_, pkg_path) in &generated_packages {
                 if let Ok(transitive_deps) = self.discover_transitive_dependencies(pkg_path) {
                     for dep in transitive_deps {
                         // Only add if we haven't seen it yet and it's a known ROS package
@@ -472,6 +629,7 @@ impl WorkflowContext {
             }
             self.patch_cargo_config(&all_generated)?;
         }
+        self.write_workspace_manifest()?;
 
         // Step 5: Invoke cargo build (unless --bindings-only)
         if !bindings_only {
@@ -484,6 +642,164 @@ impl WorkflowContext {
         Ok(())
     }
 
+    /// Print the style lints of the dependencies' interface files as warnings
+    fn print_interface_lints(
+        &self,
+        dependencies: &[RosDependency],
+        ament_packages: &HashMap<String, PathBuf>,
+    ) -> Result<()> {
+        let mut count = 0;
+        for dependency in dependencies {
+            let Some(share_dir) = ament_packages.get(&dependency.name) else {
+                continue;
+            };
+            for lint in lint::lint_package(share_dir)? {
+                eprintln!("Warning: {}", lint);
+                count += 1;
+            }
+        }
+        if self.verbose {
+            eprintln!("  {} interface lint warnings", count);
+        }
+        Ok(())
+    }
+
+    /// Pull bindings from the remote cache
+    ///
+    /// Returns the fetched crates; the other packages still need generation.
+    /// Errors talking to the cache are reported and fall back to generation.
+    fn fetch_remote(
+        &self,
+        remote: &RemoteCache,
+        packages: &[String],
+        ament_packages: &HashMap<String, PathBuf>,
+    ) -> Result<Vec<(String, PathBuf)>> {
+        // Binding crates need the shared runtime crate next to them, which is
+        // copied from the bundled sources rather than trusted to the store
+        cargo_ros2_bindgen::generator::ensure_rosidl_runtime_rs(&Output::disk(&self.output_dir))?;
+
+        let cache = Cache::load(&self.cache_file)?;
+        let ros_distro = std::env::var("ROS_DISTRO").ok();
+        let mut fetched = Vec::new();
+        for package_name in packages {
+            let Some(share_dir) = ament_packages.get(package_name) else {
+                continue;
+            };
+            let checksum = cache::calculate_package_checksum(share_dir)
+                .wrap_err_with(|| format!("Failed to calculate checksum for {}", package_name))?;
+
+            // Bindings recorded for the same inputs, e.g. in a cache file
+            // committed without its crates, must be the ones pulled
+            let recorded = cache
+                .get(package_name)
+                .filter(|entry| {
+                    entry.checksum == checksum
+                        && entry.ros_distro == ros_distro
+                        && entry.generator_version.as_deref() == Some(cache::GENERATOR_VERSION)
+                })
+                .and_then(|entry| entry.output_checksum.as_deref());
+            match remote.fetch(package_name, &checksum, &self.output_dir, recorded) {
+                Ok(true) => {
+                    let output_dir = self.output_dir.join(package_name);
+                    self.update_cache(package_name, share_dir, output_dir.clone())?;
+                    fetched.push((package_name.clone(), output_dir));
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!(
+                    "Warning: failed to pull {} from the remote cache: {:#}",
+                    package_name, e
+                ),
+            }
+        }
+
+        if self.verbose {
+            eprintln!("  {} packages pulled from the remote cache", fetched.len());
+        }
+        Ok(fetched)
+    }
+
+    /// Push freshly generated bindings to the remote cache
+    ///
+    /// Failures (e.g. read-only credentials) only produce warnings.
+    fn push_remote(
+        &self,
+        remote: &RemoteCache,
+        generated: &[(String, PathBuf)],
+        ament_packages: &HashMap<String, PathBuf>,
+    ) {
+        if generated.is_empty() {
+            return;
+        }
+
+        let mut uploads = Vec::new();
+        for (package_name, output_dir) in generated {
+            let Some(share_dir) = ament_packages.get(package_name) else {
+                continue;
+            };
+            match cache::calculate_package_checksum(share_dir) {
+                Ok(checksum) => uploads.push((package_name.clone(), checksum, output_dir.clone())),
+                Err(e) => eprintln!("Warning: not pushing {}: {:#}", package_name, e),
+            }
+        }
+
+        for (package_name, checksum, crate_dir) in uploads {
+            if let Err(e) = remote.push(&package_name, &checksum, &crate_dir) {
+                eprintln!(
+                    "Warning: failed to push {} to the remote cache: {:#}",
+                    package_name, e
+                );
+            } else if self.verbose {
+                eprintln!("  Pushed {} to the remote cache", package_name);
+            }
+        }
+    }
+
+    /// Verify recorded bindings instead of generating them (`--locked`)
+    ///
+    /// Fails if the cache signature is invalid (when an allowed signers file
+    /// is configured), a dependency has no recorded bindings, or any recorded
+    /// interface files or generated crates changed.
+    pub fn verify_locked(
+        &self,
+        dependencies: &[RosDependency],
+        ament_packages: &HashMap<String, PathBuf>,
+    ) -> Result<()> {
+        if self.verbose {
+            eprintln!("Verifying locked bindings...");
+        }
+
+        if let Some(allowed_signers) = &self.allowed_signers {
+            let principal = integrity::verify_signature(&self.cache_file, allowed_signers)?;
+            if self.verbose {
+                eprintln!("  Cache signed by {}", principal);
+            }
+        }
+
+        let cache = Cache::load(&self.cache_file)?;
+        let mut issues: Vec<_> = dependencies
+            .iter()
+            .filter(|dep| cache.get(&dep.name).is_none())
+            .map(|dep| IntegrityIssue::NotRecorded {
+                package: dep.name.clone(),
+            })
+            .collect();
+        issues.extend(integrity::verify_cache(&cache, ament_packages)?);
+
+        if !issues.is_empty() {
+            let list: Vec<_> = issues.iter().map(|issue| format!("  {}", issue)).collect();
+            return Err(eyre!(
+                "Locked bindings failed verification:\n{}\nRun without --locked to regenerate",
+                list.join("\n")
+            ));
+        }
+
+        if self.verbose {
+            eprintln!("  {} packages verified", cache.len());
+        }
+
+        Ok(())
+    }
+
     /// Discover transitive dependencies from a generated package
     fn discover_transitive_dependencies(&self, package_path: &Path) -> Result<Vec<String>> {
         use std::fs;
@@ -569,6 +885,26 @@ mod tests {
         assert!(ctx.verbose);
     }
 
+    #[test]
+    fn test_generated_bindings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ctx = WorkflowContext::new(temp_dir.path().to_path_buf(), false);
+
+        let std_msgs = ctx.output_dir.join("std_msgs");
+        std::fs::create_dir_all(&std_msgs).unwrap();
+        std::fs::write(std_msgs.join("Cargo.toml"), "[package]\n").unwrap();
+
+        // Directories without a manifest are not binding crates
+        std::fs::create_dir_all(ctx.output_dir.join("leftover")).unwrap();
+
+        let bindings = ctx.generated_bindings().unwrap();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings.get("std_msgs"), Some(&std_msgs));
+
+        assert_eq!(ctx.bindings_path("std_msgs").unwrap(), Some(std_msgs));
+        assert_eq!(ctx.bindings_path("geometry_msgs").unwrap(), None);
+    }
+
     #[test]
     fn test_discover_ament_packages_no_ros() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -223,6 +223,8 @@ mod cache_tests {
                 .unwrap()
                 .as_secs(),
             output_dir: temp_dir.path().join("output"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry);
@@ -265,6 +267,8 @@ mod cache_tests {
                 .unwrap()
                 .as_secs(),
             output_dir: output_dir.clone(),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry);
@@ -302,6 +306,8 @@ mod cache_tests {
                 .unwrap()
                 .as_secs(),
             output_dir,
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry);
@@ -554,6 +560,8 @@ mod cli_tests {
                 .unwrap()
                 .as_secs(),
             output_dir: temp_dir.path().join("std_msgs"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         let entry2 = CacheEntry {
@@ -566,6 +574,8 @@ mod cli_tests {
                 .unwrap()
                 .as_secs(),
             output_dir: temp_dir.path().join("geometry_msgs"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry1);
@@ -604,6 +614,8 @@ mod cli_tests {
                 .unwrap()
                 .as_secs(),
             output_dir: temp_dir.path().join("std_msgs"),
+            generator_version: None,
+            output_checksum: None,
         };
 
         cache.insert(entry);
@@ -645,6 +657,8 @@ mod cli_tests {
                     .unwrap()
                     .as_secs(),
                 output_dir: temp_dir.path().join(format!("package_{}", i)),
+                generator_version: None,
+                output_checksum: None,
             };
             cache.insert(entry);
         }
@@ -0,0 +1,102 @@
+//! ROS 2 bag file support for cargo-ros2 projects
+//!
+//! This crate records and replays ROS 2 messages using the pure-Rust CDR encoding
+//! from `rosidl_runtime_rs::cdr`, so Rust nodes can work with bag files without
+//! linking rosbag2's C++ stack.
+//!
+//! # Storage formats
+//! - **MCAP** (`mcap_storage`, `mcap` feature): read/write, compatible with
+//!   `rosbag2_storage_mcap`
+//! - **SQLite3** (`sqlite3_storage`, `sqlite3` feature): read-only, for bags
+//!   recorded with the legacy `rosbag2_storage_default_plugins` backend
+//!
+//! Readers stream messages from the storage rather than loading the bag.
+//!
+//! # Example
+//! ```ignore
+//! use rosbag2_rs::{McapReader, McapWriter};
+//!
+//! let mut writer = McapWriter::create("run.mcap")?;
+//! writer.add_topic("/chatter", "std_msgs/msg/String", "string data")?;
+//! writer.write("/chatter", 1_000, &"hello".to_string())?;
+//! writer.finish()?;
+//!
+//! let reader = McapReader::open("run.mcap")?;
+//! for message in reader.messages()? {
+//!     let text: String = message?.decode()?;
+//! }
+//! ```
+
+#[cfg(feature = "mcap")]
+pub mod mcap_storage;
+#[cfg(feature = "sqlite3")]
+pub mod sqlite3_storage;
+
+use rosidl_runtime_rs::cdr::{from_cdr, CdrDeserialize, CdrError};
+use thiserror::Error;
+
+#[cfg(feature = "mcap")]
+pub use mcap_storage::{McapReader, McapWriter};
+#[cfg(feature = "sqlite3")]
+pub use sqlite3_storage::Sqlite3Reader;
+
+/// Serialization format used by ROS 2 for bagged messages
+pub const CDR_SERIALIZATION_FORMAT: &str = "cdr";
+
+#[derive(Error, Debug)]
+pub enum BagError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "mcap")]
+    #[error("MCAP error: {0}")]
+    Mcap(#[from] mcap::McapError),
+
+    #[cfg(feature = "sqlite3")]
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("No .db3 files found in bag directory: {}", .0.display())]
+    EmptyBag(std::path::PathBuf),
+
+    #[error("CDR error: {0}")]
+    Cdr(#[from] CdrError),
+
+    #[error("Topic not registered: {0}")]
+    UnknownTopic(String),
+
+    #[error("Topic {topic} uses unsupported serialization format '{format}'")]
+    UnsupportedFormat { topic: String, format: String },
+}
+
+/// Topic information stored in a bag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicMetadata {
+    /// Topic name (e.g. `/chatter`)
+    pub name: String,
+    /// Fully qualified type name (e.g. `std_msgs/msg/String`)
+    pub type_name: String,
+    /// Message serialization format (normally `cdr`)
+    pub serialization_format: String,
+    /// Message definition text, empty if the bag does not store it
+    pub type_definition: String,
+}
+
+/// A single serialized message read from a bag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BagMessage {
+    pub topic: String,
+    /// Receive time in nanoseconds since the Unix epoch
+    pub log_time: u64,
+    /// Publish time in nanoseconds since the Unix epoch
+    pub publish_time: u64,
+    /// CDR payload including the encapsulation header
+    pub data: Vec<u8>,
+}
+
+impl BagMessage {
+    /// Decode the CDR payload into a message type
+    pub fn decode<M: CdrDeserialize>(&self) -> Result<M, BagError> {
+        Ok(from_cdr(&self.data)?)
+    }
+}
@@ -0,0 +1,411 @@
+//! MCAP storage backend
+//!
+//! Files follow the conventions of `rosbag2_storage_mcap`: the header profile is
+//! `ros2`, schemas are named after the message type (`pkg/msg/Type`) with the
+//! `ros2msg` encoding, and channels carry `cdr`-encoded messages.
+
+use crate::{BagError, BagMessage, TopicMetadata, CDR_SERIALIZATION_FORMAT};
+use memmap2::Mmap;
+use rosidl_runtime_rs::cdr::{to_cdr, CdrSerialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// MCAP header profile used by ROS 2
+pub const ROS2_PROFILE: &str = "ros2";
+
+/// Schema encoding for `.msg` definitions
+pub const ROS2MSG_SCHEMA_ENCODING: &str = "ros2msg";
+
+/// Writes ROS 2 messages to an MCAP file
+pub struct McapWriter<W: Write + Seek> {
+    writer: mcap::Writer<W>,
+    schemas: HashMap<String, u16>,
+    channels: HashMap<String, u16>,
+    sequence: u32,
+}
+
+impl McapWriter<BufWriter<File>> {
+    /// Create (or truncate) an MCAP file at `path`
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, BagError> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> McapWriter<W> {
+    /// Start writing an MCAP stream to `writer`
+    pub fn new(writer: W) -> Result<Self, BagError> {
+        let writer = mcap::WriteOptions::new()
+            .profile(ROS2_PROFILE)
+            .create(writer)?;
+
+        Ok(Self {
+            writer,
+            schemas: HashMap::new(),
+            channels: HashMap::new(),
+            sequence: 0,
+        })
+    }
+
+    /// Register a topic before writing messages to it
+    ///
+    /// `type_name` is the fully qualified type (e.g. `geometry_msgs/msg/Point`) and
+    /// `type_definition` the `.msg` text used by tools like Foxglove to decode it.
+    /// Registering the same topic twice is a no-op.
+    pub fn add_topic(
+        &mut self,
+        topic: &str,
+        type_name: &str,
+        type_definition: &str,
+    ) -> Result<(), BagError> {
+        if self.channels.contains_key(topic) {
+            return Ok(());
+        }
+
+        let schema_id = match self.schemas.get(type_name) {
+            Some(id) => *id,
+            None => {
+                let id = self.writer.add_schema(
+                    type_name,
+                    ROS2MSG_SCHEMA_ENCODING,
+                    type_definition.as_bytes(),
+                )?;
+                self.schemas.insert(type_name.to_string(), id);
+                id
+            }
+        };
+
+        let channel_id = self.writer.add_channel(
+            schema_id,
+            topic,
+            CDR_SERIALIZATION_FORMAT,
+            &BTreeMap::new(),
+        )?;
+        self.channels.insert(topic.to_string(), channel_id);
+
+        Ok(())
+    }
+
+    /// Serialize and write a message to a registered topic
+    ///
+    /// `time` is used as both log and publish time, in nanoseconds since the Unix epoch.
+    pub fn write<M: CdrSerialize + ?Sized>(
+        &mut self,
+        topic: &str,
+        time: u64,
+        message: &M,
+    ) -> Result<(), BagError> {
+        self.write_serialized(topic, time, &to_cdr(message))
+    }
+
+    /// Write an already CDR-encoded message to a registered topic
+    pub fn write_serialized(
+        &mut self,
+        topic: &str,
+        time: u64,
+        data: &[u8],
+    ) -> Result<(), BagError> {
+        let channel_id = *self
+            .channels
+            .get(topic)
+            .ok_or_else(|| BagError::UnknownTopic(topic.to_string()))?;
+
+        self.writer.write_to_known_channel(
+            &mcap::records::MessageHeader {
+                channel_id,
+                sequence: self.sequence,
+                log_time: time,
+                publish_time: time,
+            },
+            data,
+        )?;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        Ok(())
+    }
+
+    /// Write the summary section and flush the file
+    pub fn finish(mut self) -> Result<(), BagError> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Reads ROS 2 messages from an MCAP file
+///
+/// Messages are decoded lazily from the file (memory-mapped by [`McapReader::open`]),
+/// in file order; only the topic list is read up front.
+#[derive(Debug)]
+pub struct McapReader<B = Mmap> {
+    bytes: B,
+    topics: BTreeMap<String, TopicMetadata>,
+}
+
+impl McapReader<Mmap> {
+    /// Open an MCAP file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BagError> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only; as with any mapped file, the bag must not
+        // be truncated while it is being read
+        let bytes = unsafe { Mmap::map(&file) }?;
+        Self::from_bytes(bytes)
+    }
+}
+
+impl<B: AsRef<[u8]>> McapReader<B> {
+    /// Read an in-memory MCAP file
+    pub fn from_bytes(bytes: B) -> Result<Self, BagError> {
+        let topics = read_topics(bytes.as_ref())?;
+        Ok(Self { bytes, topics })
+    }
+
+    /// Topics of the file, sorted by name
+    pub fn topics(&self) -> impl Iterator<Item = &TopicMetadata> {
+        self.topics.values()
+    }
+
+    /// All messages in file order
+    pub fn messages(&self) -> Result<McapMessages<'_>, BagError> {
+        Ok(McapMessages {
+            stream: mcap::MessageStream::new(self.bytes.as_ref())?,
+        })
+    }
+
+    /// Messages on a single topic
+    pub fn topic_messages<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> Result<impl Iterator<Item = Result<BagMessage, BagError>> + 'a, BagError> {
+        Ok(self.messages()?.filter(move |message| match message {
+            Ok(message) => message.topic == topic,
+            Err(_) => true,
+        }))
+    }
+}
+
+/// Streaming iterator over the messages of an MCAP file
+pub struct McapMessages<'a> {
+    stream: mcap::MessageStream<'a>,
+}
+
+impl Iterator for McapMessages<'_> {
+    type Item = Result<BagMessage, BagError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let message = match self.stream.next()? {
+            Ok(message) => message,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let channel = &message.channel;
+        if let Err(e) = check_encoding(channel) {
+            return Some(Err(e));
+        }
+
+        Some(Ok(BagMessage {
+            topic: channel.topic.clone(),
+            log_time: message.log_time,
+            publish_time: message.publish_time,
+            data: message.data.into_owned(),
+        }))
+    }
+}
+
+/// Read the channels of a file as topics
+///
+/// Channels come from the summary section; files without one (such as a
+/// recording that was cut short) are scanned for the channels of their messages.
+fn read_topics(bytes: &[u8]) -> Result<BTreeMap<String, TopicMetadata>, BagError> {
+    let channels: Vec<Arc<mcap::Channel>> = match mcap::Summary::read(bytes)? {
+        Some(summary) => summary.channels.into_values().collect(),
+        None => {
+            let mut channels = BTreeMap::new();
+            for message in mcap::MessageStream::new(bytes)? {
+                let channel = message?.channel;
+                channels.entry(channel.id).or_insert(channel);
+            }
+            channels.into_values().collect()
+        }
+    };
+
+    let mut topics = BTreeMap::new();
+    for channel in channels {
+        check_encoding(&channel)?;
+        let (type_name, type_definition) = match &channel.schema {
+            Some(schema) => (
+                schema.name.clone(),
+                String::from_utf8_lossy(&schema.data).to_string(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        topics
+            .entry(channel.topic.clone())
+            .or_insert_with(|| TopicMetadata {
+                name: channel.topic.clone(),
+                type_name,
+                serialization_format: channel.message_encoding.clone(),
+                type_definition,
+            });
+    }
+    Ok(topics)
+}
+
+/// Reject channels whose messages are not CDR-encoded
+fn check_encoding(channel: &mcap::Channel) -> Result<(), BagError> {
+    if channel.message_encoding != CDR_SERIALIZATION_FORMAT {
+        return Err(BagError::UnsupportedFormat {
+            topic: channel.topic.clone(),
+            format: channel.message_encoding.clone(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test.mcap");
+
+        let mut writer = McapWriter::create(&path).unwrap();
+        writer
+            .add_topic("/chatter", "std_msgs/msg/String", "string data")
+            .unwrap();
+        writer
+            .add_topic(
+                "/point",
+                "geometry_msgs/msg/Point",
+                "float64 x\nfloat64 y\nfloat64 z",
+            )
+            .unwrap();
+        writer.write("/chatter", 10, "hello").unwrap();
+        writer
+            .write("/point", 20, &(1.0f64, 2.0f64, 3.0f64))
+            .unwrap();
+        writer.write("/chatter", 30, "world").unwrap();
+        writer.finish().unwrap();
+
+        let reader = McapReader::open(&path).unwrap();
+        let topics: Vec<_> = reader.topics().collect();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].name, "/chatter");
+        assert_eq!(topics[0].type_name, "std_msgs/msg/String");
+        assert_eq!(topics[0].type_definition, "string data");
+        assert_eq!(topics[1].serialization_format, "cdr");
+
+        let chatter: Vec<String> = reader
+            .topic_messages("/chatter")
+            .unwrap()
+            .map(|m| m.unwrap().decode().unwrap())
+            .collect();
+        assert_eq!(chatter, vec!["hello", "world"]);
+
+        let point: (f64, f64, f64) = reader
+            .topic_messages("/point")
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(point, (1.0, 2.0, 3.0));
+        assert_eq!(
+            reader
+                .messages()
+                .unwrap()
+                .map(|m| m.unwrap().log_time)
+                .collect::<Vec<_>>(),
+            vec![10, 20, 30]
+        );
+    }
+
+    #[test]
+    fn test_read_from_bytes() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer = McapWriter::new(&mut buffer).unwrap();
+        writer
+            .add_topic("/count", "std_msgs/msg/Int32", "int32 data")
+            .unwrap();
+        for (time, value) in [(5, 1i32), (6, 2)] {
+            writer.write("/count", time, &value).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = McapReader::from_bytes(buffer.into_inner()).unwrap();
+        assert_eq!(reader.topics().count(), 1);
+        let values: Vec<i32> = reader
+            .messages()
+            .unwrap()
+            .map(|m| m.unwrap().decode().unwrap())
+            .collect();
+        assert_eq!(values, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_roundtrip_follows_rosbag2_conventions() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer = McapWriter::new(&mut buffer).unwrap();
+        writer
+            .add_topic("/chatter", "std_msgs/msg/String", "string data")
+            .unwrap();
+        writer
+            .add_topic("/echo", "std_msgs/msg/String", "string data")
+            .unwrap();
+        writer.write("/chatter", 1, "hello").unwrap();
+        writer
+            .write_serialized("/echo", 2, &to_cdr("hello"))
+            .unwrap();
+        writer.finish().unwrap();
+        let bytes = buffer.into_inner();
+
+        // Both topics share the schema of their type
+        let summary = mcap::Summary::read(&bytes).unwrap().unwrap();
+        assert_eq!(summary.schemas.len(), 1);
+        let schema = summary.schemas.values().next().unwrap();
+        assert_eq!(schema.name, "std_msgs/msg/String");
+        assert_eq!(schema.encoding, ROS2MSG_SCHEMA_ENCODING);
+        assert!(summary
+            .channels
+            .values()
+            .all(|channel| channel.message_encoding == CDR_SERIALIZATION_FORMAT));
+
+        let reader = McapReader::from_bytes(bytes).unwrap();
+        let messages: Vec<BagMessage> = reader.messages().unwrap().map(Result::unwrap).collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].data, messages[1].data);
+        assert_eq!(messages[1].topic, "/echo");
+        assert_eq!(messages[1].publish_time, 2);
+        assert_eq!(messages[1].decode::<String>().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_read_rejects_non_cdr_channel() {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        let mut writer = mcap::Writer::new(&mut buffer).unwrap();
+        let schema_id = writer.add_schema("Point", "jsonschema", b"{}").unwrap();
+        writer
+            .add_channel(schema_id, "/point", "json", &BTreeMap::new())
+            .unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let result = McapReader::from_bytes(buffer.into_inner());
+        assert!(matches!(
+            result,
+            Err(BagError::UnsupportedFormat { topic, format }) if topic == "/point" && format == "json"
+        ));
+    }
+
+    #[test]
+    fn test_write_unknown_topic_fails() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut writer = McapWriter::create(temp_dir.path().join("test.mcap")).unwrap();
+
+        let result = writer.write("/missing", 0, &1u32);
+        assert!(matches!(result, Err(BagError::UnknownTopic(t)) if t == "/missing"));
+    }
+}
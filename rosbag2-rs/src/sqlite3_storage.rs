@@ -0,0 +1,513 @@
+//! SQLite3 storage backend (read-only)
+//!
+//! Reads bags written by the legacy `sqlite3` storage plugin, which was the
+//! `ros2 bag record` default before Iron. A bag is a directory holding
+//! `metadata.yaml` and one `.db3` file per split; each database has a `topics`
+//! table and a `messages` table with CDR payloads. Bags from Iron onwards may
+//! also carry a `message_definitions` table, which is used for type definitions
+//! when present.
+
+use crate::{BagError, BagMessage, TopicMetadata, CDR_SERIALIZATION_FORMAT};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Messages fetched from a split per query
+const BATCH_SIZE: usize = 1024;
+
+/// Reads ROS 2 messages from a sqlite3 bag
+///
+/// Only the topics are read on open. Messages are streamed from the splits in
+/// timestamp order, a batch at a time from each split.
+#[derive(Debug, Clone, Default)]
+pub struct Sqlite3Reader {
+    topics: BTreeMap<String, TopicMetadata>,
+    databases: Vec<PathBuf>,
+}
+
+impl Sqlite3Reader {
+    /// Open a bag directory or a single `.db3` file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BagError> {
+        let mut reader = Sqlite3Reader {
+            databases: Self::database_files(path.as_ref())?,
+            ..Default::default()
+        };
+
+        for db_path in &reader.databases {
+            let conn = open_database(db_path)?;
+            let definitions = read_message_definitions(&conn)?;
+            for (_, topic) in read_topics(&conn)? {
+                let type_definition = definitions
+                    .get(&topic.type_name)
+                    .cloned()
+                    .unwrap_or_default();
+                reader
+                    .topics
+                    .entry(topic.name.clone())
+                    .or_insert(TopicMetadata {
+                        type_definition,
+                        ..topic
+                    });
+            }
+        }
+
+        Ok(reader)
+    }
+
+    /// Resolve the `.db3` files making up a bag, sorted by file name
+    fn database_files(path: &Path) -> Result<Vec<PathBuf>, BagError> {
+        if !path.is_dir() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        let mut files: Vec<PathBuf> = fs::read_dir(path)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("db3"))
+            .collect();
+        files.sort();
+
+        if files.is_empty() {
+            return Err(BagError::EmptyBag(path.to_path_buf()));
+        }
+        Ok(files)
+    }
+
+    /// Topics recorded in the bag, sorted by name
+    pub fn topics(&self) -> impl Iterator<Item = &TopicMetadata> {
+        self.topics.values()
+    }
+
+    /// All messages ordered by timestamp
+    ///
+    /// Messages with the same timestamp keep the order of their splits.
+    pub fn messages(&self) -> Result<Sqlite3Messages, BagError> {
+        let mut messages = Sqlite3Messages {
+            splits: Vec::new(),
+            heads: Vec::new(),
+            order: BinaryHeap::new(),
+            refill: None,
+        };
+
+        for db_path in &self.databases {
+            let mut split = Split::open(db_path)?;
+            let head = split.next()?;
+            if let Some(message) = &head {
+                messages
+                    .order
+                    .push(Reverse((message.log_time, messages.splits.len())));
+            }
+            messages.splits.push(split);
+            messages.heads.push(head);
+        }
+
+        Ok(messages)
+    }
+
+    /// Messages on a single topic
+    pub fn topic_messages<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> Result<impl Iterator<Item = Result<BagMessage, BagError>> + 'a, BagError> {
+        Ok(self.messages()?.filter(move |message| match message {
+            Ok(message) => message.topic == topic,
+            Err(_) => true,
+        }))
+    }
+}
+
+/// Streaming iterator over the messages of a sqlite3 bag
+///
+/// Each split is read in timestamp order and the splits are merged by the
+/// timestamp of their next message.
+pub struct Sqlite3Messages {
+    splits: Vec<Split>,
+    /// Next message of each split
+    heads: Vec<Option<BagMessage>>,
+    /// Timestamps of the heads, by split index
+    order: BinaryHeap<Reverse<(u64, usize)>>,
+    /// Split whose head was just returned
+    refill: Option<usize>,
+}
+
+impl Iterator for Sqlite3Messages {
+    type Item = Result<BagMessage, BagError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Refill lazily so that an error is returned on its own; the split is
+        // dropped from the merge after it
+        if let Some(index) = self.refill.take() {
+            match self.splits[index].next() {
+                Ok(Some(message)) => {
+                    self.order.push(Reverse((message.log_time, index)));
+                    self.heads[index] = Some(message);
+                }
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        let Reverse((_, index)) = self.order.pop()?;
+        self.refill = Some(index);
+        self.heads[index].take().map(Ok)
+    }
+}
+
+/// Cursor over the messages of one `.db3` file, ordered by timestamp
+///
+/// Messages are fetched in batches, each query resuming after the last row of
+/// the previous one, so that the connection is not borrowed between calls.
+struct Split {
+    conn: Connection,
+    /// Topic names by id; ids are local to each database file
+    topic_names: HashMap<i64, String>,
+    /// Timestamp and id of the last row fetched
+    last: (i64, i64),
+    pending: VecDeque<BagMessage>,
+    done: bool,
+}
+
+impl Split {
+    fn open(db_path: &Path) -> Result<Self, BagError> {
+        let conn = open_database(db_path)?;
+        let topic_names = read_topics(&conn)?
+            .into_iter()
+            .map(|(id, topic)| (id, topic.name))
+            .collect();
+
+        Ok(Self {
+            conn,
+            topic_names,
+            last: (i64::MIN, i64::MIN),
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    fn next(&mut self) -> Result<Option<BagMessage>, BagError> {
+        while self.pending.is_empty() && !self.done {
+            self.fetch()?;
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn fetch(&mut self) -> Result<(), BagError> {
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT id, topic_id, timestamp, data FROM messages \
+             WHERE (timestamp, id) > (?1, ?2) ORDER BY timestamp, id LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![self.last.0, self.last.1, BATCH_SIZE], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Vec<u8>>(3)?,
+            ))
+        })?;
+
+        let mut count = 0;
+        for row in rows {
+            let (id, topic_id, timestamp, data) = row?;
+            count += 1;
+            self.last = (timestamp, id);
+            // Messages referencing a missing topic row cannot be decoded; skip them
+            let Some(topic) = self.topic_names.get(&topic_id) else {
+                continue;
+            };
+            self.pending.push_back(BagMessage {
+                topic: topic.clone(),
+                log_time: timestamp as u64,
+                // sqlite3 bags only store the receive time
+                publish_time: timestamp as u64,
+                data,
+            });
+        }
+        self.done = count < BATCH_SIZE;
+
+        Ok(())
+    }
+}
+
+fn open_database(db_path: &Path) -> Result<Connection, BagError> {
+    Ok(Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?)
+}
+
+/// Read the `topics` table, by topic id, without type definitions
+///
+/// Fails on topics whose messages are not CDR-encoded.
+fn read_topics(conn: &Connection) -> Result<Vec<(i64, TopicMetadata)>, BagError> {
+    let mut stmt = conn.prepare("SELECT id, name, type, serialization_format FROM topics")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            TopicMetadata {
+                name: row.get(1)?,
+                type_name: row.get(2)?,
+                serialization_format: row.get(3)?,
+                type_definition: String::new(),
+            },
+        ))
+    })?;
+
+    let topics: Vec<(i64, TopicMetadata)> = rows.collect::<Result<_, _>>()?;
+    for (_, topic) in &topics {
+        check_serialization_format(topic)?;
+    }
+    Ok(topics)
+}
+
+/// Reject topics whose messages are not CDR-encoded
+fn check_serialization_format(topic: &TopicMetadata) -> Result<(), BagError> {
+    if topic.serialization_format != CDR_SERIALIZATION_FORMAT {
+        return Err(BagError::UnsupportedFormat {
+            topic: topic.name.clone(),
+            format: topic.serialization_format.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Read `message_definitions` (Iron+), returning an empty map for older bags
+fn read_message_definitions(conn: &Connection) -> Result<HashMap<String, String>, BagError> {
+    let has_table: Option<String> = conn
+        .query_row(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'message_definitions'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if has_table.is_none() {
+        return Ok(HashMap::new());
+    }
+
+    let mut stmt =
+        conn.prepare("SELECT topic_type, encoded_message_definition FROM message_definitions")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut definitions = HashMap::new();
+    for row in rows {
+        let (topic_type, definition) = row?;
+        definitions.insert(topic_type, definition);
+    }
+    Ok(definitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_runtime_rs::cdr::to_cdr;
+
+    /// Create a database with the schema written by rosbag2_storage_default_plugins
+    fn create_bag_db(path: &Path, with_definitions: bool) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE topics(id INTEGER PRIMARY KEY, name TEXT NOT NULL, type TEXT NOT NULL, \
+             serialization_format TEXT NOT NULL, offered_qos_profiles TEXT NOT NULL);
+             CREATE TABLE messages(id INTEGER PRIMARY KEY, topic_id INTEGER NOT NULL, \
+             timestamp INTEGER NOT NULL, data BLOB NOT NULL);",
+        )
+        .unwrap();
+        if with_definitions {
+            conn.execute_batch(
+                "CREATE TABLE message_definitions(id INTEGER PRIMARY KEY, topic_type TEXT NOT NULL, \
+                 encoding TEXT NOT NULL, encoded_message_definition TEXT NOT NULL, \
+                 type_description_hash TEXT NOT NULL);
+                 INSERT INTO message_definitions VALUES(1, 'std_msgs/msg/String', 'ros2msg', 'string data', '');",
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn insert_message(conn: &Connection, topic_id: i64, timestamp: i64, data: &[u8]) {
+        conn.execute(
+            "INSERT INTO messages(topic_id, timestamp, data) VALUES(?1, ?2, ?3)",
+            rusqlite::params![topic_id, timestamp, data],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_read_db3_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("bag_0.db3");
+        let conn = create_bag_db(&db_path, true);
+        conn.execute_batch(
+            "INSERT INTO topics VALUES(1, '/chatter', 'std_msgs/msg/String', 'cdr', '');
+             INSERT INTO topics VALUES(2, '/count', 'std_msgs/msg/Int32', 'cdr', '');",
+        )
+        .unwrap();
+        insert_message(&conn, 1, 200, &to_cdr("world"));
+        insert_message(&conn, 2, 150, &to_cdr(&7i32));
+        insert_message(&conn, 1, 100, &to_cdr("hello"));
+        drop(conn);
+
+        let reader = Sqlite3Reader::open(&db_path).unwrap();
+
+        let topics: Vec<_> = reader.topics().collect();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].name, "/chatter");
+        assert_eq!(topics[0].type_definition, "string data");
+        assert_eq!(topics[1].type_name, "std_msgs/msg/Int32");
+        assert_eq!(topics[1].type_definition, "");
+
+        let times: Vec<u64> = reader
+            .messages()
+            .unwrap()
+            .map(|m| m.unwrap().log_time)
+            .collect();
+        assert_eq!(times, vec![100, 150, 200]);
+
+        let chatter: Vec<String> = reader
+            .topic_messages("/chatter")
+            .unwrap()
+            .map(|m| m.unwrap().decode().unwrap())
+            .collect();
+        assert_eq!(chatter, vec!["hello", "world"]);
+
+        let count: i32 = reader
+            .topic_messages("/count")
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(count, 7);
+    }
+
+    #[test]
+    fn test_read_split_bag_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for (index, timestamp) in [(0i32, 10), (1, 20)] {
+            let conn = create_bag_db(&temp_dir.path().join(format!("bag_{}.db3", index)), false);
+            conn.execute_batch(
+                "INSERT INTO topics VALUES(1, '/count', 'std_msgs/msg/Int32', 'cdr', '');",
+            )
+            .unwrap();
+            insert_message(&conn, 1, timestamp, &to_cdr(&index));
+        }
+        fs::write(
+            temp_dir.path().join("metadata.yaml"),
+            "rosbag2_bagfile_information: {}",
+        )
+        .unwrap();
+
+        let reader = Sqlite3Reader::open(temp_dir.path()).unwrap();
+        let values: Vec<i32> = reader
+            .messages()
+            .unwrap()
+            .map(|m| m.unwrap().decode().unwrap())
+            .collect();
+        assert_eq!(values, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_merge_interleaved_splits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let splits: [&[i64]; 3] = [&[10, 30, 30, 60], &[20, 30, 50], &[]];
+        for (index, timestamps) in splits.iter().enumerate() {
+            let conn = create_bag_db(&temp_dir.path().join(format!("bag_{}.db3", index)), false);
+            // Topic ids differ between splits
+            conn.execute_batch(&format!(
+                "INSERT INTO topics VALUES({}, '/count', 'std_msgs/msg/Int32', 'cdr', '');",
+                index + 1
+            ))
+            .unwrap();
+            for timestamp in timestamps.iter().rev() {
+                insert_message(
+                    &conn,
+                    index as i64 + 1,
+                    *timestamp,
+                    &to_cdr(&(index as i32)),
+                );
+            }
+        }
+
+        let reader = Sqlite3Reader::open(temp_dir.path()).unwrap();
+        assert_eq!(reader.topics().count(), 1);
+        let messages: Vec<(u64, i32)> = reader
+            .messages()
+            .unwrap()
+            .map(|m| {
+                let m = m.unwrap();
+                (m.log_time, m.decode().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (10, 0),
+                (20, 1),
+                (30, 0),
+                (30, 0),
+                (30, 1),
+                (50, 1),
+                (60, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_across_batches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("bag_0.db3");
+        let mut conn = create_bag_db(&db_path, false);
+        conn.execute_batch(
+            "INSERT INTO topics VALUES(1, '/count', 'std_msgs/msg/Int32', 'cdr', '');",
+        )
+        .unwrap();
+        let total = 2 * BATCH_SIZE + 1;
+        let transaction = conn.transaction().unwrap();
+        for value in 0..total as i32 {
+            // Pairs of messages share a timestamp, some of them across batches
+            insert_message(&transaction, 1, (value / 2) as i64, &to_cdr(&value));
+            // Messages of an unknown topic are skipped
+            insert_message(&transaction, 9, (value / 2) as i64, &[]);
+        }
+        transaction.commit().unwrap();
+        drop(conn);
+
+        let reader = Sqlite3Reader::open(&db_path).unwrap();
+        let values: Vec<i32> = reader
+            .messages()
+            .unwrap()
+            .map(|m| m.unwrap().decode().unwrap())
+            .collect();
+        assert_eq!(values, (0..total as i32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_read_rejects_non_cdr_topic() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db_path = temp_dir.path().join("bag_0.db3");
+        let conn = create_bag_db(&db_path, false);
+        conn.execute_batch(
+            "INSERT INTO topics VALUES(1, '/chatter', 'std_msgs/msg/String', 'cdr', '');
+             INSERT INTO topics VALUES(2, '/point', 'geometry_msgs/msg/Point', 'json', '');",
+        )
+        .unwrap();
+        insert_message(&conn, 2, 100, b"{}");
+        drop(conn);
+
+        let result = Sqlite3Reader::open(&db_path);
+        assert!(matches!(
+            result,
+            Err(BagError::UnsupportedFormat { topic, format }) if topic == "/point" && format == "json"
+        ));
+    }
+
+    #[test]
+    fn test_empty_bag_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = Sqlite3Reader::open(temp_dir.path());
+        assert!(matches!(result, Err(BagError::EmptyBag(_))));
+    }
+}
@@ -1,7 +1,8 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use rosidl_codegen::{generate_action_package, generate_message_package, generate_service_package};
 use rosidl_parser::{parse_action, parse_message, parse_service};
 use std::collections::HashSet;
+use std::hint::black_box;
 
 fn benchmark_simple_message(c: &mut Criterion) {
     let msg_def = "int32 x\nfloat64 y\nstring name\n";
@@ -0,0 +1,37 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    // Generated code depends on the templates and on the Rust code rendering
+    // them, so both go into the version recorded with generated bindings
+    let mut files = Vec::new();
+    for dir in ["src", "templates"] {
+        println!("cargo:rerun-if-changed={dir}");
+        collect_files(Path::new(dir), &mut files);
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in &files {
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(fs::read(file).expect("failed to read codegen source"));
+    }
+    let hash: String = hasher.finalize()[..6]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    println!("cargo:rustc-env=ROSIDL_CODEGEN_SOURCE_HASH={hash}");
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).expect("failed to read codegen sources") {
+        let path = entry.expect("failed to read codegen sources").path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,286 @@
+//! C++ interop through cxx
+//!
+//! Emits `src/cxx_bridge.rs` for a package, compiled behind its `cxx` feature: a
+//! `#[cxx::bridge]` declaring one shared struct per message (namespace
+//! `<package>::msg`) and `From` conversions in both directions between the shared
+//! structs and the idiomatic messages. cxx-build generates the matching header,
+//! `<package>/src/cxx_bridge.rs.h`, from the build script. Shared structs of other
+//! packages are reused through their bridges, so the feature is forwarded to every
+//! dependency.
+
+use crate::types::{escape_keyword, to_snake_case};
+use crate::utils::nested_type;
+use rosidl_parser::{FieldType, Message};
+use std::collections::BTreeSet;
+
+/// Member of empty messages, which cxx (like C) does not allow
+const EMPTY_STRUCT_MEMBER: &str = "structure_needs_at_least_one_member";
+
+/// C++ keywords that are valid Rust identifiers, renamed with a trailing `_` on the C++ side
+const CXX_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "and",
+    "asm",
+    "auto",
+    "bool",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "default",
+    "delete",
+    "double",
+    "explicit",
+    "export",
+    "float",
+    "friend",
+    "goto",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "operator",
+    "or",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "short",
+    "signed",
+    "sizeof",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "virtual",
+    "void",
+    "volatile",
+    "xor",
+];
+
+/// Packages whose bridges the package's messages reuse
+pub fn cxx_dependencies(package: &str, messages: &[(String, Message)]) -> BTreeSet<String> {
+    let mut deps = BTreeSet::new();
+    for (_, message) in messages {
+        for field in &message.fields {
+            if let Some((Some(dep), _)) = nested_type(&field.field_type) {
+                if dep != package {
+                    deps.insert(dep.to_string());
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// Generate `src/cxx_bridge.rs` for the package's messages
+pub fn generate_cxx_bridge(package: &str, messages: &[(String, Message)]) -> String {
+    let mut out = format!(
+        "// C++ interop for ROS 2 package: {package}\n\
+         // Compiled with the `cxx` feature; the header is generated by build.rs\n\n\
+         #[cxx::bridge(namespace = \"{package}::msg\")]\n\
+         pub mod ffi {{\n"
+    );
+
+    for (name, message) in messages {
+        out.push_str(&format!("    /// {}/msg/{}\n", package, name));
+        out.push_str("    #[derive(Debug, Clone, PartialEq)]\n");
+        out.push_str(&format!("    struct {} {{\n", name));
+        if message.fields.is_empty() {
+            out.push_str(&format!("        {}: u8,\n", EMPTY_STRUCT_MEMBER));
+        }
+        for field in &message.fields {
+            if CXX_KEYWORDS.contains(&field.name.as_str()) {
+                out.push_str(&format!("        #[cxx_name = \"{}_\"]\n", field.name));
+            }
+            out.push_str(&format!(
+                "        {}: {},\n",
+                escape_keyword(&field.name),
+                bridge_type(package, &field.field_type)
+            ));
+        }
+        out.push_str("    }\n\n");
+    }
+
+    // Shared structs of other packages, reused from their bridges
+    let mut external: BTreeSet<(String, String)> = BTreeSet::new();
+    for (_, message) in messages {
+        for field in &message.fields {
+            if let Some((Some(dep), name)) = nested_type(&field.field_type) {
+                if dep != package {
+                    external.insert((dep.to_string(), name.to_string()));
+                }
+            }
+        }
+    }
+    if !external.is_empty() {
+        out.push_str("    extern \"C++\" {\n");
+        let includes: BTreeSet<&str> = external.iter().map(|(dep, _)| dep.as_str()).collect();
+        for dep in includes {
+            out.push_str(&format!(
+                "        include!(\"{}/src/cxx_bridge.rs.h\");\n",
+                dep
+            ));
+        }
+        for (dep, name) in &external {
+            out.push_str(&format!(
+                "        #[namespace = \"{dep}::msg\"]\n        #[cxx_name = \"{name}\"]\n        type {alias} = {dep}::cxx_bridge::ffi::{name};\n",
+                alias = external_alias(dep, name),
+            ));
+        }
+        out.push_str("    }\n\n");
+    }
+
+    // Let dependent bridges use rust::Vec of these structs
+    for (name, _) in messages {
+        out.push_str(&format!("    impl Vec<{}> {{}}\n", name));
+    }
+    out.push_str("}\n");
+
+    for (name, message) in messages {
+        let idiomatic = format!("crate::msg::{}::{}", to_snake_case(name), name);
+        let arg = if message.fields.is_empty() {
+            "_msg"
+        } else {
+            "msg"
+        };
+
+        out.push_str(&format!(
+            "\nimpl From<{idiomatic}> for ffi::{name} {{\n    fn from({arg}: {idiomatic}) -> Self {{\n        Self {{\n"
+        ));
+        if message.fields.is_empty() {
+            out.push_str(&format!("            {}: 0,\n", EMPTY_STRUCT_MEMBER));
+        }
+        for field in &message.fields {
+            let field_name = escape_keyword(&field.name);
+            out.push_str(&format!(
+                "            {}: {},\n",
+                field_name,
+                convert(&field.field_type, &format!("msg.{}", field_name))
+            ));
+        }
+        out.push_str("        }\n    }\n}\n");
+
+        out.push_str(&format!(
+            "\nimpl From<ffi::{name}> for {idiomatic} {{\n    fn from({arg}: ffi::{name}) -> Self {{\n        Self {{\n"
+        ));
+        for field in &message.fields {
+            let field_name = escape_keyword(&field.name);
+            out.push_str(&format!(
+                "            {}: {},\n",
+                field_name,
+                convert(&field.field_type, &format!("msg.{}", field_name))
+            ));
+        }
+        out.push_str("        }\n    }\n}\n");
+    }
+    out
+}
+
+/// Name of a shared struct of another package inside this bridge (`std_msgs_Header`)
+fn external_alias(package: &str, name: &str) -> String {
+    format!("{}_{}", package, name)
+}
+
+/// Field type inside the bridge
+fn bridge_type(package: &str, field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Primitive(primitive) => primitive.rust_type().to_string(),
+        FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_) => "String".to_string(),
+        FieldType::NamespacedType {
+            package: Some(type_package),
+            name,
+        } if type_package != package => external_alias(type_package, name),
+        FieldType::NamespacedType { name, .. } => name.clone(),
+        FieldType::Array { element_type, size } => {
+            format!("[{}; {}]", bridge_type(package, element_type), size)
+        }
+        FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. } => {
+            format!("Vec<{}>", bridge_type(package, element_type))
+        }
+    }
+}
+
+/// Expression converting a field between the idiomatic and the shared struct
+///
+/// Both sides use the same Rust types except for nested messages, so the
+/// expression is the same in either direction.
+fn convert(field_type: &FieldType, value: &str) -> String {
+    match field_type {
+        FieldType::NamespacedType { .. } => format!("{}.into()", value),
+        FieldType::Array { element_type, .. } if nested_type(element_type).is_some() => {
+            format!("{}.map(Into::into)", value)
+        }
+        FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. }
+            if nested_type(element_type).is_some() =>
+        {
+            format!("{}.into_iter().map(Into::into).collect()", value)
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::messages;
+
+    #[test]
+    fn test_cxx_bridge() {
+        let msgs = messages(&[
+            (
+                "Scan",
+                "std_msgs/Header header\nfloat32[] ranges\nbyte[4] id\nstring type\nint32 class\nLocal[2] parts\ngeometry_msgs/Point[] points\n",
+            ),
+            ("Local", "int32 id\n"),
+        ]);
+        let rust = generate_cxx_bridge("my_msgs", &msgs);
+
+        assert!(rust.contains("#[cxx::bridge(namespace = \"my_msgs::msg\")]\npub mod ffi {\n"));
+        assert!(rust.contains("    struct Scan {\n        header: std_msgs_Header,\n"));
+        assert!(rust.contains("        ranges: Vec<f32>,\n"));
+        assert!(rust.contains("        id: [u8; 4],\n"));
+        assert!(rust.contains("        type_: String,\n"));
+        assert!(rust.contains("        #[cxx_name = \"class_\"]\n        class: i32,\n"));
+        assert!(rust.contains("        parts: [Local; 2],\n"));
+        assert!(rust.contains("        points: Vec<geometry_msgs_Point>,\n"));
+        assert!(rust.contains("        include!(\"geometry_msgs/src/cxx_bridge.rs.h\");\n        include!(\"std_msgs/src/cxx_bridge.rs.h\");\n"));
+        assert!(rust.contains("        #[namespace = \"std_msgs::msg\"]\n        #[cxx_name = \"Header\"]\n        type std_msgs_Header = std_msgs::cxx_bridge::ffi::Header;\n"));
+        assert!(rust.contains("    impl Vec<Local> {}\n"));
+
+        assert!(rust.contains("impl From<crate::msg::scan::Scan> for ffi::Scan {"));
+        assert!(rust.contains("impl From<ffi::Scan> for crate::msg::scan::Scan {"));
+        assert!(rust.contains("            header: msg.header.into(),\n"));
+        assert!(rust.contains("            ranges: msg.ranges,\n"));
+        assert!(rust.contains("            parts: msg.parts.map(Into::into),\n"));
+        assert!(rust
+            .contains("            points: msg.points.into_iter().map(Into::into).collect(),\n"));
+
+        let deps: Vec<_> = cxx_dependencies("my_msgs", &msgs).into_iter().collect();
+        assert_eq!(deps, vec!["geometry_msgs", "std_msgs"]);
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let rust = generate_cxx_bridge("std_msgs", &messages(&[("Empty", "")]));
+        assert!(rust.contains(
+            "    struct Empty {\n        structure_needs_at_least_one_member: u8,\n    }\n"
+        ));
+        assert!(rust.contains("            structure_needs_at_least_one_member: 0,\n"));
+        assert!(rust.contains("fn from(_msg: crate::msg::empty::Empty) -> Self {"));
+        assert!(!rust.contains("extern \"C++\""));
+    }
+}
@@ -0,0 +1,145 @@
+//! Reference cycles between the messages of a package
+//!
+//! A message may nest itself through the messages of its fields, such as a tree
+//! node holding a sequence of child nodes. Sequences are heap-allocated in the
+//! idiomatic and RMW layers, so such types have a finite size, but embedded
+//! bindings store sequences inline and rkyv must not require its traits of the
+//! nested type while deriving them for it. The generators read the fields found
+//! here to add that indirection. Cycles run within one package, as packages
+//! cannot depend on each other both ways.
+
+use crate::generator::GeneratorError;
+use rosidl_parser::{FieldType, Message};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Fields of the package's messages that nest a message which in turn nests the
+/// field's own message, as `(message, field)` pairs
+///
+/// Fails when a message nests itself through single messages and arrays only:
+/// its values would be infinitely large, so only a sequence can close a cycle.
+pub fn cyclic_fields(
+    package_name: &str,
+    messages: &BTreeMap<String, Message>,
+) -> Result<BTreeSet<(String, String)>, GeneratorError> {
+    let mut cyclic = BTreeSet::new();
+    for (message_name, message) in messages {
+        for field in &message.fields {
+            let Some((target, inline)) = nested_message(&field.field_type, package_name) else {
+                continue;
+            };
+            if !reaches(messages, package_name, target, message_name, false) {
+                continue;
+            }
+            if inline && reaches(messages, package_name, target, message_name, true) {
+                return Err(GeneratorError::InvalidMessage(
+                    "nests its own message without a sequence in between".to_string(),
+                )
+                .in_field(package_name, message_name, &field.name));
+            }
+            cyclic.insert((message_name.clone(), field.name.clone()));
+        }
+    }
+    Ok(cyclic)
+}
+
+/// Message of the package nested in a field, and whether it is stored inline
+/// (a single message or an array of them, not a sequence)
+fn nested_message<'a>(field_type: &'a FieldType, package_name: &str) -> Option<(&'a str, bool)> {
+    match field_type {
+        FieldType::NamespacedType { package, name }
+            if package
+                .as_deref()
+                .is_none_or(|package| package == package_name) =>
+        {
+            Some((name, true))
+        }
+        FieldType::Array { element_type, .. } => nested_message(element_type, package_name),
+        FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. } => {
+            nested_message(element_type, package_name).map(|(name, _)| (name, false))
+        }
+        _ => None,
+    }
+}
+
+/// Whether message `from` is `to` or nests it, through inline fields only if `inline_only`
+fn reaches(
+    messages: &BTreeMap<String, Message>,
+    package_name: &str,
+    from: &str,
+    to: &str,
+    inline_only: bool,
+) -> bool {
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![from];
+    while let Some(name) = pending.pop() {
+        if name == to {
+            return true;
+        }
+        if !visited.insert(name) {
+            continue;
+        }
+        let Some(message) = messages.get(name) else {
+            continue;
+        };
+        pending.extend(
+            message
+                .fields
+                .iter()
+                .filter_map(|field| nested_message(&field.field_type, package_name))
+                .filter(|(_, inline)| *inline || !inline_only)
+                .map(|(name, _)| name),
+        );
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_parser::parse_message;
+
+    fn package(messages: &[(&str, &str)]) -> BTreeMap<String, Message> {
+        messages
+            .iter()
+            .map(|(name, text)| (name.to_string(), parse_message(text).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cyclic_fields() {
+        let messages = package(&[
+            ("Node", "string label\nNode[] children\nEdge[<=2] edges\n"),
+            ("Edge", "Node target\nfloat64 weight\n"),
+            ("Leaf", "Node[] unrelated\ntree_msgs/Leaf[] qualified\n"),
+        ]);
+        let cyclic = cyclic_fields("tree_msgs", &messages).unwrap();
+        let fields: Vec<_> = cyclic
+            .iter()
+            .map(|(message, field)| format!("{}.{}", message, field))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "Edge.target",
+                "Leaf.qualified",
+                "Node.children",
+                "Node.edges"
+            ]
+        );
+
+        // Without sequences the values would never end
+        let messages = package(&[("A", "B b\n"), ("B", "A[2] a\n")]);
+        let err = cyclic_fields("tree_msgs", &messages).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed generating tree_msgs/A field `b` (Invalid message structure: \
+             nests its own message without a sequence in between)"
+        );
+
+        assert!(
+            cyclic_fields("other_msgs", &package(&[("A", "tree_msgs/A a\n")]))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -1,16 +1,25 @@
+use crate::ir::StructIr;
+use crate::naming::Naming;
 use crate::templates::{
-    ActionIdiomaticTemplate, ActionRmwTemplate, BuildRsTemplate, CargoTomlTemplate, IdiomaticField,
-    LibRsTemplate, MessageConstant, MessageIdiomaticTemplate, MessageRmwTemplate, RmwField,
-    ServiceIdiomaticTemplate, ServiceRmwTemplate,
+    ActionIdiomaticTemplate, ActionRmwTemplate, BoundSettersTemplate, BoundedField, BoundedStruct,
+    BuildRsTemplate, CargoTomlTemplate, CdrStruct, CdrTemplate, ConstantEnum, ConstantVariant,
+    ImplicitMessage, IntrospectionMember, LayoutCheck, LayoutField, LibRsTemplate,
+    MessageBuiltinTemplate, MessageConstantEnumsTemplate, MessageEmbeddedTemplate,
+    MessageIdiomaticTemplate, MessageRmwTemplate, MessageTypesOnlyTemplate, RmwField,
+    RmwLayoutTemplate, SerdeArrayTemplate, ServiceIdiomaticTemplate, ServiceRmwTemplate,
+    TypesOnlyField,
 };
 use crate::types::{
-    constant_value_to_rust, escape_keyword, is_array_type, is_large_array, is_primitive_sequence,
-    is_primitive_type, is_sequence_type, rust_type_for_field,
+    bound_check, c_layout, comment_docs, constant_rust_type, constant_value_to_rust,
+    embedded_type_for_field, escape_keyword, rust_type_for_field, to_upper_camel_case,
+    typed_constant_value,
 };
-use crate::utils::{extract_dependencies, needs_big_array};
+use crate::utils::{check_packages, extract_dependencies, to_snake_case};
 use askama::Template;
-use rosidl_parser::{Action, Message, Service};
-use std::collections::HashSet;
+use rosidl_parser::ast::ConstantValue;
+use rosidl_parser::{Action, BuiltinType, Constant, FieldType, Message, PrimitiveType, Service};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -20,6 +29,290 @@ pub enum GeneratorError {
 
     #[error("Invalid message structure: {0}")]
     InvalidMessage(String),
+
+    /// Nested type that could not be resolved, as `package/Name`
+    #[error("unknown type {0}")]
+    UnknownType(String),
+
+    /// Package that is not among the known ones, with the closest known name
+    #[error(
+        "unknown package {package}{}",
+        suggestion.as_ref().map(|s| format!(", did you mean {}?", s)).unwrap_or_default()
+    )]
+    UnknownPackage {
+        package: String,
+        suggestion: Option<String>,
+    },
+
+    /// Error in one field of an interface
+    #[error("failed generating {package}/{interface} field `{field}` ({cause})")]
+    Field {
+        package: String,
+        interface: String,
+        field: String,
+        cause: Box<GeneratorError>,
+    },
+
+    /// Error in an interface, outside of a single field
+    #[error("failed generating {package}/{interface} ({cause})")]
+    Interface {
+        package: String,
+        interface: String,
+        cause: Box<GeneratorError>,
+    },
+}
+
+impl GeneratorError {
+    /// Attribute the error to a field of an interface
+    pub fn in_field(self, package: &str, interface: &str, field: &str) -> Self {
+        Self::Field {
+            package: package.to_string(),
+            interface: interface.to_string(),
+            field: field.to_string(),
+            cause: Box::new(self),
+        }
+    }
+
+    /// Attribute the error to an interface, unless it already names one
+    pub fn in_interface(self, package: &str, interface: &str) -> Self {
+        match self {
+            Self::Field { .. } | Self::Interface { .. } => self,
+            cause => Self::Interface {
+                package: package.to_string(),
+                interface: interface.to_string(),
+                cause: Box::new(cause),
+            },
+        }
+    }
+
+    /// Innermost error, without the interface and field context
+    pub fn root_cause(&self) -> &GeneratorError {
+        match self {
+            Self::Field { cause, .. } | Self::Interface { cause, .. } => cause.root_cause(),
+            other => other,
+        }
+    }
+}
+
+/// Top-level module name for C-compatible FFI layer (Foreign Function Interface).
+/// This is placed at the crate root level to avoid conflicts with any message/service/action names.
+///
+/// The dual-layer architecture is:
+/// - `pkg::ffi::msg::Type` - C-compatible FFI structs for interop with ROS C libraries
+/// - `pkg::msg::Type` - Idiomatic Rust wrappers with safe types (String, Vec, etc.)
+///
+/// By placing `ffi` at the package root (not nested in msg/srv/action), it cannot conflict
+/// with any message names (e.g., ffi.msg, rmw.msg, etc.)
+pub const FFI_MODULE: &str = "ffi";
+
+/// Package providing `ServiceEventInfo` for service event messages
+pub const SERVICE_MSGS_PACKAGE: &str = "service_msgs";
+
+/// Package providing the `UUID` of action goals
+pub const UNIQUE_IDENTIFIER_MSGS_PACKAGE: &str = "unique_identifier_msgs";
+
+/// Package providing the `Time` a goal is accepted at
+pub const BUILTIN_INTERFACES_PACKAGE: &str = "builtin_interfaces";
+
+/// Version requirement of the upstream `rosidl_runtime_rs` with `rclrs_compat`:
+/// the 0.4 series, which rclrs 0.4 depends on
+pub const RCLRS_RUNTIME_VERSION: &str = "0.4";
+
+/// Options shared by the package generators
+#[derive(Debug, Clone, Default)]
+pub struct GeneratorOptions {
+    /// Generate code against the upstream ros2-rust `rosidl_runtime_rs` trait
+    /// signatures, so the bindings can be used with rclrs directly
+    pub rclrs_compat: bool,
+    /// Version requirement of the upstream `rosidl_runtime_rs` with
+    /// `rclrs_compat`, for an rclrs release on another series; defaults to
+    /// [`RCLRS_RUNTIME_VERSION`]
+    pub rclrs_runtime_version: Option<String>,
+    /// Also generate the REP-2012 `<Service>_Event` message for each service,
+    /// used for service introspection (ROS 2 Iron and later, needs `service_msgs`)
+    pub service_events: bool,
+    /// Generate only the idiomatic message types, with defaults and CDR encoding
+    /// in plain Rust. Nothing links against the ROS C libraries, so the bindings
+    /// build for `wasm32-unknown-unknown` (with `rosidl_runtime_rs/mock-ffi`)
+    pub types_only: bool,
+    /// Generate `no_std` message types for embedded targets (micro-ROS): strings
+    /// and sequences are stored inline with these capacities, nothing links
+    /// against the ROS C libraries and serde is opt-in
+    pub embedded: Option<EmbeddedCapacity>,
+    /// Extra derives of the idiomatic message, service and action structs, such
+    /// as `Eq` or `Hash`; each struct gets the ones its fields support
+    pub derives: Vec<String>,
+    /// Extra attributes of the idiomatic structs, such as `#[non_exhaustive]`
+    pub attributes: Vec<String>,
+    /// Also generate an enum with `From`/`TryFrom` conversions for each group of
+    /// integer constants sharing a name prefix, such as the `STATUS_*` constants
+    /// of `action_msgs/GoalStatus`
+    pub constant_enums: bool,
+    /// Also bind the `rosidl_typesupport_introspection_c` type support of each
+    /// message, for runtime reflection through `rosidl_runtime_rs::introspection`
+    pub introspection: bool,
+    /// Also alias the constants of each struct, its associated consts, as
+    /// module-level consts, where bindings before associated consts had them
+    pub module_constants: bool,
+    /// Fields of the package's messages on a reference cycle, as found by
+    /// [`cyclic_fields`](crate::cyclic_fields): embedded bindings keep their
+    /// sequences on the heap and rkyv skips the bounds of the nested type
+    pub cyclic_fields: BTreeSet<(String, String)>,
+    /// Gate each interface of the package behind a cargo feature of its own, all
+    /// enabled by a default `full` feature, so crates can build only what they use
+    pub interface_features: bool,
+    /// Name keyword fields with raw identifiers (`r#type`) instead of appending an
+    /// underscore (`type_`), so Rust and serde use the name of the definition
+    pub raw_identifiers: bool,
+    /// Crate names, exported type names and serde field names
+    pub naming: Naming,
+}
+
+impl GeneratorOptions {
+    /// Whether only message types are generated, without the RMW layer, services
+    /// and actions (types-only and embedded bindings)
+    pub fn messages_only(&self) -> bool {
+        self.types_only || self.embedded.is_some()
+    }
+}
+
+/// Capacities of unbounded strings and sequences in embedded bindings
+///
+/// Bounded strings and sequences keep the bound of the message definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedCapacity {
+    /// Maximum length of a `string`/`wstring` in bytes
+    pub string: usize,
+    /// Maximum number of elements of a `T[]` sequence
+    pub sequence: usize,
+}
+
+impl Default for EmbeddedCapacity {
+    /// Same defaults as micro-ROS (`micro_ros_utilities`)
+    fn default() -> Self {
+        Self {
+            string: 20,
+            sequence: 5,
+        }
+    }
+}
+
+/// Parsed interface to generate code for
+#[derive(Debug, Clone, Copy)]
+pub enum Interface<'a> {
+    Message(&'a Message),
+    Service(&'a Service),
+    Action(&'a Action),
+}
+
+impl<'a> Interface<'a> {
+    /// Module of the interface kind: `msg`, `srv` or `action`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Interface::Message(_) => "msg",
+            Interface::Service(_) => "srv",
+            Interface::Action(_) => "action",
+        }
+    }
+
+    /// Messages the interface is made of, as written in its file
+    pub fn messages(&self) -> Vec<&'a Message> {
+        match *self {
+            Interface::Message(message) => vec![message],
+            Interface::Service(service) => vec![&service.request, &service.response],
+            Interface::Action(action) => {
+                vec![
+                    &action.spec.goal,
+                    &action.spec.result,
+                    &action.spec.feedback,
+                ]
+            }
+        }
+    }
+}
+
+/// Source files generated for one interface
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratedArtifacts {
+    /// Files as (path relative to the crate's `src` directory, content)
+    pub files: Vec<(PathBuf, String)>,
+}
+
+/// Generate the source files of a message, service or action
+///
+/// The RMW layer goes to `ffi/<kind>/<name>_rmw.rs` (absent for types-only and
+/// embedded messages) and the idiomatic layer to `<kind>/<name>_idiomatic.rs`,
+/// with `<name>` in snake_case.
+///
+/// Unless `known_packages` is empty, a field of a package other than
+/// `package_name` and the known ones is an error.
+pub fn generate_interface(
+    package_name: &str,
+    name: &str,
+    interface: Interface<'_>,
+    known_packages: &HashSet<String>,
+    options: GeneratorOptions,
+) -> Result<GeneratedArtifacts, GeneratorError> {
+    if !known_packages.is_empty() {
+        for message in interface.messages() {
+            check_packages(package_name, name, message, known_packages)?;
+        }
+    }
+    // The crate manifest is generated by the caller
+    let all_dependencies = &HashSet::new();
+    let in_interface = |err: GeneratorError| err.in_interface(package_name, name);
+    let (rmw, idiomatic) = match interface {
+        Interface::Message(message) => {
+            let generated = generate_message_package_with_options(
+                package_name,
+                name,
+                message,
+                all_dependencies,
+                options,
+            )
+            .map_err(in_interface)?;
+            (generated.message_rmw, generated.message_idiomatic)
+        }
+        Interface::Service(service) => {
+            let generated = generate_service_package_with_options(
+                package_name,
+                name,
+                service,
+                all_dependencies,
+                options,
+            )
+            .map_err(in_interface)?;
+            (generated.service_rmw, generated.service_idiomatic)
+        }
+        Interface::Action(action) => {
+            let generated = generate_action_package_with_options(
+                package_name,
+                name,
+                action,
+                all_dependencies,
+                options,
+            )
+            .map_err(in_interface)?;
+            (generated.action_rmw, generated.action_idiomatic)
+        }
+    };
+
+    let kind = interface.kind();
+    let module_name = to_snake_case(name);
+    let mut files = Vec::new();
+    if !rmw.is_empty() {
+        files.push((
+            Path::new(FFI_MODULE)
+                .join(kind)
+                .join(format!("{}_rmw.rs", module_name)),
+            rmw,
+        ));
+    }
+    files.push((
+        Path::new(kind).join(format!("{}_idiomatic.rs", module_name)),
+        idiomatic,
+    ));
+    Ok(GeneratedArtifacts { files })
 }
 
 pub struct GeneratedPackage {
@@ -36,6 +329,23 @@ pub fn generate_message_package(
     message_name: &str,
     message: &Message,
     all_dependencies: &HashSet<String>,
+) -> Result<GeneratedPackage, GeneratorError> {
+    generate_message_package_with_options(
+        package_name,
+        message_name,
+        message,
+        all_dependencies,
+        GeneratorOptions::default(),
+    )
+}
+
+/// Same as [`generate_message_package`], with explicit generator options
+pub fn generate_message_package_with_options(
+    package_name: &str,
+    message_name: &str,
+    message: &Message,
+    all_dependencies: &HashSet<String>,
+    options: GeneratorOptions,
 ) -> Result<GeneratedPackage, GeneratorError> {
     // Extract dependencies from this specific message
     let msg_deps = extract_dependencies(message);
@@ -46,14 +356,10 @@ pub fn generate_message_package(
     all_deps.sort();
     all_deps.dedup();
 
-    // Check if we need serde's big-array feature
-    let needs_big_array_feature = needs_big_array(message);
-
     // Generate Cargo.toml
     let cargo_toml_template = CargoTomlTemplate {
         package_name,
         dependencies: &all_deps,
-        needs_big_array: needs_big_array_feature,
     };
     let cargo_toml = cargo_toml_template.render()?;
 
@@ -69,83 +375,535 @@ pub fn generate_message_package(
     };
     let lib_rs = lib_rs_template.render()?;
 
+    let constant_enums = if options.constant_enums {
+        render_constant_enums(package_name, message_name, &message.constants)?
+    } else {
+        String::new()
+    };
+
+    let ir = StructIr::resolve(package_name, message_name, message, &options);
+
+    // Embedded bindings have no RMW layer either
+    if let Some(capacity) = options.embedded {
+        return Ok(GeneratedPackage {
+            cargo_toml,
+            build_rs,
+            lib_rs,
+            message_rmw: String::new(),
+            message_idiomatic: render_embedded_message(
+                package_name,
+                message_name,
+                &ir,
+                capacity,
+                &options,
+            )? + &constant_enums,
+        });
+    }
+
+    // The upstream runtime has no `BoundExceeded` to report bounds with, nor CDR
+    let runtime_extensions = if options.rclrs_compat {
+        String::new()
+    } else {
+        render_bound_setters(&[(message_name.to_string(), &ir)])?
+            + &render_cdr_impls(&[(message_name.to_string(), &ir)])?
+    };
+
+    // Types-only bindings have no RMW layer
+    if options.types_only {
+        return Ok(GeneratedPackage {
+            cargo_toml,
+            build_rs,
+            lib_rs,
+            message_rmw: String::new(),
+            message_idiomatic: render_types_only_message(
+                package_name,
+                message_name,
+                &ir,
+                &options,
+            )? + &render_builtin_conversions(package_name, message_name)?
+                + &runtime_extensions
+                + &constant_enums,
+        });
+    }
+
     // Generate RMW layer message
-    let rmw_fields: Vec<RmwField> = message
-        .fields
+    let message_rmw_template = MessageRmwTemplate {
+        package_name,
+        message_name,
+        docs: ir.docs.clone(),
+        fields: ir.rmw_fields(),
+        constants: ir.constants(true),
+        module_constants: options.module_constants,
+        introspection: options.introspection,
+        introspection_members: introspection_members(&ir),
+    };
+    let message_rmw = message_rmw_template.render()?
+        + &render_layout_checks(package_name, &[(message_name.to_string(), &ir)])?;
+
+    // Generate idiomatic layer message
+    let message_idiomatic_template = MessageIdiomaticTemplate {
+        package_name,
+        message_name,
+        rclrs_compat: options.rclrs_compat,
+        docs: ir.idiomatic_docs(),
+        fields: ir.idiomatic_fields(),
+        constants: ir.constants(false),
+        module_constants: options.module_constants,
+        derives: ir.derives.clone(),
+        attributes: &options.attributes,
+        serde_rename_all: options.naming.serde_rename_all.as_deref(),
+    };
+    let message_idiomatic = message_idiomatic_template.render()?
+        + &render_builtin_conversions(package_name, message_name)?
+        + &runtime_extensions
+        + &constant_enums;
+
+    Ok(GeneratedPackage {
+        cargo_toml,
+        build_rs,
+        lib_rs,
+        message_rmw,
+        message_idiomatic,
+    })
+}
+
+/// Compile-time layout checks of the RMW structs of an interface, given with
+/// their path in the RMW module
+///
+/// Structs without fields are checked against the placeholder
+/// `structure_needs_at_least_one_member` byte of their C struct.
+fn render_layout_checks(
+    package_name: &str,
+    structs: &[(String, &StructIr)],
+) -> Result<String, GeneratorError> {
+    let structs = structs
         .iter()
-        .map(|f| RmwField {
-            name: escape_keyword(&f.name),
-            rust_type: rust_type_for_field(&f.field_type, true, Some(package_name)),
-            default_value: f
-                .default_value
-                .as_ref()
-                .map(constant_value_to_rust)
-                .unwrap_or_default(),
+        .map(|(path, ir)| LayoutCheck {
+            path: path.clone(),
+            fields: if ir.fields.is_empty() {
+                vec![LayoutField {
+                    name: "structure_needs_at_least_one_member".to_string(),
+                    size: "1".to_string(),
+                    align: "1".to_string(),
+                }]
+            } else {
+                ir.fields
+                    .iter()
+                    .map(|f| {
+                        let (size, align) = c_layout(&f.source.field_type, Some(package_name));
+                        LayoutField {
+                            name: f.name.clone(),
+                            size,
+                            align,
+                        }
+                    })
+                    .collect()
+            },
         })
         .collect();
+    Ok(RmwLayoutTemplate { structs }.render()?)
+}
 
-    let rmw_constants: Vec<MessageConstant> = message
-        .constants
+/// `set_<field>` and `check_bounds` methods of the idiomatic structs of an
+/// interface with bounded fields, given with their path in the idiomatic module
+///
+/// Bounded strings and sequences are plain `String` and `Vec` in the idiomatic
+/// layer, so the setters are what keeps a value within the bound the RMW layer
+/// enforces.
+fn render_bound_setters(structs: &[(String, &StructIr)]) -> Result<String, GeneratorError> {
+    let structs = structs
         .iter()
-        .map(|c| MessageConstant {
-            name: c.name.clone(),
-            rust_type: rust_type_for_field(&c.constant_type, true, Some(package_name)),
-            value: constant_value_to_rust(&c.value),
+        .map(|(path, ir)| BoundedStruct {
+            path: path.clone(),
+            fields: ir
+                .fields
+                .iter()
+                .filter_map(|f| {
+                    let source = f.source;
+                    Some(BoundedField {
+                        value_check: bound_check(&source.field_type, &source.name, "value")?,
+                        self_check: bound_check(
+                            &source.field_type,
+                            &source.name,
+                            &format!("self.{}", f.name),
+                        )?,
+                        name: f.name.clone(),
+                        ros_name: source.name.clone(),
+                        ros_type: source.field_type.to_string(),
+                        rust_type: f.idiomatic_type.clone(),
+                    })
+                })
+                .collect::<Vec<_>>(),
         })
+        .filter(|bounded| !bounded.fields.is_empty())
         .collect();
+    Ok(BoundSettersTemplate { structs }.render()?)
+}
 
-    let message_rmw_template = MessageRmwTemplate {
-        package_name,
-        message_name,
-        fields: rmw_fields,
-        constants: rmw_constants,
-    };
-    let message_rmw = message_rmw_template.render()?;
+/// Fields of an RMW message as rosidl_typesupport_introspection_c describes them
+fn introspection_members(ir: &StructIr) -> Vec<IntrospectionMember> {
+    if ir.fields.is_empty() {
+        // The placeholder member of the C struct
+        return vec![IntrospectionMember {
+            name: "structure_needs_at_least_one_member".to_string(),
+            ros_name: "structure_needs_at_least_one_member".to_string(),
+            kind: "Uint8",
+            is_array: false,
+            array_size: 0,
+            is_upper_bound: false,
+            string_upper_bound: 0,
+        }];
+    }
+    ir.fields
+        .iter()
+        .map(|f| {
+            let (element_type, is_array, array_size, is_upper_bound) = match &f.source.field_type {
+                FieldType::Array { element_type, size } => (&**element_type, true, *size, false),
+                FieldType::Sequence { element_type } => (&**element_type, true, 0, false),
+                FieldType::BoundedSequence {
+                    element_type,
+                    max_size,
+                } => (&**element_type, true, *max_size, true),
+                field_type => (field_type, false, 0, false),
+            };
+            let (kind, string_upper_bound) = match element_type {
+                FieldType::Primitive(primitive) => (introspection_kind(*primitive), 0),
+                FieldType::String => ("String", 0),
+                FieldType::BoundedString(bound) => ("String", *bound),
+                FieldType::WString => ("WString", 0),
+                FieldType::BoundedWString(bound) => ("WString", *bound),
+                _ => ("Message", 0),
+            };
+            IntrospectionMember {
+                name: f.name.clone(),
+                ros_name: f.source.name.clone(),
+                kind,
+                is_array,
+                array_size,
+                is_upper_bound,
+                string_upper_bound,
+            }
+        })
+        .collect()
+}
 
-    // Generate idiomatic layer message
-    let idiomatic_fields: Vec<IdiomaticField> = message
-        .fields
+/// `FieldKind` variant of a primitive type; `char` is a `uint8` since ROS 2
+fn introspection_kind(primitive: PrimitiveType) -> &'static str {
+    match primitive {
+        PrimitiveType::Bool => "Bool",
+        PrimitiveType::Byte => "Byte",
+        PrimitiveType::Char | PrimitiveType::UInt8 => "Uint8",
+        PrimitiveType::Int8 => "Int8",
+        PrimitiveType::Int16 => "Int16",
+        PrimitiveType::UInt16 => "Uint16",
+        PrimitiveType::Int32 => "Int32",
+        PrimitiveType::UInt32 => "Uint32",
+        PrimitiveType::Int64 => "Int64",
+        PrimitiveType::UInt64 => "Uint64",
+        PrimitiveType::Float32 => "Float32",
+        PrimitiveType::Float64 => "Float64",
+    }
+}
+
+/// `CdrSerialize`/`CdrDeserialize` impls of idiomatic structs, given by their path
+/// from the idiomatic module
+///
+/// The idiomatic fields encode themselves, so the structs can be decoded from rmw
+/// payloads and bag files without the C type support.
+fn render_cdr_impls(structs: &[(String, &StructIr)]) -> Result<String, GeneratorError> {
+    let structs = structs
         .iter()
-        .map(|f| IdiomaticField {
-            name: escape_keyword(&f.name),
-            rust_type: rust_type_for_field(&f.field_type, false, Some(package_name)),
-            default_value: f
-                .default_value
-                .as_ref()
-                .map(constant_value_to_rust)
-                .unwrap_or_default(),
-            is_sequence: is_sequence_type(&f.field_type),
-            is_primitive: is_primitive_type(&f.field_type),
-            is_primitive_sequence: is_primitive_sequence(&f.field_type),
-            is_array: is_array_type(&f.field_type),
-            is_large_array: is_large_array(&f.field_type),
+        .map(|(path, ir)| CdrStruct {
+            path: path.clone(),
+            fields: ir.fields.iter().map(|f| f.name.clone()).collect(),
         })
         .collect();
+    Ok(CdrTemplate { structs }.render()?)
+}
+
+/// Source of the `serde_array` module every generated crate has, which large
+/// array fields are serialized with
+pub fn generate_serde_array_rs() -> Result<String, GeneratorError> {
+    Ok(SerdeArrayTemplate.render()?)
+}
 
-    let idiomatic_constants: Vec<MessageConstant> = message
-        .constants
+/// Enums of the constant groups of a message; empty if it has none
+fn render_constant_enums(
+    package_name: &str,
+    message_name: &str,
+    constants: &[Constant],
+) -> Result<String, GeneratorError> {
+    let enums = constant_enums(package_name, message_name, constants);
+    if enums.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(MessageConstantEnumsTemplate { enums }.render()?)
+}
+
+/// Groups of integer constants sharing a name prefix, in definition order
+///
+/// Constants are grouped by the first `_`-separated word of their name, and the
+/// enum is named after the longest prefix the whole group shares: `STATUS_ACCEPTED`
+/// and `STATUS_EXECUTING` become `Status::Accepted` and `Status::Executing`. A
+/// group needs two constants or more of the same integer type with distinct
+/// values; groups whose enum or variant names would not be valid Rust identifiers,
+/// or whose enum would shadow the message, are left as plain constants.
+fn constant_enums(
+    package_name: &str,
+    message_name: &str,
+    constants: &[Constant],
+) -> Vec<ConstantEnum> {
+    let mut groups: Vec<(&str, Vec<&Constant>)> = Vec::new();
+    for constant in constants {
+        let is_integer = matches!(
+            constant.constant_type,
+            FieldType::Primitive(primitive) if !matches!(
+                primitive,
+                PrimitiveType::Bool | PrimitiveType::Float32 | PrimitiveType::Float64
+            )
+        );
+        let Some((word, _)) = constant.name.split_once('_') else {
+            continue;
+        };
+        if !is_integer {
+            continue;
+        }
+        match groups.iter_mut().find(|(w, _)| *w == word) {
+            Some((_, members)) => members.push(constant),
+            None => groups.push((word, vec![constant])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter_map(|(_, members)| constant_enum(package_name, message_name, &members))
+        .collect()
+}
+
+fn constant_enum(
+    package_name: &str,
+    message_name: &str,
+    members: &[&Constant],
+) -> Option<ConstantEnum> {
+    let constant_type = &members.first()?.constant_type;
+    if members.len() < 2 || members.iter().any(|c| c.constant_type != *constant_type) {
+        return None;
+    }
+
+    let words: Vec<Vec<&str>> = members
+        .iter()
+        .map(|c| c.name.split('_').collect())
+        .collect();
+    let shortest = words.iter().map(Vec::len).min()?;
+    let shared = (1..shortest)
+        .take_while(|&n| words.iter().all(|w| w[..n] == words[0][..n]))
+        .last()?;
+    let prefix = words[0][..shared].join("_");
+    let name = to_upper_camel_case(&prefix.to_lowercase());
+
+    let variants: Vec<ConstantVariant> = members
         .iter()
-        .map(|c| MessageConstant {
-            name: c.name.clone(),
-            rust_type: rust_type_for_field(&c.constant_type, false, Some(package_name)),
-            value: constant_value_to_rust(&c.value),
+        .zip(&words)
+        .map(|(constant, words)| ConstantVariant {
+            name: to_upper_camel_case(&words[shared..].join("_").to_lowercase()),
+            constant: format!("{}::{}", message_name, constant.name),
+            docs: comment_docs(&constant.comments),
         })
         .collect();
 
-    let message_idiomatic_template = MessageIdiomaticTemplate {
+    let is_identifier =
+        |s: &str| s.starts_with(|c: char| c.is_ascii_alphabetic()) && escape_keyword(s) == s;
+    let mut values: Vec<String> = members
+        .iter()
+        .map(|c| typed_constant_value(&c.value, &c.constant_type))
+        .collect();
+    values.sort();
+    values.dedup();
+    let mut variant_names: Vec<&str> = variants.iter().map(|v| v.name.as_str()).collect();
+    variant_names.sort();
+    variant_names.dedup();
+    if name == message_name
+        || !is_identifier(&name)
+        || !variants.iter().all(|v| is_identifier(&v.name))
+        || values.len() != members.len()
+        || variant_names.len() != members.len()
+    {
+        return None;
+    }
+
+    Some(ConstantEnum {
+        name,
+        prefix,
+        rust_type: constant_rust_type(constant_type, false, Some(package_name)),
+        variants,
+    })
+}
+
+/// `std::time` conversions for `builtin_interfaces/Time` and `Duration`; empty
+/// for other messages
+fn render_builtin_conversions(
+    package_name: &str,
+    message_name: &str,
+) -> Result<String, GeneratorError> {
+    let Some(builtin) = BuiltinType::from_name(package_name, message_name) else {
+        return Ok(String::new());
+    };
+    Ok(MessageBuiltinTemplate {
+        message_name,
+        is_duration: builtin == BuiltinType::Duration,
+    }
+    .render()?)
+}
+
+/// Render the idiomatic message of types-only bindings
+fn render_types_only_message(
+    package_name: &str,
+    message_name: &str,
+    ir: &StructIr,
+    options: &GeneratorOptions,
+) -> Result<String, GeneratorError> {
+    Ok(MessageTypesOnlyTemplate {
         package_name,
         message_name,
-        fields: idiomatic_fields,
-        constants: idiomatic_constants,
-    };
-    let message_idiomatic = message_idiomatic_template.render()?;
+        docs: ir.docs.clone(),
+        fields: ir.types_only_fields(),
+        constants: ir.constants(false),
+        module_constants: options.module_constants,
+        serde_rename_all: options.naming.serde_rename_all.as_deref(),
+    }
+    .render()?)
+}
 
-    Ok(GeneratedPackage {
-        cargo_toml,
-        build_rs,
-        lib_rs,
-        message_rmw,
-        message_idiomatic,
+/// Render the message of embedded bindings
+fn render_embedded_message(
+    package_name: &str,
+    message_name: &str,
+    ir: &StructIr,
+    capacity: EmbeddedCapacity,
+    options: &GeneratorOptions,
+) -> Result<String, GeneratorError> {
+    let fields = ir
+        .fields
+        .iter()
+        .map(|f| {
+            let rust_type = match &f.source.field_type {
+                // An inline sequence of a message nesting this one would never end
+                FieldType::Sequence { element_type }
+                | FieldType::BoundedSequence { element_type, .. }
+                    if f.cyclic =>
+                {
+                    format!(
+                        "alloc::vec::Vec<{}>",
+                        embedded_type_for_field(element_type, Some(package_name), capacity)
+                    )
+                }
+                field_type => embedded_type_for_field(field_type, Some(package_name), capacity),
+            };
+            let source = f.source;
+            Ok(TypesOnlyField {
+                name: f.name.clone(),
+                serde_rename: f.serde_rename.clone(),
+                rust_type,
+                docs: f.docs.clone(),
+                deprecated: f.deprecated.clone(),
+                default_value: embedded_default(
+                    &source.field_type,
+                    source.default_value.as_ref(),
+                    capacity,
+                )
+                .map_err(|err| err.in_field(package_name, message_name, &source.name))?,
+                is_large_array: f.is_large_array,
+                arbitrary_value: String::new(),
+                cyclic: f.cyclic,
+            })
+        })
+        .collect::<Result<_, GeneratorError>>()?;
+
+    Ok(MessageEmbeddedTemplate {
+        package_name,
+        message_name,
+        docs: ir.docs.clone(),
+        fields,
+        constants: ir.constants(false),
+        module_constants: options.module_constants,
+        serde_rename_all: options.naming.serde_rename_all.as_deref(),
+    }
+    .render()?)
+}
+
+/// Field value in `Default::default()` of an embedded message
+///
+/// String and sequence defaults must fit the capacity, so the generated code can
+/// unwrap the conversion.
+fn embedded_default(
+    field_type: &FieldType,
+    default_value: Option<&ConstantValue>,
+    capacity: EmbeddedCapacity,
+) -> Result<String, GeneratorError> {
+    let string_capacity = match field_type {
+        FieldType::String | FieldType::WString => Some(capacity.string),
+        FieldType::BoundedString(size) | FieldType::BoundedWString(size) => Some(*size),
+        _ => None,
+    };
+    Ok(match (string_capacity, default_value) {
+        (Some(max), Some(ConstantValue::String(value) | ConstantValue::WString(value))) => {
+            if value.len() > max {
+                return Err(GeneratorError::InvalidMessage(format!(
+                    "default value is {} bytes, the string capacity is {}",
+                    value.len(),
+                    max
+                )));
+            }
+            format!(
+                "rosidl_runtime_rs::bounded::BoundedString::try_from({}).unwrap()",
+                constant_value_to_rust(&ConstantValue::String(value.clone()))
+            )
+        }
+        (None, Some(value @ (ConstantValue::Integer(_) | ConstantValue::UInteger(_))))
+            if matches!(
+                field_type,
+                FieldType::Primitive(PrimitiveType::Float32 | PrimitiveType::Float64)
+            ) =>
+        {
+            format!("{}.0", value)
+        }
+        (None, Some(value)) if matches!(field_type, FieldType::Primitive(_)) => {
+            constant_value_to_rust(value)
+        }
+        (None, Some(ConstantValue::Array(values))) => {
+            let (element_type, max) = match field_type {
+                FieldType::Array { element_type, .. } => (element_type, None),
+                FieldType::Sequence { element_type } => (element_type, Some(capacity.sequence)),
+                FieldType::BoundedSequence {
+                    element_type,
+                    max_size,
+                } => (element_type, Some(*max_size)),
+                _ => return Ok("Default::default()".to_string()),
+            };
+            let elements = values
+                .iter()
+                .map(|value| embedded_default(element_type, Some(value), capacity))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ");
+            match max {
+                None => format!("[{}]", elements),
+                Some(max) if values.len() > max => {
+                    return Err(GeneratorError::InvalidMessage(format!(
+                        "default value has {} elements, the sequence capacity is {}",
+                        values.len(),
+                        max
+                    )))
+                }
+                Some(_) => format!(
+                    "rosidl_runtime_rs::bounded::BoundedVec::try_from(&[{}][..]).unwrap()",
+                    elements
+                ),
+            }
+        }
+        // Arrays of more than 32 elements have no Default impl
+        (_, _) if matches!(field_type, FieldType::Array { .. }) => {
+            "core::array::from_fn(|_| Default::default())".to_string()
+        }
+        _ => "Default::default()".to_string(),
     })
 }
 
@@ -163,6 +921,23 @@ pub fn generate_service_package(
     service_name: &str,
     service: &Service,
     all_dependencies: &HashSet<String>,
+) -> Result<GeneratedServicePackage, GeneratorError> {
+    generate_service_package_with_options(
+        package_name,
+        service_name,
+        service,
+        all_dependencies,
+        GeneratorOptions::default(),
+    )
+}
+
+/// Same as [`generate_service_package`], with explicit generator options
+pub fn generate_service_package_with_options(
+    package_name: &str,
+    service_name: &str,
+    service: &Service,
+    all_dependencies: &HashSet<String>,
+    options: GeneratorOptions,
 ) -> Result<GeneratedServicePackage, GeneratorError> {
     // Extract dependencies from request and response
     let mut req_deps = extract_dependencies(&service.request);
@@ -172,18 +947,17 @@ pub fn generate_service_package(
     // Combine with externally provided dependencies
     let mut all_deps: Vec<String> = all_dependencies.iter().cloned().collect();
     all_deps.extend(req_deps);
+    if options.service_events {
+        // The event message embeds service_msgs/ServiceEventInfo
+        all_deps.push(SERVICE_MSGS_PACKAGE.to_string());
+    }
     all_deps.sort();
     all_deps.dedup();
 
-    // Check if we need serde's big-array feature
-    let needs_big_array_feature =
-        needs_big_array(&service.request) || needs_big_array(&service.response);
-
     // Generate Cargo.toml
     let cargo_toml_template = CargoTomlTemplate {
         package_name,
         dependencies: &all_deps,
-        needs_big_array: needs_big_array_feature,
     };
     let cargo_toml = cargo_toml_template.render()?;
 
@@ -199,74 +973,63 @@ pub fn generate_service_package(
     };
     let lib_rs = lib_rs_template.render()?;
 
-    // Helper functions to convert Message to field vectors
-    let message_to_rmw_fields = |msg: &Message| {
-        msg.fields
-            .iter()
-            .map(|f| RmwField {
-                name: escape_keyword(&f.name),
-                rust_type: rust_type_for_field(&f.field_type, true, Some(package_name)),
-                default_value: f
-                    .default_value
-                    .as_ref()
-                    .map(constant_value_to_rust)
-                    .unwrap_or_default(),
-            })
-            .collect()
-    };
-
-    let message_to_idiomatic_fields = |msg: &Message| {
-        msg.fields
-            .iter()
-            .map(|f| IdiomaticField {
-                name: escape_keyword(&f.name),
-                rust_type: rust_type_for_field(&f.field_type, false, Some(package_name)),
-                default_value: f
-                    .default_value
-                    .as_ref()
-                    .map(constant_value_to_rust)
-                    .unwrap_or_default(),
-                is_sequence: is_sequence_type(&f.field_type),
-                is_primitive: is_primitive_type(&f.field_type),
-                is_primitive_sequence: is_primitive_sequence(&f.field_type),
-                is_array: is_array_type(&f.field_type),
-                is_large_array: is_large_array(&f.field_type),
-            })
-            .collect()
-    };
-
-    let message_to_constants = |msg: &Message, rmw_layer: bool| {
-        msg.constants
-            .iter()
-            .map(|c| MessageConstant {
-                name: c.name.clone(),
-                rust_type: rust_type_for_field(&c.constant_type, rmw_layer, Some(package_name)),
-                value: constant_value_to_rust(&c.value),
-            })
-            .collect()
-    };
+    let request = StructIr::resolve(
+        package_name,
+        &format!("{}Request", service_name),
+        &service.request,
+        &options,
+    );
+    let response = StructIr::resolve(
+        package_name,
+        &format!("{}Response", service_name),
+        &service.response,
+        &options,
+    );
 
     // Generate RMW layer service
     let service_rmw_template = ServiceRmwTemplate {
         package_name,
         service_name,
-        request_fields: message_to_rmw_fields(&service.request),
-        request_constants: message_to_constants(&service.request, true),
-        response_fields: message_to_rmw_fields(&service.response),
-        response_constants: message_to_constants(&service.response, true),
+        request_docs: request.docs.clone(),
+        request_fields: request.rmw_fields(),
+        request_constants: request.constants(true),
+        response_docs: response.docs.clone(),
+        response_fields: response.rmw_fields(),
+        response_constants: response.constants(true),
+        module_constants: options.module_constants,
+        service_events: options.service_events,
     };
-    let service_rmw = service_rmw_template.render()?;
+    let structs = [
+        (format!("request::{}Request", service_name), &request),
+        (format!("response::{}Response", service_name), &response),
+    ];
+    let service_rmw =
+        service_rmw_template.render()? + &render_layout_checks(package_name, &structs)?;
 
     // Generate idiomatic layer service
     let service_idiomatic_template = ServiceIdiomaticTemplate {
         package_name,
         service_name,
-        request_fields: message_to_idiomatic_fields(&service.request),
-        request_constants: message_to_constants(&service.request, false),
-        response_fields: message_to_idiomatic_fields(&service.response),
-        response_constants: message_to_constants(&service.response, false),
+        rclrs_compat: options.rclrs_compat,
+        request_docs: request.idiomatic_docs(),
+        request_fields: request.idiomatic_fields(),
+        request_constants: request.constants(false),
+        response_docs: response.idiomatic_docs(),
+        response_fields: response.idiomatic_fields(),
+        response_constants: response.constants(false),
+        module_constants: options.module_constants,
+        service_events: options.service_events,
+        request_derives: request.derives.clone(),
+        response_derives: response.derives.clone(),
+        attributes: &options.attributes,
+        serde_rename_all: options.naming.serde_rename_all.as_deref(),
     };
-    let service_idiomatic = service_idiomatic_template.render()?;
+    let service_idiomatic = service_idiomatic_template.render()?
+        + &if options.rclrs_compat {
+            String::new()
+        } else {
+            render_bound_setters(&structs)? + &render_cdr_impls(&structs)?
+        };
 
     Ok(GeneratedServicePackage {
         cargo_toml,
@@ -277,6 +1040,109 @@ pub fn generate_service_package(
     })
 }
 
+/// Messages of the implicit services and feedback topic of an action, with the
+/// fields rosidl gives them
+fn implicit_action_messages(action_name: &str) -> Vec<ImplicitMessage> {
+    let field = |name: &str, rust_type: String, doc: &str| RmwField {
+        name: name.to_string(),
+        rust_type,
+        docs: vec![doc.to_string()],
+        default_value: String::new(),
+    };
+    let external = |package: &str, name: &str| {
+        let field_type = FieldType::NamespacedType {
+            package: Some(package.to_string()),
+            name: name.to_string(),
+        };
+        rust_type_for_field(&field_type, true, None)
+    };
+    let goal_id = || {
+        field(
+            "goal_id",
+            external(UNIQUE_IDENTIFIER_MSGS_PACKAGE, "UUID"),
+            "Unique ID of the goal",
+        )
+    };
+
+    vec![
+        ImplicitMessage {
+            module: "feedback_message",
+            type_suffix: "FeedbackMessage",
+            c_suffix: "FeedbackMessage",
+            docs: vec!["Feedback published for a goal".to_string()],
+            fields: vec![
+                goal_id(),
+                field(
+                    "feedback",
+                    format!("super::feedback::{}Feedback", action_name),
+                    "Feedback of the goal",
+                ),
+            ],
+        },
+        ImplicitMessage {
+            module: "send_goal_request",
+            type_suffix: "SendGoalRequest",
+            c_suffix: "SendGoal_Request",
+            docs: vec!["Request of the implicit service sending a goal".to_string()],
+            fields: vec![
+                goal_id(),
+                field(
+                    "goal",
+                    format!("super::goal::{}Goal", action_name),
+                    "Goal to pursue",
+                ),
+            ],
+        },
+        ImplicitMessage {
+            module: "send_goal_response",
+            type_suffix: "SendGoalResponse",
+            c_suffix: "SendGoal_Response",
+            docs: vec!["Response of the implicit service sending a goal".to_string()],
+            fields: vec![
+                field(
+                    "accepted",
+                    "bool".to_string(),
+                    "Whether the server accepted the goal",
+                ),
+                field(
+                    "stamp",
+                    external(BUILTIN_INTERFACES_PACKAGE, "Time"),
+                    "Time the goal was accepted at",
+                ),
+            ],
+        },
+        ImplicitMessage {
+            module: "get_result_request",
+            type_suffix: "GetResultRequest",
+            c_suffix: "GetResult_Request",
+            docs: vec![
+                "Request of the implicit service requesting the result of a goal".to_string(),
+            ],
+            fields: vec![goal_id()],
+        },
+        ImplicitMessage {
+            module: "get_result_response",
+            type_suffix: "GetResultResponse",
+            c_suffix: "GetResult_Response",
+            docs: vec![
+                "Response of the implicit service requesting the result of a goal".to_string(),
+            ],
+            fields: vec![
+                field(
+                    "status",
+                    "i8".to_string(),
+                    "Final status of the goal, an `action_msgs/GoalStatus` constant",
+                ),
+                field(
+                    "result",
+                    format!("super::result::{}Result", action_name),
+                    "Result of the goal",
+                ),
+            ],
+        },
+    ]
+}
+
 pub struct GeneratedActionPackage {
     pub cargo_toml: String,
     pub build_rs: String,
@@ -291,6 +1157,23 @@ pub fn generate_action_package(
     action_name: &str,
     action: &Action,
     all_dependencies: &HashSet<String>,
+) -> Result<GeneratedActionPackage, GeneratorError> {
+    generate_action_package_with_options(
+        package_name,
+        action_name,
+        action,
+        all_dependencies,
+        GeneratorOptions::default(),
+    )
+}
+
+/// Same as [`generate_action_package`], with explicit generator options
+pub fn generate_action_package_with_options(
+    package_name: &str,
+    action_name: &str,
+    action: &Action,
+    all_dependencies: &HashSet<String>,
+    options: GeneratorOptions,
 ) -> Result<GeneratedActionPackage, GeneratorError> {
     // Extract dependencies from goal, result, and feedback
     let mut goal_deps = extract_dependencies(&action.spec.goal);
@@ -302,19 +1185,16 @@ pub fn generate_action_package(
     // Combine with externally provided dependencies
     let mut all_deps: Vec<String> = all_dependencies.iter().cloned().collect();
     all_deps.extend(goal_deps);
+    // The implicit messages embed unique_identifier_msgs/UUID and builtin_interfaces/Time
+    all_deps.push(UNIQUE_IDENTIFIER_MSGS_PACKAGE.to_string());
+    all_deps.push(BUILTIN_INTERFACES_PACKAGE.to_string());
     all_deps.sort();
     all_deps.dedup();
 
-    // Check if we need serde's big-array feature
-    let needs_big_array_feature = needs_big_array(&action.spec.goal)
-        || needs_big_array(&action.spec.result)
-        || needs_big_array(&action.spec.feedback);
-
     // Generate Cargo.toml
     let cargo_toml_template = CargoTomlTemplate {
         package_name,
         dependencies: &all_deps,
-        needs_big_array: needs_big_array_feature,
     };
     let cargo_toml = cargo_toml_template.render()?;
 
@@ -330,78 +1210,69 @@ pub fn generate_action_package(
     };
     let lib_rs = lib_rs_template.render()?;
 
-    // Helper functions to convert Message to field vectors
-    let message_to_rmw_fields = |msg: &Message| {
-        msg.fields
-            .iter()
-            .map(|f| RmwField {
-                name: escape_keyword(&f.name),
-                rust_type: rust_type_for_field(&f.field_type, true, Some(package_name)),
-                default_value: f
-                    .default_value
-                    .as_ref()
-                    .map(constant_value_to_rust)
-                    .unwrap_or_default(),
-            })
-            .collect()
-    };
-
-    let message_to_idiomatic_fields = |msg: &Message| {
-        msg.fields
-            .iter()
-            .map(|f| IdiomaticField {
-                name: escape_keyword(&f.name),
-                rust_type: rust_type_for_field(&f.field_type, false, Some(package_name)),
-                default_value: f
-                    .default_value
-                    .as_ref()
-                    .map(constant_value_to_rust)
-                    .unwrap_or_default(),
-                is_sequence: is_sequence_type(&f.field_type),
-                is_primitive: is_primitive_type(&f.field_type),
-                is_primitive_sequence: is_primitive_sequence(&f.field_type),
-                is_array: is_array_type(&f.field_type),
-                is_large_array: is_large_array(&f.field_type),
-            })
-            .collect()
-    };
-
-    let message_to_constants = |msg: &Message, rmw_layer: bool| {
-        msg.constants
-            .iter()
-            .map(|c| MessageConstant {
-                name: c.name.clone(),
-                rust_type: rust_type_for_field(&c.constant_type, rmw_layer, Some(package_name)),
-                value: constant_value_to_rust(&c.value),
-            })
-            .collect()
+    let resolve = |suffix: &str, message| {
+        StructIr::resolve(
+            package_name,
+            &format!("{}{}", action_name, suffix),
+            message,
+            &options,
+        )
     };
+    let goal = resolve("Goal", &action.spec.goal);
+    let result = resolve("Result", &action.spec.result);
+    let feedback = resolve("Feedback", &action.spec.feedback);
 
     // Generate RMW layer action
     let action_rmw_template = ActionRmwTemplate {
         package_name,
         action_name,
-        goal_fields: message_to_rmw_fields(&action.spec.goal),
-        goal_constants: message_to_constants(&action.spec.goal, true),
-        result_fields: message_to_rmw_fields(&action.spec.result),
-        result_constants: message_to_constants(&action.spec.result, true),
-        feedback_fields: message_to_rmw_fields(&action.spec.feedback),
-        feedback_constants: message_to_constants(&action.spec.feedback, true),
+        rclrs_compat: options.rclrs_compat,
+        goal_docs: goal.docs.clone(),
+        goal_fields: goal.rmw_fields(),
+        goal_constants: goal.constants(true),
+        result_docs: result.docs.clone(),
+        result_fields: result.rmw_fields(),
+        result_constants: result.constants(true),
+        feedback_docs: feedback.docs.clone(),
+        feedback_fields: feedback.rmw_fields(),
+        feedback_constants: feedback.constants(true),
+        module_constants: options.module_constants,
+        implicit_messages: implicit_action_messages(action_name),
     };
-    let action_rmw = action_rmw_template.render()?;
+    let structs = [
+        (format!("goal::{}Goal", action_name), &goal),
+        (format!("result::{}Result", action_name), &result),
+        (format!("feedback::{}Feedback", action_name), &feedback),
+    ];
+    let action_rmw = action_rmw_template.render()? + &render_layout_checks(package_name, &structs)?;
 
     // Generate idiomatic layer action
     let action_idiomatic_template = ActionIdiomaticTemplate {
         package_name,
         action_name,
-        goal_fields: message_to_idiomatic_fields(&action.spec.goal),
-        goal_constants: message_to_constants(&action.spec.goal, false),
-        result_fields: message_to_idiomatic_fields(&action.spec.result),
-        result_constants: message_to_constants(&action.spec.result, false),
-        feedback_fields: message_to_idiomatic_fields(&action.spec.feedback),
-        feedback_constants: message_to_constants(&action.spec.feedback, false),
+        rclrs_compat: options.rclrs_compat,
+        goal_docs: goal.idiomatic_docs(),
+        goal_fields: goal.idiomatic_fields(),
+        goal_constants: goal.constants(false),
+        result_docs: result.idiomatic_docs(),
+        result_fields: result.idiomatic_fields(),
+        result_constants: result.constants(false),
+        feedback_docs: feedback.idiomatic_docs(),
+        feedback_fields: feedback.idiomatic_fields(),
+        feedback_constants: feedback.constants(false),
+        module_constants: options.module_constants,
+        goal_derives: goal.derives.clone(),
+        result_derives: result.derives.clone(),
+        feedback_derives: feedback.derives.clone(),
+        attributes: &options.attributes,
+        serde_rename_all: options.naming.serde_rename_all.as_deref(),
     };
-    let action_idiomatic = action_idiomatic_template.render()?;
+    let action_idiomatic = action_idiomatic_template.render()?
+        + &if options.rclrs_compat {
+            String::new()
+        } else {
+            render_bound_setters(&structs)? + &render_cdr_impls(&structs)?
+        };
 
     Ok(GeneratedActionPackage {
         cargo_toml,
@@ -445,6 +1316,101 @@ mod tests {
         assert!(pkg.cargo_toml.contains("geometry_msgs"));
     }
 
+    #[test]
+    fn test_generate_interface_artifacts() {
+        let deps = HashSet::new();
+        let paths = |artifacts: &GeneratedArtifacts| -> Vec<String> {
+            artifacts
+                .files
+                .iter()
+                .map(|(path, _)| path.to_string_lossy().replace('\\', "/"))
+                .collect()
+        };
+
+        let srv = parse_service("int64 a\n---\nint64 sum\n").unwrap();
+        let artifacts = generate_interface(
+            "example_interfaces",
+            "AddTwoInts",
+            Interface::Service(&srv),
+            &deps,
+            GeneratorOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            paths(&artifacts),
+            [
+                "ffi/srv/add_two_ints_rmw.rs",
+                "srv/add_two_ints_idiomatic.rs"
+            ]
+        );
+        assert!(artifacts.files[1]
+            .1
+            .contains("pub struct AddTwoIntsRequest"));
+
+        // Types-only messages have no RMW layer
+        let msg = parse_message("int32 x\n").unwrap();
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let artifacts = generate_interface(
+            "test_msgs",
+            "Point",
+            Interface::Message(&msg),
+            &deps,
+            options,
+        )
+        .unwrap();
+        assert_eq!(paths(&artifacts), ["msg/point_idiomatic.rs"]);
+    }
+
+    #[test]
+    fn test_generate_interface_known_packages() {
+        let known: HashSet<String> = ["std_msgs", "geometry_msgs"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let srv =
+            parse_service("geometry_msgs/Point target\n---\ngeometry_msg/Pose pose\n").unwrap();
+        let err = generate_interface(
+            "nav_msgs",
+            "GetPose",
+            Interface::Service(&srv),
+            &known,
+            GeneratorOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed generating nav_msgs/GetPose field `pose` \
+             (unknown package geometry_msg, did you mean geometry_msgs?)"
+        );
+
+        // Own types and known packages are fine, nothing close is suggested
+        let msg = parse_message("Path path\nstd_msgs/Header header\n").unwrap();
+        assert!(generate_interface(
+            "nav_msgs",
+            "Track",
+            Interface::Message(&msg),
+            &known,
+            GeneratorOptions::default(),
+        )
+        .is_ok());
+        let msg = parse_message("sensor_msgs/Imu imu\n").unwrap();
+        let err = generate_interface(
+            "nav_msgs",
+            "Track",
+            Interface::Message(&msg),
+            &known,
+            GeneratorOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.root_cause(),
+            GeneratorError::UnknownPackage { package, suggestion: None } if package == "sensor_msgs"
+        ));
+    }
+
     #[test]
     fn test_message_with_large_array() {
         let mut msg = Message::new();
@@ -455,6 +1421,9 @@ mod tests {
             },
             name: "data".to_string(),
             default_value: None,
+            comments: Vec::new(),
+            annotations: Vec::new(),
+            deprecated: None,
         });
 
         let deps = HashSet::new();
@@ -462,7 +1431,11 @@ mod tests {
         assert!(result.is_ok());
 
         let pkg = result.unwrap();
-        assert!(pkg.cargo_toml.contains("big-array"));
+        assert!(!pkg.cargo_toml.contains("big-array"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("serde(with = \"crate::serde_array\")"));
+        assert!(pkg.lib_rs.contains("mod serde_array {"));
     }
 
     #[test]
@@ -478,6 +1451,1151 @@ mod tests {
         assert!(pkg.message_rmw.contains("match_"));
     }
 
+    #[test]
+    fn test_types_only_message_generation() {
+        let msg = parse_message(
+            "float64 x 1\nstring frame_id \"map\"\nint32[3] ids\ngeometry_msgs/Point[] points\n",
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Path",
+            &msg,
+            &HashSet::new(),
+            options.clone(),
+        )
+        .unwrap();
+        assert!(pkg.message_rmw.is_empty());
+        let rust = &pkg.message_idiomatic;
+        assert!(!rust.contains("crate::ffi"));
+        assert!(rust.contains("x: 1.0,"));
+        assert!(rust.contains("frame_id: std::string::String::from(\"map\"),"));
+        assert!(rust.contains("ids: std::array::from_fn(|_| Default::default()),"));
+        assert!(rust.contains("points: Default::default(),"));
+        assert!(rust.contains("impl crate::rosidl_runtime_rs::cdr::CdrSerialize for Path {"));
+        assert!(rust.contains(
+            "crate::rosidl_runtime_rs::cdr::CdrSerialize::cdr_serialize(&self.frame_id, writer);"
+        ));
+        assert!(rust.contains(
+            "ids: crate::rosidl_runtime_rs::cdr::CdrDeserialize::cdr_deserialize(reader)?,"
+        ));
+
+        let empty = generate_message_package_with_options(
+            "std_msgs",
+            "Empty",
+            &Message::new(),
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(empty
+            .message_idiomatic
+            .contains("crate::rosidl_runtime_rs::cdr::CdrSerialize::cdr_serialize(&0u8, writer);"));
+    }
+
+    #[test]
+    fn test_arbitrary_impl_generation() {
+        let msg = parse_message("string<=8 name\nfloat32[2] xy\ngeometry_msgs/Point[<=3] points\n")
+            .unwrap();
+
+        let pkg = generate_message_package("test_msgs", "Path", &msg, &HashSet::new()).unwrap();
+        let rust = &pkg.message_idiomatic;
+        assert!(rust.contains("#[cfg(feature = \"arbitrary\")]"));
+        assert!(rust.contains("name: fuzz::string(u, Some(8))?,"));
+        assert!(rust.contains("xy: fuzz::array(u, |u| fuzz::Arbitrary::arbitrary(u))?,"));
+        assert!(rust.contains("points: fuzz::vec(u, Some(3), |u| fuzz::Arbitrary::arbitrary(u))?,"));
+
+        // The upstream runtime has no fuzz helpers
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Path",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(!pkg.message_idiomatic.contains("Arbitrary"));
+    }
+
+    #[test]
+    fn test_embedded_message_generation() {
+        let msg = parse_message(
+            "string NAME=\"base\"\nfloat32 x 2\nstring frame_id \"map\"\nstring<=4 code\nint32[] ids\nuint8[<=8] data\nfloat64[3] xyz\ngeometry_msgs/Point[<=2] points\n",
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity::default()),
+            ..Default::default()
+        };
+        assert!(options.messages_only());
+
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Path",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg.message_rmw.is_empty());
+        let rust = &pkg.message_idiomatic;
+        assert!(!rust.contains("std::"));
+        assert!(rust.contains("pub const NAME: &str = \"base\";"));
+        assert!(rust.contains("pub frame_id: rosidl_runtime_rs::bounded::BoundedString<20>,"));
+        assert!(rust.contains("pub code: rosidl_runtime_rs::bounded::BoundedString<4>,"));
+        assert!(rust.contains("pub ids: rosidl_runtime_rs::bounded::BoundedVec<i32, 5>,"));
+        assert!(rust.contains("pub data: rosidl_runtime_rs::bounded::BoundedVec<u8, 8>,"));
+        assert!(rust.contains(
+            "pub points: rosidl_runtime_rs::bounded::BoundedVec<geometry_msgs::msg::point::Point, 2>,"
+        ));
+        assert!(rust.contains("x: 2.0,"));
+        assert!(rust.contains(
+            "frame_id: rosidl_runtime_rs::bounded::BoundedString::try_from(\"map\").unwrap(),"
+        ));
+        assert!(rust.contains("xyz: core::array::from_fn(|_| Default::default()),"));
+
+        // Defaults must fit the capacity
+        let long = parse_message("string frame_id \"odometry\"\n").unwrap();
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity {
+                string: 4,
+                sequence: 5,
+            }),
+            ..Default::default()
+        };
+        let err = generate_interface(
+            "test_msgs",
+            "Frame",
+            Interface::Message(&long),
+            &HashSet::new(),
+            options,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed generating test_msgs/Frame field `frame_id` (Invalid message structure: \
+             default value is 8 bytes, the string capacity is 4)"
+        );
+        assert!(matches!(
+            err.root_cause(),
+            GeneratorError::InvalidMessage(_)
+        ));
+
+        let err = GeneratorError::UnknownType("nav_msgs/Odometry".to_string())
+            .in_interface("test_msgs", "Track");
+        assert_eq!(
+            err.to_string(),
+            "failed generating test_msgs/Track (unknown type nav_msgs/Odometry)"
+        );
+        // The innermost interface is kept
+        assert!(matches!(
+            err.in_interface("test_msgs", "Other"),
+            GeneratorError::Interface { interface, .. } if interface == "Track"
+        ));
+    }
+
+    #[test]
+    fn test_annotation_docs_generation() {
+        let msg = parse_message("# @unit(m/s)\nfloat64 speed\n").unwrap();
+
+        let pkg = generate_message_package("test_msgs", "Speed", &msg, &HashSet::new()).unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("/// `@unit`: m/s\n    pub speed: f64,"));
+
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Speed",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("/// `@unit`: m/s\n    pub speed: f64,"));
+    }
+
+    #[test]
+    fn test_comment_docs_placement() {
+        let msg = parse_message(
+            "# A point in space\nfloat64 x # along the x axis\n\n# Height\n# @unit(m)\nfloat64 z\n",
+        )
+        .unwrap();
+
+        let pkg = generate_message_package("test_msgs", "Point", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("/// A point in space\n#[derive("));
+        assert!(idiomatic.contains("/// along the x axis\n    pub x: f64,"));
+        assert!(idiomatic.contains("/// Height\n    /// `@unit`: m\n    pub z: f64,"));
+
+        // The RMW layer carries the same docs, constants included in both layers
+        let msg =
+            parse_message("# A point\n\n# Upper limit\nfloat64 MAX=1.0\nfloat64 x # along x\n")
+                .unwrap();
+        let pkg = generate_message_package("test_msgs", "Point", &msg, &HashSet::new()).unwrap();
+        let rmw = &pkg.message_rmw;
+        assert!(rmw.contains("/// A point\n#[repr(C)]"));
+        assert!(rmw.contains("/// along x\n    pub x: f64,"));
+        for rust in [rmw, &pkg.message_idiomatic] {
+            assert!(rust.contains("/// Upper limit\n    pub const MAX: f64 = 1.0f64;"));
+        }
+
+        let srv = parse_service("# Sum request\n\nint64 a # first\n---\nint64 sum\n").unwrap();
+        let pkg =
+            generate_service_package("test_msgs", "AddTwoInts", &srv, &HashSet::new()).unwrap();
+        assert!(pkg.service_rmw.contains("/// Sum request\n    #[repr(C)]"));
+        assert!(pkg.service_rmw.contains("/// first\n        pub a: i64,"));
+    }
+
+    #[test]
+    fn test_rmw_type_support_bindings() {
+        let msg = parse_message("int32 x\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Point", &msg, &HashSet::new()).unwrap();
+        let rmw = &pkg.message_rmw;
+        assert!(rmw.contains("#[link(name = \"test_msgs__rosidl_typesupport_c\")]"));
+        assert!(rmw.contains(
+            "fn rosidl_typesupport_c__get_message_type_support_handle__test_msgs__msg__Point() -> *const std::ffi::c_void;"
+        ));
+        assert!(rmw.contains("impl rosidl_runtime_rs::RmwMessage for Point"));
+        assert!(rmw.contains("const TYPE_NAME: &'static str = \"test_msgs/msg/Point\";"));
+
+        let srv = parse_service("int64 a\n---\nint64 sum\n").unwrap();
+        let pkg =
+            generate_service_package("test_msgs", "AddTwoInts", &srv, &HashSet::new()).unwrap();
+        for section in ["Request", "Response"] {
+            assert!(pkg.service_rmw.contains(&format!(
+                "const TYPE_NAME: &'static str = \"test_msgs/srv/AddTwoInts_{}\";",
+                section
+            )));
+        }
+
+        let action = parse_action("int32 order\n---\n---\n").unwrap();
+        let pkg =
+            generate_action_package("test_msgs", "Fibonacci", &action, &HashSet::new()).unwrap();
+        for section in ["Goal", "Result", "Feedback"] {
+            assert!(pkg.action_rmw.contains(&format!(
+                "unsafe {{ rosidl_typesupport_c__get_message_type_support_handle__test_msgs__action__Fibonacci_{}() }}",
+                section
+            )));
+        }
+    }
+
+    #[test]
+    fn test_service_type_support_binding() {
+        let srv = parse_service("int64 a\n---\nint64 sum\n").unwrap();
+        let pkg =
+            generate_service_package("test_msgs", "AddTwoInts", &srv, &HashSet::new()).unwrap();
+        for (rust, runtime) in [
+            (&pkg.service_rmw, "rosidl_runtime_rs"),
+            (&pkg.service_idiomatic, "crate::rosidl_runtime_rs"),
+        ] {
+            assert!(rust.contains("pub struct AddTwoInts;"));
+            assert!(rust.contains(&format!(
+                "impl {}::Service for AddTwoInts {{\n    type Request = AddTwoIntsRequest;\n    type Response = AddTwoIntsResponse;",
+                runtime
+            )));
+            assert!(rust.contains(
+                "fn rosidl_typesupport_c__get_service_type_support_handle__test_msgs__srv__AddTwoInts() -> *const std::ffi::c_void;"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_implicit_action_types() {
+        let action =
+            parse_action("int32 order\n---\nint32[] sequence\n---\nint32[] partial\n").unwrap();
+        let pkg =
+            generate_action_package("test_msgs", "Fibonacci", &action, &HashSet::new()).unwrap();
+        assert!(pkg.cargo_toml.contains("unique_identifier_msgs"));
+        assert!(pkg.cargo_toml.contains("builtin_interfaces"));
+
+        let rmw = &pkg.action_rmw;
+        assert!(rmw.contains("pub struct FibonacciSendGoalRequest {"));
+        assert!(rmw.contains("pub goal_id: unique_identifier_msgs::ffi::msg::uuid::UUID,"));
+        assert!(rmw.contains("pub goal: super::goal::FibonacciGoal,"));
+        assert!(rmw.contains("pub stamp: builtin_interfaces::ffi::msg::time::Time,"));
+        assert!(rmw.contains("pub result: super::result::FibonacciResult,"));
+        assert!(rmw.contains("pub feedback: super::feedback::FibonacciFeedback,"));
+        assert!(rmw.contains(
+            "const TYPE_NAME: &'static str = \"test_msgs/action/Fibonacci_GetResult_Response\";"
+        ));
+        assert!(rmw.contains("fn test_msgs__action__Fibonacci_FeedbackMessage__init("));
+        assert!(rmw.contains("impl rosidl_runtime_rs::Service for FibonacciSendGoal {"));
+        assert!(rmw.contains("type GetResultService = FibonacciGetResult;"));
+        assert!(rmw.contains(
+            "unsafe { rosidl_typesupport_c__get_action_type_support_handle__test_msgs__action__Fibonacci() }"
+        ));
+
+        let idiomatic = &pkg.action_idiomatic;
+        assert!(idiomatic.contains("impl crate::rosidl_runtime_rs::Action for Fibonacci {"));
+        assert!(idiomatic.contains("type Goal = FibonacciGoal;"));
+        assert!(idiomatic
+            .contains("type SendGoalService = crate::ffi::action::fibonacci::FibonacciSendGoal;"));
+
+        // rclrs brings its own action machinery
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        let pkg = generate_action_package_with_options(
+            "test_msgs",
+            "Fibonacci",
+            &action,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .action_rmw
+            .contains("pub struct FibonacciGetResultRequest {"));
+        assert!(!pkg.action_rmw.contains("rosidl_runtime_rs::Action for"));
+    }
+
+    #[test]
+    fn test_display_generation() {
+        let msg = parse_message("float64 x\nuint8 type\ngeometry_msgs/Point[] points\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Path", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(
+            idiomatic.contains("impl crate::rosidl_runtime_rs::display::DisplayValue for Path {")
+        );
+        assert!(idiomatic.contains("(\"x\", &self.x),"));
+        assert!(idiomatic.contains("(\"type\", &self.type_),"));
+        assert!(idiomatic.contains("(\"points\", &self.points),"));
+        assert!(idiomatic.contains("crate::rosidl_runtime_rs::display::fmt_message(self, f)"));
+
+        // The upstream runtime has no display module
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Path",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(!pkg.message_idiomatic.contains("std::fmt::Display for Path"));
+    }
+
+    #[test]
+    fn test_extra_derives_and_attributes() {
+        let options = GeneratorOptions {
+            derives: vec!["Eq".to_string(), "Hash".to_string()],
+            attributes: vec!["#[non_exhaustive]".to_string()],
+            ..Default::default()
+        };
+
+        let msg = parse_message("int32 x\n").unwrap();
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Id",
+            &msg,
+            &HashSet::new(),
+            options.clone(),
+        )
+        .unwrap();
+        assert!(pkg.message_idiomatic.contains(
+            "#[derive(Debug, Clone, PartialEq, Eq, Hash)]\n\
+             #[cfg_attr(feature = \"serde\", derive(Serialize, Deserialize))]\n\
+             #[cfg_attr(feature = \"rkyv\", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]\n\
+             #[non_exhaustive]\n\
+             pub struct Id {"
+        ));
+        // The FFI layer keeps its derives
+        assert!(pkg
+            .message_rmw
+            .contains("#[derive(Debug, Clone, PartialEq)]\npub struct Id {"));
+
+        let srv = parse_service("int64 a\n---\nfloat64 sum\n").unwrap();
+        let pkg = generate_service_package_with_options(
+            "test_msgs",
+            "Add",
+            &srv,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        let idiomatic = &pkg.service_idiomatic;
+        assert!(idiomatic.contains("#[derive(Debug, Clone, PartialEq, Eq, Hash)]"));
+        assert!(idiomatic.contains("#[derive(Debug, Clone, PartialEq)]"));
+        assert_eq!(idiomatic.matches("#[non_exhaustive]").count(), 2);
+    }
+
+    #[test]
+    fn test_rmw_layout_checks() {
+        let msg = parse_message(
+            "bool flag\nfloat64 value\nstring name\nint16[3] ids\ngeometry_msgs/Point[2] points\nint32 type\n",
+        )
+        .unwrap();
+        let pkg = generate_message_package("test_msgs", "Sample", &msg, &HashSet::new()).unwrap();
+        let rmw = &pkg.message_rmw;
+        assert!(rmw.contains("const _: () = {"));
+        assert!(rmw.contains("let offset = end.next_multiple_of(align_of::<f64>());"));
+        assert!(rmw.contains("end = offset + 3 * size_of::<usize>();"));
+        assert!(rmw.contains("end = offset + 6;"));
+        assert!(
+            rmw.contains("end = offset + 2 * size_of::<geometry_msgs::ffi::msg::point::Point>();")
+        );
+        assert!(rmw.contains("offset_of!(Sample, type_) == offset"));
+        assert!(rmw.contains("size_of::<Sample>() == end.next_multiple_of(align)"));
+
+        // Empty sections are checked against the placeholder member of C
+        let srv = parse_service("---\nbool success\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Trigger", &srv, &HashSet::new()).unwrap();
+        let rmw = &pkg.service_rmw;
+        assert!(rmw.contains("offset_of!(response::TriggerResponse, success)"));
+        assert!(rmw.contains(
+            "offset_of!(request::TriggerRequest, structure_needs_at_least_one_member) == offset"
+        ));
+    }
+
+    #[test]
+    fn test_empty_message_generation() {
+        let msg = parse_message("# Nothing but a signal\n").unwrap();
+        let pkg = generate_message_package("std_msgs", "Empty", &msg, &HashSet::new()).unwrap();
+        assert!(pkg
+            .message_rmw
+            .contains("pub structure_needs_at_least_one_member: u8,"));
+        assert!(pkg
+            .message_rmw
+            .contains("offset_of!(Empty, structure_needs_at_least_one_member) == offset"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("structure_needs_at_least_one_member: 0,"));
+        // Conversions without fields to convert leave their argument unused
+        assert!(pkg
+            .message_idiomatic
+            .contains("fn from(_idiomatic: &Empty)"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("fn from(_rmw: &crate::ffi::msg::empty::Empty)"));
+
+        let srv = parse_service("---\n").unwrap();
+        let pkg = generate_service_package("std_srvs", "Empty", &srv, &HashSet::new()).unwrap();
+        assert_eq!(
+            pkg.service_rmw
+                .matches("pub structure_needs_at_least_one_member: u8,")
+                .count(),
+            2
+        );
+        assert_eq!(
+            pkg.service_idiomatic
+                .matches("structure_needs_at_least_one_member: 0,")
+                .count(),
+            2
+        );
+        assert_eq!(pkg.service_idiomatic.matches("fn from(_rmw: ").count(), 2);
+        assert_eq!(
+            pkg.service_idiomatic
+                .matches("fn from(_idiomatic: ")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_constant_enums() {
+        let msg = parse_message(
+            "int8 STATUS_UNKNOWN=0\n\
+             # The goal was accepted\n\
+             int8 STATUS_ACCEPTED=1\n\
+             int8 STATUS_CANCEL_REQUESTED=2\n\
+             uint8 MODE_A=0\n\
+             uint8 MODE_B=0\n\
+             float64 GAIN_MIN=0.5\n\
+             float64 GAIN_MAX=2.0\n\
+             uint8 LIMIT=3\n\
+             int8 status\n",
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            constant_enums: true,
+            ..Default::default()
+        };
+
+        let pkg = generate_message_package_with_options(
+            "action_msgs",
+            "GoalStatus",
+            &msg,
+            &HashSet::new(),
+            options.clone(),
+        )
+        .unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("pub enum Status {"));
+        assert!(idiomatic.contains("/// The goal was accepted\n    Accepted,"));
+        assert!(idiomatic.contains("CancelRequested,"));
+        assert!(idiomatic.contains("impl From<Status> for i8 {"));
+        assert!(
+            idiomatic.contains("Status::CancelRequested => GoalStatus::STATUS_CANCEL_REQUESTED,")
+        );
+        assert!(idiomatic.contains("impl core::convert::TryFrom<i8> for Status {"));
+        assert!(idiomatic.contains("GoalStatus::STATUS_UNKNOWN => Ok(Self::Unknown),"));
+        // The field keeps its raw type
+        assert!(idiomatic.contains("pub status: i8,"));
+        // Duplicate values and floats stay plain constants
+        assert!(!idiomatic.contains("enum Mode"));
+        assert!(!idiomatic.contains("enum Gain"));
+        assert!(!pkg.message_rmw.contains("enum Status"));
+
+        let types_only = generate_message_package_with_options(
+            "action_msgs",
+            "GoalStatus",
+            &msg,
+            &HashSet::new(),
+            GeneratorOptions {
+                types_only: true,
+                ..options
+            },
+        )
+        .unwrap();
+        assert!(types_only.message_idiomatic.contains("pub enum Status {"));
+
+        let pkg =
+            generate_message_package("action_msgs", "GoalStatus", &msg, &HashSet::new()).unwrap();
+        assert!(!pkg.message_idiomatic.contains("enum Status"));
+    }
+
+    #[test]
+    fn test_typed_constant_generation() {
+        let msg = parse_message(
+            "uint8 LEVEL=100\nfloat32 GAIN=1\nfloat64 PI=3.14\nstring NAME=\"base\"\nwstring<=4 W=\"w\"\n",
+        )
+        .unwrap();
+
+        let pkg = generate_message_package("test_msgs", "Config", &msg, &HashSet::new()).unwrap();
+        for rust in [&pkg.message_rmw, &pkg.message_idiomatic] {
+            assert!(rust.contains("pub const LEVEL: u8 = 100u8;"));
+            assert!(rust.contains("pub const GAIN: f32 = 1.0f32;"));
+            assert!(rust.contains("pub const PI: f64 = 3.14f64;"));
+            assert!(rust.contains("pub const NAME: &str = \"base\";"));
+            assert!(rust.contains("pub const W: &str = \"w\";"));
+        }
+    }
+
+    #[test]
+    fn test_nested_container_conversions() {
+        let msg = parse_message("Point[2] pair\nPoint[<=3] path\nint32[] ids\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Track", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("pair: std::array::from_fn(|i| (&idiomatic.pair[i]).into()),"));
+        assert!(idiomatic.contains("pair: std::array::from_fn(|i| (&rmw.pair[i]).into()),"));
+        assert!(idiomatic.contains(
+            "path: crate::rosidl_runtime_rs::BoundedSequence::try_from_slice_converted(&idiomatic.path)"
+        ));
+        assert!(idiomatic.contains("ids: rmw.ids.as_slice().to_vec(),"));
+
+        // Services and actions convert their fields the same way
+        let srv = parse_service("Point[] points\n---\nbool ok\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Put", &srv, &HashSet::new()).unwrap();
+        assert!(pkg.service_idiomatic.contains(
+            "points: crate::rosidl_runtime_rs::Sequence::from_slice_converted(&idiomatic.points),"
+        ));
+        assert!(pkg
+            .service_idiomatic
+            .contains("points: rmw.points.to_vec_converted(),"));
+    }
+
+    #[test]
+    fn test_cyclic_message_generation() {
+        let msg = parse_message("string label\nNode[<=4] children\n").unwrap();
+        let options = GeneratorOptions {
+            cyclic_fields: [("Node".to_string(), "children".to_string())].into(),
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "tree_msgs",
+            "Node",
+            &msg,
+            &HashSet::new(),
+            options.clone(),
+        )
+        .unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("rkyv(deserialize_bounds(__D::Error: rkyv::rancor::Source))"));
+        assert!(idiomatic.contains(
+            "#[cfg_attr(feature = \"rkyv\", rkyv(omit_bounds))]\n    \
+             pub children: std::vec::Vec<crate::msg::node::Node>,"
+        ));
+
+        // Embedded bindings store sequences inline, except the cyclic ones
+        let pkg = generate_message_package_with_options(
+            "tree_msgs",
+            "Node",
+            &msg,
+            &HashSet::new(),
+            GeneratorOptions {
+                embedded: Some(EmbeddedCapacity::default()),
+                ..options
+            },
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("pub children: alloc::vec::Vec<crate::msg::node::Node>,"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("pub label: rosidl_runtime_rs::bounded::BoundedString<20>,"));
+    }
+
+    #[test]
+    fn test_associated_constants() {
+        let msg = parse_message(
+            "int8 STATUS_SUCCEEDED=4
+int8 status
+",
+        )
+        .unwrap();
+        let srv = parse_service(
+            "int32 MAX=100
+int32 a
+---
+int32 sum
+",
+        )
+        .unwrap();
+
+        let pkg =
+            generate_message_package("action_msgs", "GoalStatus", &msg, &HashSet::new()).unwrap();
+        for rust in [&pkg.message_rmw, &pkg.message_idiomatic] {
+            assert!(rust.contains("impl GoalStatus {"));
+            assert!(rust.contains("    pub const STATUS_SUCCEEDED: i8 = 4i8;"));
+            assert!(!rust.contains("\npub const STATUS_SUCCEEDED"));
+        }
+        let pkg =
+            generate_service_package("test_msgs", "AddTwoInts", &srv, &HashSet::new()).unwrap();
+        for rust in [&pkg.service_rmw, &pkg.service_idiomatic] {
+            assert!(rust.contains("    impl AddTwoIntsRequest {"));
+            assert!(rust.contains("        pub const MAX: i32 = 100i32;"));
+        }
+
+        // Module-level aliases for code written against the free constants
+        let options = GeneratorOptions {
+            module_constants: true,
+            ..Default::default()
+        };
+        for options in [
+            options.clone(),
+            GeneratorOptions {
+                types_only: true,
+                ..options.clone()
+            },
+            GeneratorOptions {
+                embedded: Some(EmbeddedCapacity::default()),
+                ..options.clone()
+            },
+        ] {
+            let pkg = generate_message_package_with_options(
+                "action_msgs",
+                "GoalStatus",
+                &msg,
+                &HashSet::new(),
+                options,
+            )
+            .unwrap();
+            assert!(pkg
+                .message_idiomatic
+                .contains("\npub const STATUS_SUCCEEDED: i8 = GoalStatus::STATUS_SUCCEEDED;"));
+        }
+        let pkg = generate_service_package_with_options(
+            "test_msgs",
+            "AddTwoInts",
+            &srv,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .service_idiomatic
+            .contains("    pub const MAX: i32 = AddTwoIntsRequest::MAX;"));
+    }
+
+    #[test]
+    fn test_deprecated_field_generation() {
+        let msg = parse_message(
+            "\n# DEPRECATED: use \"pose\"\nfloat64 x\n# @deprecated\nfloat64 y\nfloat64 z\n",
+        )
+        .unwrap();
+
+        let pkg = generate_message_package("test_msgs", "Point", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("#![allow(deprecated)]"));
+        assert!(idiomatic.contains("#[deprecated(note = \"use \\\"pose\\\"\")]\n    pub x: f64,"));
+        assert!(idiomatic.contains("#[deprecated]\n    pub y: f64,"));
+        assert!(!idiomatic.contains("`@deprecated`"));
+        assert!(!pkg.message_rmw.contains("deprecated"));
+
+        let msg = parse_message("float64 x\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Point", &msg, &HashSet::new()).unwrap();
+        assert!(!pkg.message_idiomatic.contains("deprecated"));
+    }
+
+    #[test]
+    fn test_builtin_time_conversions() {
+        let msg = parse_message("int32 sec\nuint32 nanosec\n").unwrap();
+
+        let pkg =
+            generate_message_package("builtin_interfaces", "Time", &msg, &HashSet::new()).unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("impl From<std::time::SystemTime> for Time {"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("impl From<Time> for std::time::SystemTime {"));
+
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "builtin_interfaces",
+            "Duration",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("impl From<std::time::Duration> for Duration {"));
+        assert!(pkg
+            .message_idiomatic
+            .contains("impl TryFrom<Duration> for std::time::Duration {"));
+
+        // Same-named messages of other packages get no conversions
+        let pkg = generate_message_package("my_msgs", "Time", &msg, &HashSet::new()).unwrap();
+        assert!(!pkg.message_idiomatic.contains("std::time"));
+    }
+
+    #[test]
+    fn test_wstring_conversions() {
+        let msg = parse_message("wstring label\nwstring<=5 code\nwstring[] names\nstring plain\n")
+            .unwrap();
+        let pkg = generate_message_package("test_msgs", "Label", &msg, &HashSet::new()).unwrap();
+        assert!(pkg
+            .message_rmw
+            .contains("pub label: rosidl_runtime_rs::WString,"));
+        assert!(pkg
+            .message_rmw
+            .contains("pub code: rosidl_runtime_rs::BoundedWString<5>,"));
+        assert!(pkg
+            .message_rmw
+            .contains("pub names: rosidl_runtime_rs::Sequence<rosidl_runtime_rs::WString>,"));
+
+        // Wide strings convert like in rclrs mode, plain strings keep their conversion
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("label: (&idiomatic.label).as_str().into(),"));
+        assert!(idiomatic.contains(
+            "code: (&idiomatic.code).as_str().try_into().expect(\"string exceeds its bound\"),"
+        ));
+        assert!(idiomatic
+            .contains("names: idiomatic.names.iter().map(|e| e.as_str().into()).collect(),"));
+        assert!(idiomatic.contains("names: rmw.names.iter().map(|e| e.to_string()).collect(),"));
+        assert!(idiomatic.contains("plain: (&idiomatic.plain).into(),"));
+
+        let srv = parse_service("wstring query\n---\nint32 count\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Ask", &srv, &HashSet::new()).unwrap();
+        assert!(pkg
+            .service_idiomatic
+            .contains("query: (&rmw.query).to_string(),"));
+    }
+
+    #[test]
+    fn test_serde_rename_all() {
+        let msg = parse_message("int32 frame_id\n").unwrap();
+        let options = GeneratorOptions {
+            naming: Naming {
+                serde_rename_all: Some("camelCase".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Header",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg.message_idiomatic.contains(
+            "#[cfg_attr(feature = \"serde\", derive(Serialize, Deserialize))]\n#[cfg_attr(feature = \"serde\", serde(rename_all = \"camelCase\"))]\n"
+        ));
+    }
+
+    #[test]
+    fn test_raw_identifiers() {
+        let msg = parse_message("int32 type\nstring<=4 self\n").unwrap();
+        let options = GeneratorOptions {
+            raw_identifiers: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Keywords",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+
+        assert!(pkg.message_rmw.contains("pub r#type: i32,"));
+        assert!(pkg.message_idiomatic.contains("pub r#type: i32,"));
+        assert!(pkg.message_idiomatic.contains("r#type: idiomatic.r#type,"));
+        // serde strips `r#`; a keyword without a raw form is renamed back
+        assert!(pkg.message_idiomatic.contains(
+            "#[cfg_attr(feature = \"serde\", serde(rename = \"self\"))]\n    pub self_: std::string::String,"
+        ));
+        assert!(pkg.message_idiomatic.contains("pub fn set_self("));
+    }
+
+    #[test]
+    fn test_introspection_generation() {
+        let msg = parse_message("int32 data\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Int", &msg, &HashSet::new()).unwrap();
+        assert!(!pkg.message_rmw.contains("introspection"));
+
+        let options = GeneratorOptions {
+            introspection: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Int",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .message_rmw
+            .contains("#[link(name = \"test_msgs__rosidl_typesupport_introspection_c\")]"));
+        assert!(pkg
+            .message_rmw
+            .contains("impl rosidl_runtime_rs::introspection::IntrospectionMessage for Int {"));
+        assert!(pkg.message_rmw.contains(
+            "rosidl_typesupport_introspection_c__get_message_type_support_handle__test_msgs__msg__Int()"
+        ));
+        assert!(pkg.message_rmw.contains("fn matches_introspection() {"));
+        assert!(pkg
+            .message_rmw
+            .contains("offset: std::mem::offset_of!(Int, data),"));
+
+        // The test expects the members the C struct has
+        let msg = parse_message("char type\nstring<=4[<=3] names\nfloat64[2] xy\n").unwrap();
+        let ir = StructIr::resolve("test_msgs", "Int", &msg, &GeneratorOptions::default());
+        let members: Vec<_> = introspection_members(&ir)
+            .into_iter()
+            .map(|m| {
+                (
+                    m.name,
+                    m.ros_name,
+                    m.kind,
+                    m.is_array,
+                    m.array_size,
+                    m.is_upper_bound,
+                    m.string_upper_bound,
+                )
+            })
+            .collect();
+        assert_eq!(
+            members,
+            [
+                ("type_".into(), "type".into(), "Uint8", false, 0, false, 0),
+                ("names".into(), "names".into(), "String", true, 3, true, 4),
+                ("xy".into(), "xy".into(), "Float64", true, 2, false, 0),
+            ]
+        );
+        let empty = Message::new();
+        let ir = StructIr::resolve("test_msgs", "Empty", &empty, &GeneratorOptions::default());
+        let placeholder = &introspection_members(&ir)[0];
+        assert_eq!(placeholder.name, "structure_needs_at_least_one_member");
+    }
+
+    #[test]
+    fn test_bound_setters() {
+        let msg = parse_message(
+            "string<=8 frame_id\nint32[<=3] ids\nstring<=2[<=2] type\nstring plain\n",
+        )
+        .unwrap();
+        let pkg = generate_message_package("test_msgs", "Tag", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains(
+            "pub fn set_frame_id(&mut self, value: std::string::String) -> \
+             Result<(), crate::rosidl_runtime_rs::bounded::BoundExceeded> {"
+        ));
+        assert!(idiomatic.contains("BoundExceeded::check(\"ids\", self.ids.len(), 3)?;"));
+        assert!(idiomatic.contains(
+            "BoundExceeded::check(\"type\", value.len(), 2)?; for element in value.iter() { \
+             crate::rosidl_runtime_rs::bounded::BoundExceeded::check(\"type\", element.len(), 2)?; }"
+        ));
+        assert!(idiomatic.contains("self.type_ = value;"));
+        assert!(!idiomatic.contains("set_plain"));
+
+        // Services get setters per struct with bounded fields; rclrs mode none
+        let srv = parse_service("int32 a\n---\nwstring<=4 reply\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Ask", &srv, &HashSet::new()).unwrap();
+        assert!(pkg
+            .service_idiomatic
+            .contains("impl response::AskResponse {"));
+        assert!(!pkg.service_idiomatic.contains("impl request::AskRequest {"));
+        assert!(pkg
+            .service_idiomatic
+            .contains("check(\"reply\", value.encode_utf16().count(), 4)?;"));
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Tag",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(!pkg.message_idiomatic.contains("check_bounds"));
+    }
+
+    #[test]
+    fn test_cdr_impls() {
+        let msg = parse_message(
+            "int32 type
+string[] names
+",
+        )
+        .unwrap();
+        let pkg = generate_message_package("test_msgs", "Tag", &msg, &HashSet::new()).unwrap();
+        let idiomatic = &pkg.message_idiomatic;
+        assert!(idiomatic.contains("impl crate::rosidl_runtime_rs::cdr::CdrSerialize for Tag {"));
+        assert!(idiomatic.contains(
+            "crate::rosidl_runtime_rs::cdr::CdrSerialize::cdr_serialize(&self.type_, writer);"
+        ));
+        assert!(idiomatic.contains(
+            "names: crate::rosidl_runtime_rs::cdr::CdrDeserialize::cdr_deserialize(reader)?,"
+        ));
+
+        // Every struct of services and actions, empty ones with their placeholder byte
+        let srv = parse_service("int32 a\n---\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Ask", &srv, &HashSet::new()).unwrap();
+        assert!(pkg.service_idiomatic.contains(
+            "impl crate::rosidl_runtime_rs::cdr::CdrDeserialize for request::AskRequest {"
+        ));
+        assert!(pkg.service_idiomatic.contains(
+            "impl crate::rosidl_runtime_rs::cdr::CdrSerialize for response::AskResponse {"
+        ));
+        assert!(pkg
+            .service_idiomatic
+            .contains("crate::rosidl_runtime_rs::cdr::CdrSerialize::cdr_serialize(&0u8, writer);"));
+        let action = parse_action("int32 order\n---\n---\nint32[] partial\n").unwrap();
+        let pkg = generate_action_package("test_msgs", "Fib", &action, &HashSet::new()).unwrap();
+        for path in [
+            "goal::FibGoal",
+            "result::FibResult",
+            "feedback::FibFeedback",
+        ] {
+            assert!(pkg.action_idiomatic.contains(&format!(
+                "impl crate::rosidl_runtime_rs::cdr::CdrSerialize for {} {{",
+                path
+            )));
+        }
+
+        // The upstream runtime has no `cdr` module
+        let options = GeneratorOptions {
+            rclrs_compat: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Tag",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(!pkg.message_idiomatic.contains("cdr"));
+    }
+
+    #[test]
+    fn test_wstring_default_generation() {
+        let msg = parse_message("wstring LABEL=\"héllo\"\nwstring<=5 label \"été\"\n").unwrap();
+
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Label",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("label: std::string::String::from(\"été\"),"));
+
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity::default()),
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Label",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("pub const LABEL: &str = \"héllo\";"));
+    }
+
+    #[test]
+    fn test_array_default_generation() {
+        let msg = parse_message(
+            "float64[2] gains [1, 0.5]\nstring[] names [\"a\", \"b\"]\nint32[<=3] ids [1, 2]\n",
+        )
+        .unwrap();
+
+        let options = GeneratorOptions {
+            types_only: true,
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Gains",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        let rust = &pkg.message_idiomatic;
+        assert!(rust.contains("gains: [1.0, 0.5],"));
+        assert!(rust.contains(
+            "names: std::vec![std::string::String::from(\"a\"), std::string::String::from(\"b\")],"
+        ));
+        assert!(rust.contains("ids: std::vec![1, 2],"));
+
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity::default()),
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Gains",
+            &msg,
+            &HashSet::new(),
+            options,
+        )
+        .unwrap();
+        let rust = &pkg.message_idiomatic;
+        assert!(rust.contains("gains: [1.0, 0.5],"));
+        assert!(rust.contains(
+            "ids: rosidl_runtime_rs::bounded::BoundedVec::try_from(&[1, 2][..]).unwrap(),"
+        ));
+        assert!(
+            rust.contains("rosidl_runtime_rs::bounded::BoundedString::try_from(\"b\").unwrap()")
+        );
+
+        // Sequence defaults must fit the sequence capacity
+        let options = GeneratorOptions {
+            embedded: Some(EmbeddedCapacity {
+                string: 4,
+                sequence: 1,
+            }),
+            ..Default::default()
+        };
+        let result = generate_message_package_with_options(
+            "test_msgs",
+            "Gains",
+            &msg,
+            &HashSet::new(),
+            options,
+        );
+        assert!(matches!(result, Err(GeneratorError::Field { field, .. }) if field == "names"));
+    }
+
+    #[test]
+    fn test_large_array_defaults() {
+        // Arrays of more than 32 elements implement neither Default nor serde
+        let msg = parse_message("float64[36] covariance\nPoint[40] points\n").unwrap();
+        let options = GeneratorOptions {
+            derives: vec!["Default".to_string()],
+            ..Default::default()
+        };
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Odometry",
+            &msg,
+            &HashSet::new(),
+            options.clone(),
+        )
+        .unwrap();
+        for rust in [&pkg.message_rmw, &pkg.message_idiomatic] {
+            assert!(rust.contains("#[derive(Debug, Clone, PartialEq)]\n"));
+            assert!(rust.contains("impl Default for Odometry {"));
+        }
+
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Odometry",
+            &msg,
+            &HashSet::new(),
+            GeneratorOptions {
+                types_only: true,
+                ..options.clone()
+            },
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("covariance: std::array::from_fn(|_| Default::default()),"));
+
+        let pkg = generate_message_package_with_options(
+            "test_msgs",
+            "Odometry",
+            &msg,
+            &HashSet::new(),
+            GeneratorOptions {
+                embedded: Some(EmbeddedCapacity::default()),
+                ..options
+            },
+        )
+        .unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("points: core::array::from_fn(|_| Default::default()),"));
+
+        let srv = parse_service("float64[36] covariance\n---\nPoint[33] points\n").unwrap();
+        let pkg = generate_service_package("test_msgs", "Cov", &srv, &HashSet::new()).unwrap();
+        assert_eq!(
+            pkg.service_idiomatic
+                .matches("serde(with = \"crate::serde_array\"))]\n        pub ")
+                .count(),
+            2
+        );
+    }
+
     #[test]
     fn test_simple_service_generation() {
         let srv = parse_service("int32 a\nint32 b\n---\nint32 sum\n").unwrap();
@@ -495,6 +2613,35 @@ mod tests {
         assert!(pkg.service_idiomatic.contains("AddTwoIntsResponse"));
     }
 
+    #[test]
+    fn test_service_section_docs() {
+        let srv = parse_service(
+            "# Adds two integers\n#   (indented)\n\nint32 MAX=100\nint32 a\n---\n# The sum\n\nint64 sum\n",
+        )
+        .unwrap();
+
+        let pkg =
+            generate_service_package("example_interfaces", "AddTwoInts", &srv, &HashSet::new())
+                .unwrap();
+        let idiomatic = &pkg.service_idiomatic;
+        let (request, response) = idiomatic.split_once("pub mod response").unwrap();
+        assert!(request.contains(
+            "/// Adds two integers\n    /// (indented)\n    #[derive(Debug, Clone, PartialEq)]"
+        ));
+        assert!(request.contains("pub const MAX: i32 = 100i32;"));
+        assert!(response.contains("/// The sum\n    #[derive(Debug, Clone, PartialEq)]"));
+        assert!(!response.contains("MAX"));
+    }
+
+    #[test]
+    fn test_message_doc_code_fences() {
+        let msg = parse_message("# ```\n# int main() {}\n# ```\n\nint32 x\n").unwrap();
+        let pkg = generate_message_package("test_msgs", "Code", &msg, &HashSet::new()).unwrap();
+        assert!(pkg
+            .message_idiomatic
+            .contains("/// \\```\n/// int main() {}\n/// \\```\n#[derive"));
+    }
+
     #[test]
     fn test_service_with_dependencies() {
         let srv = parse_service("geometry_msgs/Point position\n---\nbool success\n").unwrap();
@@ -507,6 +2654,44 @@ mod tests {
         assert!(pkg.cargo_toml.contains("geometry_msgs"));
     }
 
+    #[test]
+    fn test_service_event_generation() {
+        let srv = parse_service("int32 a\nint32 b\n---\nint32 sum\n").unwrap();
+        let deps = HashSet::new();
+
+        let pkg =
+            generate_service_package("example_interfaces", "AddTwoInts", &srv, &deps).unwrap();
+        assert!(!pkg.service_rmw.contains("AddTwoIntsEvent"));
+        assert!(!pkg.cargo_toml.contains(SERVICE_MSGS_PACKAGE));
+
+        let options = GeneratorOptions {
+            service_events: true,
+            ..Default::default()
+        };
+        let pkg = generate_service_package_with_options(
+            "example_interfaces",
+            "AddTwoInts",
+            &srv,
+            &deps,
+            options,
+        )
+        .unwrap();
+        assert!(pkg.cargo_toml.contains(SERVICE_MSGS_PACKAGE));
+        assert!(pkg.service_rmw.contains("pub struct AddTwoIntsEvent"));
+        assert!(pkg
+            .service_rmw
+            .contains("\"example_interfaces/srv/AddTwoInts_Event\""));
+        assert!(pkg
+            .service_rmw
+            .contains("example_interfaces__srv__AddTwoInts_Event__init"));
+        assert!(pkg
+            .service_idiomatic
+            .contains("pub use event::AddTwoIntsEvent;"));
+        assert!(pkg
+            .service_idiomatic
+            .contains("pub request: std::vec::Vec<AddTwoIntsRequest>"));
+    }
+
     #[test]
     fn test_simple_action_generation() {
         let action =
@@ -0,0 +1,322 @@
+//! OMG IDL output
+//!
+//! Converts parsed interfaces back into `.idl` files laid out like the output of
+//! `rosidl_adapter` (`module pkg { module msg { struct Name { ... }; }; };`), so
+//! interfaces authored as `.msg`/`.srv`/`.action` can be consumed by DDS tooling.
+//! Comments from the original files are not emitted.
+
+use rosidl_parser::ast::ConstantValue;
+use rosidl_parser::{Action, FieldType, Message, PrimitiveType, Section, Service};
+use std::collections::BTreeSet;
+
+/// Render a message as `pkg/msg/Name.idl`
+pub fn message_to_idl(package: &str, name: &str, message: &Message) -> String {
+    render(package, "msg", name, &[(name.to_string(), message)])
+}
+
+/// Render a service as `pkg/srv/Name.idl`
+pub fn service_to_idl(package: &str, name: &str, service: &Service) -> String {
+    render(
+        package,
+        "srv",
+        name,
+        &section_structs(name, service.sections()),
+    )
+}
+
+/// Render an action as `pkg/action/Name.idl`
+pub fn action_to_idl(package: &str, name: &str, action: &Action) -> String {
+    render(
+        package,
+        "action",
+        name,
+        &section_structs(name, action.spec.sections()),
+    )
+}
+
+/// Struct of each section, named `<name>_<Section>`
+fn section_structs<'a, const N: usize>(
+    name: &str,
+    sections: [(Section, &'a Message); N],
+) -> [(String, &'a Message); N] {
+    sections.map(|(section, message)| (format!("{}_{}", name, section.suffix()), message))
+}
+
+fn render(package: &str, kind: &str, name: &str, structs: &[(String, &Message)]) -> String {
+    let mut includes = BTreeSet::new();
+    let mut typedefs = Vec::new();
+    let mut body = String::new();
+
+    for (struct_name, message) in structs {
+        if !message.constants.is_empty() {
+            body.push_str(&format!("    module {}_Constants {{\n", struct_name));
+            for constant in &message.constants {
+                body.push_str(&format!(
+                    "      const {} {} = {};\n",
+                    idl_type(
+                        package,
+                        &constant.constant_type,
+                        &mut includes,
+                        &mut typedefs
+                    ),
+                    constant.name,
+                    idl_value(&constant.value)
+                ));
+            }
+            body.push_str("    };\n");
+        }
+
+        body.push_str(&format!("    struct {} {{\n", struct_name));
+        if message.fields.is_empty() {
+            // IDL structs must have at least one member
+            body.push_str("      uint8 structure_needs_at_least_one_member;\n");
+        }
+        for (index, field) in message.fields.iter().enumerate() {
+            if index > 0 {
+                body.push('\n');
+            }
+            if let Some(default) = &field.default_value {
+                body.push_str(&format!("      @default (value={})\n", idl_value(default)));
+            }
+            body.push_str(&format!(
+                "      {} {};\n",
+                idl_type(package, &field.field_type, &mut includes, &mut typedefs),
+                field.name
+            ));
+        }
+        body.push_str("    };\n");
+    }
+
+    let mut out = String::new();
+    out.push_str(&format!(
+        "// generated from rosidl_adapter/resource/{kind}.idl.em\n\
+         // with input from {package}/{kind}/{name}.{kind}\n\
+         // generated code does not contain a copyright notice\n\n"
+    ));
+    for include in &includes {
+        out.push_str(&format!("#include \"{}.idl\"\n", include));
+    }
+    if !includes.is_empty() {
+        out.push('\n');
+    }
+
+    out.push_str(&format!("module {} {{\n", package));
+    out.push_str(&format!("  module {} {{\n", kind));
+    let mut seen_typedefs = BTreeSet::new();
+    for typedef in typedefs {
+        if seen_typedefs.insert(typedef.clone()) {
+            out.push_str(&format!("    {}\n", typedef));
+        }
+    }
+    out.push_str(&body);
+    out.push_str("  };\n");
+    out.push_str("};\n");
+    out
+}
+
+/// IDL type for a field, registering includes and array typedefs as needed
+fn idl_type(
+    package: &str,
+    field_type: &FieldType,
+    includes: &mut BTreeSet<String>,
+    typedefs: &mut Vec<String>,
+) -> String {
+    match field_type {
+        FieldType::Primitive(primitive) => idl_primitive(*primitive).to_string(),
+        FieldType::String => "string".to_string(),
+        FieldType::BoundedString(max) => format!("string<{}>", max),
+        FieldType::WString => "wstring".to_string(),
+        FieldType::BoundedWString(max) => format!("wstring<{}>", max),
+        FieldType::Array { element_type, size } => {
+            // rosidl_adapter declares fixed-size arrays through typedefs
+            let element = idl_type(package, element_type, includes, typedefs);
+            let alias = format!(
+                "{}__{}",
+                element
+                    .replace("::", "__")
+                    .replace(['<', '>'], "_")
+                    .replace(' ', "_"),
+                size
+            );
+            typedefs.push(format!("typedef {} {}[{}];", element, alias, size));
+            alias
+        }
+        FieldType::Sequence { element_type } => {
+            format!(
+                "sequence<{}>",
+                idl_type(package, element_type, includes, typedefs)
+            )
+        }
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => format!(
+            "sequence<{}, {}>",
+            idl_type(package, element_type, includes, typedefs),
+            max_size
+        ),
+        FieldType::NamespacedType {
+            package: type_package,
+            name,
+        } => {
+            let type_package = type_package.as_deref().unwrap_or(package);
+            includes.insert(format!("{}/msg/{}", type_package, name));
+            format!("{}::msg::{}", type_package, name)
+        }
+    }
+}
+
+fn idl_primitive(primitive: PrimitiveType) -> &'static str {
+    match primitive {
+        PrimitiveType::Bool => "boolean",
+        PrimitiveType::Byte => "octet",
+        PrimitiveType::Char => "uint8",
+        PrimitiveType::Int8 => "int8",
+        PrimitiveType::UInt8 => "uint8",
+        PrimitiveType::Int16 => "int16",
+        PrimitiveType::UInt16 => "uint16",
+        PrimitiveType::Int32 => "int32",
+        PrimitiveType::UInt32 => "uint32",
+        PrimitiveType::Int64 => "int64",
+        PrimitiveType::UInt64 => "uint64",
+        PrimitiveType::Float32 => "float",
+        PrimitiveType::Float64 => "double",
+    }
+}
+
+fn idl_value(value: &ConstantValue) -> String {
+    match value {
+        ConstantValue::Integer(v) => v.to_string(),
+        ConstantValue::UInteger(v) => v.to_string(),
+        // Debug keeps the decimal point for whole numbers (1.0 rather than 1)
+        ConstantValue::Float(v) => format!("{:?}", v),
+        ConstantValue::String(v) | ConstantValue::WString(v) => {
+            format!("\"{}\"", idl_escape(v, '"'))
+        }
+        ConstantValue::Bool(v) => if *v { "TRUE" } else { "FALSE" }.to_string(),
+        // rosidl_adapter writes array defaults as a string holding a tuple
+        ConstantValue::Array(values) => {
+            let tuple = values
+                .iter()
+                .map(idl_array_element)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("\"({})\"", idl_escape(&tuple, '"'))
+        }
+    }
+}
+
+fn idl_array_element(value: &ConstantValue) -> String {
+    match value {
+        ConstantValue::String(v) | ConstantValue::WString(v) => {
+            format!("'{}'", idl_escape(v, '\''))
+        }
+        value => idl_value(value),
+    }
+}
+
+/// Escape backslashes, `quote` and control characters for a string literal
+fn idl_escape(value: &str, quote: char) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_parser::{
+        parse_action, parse_idl_action, parse_idl_message, parse_idl_service, parse_message,
+        parse_service,
+    };
+
+    #[test]
+    fn test_message_to_idl() {
+        let msg = parse_message("float64 x 1.5\nfloat64[9] covariance\nstring<=10 name\n").unwrap();
+        let idl = message_to_idl("my_msgs", "Pose", &msg);
+
+        assert!(idl.contains("// with input from my_msgs/msg/Pose.msg\n"));
+        assert!(idl.contains("module my_msgs {\n  module msg {\n"));
+        assert!(idl.contains("    typedef double double__9[9];\n"));
+        assert!(idl.contains("      @default (value=1.5)\n      double x;\n"));
+        assert!(idl.contains("      double__9 covariance;\n"));
+        assert!(idl.contains("      string<10> name;\n"));
+        assert!(idl.ends_with("    };\n  };\n};\n"));
+    }
+
+    #[test]
+    fn test_message_constants_and_includes() {
+        let msg = parse_message(
+            "int32 MIN_TEMP=-40\nstd_msgs/Header header\ngeometry_msgs/Point[<=4] points\n",
+        )
+        .unwrap();
+        let idl = message_to_idl("my_msgs", "Zone", &msg);
+
+        assert!(idl.contains(
+            "#include \"geometry_msgs/msg/Point.idl\"\n#include \"std_msgs/msg/Header.idl\"\n"
+        ));
+        assert!(idl
+            .contains("    module Zone_Constants {\n      const int32 MIN_TEMP = -40;\n    };\n"));
+        assert!(idl.contains("      std_msgs::msg::Header header;\n"));
+        assert!(idl.contains("      sequence<geometry_msgs::msg::Point, 4> points;\n"));
+    }
+
+    #[test]
+    fn test_empty_message_has_placeholder_member() {
+        let idl = message_to_idl("std_msgs", "Empty", &parse_message("").unwrap());
+        assert!(idl.contains("uint8 structure_needs_at_least_one_member;"));
+    }
+
+    #[test]
+    fn test_service_and_action_structs() {
+        let srv = parse_service("int64 a\nint64 b\n---\nint64 sum\n").unwrap();
+        let idl = service_to_idl("example_interfaces", "AddTwoInts", &srv);
+        assert!(idl.contains("  module srv {\n"));
+        assert!(idl.contains("    struct AddTwoInts_Request {\n"));
+        assert!(idl.contains("    struct AddTwoInts_Response {\n      int64 sum;\n"));
+
+        let action =
+            parse_action("int32 order\n---\nint32[] sequence\n---\nint32[] partial_sequence\n")
+                .unwrap();
+        let idl = action_to_idl("example_interfaces", "Fibonacci", &action);
+        assert!(idl.contains("    struct Fibonacci_Goal {\n"));
+        assert!(idl.contains("      sequence<int32> partial_sequence;\n"));
+    }
+
+    #[test]
+    fn test_idl_round_trip() {
+        let msg = parse_message(
+            "int32 MIN_TEMP=-40\nstd_msgs/Header header\nfloat64[9] covariance\nstring<=10 name \"a\"\ngeometry_msgs/Point[<=4] points\nbool flag true\nint32[2] pair [1, -2]\nstring[] names [\"x\", \"it's \\\"y\\\"\\n\"]\nstring GREETING=\"a\\\\b\"\n",
+        )
+        .unwrap();
+        let idl = message_to_idl("my_msgs", "Zone", &msg);
+        assert_eq!(parse_idl_message(&idl).unwrap(), msg);
+
+        let empty = parse_message("").unwrap();
+        let idl = message_to_idl("std_msgs", "Empty", &empty);
+        assert_eq!(parse_idl_message(&idl).unwrap(), empty);
+
+        let srv = parse_service("int64 a\nint64 b\n---\nint64 sum\n").unwrap();
+        let idl = service_to_idl("example_interfaces", "AddTwoInts", &srv);
+        assert_eq!(parse_idl_service(&idl).unwrap(), srv);
+
+        let action =
+            parse_action("int32 order\n---\nint32[] sequence\n---\nint32[] partial_sequence\n")
+                .unwrap();
+        let idl = action_to_idl("example_interfaces", "Fibonacci", &action);
+        assert_eq!(parse_idl_action(&idl).unwrap(), action);
+    }
+}
@@ -0,0 +1,372 @@
+//! Resolved structs between the parser AST and the templates
+//!
+//! Every struct of an interface (a message, a service request or response, an
+//! action goal, result or feedback) is resolved once into a [`StructIr`]: the
+//! Rust types of its fields in each layer, the conversions between the layers,
+//! their default values and the derives the struct supports. The message,
+//! service and action generators all read their template fields off it.
+
+use crate::generator::GeneratorOptions;
+use crate::templates::{IdiomaticField, MessageConstant, RmwField, TypesOnlyField};
+use crate::types::{
+    arbitrary_expr, comment_docs, constant_rust_type, constant_value_to_rust, deprecated_attribute,
+    field_conversions, field_docs, field_identifier, is_bounded_type, is_large_array,
+    rust_type_for_field, struct_derives, typed_constant_value,
+};
+use rosidl_parser::ast::ConstantValue;
+use rosidl_parser::{Constant, Field, FieldType, Message, PrimitiveType};
+
+/// A message, or a struct of a service or action, resolved against its package
+pub struct StructIr<'a> {
+    /// Doc comment lines from the struct's comments
+    pub docs: Vec<String>,
+    pub fields: Vec<FieldIr<'a>>,
+    pub constants: Vec<ConstantIr>,
+    /// Requested derives of the idiomatic struct that all its fields support
+    pub derives: Vec<String>,
+}
+
+/// A field with its types, conversions and defaults in each layer
+pub struct FieldIr<'a> {
+    /// Field of the interface definition
+    pub source: &'a Field,
+    /// Rust field name, escaped or a raw identifier if it is a keyword
+    pub name: String,
+    /// serde name of a field whose Rust name differs from its definition despite
+    /// raw identifiers
+    pub serde_rename: Option<String>,
+    /// Doc comment lines from the field's comments and annotations
+    pub docs: Vec<String>,
+    /// `#[deprecated]` attribute of a deprecated field
+    pub deprecated: Option<String>,
+    pub rmw_type: String,
+    pub idiomatic_type: String,
+    /// Default value of the interface definition, as a Rust literal
+    pub default_value: String,
+    /// Field value in `Default::default()` of a types-only message
+    pub types_only_default: String,
+    pub is_large_array: bool,
+    /// Idiomatic -> RMW conversion of `idiomatic.<field>`
+    pub to_rmw: String,
+    /// RMW -> idiomatic conversion of `rmw.<field>`
+    pub from_rmw: String,
+    /// Arbitrary value of the field (`arbitrary` feature)
+    pub arbitrary_value: String,
+    /// On a reference cycle of the package's messages
+    pub cyclic: bool,
+}
+
+/// A constant with its type in each layer
+pub struct ConstantIr {
+    pub name: String,
+    /// Doc comment lines from the constant's comments
+    pub docs: Vec<String>,
+    pub rmw_type: String,
+    pub idiomatic_type: String,
+    pub value: String,
+}
+
+impl<'a> StructIr<'a> {
+    /// Resolve the struct `name` of a package; cyclic fields are looked up by it
+    pub fn resolve(
+        package_name: &str,
+        name: &str,
+        message: &'a Message,
+        options: &GeneratorOptions,
+    ) -> Self {
+        let fields = message
+            .fields
+            .iter()
+            .map(|field| {
+                let ident = field_identifier(&field.name, options.raw_identifiers);
+                let (to_rmw, from_rmw) = field_conversions(
+                    &field.field_type,
+                    &ident,
+                    Some(package_name),
+                    options.rclrs_compat,
+                );
+                FieldIr {
+                    source: field,
+                    serde_rename: (options.raw_identifiers
+                        && ident.trim_start_matches("r#") != field.name)
+                        .then(|| field.name.clone()),
+                    name: ident,
+                    docs: field_docs(field),
+                    deprecated: deprecated_attribute(field.deprecated.as_deref()),
+                    rmw_type: rust_type_for_field(&field.field_type, true, Some(package_name)),
+                    idiomatic_type: rust_type_for_field(
+                        &field.field_type,
+                        false,
+                        Some(package_name),
+                    ),
+                    default_value: field
+                        .default_value
+                        .as_ref()
+                        .map(constant_value_to_rust)
+                        .unwrap_or_default(),
+                    types_only_default: types_only_default(
+                        &field.field_type,
+                        field.default_value.as_ref(),
+                    ),
+                    is_large_array: is_large_array(&field.field_type),
+                    to_rmw,
+                    from_rmw,
+                    arbitrary_value: arbitrary_expr(&field.field_type),
+                    cyclic: options
+                        .cyclic_fields
+                        .contains(&(name.to_string(), field.name.clone())),
+                }
+            })
+            .collect();
+
+        Self {
+            docs: comment_docs(&message.comments),
+            fields,
+            constants: message
+                .constants
+                .iter()
+                .map(|constant| ConstantIr::resolve(package_name, constant))
+                .collect(),
+            derives: struct_derives(&options.derives, &message.fields),
+        }
+    }
+
+    /// Doc comment lines of the idiomatic struct: its docs, then a `# Panics`
+    /// section naming the bounded fields that its RMW conversion checks
+    pub fn idiomatic_docs(&self) -> Vec<String> {
+        let bounded: Vec<String> = self
+            .fields
+            .iter()
+            .filter(|f| is_bounded_type(&f.source.field_type))
+            .map(|f| format!("`{}`", f.source.name))
+            .collect();
+        let mut docs = self.docs.clone();
+        let Some((last, rest)) = bounded.split_last() else {
+            return docs;
+        };
+        let condition = if rest.is_empty() {
+            format!("{} exceeds its bound", last)
+        } else {
+            format!("{} or {} exceed their bounds", rest.join(", "), last)
+        };
+        if !docs.is_empty() {
+            docs.push(String::new());
+        }
+        docs.extend([
+            "# Panics".to_string(),
+            String::new(),
+            format!(
+                "Converting into the RMW struct (its `From` impls, and so publishing) panics if {}.",
+                condition
+            ),
+        ]);
+        docs
+    }
+
+    pub fn rmw_fields(&self) -> Vec<RmwField> {
+        self.fields
+            .iter()
+            .map(|f| RmwField {
+                name: f.name.clone(),
+                rust_type: f.rmw_type.clone(),
+                docs: f.docs.clone(),
+                default_value: f.default_value.clone(),
+            })
+            .collect()
+    }
+
+    pub fn idiomatic_fields(&self) -> Vec<IdiomaticField> {
+        self.fields
+            .iter()
+            .map(|f| IdiomaticField {
+                name: f.name.clone(),
+                serde_rename: f.serde_rename.clone(),
+                ros_name: f.source.name.clone(),
+                rust_type: f.idiomatic_type.clone(),
+                docs: f.docs.clone(),
+                deprecated: f.deprecated.clone(),
+                default_value: f.default_value.clone(),
+                is_large_array: f.is_large_array,
+                to_rmw: f.to_rmw.clone(),
+                from_rmw: f.from_rmw.clone(),
+                arbitrary_value: f.arbitrary_value.clone(),
+                cyclic: f.cyclic,
+            })
+            .collect()
+    }
+
+    pub fn types_only_fields(&self) -> Vec<TypesOnlyField> {
+        self.fields
+            .iter()
+            .map(|f| TypesOnlyField {
+                name: f.name.clone(),
+                serde_rename: f.serde_rename.clone(),
+                rust_type: f.idiomatic_type.clone(),
+                docs: f.docs.clone(),
+                deprecated: f.deprecated.clone(),
+                default_value: f.types_only_default.clone(),
+                is_large_array: f.is_large_array,
+                arbitrary_value: f.arbitrary_value.clone(),
+                cyclic: f.cyclic,
+            })
+            .collect()
+    }
+
+    /// Constants of the RMW layer if `rmw_layer`, otherwise of the idiomatic layer
+    pub fn constants(&self, rmw_layer: bool) -> Vec<MessageConstant> {
+        self.constants
+            .iter()
+            .map(|c| MessageConstant {
+                name: c.name.clone(),
+                docs: c.docs.clone(),
+                rust_type: if rmw_layer {
+                    c.rmw_type.clone()
+                } else {
+                    c.idiomatic_type.clone()
+                },
+                value: c.value.clone(),
+            })
+            .collect()
+    }
+}
+
+impl ConstantIr {
+    fn resolve(package_name: &str, constant: &Constant) -> Self {
+        Self {
+            name: constant.name.clone(),
+            docs: comment_docs(&constant.comments),
+            rmw_type: constant_rust_type(&constant.constant_type, true, Some(package_name)),
+            idiomatic_type: constant_rust_type(&constant.constant_type, false, Some(package_name)),
+            value: typed_constant_value(&constant.value, &constant.constant_type),
+        }
+    }
+}
+
+/// Field value in `Default::default()` of a types-only message
+///
+/// Without the C init function, the default values of the message definition are
+/// applied here; everything else starts zeroed/empty like in C.
+fn types_only_default(field_type: &FieldType, default_value: Option<&ConstantValue>) -> String {
+    match (field_type, default_value) {
+        (
+            FieldType::String
+            | FieldType::BoundedString(_)
+            | FieldType::WString
+            | FieldType::BoundedWString(_),
+            Some(value @ (ConstantValue::String(_) | ConstantValue::WString(_))),
+        ) => format!(
+            "std::string::String::from({})",
+            constant_value_to_rust(value)
+        ),
+        (
+            FieldType::Primitive(PrimitiveType::Float32 | PrimitiveType::Float64),
+            Some(value @ (ConstantValue::Integer(_) | ConstantValue::UInteger(_))),
+        ) => format!("{}.0", value),
+        (FieldType::Primitive(_), Some(value)) => constant_value_to_rust(value),
+        (FieldType::Array { element_type, .. }, Some(ConstantValue::Array(values))) => {
+            format!("[{}]", types_only_elements(element_type, values))
+        }
+        (
+            FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. },
+            Some(ConstantValue::Array(values)),
+        ) => format!("std::vec![{}]", types_only_elements(element_type, values)),
+        // Arrays of more than 32 elements have no Default impl
+        (FieldType::Array { .. }, _) => "std::array::from_fn(|_| Default::default())".to_string(),
+        _ => "Default::default()".to_string(),
+    }
+}
+
+fn types_only_elements(element_type: &FieldType, values: &[ConstantValue]) -> String {
+    values
+        .iter()
+        .map(|value| types_only_default(element_type, Some(value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_parser::parse_message;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_resolve_struct() {
+        let msg = parse_message(
+            "# A node\nint32 type 7\nstring<=4[] names\nNode[] children\nuint8 MAX=3\n",
+        )
+        .unwrap();
+        let options = GeneratorOptions {
+            derives: vec!["Eq".to_string()],
+            cyclic_fields: BTreeSet::from([("Node".to_string(), "children".to_string())]),
+            ..Default::default()
+        };
+        let ir = StructIr::resolve("tree_msgs", "Node", &msg, &options);
+
+        assert_eq!(ir.docs, ["A node"]);
+        // The nested message may not be `Eq`
+        assert!(ir.derives.is_empty());
+        let field = &ir.fields[0];
+        assert_eq!(field.name, "type_");
+        assert_eq!(field.source.name, "type");
+        assert_eq!(field.rmw_type, "i32");
+        assert_eq!(field.default_value, "7");
+        assert_eq!(field.types_only_default, "7");
+        assert!(!field.cyclic);
+        assert_ne!(ir.fields[1].rmw_type, ir.fields[1].idiomatic_type);
+        assert!(ir.fields[2].cyclic);
+
+        // Each layer reads its own side of the same fields
+        assert_eq!(ir.rmw_fields()[1].rust_type, ir.fields[1].rmw_type);
+        assert_eq!(ir.idiomatic_fields()[0].ros_name, "type");
+        assert_eq!(
+            ir.types_only_fields()[2].rust_type,
+            ir.fields[2].idiomatic_type
+        );
+        assert_eq!(ir.constants(true)[0].name, "MAX");
+        assert_eq!(ir.constants(false)[0].value, ir.constants[0].value);
+    }
+
+    #[test]
+    fn test_idiomatic_docs_panics() {
+        let msg = parse_message("# A label\nstring<=4 name\nint32 x\nint32[<=2] ids\n").unwrap();
+        let ir = StructIr::resolve("pkg", "Label", &msg, &GeneratorOptions::default());
+        assert_eq!(
+            ir.idiomatic_docs(),
+            [
+                "A label",
+                "",
+                "# Panics",
+                "",
+                "Converting into the RMW struct (its `From` impls, and so publishing) panics if `name` or `ids` exceed their bounds.",
+            ]
+        );
+
+        let msg = parse_message("string<=4[2] names\nstring text\n").unwrap();
+        let ir = StructIr::resolve("pkg", "Labels", &msg, &GeneratorOptions::default());
+        assert!(ir.idiomatic_docs()[2].ends_with("if `names` exceeds its bound."));
+
+        // Without bounded fields, the docs are the struct's own
+        let msg = parse_message("# Plain\nstring text\n").unwrap();
+        let ir = StructIr::resolve("pkg", "Plain", &msg, &GeneratorOptions::default());
+        assert_eq!(ir.idiomatic_docs(), ["Plain"]);
+    }
+
+    #[test]
+    fn test_resolve_raw_identifiers() {
+        let msg = parse_message("int32 type\nint32 self\nint32 x\n").unwrap();
+        let options = GeneratorOptions {
+            raw_identifiers: true,
+            ..Default::default()
+        };
+        let ir = StructIr::resolve("test_msgs", "Keywords", &msg, &options);
+
+        assert_eq!(ir.fields[0].name, "r#type");
+        assert_eq!(ir.fields[0].to_rmw, "idiomatic.r#type");
+        assert_eq!(ir.fields[0].serde_rename, None);
+        // Without a raw form, serde keeps the name of the definition
+        assert_eq!(ir.fields[1].name, "self_");
+        assert_eq!(ir.fields[1].serde_rename.as_deref(), Some("self"));
+        assert_eq!(ir.fields[2].name, "x");
+    }
+}
@@ -1,17 +1,40 @@
+pub mod cxx;
+pub mod cycles;
 pub mod generator;
+pub mod idl;
+pub mod ir;
+pub mod naming;
+pub mod protobuf;
+pub mod python;
+pub mod ros1;
 pub mod templates;
+pub mod type_description;
 pub mod types;
 pub mod utils;
 
+pub use cycles::cyclic_fields;
 pub use generator::{
-    generate_action_package, generate_message_package, generate_service_package,
-    GeneratedActionPackage, GeneratedPackage, GeneratedServicePackage, GeneratorError,
+    generate_action_package, generate_action_package_with_options, generate_interface,
+    generate_message_package, generate_message_package_with_options, generate_serde_array_rs,
+    generate_service_package, generate_service_package_with_options, EmbeddedCapacity,
+    GeneratedActionPackage, GeneratedArtifacts, GeneratedPackage, GeneratedServicePackage,
+    GeneratorError, GeneratorOptions, Interface, BUILTIN_INTERFACES_PACKAGE, FFI_MODULE,
+    RCLRS_RUNTIME_VERSION, SERVICE_MSGS_PACKAGE, UNIQUE_IDENTIFIER_MSGS_PACKAGE,
 };
+pub use naming::Naming;
 pub use types::{
     escape_keyword, is_array_type, is_primitive_sequence, is_primitive_type, is_sequence_type,
     rust_type_for_field,
 };
 
+/// Version of the code generator: the crate version plus a hash of its sources
+/// and templates, so any change to the generated code changes it
+pub const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "+",
+    env!("ROSIDL_CODEGEN_SOURCE_HASH")
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -20,7 +43,7 @@ mod tests {
     #[test]
     fn test_basic_type_mapping() {
         let field_type = FieldType::Primitive(PrimitiveType::Int32);
-        let rust_type = rust_type_for_field(&field_type, false);
+        let rust_type = rust_type_for_field(&field_type, false, None);
         assert_eq!(rust_type, "i32");
     }
 
@@ -0,0 +1,135 @@
+//! Naming rules of generated crates
+//!
+//! Generated crates are named after their ROS package and export each type
+//! under its interface name, which clashes with crates.io packages of the same
+//! name (`std_msgs`, ...). These rules give the crates names of their own,
+//! export types under other names and pick how serde names the fields.
+
+use crate::utils::to_snake_case;
+use std::collections::BTreeMap;
+
+/// serde `rename_all` rules
+pub const SERDE_RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// Crate names, exported type names and serde field names of generated crates
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Naming {
+    /// Prefix of the crate name of every generated crate, such as `ros2_`
+    pub crate_prefix: String,
+    /// Crate names of single packages, taking precedence over the prefix
+    pub crate_names: BTreeMap<String, String>,
+    /// Exported names of interfaces by their full name, such as
+    /// `std_msgs/msg/String`; services and actions keep the suffixes of their
+    /// parts (`RequestRequest` for a service renamed to `Request`)
+    pub type_renames: BTreeMap<String, String>,
+    /// serde `rename_all` rule of the fields of the idiomatic structs, one of
+    /// [`SERDE_RENAME_RULES`]
+    pub serde_rename_all: Option<String>,
+}
+
+impl Naming {
+    /// Crate name of the bindings of a package
+    pub fn crate_name(&self, package: &str) -> String {
+        match self.crate_names.get(package) {
+            Some(name) => name.clone(),
+            None => format!("{}{}", self.crate_prefix, package).replace('-', "_"),
+        }
+    }
+
+    /// Name the type of an interface is exported under, `kind` being `msg`,
+    /// `srv` or `action`
+    pub fn type_name(&self, package: &str, kind: &str, name: &str) -> String {
+        self.type_renames
+            .get(&format!("{}/{}/{}", package, kind, name))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    /// Check the rules, naming the first invalid one
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(rule) = &self.serde_rename_all {
+            if !SERDE_RENAME_RULES.contains(&rule.as_str()) {
+                return Err(format!(
+                    "unknown serde rename rule `{}`, expected one of {}",
+                    rule,
+                    SERDE_RENAME_RULES.join(", ")
+                ));
+            }
+        }
+        for (package, name) in &self.crate_names {
+            if !is_identifier(name) || to_snake_case(name) != *name {
+                return Err(format!(
+                    "crate name `{}` of {} is not a snake_case identifier",
+                    name, package
+                ));
+            }
+        }
+        for (interface, name) in &self.type_renames {
+            if interface.split('/').count() != 3 {
+                return Err(format!(
+                    "renamed type `{}` is not of the form `<package>/<kind>/<Name>`",
+                    interface
+                ));
+            }
+            if !is_identifier(name) {
+                return Err(format!(
+                    "new name `{}` of {} is not an identifier",
+                    name, interface
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming() {
+        let naming = Naming {
+            crate_prefix: "ros2_".to_string(),
+            crate_names: BTreeMap::from([("std_msgs".to_string(), "ros_std_msgs".to_string())]),
+            type_renames: BTreeMap::from([(
+                "std_msgs/msg/String".to_string(),
+                "StringMsg".to_string(),
+            )]),
+            serde_rename_all: Some("camelCase".to_string()),
+        };
+        assert!(naming.validate().is_ok());
+        assert_eq!(naming.crate_name("std_msgs"), "ros_std_msgs");
+        assert_eq!(naming.crate_name("geometry_msgs"), "ros2_geometry_msgs");
+        assert_eq!(naming.type_name("std_msgs", "msg", "String"), "StringMsg");
+        assert_eq!(naming.type_name("std_msgs", "msg", "Header"), "Header");
+        assert_eq!(Naming::default().crate_name("std_msgs"), "std_msgs");
+
+        let naming = Naming {
+            serde_rename_all: Some("camel".to_string()),
+            ..Default::default()
+        };
+        assert!(naming
+            .validate()
+            .unwrap_err()
+            .starts_with("unknown serde rename rule `camel`"));
+        let naming = Naming {
+            type_renames: BTreeMap::from([("String".to_string(), "StringMsg".to_string())]),
+            ..Default::default()
+        };
+        assert!(naming.validate().is_err());
+    }
+}
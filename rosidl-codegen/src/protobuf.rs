@@ -0,0 +1,374 @@
+//! Protobuf mapping
+//!
+//! Emits a proto3 file per interface package and the `From` conversions between the
+//! idiomatic message structs and the types prost generates from it. Integer types
+//! narrower than 32 bits are widened to `int32`/`uint32`, `byte`/`char`/`uint8`
+//! arrays and sequences map to `bytes`, and nested messages of other packages are
+//! imported from `<package>.proto`. Constants have no proto3 equivalent and stay on
+//! the Rust side only.
+
+use crate::types::{escape_keyword, to_snake_case};
+use crate::utils::{element_type, nested_type};
+use rosidl_parser::{FieldType, Message, PrimitiveType};
+use std::collections::BTreeSet;
+
+/// Packages whose `.proto` files the package's messages import
+pub fn proto_dependencies(package: &str, messages: &[(String, Message)]) -> BTreeSet<String> {
+    let mut deps = BTreeSet::new();
+    for (_, message) in messages {
+        for field in &message.fields {
+            if let Some((Some(dep), _)) = nested_type(&field.field_type) {
+                if dep != package {
+                    deps.insert(dep.to_string());
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// Generate `<package>.proto` for the package's messages
+pub fn generate_proto_file(package: &str, messages: &[(String, Message)]) -> String {
+    let mut out = format!(
+        "// Protobuf mapping for ROS 2 package: {}\n\nsyntax = \"proto3\";\n\npackage {};\n",
+        package, package
+    );
+
+    let deps = proto_dependencies(package, messages);
+    if !deps.is_empty() {
+        out.push('\n');
+        for dep in &deps {
+            out.push_str(&format!("import \"{}.proto\";\n", dep));
+        }
+    }
+
+    for (name, message) in messages {
+        out.push_str(&format!(
+            "\n// {}/msg/{}\nmessage {} {{\n",
+            package, name, name
+        ));
+        for (index, field) in message.fields.iter().enumerate() {
+            out.push_str(&format!(
+                "  {} {} = {};\n",
+                proto_type(package, &field.field_type),
+                field.name,
+                index + 1
+            ));
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+/// Generate `src/proto.rs`: the prost types (included from `OUT_DIR`) plus `From`
+/// conversions in both directions for every message
+pub fn generate_proto_conversions(package: &str, messages: &[(String, Message)]) -> String {
+    let mut out = format!(
+        "// Protobuf mapping for ROS 2 package: {package}\n\
+         // prost types are compiled from proto/{package}.proto by build.rs\n\n\
+         include!(concat!(env!(\"OUT_DIR\"), \"/{package}.rs\"));\n"
+    );
+
+    for (name, message) in messages {
+        let idiomatic = format!("crate::msg::{}::{}", to_snake_case(name), name);
+        let prost_name = prost_type_name(name);
+        let arg = if message.fields.is_empty() {
+            "_msg"
+        } else {
+            "msg"
+        };
+
+        out.push_str(&format!(
+            "\nimpl From<{idiomatic}> for {prost_name} {{\n    fn from({arg}: {idiomatic}) -> Self {{\n        Self {{\n"
+        ));
+        for field in &message.fields {
+            out.push_str(&format!(
+                "            {}: {},\n",
+                prost_field_name(&field.name),
+                to_proto(
+                    &field.field_type,
+                    &format!("msg.{}", escape_keyword(&field.name))
+                )
+            ));
+        }
+        out.push_str("        }\n    }\n}\n");
+
+        out.push_str(&format!(
+            "\nimpl From<{prost_name}> for {idiomatic} {{\n    fn from({arg}: {prost_name}) -> Self {{\n        Self {{\n"
+        ));
+        for field in &message.fields {
+            out.push_str(&format!(
+                "            {}: {},\n",
+                escape_keyword(&field.name),
+                from_proto(
+                    &field.field_type,
+                    &format!("msg.{}", prost_field_name(&field.name))
+                )
+            ));
+        }
+        out.push_str("        }\n    }\n}\n");
+    }
+
+    out.push_str(
+        "\n/// Fill a fixed-size array from a repeated field, padding with defaults\n\
+         #[allow(dead_code)]\n\
+         fn to_array<T: Default, const N: usize>(values: Vec<T>) -> [T; N] {\n    \
+             let mut values = values.into_iter();\n    \
+             std::array::from_fn(|_| values.next().unwrap_or_default())\n\
+         }\n",
+    );
+    out
+}
+
+fn is_byte_like(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Primitive(PrimitiveType::Byte | PrimitiveType::Char | PrimitiveType::UInt8)
+    )
+}
+
+fn proto_type(package: &str, field_type: &FieldType) -> String {
+    if let Some(element) = element_type(field_type) {
+        return if is_byte_like(element) {
+            "bytes".to_string()
+        } else {
+            format!("repeated {}", proto_type(package, element))
+        };
+    }
+
+    match field_type {
+        FieldType::Primitive(primitive) => proto_primitive(*primitive).to_string(),
+        FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_) => "string".to_string(),
+        FieldType::NamespacedType {
+            package: Some(type_package),
+            name,
+        } if type_package != package => format!("{}.{}", type_package, name),
+        FieldType::NamespacedType { name, .. } => name.clone(),
+        FieldType::Array { .. }
+        | FieldType::Sequence { .. }
+        | FieldType::BoundedSequence { .. } => {
+            unreachable!("handled above")
+        }
+    }
+}
+
+fn proto_primitive(primitive: PrimitiveType) -> &'static str {
+    match primitive {
+        PrimitiveType::Bool => "bool",
+        PrimitiveType::Byte
+        | PrimitiveType::Char
+        | PrimitiveType::UInt8
+        | PrimitiveType::UInt16
+        | PrimitiveType::UInt32 => "uint32",
+        PrimitiveType::Int8 | PrimitiveType::Int16 | PrimitiveType::Int32 => "int32",
+        PrimitiveType::Int64 => "int64",
+        PrimitiveType::UInt64 => "uint64",
+        PrimitiveType::Float32 => "float",
+        PrimitiveType::Float64 => "double",
+    }
+}
+
+/// Expression converting an idiomatic value to its prost representation
+fn to_proto(field_type: &FieldType, value: &str) -> String {
+    if let Some(element) = element_type(field_type) {
+        let is_array = matches!(field_type, FieldType::Array { .. });
+        // Byte-like elements are carried as `bytes` (Vec<u8>) without widening
+        let convert = if is_byte_like(element) {
+            "v".to_string()
+        } else {
+            element_to_proto(element, "v")
+        };
+        return match (convert == "v", is_array) {
+            (true, true) => format!("{}.to_vec()", value),
+            (true, false) => value.to_string(),
+            (false, _) => format!("{}.into_iter().map(|v| {}).collect()", value, convert),
+        };
+    }
+
+    match field_type {
+        FieldType::NamespacedType { .. } => format!("Some({}.into())", value),
+        _ => element_to_proto(field_type, value),
+    }
+}
+
+/// Expression converting a prost value back to the idiomatic representation
+fn from_proto(field_type: &FieldType, value: &str) -> String {
+    if let Some(element) = element_type(field_type) {
+        let is_array = matches!(field_type, FieldType::Array { .. });
+        let convert = if is_byte_like(element) {
+            "v".to_string()
+        } else {
+            element_from_proto(element, "v")
+        };
+        let collected = if convert == "v" {
+            value.to_string()
+        } else {
+            format!("{}.into_iter().map(|v| {}).collect()", value, convert)
+        };
+        return if is_array {
+            format!("to_array({})", collected)
+        } else {
+            collected
+        };
+    }
+
+    match field_type {
+        FieldType::NamespacedType { .. } => {
+            format!("{}.map(Into::into).unwrap_or_default()", value)
+        }
+        _ => element_from_proto(field_type, value),
+    }
+}
+
+fn element_to_proto(field_type: &FieldType, value: &str) -> String {
+    match field_type {
+        FieldType::Primitive(
+            PrimitiveType::Byte
+            | PrimitiveType::Char
+            | PrimitiveType::UInt8
+            | PrimitiveType::UInt16,
+        ) => format!("u32::from({})", value),
+        FieldType::Primitive(PrimitiveType::Int8 | PrimitiveType::Int16) => {
+            format!("i32::from({})", value)
+        }
+        FieldType::NamespacedType { .. } => format!("{}.into()", value),
+        _ => value.to_string(),
+    }
+}
+
+fn element_from_proto(field_type: &FieldType, value: &str) -> String {
+    match field_type {
+        FieldType::Primitive(
+            primitive @ (PrimitiveType::Byte
+            | PrimitiveType::Char
+            | PrimitiveType::UInt8
+            | PrimitiveType::UInt16
+            | PrimitiveType::Int8
+            | PrimitiveType::Int16),
+        ) => format!("{} as {}", value, primitive.rust_type()),
+        FieldType::NamespacedType { .. } => format!("{}.into()", value),
+        _ => value.to_string(),
+    }
+}
+
+/// Type name as emitted by prost (UpperCamelCase with acronyms lowered, `GPSFix` -> `GpsFix`)
+fn prost_type_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && match prev {
+                Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                _ => false,
+            };
+        if boundary || words.is_empty() {
+            words.push(String::new());
+        }
+        words.last_mut().unwrap().push(c);
+    }
+
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(|c| c.to_lowercase()))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Field name as emitted by prost (raw identifiers for Rust keywords)
+fn prost_field_name(name: &str) -> String {
+    match name {
+        "self" | "super" | "crate" | "extern" => format!("{}_", name),
+        _ if escape_keyword(name) != name => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::messages;
+
+    #[test]
+    fn test_proto_file() {
+        let msgs = messages(&[(
+            "Scan",
+            "std_msgs/Header header\nuint8 mode\nint16 offset\nfloat32[] ranges\nuint8[16] id\nLocal[] parts\nstring<=8 label\n",
+        )]);
+        let proto = generate_proto_file("my_msgs", &msgs);
+
+        assert!(proto.contains("syntax = \"proto3\";\n\npackage my_msgs;\n"));
+        assert!(proto.contains("import \"std_msgs.proto\";\n"));
+        assert!(proto.contains("message Scan {\n"));
+        assert!(proto.contains("  std_msgs.Header header = 1;\n"));
+        assert!(proto.contains("  uint32 mode = 2;\n"));
+        assert!(proto.contains("  int32 offset = 3;\n"));
+        assert!(proto.contains("  repeated float ranges = 4;\n"));
+        assert!(proto.contains("  bytes id = 5;\n"));
+        assert!(proto.contains("  repeated Local parts = 6;\n"));
+        assert!(proto.contains("  string label = 7;\n"));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let msgs = messages(&[(
+            "Scan",
+            "std_msgs/Header header\nuint8 mode\nfloat64[3] offsets\nint8[] levels\nstring type\nuint8[] data\nbyte[4] id\n",
+        )]);
+        let rust = generate_proto_conversions("my_msgs", &msgs);
+
+        assert!(rust.contains("include!(concat!(env!(\"OUT_DIR\"), \"/my_msgs.rs\"));"));
+        assert!(rust.contains("impl From<crate::msg::scan::Scan> for Scan {"));
+        assert!(rust.contains("impl From<Scan> for crate::msg::scan::Scan {"));
+        assert!(rust.contains("header: Some(msg.header.into()),"));
+        assert!(rust.contains("header: msg.header.map(Into::into).unwrap_or_default(),"));
+        assert!(rust.contains("mode: u32::from(msg.mode),"));
+        assert!(rust.contains("mode: msg.mode as u8,"));
+        assert!(rust.contains("offsets: msg.offsets.to_vec(),"));
+        assert!(rust.contains("offsets: to_array(msg.offsets),"));
+        assert!(rust.contains("levels: msg.levels.into_iter().map(|v| i32::from(v)).collect(),"));
+        assert!(rust.contains("levels: msg.levels.into_iter().map(|v| v as i8).collect(),"));
+        assert!(rust.contains("r#type: msg.type_,"));
+        assert!(rust.contains("type_: msg.r#type,"));
+        assert!(rust.contains("data: msg.data,"));
+        assert!(rust.contains("id: msg.id.to_vec(),"));
+        assert!(rust.contains("id: to_array(msg.id),"));
+    }
+
+    #[test]
+    fn test_empty_message_conversions() {
+        let rust = generate_proto_conversions("std_msgs", &messages(&[("Empty", "")]));
+        assert!(rust.contains("fn from(_msg: crate::msg::empty::Empty) -> Self {"));
+    }
+
+    #[test]
+    fn test_prost_type_name() {
+        assert_eq!(prost_type_name("Point"), "Point");
+        assert_eq!(prost_type_name("GPSFix"), "GpsFix");
+        assert_eq!(prost_type_name("PointCloud2"), "PointCloud2");
+        assert_eq!(prost_type_name("IMU"), "Imu");
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let msgs = messages(&[(
+            "Path",
+            "geometry_msgs/PoseStamped[] poses\nmy_msgs/Local local\nstd_msgs/Header header\n",
+        )]);
+        let deps: Vec<_> = proto_dependencies("my_msgs", &msgs).into_iter().collect();
+        assert_eq!(deps, vec!["geometry_msgs", "std_msgs"]);
+    }
+}
@@ -0,0 +1,297 @@
+//! PyO3 bindings
+//!
+//! Emits `src/python.rs` for a package, compiled behind its `python` feature: a
+//! `#[pyclass]` wrapper per idiomatic message plus `<message>_to_dict` /
+//! `<message>_from_dict` functions. The dicts have the layout rclpy uses
+//! (`rosidl_runtime_py.message_to_ordereddict`): original field names, nested
+//! messages as nested dicts and arrays/sequences as lists. Messages of other
+//! packages are converted through `<package>::python`, so the feature is
+//! forwarded to every dependency.
+
+use crate::types::{escape_keyword, to_snake_case};
+use crate::utils::nested_type;
+use rosidl_parser::{FieldType, Message};
+use std::collections::BTreeSet;
+
+/// Packages whose `python` modules the package's messages use
+pub fn python_dependencies(package: &str, messages: &[(String, Message)]) -> BTreeSet<String> {
+    let mut deps = BTreeSet::new();
+    for (_, message) in messages {
+        for field in &message.fields {
+            if let Some((Some(dep), _)) = nested_type(&field.field_type) {
+                if dep != package {
+                    deps.insert(dep.to_string());
+                }
+            }
+        }
+    }
+    deps
+}
+
+/// Generate `src/python.rs` for the package's messages
+pub fn generate_python_module(package: &str, messages: &[(String, Message)]) -> String {
+    let mut out = format!(
+        "// Python bindings for ROS 2 package: {package}\n\
+         // Compiled with the `python` feature (PyO3)\n\n\
+         use pyo3::exceptions::{{PyAttributeError, PyValueError}};\n\
+         use pyo3::prelude::*;\n\
+         use pyo3::types::PyDict;\n"
+    );
+
+    for (name, message) in messages {
+        out.push_str(&message_functions(package, name, message));
+        out.push_str(&message_class(package, name));
+    }
+
+    out.push_str("\n/// Add the message classes to a Python module\n");
+    out.push_str("pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {\n");
+    for (name, _) in messages {
+        out.push_str(&format!("    module.add_class::<Py{}>()?;\n", name));
+    }
+    out.push_str("    Ok(())\n}\n");
+
+    out.push_str(
+        "\n/// Reject dict keys that are not fields of the message, like rclpy does\n\
+         #[allow(dead_code)]\n\
+         fn check_fields(type_name: &str, dict: &Bound<'_, PyDict>, fields: &[&str]) -> PyResult<()> {\n    \
+             for key in dict.keys() {\n        \
+                 let key: String = key.extract()?;\n        \
+                 if !fields.contains(&key.as_str()) {\n            \
+                     return Err(PyAttributeError::new_err(format!(\n                \
+                         \"{} has no field '{}'\",\n                \
+                         type_name, key\n            \
+                     )));\n        \
+                 }\n    \
+             }\n    \
+             Ok(())\n\
+         }\n\
+         \n\
+         /// Convert a list to a fixed-size array, requiring the exact length\n\
+         #[allow(dead_code)]\n\
+         fn to_array<T, const N: usize>(values: Vec<T>) -> PyResult<[T; N]> {\n    \
+             let len = values.len();\n    \
+             values.try_into().map_err(|_| {\n        \
+                 PyValueError::new_err(format!(\"expected {} elements, got {}\", N, len))\n    \
+             })\n\
+         }\n",
+    );
+    out
+}
+
+/// `<message>_to_dict` and `<message>_from_dict`
+fn message_functions(package: &str, name: &str, message: &Message) -> String {
+    let snake = to_snake_case(name);
+    let idiomatic = format!("crate::msg::{}::{}", snake, name);
+    let type_name = format!("{}/msg/{}", package, name);
+    let arg = if message.fields.is_empty() {
+        "_msg"
+    } else {
+        "msg"
+    };
+
+    let mut out = format!(
+        "\n/// `{type_name}` as an rclpy-compatible dict\n\
+         pub fn {snake}_to_dict<'py>(py: Python<'py>, {arg}: &{idiomatic}) -> PyResult<Bound<'py, PyDict>> {{\n    \
+             let dict = PyDict::new_bound(py);\n"
+    );
+    for field in &message.fields {
+        let value = format!("msg.{}", escape_keyword(&field.name));
+        out.push_str(&format!(
+            "    dict.set_item({:?}, {})?;\n",
+            field.name,
+            to_python(package, &field.field_type, &value)
+        ));
+    }
+    out.push_str("    Ok(dict)\n}\n");
+
+    let fields: Vec<String> = message
+        .fields
+        .iter()
+        .map(|f| format!("{:?}", f.name))
+        .collect();
+    out.push_str(&format!(
+        "\n/// Build `{type_name}` from a dict, keeping defaults for missing fields\n\
+         pub fn {snake}_from_dict(dict: &Bound<'_, PyDict>) -> PyResult<{idiomatic}> {{\n    \
+             check_fields({type_name:?}, dict, &[{}])?;\n",
+        fields.join(", ")
+    ));
+    if message.fields.is_empty() {
+        out.push_str("    Ok(Default::default())\n}\n");
+        return out;
+    }
+    out.push_str(&format!("    let mut msg = {}::default();\n", idiomatic));
+    for field in &message.fields {
+        out.push_str(&format!(
+            "    if let Some(value) = dict.get_item({:?})? {{\n        msg.{} = {};\n    }}\n",
+            field.name,
+            escape_keyword(&field.name),
+            from_python(package, &field.field_type)
+        ));
+    }
+    out.push_str("    Ok(msg)\n}\n");
+    out
+}
+
+/// `#[pyclass]` wrapper with keyword constructor, dict conversions and field access
+fn message_class(package: &str, name: &str) -> String {
+    let snake = to_snake_case(name);
+    format!(
+        r#"
+/// Python class for `{package}/msg/{name}`
+#[pyclass(name = "{name}", module = "{package}.msg")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Py{name}(pub crate::msg::{snake}::{name});
+
+#[pymethods]
+impl Py{name} {{
+    #[new]
+    #[pyo3(signature = (**fields))]
+    fn new(fields: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {{
+        match fields {{
+            Some(fields) => Ok(Self({snake}_from_dict(fields)?)),
+            None => Ok(Self(Default::default())),
+        }}
+    }}
+
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {{
+        Ok(Self({snake}_from_dict(dict)?))
+    }}
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {{
+        {snake}_to_dict(py, &self.0)
+    }}
+
+    fn __getattr__(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {{
+        self.to_dict(py)?
+            .get_item(name)?
+            .map(Bound::unbind)
+            .ok_or_else(|| PyAttributeError::new_err(format!("{package}/msg/{name} has no field '{{}}'", name)))
+    }}
+
+    fn __setattr__(&mut self, name: &str, value: Bound<'_, PyAny>) -> PyResult<()> {{
+        let dict = self.to_dict(value.py())?;
+        dict.set_item(name, value)?;
+        self.0 = {snake}_from_dict(&dict)?;
+        Ok(())
+    }}
+
+    fn __eq__(&self, other: &Self) -> bool {{
+        self.0 == other.0
+    }}
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {{
+        Ok(format!("{package}.msg.{name}({{}})", self.to_dict(py)?.repr()?))
+    }}
+}}
+"#
+    )
+}
+
+/// Path of the conversion function for a nested message (`to_dict` or `from_dict`)
+fn nested_function(package: &str, type_package: Option<&str>, name: &str, suffix: &str) -> String {
+    match type_package {
+        Some(type_package) if type_package != package => {
+            format!(
+                "{}::python::{}_{}",
+                type_package,
+                to_snake_case(name),
+                suffix
+            )
+        }
+        _ => format!("{}_{}", to_snake_case(name), suffix),
+    }
+}
+
+/// Expression converting an idiomatic field to a value accepted by `PyDict::set_item`
+fn to_python(package: &str, field_type: &FieldType, value: &str) -> String {
+    match (field_type, nested_type(field_type)) {
+        (FieldType::NamespacedType { .. }, Some((type_package, name))) => format!(
+            "{}(py, &{})?",
+            nested_function(package, type_package, name, "to_dict"),
+            value
+        ),
+        (_, Some((type_package, name))) => format!(
+            "{}.iter().map(|v| {}(py, v)).collect::<PyResult<Vec<_>>>()?",
+            value,
+            nested_function(package, type_package, name, "to_dict")
+        ),
+        // Primitives, strings and their arrays/sequences convert directly
+        (_, None) => format!("&{}", value),
+    }
+}
+
+/// Expression converting a Python `value` back to the idiomatic field type
+fn from_python(package: &str, field_type: &FieldType) -> String {
+    match (field_type, nested_type(field_type)) {
+        (FieldType::NamespacedType { .. }, Some((type_package, name))) => format!(
+            "{}(value.downcast()?)?",
+            nested_function(package, type_package, name, "from_dict")
+        ),
+        (_, Some((type_package, name))) => {
+            let collected = format!(
+                "value\n            .extract::<Vec<Bound<'_, PyDict>>>()?\n            .iter()\n            .map({})\n            .collect::<PyResult<Vec<_>>>()?",
+                nested_function(package, type_package, name, "from_dict")
+            );
+            if matches!(field_type, FieldType::Array { .. }) {
+                format!("to_array({})?", collected)
+            } else {
+                collected
+            }
+        }
+        (_, None) => "value.extract()?".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::messages;
+
+    #[test]
+    fn test_python_module() {
+        let msgs = messages(&[
+            (
+                "Scan",
+                "std_msgs/Header header\nfloat32[] ranges\nstring type\nLocal[2] parts\ngeometry_msgs/Point[] points\n",
+            ),
+            ("Local", "int32 id\n"),
+        ]);
+        let rust = generate_python_module("my_msgs", &msgs);
+
+        assert!(rust
+            .contains("pub fn scan_to_dict<'py>(py: Python<'py>, msg: &crate::msg::scan::Scan)"));
+        assert!(rust.contains(
+            "dict.set_item(\"header\", std_msgs::python::header_to_dict(py, &msg.header)?)?;"
+        ));
+        assert!(rust.contains("dict.set_item(\"ranges\", &msg.ranges)?;"));
+        assert!(rust.contains("dict.set_item(\"type\", &msg.type_)?;"));
+        assert!(rust.contains(
+            "dict.set_item(\"parts\", msg.parts.iter().map(|v| local_to_dict(py, v)).collect::<PyResult<Vec<_>>>()?)?;"
+        ));
+        assert!(rust.contains("check_fields(\"my_msgs/msg/Scan\", dict, &[\"header\", \"ranges\", \"type\", \"parts\", \"points\"])?;"));
+        assert!(
+            rust.contains("msg.header = std_msgs::python::header_from_dict(value.downcast()?)?;")
+        );
+        assert!(rust.contains("msg.type_ = value.extract()?;"));
+        assert!(
+            rust.contains(".map(local_from_dict)\n            .collect::<PyResult<Vec<_>>>()?)?;")
+        );
+        assert!(rust.contains("#[pyclass(name = \"Scan\", module = \"my_msgs.msg\")]"));
+        assert!(rust.contains("pub struct PyLocal(pub crate::msg::local::Local);"));
+        assert!(rust
+            .contains("    module.add_class::<PyScan>()?;\n    module.add_class::<PyLocal>()?;\n"));
+
+        let deps: Vec<_> = python_dependencies("my_msgs", &msgs).into_iter().collect();
+        assert_eq!(deps, vec!["geometry_msgs", "std_msgs"]);
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let rust = generate_python_module("std_msgs", &messages(&[("Empty", "")]));
+        assert!(rust.contains("py: Python<'py>, _msg: &crate::msg::empty::Empty"));
+        assert!(rust.contains(
+            "check_fields(\"std_msgs/msg/Empty\", dict, &[])?;\n    Ok(Default::default())"
+        ));
+    }
+}
@@ -0,0 +1,524 @@
+//! ROS 1 compatibility metadata
+//!
+//! ros1_bridge and ROS 1 tooling identify messages by an md5sum computed over a
+//! normalized definition, and transmit the full definition text alongside it.
+//! This module derives both from the parsed ROS 2 AST, mapping ROS 2 types to their
+//! ROS 1 equivalents (e.g. `builtin_interfaces/Time` -> `time`) and recording every
+//! field whose representation differs between the two.
+//!
+//! Constants are rendered from their parsed values rather than the original text,
+//! so messages with unusual constant spellings (e.g. `0x10`) may not reproduce the
+//! md5sum of a hand-written ROS 1 definition.
+
+use crate::generator::GeneratorError;
+use md5::{Digest, Md5};
+use rosidl_parser::ast::ConstantValue;
+use rosidl_parser::{BuiltinType, FieldType, Message, PrimitiveType};
+use std::collections::HashMap;
+
+/// Separator between definitions in a ROS 1 full message definition
+const DEFINITION_SEPARATOR: &str =
+    "================================================================================";
+
+/// ROS 1 `std_msgs/Header` definition (ROS 2 removed the `seq` field)
+const ROS1_HEADER_DEFINITION: &str = "uint32 seq\ntime stamp\nstring frame_id";
+
+/// ROS 1 metadata for a single message type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ros1MessageInfo {
+    /// ROS 1 type name (`pkg/Name`)
+    pub datatype: String,
+    /// md5sum as computed by genmsg
+    pub md5sum: String,
+    /// Full definition text including dependencies
+    pub definition: String,
+    /// Fields whose ROS 1 representation differs from ROS 2
+    pub mapping_notes: Vec<TypeMappingNote>,
+}
+
+/// A field whose type changes when mapped to ROS 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMappingNote {
+    /// Message containing the field (`pkg/Name`)
+    pub datatype: String,
+    pub field: String,
+    pub ros2_type: String,
+    pub ros1_type: String,
+    pub reason: String,
+}
+
+/// Computes ROS 1 metadata, resolving referenced messages through a callback
+///
+/// The resolver returns the parsed definition of `package/Name`, or `None` if it
+/// is unknown. Results are memoized per type.
+pub struct Ros1Resolver<F>
+where
+    F: FnMut(&str, &str) -> Option<Message>,
+{
+    resolve: F,
+    cache: HashMap<String, Ros1MessageInfo>,
+}
+
+/// Per-message intermediate result: md5 text, own definition, and dependency order
+struct Rendered {
+    md5_text: String,
+    own_definition: String,
+    dependencies: Vec<String>,
+    notes: Vec<TypeMappingNote>,
+}
+
+impl<F> Ros1Resolver<F>
+where
+    F: FnMut(&str, &str) -> Option<Message>,
+{
+    pub fn new(resolve: F) -> Self {
+        Self {
+            resolve,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Compute metadata for `package/name` with the given parsed definition
+    pub fn message_info(
+        &mut self,
+        package: &str,
+        name: &str,
+        message: &Message,
+    ) -> Result<Ros1MessageInfo, GeneratorError> {
+        let datatype = format!("{}/{}", package, name);
+        if let Some(info) = self.cache.get(&datatype) {
+            return Ok(info.clone());
+        }
+
+        let rendered = self.render(package, &datatype, message)?;
+
+        let mut definition = rendered.own_definition;
+        for dep in &rendered.dependencies {
+            let dep_definition = self.own_definition(dep)?;
+            definition.push_str(&format!(
+                "\n{}\nMSG: {}\n{}",
+                DEFINITION_SEPARATOR, dep, dep_definition
+            ));
+        }
+        definition.push('\n');
+
+        let info = Ros1MessageInfo {
+            datatype: datatype.clone(),
+            md5sum: md5_hex(&rendered.md5_text),
+            definition,
+            mapping_notes: rendered.notes,
+        };
+        self.cache.insert(datatype, info.clone());
+        Ok(info)
+    }
+
+    /// md5sum of a referenced type
+    fn dependency_md5(&mut self, datatype: &str) -> Result<String, GeneratorError> {
+        if datatype == "std_msgs/Header" {
+            return Ok(md5_hex(ROS1_HEADER_DEFINITION));
+        }
+        let (package, name) = split_datatype(datatype);
+        let message = self.lookup(package, name)?;
+        Ok(self.message_info(package, name, &message)?.md5sum)
+    }
+
+    /// Definition text of a referenced type, without its own dependencies
+    fn own_definition(&mut self, datatype: &str) -> Result<String, GeneratorError> {
+        if datatype == "std_msgs/Header" {
+            return Ok(ROS1_HEADER_DEFINITION.to_string());
+        }
+        let (package, name) = split_datatype(datatype);
+        let message = self.lookup(package, name)?;
+        Ok(self.render(package, datatype, &message)?.own_definition)
+    }
+
+    fn lookup(&mut self, package: &str, name: &str) -> Result<Message, GeneratorError> {
+        (self.resolve)(package, name)
+            .ok_or_else(|| GeneratorError::UnknownType(format!("{}/{}", package, name)))
+    }
+
+    fn render(
+        &mut self,
+        package: &str,
+        datatype: &str,
+        message: &Message,
+    ) -> Result<Rendered, GeneratorError> {
+        let mut md5_lines = Vec::new();
+        let mut definition_lines = Vec::new();
+        let mut dependencies: Vec<String> = Vec::new();
+        let mut notes = Vec::new();
+
+        for constant in &message.constants {
+            let mapped = map_type(package, &constant.constant_type);
+            let line = format!(
+                "{} {}={}",
+                mapped.ros1_type,
+                constant.name,
+                constant_value_text(&constant.value)
+            );
+            md5_lines.push(line.clone());
+            definition_lines.push(line);
+        }
+
+        for field in &message.fields {
+            let mapped = map_type(package, &field.field_type);
+            if let Some(reason) = &mapped.reason {
+                notes.push(TypeMappingNote {
+                    datatype: datatype.to_string(),
+                    field: field.name.clone(),
+                    ros2_type: ros2_type_text(&field.field_type),
+                    ros1_type: mapped.ros1_type.clone(),
+                    reason: reason.clone(),
+                });
+            }
+
+            definition_lines.push(format!("{} {}", mapped.ros1_type, field.name));
+
+            match &mapped.message_type {
+                Some(dep) => {
+                    // genmsg replaces message types (and arrays of them) by their md5sum
+                    md5_lines.push(format!("{} {}", self.dependency_md5(dep)?, field.name));
+                    self.collect_dependency(dep, &mut dependencies)?;
+                }
+                None => md5_lines.push(format!("{} {}", mapped.ros1_type, field.name)),
+            }
+        }
+
+        Ok(Rendered {
+            md5_text: md5_lines.join("\n"),
+            own_definition: definition_lines.join("\n"),
+            dependencies,
+            notes,
+        })
+    }
+
+    /// Add `dep` and its transitive dependencies in depth-first order
+    fn collect_dependency(
+        &mut self,
+        dep: &str,
+        dependencies: &mut Vec<String>,
+    ) -> Result<(), GeneratorError> {
+        if dependencies.iter().any(|d| d == dep) {
+            return Ok(());
+        }
+        dependencies.push(dep.to_string());
+
+        if dep == "std_msgs/Header" {
+            return Ok(());
+        }
+        let (package, name) = split_datatype(dep);
+        let message = self.lookup(package, name)?;
+        for field in &message.fields {
+            if let Some(nested) = map_type(package, &field.field_type).message_type {
+                self.collect_dependency(&nested, dependencies)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Result of mapping a ROS 2 field type to ROS 1
+struct MappedType {
+    /// Type as written in a ROS 1 definition
+    ros1_type: String,
+    /// Referenced message (`pkg/Name`), if the base type is a message
+    message_type: Option<String>,
+    /// Why the ROS 1 type differs, if it does
+    reason: Option<String>,
+}
+
+fn map_type(current_package: &str, field_type: &FieldType) -> MappedType {
+    let plain = |ros1_type: &str| MappedType {
+        ros1_type: ros1_type.to_string(),
+        message_type: None,
+        reason: None,
+    };
+
+    match field_type {
+        FieldType::Primitive(PrimitiveType::Byte) => MappedType {
+            ros1_type: "uint8".to_string(),
+            message_type: None,
+            reason: Some("ROS 1 byte is signed; ROS 2 byte is an unsigned octet".to_string()),
+        },
+        FieldType::Primitive(PrimitiveType::Char) => MappedType {
+            ros1_type: "uint8".to_string(),
+            message_type: None,
+            reason: Some("ROS 2 char is a character type; ROS 1 char is uint8".to_string()),
+        },
+        FieldType::Primitive(primitive) => plain(primitive_name(*primitive)),
+        FieldType::String => plain("string"),
+        FieldType::BoundedString(_) => MappedType {
+            ros1_type: "string".to_string(),
+            message_type: None,
+            reason: Some("ROS 1 has no bounded strings; bound dropped".to_string()),
+        },
+        FieldType::WString | FieldType::BoundedWString(_) => MappedType {
+            ros1_type: "string".to_string(),
+            message_type: None,
+            reason: Some("ROS 1 has no wide strings; mapped to UTF-8 string".to_string()),
+        },
+        FieldType::Array { element_type, size } => {
+            let inner = map_type(current_package, element_type);
+            MappedType {
+                ros1_type: format!("{}[{}]", inner.ros1_type, size),
+                message_type: inner.message_type,
+                reason: inner.reason,
+            }
+        }
+        FieldType::Sequence { element_type } => {
+            let inner = map_type(current_package, element_type);
+            let reason = inner.reason.or_else(|| {
+                is_octet_type(element_type).then(|| {
+                    "uint8[] is bytes in ROS 2 Python but str in ROS 1 Python 2".to_string()
+                })
+            });
+            MappedType {
+                ros1_type: format!("{}[]", inner.ros1_type),
+                message_type: inner.message_type,
+                reason,
+            }
+        }
+        FieldType::BoundedSequence { element_type, .. } => {
+            let inner = map_type(current_package, element_type);
+            MappedType {
+                ros1_type: format!("{}[]", inner.ros1_type),
+                message_type: inner.message_type,
+                reason: Some(
+                    "ROS 1 has no bounded sequences; mapped to unbounded array".to_string(),
+                ),
+            }
+        }
+        FieldType::NamespacedType { package, name } => {
+            let package = package.as_deref().unwrap_or(current_package);
+            if let Some(builtin) = BuiltinType::from_name(package, name) {
+                let ros1_type = builtin.name().to_lowercase();
+                return MappedType {
+                    reason: Some(format!("{} maps to ROS 1 builtin {}", builtin, ros1_type)),
+                    ros1_type,
+                    message_type: None,
+                };
+            }
+            match (package, name.as_str()) {
+                ("std_msgs", "Header") => MappedType {
+                    ros1_type: "std_msgs/Header".to_string(),
+                    message_type: Some("std_msgs/Header".to_string()),
+                    reason: Some("ROS 1 Header has an extra uint32 seq field".to_string()),
+                },
+                _ => MappedType {
+                    ros1_type: format!("{}/{}", package, name),
+                    message_type: Some(format!("{}/{}", package, name)),
+                    reason: None,
+                },
+            }
+        }
+    }
+}
+
+fn is_octet_type(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Primitive(PrimitiveType::UInt8 | PrimitiveType::Byte)
+    )
+}
+
+fn primitive_name(primitive: PrimitiveType) -> &'static str {
+    match primitive {
+        PrimitiveType::Bool => "bool",
+        PrimitiveType::Byte => "byte",
+        PrimitiveType::Char => "char",
+        PrimitiveType::Int8 => "int8",
+        PrimitiveType::UInt8 => "uint8",
+        PrimitiveType::Int16 => "int16",
+        PrimitiveType::UInt16 => "uint16",
+        PrimitiveType::Int32 => "int32",
+        PrimitiveType::UInt32 => "uint32",
+        PrimitiveType::Int64 => "int64",
+        PrimitiveType::UInt64 => "uint64",
+        PrimitiveType::Float32 => "float32",
+        PrimitiveType::Float64 => "float64",
+    }
+}
+
+/// ROS 2 `.msg` spelling of a field type (for reports)
+fn ros2_type_text(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Primitive(primitive) => primitive_name(*primitive).to_string(),
+        FieldType::String => "string".to_string(),
+        FieldType::BoundedString(max) => format!("string<={}", max),
+        FieldType::WString => "wstring".to_string(),
+        FieldType::BoundedWString(max) => format!("wstring<={}", max),
+        FieldType::Array { element_type, size } => {
+            format!("{}[{}]", ros2_type_text(element_type), size)
+        }
+        FieldType::Sequence { element_type } => format!("{}[]", ros2_type_text(element_type)),
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => format!("{}[<={}]", ros2_type_text(element_type), max_size),
+        FieldType::NamespacedType { package, name } => match package {
+            Some(package) => format!("{}/{}", package, name),
+            None => name.clone(),
+        },
+    }
+}
+
+fn constant_value_text(value: &ConstantValue) -> String {
+    match value {
+        ConstantValue::Integer(v) => v.to_string(),
+        ConstantValue::UInteger(v) => v.to_string(),
+        ConstantValue::Float(v) => v.to_string(),
+        ConstantValue::String(v) | ConstantValue::WString(v) => v.clone(),
+        ConstantValue::Bool(v) => if *v { "True" } else { "False" }.to_string(),
+        ConstantValue::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(constant_value_text)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn split_datatype(datatype: &str) -> (&str, &str) {
+    datatype.split_once('/').unwrap_or(("", datatype))
+}
+
+fn md5_hex(text: &str) -> String {
+    format!("{:x}", Md5::digest(text.as_bytes()))
+}
+
+/// Render the generated `ros1.rs` module for a package
+///
+/// `messages` pairs each message's module name (snake_case) with its metadata.
+pub fn generate_ros1_module(package_name: &str, messages: &[(String, Ros1MessageInfo)]) -> String {
+    let mut out = String::new();
+    out.push_str("// ROS 1 compatibility metadata (md5sums and full definitions)\n");
+    out.push_str(&format!("// Package: {}\n\n", package_name));
+    out.push_str("pub mod msg {\n");
+    for (module_name, info) in messages {
+        out.push_str(&format!("    pub mod {} {{\n", module_name));
+        out.push_str(&format!(
+            "        pub const DATATYPE: &str = {:?};\n",
+            info.datatype
+        ));
+        out.push_str(&format!(
+            "        pub const MD5SUM: &str = {:?};\n",
+            info.md5sum
+        ));
+        out.push_str(&format!(
+            "        pub const MESSAGE_DEFINITION: &str = {:?};\n",
+            info.definition
+        ));
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render a plain-text report of fields whose ROS 1 type differs
+pub fn type_mapping_report(notes: &[TypeMappingNote]) -> String {
+    if notes.is_empty() {
+        return "All fields map to ROS 1 without changes.\n".to_string();
+    }
+
+    let mut out = String::new();
+    for note in notes {
+        out.push_str(&format!(
+            "{}.{}: {} -> {} ({})\n",
+            note.datatype, note.field, note.ros2_type, note.ros1_type, note.reason
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_parser::parse_message;
+
+    fn resolver() -> Ros1Resolver<impl FnMut(&str, &str) -> Option<Message>> {
+        Ros1Resolver::new(|package, name| match (package, name) {
+            ("geometry_msgs", "Point") => parse_message("float64 x\nfloat64 y\nfloat64 z\n").ok(),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_md5sum_matches_ros1() {
+        let mut resolver = resolver();
+
+        let string_msg = parse_message("string data\n").unwrap();
+        let info = resolver
+            .message_info("std_msgs", "String", &string_msg)
+            .unwrap();
+        assert_eq!(info.md5sum, "992ce8a1687cec8c8bd883ec73ca41d1");
+        assert_eq!(info.datatype, "std_msgs/String");
+
+        let point = parse_message("float64 x\nfloat64 y\nfloat64 z\n").unwrap();
+        let info = resolver
+            .message_info("geometry_msgs", "Point", &point)
+            .unwrap();
+        assert_eq!(info.md5sum, "4a842b65f413084dc2b10fb484ea7f17");
+    }
+
+    #[test]
+    fn test_md5sum_with_header_and_nested_message() {
+        let mut resolver = resolver();
+        let stamped = parse_message("std_msgs/Header header\ngeometry_msgs/Point point\n").unwrap();
+        let info = resolver
+            .message_info("geometry_msgs", "PointStamped", &stamped)
+            .unwrap();
+
+        assert_eq!(info.md5sum, "c63aecb41bfdfd6b7e1fac37c7cbe7bf");
+        assert!(info
+            .definition
+            .contains("MSG: std_msgs/Header\nuint32 seq\ntime stamp\nstring frame_id"));
+        assert!(info
+            .definition
+            .contains("MSG: geometry_msgs/Point\nfloat64 x"));
+        assert_eq!(info.mapping_notes.len(), 1);
+        assert_eq!(info.mapping_notes[0].field, "header");
+    }
+
+    #[test]
+    fn test_type_mapping_notes() {
+        let mut resolver = resolver();
+        let msg = parse_message(
+            "builtin_interfaces/Time stamp\nuint8[] data\nstring<=10 name\nfloat64 value\n",
+        )
+        .unwrap();
+        let info = resolver.message_info("my_msgs", "Sample", &msg).unwrap();
+
+        let fields: Vec<_> = info
+            .mapping_notes
+            .iter()
+            .map(|n| n.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["stamp", "data", "name"]);
+        assert_eq!(info.mapping_notes[0].ros1_type, "time");
+        assert!(info
+            .definition
+            .starts_with("time stamp\nuint8[] data\nstring name\n"));
+        assert!(type_mapping_report(&info.mapping_notes).contains("my_msgs/Sample.stamp"));
+    }
+
+    #[test]
+    fn test_unresolved_dependency_fails() {
+        let mut resolver = resolver();
+        let msg = parse_message("other_msgs/Missing value\n").unwrap();
+        assert!(resolver.message_info("my_msgs", "Broken", &msg).is_err());
+    }
+
+    #[test]
+    fn test_generate_ros1_module() {
+        let mut resolver = resolver();
+        let msg = parse_message("string data\n").unwrap();
+        let info = resolver.message_info("std_msgs", "String", &msg).unwrap();
+
+        let module = generate_ros1_module("std_msgs", &[("string".to_string(), info)]);
+        assert!(module.contains("pub mod string {"));
+        assert!(module.contains("pub const MD5SUM: &str = \"992ce8a1687cec8c8bd883ec73ca41d1\";"));
+        assert!(module.contains("pub const MESSAGE_DEFINITION: &str = \"string data\\n\";"));
+    }
+}
@@ -14,13 +14,18 @@ pub mod filters {
 pub struct CargoTomlTemplate<'a> {
     pub package_name: &'a str,
     pub dependencies: &'a [String],
-    pub needs_big_array: bool,
 }
 
 #[derive(Template)]
 #[template(path = "build.rs.jinja", escape = "none")]
 pub struct BuildRsTemplate;
 
+/// `serde_array` module of a generated crate: serde for arrays of more than 32
+/// elements, used by their fields with `#[serde(with = "crate::serde_array")]`
+#[derive(Template)]
+#[template(path = "serde_array.rs.jinja", escape = "none")]
+pub struct SerdeArrayTemplate;
+
 #[derive(Template)]
 #[template(path = "lib.rs.jinja", escape = "none")]
 pub struct LibRsTemplate {
@@ -34,8 +39,29 @@ pub struct LibRsTemplate {
 pub struct MessageRmwTemplate<'a> {
     pub package_name: &'a str,
     pub message_name: &'a str,
+    /// Doc comment lines from the message comment
+    pub docs: Vec<String>,
     pub fields: Vec<RmwField>,
     pub constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// Also bind the `rosidl_typesupport_introspection_c` type support
+    pub introspection: bool,
+    /// Fields as the introspection should describe them, checked in a test
+    pub introspection_members: Vec<IntrospectionMember>,
+}
+
+/// Field of an RMW struct as its introspection type support describes it
+pub struct IntrospectionMember {
+    pub name: String,
+    pub ros_name: String,
+    /// `FieldKind` variant of the field, or of its elements
+    pub kind: &'static str,
+    pub is_array: bool,
+    /// Size of an array, bound of a bounded sequence, 0 otherwise
+    pub array_size: usize,
+    pub is_upper_bound: bool,
+    pub string_upper_bound: usize,
 }
 
 #[derive(Template)]
@@ -43,29 +69,237 @@ pub struct MessageRmwTemplate<'a> {
 pub struct MessageIdiomaticTemplate<'a> {
     pub package_name: &'a str,
     pub message_name: &'a str,
+    pub rclrs_compat: bool,
+    /// Doc comment lines from the message comment
+    pub docs: Vec<String>,
     pub fields: Vec<IdiomaticField>,
     pub constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// Extra derives of the struct, the ones its fields support
+    pub derives: Vec<String>,
+    /// Extra attributes of the struct, such as `#[non_exhaustive]`
+    pub attributes: &'a [String],
+    /// serde `rename_all` rule of the fields
+    pub serde_rename_all: Option<&'a str>,
+}
+
+/// Idiomatic message for types-only bindings: no RMW layer, CDR encoded in Rust
+#[derive(Template)]
+#[template(path = "message_types_only.rs.jinja", escape = "none")]
+pub struct MessageTypesOnlyTemplate<'a> {
+    pub package_name: &'a str,
+    pub message_name: &'a str,
+    /// Doc comment lines from the message comment
+    pub docs: Vec<String>,
+    pub fields: Vec<TypesOnlyField>,
+    pub constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// serde `rename_all` rule of the fields
+    pub serde_rename_all: Option<&'a str>,
+}
+
+/// Message of embedded (`no_std`) bindings: fixed-capacity strings and sequences
+#[derive(Template)]
+#[template(path = "message_embedded.rs.jinja", escape = "none")]
+pub struct MessageEmbeddedTemplate<'a> {
+    pub package_name: &'a str,
+    pub message_name: &'a str,
+    /// Doc comment lines from the message comment
+    pub docs: Vec<String>,
+    pub fields: Vec<TypesOnlyField>,
+    pub constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// serde `rename_all` rule of the fields
+    pub serde_rename_all: Option<&'a str>,
+}
+
+/// Compile-time checks of the RMW structs against their C layout, appended to
+/// the RMW layer
+#[derive(Template)]
+#[template(path = "rmw_layout.rs.jinja", escape = "none")]
+pub struct RmwLayoutTemplate {
+    pub structs: Vec<LayoutCheck>,
+}
+
+pub struct LayoutCheck {
+    /// Path of the struct from the RMW module, such as `request::AddTwoIntsRequest`
+    pub path: String,
+    pub fields: Vec<LayoutField>,
+}
+
+/// Size and alignment of a field in the C struct, as const expressions
+pub struct LayoutField {
+    pub name: String,
+    pub size: String,
+    pub align: String,
+}
+
+/// `CdrSerialize` and `CdrDeserialize` impls of the idiomatic structs
+#[derive(Template)]
+#[template(path = "cdr.rs.jinja", escape = "none")]
+pub struct CdrTemplate {
+    pub structs: Vec<CdrStruct>,
+}
+
+pub struct CdrStruct {
+    /// Path of the struct from the idiomatic module, such as `request::AddTwoIntsRequest`
+    pub path: String,
+    /// Field names, in the order they are encoded
+    pub fields: Vec<String>,
+}
+
+/// `set_<field>` and `check_bounds` methods of the idiomatic structs with
+/// bounded fields
+#[derive(Template)]
+#[template(path = "bound_setters.rs.jinja", escape = "none")]
+pub struct BoundSettersTemplate {
+    pub structs: Vec<BoundedStruct>,
+}
+
+pub struct BoundedStruct {
+    /// Path of the struct from the idiomatic module, such as `request::AddTwoIntsRequest`
+    pub path: String,
+    pub fields: Vec<BoundedField>,
+}
+
+pub struct BoundedField {
+    pub name: String,
+    pub ros_name: String,
+    /// Type as written in the interface, such as `string<=8[<=3]`
+    pub ros_type: String,
+    pub rust_type: String,
+    /// Statements checking the `value` argument of the setter
+    pub value_check: String,
+    /// Statements checking the field of `self`
+    pub self_check: String,
 }
 
 pub struct RmwField {
     pub name: String,
     pub rust_type: String,
+    /// Doc comment lines from the field's comments and annotations
+    pub docs: Vec<String>,
     pub default_value: String,
 }
 
 pub struct IdiomaticField {
     pub name: String,
+    /// serde name, when it differs from the Rust name
+    pub serde_rename: Option<String>,
+    /// Field name of the interface definition, before keyword escaping
+    pub ros_name: String,
     pub rust_type: String,
+    /// Doc comment lines from the field's comments and annotations
+    pub docs: Vec<String>,
+    /// `#[deprecated]` attribute of a deprecated field
+    pub deprecated: Option<String>,
     pub default_value: String,
-    pub is_sequence: bool,
-    pub is_primitive: bool,
-    pub is_primitive_sequence: bool,
-    pub is_array: bool,
     pub is_large_array: bool,
+    /// Idiomatic -> RMW conversion of `idiomatic.<field>`
+    pub to_rmw: String,
+    /// RMW -> idiomatic conversion of `rmw.<field>`
+    pub from_rmw: String,
+    /// Arbitrary value of the field (`arbitrary` feature)
+    pub arbitrary_value: String,
+    /// On a reference cycle of the package's messages
+    pub cyclic: bool,
+}
+
+/// Field of a types-only or embedded message
+pub struct TypesOnlyField {
+    pub name: String,
+    /// serde name, when it differs from the Rust name
+    pub serde_rename: Option<String>,
+    pub rust_type: String,
+    /// Doc comment lines from the field's comments and annotations
+    pub docs: Vec<String>,
+    /// `#[deprecated]` attribute of a deprecated field
+    pub deprecated: Option<String>,
+    /// Expression for the field's value in `Default::default()`
+    pub default_value: String,
+    pub is_large_array: bool,
+    /// Arbitrary value of the field (`arbitrary` feature, not in embedded bindings)
+    pub arbitrary_value: String,
+    /// On a reference cycle of the package's messages
+    pub cyclic: bool,
+}
+
+/// Whether any of the fields is deprecated; the generated impls then
+/// `#![allow(deprecated)]` for their own uses of it
+fn any_deprecated<'a>(fields: impl IntoIterator<Item = &'a Option<String>>) -> bool {
+    fields.into_iter().any(Option::is_some)
+}
+
+impl MessageIdiomaticTemplate<'_> {
+    fn has_deprecated_fields(&self) -> bool {
+        any_deprecated(self.fields.iter().map(|f| &f.deprecated))
+    }
+
+    fn has_cyclic_fields(&self) -> bool {
+        self.fields.iter().any(|f| f.cyclic)
+    }
+}
+
+impl MessageTypesOnlyTemplate<'_> {
+    fn has_deprecated_fields(&self) -> bool {
+        any_deprecated(self.fields.iter().map(|f| &f.deprecated))
+    }
+
+    fn has_cyclic_fields(&self) -> bool {
+        self.fields.iter().any(|f| f.cyclic)
+    }
+}
+
+impl MessageEmbeddedTemplate<'_> {
+    fn has_deprecated_fields(&self) -> bool {
+        any_deprecated(self.fields.iter().map(|f| &f.deprecated))
+    }
+}
+
+/// Conversions between a `builtin_interfaces` message and its `std::time` type,
+/// appended to the idiomatic message
+#[derive(Template)]
+#[template(path = "message_builtin.rs.jinja", escape = "none")]
+pub struct MessageBuiltinTemplate<'a> {
+    pub message_name: &'a str,
+    /// `Duration` rather than `Time`
+    pub is_duration: bool,
+}
+
+/// Enums of the constant groups of a message, appended to its idiomatic module
+#[derive(Template)]
+#[template(path = "message_constant_enums.rs.jinja", escape = "none")]
+pub struct MessageConstantEnumsTemplate {
+    pub enums: Vec<ConstantEnum>,
+}
+
+/// Integer constants sharing a name prefix, such as `STATUS_ACCEPTED` and
+/// `STATUS_EXECUTING`, generated as an enum
+pub struct ConstantEnum {
+    /// Enum name, the UpperCamelCase prefix (`Status`)
+    pub name: String,
+    /// Shared prefix of the constant names, without the trailing `_`
+    pub prefix: String,
+    pub rust_type: String,
+    pub variants: Vec<ConstantVariant>,
+}
+
+pub struct ConstantVariant {
+    /// UpperCamelCase rest of the constant name (`Accepted`)
+    pub name: String,
+    /// Name of the constant the variant converts to and from
+    pub constant: String,
+    /// Doc comment lines from the constant's comments
+    pub docs: Vec<String>,
 }
 
 pub struct MessageConstant {
     pub name: String,
+    /// Doc comment lines from the constant's comments
+    pub docs: Vec<String>,
     pub rust_type: String,
     pub value: String,
 }
@@ -75,10 +309,17 @@ pub struct MessageConstant {
 pub struct ServiceRmwTemplate<'a> {
     pub package_name: &'a str,
     pub service_name: &'a str,
+    /// Doc comment lines from the request section comment
+    pub request_docs: Vec<String>,
     pub request_fields: Vec<RmwField>,
     pub request_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the response section comment
+    pub response_docs: Vec<String>,
     pub response_fields: Vec<RmwField>,
     pub response_constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    pub service_events: bool,
 }
 
 #[derive(Template)]
@@ -86,10 +327,25 @@ pub struct ServiceRmwTemplate<'a> {
 pub struct ServiceIdiomaticTemplate<'a> {
     pub package_name: &'a str,
     pub service_name: &'a str,
+    pub rclrs_compat: bool,
+    /// Doc comment lines from the request section comment
+    pub request_docs: Vec<String>,
     pub request_fields: Vec<IdiomaticField>,
     pub request_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the response section comment
+    pub response_docs: Vec<String>,
     pub response_fields: Vec<IdiomaticField>,
     pub response_constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    pub service_events: bool,
+    /// Extra derives of the request and response structs, the ones their fields support
+    pub request_derives: Vec<String>,
+    pub response_derives: Vec<String>,
+    /// Extra attributes of the request and response structs
+    pub attributes: &'a [String],
+    /// serde `rename_all` rule of the fields
+    pub serde_rename_all: Option<&'a str>,
 }
 
 #[derive(Template)]
@@ -97,12 +353,35 @@ pub struct ServiceIdiomaticTemplate<'a> {
 pub struct ActionRmwTemplate<'a> {
     pub package_name: &'a str,
     pub action_name: &'a str,
+    pub rclrs_compat: bool,
+    /// Doc comment lines from the goal section comment
+    pub goal_docs: Vec<String>,
     pub goal_fields: Vec<RmwField>,
     pub goal_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the result section comment
+    pub result_docs: Vec<String>,
     pub result_fields: Vec<RmwField>,
     pub result_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the feedback section comment
+    pub feedback_docs: Vec<String>,
     pub feedback_fields: Vec<RmwField>,
     pub feedback_constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// Messages of the implicit services and feedback topic
+    pub implicit_messages: Vec<ImplicitMessage>,
+}
+
+/// Message an action defines implicitly, such as `<Action>_SendGoal_Request`
+pub struct ImplicitMessage {
+    /// Module of the message in the RMW layer
+    pub module: &'static str,
+    /// Type name after the action name, such as `SendGoalRequest`
+    pub type_suffix: &'static str,
+    /// C type name after the action name, such as `SendGoal_Request`
+    pub c_suffix: &'static str,
+    pub docs: Vec<String>,
+    pub fields: Vec<RmwField>,
 }
 
 #[derive(Template)]
@@ -110,10 +389,50 @@ pub struct ActionRmwTemplate<'a> {
 pub struct ActionIdiomaticTemplate<'a> {
     pub package_name: &'a str,
     pub action_name: &'a str,
+    pub rclrs_compat: bool,
+    /// Doc comment lines from the goal section comment
+    pub goal_docs: Vec<String>,
     pub goal_fields: Vec<IdiomaticField>,
     pub goal_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the result section comment
+    pub result_docs: Vec<String>,
     pub result_fields: Vec<IdiomaticField>,
     pub result_constants: Vec<MessageConstant>,
+    /// Doc comment lines from the feedback section comment
+    pub feedback_docs: Vec<String>,
     pub feedback_fields: Vec<IdiomaticField>,
     pub feedback_constants: Vec<MessageConstant>,
+    /// Also alias the constants at module level, next to the struct
+    pub module_constants: bool,
+    /// Extra derives of the goal, result and feedback structs, the ones their fields support
+    pub goal_derives: Vec<String>,
+    pub result_derives: Vec<String>,
+    pub feedback_derives: Vec<String>,
+    /// Extra attributes of the goal, result and feedback structs
+    pub attributes: &'a [String],
+    /// serde `rename_all` rule of the fields
+    pub serde_rename_all: Option<&'a str>,
+}
+
+impl ServiceIdiomaticTemplate<'_> {
+    fn has_deprecated_fields(&self) -> bool {
+        any_deprecated(
+            self.request_fields
+                .iter()
+                .chain(&self.response_fields)
+                .map(|f| &f.deprecated),
+        )
+    }
+}
+
+impl ActionIdiomaticTemplate<'_> {
+    fn has_deprecated_fields(&self) -> bool {
+        any_deprecated(
+            self.goal_fields
+                .iter()
+                .chain(&self.result_fields)
+                .chain(&self.feedback_fields)
+                .map(|f| &f.deprecated),
+        )
+    }
 }
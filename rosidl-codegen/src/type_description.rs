@@ -0,0 +1,449 @@
+//! ROS 2 type descriptions and type hashes (REP-2011)
+//!
+//! Since Iron every interface has a type hash, which middlewares compare to tell
+//! apart incompatible versions of a type with the same name. The hash is the
+//! SHA-256 of the JSON form of the `type_description_interfaces/TypeDescription`
+//! of the type, without default values: its own fields plus every type it refers
+//! to, sorted by name. It is written as `RIHS01_<hex>`.
+//!
+//! Descriptions follow the IDL form of `.msg` files: `char` is a `uint8` and empty
+//! messages have a `structure_needs_at_least_one_member` field.
+
+use crate::generator::GeneratorError;
+use rosidl_parser::{FieldType, Message, PrimitiveType};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Type id offset of arrays, bounded and unbounded sequences in `FieldType`
+const ARRAY_OFFSET: u8 = 48;
+const BOUNDED_SEQUENCE_OFFSET: u8 = 96;
+const UNBOUNDED_SEQUENCE_OFFSET: u8 = 144;
+
+/// Field of a type description (`type_description_interfaces/Field`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDescription {
+    pub name: String,
+    /// Id of `type_description_interfaces/FieldType`, such as 17 for `string`
+    pub type_id: u8,
+    /// Size of an array or bound of a bounded sequence, 0 otherwise
+    pub capacity: u64,
+    /// Bound of a bounded string, 0 otherwise
+    pub string_capacity: u64,
+    /// `package/msg/Name` of nested types, empty otherwise
+    pub nested_type_name: String,
+    /// Default value as written in the definition, empty without one
+    pub default_value: String,
+}
+
+/// Fields of one type (`type_description_interfaces/IndividualTypeDescription`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndividualTypeDescription {
+    /// Name such as `std_msgs/msg/Header`
+    pub type_name: String,
+    pub fields: Vec<FieldDescription>,
+}
+
+/// Description and hash of a message type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDescriptionInfo {
+    pub description: IndividualTypeDescription,
+    /// Descriptions of the types referenced directly or transitively, sorted by name
+    pub referenced: Vec<IndividualTypeDescription>,
+    /// `RIHS01_` followed by the hex SHA-256
+    pub type_hash: String,
+}
+
+/// Computes type descriptions, resolving referenced messages through a callback
+///
+/// The resolver returns the parsed definition of `package/Name`, or `None` if it
+/// is unknown. Results are memoized per type.
+pub struct TypeDescriptionResolver<F>
+where
+    F: FnMut(&str, &str) -> Option<Message>,
+{
+    resolve: F,
+    cache: HashMap<String, TypeDescriptionInfo>,
+}
+
+impl<F> TypeDescriptionResolver<F>
+where
+    F: FnMut(&str, &str) -> Option<Message>,
+{
+    pub fn new(resolve: F) -> Self {
+        Self {
+            resolve,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Compute the description and hash of `package/name` with the given parsed
+    /// definition
+    pub fn message_info(
+        &mut self,
+        package: &str,
+        name: &str,
+        message: &Message,
+    ) -> Result<TypeDescriptionInfo, GeneratorError> {
+        let type_name = format!("{}/msg/{}", package, name);
+        if let Some(info) = self.cache.get(&type_name) {
+            return Ok(info.clone());
+        }
+
+        let description = individual_description(package, &type_name, message);
+        // Walked breadth-first rather than through the descriptions of the nested
+        // types, as messages of a package may nest each other. Each type keeps the
+        // field of this message it is reached through, to report unknown types.
+        let mut referenced = BTreeMap::new();
+        let mut pending: VecDeque<(String, &str)> = description
+            .fields
+            .iter()
+            .filter(|field| !field.nested_type_name.is_empty())
+            .map(|field| (field.nested_type_name.clone(), field.name.as_str()))
+            .collect();
+        while let Some((nested_type_name, field)) = pending.pop_front() {
+            if nested_type_name == type_name || referenced.contains_key(&nested_type_name) {
+                continue;
+            }
+            let (dep_package, dep_name) = split_type_name(&nested_type_name);
+            let dep_message = (self.resolve)(dep_package, dep_name).ok_or_else(|| {
+                GeneratorError::UnknownType(format!("{}/{}", dep_package, dep_name))
+                    .in_field(package, name, field)
+            })?;
+            let dep = individual_description(dep_package, &nested_type_name, &dep_message);
+            pending.extend(nested_type_names(&dep).map(|nested| (nested, field)));
+            referenced.insert(nested_type_name, dep);
+        }
+
+        let referenced: Vec<_> = referenced.into_values().collect();
+        let info = TypeDescriptionInfo {
+            type_hash: type_hash(&description, &referenced),
+            description,
+            referenced,
+        };
+        self.cache.insert(type_name, info.clone());
+        Ok(info)
+    }
+}
+
+/// Types of the nested message fields of a description
+fn nested_type_names(description: &IndividualTypeDescription) -> impl Iterator<Item = String> + '_ {
+    description
+        .fields
+        .iter()
+        .filter(|field| !field.nested_type_name.is_empty())
+        .map(|field| field.nested_type_name.clone())
+}
+
+/// Description of the fields of `message`, named `type_name`
+fn individual_description(
+    package: &str,
+    type_name: &str,
+    message: &Message,
+) -> IndividualTypeDescription {
+    let mut fields: Vec<_> = message
+        .fields
+        .iter()
+        .map(|field| {
+            let mut description = field_description(package, &field.field_type);
+            description.name = field.name.clone();
+            if let Some(value) = &field.default_value {
+                description.default_value = value.to_string();
+            }
+            description
+        })
+        .collect();
+    if fields.is_empty() {
+        fields.push(FieldDescription {
+            name: "structure_needs_at_least_one_member".to_string(),
+            ..field_description(package, &FieldType::Primitive(PrimitiveType::UInt8))
+        });
+    }
+    IndividualTypeDescription {
+        type_name: type_name.to_string(),
+        fields,
+    }
+}
+
+/// Unnamed field of `field_type`, whose unqualified message types are in `package`
+fn field_description(package: &str, field_type: &FieldType) -> FieldDescription {
+    let field = |type_id, string_capacity, nested_type_name| FieldDescription {
+        name: String::new(),
+        type_id,
+        capacity: 0,
+        string_capacity,
+        nested_type_name,
+        default_value: String::new(),
+    };
+    let sequence = |element_type: &FieldType, offset, capacity| {
+        let element = field_description(package, element_type);
+        FieldDescription {
+            type_id: element.type_id + offset,
+            capacity,
+            ..element
+        }
+    };
+    match field_type {
+        FieldType::Primitive(primitive) => field(primitive_type_id(*primitive), 0, String::new()),
+        FieldType::String => field(17, 0, String::new()),
+        FieldType::WString => field(18, 0, String::new()),
+        FieldType::BoundedString(bound) => field(21, *bound as u64, String::new()),
+        FieldType::BoundedWString(bound) => field(22, *bound as u64, String::new()),
+        FieldType::Array { element_type, size } => {
+            sequence(element_type, ARRAY_OFFSET, *size as u64)
+        }
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => sequence(element_type, BOUNDED_SEQUENCE_OFFSET, *max_size as u64),
+        FieldType::Sequence { element_type } => {
+            sequence(element_type, UNBOUNDED_SEQUENCE_OFFSET, 0)
+        }
+        FieldType::NamespacedType {
+            package: type_package,
+            name,
+        } => field(
+            1,
+            0,
+            format!(
+                "{}/msg/{}",
+                type_package.as_deref().unwrap_or(package),
+                name
+            ),
+        ),
+    }
+}
+
+fn primitive_type_id(primitive: PrimitiveType) -> u8 {
+    match primitive {
+        PrimitiveType::Int8 => 2,
+        // `char` is a `uint8` in IDL
+        PrimitiveType::UInt8 | PrimitiveType::Char => 3,
+        PrimitiveType::Int16 => 4,
+        PrimitiveType::UInt16 => 5,
+        PrimitiveType::Int32 => 6,
+        PrimitiveType::UInt32 => 7,
+        PrimitiveType::Int64 => 8,
+        PrimitiveType::UInt64 => 9,
+        PrimitiveType::Float32 => 10,
+        PrimitiveType::Float64 => 11,
+        PrimitiveType::Bool => 15,
+        PrimitiveType::Byte => 16,
+    }
+}
+
+/// `RIHS01_` hash of a description and its sorted referenced descriptions
+///
+/// The JSON is laid out like Python's `json.dumps` with `", "` and `": "`
+/// separators, which rosidl hashes.
+fn type_hash(
+    description: &IndividualTypeDescription,
+    referenced: &[IndividualTypeDescription],
+) -> String {
+    let referenced: Vec<_> = referenced.iter().map(description_json).collect();
+    let json = format!(
+        "{{\"type_description\": {}, \"referenced_type_descriptions\": [{}]}}",
+        description_json(description),
+        referenced.join(", ")
+    );
+    let digest = Sha256::digest(json.as_bytes());
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("RIHS01_{}", hex)
+}
+
+/// Hashed JSON form of a description, without default values
+fn description_json(description: &IndividualTypeDescription) -> String {
+    let fields: Vec<_> = description
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{{\"name\": \"{}\", \"type\": {{\"type_id\": {}, \"capacity\": {}, \
+                 \"string_capacity\": {}, \"nested_type_name\": \"{}\"}}}}",
+                field.name,
+                field.type_id,
+                field.capacity,
+                field.string_capacity,
+                field.nested_type_name
+            )
+        })
+        .collect();
+    format!(
+        "{{\"type_name\": \"{}\", \"fields\": [{}]}}",
+        description.type_name,
+        fields.join(", ")
+    )
+}
+
+/// Split `package/msg/Name` into package and name
+fn split_type_name(type_name: &str) -> (&str, &str) {
+    let (package, rest) = type_name.split_once('/').unwrap_or(("", type_name));
+    (package, rest.rsplit('/').next().unwrap_or(rest))
+}
+
+/// Render `src/type_description.rs`: `TYPE_NAME` and `TYPE_HASH` per message, and
+/// its `TYPE_DESCRIPTION` and `REFERENCED_TYPE_DESCRIPTIONS` unless `rclrs_compat`
+/// (the upstream runtime has no type description structs)
+pub fn generate_type_description_module(
+    package_name: &str,
+    messages: &[(String, TypeDescriptionInfo)],
+    rclrs_compat: bool,
+) -> String {
+    let mut out = String::new();
+    out.push_str("// ROS 2 type descriptions and type hashes (REP-2011)\n");
+    out.push_str(&format!("// Package: {}\n\n", package_name));
+    out.push_str("pub mod msg {\n");
+    for (module_name, info) in messages {
+        out.push_str(&format!("    pub mod {} {{\n", module_name));
+        out.push_str(&format!(
+            "        pub const TYPE_NAME: &str = {:?};\n",
+            info.description.type_name
+        ));
+        out.push_str(&format!(
+            "        pub const TYPE_HASH: &str = {:?};\n",
+            info.type_hash
+        ));
+        if !rclrs_compat {
+            out.push_str(&format!(
+                "        pub const TYPE_DESCRIPTION: rosidl_runtime_rs::type_description::TypeDescription = {};\n",
+                description_rust(&info.description)
+            ));
+            let referenced: Vec<_> = info.referenced.iter().map(description_rust).collect();
+            out.push_str(&format!(
+                "        pub const REFERENCED_TYPE_DESCRIPTIONS: &[rosidl_runtime_rs::type_description::TypeDescription] = &[{}];\n",
+                referenced.join(", ")
+            ));
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Const expression of a `rosidl_runtime_rs::type_description::TypeDescription`
+fn description_rust(description: &IndividualTypeDescription) -> String {
+    let fields: Vec<_> = description
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "rosidl_runtime_rs::type_description::Field {{ name: {:?}, \
+                 field_type: rosidl_runtime_rs::type_description::FieldType {{ type_id: {}, \
+                 capacity: {}, string_capacity: {}, nested_type_name: {:?} }}, \
+                 default_value: {:?} }}",
+                field.name,
+                field.type_id,
+                field.capacity,
+                field.string_capacity,
+                field.nested_type_name,
+                field.default_value
+            )
+        })
+        .collect();
+    format!(
+        "rosidl_runtime_rs::type_description::TypeDescription {{ type_name: {:?}, fields: &[{}] }}",
+        description.type_name,
+        fields.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosidl_parser::parse_message;
+
+    #[test]
+    fn test_type_hash() {
+        let mut resolver =
+            TypeDescriptionResolver::new(|package: &str, name: &str| match (package, name) {
+                ("builtin_interfaces", "Time") => {
+                    Some(parse_message("int32 sec\nuint32 nanosec\n").unwrap())
+                }
+                _ => None,
+            });
+
+        // Hashes of the ROS 2 Jazzy interfaces
+        let header = parse_message("builtin_interfaces/Time stamp\nstring frame_id\n").unwrap();
+        let info = resolver
+            .message_info("std_msgs", "Header", &header)
+            .unwrap();
+        assert_eq!(
+            info.type_hash,
+            "RIHS01_f49fb3ae2cf070f793645ff749683ac6b06203e41c891e17701b1cb597ce6a01"
+        );
+        assert_eq!(info.referenced.len(), 1);
+        assert_eq!(info.referenced[0].type_name, "builtin_interfaces/msg/Time");
+        assert_eq!(
+            info.description.fields[0].nested_type_name,
+            "builtin_interfaces/msg/Time"
+        );
+
+        let stamped = parse_message("std_msgs/Header header\nint8[<=3] data\n").unwrap();
+        let err = resolver
+            .message_info("test_msgs", "Stamped", &stamped)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed generating test_msgs/Stamped field `header` (unknown type std_msgs/Header)"
+        );
+
+        let bounded = parse_message("string<=8[4] names [\"a\", \"b\", \"c\", \"d\"]\n").unwrap();
+        let info = resolver
+            .message_info("test_msgs", "Names", &bounded)
+            .unwrap();
+        let field = &info.description.fields[0];
+        assert_eq!(
+            (field.type_id, field.capacity, field.string_capacity),
+            (21 + ARRAY_OFFSET, 4, 8)
+        );
+        assert_eq!(field.default_value, "[\"a\", \"b\", \"c\", \"d\"]");
+
+        let empty = resolver
+            .message_info("std_msgs", "Empty", &parse_message("").unwrap())
+            .unwrap();
+        assert_eq!(
+            empty.description.fields[0].name,
+            "structure_needs_at_least_one_member"
+        );
+
+        // Messages nesting each other reference one another
+        let mut resolver = TypeDescriptionResolver::new(|_: &str, name: &str| match name {
+            "Node" => Some(parse_message("Edge[] edges\n").unwrap()),
+            "Edge" => Some(parse_message("Node target\n").unwrap()),
+            _ => None,
+        });
+        let node = parse_message("Edge[] edges\n").unwrap();
+        let info = resolver.message_info("tree_msgs", "Node", &node).unwrap();
+        let referenced: Vec<_> = info.referenced.iter().map(|d| &d.type_name).collect();
+        assert_eq!(referenced, ["tree_msgs/msg/Edge"]);
+    }
+
+    #[test]
+    fn test_generate_type_description_module() {
+        let mut resolver = TypeDescriptionResolver::new(|_: &str, _: &str| None);
+        let info = resolver
+            .message_info(
+                "test_msgs",
+                "Count",
+                &parse_message("int32 count 1\n").unwrap(),
+            )
+            .unwrap();
+        let module = generate_type_description_module(
+            "test_msgs",
+            &[("count".to_string(), info.clone())],
+            false,
+        );
+        assert!(module.contains("pub const TYPE_NAME: &str = \"test_msgs/msg/Count\";"));
+        assert!(module.contains(&format!(
+            "pub const TYPE_HASH: &str = {:?};",
+            info.type_hash
+        )));
+        assert!(module.contains("name: \"count\", field_type: rosidl_runtime_rs::type_description::FieldType { type_id: 6,"));
+        assert!(module.contains("default_value: \"1\""));
+        assert!(module.contains("REFERENCED_TYPE_DESCRIPTIONS: &[rosidl_runtime_rs::type_description::TypeDescription] = &[];"));
+
+        let module =
+            generate_type_description_module("test_msgs", &[("count".to_string(), info)], true);
+        assert!(module.contains("TYPE_HASH"));
+        assert!(!module.contains("TYPE_DESCRIPTION"));
+    }
+}
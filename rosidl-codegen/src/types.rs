@@ -1,5 +1,6 @@
+use crate::generator::EmbeddedCapacity;
 use rosidl_parser::ast::ConstantValue;
-use rosidl_parser::FieldType;
+use rosidl_parser::{Annotation, Field, FieldType, PrimitiveType};
 
 /// Check if a field type is a sequence (unbounded or bounded)
 pub fn is_sequence_type(field_type: &FieldType) -> bool {
@@ -29,15 +30,144 @@ pub fn is_array_type(field_type: &FieldType) -> bool {
     matches!(field_type, FieldType::Array { .. })
 }
 
-/// Check if a field type is a large array (> 32 elements, needs big_array for serde)
+/// Check if a field type is a wide string, or an array or sequence of them
+pub fn is_wstring_type(field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::WString | FieldType::BoundedWString(_) => true,
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => is_wstring_type(element_type),
+        _ => false,
+    }
+}
+
+/// Check if a field type has a bound: a bounded string or sequence, or an array
+/// or sequence of bounded strings
+pub fn is_bounded_type(field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::BoundedString(_)
+        | FieldType::BoundedWString(_)
+        | FieldType::BoundedSequence { .. } => true,
+        FieldType::Array { element_type, .. } | FieldType::Sequence { element_type } => {
+            is_bounded_type(element_type)
+        }
+        _ => false,
+    }
+}
+
+/// Check if a field type is a large array (> 32 elements, beyond serde's array impls)
 pub fn is_large_array(field_type: &FieldType) -> bool {
     matches!(field_type, FieldType::Array { size, .. } if *size > 32)
 }
 
+/// Statement checking the bounds of `value`, an idiomatic value of `field_type`;
+/// `None` if the type has no bound
+///
+/// String bounds count bytes, wide string bounds UTF-16 code units and sequence
+/// bounds elements, as the C structs do. The elements of arrays and sequences of
+/// bounded strings are checked one by one.
+pub fn bound_check(field_type: &FieldType, field_name: &str, value: &str) -> Option<String> {
+    let check = |len: String, bound: &usize| {
+        format!(
+            "crate::rosidl_runtime_rs::bounded::BoundExceeded::check({:?}, {}, {})?;",
+            field_name, len, bound
+        )
+    };
+    let element_check = |element_type: &FieldType| {
+        bound_check(element_type, field_name, "element")
+            .map(|check| format!("for element in {}.iter() {{ {} }}", value, check))
+    };
+    match field_type {
+        FieldType::BoundedString(bound) => Some(check(format!("{}.len()", value), bound)),
+        FieldType::BoundedWString(bound) => {
+            Some(check(format!("{}.encode_utf16().count()", value), bound))
+        }
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => {
+            let mut checks = check(format!("{}.len()", value), max_size);
+            if let Some(element) = element_check(element_type) {
+                checks.push(' ');
+                checks.push_str(&element);
+            }
+            Some(checks)
+        }
+        FieldType::Array { element_type, .. } | FieldType::Sequence { element_type } => {
+            element_check(element_type)
+        }
+        _ => None,
+    }
+}
+
+/// Doc comment lines for interface comments
+///
+/// Lines are left-trimmed and code fences escaped, so that comments never turn
+/// into code blocks that rustdoc would run as doctests.
+pub fn comment_docs(comments: &[String]) -> Vec<String> {
+    comments
+        .iter()
+        .map(|comment| {
+            let line = comment.trim_start();
+            if line.starts_with("```") || line.starts_with("~~~") {
+                format!("\\{}", line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Doc comment lines of a field: its comments, then its annotations
+///
+/// A `DEPRECATED` marker comment is left out; like `@deprecated`, it becomes a
+/// `#[deprecated]` attribute instead.
+pub fn field_docs(field: &Field) -> Vec<String> {
+    let comments: Vec<String> = field
+        .comments
+        .iter()
+        .filter(|comment| {
+            let comment = comment.trim();
+            comment != "DEPRECATED" && !comment.starts_with("DEPRECATED:")
+        })
+        .cloned()
+        .collect();
+    let mut docs = comment_docs(&comments);
+    docs.extend(annotation_docs(&field.annotations));
+    docs
+}
+
+/// Doc comment lines for field annotations: `` `@unit`: m/s `` or `` `@verbatim` ``
+///
+/// `@deprecated` is left out; it becomes a `#[deprecated]` attribute instead.
+pub fn annotation_docs(annotations: &[Annotation]) -> Vec<String> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.name != "deprecated")
+        .map(|annotation| match &annotation.value {
+            Some(value) => format!(
+                "`@{}`: {}",
+                annotation.name,
+                value.split_whitespace().collect::<Vec<_>>().join(" ")
+            ),
+            None => format!("`@{}`", annotation.name),
+        })
+        .collect()
+}
+
+/// `#[deprecated]` attribute for a field's deprecation note, if it has one
+pub fn deprecated_attribute(note: Option<&str>) -> Option<String> {
+    note.map(|note| match note {
+        "" => "#[deprecated]".to_string(),
+        note => format!("#[deprecated(note = {:?})]", note),
+    })
+}
+
 /// Convert a ConstantValue to a Rust code string
 pub fn constant_value_to_rust(value: &ConstantValue) -> String {
     match value {
         ConstantValue::Integer(i) => i.to_string(),
+        ConstantValue::UInteger(u) => u.to_string(),
         ConstantValue::Float(f) => {
             // Ensure float literals always have decimal point
             let s = f.to_string();
@@ -48,7 +178,148 @@ pub fn constant_value_to_rust(value: &ConstantValue) -> String {
             }
         }
         ConstantValue::Bool(b) => b.to_string(),
-        ConstantValue::String(s) => format!("\"{}\"", s.escape_default()),
+        // Debug escapes quotes, backslashes and control characters but keeps
+        // printable non-ASCII text readable
+        ConstantValue::String(s) | ConstantValue::WString(s) => format!("{:?}", s),
+        ConstantValue::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(constant_value_to_rust)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Rust type of a constant of type `field_type`
+///
+/// String constants are `&str`, as neither `String` nor the RMW string types
+/// can be built in a `const`. Other types are those of fields.
+pub fn constant_rust_type(
+    field_type: &FieldType,
+    rmw_layer: bool,
+    current_package: Option<&str>,
+) -> String {
+    match field_type {
+        FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_) => "&str".to_string(),
+        _ => rust_type_for_field(field_type, rmw_layer, current_package),
+    }
+}
+
+/// Literal of a constant of type `field_type`, suffixed with its Rust type
+/// (`100u8`, `-1i32`, `1.0f32`)
+///
+/// Integer values of float constants become float literals.
+pub fn typed_constant_value(value: &ConstantValue, field_type: &FieldType) -> String {
+    let FieldType::Primitive(primitive) = field_type else {
+        return constant_value_to_rust(value);
+    };
+    let suffix = primitive.rust_type();
+    match (primitive, value) {
+        (
+            PrimitiveType::Float32 | PrimitiveType::Float64,
+            ConstantValue::Integer(_) | ConstantValue::UInteger(_),
+        ) => format!("{}.0{}", value, suffix),
+        (_, ConstantValue::Integer(_) | ConstantValue::UInteger(_) | ConstantValue::Float(_)) => {
+            format!("{}{}", constant_value_to_rust(value), suffix)
+        }
+        _ => constant_value_to_rust(value),
+    }
+}
+
+/// Derives of `requested` that a struct with `fields` supports
+///
+/// `Copy` needs fields of primitive types or arrays of them, and `Eq`, `Ord` and
+/// `Hash` fields without floating point numbers. A nested message may not support
+/// these (nor `PartialOrd`), so structs with one skip them. The derives generated
+/// structs always have, and `Default` they implement by hand, are skipped as well.
+pub fn struct_derives(requested: &[String], fields: &[Field]) -> Vec<String> {
+    requested
+        .iter()
+        .filter(|derive| {
+            // `std::hash::Hash` is `Hash`
+            let name = derive.rsplit("::").next().unwrap_or(derive);
+            !matches!(name, "Debug" | "Clone" | "PartialEq" | "Default")
+                && fields.iter().all(|f| supports_derive(name, &f.field_type))
+        })
+        .cloned()
+        .collect()
+}
+
+fn supports_derive(derive: &str, field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::Primitive(PrimitiveType::Float32 | PrimitiveType::Float64) => {
+            !matches!(derive, "Eq" | "Ord" | "Hash")
+        }
+        FieldType::Primitive(_) => true,
+        FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_) => derive != "Copy",
+        FieldType::Array { element_type, .. } => supports_derive(derive, element_type),
+        FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. } => {
+            derive != "Copy" && supports_derive(derive, element_type)
+        }
+        FieldType::NamespacedType { .. } => {
+            !matches!(derive, "Copy" | "Eq" | "Ord" | "PartialOrd" | "Hash")
+        }
+    }
+}
+
+/// Size and alignment of a field in the C struct of rosidl_generator_c, as const
+/// expressions of the RMW layer
+///
+/// Strings and sequences are `{data, size, capacity}` structs and nested messages
+/// take the layout of their RMW struct, which its own module checks.
+pub fn c_layout(field_type: &FieldType, current_package: Option<&str>) -> (String, String) {
+    match field_type {
+        FieldType::Primitive(primitive) => {
+            let size = match primitive {
+                PrimitiveType::Bool
+                | PrimitiveType::Byte
+                | PrimitiveType::Char
+                | PrimitiveType::Int8
+                | PrimitiveType::UInt8 => 1,
+                PrimitiveType::Int16 | PrimitiveType::UInt16 => 2,
+                PrimitiveType::Int32 | PrimitiveType::UInt32 | PrimitiveType::Float32 => 4,
+                PrimitiveType::Int64 | PrimitiveType::UInt64 | PrimitiveType::Float64 => 8,
+            };
+            // 8-byte types are only 4-aligned on some 32-bit targets, in C and Rust alike
+            let align = if size == 8 {
+                format!("align_of::<{}>()", primitive.rust_type())
+            } else {
+                size.to_string()
+            };
+            (size.to_string(), align)
+        }
+        FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_)
+        | FieldType::Sequence { .. }
+        | FieldType::BoundedSequence { .. } => (
+            "3 * size_of::<usize>()".to_string(),
+            "align_of::<usize>()".to_string(),
+        ),
+        FieldType::Array { element_type, size } => {
+            let (element_size, align) = c_layout(element_type, current_package);
+            let size = match element_size.parse::<usize>() {
+                Ok(element_size) => (size * element_size).to_string(),
+                Err(_) => format!("{} * {}", size, element_size),
+            };
+            (size, align)
+        }
+        FieldType::NamespacedType { .. } => {
+            let rust_type = rust_type_for_field(field_type, true, current_package);
+            (
+                format!("size_of::<{}>()", rust_type),
+                format!("align_of::<{}>()", rust_type),
+            )
+        }
     }
 }
 
@@ -61,6 +332,9 @@ const RUST_KEYWORDS: &[&str] = &[
     "override", "priv", "typeof", "unsized", "virtual", "yield", "try",
 ];
 
+/// Keywords that cannot be raw identifiers
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
 /// Escape Rust keywords by appending underscore
 pub fn escape_keyword(name: &str) -> String {
     if RUST_KEYWORDS.contains(&name) {
@@ -70,6 +344,19 @@ pub fn escape_keyword(name: &str) -> String {
     }
 }
 
+/// Rust identifier of a field: a keyword becomes a raw identifier (`r#type`) with
+/// `raw_identifiers`, keeping the name of the interface definition, or gets an
+/// underscore appended (`type_`) otherwise
+///
+/// `crate`, `self`, `Self` and `super` have no raw form and are always escaped.
+pub fn field_identifier(name: &str, raw_identifiers: bool) -> String {
+    if raw_identifiers && RUST_KEYWORDS.contains(&name) && !NON_RAW_KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        escape_keyword(name)
+    }
+}
+
 /// Get the Rust type string for a field type
 /// If `rmw_layer` is true, returns RMW types (rosidl_runtime_rs::*), else idiomatic types
 /// `current_package` is used to detect self-references and use `crate::` instead of `pkg::`
@@ -173,6 +460,286 @@ pub fn rust_type_for_field(
     }
 }
 
+/// Rust type of a field in embedded (`no_std`) bindings
+///
+/// Strings and sequences become the fixed-capacity containers of
+/// `rosidl_runtime_rs::bounded`: bounded types keep their bound, unbounded ones get
+/// the capacities of `capacity`. Wide strings are stored as UTF-8 like strings.
+pub fn embedded_type_for_field(
+    field_type: &FieldType,
+    current_package: Option<&str>,
+    capacity: EmbeddedCapacity,
+) -> String {
+    match field_type {
+        FieldType::String | FieldType::WString => format!(
+            "rosidl_runtime_rs::bounded::BoundedString<{}>",
+            capacity.string
+        ),
+        FieldType::BoundedString(size) | FieldType::BoundedWString(size) => {
+            format!("rosidl_runtime_rs::bounded::BoundedString<{}>", size)
+        }
+        FieldType::Array { element_type, size } => format!(
+            "[{}; {}]",
+            embedded_type_for_field(element_type, current_package, capacity),
+            size
+        ),
+        FieldType::Sequence { element_type } => format!(
+            "rosidl_runtime_rs::bounded::BoundedVec<{}, {}>",
+            embedded_type_for_field(element_type, current_package, capacity),
+            capacity.sequence
+        ),
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => format!(
+            "rosidl_runtime_rs::bounded::BoundedVec<{}, {}>",
+            embedded_type_for_field(element_type, current_package, capacity),
+            max_size
+        ),
+        FieldType::Primitive(_) | FieldType::NamespacedType { .. } => {
+            rust_type_for_field(field_type, false, current_package)
+        }
+    }
+}
+
+/// Expression building an arbitrary value of a field from `u` (`arbitrary` feature)
+///
+/// Evaluates to `fuzz::Result<T>`, with `fuzz` being `rosidl_runtime_rs::fuzz`.
+pub fn arbitrary_expr(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::String | FieldType::WString => "fuzz::string(u, None)".to_string(),
+        FieldType::BoundedString(size) | FieldType::BoundedWString(size) => {
+            format!("fuzz::string(u, Some({}))", size)
+        }
+        FieldType::Array { element_type, .. } => {
+            format!("fuzz::array(u, |u| {})", arbitrary_expr(element_type))
+        }
+        FieldType::Sequence { element_type } => {
+            format!("fuzz::vec(u, None, |u| {})", arbitrary_expr(element_type))
+        }
+        FieldType::BoundedSequence {
+            element_type,
+            max_size,
+        } => format!(
+            "fuzz::vec(u, Some({}), |u| {})",
+            max_size,
+            arbitrary_expr(element_type)
+        ),
+        FieldType::Primitive(_) | FieldType::NamespacedType { .. } => {
+            "fuzz::Arbitrary::arbitrary(u)".to_string()
+        }
+    }
+}
+
+/// Conversion expressions for a field as `(idiomatic -> RMW, RMW -> idiomatic)`
+/// for the runtime the bindings are generated against: the upstream one in rclrs
+/// compatibility mode, the one of this repository otherwise
+///
+/// `field_name` is the name of the interface definition or the field's Rust
+/// identifier; keywords of the former are escaped.
+pub fn field_conversions(
+    field_type: &FieldType,
+    field_name: &str,
+    current_package: Option<&str>,
+    rclrs_compat: bool,
+) -> (String, String) {
+    if rclrs_compat {
+        rclrs_field_conversions(field_type, field_name, current_package)
+    } else {
+        native_field_conversions(field_type, field_name, current_package)
+    }
+}
+
+/// Conversion expressions for a field as `(idiomatic -> RMW, RMW -> idiomatic)`,
+/// written against the `rosidl_runtime_rs` of this repository. The sources are
+/// `idiomatic.<field>` and `rmw.<field>`, read by reference.
+///
+/// Nested messages and strings convert through their `From<&T>` impls, element
+/// by element in arrays and sequences. More elements than the bound of a bounded
+/// sequence panic, and so does a bounded string past its bound, like the strings
+/// of [`rclrs_field_conversions`]; the idiomatic struct documents it (see
+/// [`crate::ir::StructIr::idiomatic_docs`]).
+pub fn native_field_conversions(
+    field_type: &FieldType,
+    field_name: &str,
+    current_package: Option<&str>,
+) -> (String, String) {
+    // Wide strings convert through UTF-16 the same way with either runtime
+    if is_wstring_type(field_type) {
+        return rclrs_field_conversions(field_type, field_name, current_package);
+    }
+
+    let name = escape_keyword(field_name);
+    let idiomatic = format!("idiomatic.{}", name);
+    let rmw = format!("rmw.{}", name);
+    let is_primitive = |element: &FieldType| matches!(element, FieldType::Primitive(_));
+    let is_bounded_string = |element: &FieldType| matches!(element, FieldType::BoundedString(_));
+    let bounded_string = |value: &str| {
+        format!(
+            "{}.as_str().try_into().expect(\"string exceeds its bound\")",
+            value
+        )
+    };
+
+    match field_type {
+        FieldType::Primitive(_) => (idiomatic, rmw),
+        FieldType::BoundedString(_) => (
+            bounded_string(&format!("(&{})", idiomatic)),
+            format!("(&{}).into()", rmw),
+        ),
+        FieldType::Array { element_type, .. } if is_bounded_string(element_type) => (
+            format!(
+                "std::array::from_fn(|i| {})",
+                bounded_string(&format!("(&{}[i])", idiomatic))
+            ),
+            format!("std::array::from_fn(|i| (&{}[i]).into())", rmw),
+        ),
+        FieldType::Sequence { element_type } if is_bounded_string(element_type) => (
+            format!("{}.iter().map(|e| {}).collect()", idiomatic, bounded_string("e")),
+            format!("{}.to_vec_converted()", rmw),
+        ),
+        FieldType::BoundedSequence { element_type, .. } if is_bounded_string(element_type) => (
+            format!(
+                "crate::rosidl_runtime_rs::BoundedSequence::try_from({}.iter().map(|e| {}).collect::<crate::rosidl_runtime_rs::Sequence<_>>()).expect(\"sequence exceeds its bound\")",
+                idiomatic,
+                bounded_string("e")
+            ),
+            format!("{}.to_vec_converted()", rmw),
+        ),
+        // Arrays of primitives are Copy
+        FieldType::Array { element_type, .. } if is_primitive(element_type) => (idiomatic, rmw),
+        FieldType::Array { .. } => (
+            format!("std::array::from_fn(|i| (&{}[i]).into())", idiomatic),
+            format!("std::array::from_fn(|i| (&{}[i]).into())", rmw),
+        ),
+        FieldType::Sequence { element_type } if is_primitive(element_type) => (
+            format!("{}.clone().into()", idiomatic),
+            format!("{}.as_slice().to_vec()", rmw),
+        ),
+        FieldType::Sequence { .. } => (
+            format!(
+                "crate::rosidl_runtime_rs::Sequence::from_slice_converted(&{})",
+                idiomatic
+            ),
+            format!("{}.to_vec_converted()", rmw),
+        ),
+        FieldType::BoundedSequence { element_type, .. } if is_primitive(element_type) => (
+            format!(
+                "crate::rosidl_runtime_rs::BoundedSequence::try_from({}.clone()).expect(\"sequence exceeds its bound\")",
+                idiomatic
+            ),
+            format!("{}.as_slice().to_vec()", rmw),
+        ),
+        FieldType::BoundedSequence { .. } => (
+            format!(
+                "crate::rosidl_runtime_rs::BoundedSequence::try_from_slice_converted(&{}).expect(\"sequence exceeds its bound\")",
+                idiomatic
+            ),
+            format!("{}.to_vec_converted()", rmw),
+        ),
+        _ => (
+            format!("(&{}).into()", idiomatic),
+            format!("(&{}).into()", rmw),
+        ),
+    }
+}
+
+/// Conversion expressions for a field as `(idiomatic -> RMW, RMW -> idiomatic)`,
+/// written against the upstream ros2-rust `rosidl_runtime_rs` API (used in rclrs
+/// compatibility mode). The sources are `idiomatic.<field>` and `rmw.<field>`.
+pub fn rclrs_field_conversions(
+    field_type: &FieldType,
+    field_name: &str,
+    current_package: Option<&str>,
+) -> (String, String) {
+    let name = escape_keyword(field_name);
+    let idiomatic = format!("idiomatic.{}", name);
+    let rmw = format!("rmw.{}", name);
+
+    match field_type {
+        FieldType::Primitive(_) => (idiomatic, rmw),
+        FieldType::Array { element_type, .. } => match element_type.as_ref() {
+            // Arrays of primitives are Copy
+            FieldType::Primitive(_) => (idiomatic, rmw),
+            element => (
+                format!(
+                    "std::array::from_fn(|i| {})",
+                    rclrs_element_to_rmw(element, &format!("(&{}[i])", idiomatic), current_package)
+                ),
+                format!(
+                    "std::array::from_fn(|i| {})",
+                    rclrs_element_from_rmw(element, &format!("(&{}[i])", rmw), current_package)
+                ),
+            ),
+        },
+        FieldType::Sequence { element_type } | FieldType::BoundedSequence { element_type, .. } => {
+            match element_type.as_ref() {
+                FieldType::Primitive(_) => (
+                    format!("{}.iter().copied().collect()", idiomatic),
+                    format!("{}.iter().copied().collect()", rmw),
+                ),
+                element => (
+                    format!(
+                        "{}.iter().map(|e| {}).collect()",
+                        idiomatic,
+                        rclrs_element_to_rmw(element, "e", current_package)
+                    ),
+                    format!(
+                        "{}.iter().map(|e| {}).collect()",
+                        rmw,
+                        rclrs_element_from_rmw(element, "e", current_package)
+                    ),
+                ),
+            }
+        }
+        scalar => (
+            rclrs_element_to_rmw(scalar, &format!("(&{})", idiomatic), current_package),
+            rclrs_element_from_rmw(scalar, &format!("(&{})", rmw), current_package),
+        ),
+    }
+}
+
+/// Idiomatic -> RMW conversion of a non-container value given by reference
+fn rclrs_element_to_rmw(
+    field_type: &FieldType,
+    value: &str,
+    current_package: Option<&str>,
+) -> String {
+    match field_type {
+        FieldType::Primitive(_) => format!("*{}", value),
+        FieldType::String | FieldType::WString => format!("{}.as_str().into()", value),
+        FieldType::BoundedString(_) | FieldType::BoundedWString(_) => format!(
+            "{}.as_str().try_into().expect(\"string exceeds its bound\")",
+            value
+        ),
+        _ => format!(
+            "<{} as crate::rosidl_runtime_rs::Message>::into_rmw_message(std::borrow::Cow::Borrowed({})).into_owned()",
+            rust_type_for_field(field_type, false, current_package),
+            value
+        ),
+    }
+}
+
+/// RMW -> idiomatic conversion of a non-container value given by reference
+fn rclrs_element_from_rmw(
+    field_type: &FieldType,
+    value: &str,
+    current_package: Option<&str>,
+) -> String {
+    match field_type {
+        FieldType::Primitive(_) => format!("*{}", value),
+        FieldType::String
+        | FieldType::WString
+        | FieldType::BoundedString(_)
+        | FieldType::BoundedWString(_) => format!("{}.to_string()", value),
+        _ => format!(
+            "<{} as crate::rosidl_runtime_rs::Message>::from_rmw_message({}.clone())",
+            rust_type_for_field(field_type, false, current_package),
+            value
+        ),
+    }
+}
+
 /// Convert snake_case to UpperCamelCase
 pub fn to_upper_camel_case(s: &str) -> String {
     s.split('_')
@@ -210,7 +777,90 @@ pub fn to_snake_case(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rosidl_parser::PrimitiveType;
+
+    #[test]
+    fn test_string_constant_escaping() {
+        let value = ConstantValue::String("say \"hi\"\n\\ café\u{7}".to_string());
+        assert_eq!(
+            constant_value_to_rust(&value),
+            r#""say \"hi\"\n\\ café\u{7}""#
+        );
+    }
+
+    #[test]
+    fn test_annotation_docs() {
+        let annotations = [
+            Annotation {
+                name: "unit".to_string(),
+                value: Some("m/s".to_string()),
+            },
+            Annotation {
+                name: "verbatim".to_string(),
+                value: Some("line one\nline two".to_string()),
+            },
+            Annotation {
+                name: "key".to_string(),
+                value: None,
+            },
+        ];
+        assert_eq!(
+            annotation_docs(&annotations),
+            ["`@unit`: m/s", "`@verbatim`: line one line two", "`@key`"]
+        );
+    }
+
+    #[test]
+    fn test_uint64_constant() {
+        assert_eq!(
+            constant_value_to_rust(&ConstantValue::UInteger(u64::MAX)),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn test_typed_constants() {
+        let typed =
+            |value, primitive| typed_constant_value(&value, &FieldType::Primitive(primitive));
+        assert_eq!(
+            typed(ConstantValue::Integer(100), PrimitiveType::UInt8),
+            "100u8"
+        );
+        assert_eq!(
+            typed(ConstantValue::Integer(-1), PrimitiveType::Int32),
+            "-1i32"
+        );
+        assert_eq!(
+            typed(ConstantValue::UInteger(u64::MAX), PrimitiveType::UInt64),
+            "18446744073709551615u64"
+        );
+        assert_eq!(
+            typed(ConstantValue::Integer(65), PrimitiveType::Char),
+            "65u8"
+        );
+        assert_eq!(
+            typed(ConstantValue::Float(1.0), PrimitiveType::Float32),
+            "1.0f32"
+        );
+        assert_eq!(
+            typed(ConstantValue::Integer(3), PrimitiveType::Float64),
+            "3.0f64"
+        );
+        assert_eq!(
+            typed(ConstantValue::Bool(true), PrimitiveType::Bool),
+            "true"
+        );
+
+        let string = ConstantValue::String("a".to_string());
+        assert_eq!(typed_constant_value(&string, &FieldType::String), "\"a\"");
+        assert_eq!(
+            constant_rust_type(&FieldType::BoundedWString(4), true, None),
+            "&str"
+        );
+        assert_eq!(
+            constant_rust_type(&FieldType::Primitive(PrimitiveType::Float32), true, None),
+            "f32"
+        );
+    }
 
     #[test]
     fn test_escape_keywords() {
@@ -218,34 +868,42 @@ mod tests {
         assert_eq!(escape_keyword("match"), "match_");
         assert_eq!(escape_keyword("async"), "async_");
         assert_eq!(escape_keyword("normal_field"), "normal_field");
+
+        assert_eq!(field_identifier("type", true), "r#type");
+        assert_eq!(field_identifier("type", false), "type_");
+        assert_eq!(field_identifier("self", true), "self_");
+        assert_eq!(field_identifier("normal_field", true), "normal_field");
     }
 
     #[test]
     fn test_primitive_types() {
         let int32 = FieldType::Primitive(PrimitiveType::Int32);
-        assert_eq!(rust_type_for_field(&int32, false), "i32");
-        assert_eq!(rust_type_for_field(&int32, true), "i32");
+        assert_eq!(rust_type_for_field(&int32, false, None), "i32");
+        assert_eq!(rust_type_for_field(&int32, true, None), "i32");
 
         let float64 = FieldType::Primitive(PrimitiveType::Float64);
-        assert_eq!(rust_type_for_field(&float64, false), "f64");
+        assert_eq!(rust_type_for_field(&float64, false, None), "f64");
     }
 
     #[test]
     fn test_string_types() {
         let unbounded = FieldType::String;
         assert_eq!(
-            rust_type_for_field(&unbounded, false),
+            rust_type_for_field(&unbounded, false, None),
             "std::string::String"
         );
         assert_eq!(
-            rust_type_for_field(&unbounded, true),
+            rust_type_for_field(&unbounded, true, None),
             "rosidl_runtime_rs::String"
         );
 
         let bounded = FieldType::BoundedString(256);
-        assert_eq!(rust_type_for_field(&bounded, false), "std::string::String");
         assert_eq!(
-            rust_type_for_field(&bounded, true),
+            rust_type_for_field(&bounded, false, None),
+            "std::string::String"
+        );
+        assert_eq!(
+            rust_type_for_field(&bounded, true, None),
             "rosidl_runtime_rs::BoundedString<256>"
         );
     }
@@ -256,8 +914,37 @@ mod tests {
             element_type: Box::new(FieldType::Primitive(PrimitiveType::Int32)),
             size: 5,
         };
-        assert_eq!(rust_type_for_field(&array, false), "[i32; 5]");
-        assert_eq!(rust_type_for_field(&array, true), "[i32; 5]");
+        assert_eq!(rust_type_for_field(&array, false, None), "[i32; 5]");
+        assert_eq!(rust_type_for_field(&array, true, None), "[i32; 5]");
+    }
+
+    #[test]
+    fn test_bounded_string_container_types() {
+        let tags = FieldType::BoundedSequence {
+            element_type: Box::new(FieldType::BoundedString(10)),
+            max_size: 5,
+        };
+        assert_eq!(
+            rust_type_for_field(&tags, true, None),
+            "rosidl_runtime_rs::BoundedSequence<rosidl_runtime_rs::BoundedString<10>, 5>"
+        );
+        assert_eq!(
+            embedded_type_for_field(&tags, None, EmbeddedCapacity::default()),
+            "rosidl_runtime_rs::bounded::BoundedVec<rosidl_runtime_rs::bounded::BoundedString<10>, 5>"
+        );
+
+        let fixed = FieldType::Array {
+            element_type: Box::new(FieldType::BoundedString(10)),
+            size: 4,
+        };
+        assert_eq!(
+            rust_type_for_field(&fixed, true, None),
+            "[rosidl_runtime_rs::BoundedString<10>; 4]"
+        );
+        assert_eq!(
+            rust_type_for_field(&fixed, false, None),
+            "[std::string::String; 4]"
+        );
     }
 
     #[test]
@@ -265,13 +952,156 @@ mod tests {
         let seq = FieldType::Sequence {
             element_type: Box::new(FieldType::Primitive(PrimitiveType::Float64)),
         };
-        assert_eq!(rust_type_for_field(&seq, false), "std::vec::Vec<f64>");
+        assert_eq!(rust_type_for_field(&seq, false, None), "std::vec::Vec<f64>");
         assert_eq!(
-            rust_type_for_field(&seq, true),
+            rust_type_for_field(&seq, true, None),
             "rosidl_runtime_rs::Sequence<f64>"
         );
     }
 
+    #[test]
+    fn test_struct_derives() {
+        let requested: Vec<String> = [
+            "Copy",
+            "Eq",
+            "std::hash::Hash",
+            "PartialOrd",
+            "Default",
+            "Custom",
+        ]
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+        let derives = |msg: &str| {
+            let msg = rosidl_parser::parse_message(msg).unwrap();
+            struct_derives(&requested, &msg.fields)
+        };
+        assert_eq!(
+            derives("int32 a\nuint8[4] b\n"),
+            ["Copy", "Eq", "std::hash::Hash", "PartialOrd", "Custom"]
+        );
+        assert_eq!(
+            derives("string a\nint32[] b\n"),
+            ["Eq", "std::hash::Hash", "PartialOrd", "Custom"]
+        );
+        assert_eq!(derives("float64[3] a\n"), ["Copy", "PartialOrd", "Custom"]);
+        assert_eq!(derives("geometry_msgs/Point a\n"), ["Custom"]);
+    }
+
+    #[test]
+    fn test_rclrs_field_conversions() {
+        let (to_rmw, from_rmw) =
+            rclrs_field_conversions(&FieldType::Primitive(PrimitiveType::Float64), "x", None);
+        assert_eq!(to_rmw, "idiomatic.x");
+        assert_eq!(from_rmw, "rmw.x");
+
+        let (to_rmw, from_rmw) = rclrs_field_conversions(&FieldType::String, "type", None);
+        assert_eq!(to_rmw, "(&idiomatic.type_).as_str().into()");
+        assert_eq!(from_rmw, "(&rmw.type_).to_string()");
+
+        let points = FieldType::Sequence {
+            element_type: Box::new(FieldType::NamespacedType {
+                package: Some("geometry_msgs".to_string()),
+                name: "Point".to_string(),
+            }),
+        };
+        let (to_rmw, from_rmw) = rclrs_field_conversions(&points, "points", Some("nav_msgs"));
+        assert_eq!(
+            to_rmw,
+            "idiomatic.points.iter().map(|e| <geometry_msgs::msg::point::Point as crate::rosidl_runtime_rs::Message>::into_rmw_message(std::borrow::Cow::Borrowed(e)).into_owned()).collect()"
+        );
+        assert_eq!(
+            from_rmw,
+            "rmw.points.iter().map(|e| <geometry_msgs::msg::point::Point as crate::rosidl_runtime_rs::Message>::from_rmw_message(e.clone())).collect()"
+        );
+
+        let labels = FieldType::Array {
+            element_type: Box::new(FieldType::BoundedString(8)),
+            size: 2,
+        };
+        let (to_rmw, _) = rclrs_field_conversions(&labels, "labels", None);
+        assert_eq!(
+            to_rmw,
+            "std::array::from_fn(|i| (&idiomatic.labels[i]).as_str().try_into().expect(\"string exceeds its bound\"))"
+        );
+    }
+
+    #[test]
+    fn test_native_field_conversions() {
+        let point = Box::new(FieldType::NamespacedType {
+            package: Some("geometry_msgs".to_string()),
+            name: "Point".to_string(),
+        });
+        let conversions = |field_type: FieldType| native_field_conversions(&field_type, "f", None);
+
+        assert_eq!(
+            conversions(FieldType::Array {
+                element_type: Box::new(FieldType::Primitive(PrimitiveType::Float64)),
+                size: 36,
+            }),
+            ("idiomatic.f".to_string(), "rmw.f".to_string())
+        );
+        assert_eq!(
+            conversions(FieldType::Array {
+                element_type: point.clone(),
+                size: 2,
+            }),
+            (
+                "std::array::from_fn(|i| (&idiomatic.f[i]).into())".to_string(),
+                "std::array::from_fn(|i| (&rmw.f[i]).into())".to_string()
+            )
+        );
+        assert_eq!(
+            conversions(FieldType::Sequence {
+                element_type: Box::new(FieldType::Primitive(PrimitiveType::Int32)),
+            }),
+            (
+                "idiomatic.f.clone().into()".to_string(),
+                "rmw.f.as_slice().to_vec()".to_string()
+            )
+        );
+        assert_eq!(
+            conversions(FieldType::Sequence {
+                element_type: point.clone(),
+            })
+            .0,
+            "crate::rosidl_runtime_rs::Sequence::from_slice_converted(&idiomatic.f)"
+        );
+        assert_eq!(
+            conversions(FieldType::BoundedSequence {
+                element_type: point,
+                max_size: 3,
+            }),
+            (
+                "crate::rosidl_runtime_rs::BoundedSequence::try_from_slice_converted(&idiomatic.f).expect(\"sequence exceeds its bound\")".to_string(),
+                "rmw.f.to_vec_converted()".to_string()
+            )
+        );
+        assert_eq!(
+            conversions(FieldType::String),
+            (
+                "(&idiomatic.f).into()".to_string(),
+                "(&rmw.f).into()".to_string()
+            )
+        );
+        assert_eq!(
+            conversions(FieldType::BoundedString(8)),
+            (
+                "(&idiomatic.f).as_str().try_into().expect(\"string exceeds its bound\")"
+                    .to_string(),
+                "(&rmw.f).into()".to_string()
+            )
+        );
+        assert_eq!(
+            conversions(FieldType::BoundedSequence {
+                element_type: Box::new(FieldType::BoundedString(8)),
+                max_size: 2,
+            })
+            .0,
+            "crate::rosidl_runtime_rs::BoundedSequence::try_from(idiomatic.f.iter().map(|e| e.as_str().try_into().expect(\"string exceeds its bound\")).collect::<crate::rosidl_runtime_rs::Sequence<_>>()).expect(\"sequence exceeds its bound\")"
+        );
+    }
+
     #[test]
     fn test_case_conversion() {
         assert_eq!(to_upper_camel_case("test_message"), "TestMessage");
@@ -1,5 +1,7 @@
-use rosidl_parser::{FieldType, Message};
-use std::collections::HashSet;
+use crate::generator::GeneratorError;
+use rosidl_parser::visit::walk_type;
+use rosidl_parser::{FieldType, Message, Visitor};
+use std::collections::{BTreeSet, HashSet};
 
 /// Convert a PascalCase or camelCase string to snake_case
 pub fn to_snake_case(s: &str) -> String {
@@ -25,61 +27,149 @@ pub fn to_snake_case(s: &str) -> String {
     result
 }
 
-/// Extract all package dependencies from a message
-pub fn extract_dependencies(message: &Message) -> HashSet<String> {
-    let mut deps = HashSet::new();
+/// Extract all package dependencies from a message, in name order
+pub fn extract_dependencies(message: &Message) -> BTreeSet<String> {
+    let mut collector = DependencyCollector::default();
+    collector.visit_message(message);
+    collector.deps
+}
 
+/// Check that the fields of a message of `package_name` only refer to that
+/// package and `known_packages`, suggesting the closest known name otherwise
+pub fn check_packages(
+    package_name: &str,
+    interface_name: &str,
+    message: &Message,
+    known_packages: &HashSet<String>,
+) -> Result<(), GeneratorError> {
     for field in &message.fields {
-        extract_deps_from_type(&field.field_type, &mut deps);
+        let mut collector = DependencyCollector::default();
+        collector.visit_type(&field.field_type);
+        for package in collector.deps {
+            if package == package_name || known_packages.contains(&package) {
+                continue;
+            }
+            let suggestion = closest_name(&package, known_packages.iter().map(String::as_str));
+            return Err(GeneratorError::UnknownPackage {
+                suggestion: suggestion.map(str::to_string),
+                package,
+            }
+            .in_field(package_name, interface_name, &field.name));
+        }
     }
+    Ok(())
+}
 
-    for constant in &message.constants {
-        extract_deps_from_type(&constant.constant_type, &mut deps);
+/// The candidate closest to `name` by edit distance, if close enough to be a
+/// typo of it (`geometry_msgs` for `geometry_msg`)
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
     }
+    row[b.len()]
+}
 
-    deps
+/// Names of the messages of `package_name` nested in a message, in name order
+pub fn extract_local_types(message: &Message, package_name: &str) -> BTreeSet<String> {
+    let mut collector = LocalTypeCollector {
+        package_name,
+        types: BTreeSet::new(),
+    };
+    collector.visit_message(message);
+    collector.types
 }
 
-fn extract_deps_from_type(field_type: &FieldType, deps: &mut HashSet<String>) {
-    match field_type {
-        FieldType::NamespacedType {
+#[derive(Default)]
+struct DependencyCollector {
+    deps: BTreeSet<String>,
+}
+
+impl Visitor for DependencyCollector {
+    fn visit_type(&mut self, field_type: &FieldType) {
+        if let FieldType::NamespacedType {
             package: Some(pkg), ..
-        } => {
-            deps.insert(pkg.clone());
-        }
-        FieldType::Array { element_type, .. }
-        | FieldType::Sequence { element_type, .. }
-        | FieldType::BoundedSequence { element_type, .. } => {
-            extract_deps_from_type(element_type, deps);
+        } = field_type
+        {
+            self.deps.insert(pkg.clone());
         }
-        _ => {}
+        walk_type(self, field_type);
     }
 }
 
-/// Check if a message needs serde's big-array feature (arrays > 32 elements)
-pub fn needs_big_array(message: &Message) -> bool {
-    for field in &message.fields {
-        if has_large_array(&field.field_type) {
-            return true;
+struct LocalTypeCollector<'a> {
+    package_name: &'a str,
+    types: BTreeSet<String>,
+}
+
+impl Visitor for LocalTypeCollector<'_> {
+    fn visit_type(&mut self, field_type: &FieldType) {
+        if let FieldType::NamespacedType { package, name } = field_type {
+            if package
+                .as_deref()
+                .is_none_or(|package| package == self.package_name)
+            {
+                self.types.insert(name.clone());
+            }
         }
+        walk_type(self, field_type);
+    }
+}
+
+/// Package (if qualified) and name of the message a field holds, directly or as elements
+pub fn nested_type(field_type: &FieldType) -> Option<(Option<&str>, &str)> {
+    match field_type {
+        FieldType::NamespacedType { package, name } => Some((package.as_deref(), name)),
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => nested_type(element_type),
+        _ => None,
     }
-    false
 }
 
-fn has_large_array(field_type: &FieldType) -> bool {
+/// Element type of an array or sequence field
+pub fn element_type(field_type: &FieldType) -> Option<&FieldType> {
     match field_type {
-        FieldType::Array { size, .. } if *size > 32 => true,
         FieldType::Array { element_type, .. }
-        | FieldType::Sequence { element_type, .. }
-        | FieldType::BoundedSequence { element_type, .. } => has_large_array(element_type),
-        _ => false,
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => Some(element_type),
+        _ => None,
     }
 }
 
+/// Parse `(name, definition)` pairs into the message list the generators take
+#[cfg(test)]
+pub(crate) fn messages(defs: &[(&str, &str)]) -> Vec<(String, Message)> {
+    defs.iter()
+        .map(|(name, def)| (name.to_string(), rosidl_parser::parse_message(def).unwrap()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rosidl_parser::{parse_message, Field, PrimitiveType};
+    use rosidl_parser::parse_message;
 
     #[test]
     fn test_extract_dependencies() {
@@ -98,31 +188,44 @@ mod tests {
     }
 
     #[test]
-    fn test_needs_big_array() {
-        let mut msg = Message::new();
-        msg.fields.push(Field {
-            field_type: FieldType::Array {
-                element_type: Box::new(FieldType::Primitive(PrimitiveType::Int32)),
-                size: 64,
-            },
-            name: "large_array".to_string(),
-            default_value: None,
-        });
-        assert!(needs_big_array(&msg));
+    fn test_extract_local_types() {
+        let msg =
+            parse_message("Edge[] edges\ntree_msgs/Node[<=2] nodes\ngeometry_msgs/Point origin\n")
+                .unwrap();
+        let types = extract_local_types(&msg, "tree_msgs");
+        assert_eq!(types.into_iter().collect::<Vec<_>>(), ["Edge", "Node"]);
+    }
+
+    #[test]
+    fn test_nested_type() {
+        let msg =
+            parse_message("geometry_msgs/Point[2] corners\nEdge[] edges\nint32[] ids\n").unwrap();
+        let nested: Vec<_> = msg
+            .fields
+            .iter()
+            .map(|field| nested_type(&field.field_type))
+            .collect();
+        assert_eq!(
+            nested,
+            [
+                Some((Some("geometry_msgs"), "Point")),
+                Some((None, "Edge")),
+                None
+            ]
+        );
     }
 
     #[test]
-    fn test_small_array_no_big_array() {
-        let mut msg = Message::new();
-        msg.fields.push(Field {
-            field_type: FieldType::Array {
-                element_type: Box::new(FieldType::Primitive(PrimitiveType::Int32)),
-                size: 16,
-            },
-            name: "small_array".to_string(),
-            default_value: None,
-        });
-        assert!(!needs_big_array(&msg));
+    fn test_closest_name() {
+        let packages = ["geometry_msgs", "std_msgs", "std_srvs", "nav_msgs"];
+        assert_eq!(
+            closest_name("geometry_msg", packages),
+            Some("geometry_msgs")
+        );
+        assert_eq!(closest_name("std_msg", packages), Some("std_msgs"));
+        assert_eq!(closest_name("sensor_msgs", packages), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
     }
 
     #[test]
@@ -1,121 +1,96 @@
 // Compilation tests - verify generated code compiles successfully
-use rosidl_codegen::{generate_message_package, GeneratorError};
+use rosidl_codegen::{generate_message_package, GeneratedPackage, GeneratorError};
 use rosidl_parser::parse_message;
 use std::collections::HashSet;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 use tempfile::TempDir;
 
 /// Helper to create a minimal Cargo.toml for testing compilation
-fn create_test_cargo_toml(pkg_name: &str, needs_big_array: bool) -> String {
-    let big_array_dep = if needs_big_array {
-        r#"big-array = { version = "0.5", features = ["serde"] }
-"#
-    } else {
-        ""
-    };
-
+///
+/// The bindings are checked against the runtime crate of this repository, with
+/// its pure-Rust stand-ins for rosidl_runtime_c so that no ROS install is needed.
+fn create_test_cargo_toml(pkg_name: &str) -> String {
     format!(
         r#"[package]
 name = "{}"
 version = "0.1.0"
 edition = "2021"
 
+[workspace]
+
 [dependencies]
-serde = {{ version = "1.0", features = ["derive"] }}
-{}
+rosidl_runtime_rs = {{ package = "rosidl-runtime-rs", path = "{}", features = ["mock-ffi"] }}
+serde = {{ version = "1.0", features = ["derive"], optional = true }}
+
+[features]
+serde = ["dep:serde"]
+rkyv = []
+arbitrary = ["rosidl_runtime_rs/arbitrary"]
+
 [lib]
 path = "src/lib.rs"
 "#,
-        pkg_name, big_array_dep
+        pkg_name,
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../rosidl-runtime-rs")
+            .display()
     )
 }
 
-/// Helper to create a stub for rosidl_runtime_rs types (for compilation testing)
-fn create_rosidl_runtime_stub() -> String {
-    r#"
-// Stub implementations of rosidl_runtime_rs types for compilation testing
-pub mod rosidl_runtime_rs {
-    use serde::{Deserialize, Serialize};
-
-    pub type String = std::string::String;
-    pub type WString = std::string::String;
-
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub struct BoundedString<const N: usize>(std::string::String);
-
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub struct BoundedWString<const N: usize>(std::string::String);
-
-    #[repr(C)]
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub struct Sequence<T>(Vec<T>);
-
-    #[repr(C)]
-    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-    pub struct BoundedSequence<T, const N: usize>(Vec<T>);
-
-    impl<T> Default for Sequence<T> {
-        fn default() -> Self {
-            Sequence(Vec::new())
-        }
-    }
-
-    impl<T, const N: usize> Default for BoundedSequence<T, N> {
-        fn default() -> Self {
-            BoundedSequence(Vec::new())
-        }
-    }
-
-    impl<const N: usize> Default for BoundedString<N> {
-        fn default() -> Self {
-            BoundedString(std::string::String::new())
-        }
-    }
-
-    impl<const N: usize> Default for BoundedWString<N> {
-        fn default() -> Self {
-            BoundedWString(std::string::String::new())
-        }
-    }
-
-    // Trait definitions for ROS runtime
-    pub trait SequenceAlloc: Sized {
-        fn sequence_init(seq: &mut Sequence<Self>, size: usize) -> bool;
-        fn sequence_fini(seq: &mut Sequence<Self>);
-        fn sequence_copy(in_seq: &Sequence<Self>, out_seq: &mut Sequence<Self>) -> bool;
-    }
-
-    pub trait Message: Clone {
-        type RmwMsg: Clone;
-        fn into_rmw_message(msg_cow: std::borrow::Cow<'_, Self>) -> std::borrow::Cow<'_, Self::RmwMsg>;
-        fn from_rmw_message(msg: Self::RmwMsg) -> Self;
-    }
-
-    pub trait RmwMessage: Sized {
-        const TYPE_NAME: &'static str;
-        fn get_type_support() -> *const std::ffi::c_void;
-    }
+/// Helper to lay out a generated message the way cargo-ros2-bindgen does
+fn create_lib_rs(
+    module_name: &str,
+    message_name: &str,
+    result: &GeneratedPackage,
+    deny_warnings: bool,
+) -> String {
+    format!(
+        r#"{}
+use rosidl_runtime_rs;
 
-    pub trait Service {
-        type Request;
-        type Response;
-        fn get_type_support() -> *const std::ffi::c_void;
-    }
+// FFI layer at package root (conflict-free)
+pub mod ffi {{
+    pub mod msg {{
+        pub mod {} {{
+            {}
+        }}
+    }}
+}}
 
-    pub trait Action {
-        type Goal;
-        type Result;
-        type Feedback;
-        type FeedbackMessage;
-        type SendGoalService;
-        type CancelGoalService;
-        type GetResultService;
-        fn get_type_support() -> *const std::ffi::c_void;
-    }
+// Idiomatic layer at package root
+pub mod msg {{
+    pub mod {} {{
+        {}
+    }}
+    pub use {}::{};
+}}
+"#,
+        if deny_warnings {
+            "#![deny(warnings)]\n"
+        } else {
+            ""
+        },
+        module_name,
+        result.message_rmw,
+        module_name,
+        result.message_idiomatic,
+        module_name,
+        message_name
+    )
 }
-"#
-    .to_string()
+
+/// Helper to run cargo in a test package, sharing the build of the runtime
+fn cargo(subcommand: &str, pkg_dir: &Path) -> Command {
+    let mut command = Command::new("cargo");
+    command
+        .arg(subcommand)
+        .arg("--manifest-path")
+        .arg(pkg_dir.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("compilation_test"));
+    command
 }
 
 /// Helper to check if cargo is available
@@ -164,50 +139,19 @@ fn test_simple_message_compiles() -> Result<(), GeneratorError> {
     fs::create_dir_all(&pkg_dir).unwrap();
 
     // Write Cargo.toml
-    let cargo_toml = create_test_cargo_toml("test_msgs", false);
+    let cargo_toml = create_test_cargo_toml("test_msgs");
     fs::write(pkg_dir.join("Cargo.toml"), cargo_toml).unwrap();
 
     // Write generated lib.rs
     let src_dir = pkg_dir.join("src");
     fs::create_dir_all(&src_dir).unwrap();
 
-    let lib_rs = format!(
-        r#"
-{}
-
-// FFI layer at package root (conflict-free)
-pub mod ffi {{
-    pub mod msg {{
-        pub mod {} {{
-            {}
-        }}
-
-        // Re-export for convenience
-        pub use {}::{};
-    }}
-}}
-
-// Idiomatic layer at package root
-pub mod msg {{
-    // Idiomatic types
-    {}
-}}
-"#,
-        create_rosidl_runtime_stub(),
-        module_name,
-        result.message_rmw,
-        module_name,
-        message_name,
-        result.message_idiomatic
-    );
+    let lib_rs = create_lib_rs(&module_name, message_name, &result, false);
 
     fs::write(src_dir.join("lib.rs"), lib_rs).unwrap();
 
     // Try to compile it
-    let output = Command::new("cargo")
-        .arg("check")
-        .arg("--manifest-path")
-        .arg(pkg_dir.join("Cargo.toml"))
+    let output = cargo("check", &pkg_dir)
         .output()
         .expect("Failed to run cargo check");
 
@@ -228,7 +172,7 @@ fn test_message_with_arrays_compiles() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    // Use array sizes ≤ 32 to avoid needing big-array (serde limitation)
+    // Use array sizes ≤ 32, which need no serde_array helper
     let msg_def = "int32[5] small_array\nint32[32] large_array\n";
     let msg = parse_message(msg_def).unwrap();
 
@@ -241,51 +185,68 @@ fn test_message_with_arrays_compiles() -> Result<(), GeneratorError> {
     let pkg_dir = temp_dir.path().join("test_msgs_arrays");
     fs::create_dir_all(&pkg_dir).unwrap();
 
-    // Write Cargo.toml (no big-array needed for arrays ≤ 32)
-    let cargo_toml = create_test_cargo_toml("test_msgs_arrays", false);
+    // Write Cargo.toml
+    let cargo_toml = create_test_cargo_toml("test_msgs_arrays");
     fs::write(pkg_dir.join("Cargo.toml"), cargo_toml).unwrap();
 
     // Write generated lib.rs
     let src_dir = pkg_dir.join("src");
     fs::create_dir_all(&src_dir).unwrap();
 
-    let lib_rs = format!(
-        r#"
-{}
+    let lib_rs = create_lib_rs(&module_name, message_name, &result, false);
 
-// FFI layer at package root (conflict-free)
-pub mod ffi {{
-    pub mod msg {{
-        pub mod {} {{
-            {}
-        }}
+    fs::write(src_dir.join("lib.rs"), lib_rs).unwrap();
 
-        // Re-export for convenience
-        pub use {}::{};
-    }}
-}}
+    // Try to compile it
+    let output = cargo("check", &pkg_dir)
+        .output()
+        .expect("Failed to run cargo check");
 
-// Idiomatic layer at package root
-pub mod msg {{
-    // Idiomatic types
-    {}
-}}
-"#,
-        create_rosidl_runtime_stub(),
-        module_name,
-        result.message_rmw,
-        module_name,
-        message_name,
-        result.message_idiomatic
-    );
+    if !output.status.success() {
+        eprintln!("Compilation failed:");
+        eprintln!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+        eprintln!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+        panic!("Generated code with arrays failed to compile");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_bounded_string_message_compiles() -> Result<(), GeneratorError> {
+    if !cargo_available() {
+        eprintln!("Skipping compilation test - cargo not available");
+        return Ok(());
+    }
+
+    let msg_def = "string<=8 frame_id\nstring<=4[2] codes\nstring<=3[] tags\nstring<=2[<=2] short_tags\nwstring<=5 brief\n";
+    let msg = parse_message(msg_def).unwrap();
+
+    let message_name = "BoundedMsg";
+    let module_name = to_snake_case(message_name);
+    let result = generate_message_package("test_msgs", message_name, &msg, &HashSet::new())?;
+    assert!(result
+        .message_rmw
+        .contains("pub frame_id: rosidl_runtime_rs::BoundedString<8>,"));
+
+    // Create temp directory for test package
+    let temp_dir = TempDir::new().unwrap();
+    let pkg_dir = temp_dir.path().join("test_msgs_bounded");
+    fs::create_dir_all(&pkg_dir).unwrap();
 
+    // Write Cargo.toml
+    let cargo_toml = create_test_cargo_toml("test_msgs_bounded");
+    fs::write(pkg_dir.join("Cargo.toml"), cargo_toml).unwrap();
+
+    // Write generated lib.rs
+    let src_dir = pkg_dir.join("src");
+    fs::create_dir_all(&src_dir).unwrap();
+
+    let lib_rs = create_lib_rs(&module_name, message_name, &result, false);
     fs::write(src_dir.join("lib.rs"), lib_rs).unwrap();
 
     // Try to compile it
-    let output = Command::new("cargo")
-        .arg("check")
-        .arg("--manifest-path")
-        .arg(pkg_dir.join("Cargo.toml"))
+    let output = cargo("check", &pkg_dir)
         .output()
         .expect("Failed to run cargo check");
 
@@ -293,7 +254,7 @@ pub mod msg {{
         eprintln!("Compilation failed:");
         eprintln!("stdout: {}", String::from_utf8_lossy(&output.stdout));
         eprintln!("stderr: {}", String::from_utf8_lossy(&output.stderr));
-        panic!("Generated code with arrays failed to compile");
+        panic!("Generated code with bounded strings failed to compile");
     }
 
     Ok(())
@@ -319,51 +280,18 @@ fn test_check_no_warnings() -> Result<(), GeneratorError> {
     fs::create_dir_all(&pkg_dir).unwrap();
 
     // Write files
-    let cargo_toml = create_test_cargo_toml("test_msgs_warnings", false);
+    let cargo_toml = create_test_cargo_toml("test_msgs_warnings");
     fs::write(pkg_dir.join("Cargo.toml"), cargo_toml).unwrap();
 
     let src_dir = pkg_dir.join("src");
     fs::create_dir_all(&src_dir).unwrap();
 
-    let lib_rs = format!(
-        r#"
-#![deny(warnings)]
-
-{}
-
-// FFI layer at package root (conflict-free)
-pub mod ffi {{
-    pub mod msg {{
-        pub mod {} {{
-            {}
-        }}
-
-        // Re-export for convenience
-        pub use {}::{};
-    }}
-}}
-
-// Idiomatic layer at package root
-pub mod msg {{
-    // Idiomatic types
-    {}
-}}
-"#,
-        create_rosidl_runtime_stub(),
-        module_name,
-        result.message_rmw,
-        module_name,
-        message_name,
-        result.message_idiomatic
-    );
+    let lib_rs = create_lib_rs(&module_name, message_name, &result, true);
 
     fs::write(src_dir.join("lib.rs"), lib_rs).unwrap();
 
     // Compile with warnings as errors
-    let output = Command::new("cargo")
-        .arg("check")
-        .arg("--manifest-path")
-        .arg(pkg_dir.join("Cargo.toml"))
+    let output = cargo("check", &pkg_dir)
         .output()
         .expect("Failed to run cargo check");
 
@@ -407,49 +335,18 @@ fn test_clippy_no_warnings() -> Result<(), GeneratorError> {
     fs::create_dir_all(&pkg_dir).unwrap();
 
     // Write files
-    let cargo_toml = create_test_cargo_toml("test_msgs_clippy", false);
+    let cargo_toml = create_test_cargo_toml("test_msgs_clippy");
     fs::write(pkg_dir.join("Cargo.toml"), cargo_toml).unwrap();
 
     let src_dir = pkg_dir.join("src");
     fs::create_dir_all(&src_dir).unwrap();
 
-    let lib_rs = format!(
-        r#"
-{}
-
-// FFI layer at package root (conflict-free)
-pub mod ffi {{
-    pub mod msg {{
-        pub mod {} {{
-            {}
-        }}
-
-        // Re-export for convenience
-        pub use {}::{};
-    }}
-}}
-
-// Idiomatic layer at package root
-pub mod msg {{
-    // Idiomatic types
-    {}
-}}
-"#,
-        create_rosidl_runtime_stub(),
-        module_name,
-        result.message_rmw,
-        module_name,
-        message_name,
-        result.message_idiomatic
-    );
+    let lib_rs = create_lib_rs(&module_name, message_name, &result, false);
 
     fs::write(src_dir.join("lib.rs"), lib_rs).unwrap();
 
     // Run clippy
-    let output = Command::new("cargo")
-        .arg("clippy")
-        .arg("--manifest-path")
-        .arg(pkg_dir.join("Cargo.toml"))
+    let output = cargo("clippy", &pkg_dir)
         .arg("--")
         .arg("-W")
         .arg("clippy::all")
@@ -43,12 +43,17 @@ fn test_very_large_array() -> Result<(), GeneratorError> {
         },
         name: "huge_array".to_string(),
         default_value: None,
+        comments: Vec::new(),
+        annotations: Vec::new(),
+        deprecated: None,
     });
 
     let result = generate_message_package("test_msgs", "HugeArray", &msg, &HashSet::new())?;
 
     assert!(result.message_rmw.contains("[i32; 1000]"));
-    assert!(result.cargo_toml.contains("big-array"));
+    assert!(result
+        .message_idiomatic
+        .contains("serde(with = \"crate::serde_array\")"));
 
     Ok(())
 }
@@ -64,8 +64,14 @@ fn test_generate_message_with_arrays() -> Result<(), GeneratorError> {
     assert!(result.message_rmw.contains("[i32; 5]"));
     assert!(result.message_rmw.contains("[f64; 100]"));
 
-    // Verify big-array feature is added for arrays > 32
-    assert!(result.cargo_toml.contains("serde-big-array"));
+    // Only arrays > 32 need the serde_array helper
+    assert_eq!(
+        result
+            .message_idiomatic
+            .matches("serde(with = \"crate::serde_array\")")
+            .count(),
+        1
+    );
 
     Ok(())
 }
@@ -105,14 +111,18 @@ fn test_generate_message_with_dependencies() -> Result<(), GeneratorError> {
     // Verify namespaced types in RMW layer (FFI hierarchy: pkg::ffi::msg::Type)
     assert!(result
         .message_rmw
-        .contains("geometry_msgs::ffi::msg::Point"));
-    assert!(result.message_rmw.contains("std_msgs::ffi::msg::Header"));
+        .contains("geometry_msgs::ffi::msg::point::Point"));
+    assert!(result
+        .message_rmw
+        .contains("std_msgs::ffi::msg::header::Header"));
 
     // Verify namespaced types in idiomatic layer
     assert!(result
         .message_idiomatic
-        .contains("geometry_msgs::msg::Point"));
-    assert!(result.message_idiomatic.contains("std_msgs::msg::Header"));
+        .contains("geometry_msgs::msg::point::Point"));
+    assert!(result
+        .message_idiomatic
+        .contains("std_msgs::msg::header::Header"));
 
     Ok(())
 }
@@ -215,12 +225,12 @@ fn test_generate_service_with_dependencies() -> Result<(), GeneratorError> {
     // Verify namespaced types in RMW layer (FFI hierarchy: pkg::ffi::msg::Type)
     assert!(result
         .service_rmw
-        .contains("geometry_msgs::ffi::msg::Point"));
+        .contains("geometry_msgs::ffi::msg::point::Point"));
 
     // Verify namespaced types in idiomatic layer
     assert!(result
         .service_idiomatic
-        .contains("geometry_msgs::msg::Point"));
+        .contains("geometry_msgs::msg::point::Point"));
 
     Ok(())
 }
@@ -290,12 +300,14 @@ fn test_generate_action_with_dependencies() -> Result<(), GeneratorError> {
     assert!(result.cargo_toml.contains("geometry_msgs"));
 
     // Verify namespaced types in RMW layer (FFI hierarchy: pkg::ffi::msg::Type)
-    assert!(result.action_rmw.contains("geometry_msgs::ffi::msg::Point"));
+    assert!(result
+        .action_rmw
+        .contains("geometry_msgs::ffi::msg::point::Point"));
 
     // Verify namespaced types in idiomatic layer
     assert!(result
         .action_idiomatic
-        .contains("geometry_msgs::msg::Point"));
+        .contains("geometry_msgs::msg::point::Point"));
 
     Ok(())
 }
@@ -48,7 +48,7 @@ pub fn normalize_use_statements(code: &str) -> String {
             // End of use block - sort and add
             use_statements.sort();
             use_statements.dedup();
-            lines.extend(use_statements.drain(..));
+            lines.append(&mut use_statements);
             lines.push(line.to_string());
             in_use_block = false;
         } else {
@@ -56,7 +56,7 @@ pub fn normalize_use_statements(code: &str) -> String {
                 // Flush use statements before non-use content
                 use_statements.sort();
                 use_statements.dedup();
-                lines.extend(use_statements.drain(..));
+                lines.append(&mut use_statements);
                 in_use_block = false;
             }
             lines.push(line.to_string());
@@ -90,10 +90,7 @@ pub fn print_diff(label_ours: &str, label_reference: &str, ours: &str, reference
         return true;
     }
 
-    println!(
-        "\n{} diff between {} and {}:",
-        "❌", label_ours, label_reference
-    );
+    println!("\n❌ diff between {} and {}:", label_ours, label_reference);
     println!("{}", "=".repeat(80));
 
     let diff = TextDiff::from_lines(reference, ours);
@@ -44,8 +44,7 @@ fn test_std_msgs_primitives() -> Result<(), GeneratorError> {
     for msg_file in test_messages {
         let path = Path::new(ros_share).join(msg_file);
         if path.exists() {
-            let msg =
-                read_and_parse_message(&path).map_err(|e| GeneratorError::InvalidMessage(e))?;
+            let msg = read_and_parse_message(&path).map_err(GeneratorError::InvalidMessage)?;
 
             let msg_name = msg_file.trim_end_matches(".msg");
             let result = generate_message_package("std_msgs", msg_name, &msg, &HashSet::new())?;
@@ -69,8 +68,8 @@ fn test_std_msgs_header() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    let msg = read_and_parse_message(Path::new(header_path))
-        .map_err(|e| GeneratorError::InvalidMessage(e))?;
+    let msg =
+        read_and_parse_message(Path::new(header_path)).map_err(GeneratorError::InvalidMessage)?;
 
     let result = generate_message_package("std_msgs", "Header", &msg, &HashSet::new())?;
 
@@ -90,8 +89,8 @@ fn test_geometry_msgs_point() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    let msg = read_and_parse_message(Path::new(point_path))
-        .map_err(|e| GeneratorError::InvalidMessage(e))?;
+    let msg =
+        read_and_parse_message(Path::new(point_path)).map_err(GeneratorError::InvalidMessage)?;
 
     let result = generate_message_package("geometry_msgs", "Point", &msg, &HashSet::new())?;
 
@@ -113,8 +112,8 @@ fn test_geometry_msgs_pose() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    let msg = read_and_parse_message(Path::new(pose_path))
-        .map_err(|e| GeneratorError::InvalidMessage(e))?;
+    let msg =
+        read_and_parse_message(Path::new(pose_path)).map_err(GeneratorError::InvalidMessage)?;
 
     let result = generate_message_package("geometry_msgs", "Pose", &msg, &HashSet::new())?;
 
@@ -137,8 +136,8 @@ fn test_example_interfaces_service() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    let srv = read_and_parse_service(Path::new(srv_path))
-        .map_err(|e| GeneratorError::InvalidMessage(e))?;
+    let srv =
+        read_and_parse_service(Path::new(srv_path)).map_err(GeneratorError::InvalidMessage)?;
 
     let result =
         generate_service_package("example_interfaces", "AddTwoInts", &srv, &HashSet::new())?;
@@ -160,8 +159,8 @@ fn test_example_interfaces_action() -> Result<(), GeneratorError> {
         return Ok(());
     }
 
-    let action = read_and_parse_action(Path::new(action_path))
-        .map_err(|e| GeneratorError::InvalidMessage(e))?;
+    let action =
+        read_and_parse_action(Path::new(action_path)).map_err(GeneratorError::InvalidMessage)?;
 
     let result =
         generate_action_package("example_interfaces", "Fibonacci", &action, &HashSet::new())?;
@@ -190,7 +189,7 @@ fn test_parse_all_std_msgs() {
     for entry in WalkDir::new(ros_share)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "msg"))
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
     {
         count += 1;
         let path = entry.path();
@@ -244,7 +243,7 @@ fn test_parse_all_geometry_msgs() {
     for entry in WalkDir::new(ros_share)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "msg"))
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
     {
         count += 1;
         let path = entry.path();
@@ -298,7 +297,7 @@ fn test_parse_all_sensor_msgs() {
     for entry in WalkDir::new(ros_share)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "msg"))
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "msg"))
     {
         count += 1;
         let path = entry.path();
@@ -0,0 +1,6 @@
+//! Every interface parser on arbitrary bytes: `cargo +nightly fuzz run parse`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| rosidl_parser::fuzz_parse(data));
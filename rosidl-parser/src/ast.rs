@@ -1,7 +1,15 @@
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Primitive types in ROS IDL
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// `byte` and `char` are kept apart from `uint8` even though all three are
+/// 8-bit unsigned values: ROS 2 reads `byte` as the IDL `octet`, opaque data
+/// rather than a number, and `char` as a plain `uint8`. Both are deprecated
+/// spellings carried over from ROS 1; see [`PrimitiveType::deprecation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PrimitiveType {
     Bool,
     Byte,
@@ -53,10 +61,44 @@ impl PrimitiveType {
             Self::Float64 => "f64",
         }
     }
+
+    /// Why the ROS 1 spelling of this type is deprecated, for `byte` and `char`
+    ///
+    /// Their meaning changed in ROS 2: `byte` was a signed integer in ROS 1 and
+    /// `char` an unsigned one that reads like a character type.
+    pub fn deprecation(&self) -> Option<&'static str> {
+        match self {
+            Self::Byte => Some("`byte` is an opaque octet in ROS 2, not the int8 of ROS 1"),
+            Self::Char => Some("`char` is a uint8 in ROS 2, not a character; use `uint8`"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PrimitiveType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Bool => "bool",
+            Self::Byte => "byte",
+            Self::Char => "char",
+            Self::Int8 => "int8",
+            Self::UInt8 => "uint8",
+            Self::Int16 => "int16",
+            Self::UInt16 => "uint16",
+            Self::Int32 => "int32",
+            Self::UInt32 => "uint32",
+            Self::Int64 => "int64",
+            Self::UInt64 => "uint64",
+            Self::Float32 => "float32",
+            Self::Float64 => "float64",
+        };
+        f.write_str(name)
+    }
 }
 
 /// Field type specification
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FieldType {
     /// Primitive type
     Primitive(PrimitiveType),
@@ -87,47 +129,314 @@ pub enum FieldType {
     },
 }
 
+/// Formats the type using .msg syntax (e.g. `string<=10`, `geometry_msgs/Point[]`)
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Primitive(primitive) => write!(f, "{}", primitive),
+            Self::String => write!(f, "string"),
+            Self::BoundedString(max) => write!(f, "string<={}", max),
+            Self::WString => write!(f, "wstring"),
+            Self::BoundedWString(max) => write!(f, "wstring<={}", max),
+            Self::Array { element_type, size } => write!(f, "{}[{}]", element_type, size),
+            Self::Sequence { element_type } => write!(f, "{}[]", element_type),
+            Self::BoundedSequence {
+                element_type,
+                max_size,
+            } => write!(f, "{}[<={}]", element_type, max_size),
+            Self::NamespacedType {
+                package: Some(package),
+                name,
+            } => write!(f, "{}/{}", package, name),
+            Self::NamespacedType {
+                package: None,
+                name,
+            } => write!(f, "{}", name),
+        }
+    }
+}
+
+impl FieldType {
+    /// Builtin type named by this (package-qualified) type, if any
+    ///
+    /// Arrays and sequences of builtin types are not builtin types themselves.
+    pub fn builtin_type(&self) -> Option<BuiltinType> {
+        match self {
+            Self::NamespacedType {
+                package: Some(package),
+                name,
+            } => BuiltinType::from_name(package, name),
+            _ => None,
+        }
+    }
+}
+
+/// Message types of `builtin_interfaces`, which bindings treat specially
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinType {
+    /// `builtin_interfaces/Time`: `int32 sec`, `uint32 nanosec` since the epoch
+    Time,
+    /// `builtin_interfaces/Duration`: `int32 sec`, `uint32 nanosec`
+    Duration,
+}
+
+impl BuiltinType {
+    /// Package of the builtin types
+    pub const PACKAGE: &'static str = "builtin_interfaces";
+
+    /// Builtin type for the message `package/name`
+    pub fn from_name(package: &str, name: &str) -> Option<Self> {
+        match (package, name) {
+            (Self::PACKAGE, "Time") => Some(Self::Time),
+            (Self::PACKAGE, "Duration") => Some(Self::Duration),
+            _ => None,
+        }
+    }
+
+    /// Message name within `builtin_interfaces`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Time => "Time",
+            Self::Duration => "Duration",
+        }
+    }
+}
+
+/// Formats as `builtin_interfaces/Time`
+impl fmt::Display for BuiltinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", Self::PACKAGE, self.name())
+    }
+}
+
 /// Constant value
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConstantValue {
     Integer(i64),
+    /// Integer above i64::MAX, such as a large `uint64` constant
+    UInteger(u64),
     Float(f64),
     String(String),
+    /// Value of a `wstring` constant or default
+    WString(String),
     Bool(bool),
+    /// Default of an array or sequence field (`[1, 2, 3]`)
+    Array(Vec<ConstantValue>),
+}
+
+impl ConstantValue {
+    /// Text of a string or wide string value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(v) | Self::WString(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConstantValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(v) => write!(f, "{}", v),
+            Self::UInteger(v) => write!(f, "{}", v),
+            Self::Float(v) => write!(f, "{}", v),
+            Self::String(v) | Self::WString(v) => write!(f, "{:?}", v),
+            Self::Bool(v) => write!(f, "{}", v),
+            Self::Array(values) => {
+                f.write_str("[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// Annotation of a field: `# @unit(m/s)` in a `.msg` comment, `@unit (value="m/s")` in IDL
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annotation {
+    pub name: String,
+    /// Text between the parentheses (the `value` parameter in IDL), if any
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub value: Option<String>,
+}
+
+impl Annotation {
+    /// Annotation written as a whole comment, such as `@unit(m/s)` or `@verbatim`
+    ///
+    /// Returns `None` for other comments, including prose that merely starts with `@`.
+    pub fn from_comment(text: &str) -> Option<Self> {
+        let rest = text.trim().strip_prefix('@')?;
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_len);
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let rest = rest.trim_start();
+        let value = if rest.is_empty() {
+            None
+        } else {
+            let value = rest.strip_prefix('(')?.strip_suffix(')')?;
+            Some(value.trim().to_string())
+        };
+        Some(Self {
+            name: name.to_string(),
+            value,
+        })
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}", self.name)?;
+        match &self.value {
+            Some(value) => write!(f, "({})", value),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Message field
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Field {
     pub field_type: FieldType,
     pub name: String,
     pub default_value: Option<ConstantValue>,
+    /// Comment lines directly above the field, then its trailing comment (without `#`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<String>,
+    /// Annotations attached to the field, in source order; annotation comments
+    /// are not repeated in `comments`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub annotations: Vec<Annotation>,
+    /// Deprecation note from a `# DEPRECATED: reason` comment or a `@deprecated`
+    /// annotation; empty when no reason is given
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub deprecated: Option<String>,
+}
+
+impl Field {
+    /// Documentation of the field: its comments, one line each
+    pub fn doc(&self) -> Option<String> {
+        join_doc(&self.comments)
+    }
+}
+
+/// Deprecation note of an element with these comments and annotations
+///
+/// A `@deprecated(reason)` annotation takes precedence over a
+/// `DEPRECATED: reason` comment.
+pub(crate) fn deprecation_note(comments: &[String], annotations: &[Annotation]) -> Option<String> {
+    if let Some(annotation) = annotations.iter().find(|a| a.name == "deprecated") {
+        return Some(annotation.value.clone().unwrap_or_default());
+    }
+    comments.iter().find_map(|comment| {
+        let rest = comment.trim().strip_prefix("DEPRECATED")?;
+        if rest.is_empty() {
+            Some(String::new())
+        } else {
+            rest.strip_prefix(':')
+                .map(|reason| reason.trim().to_string())
+        }
+    })
 }
 
 /// Message constant
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Constant {
     pub constant_type: FieldType,
     pub name: String,
     pub value: ConstantValue,
+    /// Comment lines directly above the constant, then its trailing comment (without `#`)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<String>,
 }
 
 /// Message specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Message {
     pub fields: Vec<Field>,
     pub constants: Vec<Constant>,
+    /// File-level comment: the comment block at the top of the message (or
+    /// section), and comment lines before the first element's own block
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub comments: Vec<String>,
+}
+
+/// Section of a service or action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Request,
+    Response,
+    Goal,
+    Result,
+    Feedback,
+}
+
+impl Section {
+    /// Lowercase name, as used for the section's module (`request`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::Response => "response",
+            Self::Goal => "goal",
+            Self::Result => "result",
+            Self::Feedback => "feedback",
+        }
+    }
+
+    /// Suffix of the section's type name (`AddTwoInts` + `Request`)
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Self::Request => "Request",
+            Self::Response => "Response",
+            Self::Goal => "Goal",
+            Self::Result => "Result",
+            Self::Feedback => "Feedback",
+        }
+    }
 }
 
 /// Service specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Service {
     pub request: Message,
     pub response: Message,
 }
 
 /// Action specification sections
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ActionSpec {
     pub goal: Message,
     pub result: Message,
@@ -135,22 +444,55 @@ pub struct ActionSpec {
 }
 
 /// Action specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Action {
     pub spec: ActionSpec,
 }
 
+impl Service {
+    /// Request and response, each with its own constants and comments
+    pub fn sections(&self) -> [(Section, &Message); 2] {
+        [
+            (Section::Request, &self.request),
+            (Section::Response, &self.response),
+        ]
+    }
+}
+
+impl ActionSpec {
+    /// Goal, result and feedback, each with its own constants and comments
+    pub fn sections(&self) -> [(Section, &Message); 3] {
+        [
+            (Section::Goal, &self.goal),
+            (Section::Result, &self.result),
+            (Section::Feedback, &self.feedback),
+        ]
+    }
+}
+
 impl Message {
     pub fn new() -> Self {
         Self {
             fields: Vec::new(),
             constants: Vec::new(),
+            comments: Vec::new(),
         }
     }
 
     pub fn is_empty(&self) -> bool {
         self.fields.is_empty() && self.constants.is_empty()
     }
+
+    /// Documentation of the message (or section): its file-level comment, one
+    /// line each
+    pub fn doc(&self) -> Option<String> {
+        join_doc(&self.comments)
+    }
+}
+
+fn join_doc(comments: &[String]) -> Option<String> {
+    (!comments.is_empty()).then(|| comments.join("\n"))
 }
 
 impl Default for Message {
@@ -158,3 +500,24 @@ impl Default for Message {
         Self::new()
     }
 }
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::parse_message;
+
+    #[test]
+    fn message_json_round_trip() {
+        let msg = parse_message(
+            "# Pose\nint32 MIN=-1\nfloat64[2] xy [0.5, 1.5] # meters\nstring<=8 name\n",
+        )
+        .unwrap();
+        let json = serde_json::to_value(&msg).unwrap();
+
+        assert_eq!(json["fields"][0]["field_type"]["Array"]["size"], 2);
+        assert_eq!(json["fields"][0]["comments"][0], "meters");
+        // Empty comment lists are omitted
+        assert!(json["fields"][1].get("comments").is_none());
+        assert_eq!(json["constants"][0]["value"]["Integer"], -1);
+        assert_eq!(serde_json::from_value::<crate::Message>(json).unwrap(), msg);
+    }
+}
@@ -0,0 +1,525 @@
+//! Semantic diff between interface definitions
+//!
+//! Compares two parsed versions of a message (or the messages making up a service
+//! or action) field by field and classifies each difference as breaking or
+//! compatible. Changes that alter existing data or remove API surface (removed or
+//! renamed fields, type changes, shrinking bounds, changed constants) are breaking;
+//! additions and relaxed bounds are compatible.
+
+use crate::ast::{Action, ConstantValue, Field, FieldType, Message, Section, Service};
+use std::fmt;
+
+/// A single difference between two message definitions
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    FieldAdded {
+        name: String,
+        field_type: FieldType,
+    },
+    FieldRemoved {
+        name: String,
+        field_type: FieldType,
+    },
+    /// A field disappeared and a field of the same type appeared at the same position
+    FieldRenamed {
+        old_name: String,
+        new_name: String,
+    },
+    FieldTypeChanged {
+        name: String,
+        old_type: FieldType,
+        new_type: FieldType,
+    },
+    /// Maximum string length or sequence size was lowered (or a bound was introduced)
+    BoundShrunk {
+        name: String,
+        old_type: FieldType,
+        new_type: FieldType,
+    },
+    /// Maximum string length or sequence size was raised (or a bound was removed)
+    BoundGrown {
+        name: String,
+        old_type: FieldType,
+        new_type: FieldType,
+    },
+    DefaultChanged {
+        name: String,
+        old_value: Option<ConstantValue>,
+        new_value: Option<ConstantValue>,
+    },
+    ConstantAdded {
+        name: String,
+    },
+    ConstantRemoved {
+        name: String,
+    },
+    ConstantChanged {
+        name: String,
+        old_value: ConstantValue,
+        new_value: ConstantValue,
+    },
+}
+
+impl Change {
+    /// Whether existing users of the interface can break because of this change
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Change::FieldRemoved { .. }
+            | Change::FieldRenamed { .. }
+            | Change::FieldTypeChanged { .. }
+            | Change::BoundShrunk { .. }
+            | Change::ConstantRemoved { .. }
+            | Change::ConstantChanged { .. } => true,
+            Change::FieldAdded { .. }
+            | Change::BoundGrown { .. }
+            | Change::DefaultChanged { .. }
+            | Change::ConstantAdded { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: &Option<ConstantValue>| match v {
+            Some(v) => v.to_string(),
+            None => "none".to_string(),
+        };
+
+        match self {
+            Change::FieldAdded { name, field_type } => {
+                write!(f, "field added: {} {}", field_type, name)
+            }
+            Change::FieldRemoved { name, field_type } => {
+                write!(f, "field removed: {} {}", field_type, name)
+            }
+            Change::FieldRenamed { old_name, new_name } => {
+                write!(f, "field renamed: {} -> {}", old_name, new_name)
+            }
+            Change::FieldTypeChanged {
+                name,
+                old_type,
+                new_type,
+            } => write!(f, "type changed: {} ({} -> {})", name, old_type, new_type),
+            Change::BoundShrunk {
+                name,
+                old_type,
+                new_type,
+            } => write!(f, "bound shrunk: {} ({} -> {})", name, old_type, new_type),
+            Change::BoundGrown {
+                name,
+                old_type,
+                new_type,
+            } => write!(f, "bound grown: {} ({} -> {})", name, old_type, new_type),
+            Change::DefaultChanged {
+                name,
+                old_value,
+                new_value,
+            } => write!(
+                f,
+                "default changed: {} ({} -> {})",
+                name,
+                value(old_value),
+                value(new_value)
+            ),
+            Change::ConstantAdded { name } => write!(f, "constant added: {}", name),
+            Change::ConstantRemoved { name } => write!(f, "constant removed: {}", name),
+            Change::ConstantChanged {
+                name,
+                old_value,
+                new_value,
+            } => write!(
+                f,
+                "constant changed: {} ({} -> {})",
+                name, old_value, new_value
+            ),
+        }
+    }
+}
+
+/// Differences between two versions of a message, grouped by what changed
+///
+/// A view over [`diff_messages`] for tools that only care about some kinds of
+/// change, such as a warning that bindings were generated against an older
+/// interface.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterfaceDiff {
+    /// Every change, in the order of [`diff_messages`]
+    pub changes: Vec<Change>,
+}
+
+impl InterfaceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change can break existing users
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(Change::is_breaking)
+    }
+
+    /// Changes that break existing users
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+
+    /// Names of the fields only in the new version
+    pub fn added_fields(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::FieldAdded { name, .. } => Some(name.as_str()),
+            Change::FieldRenamed { new_name, .. } => Some(new_name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Names of the fields only in the old version
+    pub fn removed_fields(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::FieldRemoved { name, .. } => Some(name.as_str()),
+            Change::FieldRenamed { old_name, .. } => Some(old_name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Names of the fields whose type changed, bound changes included
+    pub fn retyped_fields(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::FieldTypeChanged { name, .. }
+            | Change::BoundShrunk { name, .. }
+            | Change::BoundGrown { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Constants added, removed or given another value
+    pub fn constant_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| {
+            matches!(
+                change,
+                Change::ConstantAdded { .. }
+                    | Change::ConstantRemoved { .. }
+                    | Change::ConstantChanged { .. }
+            )
+        })
+    }
+}
+
+/// One change per line
+impl fmt::Display for InterfaceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two versions of a message, grouping the changes
+pub fn diff(old: &Message, new: &Message) -> InterfaceDiff {
+    InterfaceDiff {
+        changes: diff_messages(old, new),
+    }
+}
+
+/// Compare two versions of a message
+pub fn diff_messages(old: &Message, new: &Message) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    let mut removed: Vec<(usize, &Field)> = Vec::new();
+    for (index, old_field) in old.fields.iter().enumerate() {
+        let Some(new_field) = new.fields.iter().find(|f| f.name == old_field.name) else {
+            removed.push((index, old_field));
+            continue;
+        };
+
+        if let Some(change) = compare_types(
+            &old_field.name,
+            &old_field.field_type,
+            &new_field.field_type,
+        ) {
+            changes.push(change);
+        }
+
+        if old_field.default_value != new_field.default_value {
+            changes.push(Change::DefaultChanged {
+                name: old_field.name.clone(),
+                old_value: old_field.default_value.clone(),
+                new_value: new_field.default_value.clone(),
+            });
+        }
+    }
+
+    let mut added: Vec<(usize, &Field)> = new
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| !old.fields.iter().any(|o| o.name == f.name))
+        .collect();
+
+    for (index, old_field) in removed {
+        // Same position and type on both sides is most likely a rename
+        let renamed = added
+            .iter()
+            .position(|(i, f)| *i == index && f.field_type == old_field.field_type);
+        match renamed {
+            Some(pos) => {
+                let (_, new_field) = added.remove(pos);
+                changes.push(Change::FieldRenamed {
+                    old_name: old_field.name.clone(),
+                    new_name: new_field.name.clone(),
+                });
+            }
+            None => changes.push(Change::FieldRemoved {
+                name: old_field.name.clone(),
+                field_type: old_field.field_type.clone(),
+            }),
+        }
+    }
+
+    for (_, new_field) in added {
+        changes.push(Change::FieldAdded {
+            name: new_field.name.clone(),
+            field_type: new_field.field_type.clone(),
+        });
+    }
+
+    for old_constant in &old.constants {
+        match new.constants.iter().find(|c| c.name == old_constant.name) {
+            None => changes.push(Change::ConstantRemoved {
+                name: old_constant.name.clone(),
+            }),
+            Some(new_constant) if new_constant.value != old_constant.value => {
+                changes.push(Change::ConstantChanged {
+                    name: old_constant.name.clone(),
+                    old_value: old_constant.value.clone(),
+                    new_value: new_constant.value.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for new_constant in &new.constants {
+        if !old.constants.iter().any(|c| c.name == new_constant.name) {
+            changes.push(Change::ConstantAdded {
+                name: new_constant.name.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Compare two versions of a service, labelling changes by section
+pub fn diff_services(old: &Service, new: &Service) -> Vec<(&'static str, Change)> {
+    diff_sections(old.sections(), new.sections())
+}
+
+/// Compare two versions of an action, labelling changes by section
+pub fn diff_actions(old: &Action, new: &Action) -> Vec<(&'static str, Change)> {
+    diff_sections(old.spec.sections(), new.spec.sections())
+}
+
+/// Changes of each section, labelled by the section name
+fn diff_sections<const N: usize>(
+    old: [(Section, &Message); N],
+    new: [(Section, &Message); N],
+) -> Vec<(&'static str, Change)> {
+    old.into_iter()
+        .zip(new)
+        .flat_map(|((section, old), (_, new))| {
+            diff_messages(old, new)
+                .into_iter()
+                .map(move |change| (section.name(), change))
+        })
+        .collect()
+}
+
+/// Classify a change between two bounds, where `None` means unbounded
+fn bound_change(
+    name: &str,
+    old: Option<usize>,
+    new: Option<usize>,
+    old_type: &FieldType,
+    new_type: &FieldType,
+) -> Option<Change> {
+    let shrunk = match (old, new) {
+        (Some(old), Some(new)) if old == new => return None,
+        (Some(old), Some(new)) => new < old,
+        (None, Some(_)) => true,
+        (Some(_), None) => false,
+        (None, None) => return None,
+    };
+
+    let (name, old_type, new_type) = (name.to_string(), old_type.clone(), new_type.clone());
+    Some(if shrunk {
+        Change::BoundShrunk {
+            name,
+            old_type,
+            new_type,
+        }
+    } else {
+        Change::BoundGrown {
+            name,
+            old_type,
+            new_type,
+        }
+    })
+}
+
+fn compare_types(name: &str, old: &FieldType, new: &FieldType) -> Option<Change> {
+    if old == new {
+        return None;
+    }
+
+    use FieldType::*;
+    let bounds = match (old, new) {
+        (String | BoundedString(_), String | BoundedString(_))
+        | (WString | BoundedWString(_), WString | BoundedWString(_)) => {
+            Some((string_bound(old), string_bound(new)))
+        }
+        (
+            Sequence { element_type: a }
+            | BoundedSequence {
+                element_type: a, ..
+            },
+            Sequence { element_type: b }
+            | BoundedSequence {
+                element_type: b, ..
+            },
+        ) if a == b => Some((sequence_bound(old), sequence_bound(new))),
+        _ => None,
+    };
+
+    match bounds {
+        Some((old_bound, new_bound)) => bound_change(name, old_bound, new_bound, old, new),
+        None => Some(Change::FieldTypeChanged {
+            name: name.to_string(),
+            old_type: old.clone(),
+            new_type: new.clone(),
+        }),
+    }
+}
+
+fn string_bound(field_type: &FieldType) -> Option<usize> {
+    match field_type {
+        FieldType::BoundedString(max) | FieldType::BoundedWString(max) => Some(*max),
+        _ => None,
+    }
+}
+
+fn sequence_bound(field_type: &FieldType) -> Option<usize> {
+    match field_type {
+        FieldType::BoundedSequence { max_size, .. } => Some(*max_size),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_message;
+
+    fn diff(old: &str, new: &str) -> Vec<Change> {
+        diff_messages(&parse_message(old).unwrap(), &parse_message(new).unwrap())
+    }
+
+    #[test]
+    fn test_identical_messages() {
+        assert!(diff("int32 x\nstring name\n", "int32 x\nstring name\n").is_empty());
+    }
+
+    #[test]
+    fn test_field_added_and_removed() {
+        let changes = diff("int32 x\nint32 y\n", "int32 x\nfloat64 z\nstring label\n");
+        assert!(changes.contains(&Change::FieldRemoved {
+            name: "y".to_string(),
+            field_type: FieldType::Primitive(crate::PrimitiveType::Int32),
+        }));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, Change::FieldAdded { name, .. } if name == "label")));
+        assert!(changes.iter().any(Change::is_breaking));
+    }
+
+    #[test]
+    fn test_field_renamed() {
+        let changes = diff("int32 x\nint32 y\n", "int32 x\nint32 height\n");
+        assert_eq!(
+            changes,
+            vec![Change::FieldRenamed {
+                old_name: "y".to_string(),
+                new_name: "height".to_string(),
+            }]
+        );
+        assert!(changes[0].is_breaking());
+    }
+
+    #[test]
+    fn test_bound_changes() {
+        let changes = diff(
+            "string<=20 name\nint32[] data\n",
+            "string<=10 name\nint32[<=5] data\n",
+        );
+        assert_eq!(changes.len(), 2);
+        assert!(changes
+            .iter()
+            .all(|c| matches!(c, Change::BoundShrunk { .. })));
+
+        let changes = diff("string<=10 name\n", "string name\n");
+        assert!(matches!(changes[0], Change::BoundGrown { .. }));
+        assert!(!changes[0].is_breaking());
+    }
+
+    #[test]
+    fn test_type_change() {
+        let changes = diff("int32 x\n", "int64 x\n");
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].is_breaking());
+        assert_eq!(changes[0].to_string(), "type changed: x (int32 -> int64)");
+    }
+
+    #[test]
+    fn test_constant_changes() {
+        let changes = diff("int32 A=1\nint32 B=2\n", "int32 A=3\nint32 C=4\n");
+        assert!(changes.contains(&Change::ConstantChanged {
+            name: "A".to_string(),
+            old_value: ConstantValue::Integer(1),
+            new_value: ConstantValue::Integer(3),
+        }));
+        assert!(changes.contains(&Change::ConstantRemoved {
+            name: "B".to_string()
+        }));
+        assert!(changes.contains(&Change::ConstantAdded {
+            name: "C".to_string()
+        }));
+    }
+
+    #[test]
+    fn test_interface_diff() {
+        let old = parse_message("int32 A=1\nint32 x\nstring<=8 name\nfloat64 y\nbool z\n").unwrap();
+        let new = parse_message("int32 A=2\nint32 x\nstring<=4 name\nint64 y\nbool w\nuint8 v\n")
+            .unwrap();
+        let changes = super::diff(&old, &new);
+
+        assert!(changes.is_breaking());
+        assert_eq!(changes.added_fields().collect::<Vec<_>>(), ["w", "v"]);
+        assert_eq!(changes.removed_fields().collect::<Vec<_>>(), ["z"]);
+        assert_eq!(changes.retyped_fields().collect::<Vec<_>>(), ["name", "y"]);
+        assert_eq!(changes.constant_changes().count(), 1);
+        assert_eq!(changes.breaking().count(), 4);
+        assert!(changes
+            .to_string()
+            .starts_with("bound shrunk: name (string<=8 -> string<=4)\n"));
+
+        assert!(super::diff(&old, &old).is_empty());
+        assert!(!super::diff(&old, &old).is_breaking());
+    }
+
+    #[test]
+    fn test_service_sections() {
+        let old = crate::parse_service("int32 a\n---\nint32 sum\n").unwrap();
+        let new = crate::parse_service("int32 a\n---\nint64 sum\n").unwrap();
+        let changes = diff_services(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, "response");
+    }
+}
@@ -0,0 +1,200 @@
+//! Canonical `.msg`/`.srv`/`.action` text for interface ASTs
+//!
+//! The output lists the section comment, then the constants, then the fields,
+//! with each element's comments and annotations as `#` lines directly above it.
+//! A blank line separates the elements from the top of the section whenever
+//! either has comments, as the parser reads a comment block there as the
+//! section comment.
+//! Parsing the output gives back the same AST, so formatting twice is a no-op.
+
+use crate::ast::{Action, ConstantValue, Message, Service};
+use std::fmt::Write;
+
+/// Render a message as `.msg` text
+pub fn format_message(message: &Message) -> String {
+    let mut out = String::new();
+    write_message(&mut out, message);
+    out
+}
+
+/// Render a service as `.srv` text
+pub fn format_service(service: &Service) -> String {
+    let mut out = String::new();
+    write_message(&mut out, &service.request);
+    out.push_str("---\n");
+    write_message(&mut out, &service.response);
+    out
+}
+
+/// Render an action as `.action` text
+pub fn format_action(action: &Action) -> String {
+    let mut out = String::new();
+    write_message(&mut out, &action.spec.goal);
+    out.push_str("---\n");
+    write_message(&mut out, &action.spec.result);
+    out.push_str("---\n");
+    write_message(&mut out, &action.spec.feedback);
+    out
+}
+
+fn write_message(out: &mut String, message: &Message) {
+    write_comments(out, &message.comments);
+    // The blank line keeps the section comment off the first element, and the
+    // first element's comments from becoming the section comment
+    let first_comments = match (message.constants.first(), message.fields.first()) {
+        (Some(constant), _) => &constant.comments,
+        (None, Some(field)) => &field.comments,
+        (None, None) => &message.comments,
+    };
+    if !message.is_empty() && (!message.comments.is_empty() || !first_comments.is_empty()) {
+        out.push('\n');
+    }
+
+    for constant in &message.constants {
+        write_comments(out, &constant.comments);
+        let _ = writeln!(
+            out,
+            "{} {}={}",
+            constant.constant_type,
+            constant.name,
+            format_value(&constant.value)
+        );
+    }
+    if !message.constants.is_empty() && !message.fields.is_empty() {
+        out.push('\n');
+    }
+
+    for field in &message.fields {
+        write_comments(out, &field.comments);
+        for annotation in &field.annotations {
+            let _ = writeln!(out, "# {}", annotation);
+        }
+        let _ = write!(out, "{} {}", field.field_type, field.name);
+        if let Some(value) = &field.default_value {
+            let _ = write!(out, " {}", format_value(value));
+        }
+        out.push('\n');
+    }
+}
+
+fn write_comments(out: &mut String, comments: &[String]) {
+    for comment in comments {
+        if comment.is_empty() {
+            out.push_str("#\n");
+        } else {
+            let _ = writeln!(out, "# {}", comment);
+        }
+    }
+}
+
+/// Literal for a constant value or default
+fn format_value(value: &ConstantValue) -> String {
+    match value {
+        ConstantValue::Integer(v) => v.to_string(),
+        ConstantValue::UInteger(v) => v.to_string(),
+        // Debug keeps the decimal point for whole numbers (1.0 rather than 1)
+        ConstantValue::Float(v) => format!("{:?}", v),
+        ConstantValue::Bool(v) => v.to_string(),
+        ConstantValue::String(v) | ConstantValue::WString(v) => quote(v),
+        ConstantValue::Array(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Double-quoted string literal, escaped so that [`crate::lexer::unescape`]
+/// gives back `value`
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_action, parse_message, parse_service};
+
+    #[test]
+    fn format_message_canonical() {
+        let input = "# Pose\n\n\
+                     float64[2] xy [0.5, 1] # meters\n\
+                     int32 MIN = -1\n\
+                     #@unit(m/s)\n\
+                     float32 speed 2\n\
+                     string name \"tab\\there \\\"q\\\"\"\n\
+                     uint64 MAX=18446744073709551615\n";
+        let msg = parse_message(input).unwrap();
+        assert_eq!(
+            format_message(&msg),
+            "# Pose\n\
+             \n\
+             int32 MIN=-1\n\
+             uint64 MAX=18446744073709551615\n\
+             \n\
+             # meters\n\
+             float64[2] xy [0.5, 1]\n\
+             # @unit(m/s)\n\
+             float32 speed 2\n\
+             string name \"tab\\there \\\"q\\\"\"\n"
+        );
+    }
+
+    #[test]
+    fn format_message_round_trip() {
+        let input = "#\n#  indented\n\n\
+                     # first\n\
+                     wstring<=4 label \"\u{e9}t\u{e9}\\u0007\"\n\
+                     string[<=2] names [\"a\", \"b\\\\\"]\n\
+                     bool[] flags [true, false]\n\
+                     geometry_msgs/Point origin\n\
+                     float64 ratio -2.5e-3\n\
+                     char C=65\n";
+        let msg = parse_message(input).unwrap();
+        let text = format_message(&msg);
+        assert_eq!(parse_message(&text).unwrap(), msg);
+        assert_eq!(format_message(&parse_message(&text).unwrap()), text);
+    }
+
+    #[test]
+    fn format_service_and_action_round_trip() {
+        let srv = parse_service("# Request\n\nint64 a\n---\n# Response only\n").unwrap();
+        let text = format_service(&srv);
+        assert_eq!(text, "# Request\n\nint64 a\n---\n# Response only\n");
+        assert_eq!(parse_service(&text).unwrap(), srv);
+
+        let action =
+            parse_action("int32 order\n---\n---\n# Progress\nint32[] sequence # so far\n").unwrap();
+        let text = format_action(&action);
+        assert_eq!(
+            text,
+            "int32 order\n---\n---\n# Progress\n\n# so far\nint32[] sequence\n"
+        );
+        assert_eq!(parse_action(&text).unwrap(), action);
+
+        // Comments of a first element are kept apart from the top of the section
+        let msg = parse_message("\n# first\nint32 a\n").unwrap();
+        let text = format_message(&msg);
+        assert_eq!(text, "\n# first\nint32 a\n");
+        assert_eq!(parse_message(&text).unwrap(), msg);
+    }
+}
@@ -0,0 +1,1009 @@
+//! OMG IDL front-end
+//!
+//! Parses the `.idl` files generated by `rosidl_adapter` into the same AST as the
+//! `.msg`/`.srv`/`.action` parser, for packages installed without their original
+//! sources. Covers the subset of IDL that ROS 2 produces: nested modules, structs,
+//! `<Struct>_Constants` modules, array typedefs, and annotations. `@default` and
+//! `@verbatim (language="comment")` become default values and comments; other
+//! field annotations, such as `@unit`, are kept in [`Field::annotations`].
+
+use crate::ast::*;
+use crate::lexer::unescape;
+use crate::parser::{
+    catch_internal, integer_constant, normalize, parse_array_default, signed_text, string_constant,
+    ParseError, ParseResult, Span,
+};
+use logos::Logos;
+use std::collections::HashMap;
+
+/// Placeholder member `rosidl_adapter` adds to structs without fields
+const EMPTY_STRUCT_MEMBER: &str = "structure_needs_at_least_one_member";
+
+#[derive(Logos, Debug, Clone, Copy, PartialEq)]
+#[logos(skip r"[ \t\r\n\f]+")] // Skip whitespace
+#[logos(skip r"//[^\n]*")] // Skip line comments
+#[logos(skip r"/\*[^*]*\*+([^/*][^*]*\*+)*/")] // Skip block comments
+#[logos(skip r"#[^\n]*")] // Skip preprocessor lines (#include)
+enum IdlTokenKind {
+    #[regex(r"[A-Za-z_][A-Za-z0-9_]*")]
+    Identifier,
+
+    #[regex(r"0[xX][0-9a-fA-F]+|[0-9]+")]
+    Integer,
+
+    #[regex(r"([0-9]+\.[0-9]*|\.[0-9]+)([eE][+-]?[0-9]+)?|[0-9]+[eE][+-]?[0-9]+")]
+    Float,
+
+    /// Narrow (`"..."`) or wide (`L"..."`) string literal
+    #[regex(r#"L?"([^"\\]|\\.)*""#)]
+    StringLiteral,
+
+    #[token("::")]
+    Scope,
+
+    #[token("{")]
+    LBrace,
+
+    #[token("}")]
+    RBrace,
+
+    #[token("(")]
+    LParen,
+
+    #[token(")")]
+    RParen,
+
+    #[token("[")]
+    LBracket,
+
+    #[token("]")]
+    RBracket,
+
+    #[token("<")]
+    LAngle,
+
+    #[token(">")]
+    RAngle,
+
+    #[token(";")]
+    Semicolon,
+
+    #[token(",")]
+    Comma,
+
+    #[token("=")]
+    Equals,
+
+    #[token("@")]
+    At,
+
+    #[token("-")]
+    Minus,
+}
+
+/// Token kind, text and location
+type IdlToken = (IdlTokenKind, String, Span);
+
+/// Annotation such as `@default (value=0)`; parameter values are kept as tokens
+struct Annotation {
+    name: String,
+    span: Span,
+    params: Vec<(String, Vec<IdlToken>)>,
+}
+
+impl Annotation {
+    fn param(&self, name: &str) -> Option<&[IdlToken]> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, tokens)| tokens.as_slice())
+    }
+}
+
+fn is_verbatim_comment(annotation: &Annotation) -> bool {
+    annotation.name == "verbatim"
+        && annotation
+            .param("language")
+            .is_some_and(|tokens| string_literals(tokens) == "comment")
+}
+
+/// Lines of the `@verbatim (language="comment", text=...)` annotation, if any
+fn verbatim_comment(annotations: &[Annotation]) -> Vec<String> {
+    let Some(annotation) = annotations
+        .iter()
+        .find(|annotation| is_verbatim_comment(annotation))
+    else {
+        return Vec::new();
+    };
+    let text = annotation.param("text").map(string_literals);
+    text.map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Annotations of a field other than the ones it is built from (`@default` and
+/// the `@verbatim` comment), keeping their `value` (or `text`) parameter
+fn field_annotations(annotations: &[Annotation]) -> Vec<crate::ast::Annotation> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.name != "default" && !is_verbatim_comment(annotation))
+        .map(|annotation| crate::ast::Annotation {
+            name: annotation.name.clone(),
+            value: annotation
+                .param("value")
+                .or_else(|| annotation.param("text"))
+                .map(token_text),
+        })
+        .collect()
+}
+
+/// Text of a parameter value: its string literals if it has any, else its tokens
+fn token_text(tokens: &[IdlToken]) -> String {
+    if tokens
+        .iter()
+        .any(|(kind, _, _)| *kind == IdlTokenKind::StringLiteral)
+    {
+        string_literals(tokens)
+    } else {
+        tokens.iter().map(|(_, text, _)| text.as_str()).collect()
+    }
+}
+
+/// Concatenation of adjacent string literals (`"a" "\n" "b"`)
+fn string_literals(tokens: &[IdlToken]) -> String {
+    tokens
+        .iter()
+        .filter(|(kind, _, _)| *kind == IdlTokenKind::StringLiteral)
+        .map(|(_, text, _)| unescape(text.strip_prefix('L').unwrap_or(text)))
+        .collect()
+}
+
+/// Structs and constants of an IDL file
+struct IdlFile {
+    /// Structs in declaration order
+    structs: Vec<(String, Message)>,
+    /// Constants by the struct they belong to (`<Struct>_Constants` modules)
+    constants: HashMap<String, Vec<Constant>>,
+    /// End of the input, where missing structs are reported
+    end: Span,
+}
+
+impl IdlFile {
+    /// Take the struct named `name`, with its constants
+    fn take_struct(&mut self, name: &str) -> ParseResult<Message> {
+        let index = self
+            .structs
+            .iter()
+            .position(|(struct_name, _)| struct_name == name)
+            .ok_or_else(|| ParseError::MissingStruct {
+                name: name.to_string(),
+                span: self.end,
+            })?;
+        let (_, mut message) = self.structs.remove(index);
+        message.constants = self.constants.remove(name).unwrap_or_default();
+        Ok(message)
+    }
+
+    /// Name of the first struct ending with `suffix`
+    fn struct_with_suffix(&self, suffix: &str) -> ParseResult<String> {
+        self.structs
+            .iter()
+            .map(|(name, _)| name)
+            .find(|name| name.ends_with(suffix))
+            .cloned()
+            .ok_or_else(|| ParseError::MissingStruct {
+                name: format!("*{}", suffix),
+                span: self.end,
+            })
+    }
+
+    /// Structs `<base><suffix>` for each of `suffixes`, where `<base>` comes from
+    /// the first struct ending with the first suffix
+    fn take_structs(&mut self, suffixes: &[&str]) -> ParseResult<Vec<Message>> {
+        let first = self.struct_with_suffix(suffixes[0])?;
+        let base = first
+            .strip_suffix(suffixes[0])
+            .unwrap_or(&first)
+            .to_string();
+        suffixes
+            .iter()
+            .map(|suffix| self.take_struct(&format!("{}{}", base, suffix)))
+            .collect()
+    }
+}
+
+/// Deepest module or sequence nesting parsed; the parser recurses per level
+const MAX_NESTING: usize = 64;
+
+struct IdlParser {
+    tokens: Vec<IdlToken>,
+    pos: usize,
+    /// Array typedefs (`typedef double double__9[9];`)
+    typedefs: HashMap<String, FieldType>,
+    /// Names of the enclosing modules
+    modules: Vec<String>,
+    /// Current module and sequence nesting
+    depth: usize,
+    file: IdlFile,
+}
+
+impl IdlParser {
+    fn new(input: &str) -> ParseResult<Self> {
+        let mut lexer = IdlTokenKind::lexer(input);
+        let mut tokens = Vec::new();
+        // Line and column are tracked incrementally rather than rescanning the input
+        let mut line = 1;
+        let mut line_start = 0;
+        let mut scanned = 0;
+        while let Some(kind) = lexer.next() {
+            let start = lexer.span().start;
+            for (i, c) in input[scanned..start].char_indices() {
+                if c == '\n' {
+                    line += 1;
+                    line_start = scanned + i + 1;
+                }
+            }
+            scanned = start;
+            let span = Span {
+                line,
+                column: input[line_start..start].chars().count() + 1,
+            };
+            match kind {
+                Ok(kind) => tokens.push((kind, lexer.slice().to_string(), span)),
+                Err(_) => {
+                    return Err(ParseError::LexerError {
+                        message: format!("Unexpected character: '{}'", lexer.slice()),
+                        span,
+                    })
+                }
+            }
+        }
+        Ok(Self {
+            tokens,
+            pos: 0,
+            typedefs: HashMap::new(),
+            modules: Vec::new(),
+            depth: 0,
+            file: IdlFile {
+                structs: Vec::new(),
+                constants: HashMap::new(),
+                end: Span::at(input, input.len()),
+            },
+        })
+    }
+
+    fn peek(&self) -> Option<&IdlToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<IdlTokenKind> {
+        self.peek().map(|(kind, _, _)| *kind)
+    }
+
+    /// Span of the next token (the end of input if there is none)
+    fn current_span(&self) -> Span {
+        self.peek().map_or(self.file.end, |(_, _, span)| *span)
+    }
+
+    /// Span of the token consumed last (the start of input if there is none)
+    fn previous_span(&self) -> Span {
+        self.pos
+            .checked_sub(1)
+            .and_then(|pos| self.tokens.get(pos))
+            .map_or(Span { line: 1, column: 1 }, |(_, _, span)| *span)
+    }
+
+    /// Enter a nested module or sequence, failing beyond [`MAX_NESTING`] levels
+    fn enter(&mut self) -> ParseResult<()> {
+        if self.depth == MAX_NESTING {
+            return Err(ParseError::NestingTooDeep {
+                limit: MAX_NESTING,
+                span: self.previous_span(),
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Whether the next token is the identifier `keyword`
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some((IdlTokenKind::Identifier, text, _)) if text == keyword)
+    }
+
+    fn advance(&mut self) -> ParseResult<IdlToken> {
+        let token = self.peek().cloned().ok_or(ParseError::UnexpectedEOF {
+            span: self.file.end,
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: IdlTokenKind) -> ParseResult<String> {
+        match self.advance()? {
+            (kind, text, _) if kind == expected => Ok(text),
+            (_, text, span) => Err(ParseError::UnexpectedToken {
+                expected: format!("{:?}", expected),
+                got: text,
+                span,
+            }),
+        }
+    }
+
+    fn parse_size(&mut self) -> ParseResult<usize> {
+        let text = self.expect(IdlTokenKind::Integer)?;
+        parse_integer(&text, false, self.previous_span()).map(|size| size as usize)
+    }
+
+    /// Definitions until the closing brace of the enclosing module (or the end)
+    fn parse_definitions(&mut self) -> ParseResult<()> {
+        while let Some(kind) = self.peek_kind() {
+            if kind == IdlTokenKind::RBrace {
+                break;
+            }
+            let annotations = self.parse_annotations()?;
+            let keyword = self.expect(IdlTokenKind::Identifier)?;
+            match keyword.as_str() {
+                "module" => {
+                    let name = self.expect(IdlTokenKind::Identifier)?;
+                    self.expect(IdlTokenKind::LBrace)?;
+                    self.enter()?;
+                    self.modules.push(name);
+                    self.parse_definitions()?;
+                    self.modules.pop();
+                    self.leave();
+                    self.expect(IdlTokenKind::RBrace)?;
+                }
+                "struct" => self.parse_struct(&annotations)?,
+                "typedef" => {
+                    let field_type = self.parse_type()?;
+                    let name = self.expect(IdlTokenKind::Identifier)?;
+                    let field_type = self.parse_array_suffix(field_type)?;
+                    self.typedefs.insert(name, field_type);
+                }
+                "const" => {
+                    let constant_type = self.parse_type()?;
+                    let name = self.expect(IdlTokenKind::Identifier)?;
+                    self.expect(IdlTokenKind::Equals)?;
+                    let value = self.parse_value(&constant_type)?;
+                    // Constants of `Foo` live in the module `Foo_Constants`
+                    let module = self.modules.last().cloned().unwrap_or_default();
+                    let owner = module.strip_suffix("_Constants").unwrap_or(&module);
+                    self.file
+                        .constants
+                        .entry(owner.to_string())
+                        .or_default()
+                        .push(Constant {
+                            constant_type,
+                            name,
+                            value,
+                            comments: verbatim_comment(&annotations),
+                        });
+                }
+                _ => {
+                    return Err(ParseError::UnexpectedToken {
+                        expected: "module, struct, typedef or const".to_string(),
+                        got: keyword,
+                        span: self.previous_span(),
+                    })
+                }
+            }
+            self.expect(IdlTokenKind::Semicolon)?;
+        }
+        Ok(())
+    }
+
+    fn parse_annotations(&mut self) -> ParseResult<Vec<Annotation>> {
+        let mut annotations = Vec::new();
+        while self.peek_kind() == Some(IdlTokenKind::At) {
+            let span = self.advance()?.2;
+            let name = self.expect(IdlTokenKind::Identifier)?;
+            let mut params = Vec::new();
+            if self.peek_kind() == Some(IdlTokenKind::LParen) {
+                self.advance()?;
+                while self.peek_kind() != Some(IdlTokenKind::RParen) {
+                    let param = self.expect(IdlTokenKind::Identifier)?;
+                    self.expect(IdlTokenKind::Equals)?;
+                    let mut value = Vec::new();
+                    while !matches!(
+                        self.peek_kind(),
+                        Some(IdlTokenKind::Comma | IdlTokenKind::RParen) | None
+                    ) {
+                        value.push(self.advance()?);
+                    }
+                    params.push((param, value));
+                    if self.peek_kind() == Some(IdlTokenKind::Comma) {
+                        self.advance()?;
+                    }
+                }
+                self.expect(IdlTokenKind::RParen)?;
+            }
+            annotations.push(Annotation { name, span, params });
+        }
+        Ok(annotations)
+    }
+
+    fn parse_struct(&mut self, annotations: &[Annotation]) -> ParseResult<()> {
+        let name = self.expect(IdlTokenKind::Identifier)?;
+        self.expect(IdlTokenKind::LBrace)?;
+
+        let mut message = Message::new();
+        message.comments = verbatim_comment(annotations);
+        while self.peek_kind() != Some(IdlTokenKind::RBrace) {
+            let annotations = self.parse_annotations()?;
+            let field_type = self.parse_type()?;
+            let field_name = self.expect(IdlTokenKind::Identifier)?;
+            let field_type = self.parse_array_suffix(field_type)?;
+            self.expect(IdlTokenKind::Semicolon)?;
+            if field_name == EMPTY_STRUCT_MEMBER {
+                continue;
+            }
+
+            let default = annotations
+                .iter()
+                .find(|annotation| annotation.name == "default")
+                .and_then(|annotation| Some((annotation.param("value")?, annotation.span)));
+            let default_value = match (&field_type, default) {
+                (_, None) => None,
+                (
+                    FieldType::Array { .. }
+                    | FieldType::Sequence { .. }
+                    | FieldType::BoundedSequence { .. },
+                    Some((tokens, span)),
+                ) => Some(parse_array_value(tokens, &field_type, span)?),
+                (_, Some((tokens, span))) => Some(parse_value_tokens(tokens, &field_type, span)?),
+            };
+            let comments = verbatim_comment(&annotations);
+            let annotations = field_annotations(&annotations);
+            message.fields.push(Field {
+                field_type,
+                name: field_name,
+                default_value,
+                deprecated: deprecation_note(&comments, &annotations),
+                comments,
+                annotations,
+            });
+        }
+        self.expect(IdlTokenKind::RBrace)?;
+
+        self.file.structs.push((name, message));
+        Ok(())
+    }
+
+    fn parse_type(&mut self) -> ParseResult<FieldType> {
+        let span = self.current_span();
+        let first = self.expect(IdlTokenKind::Identifier)?;
+        match first.as_str() {
+            "sequence" => {
+                self.expect(IdlTokenKind::LAngle)?;
+                self.enter()?;
+                let element_type = Box::new(self.parse_type()?);
+                self.leave();
+                let field_type = if self.peek_kind() == Some(IdlTokenKind::Comma) {
+                    self.advance()?;
+                    FieldType::BoundedSequence {
+                        element_type,
+                        max_size: self.parse_size()?,
+                    }
+                } else {
+                    FieldType::Sequence { element_type }
+                };
+                self.expect(IdlTokenKind::RAngle)?;
+                Ok(field_type)
+            }
+            "string" | "wstring" => {
+                let bound = if self.peek_kind() == Some(IdlTokenKind::LAngle) {
+                    self.advance()?;
+                    let size = self.parse_size()?;
+                    self.expect(IdlTokenKind::RAngle)?;
+                    Some(size)
+                } else {
+                    None
+                };
+                Ok(match (first.as_str(), bound) {
+                    ("string", None) => FieldType::String,
+                    ("string", Some(max)) => FieldType::BoundedString(max),
+                    (_, None) => FieldType::WString,
+                    (_, Some(max)) => FieldType::BoundedWString(max),
+                })
+            }
+            "unsigned" | "long" => {
+                // Multi-word integer types (`unsigned long long`)
+                let mut name = first;
+                while self.at_keyword("long") || self.at_keyword("short") {
+                    name.push(' ');
+                    name.push_str(&self.advance()?.1);
+                }
+                PrimitiveType::parse(&name)
+                    .map(FieldType::Primitive)
+                    .ok_or(ParseError::UnknownType { name, span })
+            }
+            _ => {
+                if let Some(primitive) = PrimitiveType::parse(&first) {
+                    return Ok(FieldType::Primitive(primitive));
+                }
+                let mut path = vec![first];
+                while self.peek_kind() == Some(IdlTokenKind::Scope) {
+                    self.advance()?;
+                    path.push(self.expect(IdlTokenKind::Identifier)?);
+                }
+                if path.len() == 1 {
+                    if let Some(alias) = self.typedefs.get(&path[0]) {
+                        return Ok(alias.clone());
+                    }
+                }
+                // `pkg::msg::Name` refers to pkg/Name
+                let name = path.pop().unwrap_or_default();
+                Ok(FieldType::NamespacedType {
+                    package: path.into_iter().next(),
+                    name,
+                })
+            }
+        }
+    }
+
+    /// Apply a declarator array size (`name[N]`) to `field_type`
+    fn parse_array_suffix(&mut self, field_type: FieldType) -> ParseResult<FieldType> {
+        if self.peek_kind() != Some(IdlTokenKind::LBracket) {
+            return Ok(field_type);
+        }
+        self.advance()?;
+        let size = self.parse_size()?;
+        self.expect(IdlTokenKind::RBracket)?;
+        Ok(FieldType::Array {
+            element_type: Box::new(field_type),
+            size,
+        })
+    }
+
+    /// Constant value up to the terminating `;`
+    fn parse_value(&mut self, value_type: &FieldType) -> ParseResult<ConstantValue> {
+        let mut tokens = Vec::new();
+        while !matches!(self.peek_kind(), Some(IdlTokenKind::Semicolon) | None) {
+            tokens.push(self.advance()?);
+        }
+        parse_value_tokens(&tokens, value_type, self.current_span())
+    }
+}
+
+/// Integer literal, negated when `negative`
+fn parse_integer(text: &str, negative: bool, span: Span) -> ParseResult<i128> {
+    let magnitude = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    let magnitude = magnitude.map_err(|_| ParseError::InvalidInteger {
+        text: signed_text(text, negative),
+        span,
+    })?;
+    Ok(if negative {
+        -i128::from(magnitude)
+    } else {
+        i128::from(magnitude)
+    })
+}
+
+/// Value of a constant or `@default` annotation of type `value_type`; `missing`
+/// is where a value is reported missing if `tokens` is empty
+fn parse_value_tokens(
+    tokens: &[IdlToken],
+    value_type: &FieldType,
+    missing: Span,
+) -> ParseResult<ConstantValue> {
+    let (negative, tokens) = match tokens {
+        [(IdlTokenKind::Minus, _, _), rest @ ..] => (true, rest),
+        _ => (false, tokens),
+    };
+    let unexpected = |got: &str, span: Span| ParseError::UnexpectedToken {
+        expected: "constant value".to_string(),
+        got: got.to_string(),
+        span,
+    };
+
+    match tokens {
+        [(IdlTokenKind::Integer, text, span)] => integer_constant(
+            parse_integer(text, negative, *span)?,
+            value_type,
+            &signed_text(text, negative),
+            *span,
+        ),
+        [(IdlTokenKind::Float, text, span)] => {
+            let value = text.parse::<f64>().map_err(|_| ParseError::InvalidFloat {
+                text: text.clone(),
+                span: *span,
+            })?;
+            Ok(ConstantValue::Float(if negative { -value } else { value }))
+        }
+        [(IdlTokenKind::Identifier, text, span)] if !negative => match text.as_str() {
+            "TRUE" | "true" => Ok(ConstantValue::Bool(true)),
+            "FALSE" | "false" => Ok(ConstantValue::Bool(false)),
+            _ => Err(unexpected(text, *span)),
+        },
+        [(IdlTokenKind::StringLiteral, _, span), ..] if !negative => {
+            string_constant(string_literals(tokens), value_type, *span)
+        }
+        [(_, text, span), ..] => Err(unexpected(text, *span)),
+        [] => Err(unexpected("", missing)),
+    }
+}
+
+/// Array default, written as a string holding a tuple (`"(1, 2)"`) or a list
+/// (`"[1, 2]"`) of `.msg` literals
+fn parse_array_value(
+    tokens: &[IdlToken],
+    field_type: &FieldType,
+    span: Span,
+) -> ParseResult<ConstantValue> {
+    let text = string_literals(tokens);
+    let trimmed = text.trim();
+    let list = match trimmed
+        .strip_prefix('(')
+        .and_then(|inner| inner.strip_suffix(')'))
+    {
+        Some(inner) => format!("[{}]", inner),
+        None => trimmed.to_string(),
+    };
+    parse_array_default(&list, field_type).map_err(|err| {
+        // Spans inside the string are meaningless to the reader; report the annotation
+        let reason = match err {
+            ParseError::InvalidDefault { message, .. } => message,
+            err => err.to_string(),
+        };
+        ParseError::InvalidDefault {
+            message: format!("{:?}: {}", text, reason),
+            span,
+        }
+    })
+}
+
+fn parse_idl_file(input: &str) -> ParseResult<IdlFile> {
+    catch_internal(|| {
+        let mut parser = IdlParser::new(&normalize(input))?;
+        parser.parse_definitions()?;
+        if let Some((_, text, span)) = parser.peek() {
+            return Err(ParseError::UnexpectedToken {
+                expected: "definition".to_string(),
+                got: text.clone(),
+                span: *span,
+            });
+        }
+        Ok(parser.file)
+    })
+}
+
+/// Parse the `.idl` of a message (`pkg/msg/Name.idl`)
+pub fn parse_idl_message(input: &str) -> ParseResult<Message> {
+    let mut file = parse_idl_file(input)?;
+    let name = file
+        .structs
+        .first()
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| ParseError::MissingStruct {
+            name: "message".to_string(),
+            span: file.end,
+        })?;
+    file.take_struct(&name)
+}
+
+/// Parse the `.idl` of a service (`Name_Request` and `Name_Response` structs)
+pub fn parse_idl_service(input: &str) -> ParseResult<Service> {
+    let mut file = parse_idl_file(input)?;
+    let mut structs = file.take_structs(&["_Request", "_Response"])?.into_iter();
+    Ok(Service {
+        request: structs.next().unwrap_or_default(),
+        response: structs.next().unwrap_or_default(),
+    })
+}
+
+/// Parse the `.idl` of an action (`Name_Goal`, `Name_Result` and `Name_Feedback` structs)
+pub fn parse_idl_action(input: &str) -> ParseResult<Action> {
+    let mut file = parse_idl_file(input)?;
+    let mut structs = file
+        .take_structs(&["_Goal", "_Result", "_Feedback"])?
+        .into_iter();
+    Ok(Action {
+        spec: ActionSpec {
+            goal: structs.next().unwrap_or_default(),
+            result: structs.next().unwrap_or_default(),
+            feedback: structs.next().unwrap_or_default(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSE_IDL: &str = r#"// generated from rosidl_adapter/resource/msg.idl.em
+// with input from my_msgs/msg/Pose.msg
+// generated code does not contain a copyright notice
+
+#include "geometry_msgs/msg/Point.idl"
+
+module my_msgs {
+  module msg {
+    typedef double double__9[9];
+    module Pose_Constants {
+      const int32 MIN_TEMP = -40;
+      const string FRAME = "map";
+      const boolean ENABLED = TRUE;
+      const int64 LOWEST = -9223372036854775808;
+      const uint64 HIGHEST = 18446744073709551615;
+    };
+    @verbatim (language="comment", text=
+      "A pose with covariance." "\n"
+      "Second line.")
+    struct Pose {
+      @verbatim (language="comment", text=
+        "Position in meters")
+      geometry_msgs::msg::Point position;
+
+      @default (value=1.5)
+      @unit (value="m")
+      double scale;
+
+      double__9 covariance;
+
+      /* bounded containers */
+      sequence<uint8, 4> flags;
+      sequence<string<10>> names;
+
+      @default (value="base")
+      string frame;
+
+      unsigned long long stamp;
+      int16 samples[3];
+
+      @default (value=L"b\u00e4se")
+      wstring<4> label;
+    };
+  };
+};
+"#;
+
+    #[test]
+    fn parse_idl_message_fields() {
+        let msg = parse_idl_message(POSE_IDL).unwrap();
+
+        assert_eq!(
+            msg.comments,
+            vec!["A pose with covariance.", "Second line."]
+        );
+        assert_eq!(msg.fields.len(), 9);
+        assert_eq!(
+            msg.fields[0].field_type,
+            FieldType::NamespacedType {
+                package: Some("geometry_msgs".to_string()),
+                name: "Point".to_string(),
+            }
+        );
+        assert_eq!(msg.fields[0].comments, vec!["Position in meters"]);
+        assert_eq!(msg.fields[1].default_value, Some(ConstantValue::Float(1.5)));
+        assert_eq!(
+            msg.fields[1].annotations,
+            vec![crate::ast::Annotation {
+                name: "unit".to_string(),
+                value: Some("m".to_string()),
+            }]
+        );
+        assert!(msg.fields[0].annotations.is_empty());
+        assert_eq!(
+            msg.fields[2].field_type,
+            FieldType::Array {
+                element_type: Box::new(FieldType::Primitive(PrimitiveType::Float64)),
+                size: 9,
+            }
+        );
+        assert_eq!(msg.fields[3].field_type.to_string(), "uint8[<=4]");
+        assert_eq!(msg.fields[4].field_type.to_string(), "string<=10[]");
+        assert_eq!(
+            msg.fields[5].default_value,
+            Some(ConstantValue::String("base".to_string()))
+        );
+        assert_eq!(
+            msg.fields[6].field_type,
+            FieldType::Primitive(PrimitiveType::UInt64)
+        );
+        assert_eq!(msg.fields[7].field_type.to_string(), "int16[3]");
+        assert_eq!(
+            msg.fields[8].default_value,
+            Some(ConstantValue::WString("bäse".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_idl_message_constants() {
+        let msg = parse_idl_message(POSE_IDL).unwrap();
+
+        assert_eq!(msg.constants.len(), 5);
+        assert_eq!(msg.constants[0].name, "MIN_TEMP");
+        assert_eq!(msg.constants[0].value, ConstantValue::Integer(-40));
+        assert_eq!(
+            msg.constants[1].value,
+            ConstantValue::String("map".to_string())
+        );
+        assert_eq!(msg.constants[2].value, ConstantValue::Bool(true));
+        assert_eq!(msg.constants[3].value, ConstantValue::Integer(i64::MIN));
+        assert_eq!(msg.constants[4].value, ConstantValue::UInteger(u64::MAX));
+
+        let err =
+            parse_idl_message(&POSE_IDL.replace("int32 MIN_TEMP = -40", "uint8 MIN_TEMP = -40"))
+                .unwrap_err();
+        assert!(matches!(err, ParseError::IntegerOutOfRange { .. }));
+    }
+
+    #[test]
+    fn parse_idl_empty_message() {
+        let input = "module std_msgs {\n  module msg {\n    struct Empty {\n      uint8 structure_needs_at_least_one_member;\n    };\n  };\n};\n";
+        let msg = parse_idl_message(input).unwrap();
+        assert!(msg.is_empty());
+
+        let windows = format!("\u{feff}{}", input.replace('\n', "\r\n"));
+        assert!(parse_idl_message(&windows).unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_idl_bounded_string_containers() {
+        let input = r#"
+module test_msgs {
+  module msg {
+    typedef string<10> string__10[4];
+    struct Tags {
+      sequence<string<10>, 5> tags;
+      string__10 fixed;
+      wstring<3> names[2];
+    };
+  };
+};
+"#;
+        let msg = parse_idl_message(input).unwrap();
+        let types: Vec<String> = msg
+            .fields
+            .iter()
+            .map(|field| field.field_type.to_string())
+            .collect();
+        assert_eq!(types, ["string<=10[<=5]", "string<=10[4]", "wstring<=3[2]"]);
+    }
+
+    #[test]
+    fn parse_idl_deprecated_fields() {
+        let input = r#"
+module test_msgs {
+  module msg {
+    struct Legacy {
+      @deprecated (value="use pose")
+      double x;
+      @verbatim (language="comment", text="DEPRECATED")
+      double y;
+      double z;
+    };
+  };
+};
+"#;
+        let msg = parse_idl_message(input).unwrap();
+        let notes: Vec<_> = msg.fields.iter().map(|f| f.deprecated.as_deref()).collect();
+        assert_eq!(notes, [Some("use pose"), Some(""), None]);
+    }
+
+    #[test]
+    fn parse_idl_array_defaults() {
+        let input = r#"
+module test_msgs {
+  module msg {
+    typedef boolean boolean__3[3];
+    struct Arrays {
+      @default (value="(FALSE, TRUE, FALSE)")
+      boolean__3 bool_values_default;
+
+      @default (value="('', 'max value')")
+      sequence<string, 3> string_values_default;
+    };
+  };
+};
+"#;
+        let msg = parse_idl_message(input).unwrap();
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::Bool(false),
+                ConstantValue::Bool(true),
+                ConstantValue::Bool(false),
+            ]))
+        );
+        assert_eq!(
+            msg.fields[1].default_value.as_ref().unwrap().to_string(),
+            r#"["", "max value"]"#
+        );
+
+        let err = parse_idl_message(&input.replace("TRUE, FALSE", "TRUE")).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidDefault { .. }));
+        assert_eq!(err.span().line, 6);
+    }
+
+    #[test]
+    fn parse_idl_service_structs() {
+        let input = r#"
+module example_interfaces {
+  module srv {
+    struct AddTwoInts_Request {
+      int64 a;
+      int64 b;
+    };
+    module AddTwoInts_Response_Constants {
+      const uint8 OK = 0;
+    };
+    struct AddTwoInts_Response {
+      int64 sum;
+    };
+  };
+};
+"#;
+        let srv = parse_idl_service(input).unwrap();
+        assert_eq!(srv.request.fields.len(), 2);
+        assert_eq!(srv.response.fields[0].name, "sum");
+        assert_eq!(srv.response.constants[0].name, "OK");
+        assert!(srv.request.constants.is_empty());
+    }
+
+    #[test]
+    fn parse_idl_action_structs() {
+        let input = r#"
+module example_interfaces {
+  module action {
+    struct Fibonacci_Goal {
+      int32 order;
+    };
+    struct Fibonacci_Result {
+      sequence<int32> sequence;
+    };
+    struct Fibonacci_Feedback {
+      sequence<int32> partial_sequence;
+    };
+  };
+};
+"#;
+        let action = parse_idl_action(input).unwrap();
+        assert_eq!(action.spec.goal.fields[0].name, "order");
+        assert_eq!(
+            action.spec.result.fields[0].field_type.to_string(),
+            "int32[]"
+        );
+        assert_eq!(action.spec.feedback.fields[0].name, "partial_sequence");
+
+        let err = parse_idl_service(input).unwrap_err();
+        assert!(matches!(err, ParseError::MissingStruct { .. }));
+    }
+
+    #[test]
+    fn parse_idl_rejects_unsupported_definitions() {
+        let input = "module m {\n  enum Color { RED };\n};\n";
+        let err = parse_idl_message(input).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedToken { .. }));
+        assert_eq!(err.span(), Span { line: 2, column: 3 });
+    }
+
+    #[test]
+    fn parse_idl_limits_nesting() {
+        let sequence = |depth: usize| {
+            format!(
+                "module m {{ module msg {{ struct Deep {{ {}int32{} x; }}; }}; }};",
+                "sequence<".repeat(depth),
+                ">".repeat(depth)
+            )
+        };
+        // Two levels are taken by the modules
+        let msg = parse_idl_message(&sequence(MAX_NESTING - 2)).unwrap();
+        assert!(msg.fields[0]
+            .field_type
+            .to_string()
+            .starts_with("int32[][]"));
+
+        let err = parse_idl_message(&sequence(1000)).unwrap_err();
+        assert!(matches!(err, ParseError::NestingTooDeep { limit: 64, .. }));
+        let modules = format!("{}{}", "module m { ".repeat(1000), "};".repeat(1000));
+        let err = parse_idl_message(&modules).unwrap_err();
+        assert!(matches!(err, ParseError::NestingTooDeep { .. }));
+    }
+}
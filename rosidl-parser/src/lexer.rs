@@ -1,4 +1,6 @@
+use crate::parser::Span;
 use logos::Logos;
+use std::ops::Range;
 
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t\r\n\f]+")] // Skip whitespace
@@ -75,6 +77,9 @@ pub enum TokenKind {
     #[token("/")]
     Slash,
 
+    #[token(",")]
+    Comma,
+
     #[token("---")]
     TripleDash,
 
@@ -84,6 +89,9 @@ pub enum TokenKind {
     #[token("-")]
     Minus,
 
+    #[token("+")]
+    Plus,
+
     // Identifiers (lowercase_with_underscores or UpperCamelCase)
     #[regex(r"[a-zA-Z][a-zA-Z0-9_]*")]
     Identifier,
@@ -124,14 +132,31 @@ pub enum TokenKind {
     False,
 }
 
+/// Token borrowing its text from the lexed input
 #[derive(Debug, Clone, PartialEq)]
-pub struct Token {
+pub struct Token<'a> {
     pub kind: TokenKind,
-    pub text: String,
+    pub text: &'a str,
     pub span: std::ops::Range<usize>,
 }
 
-pub fn lex(input: &str) -> Result<Vec<Token>, String> {
+/// Character the lexer does not recognize
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    /// Byte offset in the input
+    pub offset: usize,
+    pub text: String,
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unexpected character: '{}'", self.text)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+pub fn lex(input: &str) -> Result<Vec<Token<'_>>, LexError> {
     let mut lexer = TokenKind::lexer(input);
     let mut tokens = Vec::new();
 
@@ -140,16 +165,15 @@ pub fn lex(input: &str) -> Result<Vec<Token>, String> {
             Ok(kind) => {
                 tokens.push(Token {
                     kind,
-                    text: lexer.slice().to_string(),
+                    text: lexer.slice(),
                     span: lexer.span(),
                 });
             }
             Err(_) => {
-                return Err(format!(
-                    "Unexpected character at position {}: '{}'",
-                    lexer.span().start,
-                    lexer.slice()
-                ));
+                return Err(LexError {
+                    offset: lexer.span().start,
+                    text: lexer.slice().to_string(),
+                });
             }
         }
     }
@@ -157,6 +181,188 @@ pub fn lex(input: &str) -> Result<Vec<Token>, String> {
     Ok(tokens)
 }
 
+/// Value of a quoted string literal (`"a\"b"` or `'a'`), with escape sequences decoded
+///
+/// Supports the escapes of Python string literals, which rosidl follows:
+/// `\\ \' \" \n \t \r \a \b \f \v`, octal `\ooo`, `\xhh`, `\uhhhh` and
+/// `\Uhhhhhhhh`. Unknown or malformed sequences are kept as written.
+pub fn unescape(literal: &str) -> String {
+    let inner = literal
+        .get(1..literal.len().saturating_sub(1))
+        .unwrap_or_default();
+    let mut out = String::with_capacity(inner.len());
+    let mut rest = inner;
+    while let Some(index) = rest.find('\\') {
+        out.push_str(&rest[..index]);
+        let sequence = &rest[index + 1..];
+        let (decoded, len) = match sequence.chars().next() {
+            Some('\\') => (Some('\\'), 1),
+            Some('\'') => (Some('\''), 1),
+            Some('"') => (Some('"'), 1),
+            Some('n') => (Some('\n'), 1),
+            Some('t') => (Some('\t'), 1),
+            Some('r') => (Some('\r'), 1),
+            Some('a') => (Some('\x07'), 1),
+            Some('b') => (Some('\x08'), 1),
+            Some('f') => (Some('\x0c'), 1),
+            Some('v') => (Some('\x0b'), 1),
+            Some('0'..='7') => {
+                let digits = sequence
+                    .bytes()
+                    .take(3)
+                    .take_while(|b| (b'0'..=b'7').contains(b))
+                    .count();
+                (code_point(&sequence[..digits], 8), digits)
+            }
+            Some('x') => hex_escape(sequence, 2),
+            Some('u') => hex_escape(sequence, 4),
+            Some('U') => hex_escape(sequence, 8),
+            _ => (None, 0),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &sequence[len..];
+            }
+            None => {
+                out.push('\\');
+                rest = sequence;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `\x`/`\u`/`\U` escape at the start of `sequence` with exactly `digits` hex digits
+fn hex_escape(sequence: &str, digits: usize) -> (Option<char>, usize) {
+    match sequence.get(1..=digits) {
+        Some(hex) if hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            (code_point(hex, 16), digits + 1)
+        }
+        _ => (None, 0),
+    }
+}
+
+fn code_point(digits: &str, radix: u32) -> Option<char> {
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .and_then(char::from_u32)
+}
+
+/// Line comment skipped by [`lex`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// Text after the `#`, up to the end of the line
+    pub text: String,
+    /// Byte range of the comment, including the `#`
+    pub span: std::ops::Range<usize>,
+}
+
+/// Collect the comments of `input`, found in the gaps between its `tokens`
+pub fn comments(input: &str, tokens: &[Token]) -> Vec<Comment> {
+    comments_between(input, tokens.iter().map(|token| token.span.clone()))
+}
+
+/// Comments in the gaps between the sorted byte `ranges` of `input`
+fn comments_between(input: &str, ranges: impl Iterator<Item = Range<usize>>) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    let mut pos = 0;
+    // Each gap runs from the end of one range to the start of the next
+    let gaps = ranges
+        .map(|range| (range.start, range.end))
+        .chain(std::iter::once((input.len(), input.len())));
+    for (gap_end, next_pos) in gaps {
+        while let Some(offset) = input[pos..gap_end].find('#') {
+            let comment_start = pos + offset;
+            let comment_end = input[comment_start..gap_end]
+                .find('\n')
+                .map_or(gap_end, |offset| comment_start + offset);
+            comments.push(Comment {
+                text: input[comment_start + 1..comment_end].to_string(),
+                span: comment_start..comment_end,
+            });
+            pos = comment_end;
+        }
+        pos = next_pos;
+    }
+    comments
+}
+
+/// What a [`SpannedToken`] covers
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum SpannedTokenKind {
+    Token(TokenKind),
+    /// Line comment, including the `#`
+    Comment,
+    /// Text the lexer does not recognize
+    Error,
+}
+
+/// Piece of the input with its position, for editor tooling
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken<'a> {
+    pub kind: SpannedTokenKind,
+    pub text: &'a str,
+    /// Byte range in the input
+    pub range: Range<usize>,
+    /// Position of the first character
+    pub start: Span,
+    /// Position just past the last character
+    pub end: Span,
+}
+
+/// Tokens, comments and unrecognized text of `input`, in source order
+///
+/// Unlike [`lex`] this never fails: editors lex incomplete text, so characters
+/// the lexer does not recognize become [`SpannedTokenKind::Error`] tokens and
+/// lexing goes on after them. Only whitespace is left out. This is the entry
+/// point for syntax highlighting and similar tools and keeps its shape across
+/// releases; new kinds of tokens may be added.
+pub fn lex_with_positions(input: &str) -> Vec<SpannedToken<'_>> {
+    let mut pieces: Vec<(SpannedTokenKind, Range<usize>)> = TokenKind::lexer(input)
+        .spanned()
+        .map(|(kind, range)| match kind {
+            Ok(kind) => (SpannedTokenKind::Token(kind), range),
+            Err(_) => (SpannedTokenKind::Error, range),
+        })
+        .collect();
+    let comments = comments_between(input, pieces.iter().map(|(_, range)| range.clone()));
+    pieces.extend(
+        comments
+            .into_iter()
+            .map(|comment| (SpannedTokenKind::Comment, comment.span)),
+    );
+    pieces.sort_by_key(|(_, range)| range.start);
+
+    // Positions are counted in one pass rather than with `Span::at` per token
+    let mut cursor = 0;
+    let mut position = Span { line: 1, column: 1 };
+    let mut advance = |offset: usize| {
+        for c in input[cursor..offset].chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+        }
+        cursor = offset;
+        position
+    };
+    pieces
+        .into_iter()
+        .map(|(kind, range)| SpannedToken {
+            kind,
+            text: &input[range.clone()],
+            start: advance(range.start),
+            end: advance(range.end),
+            range,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +387,8 @@ mod tests {
         assert_eq!(tokens[0].kind, TokenKind::Int32);
         assert_eq!(tokens[1].kind, TokenKind::Identifier);
         assert_eq!(tokens[1].text, "x");
+        // Token text is a slice of the input, not a copy
+        assert!(std::ptr::eq(tokens[1].text, &input[6..]));
     }
 
     #[test]
@@ -229,6 +437,21 @@ mod tests {
         // Comments should be skipped
         assert_eq!(tokens.len(), 2); // Only int32 and x
         assert_eq!(tokens[0].kind, TokenKind::Int32);
+
+        let comments = comments(input, &tokens);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].text, " This is a comment");
+        assert_eq!(comments[1].text, " inline comment");
+        assert_eq!(&input[comments[1].span.clone()], "# inline comment");
+    }
+
+    #[test]
+    fn lex_comments_ignore_string_literals() {
+        let input = "string s \"a # b\" # note\r\n";
+        let tokens = lex(input).unwrap();
+        let comments = comments(input, &tokens);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].text, " note\r");
     }
 
     #[test]
@@ -295,4 +518,47 @@ mod tests {
             .unwrap();
         assert!(triple_dash_pos < minus_pos);
     }
+
+    #[test]
+    fn unescape_string_literals() {
+        assert_eq!(unescape(r#""hello\nworld""#), "hello\nworld");
+        assert_eq!(unescape(r#""say \"hi\"""#), "say \"hi\"");
+        assert_eq!(unescape(r"'it\'s'"), "it's");
+        assert_eq!(unescape(r#""a\\b\tc""#), "a\\b\tc");
+        assert_eq!(
+            unescape(r#""\x41\101\u00e9\U0001F600\0""#),
+            "AA\u{e9}\u{1F600}\0"
+        );
+        // Unknown and malformed escapes are kept
+        assert_eq!(unescape(r#""\q \x4 \uD800""#), r"\q \x4 \uD800");
+        assert_eq!(unescape(r#""""#), "");
+    }
+
+    #[test]
+    fn lex_with_positions_spans() {
+        let input = "# Pose é\nstd_msgs/Header h $ # hi\n  int32 X=1\n";
+        let tokens: Vec<_> = lex_with_positions(input)
+            .into_iter()
+            .map(|t| (t.kind, t.text, (t.start.line, t.start.column), t.end.column))
+            .collect();
+        use SpannedTokenKind::{Comment, Error, Token as T};
+        assert_eq!(
+            tokens,
+            [
+                (Comment, "# Pose é", (1, 1), 9),
+                (T(TokenKind::Identifier), "std_msgs", (2, 1), 9),
+                (T(TokenKind::Slash), "/", (2, 9), 10),
+                (T(TokenKind::Identifier), "Header", (2, 10), 16),
+                (T(TokenKind::Identifier), "h", (2, 17), 18),
+                (Error, "$", (2, 19), 20),
+                (Comment, "# hi", (2, 21), 25),
+                (T(TokenKind::Int32), "int32", (3, 3), 8),
+                (T(TokenKind::Identifier), "X", (3, 9), 10),
+                (T(TokenKind::Equals), "=", (3, 10), 11),
+                (T(TokenKind::DecimalInteger), "1", (3, 11), 12),
+            ]
+        );
+        let token = &lex_with_positions(input)[3];
+        assert_eq!(&input[token.range.clone()], token.text);
+    }
 }
@@ -1,14 +1,86 @@
 pub mod ast;
+pub mod diff;
+pub mod format;
+pub mod idl;
 pub mod lexer;
+pub mod lint;
+pub mod package;
 pub mod parser;
+pub mod validate;
+pub mod visit;
 
-pub use ast::{Action, ActionSpec, Constant, Field, FieldType, Message, PrimitiveType, Service};
-pub use lexer::{Token, TokenKind};
-pub use parser::{parse_action, parse_message, parse_service, ParseError};
+pub use ast::{
+    Action, ActionSpec, Annotation, BuiltinType, Constant, Field, FieldType, Message,
+    PrimitiveType, Section, Service,
+};
+pub use diff::{diff, diff_actions, diff_messages, diff_services, Change, InterfaceDiff};
+pub use format::{format_action, format_message, format_service};
+pub use idl::{parse_idl_action, parse_idl_message, parse_idl_service};
+pub use lexer::{lex_with_positions, LexError, SpannedToken, SpannedTokenKind, Token, TokenKind};
+pub use lint::{lint_interface, Lint, LintRule};
+pub use package::{
+    parse_action_file, parse_message_file, parse_package, parse_service_file, FileError,
+    ParsedPackage,
+};
+pub use parser::{
+    parse_action, parse_message, parse_message_lenient, parse_service, ParseError, ParseOptions,
+    Span,
+};
+pub use validate::{
+    validate_action, validate_message, validate_service, ValidationRule, Violation,
+};
+pub use visit::{walk_action, walk_message, walk_service, Visitor};
+
+/// Fuzzing entry point: every parser and the lints on `data`
+///
+/// Malformed input must give errors, never a panic or a [`ParseError::Internal`].
+/// Driven by the `cargo fuzz` target in `rosidl-parser/fuzz`.
+#[doc(hidden)]
+pub fn fuzz_parse(data: &[u8]) {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let strict = ParseOptions { strict: true };
+    let errors = [
+        parse_message(input).err(),
+        strict.parse_message(input).err(),
+        parse_service(input).err(),
+        parse_action(input).err(),
+        parse_idl_message(input).err(),
+        parse_idl_service(input).err(),
+        parse_idl_action(input).err(),
+        lint_interface("Fuzz", input).err(),
+    ];
+    let tokens = lex_with_positions(input);
+    assert!(tokens
+        .windows(2)
+        .all(|pair| pair[0].range.end <= pair[1].range.start));
+    let (_, lenient_errors) = parse_message_lenient(input);
+    for err in errors.iter().flatten().chain(&lenient_errors) {
+        assert!(!matches!(err, ParseError::Internal { .. }), "{}", err);
+        // Error messages and snippets are rendered for arbitrary input too
+        let _ = err.snippet(input);
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn fuzz_arbitrary_text(input in "\\PC*") {
+            fuzz_parse(input.as_bytes());
+        }
+
+        #[test]
+        fn fuzz_interface_like_text(
+            input in "([a-z0-9_/:;{}<>=,.'\"#@()\\\\ \t\r\n\\[\\]-]|int32|string|sequence|module|struct|0x|---){0,40}"
+        ) {
+            fuzz_parse(input.as_bytes());
+        }
+    }
 
     #[test]
     fn parse_simple_message() {
@@ -18,4 +90,32 @@ mod tests {
         let msg = result.unwrap();
         assert_eq!(msg.fields.len(), 2);
     }
+
+    #[test]
+    fn classify_builtin_types() {
+        let msg = parse_message(
+            "builtin_interfaces/Time stamp\nbuiltin_interfaces/Duration timeout\n\
+             builtin_interfaces/Time[] stamps\nTime local\nstd_msgs/Header header\n",
+        )
+        .unwrap();
+        let builtins: Vec<_> = msg
+            .fields
+            .iter()
+            .map(|field| field.field_type.builtin_type())
+            .collect();
+        assert_eq!(
+            builtins,
+            [
+                Some(BuiltinType::Time),
+                Some(BuiltinType::Duration),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            BuiltinType::Duration.to_string(),
+            "builtin_interfaces/Duration"
+        );
+    }
 }
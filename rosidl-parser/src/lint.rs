@@ -0,0 +1,199 @@
+//! Style lints for interface definitions
+//!
+//! [`lint_interface`] checks the naming conventions of the ROS 2 interface
+//! design guide: fields in `snake_case`, constants in `SCREAMING_CASE` and types in
+//! `CamelCase`, and flags the deprecated `byte` and `char` types. Lints are
+//! warnings; the definition still parses and generates.
+
+use crate::ast::{FieldType, PrimitiveType};
+use crate::parser::{element_sites, ParseResult, Span};
+use std::fmt;
+
+/// Naming rule a lint is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintRule {
+    /// Field names are `snake_case`
+    FieldName,
+    /// Constant names are `SCREAMING_CASE`
+    ConstantName,
+    /// Interface and message type names are `CamelCase`
+    TypeName,
+    /// `byte` and `char`, whose meaning changed since ROS 1
+    DeprecatedType,
+}
+
+/// Style warning for an interface definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: LintRule,
+    pub message: String,
+    /// Offending name in the text; `None` for the interface name, which comes
+    /// from the file name
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.span {
+            Some(span) => write!(f, "{}: {}", span, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Lint the `.msg`, `.srv` or `.action` text `input` of the interface `name`
+///
+/// Fails only if `input` does not parse.
+pub fn lint_interface(name: &str, input: &str) -> ParseResult<Vec<Lint>> {
+    let mut lints = Vec::new();
+    if !is_camel_case(name) {
+        lints.push(Lint {
+            rule: LintRule::TypeName,
+            message: format!("interface name `{}` is not CamelCase", name),
+            span: None,
+        });
+    }
+
+    for site in element_sites(input)? {
+        if let Some(type_name) = message_type_name(&site.field_type) {
+            if !is_camel_case(type_name) {
+                lints.push(Lint {
+                    rule: LintRule::TypeName,
+                    message: format!("type name `{}` is not CamelCase", type_name),
+                    span: Some(site.type_span),
+                });
+            }
+        }
+        if let Some(note) = primitive_type(&site.field_type).and_then(|p| p.deprecation()) {
+            lints.push(Lint {
+                rule: LintRule::DeprecatedType,
+                message: format!("deprecated type: {}", note),
+                span: Some(site.type_span),
+            });
+        }
+        if site.is_constant && !is_screaming_case(&site.name) {
+            lints.push(Lint {
+                rule: LintRule::ConstantName,
+                message: format!("constant name `{}` is not SCREAMING_CASE", site.name),
+                span: Some(site.name_span),
+            });
+        } else if !site.is_constant && !is_snake_case(&site.name) {
+            lints.push(Lint {
+                rule: LintRule::FieldName,
+                message: format!("field name `{}` is not snake_case", site.name),
+                span: Some(site.name_span),
+            });
+        }
+    }
+    Ok(lints)
+}
+
+/// Name of the message type in `field_type`, looking through arrays and sequences
+fn message_type_name(field_type: &FieldType) -> Option<&str> {
+    match field_type {
+        FieldType::NamespacedType { name, .. } => Some(name),
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => message_type_name(element_type),
+        _ => None,
+    }
+}
+
+/// Primitive type in `field_type`, looking through arrays and sequences
+fn primitive_type(field_type: &FieldType) -> Option<PrimitiveType> {
+    match field_type {
+        FieldType::Primitive(primitive) => Some(*primitive),
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => primitive_type(element_type),
+        _ => None,
+    }
+}
+
+/// `lower`, `lower_case2`: no leading digit, no double or trailing underscore
+fn is_snake_case(name: &str) -> bool {
+    is_separated(name, |c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// `UPPER`, `UPPER_CASE2`
+fn is_screaming_case(name: &str) -> bool {
+    is_separated(name, |c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Words of `allowed` characters joined by single underscores, starting with a letter
+fn is_separated(name: &str, allowed: impl Fn(char) -> bool) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .split('_')
+            .all(|word| !word.is_empty() && word.chars().all(&allowed))
+}
+
+/// `CamelCase`: an uppercase letter followed by letters and digits
+fn is_camel_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_names() {
+        let input = "int32 MAX_SIZE=10\nint32 minSize=1\n\
+                     geometry_msgs/point[] waypoints\n\
+                     float64 linearVelocity\n\
+                     uint8 trailing_\n\
+                     string frame_id2\n\
+                     ---\n\
+                     bool Ok\n";
+        let lints = lint_interface("bad_name", input).unwrap();
+        let found: Vec<_> = lints
+            .iter()
+            .map(|lint| (lint.rule, lint.span.map(|span| (span.line, span.column))))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (LintRule::TypeName, None),
+                (LintRule::ConstantName, Some((2, 7))),
+                (LintRule::TypeName, Some((3, 1))),
+                (LintRule::FieldName, Some((4, 9))),
+                (LintRule::FieldName, Some((5, 7))),
+                (LintRule::FieldName, Some((8, 6))),
+            ]
+        );
+        assert_eq!(
+            lints[3].to_string(),
+            "line 4, column 9: field name `linearVelocity` is not snake_case"
+        );
+    }
+
+    #[test]
+    fn lint_deprecated_types() {
+        let input = "uint8 ok\nbyte[4] raw\nchar LETTER=65\n";
+        let lints = lint_interface("Legacy", input).unwrap();
+        let found: Vec<_> = lints
+            .iter()
+            .map(|lint| (lint.rule, lint.span.map(|span| (span.line, span.column))))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (LintRule::DeprecatedType, Some((2, 1))),
+                (LintRule::DeprecatedType, Some((3, 1))),
+            ]
+        );
+        assert_eq!(
+            lints[1].to_string(),
+            "line 3, column 1: deprecated type: `char` is a uint8 in ROS 2, not a character; use `uint8`"
+        );
+    }
+
+    #[test]
+    fn lint_conforming_interface() {
+        let input = "# Doc\nuint8 STATUS_OK=0\nstd_msgs/Header header\nPoint2D[<=4] corners\n";
+        assert!(lint_interface("PolygonStamped", input).unwrap().is_empty());
+        assert!(lint_interface("Broken", "int32 = 1\n").is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Parsing of whole interface packages
+//!
+//! [`parse_package`] reads the `msg/`, `srv/` and `action/` directories of a
+//! package, either its source tree or its installed share directory. Each
+//! interface is parsed from its `.msg`/`.srv`/`.action` file, or from the `.idl`
+//! when the package was installed without the original source. Files that fail
+//! to read or parse are collected in [`ParsedPackage::errors`] while the rest of
+//! the package is still parsed.
+
+use crate::ast::{Action, Message, Service};
+use crate::idl::{parse_idl_action, parse_idl_message, parse_idl_service};
+use crate::parser::{parse_action, parse_message_lenient, parse_service, ParseError};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Interfaces of a package, keyed by interface name
+#[derive(Debug, Default)]
+pub struct ParsedPackage {
+    pub messages: BTreeMap<String, Message>,
+    pub services: BTreeMap<String, Service>,
+    pub actions: BTreeMap<String, Action>,
+    /// Files that could not be read or parsed, in path order
+    pub errors: Vec<FileError>,
+}
+
+/// Failure to load one interface file
+#[derive(Debug, Error)]
+pub enum FileError {
+    #[error("Failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+
+    /// `report` holds every error as a snippet of the offending line (all errors
+    /// of a `.msg` file, the first one otherwise)
+    #[error("Failed to parse {}\n{report}", path.display())]
+    Parse {
+        path: PathBuf,
+        errors: Vec<ParseError>,
+        report: String,
+    },
+}
+
+impl FileError {
+    /// File the error is about
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io { path, .. } | Self::Parse { path, .. } => path,
+        }
+    }
+}
+
+/// Parse every interface of the package rooted at `dir`
+///
+/// Missing `msg/`, `srv/` or `action/` directories are skipped; only failing to
+/// list an existing one is an error.
+pub fn parse_package(dir: &Path) -> io::Result<ParsedPackage> {
+    let mut package = ParsedPackage::default();
+    let messages = parse_files(
+        interface_files(&dir.join("msg"), "msg")?,
+        parse_message_file,
+    );
+    for (name, result) in messages {
+        match result {
+            Ok(message) => {
+                package.messages.insert(name, message);
+            }
+            Err(err) => package.errors.push(err),
+        }
+    }
+    let services = parse_files(
+        interface_files(&dir.join("srv"), "srv")?,
+        parse_service_file,
+    );
+    for (name, result) in services {
+        match result {
+            Ok(service) => {
+                package.services.insert(name, service);
+            }
+            Err(err) => package.errors.push(err),
+        }
+    }
+    let actions = parse_files(
+        interface_files(&dir.join("action"), "action")?,
+        parse_action_file,
+    );
+    for (name, result) in actions {
+        match result {
+            Ok(action) => {
+                package.actions.insert(name, action);
+            }
+            Err(err) => package.errors.push(err),
+        }
+    }
+    Ok(package)
+}
+
+/// Parse each file, in parallel with the `rayon` feature, keeping the order of
+/// `files`
+#[cfg(feature = "rayon")]
+fn parse_files<T: Send>(
+    files: Vec<(String, PathBuf)>,
+    parse: fn(&Path) -> Result<T, FileError>,
+) -> Vec<(String, Result<T, FileError>)> {
+    use rayon::prelude::*;
+
+    files
+        .into_par_iter()
+        .map(|(name, path)| {
+            let result = parse(&path);
+            (name, result)
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn parse_files<T>(
+    files: Vec<(String, PathBuf)>,
+    parse: fn(&Path) -> Result<T, FileError>,
+) -> Vec<(String, Result<T, FileError>)> {
+    files
+        .into_iter()
+        .map(|(name, path)| {
+            let result = parse(&path);
+            (name, result)
+        })
+        .collect()
+}
+
+/// Parse a `.msg` or `.idl` message file, reporting every error of a `.msg`
+pub fn parse_message_file(path: &Path) -> Result<Message, FileError> {
+    let content = read(path)?;
+    let result = if is_idl(path) {
+        parse_idl_message(&content).map_err(|err| vec![err])
+    } else {
+        match parse_message_lenient(&content) {
+            (message, errors) if errors.is_empty() => Ok(message),
+            (_, errors) => Err(errors),
+        }
+    };
+    result.map_err(|errors| parse_error(path, &content, errors))
+}
+
+/// Parse a `.srv` or `.idl` service file
+pub fn parse_service_file(path: &Path) -> Result<Service, FileError> {
+    let content = read(path)?;
+    if is_idl(path) {
+        parse_idl_service(&content)
+    } else {
+        parse_service(&content)
+    }
+    .map_err(|err| parse_error(path, &content, vec![err]))
+}
+
+/// Parse an `.action` or `.idl` action file
+pub fn parse_action_file(path: &Path) -> Result<Action, FileError> {
+    let content = read(path)?;
+    if is_idl(path) {
+        parse_idl_action(&content)
+    } else {
+        parse_action(&content)
+    }
+    .map_err(|err| parse_error(path, &content, vec![err]))
+}
+
+/// Interface names in `dir` with the file to parse for each, in name order
+///
+/// `Name.<extension>` is preferred over the `Name.idl` generated from it.
+fn interface_files(dir: &Path, extension: &str) -> io::Result<Vec<(String, PathBuf)>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = BTreeSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_interface = path
+            .extension()
+            .is_some_and(|ext| ext == extension || ext == "idl");
+        if !is_interface || !path.is_file() {
+            continue;
+        }
+        if let Some(stem) = path.file_stem() {
+            names.insert(stem.to_string_lossy().to_string());
+        }
+    }
+    Ok(names
+        .into_iter()
+        .map(|name| {
+            let source = dir.join(format!("{}.{}", name, extension));
+            let path = if source.exists() {
+                source
+            } else {
+                source.with_extension("idl")
+            };
+            (name, path)
+        })
+        .collect())
+}
+
+fn is_idl(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "idl")
+}
+
+fn read(path: &Path) -> Result<String, FileError> {
+    std::fs::read_to_string(path).map_err(|source| FileError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn parse_error(path: &Path, content: &str, errors: Vec<ParseError>) -> FileError {
+    let report = errors
+        .iter()
+        .map(|err| err.snippet(content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    FileError::Parse {
+        path: path.to_path_buf(),
+        errors,
+        report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parse_package_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("msg")).unwrap();
+        fs::create_dir_all(root.join("srv")).unwrap();
+        fs::write(root.join("msg/Point.msg"), "float64 x\nfloat64 y\n").unwrap();
+        // The .msg wins over the .idl generated from it
+        fs::write(root.join("msg/Point.idl"), "not idl").unwrap();
+        fs::write(
+            root.join("msg/Empty.idl"),
+            "module pkg {\n  module msg {\n    struct Empty {\n      uint8 structure_needs_at_least_one_member;\n    };\n  };\n};\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("msg/Broken.msg"),
+            "int32 x\nfloat64 = 5\nbool @\n",
+        )
+        .unwrap();
+        fs::write(root.join("msg/README.md"), "ignored").unwrap();
+        fs::write(root.join("srv/AddTwoInts.srv"), "int64 a\n---\nint64 sum\n").unwrap();
+
+        let package = parse_package(root).unwrap();
+        assert_eq!(
+            package.messages.keys().collect::<Vec<_>>(),
+            ["Empty", "Point"]
+        );
+        assert_eq!(package.messages["Point"].fields.len(), 2);
+        assert!(package.messages["Empty"].is_empty());
+        assert_eq!(
+            package.services["AddTwoInts"].response.fields[0].name,
+            "sum"
+        );
+        assert!(package.actions.is_empty());
+
+        assert_eq!(package.errors.len(), 1);
+        let err = &package.errors[0];
+        assert_eq!(err.path(), root.join("msg/Broken.msg"));
+        assert!(matches!(err, FileError::Parse { errors, .. } if errors.len() == 2));
+        assert!(err.to_string().contains("\n2 | float64 = 5\n"));
+    }
+
+    #[test]
+    fn parse_missing_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let package = parse_package(&dir.path().join("missing")).unwrap();
+        assert!(package.messages.is_empty() && package.errors.is_empty());
+
+        let err = parse_action_file(&dir.path().join("Fibonacci.action")).unwrap_err();
+        assert!(matches!(err, FileError::Io { .. }));
+    }
+}
@@ -1,45 +1,335 @@
 use crate::ast::*;
-use crate::lexer::{Token, TokenKind};
+use crate::lexer::{unescape, Token, TokenKind};
+use crate::validate::{constant_violations, field_violations, ValidationRule, Violation};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum ParseError {
-    #[error("Unexpected token: expected {expected}, got {got}")]
-    UnexpectedToken { expected: String, got: String },
+/// Location in the source text (1-based line and column)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Span {
+    pub line: usize,
+    /// Column in characters
+    pub column: usize,
+}
 
-    #[error("Unexpected end of input")]
-    UnexpectedEOF,
+impl Span {
+    /// Location of byte `offset` in `input`; offsets inside a character count
+    /// as that character
+    pub fn at(input: &str, offset: usize) -> Self {
+        let mut end = offset.min(input.len());
+        while !input.is_char_boundary(end) {
+            end -= 1;
+        }
+        let before = &input[..end];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
 
-    #[error("Invalid integer literal: {0}")]
-    InvalidInteger(String),
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
 
-    #[error("Invalid float literal: {0}")]
-    InvalidFloat(String),
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Unexpected token at {span}: expected {expected}, got {got}")]
+    UnexpectedToken {
+        expected: String,
+        got: String,
+        span: Span,
+    },
+
+    #[error("Unexpected end of input at {span}")]
+    UnexpectedEOF { span: Span },
+
+    #[error("Invalid integer literal at {span}: {text}")]
+    InvalidInteger { text: String, span: Span },
+
+    #[error("Invalid float literal at {span}: {text}")]
+    InvalidFloat { text: String, span: Span },
+
+    #[error("Unknown type at {span}: {name}")]
+    UnknownType { name: String, span: Span },
+
+    #[error("Lexer error at {span}: {message}")]
+    LexerError { message: String, span: Span },
+
+    /// The span is the end of the input
+    #[error("Missing IDL struct at {span}: {name}")]
+    MissingStruct { name: String, span: Span },
+
+    #[error("Invalid default value at {span}: {message}")]
+    InvalidDefault { message: String, span: Span },
+
+    #[error("Integer out of range at {span}: {text} does not fit in {type_name}")]
+    IntegerOutOfRange {
+        text: String,
+        type_name: String,
+        span: Span,
+    },
+
+    #[error("Invalid char literal at {span}: {text} is not a single character up to U+00FF")]
+    InvalidChar { text: String, span: Span },
+
+    /// Byte order mark, CRLF line ending or trailing whitespace, only reported
+    /// in strict mode
+    #[error("Disallowed formatting at {span}: {issue}")]
+    Formatting { issue: &'static str, span: Span },
+
+    /// Bounded strings count bytes, bounded wide strings UTF-16 code units
+    #[error("String too long at {span}: {text} does not fit in {type_name}")]
+    StringTooLong {
+        text: String,
+        type_name: String,
+        span: Span,
+    },
+
+    /// Element that parses but breaks a rule of the rosidl specification, only
+    /// reported in strict mode
+    #[error("Specification violation at {span}: {violation}")]
+    SpecViolation { violation: Violation, span: Span },
+
+    /// Modules or sequences nested deeper than the parser recurses (IDL only)
+    #[error("Nesting too deep at {span}: more than {limit} levels")]
+    NestingTooDeep { limit: usize, span: Span },
+
+    /// A bug in the parser, such as a panic, reported instead of unwinding
+    /// into the caller; the span is the start of the input
+    #[error("Internal parser error at {span}: {message}")]
+    Internal { message: String, span: Span },
+}
 
-    #[error("Unknown type: {0}")]
-    UnknownType(String),
+impl ParseError {
+    /// Location of the error
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnexpectedToken { span, .. }
+            | Self::UnexpectedEOF { span }
+            | Self::InvalidInteger { span, .. }
+            | Self::InvalidFloat { span, .. }
+            | Self::UnknownType { span, .. }
+            | Self::LexerError { span, .. }
+            | Self::MissingStruct { span, .. }
+            | Self::InvalidDefault { span, .. }
+            | Self::IntegerOutOfRange { span, .. }
+            | Self::InvalidChar { span, .. }
+            | Self::Formatting { span, .. }
+            | Self::StringTooLong { span, .. }
+            | Self::SpecViolation { span, .. }
+            | Self::NestingTooDeep { span, .. }
+            | Self::Internal { span, .. } => *span,
+        }
+    }
 
-    #[error("Lexer error: {0}")]
-    LexerError(String),
+    /// The error followed by the offending line of `input`, with a caret under
+    /// the error column:
+    ///
+    /// ```text
+    /// Unknown type at line 2, column 1: =
+    ///   |
+    /// 2 | = 5
+    ///   | ^
+    /// ```
+    pub fn snippet(&self, input: &str) -> String {
+        let span = self.span();
+        let line = input
+            .lines()
+            .nth(span.line.saturating_sub(1))
+            .unwrap_or_default();
+        let number = span.line.to_string();
+        let gutter = " ".repeat(number.len());
+        format!(
+            "{}\n{} |\n{} | {}\n{} | {}^",
+            self,
+            gutter,
+            number,
+            line,
+            gutter,
+            " ".repeat(span.column.saturating_sub(1))
+        )
+    }
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
-struct Parser {
-    tokens: Vec<Token>,
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token<'a>>,
     pos: usize,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    /// Comment text by line
+    comments: BTreeMap<usize, String>,
+    /// Lines holding at least one token
+    code_lines: BTreeSet<usize>,
+    /// Every field and constant parsed so far, in source order
+    sites: Vec<ElementSite>,
+    /// Reject elements that break the rules of [`crate::validate`]
+    strict: bool,
+}
+
+/// Where a field or constant was declared
+#[derive(Debug, Clone)]
+pub(crate) struct ElementSite {
+    pub name: String,
+    pub name_span: Span,
+    pub field_type: FieldType,
+    pub type_span: Span,
+    pub is_constant: bool,
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
+fn lexer_error(input: &str, err: &crate::lexer::LexError) -> ParseError {
+    ParseError::LexerError {
+        message: err.to_string(),
+        span: Span::at(input, err.offset),
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> ParseResult<Self> {
+        let tokens = crate::lexer::lex(input).map_err(|err| lexer_error(input, &err))?;
+        Ok(Self::from_tokens(input, input, tokens))
+    }
+
+    /// Parser over the lines of `input` that lex, with an error for each line that does not
+    fn new_lenient(input: &'a str) -> (Self, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        // Failing lines are blanked out, which keeps the byte offsets of the rest
+        let mut source = input.to_string();
+        let tokens = loop {
+            match crate::lexer::lex(&source) {
+                // Tokens lie outside the blanked lines, so their text is that of `input`
+                Ok(tokens) => {
+                    break tokens
+                        .into_iter()
+                        .map(|token| Token {
+                            text: input.get(token.span.clone()).unwrap_or_default(),
+                            kind: token.kind,
+                            span: token.span,
+                        })
+                        .collect()
+                }
+                Err(err) => {
+                    errors.push(lexer_error(input, &err));
+                    let start = source[..err.offset].rfind('\n').map_or(0, |i| i + 1);
+                    let end = source[err.offset..]
+                        .find('\n')
+                        .map_or(source.len(), |i| err.offset + i);
+                    source.replace_range(start..end, &" ".repeat(end - start));
+                }
+            }
+        };
+        (Self::from_tokens(input, &source, tokens), errors)
+    }
+
+    /// `source` is `input` or a copy of it with the same byte offsets
+    fn from_tokens(input: &'a str, source: &str, tokens: Vec<Token<'a>>) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut parser = Self {
+            input,
+            tokens: Vec::new(),
+            pos: 0,
+            line_starts,
+            comments: BTreeMap::new(),
+            code_lines: BTreeSet::new(),
+            sites: Vec::new(),
+            strict: false,
+        };
+
+        for comment in crate::lexer::comments(source, &tokens) {
+            let text = comment.text.trim_end();
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            parser
+                .comments
+                .insert(parser.line_of(comment.span.start), text.to_string());
+        }
+        parser.code_lines = tokens
+            .iter()
+            .map(|token| parser.line_of(token.span.start))
+            .collect();
+        parser.tokens = tokens;
+        parser
+    }
+
+    /// Advance to the first token after `line`
+    fn skip_past_line(&mut self, line: usize) {
+        while self.pos < self.tokens.len() && self.token_line(self.pos) <= line {
+            self.pos += 1;
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset) - 1
+    }
+
+    /// Line of the token at `index`
+    fn token_line(&self, index: usize) -> usize {
+        self.line_of(self.tokens[index].span.start)
+    }
+
+    /// Whether `line` holds a comment and nothing else
+    fn is_comment_line(&self, line: usize) -> bool {
+        self.comments.contains_key(&line) && !self.code_lines.contains(&line)
     }
 
-    fn current(&self) -> Option<&Token> {
+    /// First line of the comment block directly above the element on `line`
+    ///
+    /// As in rosidl, a block reaching up to `section_start` is the header of the
+    /// section rather than the element's; the element then keeps only the
+    /// annotations at the end of the block.
+    fn leading_comment_start(&self, line: usize, section_start: usize) -> usize {
+        let mut start = line;
+        while start > section_start && self.is_comment_line(start - 1) {
+            start -= 1;
+        }
+        if start > section_start || start == line {
+            return start;
+        }
+        let mut start = line;
+        while start > section_start
+            && Annotation::from_comment(&self.comments[&(start - 1)]).is_some()
+        {
+            start -= 1;
+        }
+        start
+    }
+
+    /// Comments of the element spanning tokens `first..=last`: the comment lines
+    /// directly above it, then its trailing comment
+    fn element_comments(&self, first: usize, last: usize, section_start: usize) -> Vec<String> {
+        let line = self.token_line(first);
+        let mut comments: Vec<String> = self
+            .comments
+            .range(self.leading_comment_start(line, section_start)..line)
+            .map(|(_, text)| text.clone())
+            .collect();
+        comments.extend(self.comments.get(&self.token_line(last)).cloned());
+        comments
+    }
+
+    /// Comment lines in `lines` that hold nothing else
+    fn comment_lines(&self, lines: std::ops::Range<usize>) -> Vec<String> {
+        self.comments
+            .range(lines)
+            .filter(|(line, _)| !self.code_lines.contains(line))
+            .map(|(_, text)| text.clone())
+            .collect()
+    }
+
+    fn current(&self) -> Option<&Token<'a>> {
         self.tokens.get(self.pos)
     }
 
-    fn advance(&mut self) -> Option<&Token> {
+    fn advance(&mut self) -> Option<&Token<'a>> {
         if self.pos < self.tokens.len() {
             let token = &self.tokens[self.pos];
             self.pos += 1;
@@ -49,30 +339,68 @@ impl Parser {
         }
     }
 
-    fn expect(&mut self, kind: TokenKind) -> ParseResult<String> {
-        match self.advance() {
-            Some(token) if token.kind == kind => Ok(token.text.clone()),
-            Some(token) => Err(ParseError::UnexpectedToken {
-                expected: format!("{:?}", kind),
-                got: token.text.clone(),
-            }),
-            None => Err(ParseError::UnexpectedEOF),
+    /// Like `advance`, failing at the end of input
+    fn advance_or_eof(&mut self) -> ParseResult<&Token<'a>> {
+        if self.pos < self.tokens.len() {
+            self.pos += 1;
+            Ok(&self.tokens[self.pos - 1])
+        } else {
+            Err(ParseError::UnexpectedEOF {
+                span: Span::at(self.input, self.input.len()),
+            })
+        }
+    }
+
+    /// Span of the current token (the end of input if there is none)
+    fn current_span(&self) -> Span {
+        let offset = self.current().map_or(self.input.len(), |t| t.span.start);
+        Span::at(self.input, offset)
+    }
+
+    /// Span of the token consumed last
+    fn previous_span(&self) -> Span {
+        Span::at(self.input, self.tokens[self.pos - 1].span.start)
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> ParseResult<&'a str> {
+        let token = self.advance_or_eof()?;
+        if token.kind == kind {
+            return Ok(token.text);
         }
+        let got = token.text.to_string();
+        Err(ParseError::UnexpectedToken {
+            expected: format!("{:?}", kind),
+            got,
+            span: self.previous_span(),
+        })
     }
 
-    fn parse_integer(&self, text: &str, kind: &TokenKind) -> ParseResult<i64> {
-        let result = match kind {
-            TokenKind::HexInteger => i64::from_str_radix(&text[2..], 16),
-            TokenKind::BinaryInteger => i64::from_str_radix(&text[2..], 2),
-            TokenKind::OctalInteger => i64::from_str_radix(&text[2..], 8),
-            TokenKind::DecimalInteger => text.parse(),
-            _ => return Err(ParseError::InvalidInteger(text.to_string())),
+    /// Value of the integer token consumed last, negated when `negative`
+    ///
+    /// Any magnitude up to u64::MAX is accepted; [`integer_constant`] narrows it
+    /// to the declared type.
+    fn parse_integer(&self, text: &str, kind: &TokenKind, negative: bool) -> ParseResult<i128> {
+        let (digits, radix) = match kind {
+            // The lexer guarantees the `0x`/`0b`/`0o` prefix; a missing one fails below
+            TokenKind::HexInteger => (text.get(2..).unwrap_or_default(), 16),
+            TokenKind::BinaryInteger => (text.get(2..).unwrap_or_default(), 2),
+            TokenKind::OctalInteger => (text.get(2..).unwrap_or_default(), 8),
+            _ => (text, 10),
         };
-        result.map_err(|_| ParseError::InvalidInteger(text.to_string()))
+        let magnitude =
+            u64::from_str_radix(digits, radix).map_err(|_| ParseError::InvalidInteger {
+                text: signed_text(text, negative),
+                span: self.previous_span(),
+            })?;
+        Ok(if negative {
+            -i128::from(magnitude)
+        } else {
+            i128::from(magnitude)
+        })
     }
 
     fn parse_field_type(&mut self) -> ParseResult<FieldType> {
-        let token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
+        let token = self.advance_or_eof()?;
 
         let base_type = match &token.kind {
             // Primitive types
@@ -95,10 +423,10 @@ impl Parser {
                 // Check for bounded string (string<=N)
                 if matches!(self.current().map(|t| &t.kind), Some(TokenKind::LessEqual)) {
                     self.advance(); // consume <=
-                    let size_token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
-                    let text = size_token.text.clone();
+                    let size_token = self.advance_or_eof()?;
+                    let text = size_token.text;
                     let kind = size_token.kind.clone();
-                    let size = self.parse_integer(&text, &kind)?;
+                    let size = self.parse_integer(text, &kind, false)?;
                     FieldType::BoundedString(size as usize)
                 } else {
                     FieldType::String
@@ -108,10 +436,10 @@ impl Parser {
             TokenKind::WString => {
                 if matches!(self.current().map(|t| &t.kind), Some(TokenKind::LessEqual)) {
                     self.advance();
-                    let size_token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
-                    let text = size_token.text.clone();
+                    let size_token = self.advance_or_eof()?;
+                    let text = size_token.text;
                     let kind = size_token.kind.clone();
-                    let size = self.parse_integer(&text, &kind)?;
+                    let size = self.parse_integer(text, &kind, false)?;
                     FieldType::BoundedWString(size as usize)
                 } else {
                     FieldType::WString
@@ -120,14 +448,14 @@ impl Parser {
 
             // Namespaced types (package/Type or Type)
             TokenKind::Identifier => {
-                let name = token.text.clone();
+                let name = token.text.to_string();
                 // Check for namespace separator
                 if matches!(self.current().map(|t| &t.kind), Some(TokenKind::Slash)) {
                     self.advance(); // consume /
                     let type_name = self.expect(TokenKind::Identifier)?;
                     FieldType::NamespacedType {
                         package: Some(name),
-                        name: type_name,
+                        name: type_name.to_string(),
                     }
                 } else {
                     FieldType::NamespacedType {
@@ -137,7 +465,13 @@ impl Parser {
                 }
             }
 
-            _ => return Err(ParseError::UnknownType(token.text.clone())),
+            _ => {
+                let name = token.text.to_string();
+                return Err(ParseError::UnknownType {
+                    name,
+                    span: self.previous_span(),
+                });
+            }
         };
 
         // Check for array/sequence specifiers
@@ -155,10 +489,10 @@ impl Parser {
                 Some(TokenKind::LessEqual) => {
                     // Bounded sequence: type[<=N]
                     self.advance();
-                    let size_token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
-                    let text = size_token.text.clone();
+                    let size_token = self.advance_or_eof()?;
+                    let text = size_token.text;
                     let kind = size_token.kind.clone();
-                    let size = self.parse_integer(&text, &kind)?;
+                    let size = self.parse_integer(text, &kind, false)?;
                     self.expect(TokenKind::RBracket)?;
                     Ok(FieldType::BoundedSequence {
                         element_type: Box::new(base_type),
@@ -172,10 +506,10 @@ impl Parser {
                     | TokenKind::OctalInteger,
                 ) => {
                     // Fixed array: type[N]
-                    let size_token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
-                    let text = size_token.text.clone();
+                    let size_token = self.advance_or_eof()?;
+                    let text = size_token.text;
                     let kind = size_token.kind.clone();
-                    let size = self.parse_integer(&text, &kind)?;
+                    let size = self.parse_integer(text, &kind, false)?;
                     self.expect(TokenKind::RBracket)?;
                     Ok(FieldType::Array {
                         element_type: Box::new(base_type),
@@ -184,7 +518,11 @@ impl Parser {
                 }
                 _ => Err(ParseError::UnexpectedToken {
                     expected: "array size or ]".to_string(),
-                    got: self.current().map(|t| t.text.clone()).unwrap_or_default(),
+                    got: self
+                        .current()
+                        .map(|t| t.text.to_string())
+                        .unwrap_or_default(),
+                    span: self.current_span(),
                 }),
             }
         } else {
@@ -192,17 +530,29 @@ impl Parser {
         }
     }
 
-    fn parse_constant_value(&mut self, _type_: &FieldType) -> ParseResult<ConstantValue> {
-        // Check for negative sign
-        let is_negative = if matches!(self.current().map(|t| &t.kind), Some(TokenKind::Minus)) {
-            self.advance(); // consume -
-            true
-        } else {
-            false
+    fn parse_constant_value(&mut self, type_: &FieldType) -> ParseResult<ConstantValue> {
+        // Optional sign, which must be attached to the number (`-40`, not `- 40`)
+        let sign = match self.current() {
+            Some(token) if matches!(token.kind, TokenKind::Minus | TokenKind::Plus) => {
+                Some((token.kind == TokenKind::Minus, token.span.end))
+            }
+            _ => None,
         };
+        if let Some((_, sign_end)) = sign {
+            self.advance(); // consume sign
+            if let Some(token) = self.current().filter(|token| token.span.start != sign_end) {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "number directly after sign".to_string(),
+                    got: token.text.to_string(),
+                    span: self.current_span(),
+                });
+            }
+        }
+        let is_signed = sign.is_some();
+        let is_negative = matches!(sign, Some((true, _)));
 
-        let token = self.advance().ok_or(ParseError::UnexpectedEOF)?;
-        let text = token.text.clone();
+        let token = self.advance_or_eof()?;
+        let text = token.text;
         let kind = token.kind.clone();
 
         match &kind {
@@ -210,86 +560,125 @@ impl Parser {
             | TokenKind::HexInteger
             | TokenKind::BinaryInteger
             | TokenKind::OctalInteger => {
-                let mut value = self.parse_integer(&text, &kind)?;
-                if is_negative {
-                    value = -value;
-                }
-                Ok(ConstantValue::Integer(value))
+                let value = self.parse_integer(text, &kind, is_negative)?;
+                integer_constant(
+                    value,
+                    type_,
+                    &signed_text(text, is_negative),
+                    self.previous_span(),
+                )
             }
             TokenKind::Float => {
-                let mut value = text
-                    .parse::<f64>()
-                    .map_err(|_| ParseError::InvalidFloat(text.clone()))?;
+                let mut value = text.parse::<f64>().map_err(|_| ParseError::InvalidFloat {
+                    text: text.to_string(),
+                    span: self.previous_span(),
+                })?;
                 if is_negative {
                     value = -value;
                 }
                 Ok(ConstantValue::Float(value))
             }
             TokenKind::True => {
-                if is_negative {
+                if is_signed {
                     return Err(ParseError::UnexpectedToken {
                         expected: "numeric value".to_string(),
                         got: "true".to_string(),
+                        span: self.previous_span(),
                     });
                 }
                 Ok(ConstantValue::Bool(true))
             }
             TokenKind::False => {
-                if is_negative {
+                if is_signed {
                     return Err(ParseError::UnexpectedToken {
                         expected: "numeric value".to_string(),
                         got: "false".to_string(),
+                        span: self.previous_span(),
                     });
                 }
                 Ok(ConstantValue::Bool(false))
             }
             TokenKind::StringLiteral => {
-                if is_negative {
+                if is_signed {
                     return Err(ParseError::UnexpectedToken {
                         expected: "numeric value".to_string(),
-                        got: text,
+                        got: text.to_string(),
+                        span: self.previous_span(),
                     });
                 }
-                // Remove quotes
-                let s = text.trim_matches(|c| c == '"' || c == '\'');
-                Ok(ConstantValue::String(s.to_string()))
+                string_constant(unescape(text), type_, self.previous_span())
             }
             _ => Err(ParseError::UnexpectedToken {
                 expected: "constant value".to_string(),
-                got: text,
+                got: text.to_string(),
+                span: self.previous_span(),
             }),
         }
     }
 
     fn parse_field_or_constant(&mut self) -> ParseResult<(Option<Field>, Option<Constant>)> {
+        let type_span = self.current_span();
         let field_type = self.parse_field_type()?;
-        let name = self.expect(TokenKind::Identifier)?;
+        let name = self.expect(TokenKind::Identifier)?.to_string();
+        let name_span = self.previous_span();
+        let is_constant = matches!(self.current().map(|t| &t.kind), Some(TokenKind::Equals));
+        self.sites.push(ElementSite {
+            name: name.clone(),
+            name_span,
+            field_type: field_type.clone(),
+            type_span,
+            is_constant,
+        });
 
         // Check if this is a constant (has = sign followed by value)
         // Constants have explicit = sign and are typically UPPER_CASE
-        if matches!(self.current().map(|t| &t.kind), Some(TokenKind::Equals)) {
+        if is_constant {
             self.advance(); // consume =
             let value = self.parse_constant_value(&field_type)?;
-            Ok((
-                None,
-                Some(Constant {
-                    constant_type: field_type,
-                    name,
-                    value,
-                }),
-            ))
+            let constant = Constant {
+                constant_type: field_type,
+                name,
+                value,
+                comments: Vec::new(),
+            };
+            if self.strict {
+                self.check_violations(constant_violations(&constant), type_span, name_span)?;
+            }
+            Ok((None, Some(constant)))
         } else {
             // It's a field, check for default value (with or without =)
             let default_value = self.try_parse_default_value(&field_type)?;
+            let field = Field {
+                field_type,
+                name,
+                default_value,
+                comments: Vec::new(),
+                annotations: Vec::new(),
+                deprecated: None,
+            };
+            if self.strict {
+                self.check_violations(field_violations(&field), type_span, name_span)?;
+            }
+            Ok((Some(field), None))
+        }
+    }
 
-            Ok((
-                Some(Field {
-                    field_type,
-                    name,
-                    default_value,
-                }),
-                None,
-            ))
+    /// The first of `violations` as an error at the offending name or type
+    fn check_violations(
+        &self,
+        violations: Vec<Violation>,
+        type_span: Span,
+        name_span: Span,
+    ) -> ParseResult<()> {
+        match violations.into_iter().next() {
+            Some(violation) => Err(ParseError::SpecViolation {
+                span: match violation.rule {
+                    ValidationRule::InvalidName => name_span,
+                    _ => type_span,
+                },
+                violation,
+            }),
+            None => Ok(()),
         }
     }
 
@@ -314,17 +703,73 @@ impl Parser {
                 | TokenKind::True
                 | TokenKind::False
                 | TokenKind::StringLiteral
-                | TokenKind::Minus, // For negative numbers
+                | TokenKind::Minus
+                | TokenKind::Plus, // For signed numbers
             ) => {
                 // Parse as default value
                 Ok(Some(self.parse_constant_value(field_type)?))
             }
+            // Defaults share the field's line; a `[` further down starts something else
+            Some(TokenKind::LBracket) if self.current_span().line == self.previous_span().line => {
+                Ok(Some(self.parse_array_literal(field_type)?))
+            }
             _ => Ok(None), // No default value
         }
     }
 
+    /// Bracketed default of an array or sequence field (`[1, 2, 3]`)
+    fn parse_array_literal(&mut self, field_type: &FieldType) -> ParseResult<ConstantValue> {
+        let span = self.current_span();
+        let invalid = |message: String| ParseError::InvalidDefault { message, span };
+        let (element_type, min_len, max_len) = match field_type {
+            FieldType::Array { element_type, size } => (element_type, *size, *size),
+            FieldType::Sequence { element_type } => (element_type, 0, usize::MAX),
+            FieldType::BoundedSequence {
+                element_type,
+                max_size,
+            } => (element_type, 0, *max_size),
+            _ => {
+                return Err(invalid(format!(
+                    "array literal for non-array type {}",
+                    field_type
+                )))
+            }
+        };
+
+        self.expect(TokenKind::LBracket)?;
+        let mut values = Vec::new();
+        while !matches!(self.current().map(|t| &t.kind), Some(TokenKind::RBracket)) {
+            if !values.is_empty() {
+                self.expect(TokenKind::Comma)?;
+            }
+            values.push(self.parse_constant_value(element_type)?);
+        }
+        self.expect(TokenKind::RBracket)?;
+
+        if values.len() < min_len || values.len() > max_len {
+            return Err(invalid(format!(
+                "{} elements for type {}",
+                values.len(),
+                field_type
+            )));
+        }
+        Ok(ConstantValue::Array(values))
+    }
+
     fn parse_message_impl(&mut self) -> ParseResult<Message> {
+        self.parse_section(None)
+    }
+
+    /// Parse up to the next `---`; with `errors`, failing elements are recorded
+    /// there and skipped to the next line instead of aborting
+    fn parse_section(&mut self, mut errors: Option<&mut Vec<ParseError>>) -> ParseResult<Message> {
         let mut message = Message::new();
+        // Sections after a separator start on the line after it
+        let section_start = match self.pos {
+            0 => 0,
+            pos => self.token_line(pos - 1) + 1,
+        };
+        let mut first_element_line = None;
 
         while self.current().is_some() {
             // Stop at triple dash (service/action separator)
@@ -332,61 +777,367 @@ impl Parser {
                 break;
             }
 
-            let (field, constant) = self.parse_field_or_constant()?;
-
-            if let Some(field) = field {
+            let first = self.pos;
+            let (field, constant) = match (self.parse_field_or_constant(), errors.as_deref_mut()) {
+                (Ok(parsed), _) => parsed,
+                (Err(err), Some(errors)) => {
+                    self.skip_past_line(self.token_line(first).max(err.span().line - 1));
+                    errors.push(err);
+                    continue;
+                }
+                (Err(err), None) => return Err(err),
+            };
+            let comments = self.element_comments(first, self.pos - 1, section_start);
+            first_element_line.get_or_insert(self.token_line(first));
+
+            if let Some(mut field) = field {
+                let mut annotations = Vec::new();
+                for comment in comments {
+                    match Annotation::from_comment(&comment) {
+                        Some(annotation) => annotations.push(annotation),
+                        None => field.comments.push(comment),
+                    }
+                }
+                field.deprecated = deprecation_note(&field.comments, &annotations);
+                field.annotations = annotations;
                 message.fields.push(field);
-            }
-            if let Some(constant) = constant {
+            } else if let Some(mut constant) = constant {
+                constant.comments = comments;
                 message.constants.push(constant);
             }
         }
 
+        // The file-level comment ends where the first element's own comment begins
+        let section_end = match first_element_line {
+            Some(line) => self.leading_comment_start(line, section_start),
+            None => match self.current() {
+                Some(_) => self.token_line(self.pos),
+                None => usize::MAX,
+            },
+        };
+        message.comments = self.comment_lines(section_start..section_end.max(section_start));
+
         Ok(message)
     }
 }
 
-pub fn parse_message(input: &str) -> ParseResult<Message> {
-    let tokens = crate::lexer::lex(input).map_err(ParseError::LexerError)?;
-    let mut parser = Parser::new(tokens);
-    parser.parse_message_impl()
+/// Options for parsing `.msg`/`.srv`/`.action` text
+///
+/// By default a UTF-8 byte order mark and CRLF line endings are normalized away
+/// and trailing whitespace is ignored, as files edited on Windows often carry
+/// them. In strict mode each occurrence is a [`ParseError::Formatting`] instead,
+/// and elements that break the rules checked by [`crate::validate`] (such as a
+/// constant of message type) are a [`ParseError::SpecViolation`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub strict: bool,
 }
 
-pub fn parse_service(input: &str) -> ParseResult<Service> {
-    let tokens = crate::lexer::lex(input).map_err(ParseError::LexerError)?;
-    let mut parser = Parser::new(tokens);
+impl ParseOptions {
+    pub fn parse_message(&self, input: &str) -> ParseResult<Message> {
+        catch_internal(|| {
+            self.check(input)?;
+            let input = normalize(input);
+            let mut parser = self.parser(&input)?;
+            parser.parse_message_impl()
+        })
+    }
+
+    /// Like [`parse_message_lenient`], with every formatting issue among the
+    /// errors in strict mode
+    pub fn parse_message_lenient(&self, input: &str) -> (Message, Vec<ParseError>) {
+        let parsed = catch_internal(|| {
+            let normalized = normalize(input);
+            let (mut parser, mut errors) = Parser::new_lenient(&normalized);
+            parser.strict = self.strict;
+            let message = parser.parse_section(Some(&mut errors)).unwrap_or_default();
+            Ok((message, errors))
+        });
+        let (message, mut errors) = parsed.unwrap_or_else(|err| (Message::default(), vec![err]));
+        if self.strict {
+            errors.extend(formatting_errors(input));
+        }
+        // Lexer errors were collected first
+        errors.sort_by_key(ParseError::span);
+        (message, errors)
+    }
+
+    pub fn parse_service(&self, input: &str) -> ParseResult<Service> {
+        catch_internal(|| {
+            self.check(input)?;
+            let input = normalize(input);
+            let mut parser = self.parser(&input)?;
+
+            let request = parser.parse_message_impl()?;
+
+            // Expect separator
+            parser.expect(TokenKind::TripleDash)?;
 
-    let request = parser.parse_message_impl()?;
+            let response = parser.parse_message_impl()?;
 
-    // Expect separator
-    parser.expect(TokenKind::TripleDash)?;
+            Ok(Service { request, response })
+        })
+    }
+
+    pub fn parse_action(&self, input: &str) -> ParseResult<Action> {
+        catch_internal(|| {
+            self.check(input)?;
+            let input = normalize(input);
+            let mut parser = self.parser(&input)?;
+
+            let goal = parser.parse_message_impl()?;
+            parser.expect(TokenKind::TripleDash)?;
+
+            let result = parser.parse_message_impl()?;
+            parser.expect(TokenKind::TripleDash)?;
+
+            let feedback = parser.parse_message_impl()?;
 
-    let response = parser.parse_message_impl()?;
+            Ok(Action {
+                spec: ActionSpec {
+                    goal,
+                    result,
+                    feedback,
+                },
+            })
+        })
+    }
+
+    fn parser<'a>(&self, input: &'a str) -> ParseResult<Parser<'a>> {
+        let mut parser = Parser::new(input)?;
+        parser.strict = self.strict;
+        Ok(parser)
+    }
 
-    Ok(Service { request, response })
+    /// First formatting issue of `input` in strict mode
+    fn check(&self, input: &str) -> ParseResult<()> {
+        match formatting_errors(input).into_iter().next() {
+            Some(err) if self.strict => Err(err),
+            _ => Ok(()),
+        }
+    }
 }
 
-pub fn parse_action(input: &str) -> ParseResult<Action> {
-    let tokens = crate::lexer::lex(input).map_err(ParseError::LexerError)?;
-    let mut parser = Parser::new(tokens);
+/// Run `parse`, reporting a panic as [`ParseError::Internal`]
+///
+/// Bindgen feeds every installed interface file to the parser, so a parser bug
+/// must fail that one file rather than the whole build. The panic message is
+/// still printed by the panic hook.
+pub(crate) fn catch_internal<T>(parse: impl FnOnce() -> ParseResult<T>) -> ParseResult<T> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(parse)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "parser panicked".to_string());
+        Err(ParseError::Internal {
+            message,
+            span: Span { line: 1, column: 1 },
+        })
+    })
+}
+
+/// `input` without a leading byte order mark and with `\n` line endings
+///
+/// Line and column numbers stay those of `input`, apart from the byte order mark
+/// no longer counting as a column.
+pub(crate) fn normalize(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    if input.contains("\r\n") {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Byte order mark, CRLF line endings and trailing whitespace in `input`
+fn formatting_errors(input: &str) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    if input.starts_with('\u{feff}') {
+        errors.push(ParseError::Formatting {
+            issue: "byte order mark",
+            span: Span::at(input, 0),
+        });
+    }
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let content = line.strip_suffix('\n').unwrap_or(line);
+        let (content, crlf) = match content.strip_suffix('\r') {
+            Some(content) => (content, true),
+            None => (content, false),
+        };
+        let trimmed = content.trim_end();
+        if trimmed.len() < content.len() {
+            errors.push(ParseError::Formatting {
+                issue: "trailing whitespace",
+                span: Span::at(input, offset + trimmed.len()),
+            });
+        }
+        if crlf {
+            errors.push(ParseError::Formatting {
+                issue: "CRLF line ending",
+                span: Span::at(input, offset + content.len()),
+            });
+        }
+        offset += line.len();
+    }
+    errors
+}
+
+pub fn parse_message(input: &str) -> ParseResult<Message> {
+    ParseOptions::default().parse_message(input)
+}
 
-    let goal = parser.parse_message_impl()?;
-    parser.expect(TokenKind::TripleDash)?;
+/// Fields and constants declared in the `.msg`, `.srv` or `.action` text `input`
+pub(crate) fn element_sites(input: &str) -> ParseResult<Vec<ElementSite>> {
+    catch_internal(|| {
+        let input = normalize(input);
+        let mut parser = Parser::new(&input)?;
+        parser.parse_message_impl()?;
+        while parser.current().is_some() {
+            parser.expect(TokenKind::TripleDash)?;
+            parser.parse_message_impl()?;
+        }
+        Ok(parser.sites)
+    })
+}
 
-    let result = parser.parse_message_impl()?;
-    parser.expect(TokenKind::TripleDash)?;
+/// Literal text with the sign that preceded it
+pub(crate) fn signed_text(text: &str, negative: bool) -> String {
+    if negative {
+        format!("-{}", text)
+    } else {
+        text.to_string()
+    }
+}
 
-    let feedback = parser.parse_message_impl()?;
+/// Integer constant of type `type_` (the element type for arrays), which must
+/// hold `value`
+///
+/// Values above i64::MAX become [`ConstantValue::UInteger`]. Types without a
+/// range of their own (floats, strings, ...) accept anything from i64::MIN to
+/// u64::MAX.
+pub(crate) fn integer_constant(
+    value: i128,
+    type_: &FieldType,
+    text: &str,
+    span: Span,
+) -> ParseResult<ConstantValue> {
+    let range = match type_ {
+        FieldType::Primitive(primitive) => integer_range(*primitive),
+        _ => None,
+    };
+    if let Some((min, max)) = range {
+        if value < min || value > max {
+            return Err(ParseError::IntegerOutOfRange {
+                text: text.to_string(),
+                type_name: type_.to_string(),
+                span,
+            });
+        }
+    }
+    match (i64::try_from(value), u64::try_from(value)) {
+        (Ok(value), _) => Ok(ConstantValue::Integer(value)),
+        (_, Ok(value)) => Ok(ConstantValue::UInteger(value)),
+        _ => Err(ParseError::InvalidInteger {
+            text: text.to_string(),
+            span,
+        }),
+    }
+}
 
-    Ok(Action {
-        spec: ActionSpec {
-            goal,
-            result,
-            feedback,
-        },
+/// String constant or default of type `type_`, checked against its bound
+///
+/// Values of `wstring` types become [`ConstantValue::WString`] and `char` literals
+/// (`'a'`) the [`ConstantValue::Integer`] of their code point; everything else is
+/// a narrow [`ConstantValue::String`].
+pub(crate) fn string_constant(
+    value: String,
+    type_: &FieldType,
+    span: Span,
+) -> ParseResult<ConstantValue> {
+    if *type_ == FieldType::Primitive(PrimitiveType::Char) {
+        let mut chars = value.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if u32::from(c) <= 0xff => {
+                Ok(ConstantValue::Integer(u32::from(c).into()))
+            }
+            _ => Err(ParseError::InvalidChar {
+                text: format!("{:?}", value),
+                span,
+            }),
+        };
+    }
+    let len = match type_ {
+        FieldType::BoundedString(max) => Some((value.len(), *max)),
+        FieldType::BoundedWString(max) => Some((value.encode_utf16().count(), *max)),
+        _ => None,
+    };
+    if let Some((len, max)) = len {
+        if len > max {
+            return Err(ParseError::StringTooLong {
+                text: format!("{:?}", value),
+                type_name: type_.to_string(),
+                span,
+            });
+        }
+    }
+    Ok(match type_ {
+        FieldType::WString | FieldType::BoundedWString(_) => ConstantValue::WString(value),
+        _ => ConstantValue::String(value),
     })
 }
 
+/// Values an integer primitive can hold
+fn integer_range(primitive: PrimitiveType) -> Option<(i128, i128)> {
+    let (min, max): (i128, i128) = match primitive {
+        PrimitiveType::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        PrimitiveType::UInt8 | PrimitiveType::Byte | PrimitiveType::Char => (0, u8::MAX.into()),
+        PrimitiveType::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        PrimitiveType::UInt16 => (0, u16::MAX.into()),
+        PrimitiveType::Int32 => (i32::MIN.into(), i32::MAX.into()),
+        PrimitiveType::UInt32 => (0, u32::MAX.into()),
+        PrimitiveType::Int64 => (i64::MIN.into(), i64::MAX.into()),
+        PrimitiveType::UInt64 => (0, u64::MAX.into()),
+        PrimitiveType::Bool | PrimitiveType::Float32 | PrimitiveType::Float64 => return None,
+    };
+    Some((min, max))
+}
+
+/// Parse `text` as the bracketed default of a `field_type` field (`[1, 2, 3]`)
+pub(crate) fn parse_array_default(
+    text: &str,
+    field_type: &FieldType,
+) -> ParseResult<ConstantValue> {
+    let mut parser = Parser::new(text)?;
+    let value = parser.parse_array_literal(field_type)?;
+    match parser.current() {
+        Some(token) => Err(ParseError::UnexpectedToken {
+            expected: "end of array literal".to_string(),
+            got: token.text.to_string(),
+            span: parser.current_span(),
+        }),
+        None => Ok(value),
+    }
+}
+
+/// Parse a message, collecting errors instead of stopping at the first one
+///
+/// Each line that fails to lex or parse is reported and skipped; the returned
+/// message holds the elements that parsed. The error list is empty exactly when
+/// [`parse_message`] succeeds, and the message is then the same.
+pub fn parse_message_lenient(input: &str) -> (Message, Vec<ParseError>) {
+    ParseOptions::default().parse_message_lenient(input)
+}
+
+pub fn parse_service(input: &str) -> ParseResult<Service> {
+    ParseOptions::default().parse_service(input)
+}
+
+pub fn parse_action(input: &str) -> ParseResult<Action> {
+    ParseOptions::default().parse_action(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -442,6 +1193,38 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parse_bounded_string_containers() {
+        let msg = parse_message("string<=10[<=5] tags\nstring<=10[4] fixed\nwstring<=3[] names\n")
+            .unwrap();
+        // The string bound belongs to the element, the array goes on top
+        assert_eq!(
+            msg.fields[0].field_type,
+            FieldType::BoundedSequence {
+                element_type: Box::new(FieldType::BoundedString(10)),
+                max_size: 5,
+            }
+        );
+        assert_eq!(
+            msg.fields[1].field_type,
+            FieldType::Array {
+                element_type: Box::new(FieldType::BoundedString(10)),
+                size: 4,
+            }
+        );
+        assert_eq!(
+            msg.fields[2].field_type,
+            FieldType::Sequence {
+                element_type: Box::new(FieldType::BoundedWString(3)),
+            }
+        );
+        assert_eq!(msg.fields[0].field_type.to_string(), "string<=10[<=5]");
+
+        // The bound cannot follow the array
+        assert!(parse_message("string[<=5]<=10 tags\n").is_err());
+        assert!(parse_message("string[4]<=10 fixed\n").is_err());
+    }
+
     #[test]
     fn parse_constant() {
         let msg = parse_message("int32 MAX_SIZE=100\n").unwrap();
@@ -503,13 +1286,168 @@ mod tests {
 
     #[test]
     fn parse_negative_float_constant() {
-        let msg = parse_message("float64 NEGATIVE_PI=-3.14159\n").unwrap();
+        let msg = parse_message("float64 NEGATIVE_SCALE=-2.5\n").unwrap();
         assert_eq!(msg.constants.len(), 1);
         assert!(
-            matches!(msg.constants[0].value, ConstantValue::Float(v) if (v + 3.14159).abs() < 0.0001)
+            matches!(msg.constants[0].value, ConstantValue::Float(v) if (v + 2.5).abs() < 0.0001)
+        );
+    }
+
+    #[test]
+    fn parse_signed_literals() {
+        let msg = parse_message(
+            "int64 MIN=-9223372036854775808\nint32 HEX=-0x10\nint32 PLUS=+5\nfloat64 SCALE=-1.5e-3\nfloat32 offset +0.5\nint64[] deltas [-1, +2]\n",
+        )
+        .unwrap();
+        let values: Vec<_> = msg.constants.iter().map(|c| c.value.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                ConstantValue::Integer(i64::MIN),
+                ConstantValue::Integer(-16),
+                ConstantValue::Integer(5),
+                ConstantValue::Float(-1.5e-3),
+            ]
+        );
+        assert_eq!(msg.fields[0].default_value, Some(ConstantValue::Float(0.5)));
+        assert_eq!(
+            msg.fields[1].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::Integer(-1),
+                ConstantValue::Integer(2),
+            ]))
         );
     }
 
+    #[test]
+    fn parse_signed_literal_errors() {
+        let err = parse_message("float64 TOO_SMALL=-9223372036854775809\n").unwrap_err();
+        assert!(
+            matches!(err, ParseError::InvalidInteger { ref text, .. } if text == "-9223372036854775809")
+        );
+        assert!(parse_message("int32 X=- 40\n").is_err());
+        assert!(parse_message("bool flag -true\n").is_err());
+        assert!(parse_message("string name +\"a\"\n").is_err());
+    }
+
+    #[test]
+    fn parse_integer_ranges() {
+        let msg = parse_message(
+            "uint64 BIG=18446744073709551615\nuint64 HEX_BIG=0xFFFFFFFFFFFFFFFF\nuint8 MAX=255\nint8 MIN=-128\nfloat64 x 18446744073709551615\nuint64[] big [1, 9223372036854775808]\n",
+        )
+        .unwrap();
+        assert_eq!(msg.constants[0].value, ConstantValue::UInteger(u64::MAX));
+        assert_eq!(msg.constants[1].value, ConstantValue::UInteger(u64::MAX));
+        assert_eq!(msg.constants[0].value.to_string(), "18446744073709551615");
+        assert_eq!(msg.constants[2].value, ConstantValue::Integer(255));
+        assert_eq!(msg.constants[3].value, ConstantValue::Integer(-128));
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::UInteger(u64::MAX))
+        );
+        assert_eq!(
+            msg.fields[1].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::Integer(1),
+                ConstantValue::UInteger(1 << 63),
+            ]))
+        );
+
+        let err = parse_message("uint8 X=256\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Integer out of range at line 1, column 9: 256 does not fit in uint8"
+        );
+        for input in [
+            "int8 x -129\n",
+            "uint32 X=-1\n",
+            "int64 X=9223372036854775808\n",
+            "byte[] data [1, 300]\n",
+            "uint64 X=18446744073709551616\n",
+        ] {
+            assert!(parse_message(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_string_escapes() {
+        let msg = parse_message(
+            "string GREETING=\"hello\\nworld\"\nstring QUOTED=\"say \\\"hi\\\"\"\nstring name 'it\\'s'\n",
+        )
+        .unwrap();
+        assert_eq!(
+            msg.constants[0].value,
+            ConstantValue::String("hello\nworld".to_string())
+        );
+        assert_eq!(
+            msg.constants[1].value,
+            ConstantValue::String("say \"hi\"".to_string())
+        );
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::String("it's".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_char_literals() {
+        let msg = parse_message(
+            "char A='a'\nchar NEWLINE=\"\\n\"\nchar LATIN='\\xe9'\nchar c 'z'\nchar[2] pair ['x', 121]\n",
+        )
+        .unwrap();
+        assert_eq!(msg.constants[0].value, ConstantValue::Integer(97));
+        assert_eq!(msg.constants[1].value, ConstantValue::Integer(10));
+        assert_eq!(msg.constants[2].value, ConstantValue::Integer(0xe9));
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::Integer(122))
+        );
+        assert_eq!(
+            msg.fields[1].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::Integer(120),
+                ConstantValue::Integer(121),
+            ]))
+        );
+
+        for input in ["char A='ab'\n", "char A=''\n", "char A='Ā'\n"] {
+            let err = parse_message(input).unwrap_err();
+            assert!(matches!(err, ParseError::InvalidChar { .. }), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_wstring_values() {
+        let msg = parse_message(
+            "wstring GREETING=\"h\\u00e9llo \\U0001F600\"\nwstring<=3 name \"日本語\"\nwstring[] names [\"a\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            msg.constants[0].value,
+            ConstantValue::WString("héllo 😀".to_string())
+        );
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::WString("日本語".to_string()))
+        );
+        assert_eq!(
+            msg.fields[1].default_value,
+            Some(ConstantValue::Array(vec![ConstantValue::WString(
+                "a".to_string()
+            )]))
+        );
+
+        // Bounds count UTF-16 code units for wstring, bytes for string
+        for input in [
+            "wstring<=2 name \"日本語\"\n",
+            "wstring<=1 name \"😀\"\n",
+            "string<=8 name \"日本語\"\n",
+        ] {
+            let err = parse_message(input).unwrap_err();
+            assert!(matches!(err, ParseError::StringTooLong { .. }), "{}", input);
+        }
+    }
+
     #[test]
     fn parse_default_value_without_equals() {
         let msg = parse_message("float64 x 0\nfloat64 y 0\nfloat64 z 0\nfloat64 w 1\n").unwrap();
@@ -602,4 +1540,334 @@ mod tests {
             Some(ConstantValue::Integer(1))
         ));
     }
+
+    #[test]
+    fn parse_message_comments() {
+        let input = "# Header line one\n#  indented\n\n# Sequence number\nuint32 seq  # wraps around\nint32 MAX=5 # limit\n\n# stray\n\nfloat64 value\n";
+        let msg = parse_message(input).unwrap();
+
+        assert_eq!(msg.comments, vec!["Header line one", " indented"]);
+        assert_eq!(msg.doc().as_deref(), Some("Header line one\n indented"));
+        assert_eq!(
+            msg.fields[0].comments,
+            vec!["Sequence number", "wraps around"]
+        );
+        assert_eq!(msg.constants[0].comments, vec!["limit"]);
+        // Comments separated by blank lines belong to no element
+        assert!(msg.fields[1].comments.is_empty());
+        assert_eq!(msg.fields[1].doc(), None);
+    }
+
+    #[test]
+    fn parse_header_comment_above_first_field() {
+        // The block at the top documents the message even without a blank line,
+        // as in rosidl; the field keeps its trailing comment and annotations
+        let input = "# A pose with a frame\n# @unit(m)\nfloat64 x # along the axis\nfloat64 y\n";
+        let msg = parse_message(input).unwrap();
+
+        assert_eq!(msg.doc().as_deref(), Some("A pose with a frame"));
+        assert_eq!(msg.fields[0].doc().as_deref(), Some("along the axis"));
+        assert_eq!(msg.fields[0].annotations[0].name, "unit");
+
+        let msg = parse_message("\n# The x coordinate\nfloat64 x\n").unwrap();
+        assert_eq!(msg.doc(), None);
+        assert_eq!(msg.fields[0].doc().as_deref(), Some("The x coordinate"));
+    }
+
+    #[test]
+    fn parse_field_annotations() {
+        let input = "\n# Forward speed\n# @unit(m/s)\nfloat64 speed # @verbatim\n# @ see above\nint32 count\n";
+        let msg = parse_message(input).unwrap();
+
+        assert_eq!(msg.fields[0].comments, vec!["Forward speed"]);
+        assert_eq!(
+            msg.fields[0].annotations,
+            vec![
+                Annotation {
+                    name: "unit".to_string(),
+                    value: Some("m/s".to_string()),
+                },
+                Annotation {
+                    name: "verbatim".to_string(),
+                    value: None,
+                },
+            ]
+        );
+        assert_eq!(msg.fields[0].annotations[0].to_string(), "@unit(m/s)");
+        // Prose starting with `@` stays a comment
+        assert_eq!(msg.fields[1].comments, vec!["@ see above"]);
+        assert!(msg.fields[1].annotations.is_empty());
+        assert_eq!(Annotation::from_comment("@default(value=5) trailing"), None);
+    }
+
+    #[test]
+    fn parse_deprecated_fields() {
+        let input = "\n\
+                     # DEPRECATED: use pose instead\n\
+                     float64[2] xy\n\
+                     int32 count # DEPRECATED\n\
+                     # @deprecated(unused)\n\
+                     # DEPRECATED: overridden by the annotation\n\
+                     bool flag\n\
+                     # DEPRECATED_FIELDS follow\n\
+                     geometry_msgs/Pose pose\n";
+        let msg = parse_message(input).unwrap();
+
+        let notes: Vec<_> = msg.fields.iter().map(|f| f.deprecated.as_deref()).collect();
+        assert_eq!(
+            notes,
+            [Some("use pose instead"), Some(""), Some("unused"), None]
+        );
+        // The marker stays in the comments, so formatting keeps it
+        assert_eq!(msg.fields[0].comments, vec!["DEPRECATED: use pose instead"]);
+    }
+
+    #[test]
+    fn parse_service_comments() {
+        let input =
+            "# Request doc\n\nint64 a # first\n---\n# Response doc\n\n# Sum of a\nint64 sum\n";
+        let srv = parse_service(input).unwrap();
+
+        assert_eq!(srv.request.comments, vec!["Request doc"]);
+        assert_eq!(srv.request.fields[0].comments, vec!["first"]);
+        assert_eq!(srv.response.comments, vec!["Response doc"]);
+        assert_eq!(srv.response.fields[0].comments, vec!["Sum of a"]);
+    }
+
+    #[test]
+    fn parse_action_comments_in_empty_sections() {
+        let input = "int32 order\n---\n# Nothing to report\n---\n# Progress\nint32[] sequence\n";
+        let action = parse_action(input).unwrap();
+
+        assert!(action.spec.goal.comments.is_empty());
+        assert_eq!(action.spec.result.comments, vec!["Nothing to report"]);
+        // A comment block at the top of a section documents the section
+        assert_eq!(action.spec.feedback.comments, vec!["Progress"]);
+        assert!(action.spec.feedback.fields[0].comments.is_empty());
+    }
+
+    #[test]
+    fn service_and_action_sections() {
+        let srv = parse_service("# Request doc\n\nint32 LIMIT=3\n---\n# Response doc\nbool ok\n")
+            .unwrap();
+        let sections: Vec<_> = srv
+            .sections()
+            .iter()
+            .map(|(section, message)| {
+                (
+                    section.name(),
+                    message.comments.clone(),
+                    message.constants.len(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            [
+                ("request", vec!["Request doc".to_string()], 1),
+                ("response", vec!["Response doc".to_string()], 0),
+            ]
+        );
+        assert!(srv.response.fields[0].comments.is_empty());
+
+        let action = parse_action("---\n---\n").unwrap();
+        let suffixes = action.spec.sections().map(|(section, _)| section.suffix());
+        assert_eq!(suffixes, ["Goal", "Result", "Feedback"]);
+    }
+
+    #[test]
+    fn parse_error_spans() {
+        let err = parse_message("int32 x\nfloat64 = 5\n").unwrap_err();
+        assert_eq!(err.span(), Span { line: 2, column: 9 });
+        assert_eq!(
+            err.to_string(),
+            "Unexpected token at line 2, column 9: expected Identifier, got ="
+        );
+
+        let err = parse_message("int32 x\nbool flag\nint64").unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedEOF { .. }));
+        assert_eq!(err.span(), Span { line: 3, column: 6 });
+
+        let err = parse_message("# héllo\nstring s\n  uint8 v @\n").unwrap_err();
+        assert!(matches!(err, ParseError::LexerError { .. }));
+        assert_eq!(
+            err.span(),
+            Span {
+                line: 3,
+                column: 11
+            }
+        );
+
+        let err = parse_message("int32 x 99999999999999999999\n").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidInteger { .. }));
+        assert_eq!(err.span(), Span { line: 1, column: 9 });
+    }
+
+    #[test]
+    fn parse_error_snippet() {
+        let input = "int32 x\n[] y\n";
+        let err = parse_message(input).unwrap_err();
+        assert_eq!(
+            err.snippet(input),
+            "Unknown type at line 2, column 1: [\n  |\n2 | [] y\n  | ^"
+        );
+    }
+
+    #[test]
+    fn parse_message_lenient_reports_every_error() {
+        let input =
+            "int32 x\nfloat64 = 5\nbool flag @\n# Kept\nstring name\nint64[ broken\nuint8 y 3\n";
+        let (msg, errors) = parse_message_lenient(input);
+
+        let names: Vec<_> = msg.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["x", "name", "y"]);
+        assert_eq!(msg.fields[1].comments, vec!["Kept"]);
+        let lines: Vec<_> = errors.iter().map(|e| e.span().line).collect();
+        assert_eq!(lines, [2, 3, 6]);
+        assert!(matches!(errors[1], ParseError::LexerError { .. }));
+    }
+
+    #[test]
+    fn parse_message_lenient_matches_strict() {
+        let input = "# Header\n\nint32 A=1\nfloat64 x 0.5 # trailing\nstring<=8[<=2] names\n";
+        let (msg, errors) = parse_message_lenient(input);
+        assert!(errors.is_empty());
+        assert_eq!(msg, parse_message(input).unwrap());
+    }
+
+    #[test]
+    fn parse_windows_formatting() {
+        let input = "\u{feff}# Header\r\n\r\nint32 A=1 \r\nstring name \"a\" # note\t\r\n";
+        let msg = parse_message(input).unwrap();
+        assert_eq!(
+            msg,
+            parse_message(&input[3..].replace("\r\n", "\n")).unwrap()
+        );
+        assert_eq!(msg.comments, vec!["Header"]);
+        assert_eq!(msg.fields[0].comments, vec!["note"]);
+
+        let srv = parse_service("\u{feff}int64 a\r\n---\r\nint64 sum\r\n").unwrap();
+        assert_eq!(srv.response.fields[0].name, "sum");
+        // Spans keep the lines and columns of the original text
+        let err = parse_message("int32 x\r\nfloat64 = 5\r\n").unwrap_err();
+        assert_eq!(err.span(), Span { line: 2, column: 9 });
+    }
+
+    #[test]
+    fn catch_internal_reports_panics() {
+        let err = catch_internal::<()>(|| panic!("index out of bounds")).unwrap_err();
+        assert!(
+            matches!(&err, ParseError::Internal { message, .. } if message == "index out of bounds")
+        );
+        assert_eq!(
+            err.to_string(),
+            "Internal parser error at line 1, column 1: index out of bounds"
+        );
+        // Spans inside a character and past the end stay in range
+        assert_eq!(Span::at("é", 1), Span { line: 1, column: 1 });
+        assert_eq!(Span::at("a\n", 10), Span { line: 2, column: 1 });
+    }
+
+    #[test]
+    fn parse_strict_formatting() {
+        let strict = ParseOptions { strict: true };
+        let input = "\u{feff}int32 A=1 \r\nstring name\n";
+        let err = strict.parse_message(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Disallowed formatting at line 1, column 1: byte order mark"
+        );
+
+        let (msg, errors) = strict.parse_message_lenient(input);
+        assert_eq!(msg.constants.len(), 1);
+        let issues: Vec<_> = errors
+            .iter()
+            .map(|err| match err {
+                ParseError::Formatting { issue, span } => (*issue, span.line, span.column),
+                err => panic!("unexpected error {}", err),
+            })
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                ("byte order mark", 1, 1),
+                ("trailing whitespace", 1, 11),
+                ("CRLF line ending", 1, 12),
+            ]
+        );
+
+        let clean = "int64 a\n---\nint64 sum\n";
+        assert_eq!(
+            strict.parse_service(clean).unwrap(),
+            parse_service(clean).unwrap()
+        );
+        assert!(strict.parse_action("int32 order\r\n---\n---\n").is_err());
+    }
+
+    #[test]
+    fn parse_strict_spec_violations() {
+        let strict = ParseOptions { strict: true };
+        let input = "uint8 OK=0\ngeometry_msgs/Point ORIGIN=0\nint32[<=0] values\n";
+        assert!(parse_message(input).is_ok());
+        let err = strict.parse_message(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Specification violation at line 2, column 1: constant `ORIGIN` has type \
+             geometry_msgs/Point, not a primitive or string type"
+        );
+
+        let (msg, errors) = strict.parse_message_lenient(input);
+        assert_eq!(msg.constants.len(), 1);
+        let spans: Vec<_> = errors.iter().map(|err| err.span().line).collect();
+        assert_eq!(spans, [2, 3]);
+
+        let srv = "int32 a\n---\nPose[] poses [1]\n";
+        assert!(matches!(
+            strict.parse_service(srv),
+            Err(ParseError::SpecViolation { span, .. }) if span.line == 3
+        ));
+    }
+
+    #[test]
+    fn parse_array_defaults() {
+        let input = "int32[] samples [1, -2, 0x3]\nstring[<=2] names [\"a\", 'b']\nfloat64[2] gains [0.5, 1]\nbool[] flags []\n";
+        let msg = parse_message(input).unwrap();
+
+        assert_eq!(
+            msg.fields[0].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::Integer(1),
+                ConstantValue::Integer(-2),
+                ConstantValue::Integer(3),
+            ]))
+        );
+        assert_eq!(
+            msg.fields[1].default_value,
+            Some(ConstantValue::Array(vec![
+                ConstantValue::String("a".to_string()),
+                ConstantValue::String("b".to_string()),
+            ]))
+        );
+        assert_eq!(
+            msg.fields[2].default_value.as_ref().unwrap().to_string(),
+            "[0.5, 1]"
+        );
+        assert_eq!(
+            msg.fields[3].default_value,
+            Some(ConstantValue::Array(vec![]))
+        );
+    }
+
+    #[test]
+    fn parse_array_default_errors() {
+        let err = parse_message("int32[3] xyz [1, 2]\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid default value at line 1, column 14: 2 elements for type int32[3]"
+        );
+        assert!(parse_message("int32[<=1] x [1, 2]\n").is_err());
+        assert!(parse_message("int32 x [1]\n").is_err());
+        assert!(parse_message("int32[] x [1 2]\n").is_err());
+        assert!(parse_message("int32[] x [1,\n").is_err());
+    }
 }
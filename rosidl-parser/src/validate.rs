@@ -0,0 +1,214 @@
+//! Conformance checks of the rosidl rules the grammar does not enforce
+//!
+//! An interface can parse and still break rules of the rosidl specification,
+//! such as a constant of message type or a `[<=0]` bound, and the code generated
+//! for it then does not compile. [`ParseOptions`](crate::ParseOptions) in strict
+//! mode rejects such elements while parsing; [`validate_message`] and friends
+//! check an AST from any source, IDL included.
+
+use crate::ast::{Action, Constant, Field, FieldType, Message, Service};
+use crate::visit::{walk_action, walk_service, walk_type, Visitor};
+use std::fmt;
+
+/// Rule of the specification a violation breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationRule {
+    /// Default values are only allowed for primitive and string types and
+    /// arrays or sequences of them
+    NonPrimitiveDefault,
+    /// Constants are only allowed for primitive and string types
+    NonPrimitiveConstant,
+    /// Bounds of bounded sequences and strings are greater than zero
+    EmptyBound,
+    /// Field and constant names start with a letter
+    InvalidName,
+}
+
+/// Element that breaks a rule of the specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: ValidationRule,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Violations of the fields and constants of `message`, fields first
+pub fn validate_message(message: &Message) -> Vec<Violation> {
+    let mut validator = Validator(Vec::new());
+    validator.visit_message(message);
+    validator.0
+}
+
+/// Violations of the request, then the response of `service`
+pub fn validate_service(service: &Service) -> Vec<Violation> {
+    let mut validator = Validator(Vec::new());
+    walk_service(&mut validator, service);
+    validator.0
+}
+
+/// Violations of the goal, result and feedback of `action`
+pub fn validate_action(action: &Action) -> Vec<Violation> {
+    let mut validator = Validator(Vec::new());
+    walk_action(&mut validator, action);
+    validator.0
+}
+
+struct Validator(Vec<Violation>);
+
+impl Visitor for Validator {
+    fn visit_field(&mut self, field: &Field) {
+        self.0.extend(field_violations(field));
+    }
+
+    fn visit_constant(&mut self, constant: &Constant) {
+        self.0.extend(constant_violations(constant));
+    }
+}
+
+/// Violations of one field, in rule order
+pub(crate) fn field_violations(field: &Field) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_name(&mut violations, "field", &field.name);
+    if field.default_value.is_some() && !is_primitive(element_type(&field.field_type)) {
+        violations.push(Violation {
+            rule: ValidationRule::NonPrimitiveDefault,
+            message: format!(
+                "field `{}` of type {} cannot have a default value",
+                field.name, field.field_type
+            ),
+        });
+    }
+    check_bounds(&mut violations, &field.name, &field.field_type);
+    violations
+}
+
+/// Violations of one constant, in rule order
+pub(crate) fn constant_violations(constant: &Constant) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_name(&mut violations, "constant", &constant.name);
+    if !is_primitive(&constant.constant_type) {
+        violations.push(Violation {
+            rule: ValidationRule::NonPrimitiveConstant,
+            message: format!(
+                "constant `{}` has type {}, not a primitive or string type",
+                constant.name, constant.constant_type
+            ),
+        });
+    }
+    check_bounds(&mut violations, &constant.name, &constant.constant_type);
+    violations
+}
+
+fn check_name(violations: &mut Vec<Violation>, kind: &str, name: &str) {
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        violations.push(Violation {
+            rule: ValidationRule::InvalidName,
+            message: format!("{} name `{}` does not start with a letter", kind, name),
+        });
+    }
+}
+
+/// Zero bounds anywhere in `field_type`, such as the `string<=0` of `string<=0[<=3]`
+fn check_bounds(violations: &mut Vec<Violation>, name: &str, field_type: &FieldType) {
+    struct Bounds<'a>(&'a mut Vec<Violation>, &'a str);
+
+    impl Visitor for Bounds<'_> {
+        fn visit_type(&mut self, field_type: &FieldType) {
+            if let FieldType::BoundedString(0)
+            | FieldType::BoundedWString(0)
+            | FieldType::BoundedSequence { max_size: 0, .. } = field_type
+            {
+                self.0.push(Violation {
+                    rule: ValidationRule::EmptyBound,
+                    message: format!("`{}` has type {} with a bound of 0", self.1, field_type),
+                });
+            }
+            walk_type(self, field_type);
+        }
+    }
+
+    Bounds(violations, name).visit_type(field_type);
+}
+
+/// Element type of arrays and sequences, `field_type` itself otherwise
+fn element_type(field_type: &FieldType) -> &FieldType {
+    match field_type {
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => element_type,
+        _ => field_type,
+    }
+}
+
+/// Primitive or (bounded) string type
+fn is_primitive(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Primitive(_)
+            | FieldType::String
+            | FieldType::BoundedString(_)
+            | FieldType::WString
+            | FieldType::BoundedWString(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_message, parse_service};
+
+    #[test]
+    fn validate_rules() {
+        let msg = parse_message(
+            "geometry_msgs/Point ORIGIN=0\n\
+             int32[] LIMITS=1\n\
+             uint8 OK=0\n\
+             geometry_msgs/Point[] points [1, 2]\n\
+             Pose pose 1\n\
+             string<=0[<=0] names\n\
+             int32[3] values [1, 2, 3]\n\
+             string<=8 label \"x\"\n",
+        )
+        .unwrap();
+        let rules: Vec<_> = validate_message(&msg).iter().map(|v| v.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                ValidationRule::NonPrimitiveDefault,
+                ValidationRule::NonPrimitiveDefault,
+                ValidationRule::EmptyBound,
+                ValidationRule::EmptyBound,
+                ValidationRule::NonPrimitiveConstant,
+                ValidationRule::NonPrimitiveConstant,
+            ]
+        );
+        assert_eq!(
+            validate_message(&msg)[3].to_string(),
+            "`names` has type string<=0 with a bound of 0"
+        );
+    }
+
+    #[test]
+    fn validate_names() {
+        let mut srv = parse_service("int32 a\n---\nint32 B=1\n").unwrap();
+        assert!(validate_service(&srv).is_empty());
+        srv.request.fields[0].name = "2d".to_string();
+        srv.response.constants[0].name = "_B".to_string();
+        let messages: Vec<_> = validate_service(&srv)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "field name `2d` does not start with a letter",
+                "constant name `_B` does not start with a letter",
+            ]
+        );
+    }
+}
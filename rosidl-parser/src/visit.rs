@@ -0,0 +1,168 @@
+//! Read-only traversal of interface ASTs
+//!
+//! Implement [`Visitor`] and override the hooks of interest. The default methods
+//! recurse through the matching `walk_*` function, so an overriding method that
+//! still wants to reach nested nodes calls it itself:
+//!
+//! ```
+//! use rosidl_parser::visit::{walk_type, Visitor};
+//! use rosidl_parser::{parse_message, FieldType};
+//!
+//! struct Packages(Vec<String>);
+//!
+//! impl Visitor for Packages {
+//!     fn visit_type(&mut self, field_type: &FieldType) {
+//!         if let FieldType::NamespacedType { package: Some(package), .. } = field_type {
+//!             self.0.push(package.clone());
+//!         }
+//!         walk_type(self, field_type);
+//!     }
+//! }
+//!
+//! let msg = parse_message("geometry_msgs/Point[] points\nint32 count\n").unwrap();
+//! let mut packages = Packages(Vec::new());
+//! packages.visit_message(&msg);
+//! assert_eq!(packages.0, ["geometry_msgs"]);
+//! ```
+
+use crate::ast::{Action, Constant, Field, FieldType, Message, Service};
+
+/// Hooks called while walking an interface
+pub trait Visitor {
+    /// Called for each message, including service and action sections
+    fn visit_message(&mut self, message: &Message) {
+        walk_message(self, message);
+    }
+
+    fn visit_field(&mut self, field: &Field) {
+        walk_field(self, field);
+    }
+
+    fn visit_constant(&mut self, constant: &Constant) {
+        walk_constant(self, constant);
+    }
+
+    /// Called for the type of each field and constant, then for element types
+    /// of arrays and sequences
+    fn visit_type(&mut self, field_type: &FieldType) {
+        walk_type(self, field_type);
+    }
+}
+
+/// Visit the fields, then the constants of `message`
+pub fn walk_message<V: Visitor + ?Sized>(visitor: &mut V, message: &Message) {
+    for field in &message.fields {
+        visitor.visit_field(field);
+    }
+    for constant in &message.constants {
+        visitor.visit_constant(constant);
+    }
+}
+
+/// Visit the request, then the response of `service`
+pub fn walk_service<V: Visitor + ?Sized>(visitor: &mut V, service: &Service) {
+    visitor.visit_message(&service.request);
+    visitor.visit_message(&service.response);
+}
+
+/// Visit the goal, result and feedback of `action`
+pub fn walk_action<V: Visitor + ?Sized>(visitor: &mut V, action: &Action) {
+    visitor.visit_message(&action.spec.goal);
+    visitor.visit_message(&action.spec.result);
+    visitor.visit_message(&action.spec.feedback);
+}
+
+pub fn walk_field<V: Visitor + ?Sized>(visitor: &mut V, field: &Field) {
+    visitor.visit_type(&field.field_type);
+}
+
+pub fn walk_constant<V: Visitor + ?Sized>(visitor: &mut V, constant: &Constant) {
+    visitor.visit_type(&constant.constant_type);
+}
+
+/// Visit the element type of arrays and sequences
+pub fn walk_type<V: Visitor + ?Sized>(visitor: &mut V, field_type: &FieldType) {
+    match field_type {
+        FieldType::Array { element_type, .. }
+        | FieldType::Sequence { element_type }
+        | FieldType::BoundedSequence { element_type, .. } => visitor.visit_type(element_type),
+        FieldType::Primitive(_)
+        | FieldType::String
+        | FieldType::BoundedString(_)
+        | FieldType::WString
+        | FieldType::BoundedWString(_)
+        | FieldType::NamespacedType { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_action, parse_service};
+
+    /// Records every hook call
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl Visitor for Trace {
+        fn visit_message(&mut self, message: &Message) {
+            self.0.push("message".to_string());
+            walk_message(self, message);
+        }
+
+        fn visit_field(&mut self, field: &Field) {
+            self.0.push(format!("field {}", field.name));
+            walk_field(self, field);
+        }
+
+        fn visit_constant(&mut self, constant: &Constant) {
+            self.0.push(format!("constant {}", constant.name));
+            walk_constant(self, constant);
+        }
+
+        fn visit_type(&mut self, field_type: &FieldType) {
+            self.0.push(format!("type {}", field_type));
+            walk_type(self, field_type);
+        }
+    }
+
+    #[test]
+    fn walk_service_order() {
+        let srv = parse_service("uint8 OK=0\nstring<=4[<=2] names\n---\nbool ok\n").unwrap();
+        let mut trace = Trace::default();
+        walk_service(&mut trace, &srv);
+        assert_eq!(
+            trace.0,
+            [
+                "message",
+                "field names",
+                "type string<=4[<=2]",
+                "type string<=4",
+                "constant OK",
+                "type uint8",
+                "message",
+                "field ok",
+                "type bool",
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_action_sections() {
+        /// Counts fields without overriding the recursion
+        struct Fields(usize);
+
+        impl Visitor for Fields {
+            fn visit_field(&mut self, _field: &Field) {
+                self.0 += 1;
+            }
+        }
+
+        let action =
+            parse_action("int32 order\n---\nint32[] sequence\n---\nint32[] partial\nint32 step\n")
+                .unwrap();
+        let mut fields = Fields(0);
+        walk_action(&mut fields, &action);
+        assert_eq!(fields.0, 4);
+    }
+}
@@ -1,4 +1,14 @@
 fn main() {
+    // no_std builds only contain the bounded containers, nothing to link
+    if std::env::var_os("CARGO_FEATURE_STD").is_none() {
+        return;
+    }
+
+    // The mock-ffi feature implements the C functions in Rust, nothing to link
+    if std::env::var_os("CARGO_FEATURE_MOCK_FFI").is_some() {
+        return;
+    }
+
     // Link against rosidl_runtime_c library
     // This library provides the generic ROS runtime functions for strings and primitive sequences
     println!("cargo:rustc-link-lib=rosidl_runtime_c");
@@ -0,0 +1,425 @@
+//! Fixed-capacity containers for `no_std` bindings
+//!
+//! Embedded bindings store strings and sequences inline, without a heap:
+//! [`BoundedString<N>`] holds at most `N` bytes of UTF-8 and [`BoundedVec<T, N>`]
+//! at most `N` elements. Operations that would exceed the capacity fail with
+//! [`BoundedCapacity`] instead of allocating.
+//!
+//! Idiomatic bindings keep bounded fields as plain strings and vectors; their
+//! generated `set_<field>` and `check_bounds` methods report a value past the
+//! bound of its field with [`BoundExceeded`].
+//!
+//! This module does not need the `std` feature.
+//!
+//! # Example
+//! ```
+//! use rosidl_runtime_rs::bounded::{BoundedString, BoundedVec};
+//!
+//! let mut ranges = BoundedVec::<f32, 2>::new();
+//! ranges.push(1.0).unwrap();
+//! ranges.push(2.0).unwrap();
+//! assert!(ranges.push(3.0).is_err());
+//!
+//! let frame = BoundedString::<8>::try_from("base").unwrap();
+//! assert_eq!(frame, "base");
+//! ```
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// Error for content exceeding the capacity of a bounded container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundedCapacity {
+    /// Capacity of the container
+    pub capacity: usize,
+    /// Length the operation needed
+    pub required: usize,
+}
+
+impl fmt::Display for BoundedCapacity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bounded capacity exceeded: {} needed, capacity is {}",
+            self.required, self.capacity
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BoundedCapacity {}
+
+/// Error for a field value exceeding the bound of its type, such as `string<=8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundExceeded {
+    /// Name of the field in the interface definition
+    pub field: &'static str,
+    /// Length of the value: bytes of a string, UTF-16 code units of a wide
+    /// string or elements of a sequence
+    pub len: usize,
+    /// Bound of the field type
+    pub bound: usize,
+}
+
+impl BoundExceeded {
+    /// Fail for `field` if `len` is greater than `bound`
+    pub fn check(field: &'static str, len: usize, bound: usize) -> Result<(), Self> {
+        if len > bound {
+            Err(Self { field, len, bound })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for BoundExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "field `{}` has length {}, exceeding its bound of {}",
+            self.field, self.len, self.bound
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BoundExceeded {}
+
+/// Sequence of at most `N` elements, stored inline
+///
+/// Unused slots hold `T::default()`.
+#[derive(Clone)]
+pub struct BoundedVec<T, const N: usize> {
+    len: usize,
+    items: [T; N],
+}
+
+impl<T: Default, const N: usize> BoundedVec<T, N> {
+    /// Create an empty sequence
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            items: core::array::from_fn(|_| T::default()),
+        }
+    }
+
+    /// Remove and return the last element
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(core::mem::take(&mut self.items[self.len]))
+    }
+
+    /// Remove all elements
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, const N: usize> BoundedVec<T, N> {
+    /// Maximum number of elements
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Append an element, failing if the sequence is full
+    pub fn push(&mut self, value: T) -> Result<(), BoundedCapacity> {
+        if self.len == N {
+            return Err(BoundedCapacity {
+                capacity: N,
+                required: N + 1,
+            });
+        }
+        self.items[self.len] = value;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Elements as a slice
+    pub fn as_slice(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+
+    /// Elements as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.items[..self.len]
+    }
+}
+
+impl<T: Default, const N: usize> Default for BoundedVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for BoundedVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for BoundedVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for BoundedVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for BoundedVec<T, N> {}
+
+impl<T: Clone + Default, const N: usize> TryFrom<&[T]> for BoundedVec<T, N> {
+    type Error = BoundedCapacity;
+
+    fn try_from(values: &[T]) -> Result<Self, Self::Error> {
+        if values.len() > N {
+            return Err(BoundedCapacity {
+                capacity: N,
+                required: values.len(),
+            });
+        }
+        let mut vec = Self::new();
+        vec.items[..values.len()].clone_from_slice(values);
+        vec.len = values.len();
+        Ok(vec)
+    }
+}
+
+/// UTF-8 string of at most `N` bytes, stored inline
+#[derive(Clone, Copy)]
+pub struct BoundedString<const N: usize> {
+    len: usize,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> BoundedString<N> {
+    /// Create an empty string
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; N],
+        }
+    }
+
+    /// Maximum length in bytes
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Append a string slice, failing (and leaving the string unchanged) if it does not fit
+    pub fn push_str(&mut self, s: &str) -> Result<(), BoundedCapacity> {
+        let required = self.len + s.len();
+        if required > N {
+            return Err(BoundedCapacity {
+                capacity: N,
+                required,
+            });
+        }
+        self.bytes[self.len..required].copy_from_slice(s.as_bytes());
+        self.len = required;
+        Ok(())
+    }
+
+    /// Append a character
+    pub fn push(&mut self, c: char) -> Result<(), BoundedCapacity> {
+        self.push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Remove all content
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Content as a string slice
+    pub fn as_str(&self) -> &str {
+        // Only whole string slices are ever copied in
+        core::str::from_utf8(&self.bytes[..self.len]).expect("BoundedString holds UTF-8")
+    }
+}
+
+impl<const N: usize> Default for BoundedString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Debug for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> PartialEq for BoundedString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<const N: usize> Eq for BoundedString<N> {}
+
+impl<const N: usize> PartialEq<str> for BoundedString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for BoundedString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = BoundedCapacity;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let mut string = Self::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{BoundedString, BoundedVec};
+    use core::fmt;
+    use core::marker::PhantomData;
+    use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+    use serde::ser::{Serialize, Serializer};
+
+    impl<T: Serialize, const N: usize> Serialize for BoundedVec<T, N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.as_slice())
+        }
+    }
+
+    impl<'de, T: Deserialize<'de> + Default, const N: usize> Deserialize<'de> for BoundedVec<T, N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct SeqVisitor<T, const N: usize>(PhantomData<T>);
+
+            impl<'de, T: Deserialize<'de> + Default, const N: usize> Visitor<'de> for SeqVisitor<T, N> {
+                type Value = BoundedVec<T, N>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "a sequence of at most {} elements", N)
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                    let mut vec = BoundedVec::new();
+                    while let Some(value) = seq.next_element()? {
+                        vec.push(value).map_err(de::Error::custom)?;
+                    }
+                    Ok(vec)
+                }
+            }
+
+            deserializer.deserialize_seq(SeqVisitor(PhantomData))
+        }
+    }
+
+    impl<const N: usize> Serialize for BoundedString<N> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.as_str())
+        }
+    }
+
+    impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct StrVisitor<const N: usize>;
+
+            impl<const N: usize> Visitor<'_> for StrVisitor<N> {
+                type Value = BoundedString<N>;
+
+                fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    write!(f, "a string of at most {} bytes", N)
+                }
+
+                fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+                    BoundedString::try_from(s).map_err(E::custom)
+                }
+            }
+
+            deserializer.deserialize_str(StrVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_bounded_vec() {
+        let mut vec = BoundedVec::<u8, 3>::try_from(&[1, 2][..]).unwrap();
+        vec.push(3).unwrap();
+        assert_eq!(
+            vec.push(4),
+            Err(BoundedCapacity {
+                capacity: 3,
+                required: 4
+            })
+        );
+        assert_eq!(&vec[..], &[1, 2, 3]);
+        assert_eq!(vec.pop(), Some(3));
+        assert_eq!(vec.len(), 2);
+        assert!(BoundedVec::<u8, 1>::try_from(&[1, 2][..]).is_err());
+        assert_eq!(vec, BoundedVec::try_from(&[1, 2][..]).unwrap());
+    }
+
+    #[test]
+    fn test_bounded_string() {
+        let mut s = BoundedString::<6>::try_from("héllo").unwrap();
+        assert_eq!(s.len(), 6);
+        assert!(s.push('!').is_err());
+        assert_eq!(s, "héllo");
+        s.clear();
+        s.push_str("map").unwrap();
+        assert_eq!(s.as_str(), "map");
+        assert_eq!(
+            BoundedString::<2>::try_from("map").unwrap_err().to_string(),
+            "bounded capacity exceeded: 3 needed, capacity is 2"
+        );
+    }
+
+    #[test]
+    fn test_bound_exceeded() {
+        assert_eq!(BoundExceeded::check("frame_id", 8, 8), Ok(()));
+        assert_eq!(
+            BoundExceeded::check("frame_id", 9, 8)
+                .unwrap_err()
+                .to_string(),
+            "field `frame_id` has length 9, exceeding its bound of 8"
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! CDR (Common Data Representation) serialization
+//!
+//! ROS 2 middlewares exchange messages as little-endian CDR (XCDR1) prefixed with a
+//! 4-byte encapsulation header. This module provides a pure-Rust encoder/decoder and
+//! the `CdrSerialize`/`CdrDeserialize` traits implemented by primitives, strings,
+//! sequences and fixed-size arrays, so message types can be serialized without the
+//! rmw C stack (e.g. when recording to or replaying from a bag file).
+//!
+//! # Example
+//! ```
+//! use rosidl_runtime_rs::cdr::{from_cdr, to_cdr};
+//!
+//! let bytes = to_cdr(&(42i32, "hello".to_string()));
+//! let (n, s): (i32, std::string::String) = from_cdr(&bytes).unwrap();
+//! assert_eq!(n, 42);
+//! assert_eq!(s, "hello");
+//! ```
+
+use std::fmt;
+
+/// Encapsulation header for little-endian plain CDR
+pub const CDR_LE_HEADER: [u8; 4] = [0x00, 0x01, 0x00, 0x00];
+
+/// Encapsulation header for big-endian plain CDR
+pub const CDR_BE_HEADER: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+
+/// Error while decoding a CDR buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CdrError {
+    /// Buffer ended before the value was complete
+    UnexpectedEof,
+    /// Encapsulation header is missing or not a supported representation
+    InvalidEncapsulation([u8; 2]),
+    /// String payload is not valid UTF-8
+    InvalidUtf8,
+    /// Boolean byte was neither 0 nor 1
+    InvalidBool(u8),
+    /// String was not terminated by a null byte
+    MissingNullTerminator,
+}
+
+impl fmt::Display for CdrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CdrError::UnexpectedEof => write!(f, "unexpected end of CDR buffer"),
+            CdrError::InvalidEncapsulation(id) => {
+                write!(
+                    f,
+                    "unsupported CDR encapsulation {:#04x}{:02x}",
+                    id[0], id[1]
+                )
+            }
+            CdrError::InvalidUtf8 => write!(f, "CDR string is not valid UTF-8"),
+            CdrError::InvalidBool(b) => write!(f, "invalid CDR boolean value {}", b),
+            CdrError::MissingNullTerminator => write!(f, "CDR string is not null-terminated"),
+        }
+    }
+}
+
+impl std::error::Error for CdrError {}
+
+/// Little-endian CDR encoder
+///
+/// Alignment is computed relative to the end of the encapsulation header, as
+/// required by the specification.
+#[derive(Debug, Clone)]
+pub struct CdrWriter {
+    buf: Vec<u8>,
+}
+
+impl Default for CdrWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CdrWriter {
+    /// Create a writer with the little-endian encapsulation header already written
+    pub fn new() -> Self {
+        Self {
+            buf: CDR_LE_HEADER.to_vec(),
+        }
+    }
+
+    fn align(&mut self, alignment: usize) {
+        let offset = self.buf.len() - CDR_LE_HEADER.len();
+        let padding = (alignment - offset % alignment) % alignment;
+        self.buf.resize(self.buf.len() + padding, 0);
+    }
+
+    /// Write raw bytes without alignment
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Write a sequence length prefix
+    pub fn write_len(&mut self, len: usize) {
+        u32::try_from(len)
+            .expect("CDR sequence length exceeds u32::MAX")
+            .cdr_serialize(self);
+    }
+
+    /// Write a string (length including null terminator, bytes, null)
+    pub fn write_str(&mut self, s: &str) {
+        self.write_len(s.len() + 1);
+        self.write_bytes(s.as_bytes());
+        self.write_bytes(&[0]);
+    }
+
+    /// Finish encoding and return the buffer, including the encapsulation header
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// CDR decoder supporting both little- and big-endian encapsulations
+#[derive(Debug, Clone)]
+pub struct CdrReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> CdrReader<'a> {
+    /// Create a reader, validating the encapsulation header
+    pub fn new(data: &'a [u8]) -> Result<Self, CdrError> {
+        if data.len() < 4 {
+            return Err(CdrError::UnexpectedEof);
+        }
+        let little_endian = match [data[0], data[1]] {
+            [0x00, 0x01] => true,
+            [0x00, 0x00] => false,
+            other => return Err(CdrError::InvalidEncapsulation(other)),
+        };
+        Ok(Self {
+            data: &data[4..],
+            pos: 0,
+            little_endian,
+        })
+    }
+
+    /// Number of undecoded bytes left in the buffer
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn align(&mut self, alignment: usize) -> Result<(), CdrError> {
+        let padding = (alignment - self.pos % alignment) % alignment;
+        self.read_bytes(padding).map(|_| ())
+    }
+
+    /// Read raw bytes without alignment
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], CdrError> {
+        let end = self.pos.checked_add(len).ok_or(CdrError::UnexpectedEof)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(CdrError::UnexpectedEof)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], CdrError> {
+        self.align(N)?;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.read_bytes(N)?);
+        if !self.little_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    /// Read a sequence length prefix
+    pub fn read_len(&mut self) -> Result<usize, CdrError> {
+        let len = u32::cdr_deserialize(self)? as usize;
+        // Every element occupies at least one byte, so a larger count is corrupt
+        if len > self.remaining() {
+            return Err(CdrError::UnexpectedEof);
+        }
+        Ok(len)
+    }
+
+    /// Read a string written by [`CdrWriter::write_str`]
+    pub fn read_str(&mut self) -> Result<&'a str, CdrError> {
+        let len = self.read_len()?;
+        if len == 0 {
+            // Some implementations encode the empty string without a terminator
+            return Ok("");
+        }
+        let bytes = self.read_bytes(len)?;
+        let (terminator, text) = bytes.split_last().ok_or(CdrError::UnexpectedEof)?;
+        if *terminator != 0 {
+            return Err(CdrError::MissingNullTerminator);
+        }
+        std::str::from_utf8(text).map_err(|_| CdrError::InvalidUtf8)
+    }
+}
+
+/// Types that can be encoded as CDR
+pub trait CdrSerialize {
+    fn cdr_serialize(&self, writer: &mut CdrWriter);
+}
+
+/// Types that can be decoded from CDR
+pub trait CdrDeserialize: Sized {
+    fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError>;
+}
+
+/// Serialize a value into a CDR buffer including the encapsulation header
+pub fn to_cdr<T: CdrSerialize + ?Sized>(value: &T) -> Vec<u8> {
+    let mut writer = CdrWriter::new();
+    value.cdr_serialize(&mut writer);
+    writer.into_bytes()
+}
+
+/// Deserialize a value from a CDR buffer including the encapsulation header
+pub fn from_cdr<T: CdrDeserialize>(data: &[u8]) -> Result<T, CdrError> {
+    let mut reader = CdrReader::new(data)?;
+    T::cdr_deserialize(&mut reader)
+}
+
+macro_rules! impl_cdr_primitive {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl CdrSerialize for $t {
+                fn cdr_serialize(&self, writer: &mut CdrWriter) {
+                    writer.align(std::mem::size_of::<$t>());
+                    writer.write_bytes(&self.to_le_bytes());
+                }
+            }
+
+            impl CdrDeserialize for $t {
+                fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+                    Ok(<$t>::from_le_bytes(reader.read_array()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_cdr_primitive!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+impl CdrSerialize for bool {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        writer.write_bytes(&[*self as u8]);
+    }
+}
+
+impl CdrDeserialize for bool {
+    fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        match reader.read_bytes(1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(CdrError::InvalidBool(other)),
+        }
+    }
+}
+
+impl CdrSerialize for str {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        writer.write_str(self);
+    }
+}
+
+impl CdrSerialize for std::string::String {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        writer.write_str(self);
+    }
+}
+
+impl CdrDeserialize for std::string::String {
+    fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        reader.read_str().map(str::to_string)
+    }
+}
+
+impl CdrSerialize for crate::String {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        writer.write_str(self.as_str());
+    }
+}
+
+impl<T: CdrSerialize> CdrSerialize for [T] {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        writer.write_len(self.len());
+        for item in self {
+            item.cdr_serialize(writer);
+        }
+    }
+}
+
+impl<T: CdrSerialize> CdrSerialize for Vec<T> {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        self.as_slice().cdr_serialize(writer);
+    }
+}
+
+impl<T: CdrDeserialize> CdrDeserialize for Vec<T> {
+    fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let len = reader.read_len()?;
+        (0..len).map(|_| T::cdr_deserialize(reader)).collect()
+    }
+}
+
+impl<T: CdrSerialize, const N: usize> CdrSerialize for [T; N] {
+    fn cdr_serialize(&self, writer: &mut CdrWriter) {
+        // Fixed-size arrays carry no length prefix
+        for item in self {
+            item.cdr_serialize(writer);
+        }
+    }
+}
+
+impl<T: CdrDeserialize, const N: usize> CdrDeserialize for [T; N] {
+    fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+        let items = (0..N)
+            .map(|_| T::cdr_deserialize(reader))
+            .collect::<Result<Vec<_>, _>>()?;
+        items.try_into().map_err(|_| CdrError::UnexpectedEof)
+    }
+}
+
+macro_rules! impl_cdr_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: CdrSerialize),+> CdrSerialize for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn cdr_serialize(&self, writer: &mut CdrWriter) {
+                let ($($name,)+) = self;
+                $($name.cdr_serialize(writer);)+
+            }
+        }
+
+        impl<$($name: CdrDeserialize),+> CdrDeserialize for ($($name,)+) {
+            fn cdr_deserialize(reader: &mut CdrReader<'_>) -> Result<Self, CdrError> {
+                Ok(($($name::cdr_deserialize(reader)?,)+))
+            }
+        }
+    };
+}
+
+impl_cdr_tuple!(A);
+impl_cdr_tuple!(A, B);
+impl_cdr_tuple!(A, B, C);
+impl_cdr_tuple!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitive_alignment() {
+        // u8 followed by u32: 3 bytes of padding after the u8
+        let bytes = to_cdr(&(1u8, 2u32));
+        assert_eq!(bytes, vec![0, 1, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+
+        let (a, b): (u8, u32) = from_cdr(&bytes).unwrap();
+        assert_eq!((a, b), (1, 2));
+    }
+
+    #[test]
+    fn test_string_encoding() {
+        let bytes = to_cdr("hi");
+        assert_eq!(bytes, vec![0, 1, 0, 0, 3, 0, 0, 0, b'h', b'i', 0]);
+        assert_eq!(from_cdr::<std::string::String>(&bytes).unwrap(), "hi");
+    }
+
+    #[test]
+    fn test_sequence_and_array_roundtrip() {
+        let value = (vec![1.5f64, -2.0], [7i16, 8, 9], true);
+        let bytes = to_cdr(&value);
+        let decoded: (Vec<f64>, [i16; 3], bool) = from_cdr(&bytes).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_big_endian_input() {
+        let bytes = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02];
+        assert_eq!(from_cdr::<u32>(&bytes).unwrap(), 0x0102);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            from_cdr::<u32>(&[0, 1, 0, 0, 1]),
+            Err(CdrError::UnexpectedEof)
+        );
+        assert_eq!(
+            from_cdr::<u8>(&[0, 9, 0, 0, 1]),
+            Err(CdrError::InvalidEncapsulation([0, 9]))
+        );
+        assert_eq!(
+            from_cdr::<bool>(&[0, 1, 0, 0, 2]),
+            Err(CdrError::InvalidBool(2))
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! YAML-like rendering of messages, as `ros2 topic echo` prints them
+//!
+//! Generated idiomatic messages implement [`std::fmt::Display`] with
+//! [`fmt_message`]: one `name: value` line per field, nested messages indented
+//! below their field and sequences as `- ` lists cut after [`TRUNCATE_LENGTH`]
+//! elements, which is far easier to read in logs than the derived `Debug`.
+//!
+//! ```text
+//! header:
+//!   stamp:
+//!     sec: 12
+//!     nanosec: 0
+//!   frame_id: 'map'
+//! points:
+//! - x: 1.0
+//!   y: 2.0
+//! ```
+
+/// Elements of a sequence shown before the rest is elided as `- '...'`
+/// (the default `--truncate-length` of `ros2 topic echo`)
+pub const TRUNCATE_LENGTH: usize = 128;
+
+/// Shape of a displayed value
+pub enum Node<'a> {
+    /// Value written on the line of its field, such as `1.5` or `'text'`
+    Scalar(String),
+    /// Fields of a message, in definition order
+    Message(Vec<(&'static str, &'a dyn DisplayValue)>),
+    /// First [`TRUNCATE_LENGTH`] elements of a sequence or array, with its length
+    Sequence(Vec<&'a dyn DisplayValue>, usize),
+}
+
+/// Field value that can be displayed, implemented by generated messages
+pub trait DisplayValue {
+    fn node(&self) -> Node<'_>;
+}
+
+/// Write `message` with one line per field, without a trailing newline
+pub fn fmt_message(
+    message: &dyn DisplayValue,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    let mut out = String::new();
+    match message.node() {
+        Node::Message(fields) if fields.is_empty() => out.push_str("{}"),
+        Node::Message(fields) => write_fields(&mut out, &fields, 0, false),
+        Node::Scalar(value) => out.push_str(&value),
+        Node::Sequence(items, len) => write_items(&mut out, &items, len, 0),
+    }
+    f.write_str(out.trim_end_matches('\n'))
+}
+
+/// Fields at `indent`, the first one on the current line if `inline_first`
+fn write_fields(
+    out: &mut String,
+    fields: &[(&'static str, &dyn DisplayValue)],
+    indent: usize,
+    inline_first: bool,
+) {
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 || !inline_first {
+            pad(out, indent);
+        }
+        out.push_str(name);
+        out.push(':');
+        write_value(out, value.node(), indent);
+    }
+}
+
+/// Value of a field whose `name:` is already written
+fn write_value(out: &mut String, node: Node<'_>, indent: usize) {
+    match node {
+        Node::Scalar(value) => {
+            out.push(' ');
+            out.push_str(&value);
+            out.push('\n');
+        }
+        Node::Message(fields) if fields.is_empty() => out.push_str(" {}\n"),
+        Node::Message(fields) => {
+            out.push('\n');
+            write_fields(out, &fields, indent + 2, false);
+        }
+        Node::Sequence(_, 0) => out.push_str(" []\n"),
+        Node::Sequence(items, len) => {
+            out.push('\n');
+            write_items(out, &items, len, indent);
+        }
+    }
+}
+
+/// `- ` list items at `indent`; the first field of a message shares the dash line
+fn write_items(out: &mut String, items: &[&dyn DisplayValue], len: usize, indent: usize) {
+    for item in items {
+        pad(out, indent);
+        out.push('-');
+        match item.node() {
+            Node::Message(fields) if !fields.is_empty() => {
+                out.push(' ');
+                write_fields(out, &fields, indent + 2, true);
+            }
+            node => write_value(out, node, indent + 2),
+        }
+    }
+    if len > items.len() {
+        pad(out, indent);
+        out.push_str("- '...'\n");
+    }
+}
+
+fn pad(out: &mut String, indent: usize) {
+    out.push_str(&" ".repeat(indent));
+}
+
+/// Node of a sequence or array, truncated to [`TRUNCATE_LENGTH`] elements
+pub fn sequence<T: DisplayValue>(items: &[T]) -> Node<'_> {
+    Node::Sequence(
+        items
+            .iter()
+            .take(TRUNCATE_LENGTH)
+            .map(|item| item as &dyn DisplayValue)
+            .collect(),
+        items.len(),
+    )
+}
+
+macro_rules! impl_display_value {
+    ($fmt:literal => $($t:ty),*) => {
+        $(impl DisplayValue for $t {
+            fn node(&self) -> Node<'_> {
+                Node::Scalar(format!($fmt, self))
+            }
+        })*
+    };
+}
+
+impl_display_value!("{}" => bool, i8, u8, i16, u16, i32, u32, i64, u64);
+// Debug keeps the fraction of whole numbers (`1.0`, not `1`)
+impl_display_value!("{:?}" => f32, f64);
+
+impl DisplayValue for std::string::String {
+    fn node(&self) -> Node<'_> {
+        // Single-quoted YAML scalar, where a quote is escaped by doubling it
+        Node::Scalar(format!("'{}'", self.replace('\'', "''")))
+    }
+}
+
+impl<T: DisplayValue> DisplayValue for Vec<T> {
+    fn node(&self) -> Node<'_> {
+        sequence(self)
+    }
+}
+
+impl<T: DisplayValue, const N: usize> DisplayValue for [T; N] {
+    fn node(&self) -> Node<'_> {
+        sequence(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt;
+
+    struct Point {
+        x: f64,
+        y: f64,
+    }
+
+    impl DisplayValue for Point {
+        fn node(&self) -> Node<'_> {
+            Node::Message(vec![("x", &self.x), ("y", &self.y)])
+        }
+    }
+
+    struct Path {
+        frame_id: std::string::String,
+        origin: Point,
+        points: Vec<Point>,
+        ids: Vec<u8>,
+        empty: Vec<i32>,
+    }
+
+    impl DisplayValue for Path {
+        fn node(&self) -> Node<'_> {
+            Node::Message(vec![
+                ("frame_id", &self.frame_id),
+                ("origin", &self.origin),
+                ("points", &self.points),
+                ("ids", &self.ids),
+                ("empty", &self.empty),
+            ])
+        }
+    }
+
+    impl fmt::Display for Path {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt_message(self, f)
+        }
+    }
+
+    #[test]
+    fn test_fmt_message() {
+        let path = Path {
+            frame_id: "robot's map".to_string(),
+            origin: Point { x: 1.0, y: -0.5 },
+            points: vec![Point { x: 0.0, y: 2.0 }, Point { x: 3.0, y: 4.0 }],
+            ids: (0..=TRUNCATE_LENGTH as u8).collect(),
+            empty: Vec::new(),
+        };
+        let text = path.to_string();
+        assert!(text.starts_with(
+            "frame_id: 'robot''s map'\n\
+             origin:\n  x: 1.0\n  y: -0.5\n\
+             points:\n- x: 0.0\n  y: 2.0\n- x: 3.0\n  y: 4.0\n\
+             ids:\n- 0\n- 1\n"
+        ));
+        assert!(text.ends_with("- 127\n- '...'\nempty: []"));
+    }
+}
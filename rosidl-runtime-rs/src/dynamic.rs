@@ -0,0 +1,935 @@
+//! Dynamic messages
+//!
+//! Builds messages at runtime from `.msg` definitions instead of generated code.
+//! A [`DynamicMessageFactory`] holds the parsed definitions (the `rosidl_parser`
+//! AST of each type, not the C introspection type support) and creates
+//! [`DynamicMessage`] values whose fields can be read and written by name,
+//! type-checked against the definition, and encoded to or decoded from CDR. Useful for scripting tools and generic bridges where generating and
+//! compiling a crate per type is too heavy.
+//!
+//! Requires the `dynamic` feature.
+//!
+//! # Example
+//! ```
+//! use rosidl_runtime_rs::dynamic::{DynamicMessageFactory, DynamicValue};
+//!
+//! let mut factory = DynamicMessageFactory::new();
+//! factory
+//!     .add_definition("geometry_msgs/msg/Point", "float64 x\nfloat64 y\nfloat64 z\n")
+//!     .unwrap();
+//!
+//! let mut point = factory.create("geometry_msgs/msg/Point").unwrap();
+//! point.set("x", 1.5f64).unwrap();
+//! assert_eq!(point.get("x"), Some(&DynamicValue::Float64(1.5)));
+//! assert!(point.set("x", "not a number").is_err());
+//!
+//! let bytes = point.to_cdr();
+//! let decoded = factory.from_cdr("geometry_msgs/msg/Point", &bytes).unwrap();
+//! assert_eq!(decoded, point);
+//! ```
+
+use crate::cdr::{CdrDeserialize, CdrError, CdrReader, CdrSerialize, CdrWriter};
+use rosidl_parser::ast::ConstantValue;
+use rosidl_parser::{Field, FieldType, Message, PrimitiveType};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Error while building, modifying or decoding a dynamic message
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicError {
+    /// Type name is not `pkg/msg/Name` or `pkg/Name`
+    InvalidTypeName(String),
+    /// Definition text could not be parsed
+    Parse { type_name: String, message: String },
+    /// No definition registered for the type
+    UnknownType(String),
+    /// Type contains itself other than through a sequence, so it has no finite value
+    RecursiveType(String),
+    /// Message has no field with this name
+    UnknownField { type_name: String, field: String },
+    /// Value does not match the field's declared type
+    TypeMismatch { field: String, expected: String },
+    /// String or sequence is longer than its bound, or array has the wrong length
+    BoundExceeded {
+        field: String,
+        bound: usize,
+        len: usize,
+    },
+    /// CDR decoding failed
+    Cdr(CdrError),
+}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicError::InvalidTypeName(name) => {
+                write!(f, "invalid message type name '{}'", name)
+            }
+            DynamicError::Parse { type_name, message } => {
+                write!(
+                    f,
+                    "failed to parse definition of {}: {}",
+                    type_name, message
+                )
+            }
+            DynamicError::UnknownType(name) => write!(f, "no definition for message type {}", name),
+            DynamicError::RecursiveType(name) => {
+                write!(f, "message type {} contains itself", name)
+            }
+            DynamicError::UnknownField { type_name, field } => {
+                write!(f, "{} has no field '{}'", type_name, field)
+            }
+            DynamicError::TypeMismatch { field, expected } => {
+                write!(f, "field '{}' expects a value of type {}", field, expected)
+            }
+            DynamicError::BoundExceeded { field, bound, len } => write!(
+                f,
+                "field '{}' holds {} element(s), bound is {}",
+                field, len, bound
+            ),
+            DynamicError::Cdr(err) => write!(f, "CDR error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DynamicError {}
+
+impl From<CdrError> for DynamicError {
+    fn from(err: CdrError) -> Self {
+        DynamicError::Cdr(err)
+    }
+}
+
+/// Parsed definition of one message type
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageDescription {
+    /// Package the type belongs to (used to resolve unqualified nested types)
+    pub package: String,
+    /// Full type name (`pkg/msg/Name`)
+    pub type_name: String,
+    /// Parsed definition
+    pub message: Message,
+}
+
+impl MessageDescription {
+    /// Field definitions in declaration order
+    pub fn fields(&self) -> &[Field] {
+        &self.message.fields
+    }
+
+    fn field_index(&self, name: &str) -> Option<usize> {
+        self.message.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Full type name of a nested field type, resolved against this package
+    fn nested_type_name(&self, package: Option<&str>, name: &str) -> String {
+        format!("{}/msg/{}", package.unwrap_or(&self.package), name)
+    }
+}
+
+/// Value of a single field of a dynamic message
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    Bool(bool),
+    Byte(u8),
+    Char(u8),
+    Int8(i8),
+    UInt8(u8),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Float32(f32),
+    Float64(f64),
+    String(String),
+    WString(String),
+    Message(DynamicMessage),
+    /// Fixed-size array or (bounded) sequence
+    Array(Vec<DynamicValue>),
+}
+
+macro_rules! impl_dynamic_value_from {
+    ($($t:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$t> for DynamicValue {
+                fn from(value: $t) -> Self {
+                    DynamicValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_dynamic_value_from!(
+    bool => Bool,
+    i8 => Int8,
+    u8 => UInt8,
+    i16 => Int16,
+    u16 => UInt16,
+    i32 => Int32,
+    u32 => UInt32,
+    i64 => Int64,
+    u64 => UInt64,
+    f32 => Float32,
+    f64 => Float64,
+    String => String,
+    DynamicMessage => Message,
+);
+
+impl From<&str> for DynamicValue {
+    fn from(value: &str) -> Self {
+        DynamicValue::String(value.to_string())
+    }
+}
+
+impl<T: Into<DynamicValue>> From<Vec<T>> for DynamicValue {
+    fn from(values: Vec<T>) -> Self {
+        DynamicValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A message whose layout is only known at runtime
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicMessage {
+    description: Arc<MessageDescription>,
+    values: Vec<DynamicValue>,
+}
+
+impl DynamicMessage {
+    /// Full type name (`pkg/msg/Name`)
+    pub fn type_name(&self) -> &str {
+        &self.description.type_name
+    }
+
+    /// Parsed definition of the message type
+    pub fn description(&self) -> &MessageDescription {
+        &self.description
+    }
+
+    /// Value of a field
+    pub fn get(&self, field: &str) -> Option<&DynamicValue> {
+        let index = self.description.field_index(field)?;
+        self.values.get(index)
+    }
+
+    /// Nested message of a field, for chained access (`msg.message("header")?.get("stamp")`)
+    pub fn message(&self, field: &str) -> Option<&DynamicMessage> {
+        match self.get(field)? {
+            DynamicValue::Message(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Mutable nested message of a field
+    pub fn message_mut(&mut self, field: &str) -> Option<&mut DynamicMessage> {
+        let index = self.description.field_index(field)?;
+        match self.values.get_mut(index)? {
+            DynamicValue::Message(message) => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Set a field, checking the value against the field's type and bounds
+    ///
+    /// `u8` values are accepted for `byte` and `char` fields, and strings for
+    /// `wstring` fields.
+    pub fn set(&mut self, field: &str, value: impl Into<DynamicValue>) -> Result<(), DynamicError> {
+        let index =
+            self.description
+                .field_index(field)
+                .ok_or_else(|| DynamicError::UnknownField {
+                    type_name: self.description.type_name.clone(),
+                    field: field.to_string(),
+                })?;
+        let field_type = &self.description.message.fields[index].field_type;
+        self.values[index] = coerce(&self.description, field, field_type, value.into())?;
+        Ok(())
+    }
+
+    /// Iterate over `(field name, value)` pairs in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DynamicValue)> {
+        self.description
+            .message
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .zip(self.values.iter())
+    }
+
+    /// Encode as little-endian CDR, including the encapsulation header
+    pub fn to_cdr(&self) -> Vec<u8> {
+        let mut writer = CdrWriter::new();
+        self.write_cdr(&mut writer);
+        writer.into_bytes()
+    }
+
+    /// Encode the fields into an existing writer
+    pub fn write_cdr(&self, writer: &mut CdrWriter) {
+        for (field, value) in self.description.message.fields.iter().zip(&self.values) {
+            write_value(&field.field_type, value, writer);
+        }
+    }
+}
+
+/// Registry of message definitions that creates and decodes dynamic messages
+#[derive(Debug, Clone, Default)]
+pub struct DynamicMessageFactory {
+    descriptions: HashMap<String, Arc<MessageDescription>>,
+}
+
+impl DynamicMessageFactory {
+    /// Create an empty factory
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the `.msg` definition of `type_name` (`pkg/msg/Name` or `pkg/Name`)
+    pub fn add_definition(
+        &mut self,
+        type_name: &str,
+        definition: &str,
+    ) -> Result<(), DynamicError> {
+        let (package, name) = split_type_name(type_name)?;
+        let type_name = format!("{}/msg/{}", package, name);
+        let message =
+            rosidl_parser::parse_message(definition).map_err(|e| DynamicError::Parse {
+                type_name: type_name.clone(),
+                message: e.to_string(),
+            })?;
+
+        self.descriptions.insert(
+            type_name.clone(),
+            Arc::new(MessageDescription {
+                package: package.to_string(),
+                type_name,
+                message,
+            }),
+        );
+        Ok(())
+    }
+
+    /// Register a concatenated definition as stored in MCAP `ros2msg` schemas and
+    /// rosbag2 metadata: the root definition followed by `====`-separated
+    /// `MSG: pkg/msg/Name` sections for every nested type
+    pub fn add_concatenated_definition(
+        &mut self,
+        type_name: &str,
+        text: &str,
+    ) -> Result<(), DynamicError> {
+        let mut current = type_name.to_string();
+        let mut body = String::new();
+        for line in text.lines() {
+            if line.starts_with("====") {
+                self.add_definition(&current, &body)?;
+                body.clear();
+                continue;
+            }
+            if let Some(nested) = line.strip_prefix("MSG: ") {
+                current = nested.trim().to_string();
+                continue;
+            }
+            body.push_str(line);
+            body.push('\n');
+        }
+        self.add_definition(&current, &body)
+    }
+
+    /// Parsed definition of a registered type
+    pub fn description(&self, type_name: &str) -> Option<&MessageDescription> {
+        let (package, name) = split_type_name(type_name).ok()?;
+        self.descriptions
+            .get(&format!("{}/msg/{}", package, name))
+            .map(|d| d.as_ref())
+    }
+
+    /// Create a message with every field set to its default value
+    pub fn create(&self, type_name: &str) -> Result<DynamicMessage, DynamicError> {
+        self.create_nested(type_name, &mut Vec::new())
+    }
+
+    /// Decode a CDR buffer (with encapsulation header) as `type_name`
+    pub fn from_cdr(&self, type_name: &str, data: &[u8]) -> Result<DynamicMessage, DynamicError> {
+        let mut reader = CdrReader::new(data)?;
+        self.read_message(type_name, &mut reader, &mut Vec::new())
+    }
+
+    /// Look up `type_name` as a field nested in the types of `enclosing`, which
+    /// must not contain it already
+    fn lookup_nested(
+        &self,
+        type_name: &str,
+        enclosing: &[Arc<MessageDescription>],
+    ) -> Result<Arc<MessageDescription>, DynamicError> {
+        let description = self.lookup(type_name)?;
+        if enclosing
+            .iter()
+            .any(|outer| outer.type_name == description.type_name)
+        {
+            return Err(DynamicError::RecursiveType(description.type_name.clone()));
+        }
+        Ok(description)
+    }
+
+    /// Default message of `type_name`, nested in the types of `enclosing`
+    fn create_nested(
+        &self,
+        type_name: &str,
+        enclosing: &mut Vec<Arc<MessageDescription>>,
+    ) -> Result<DynamicMessage, DynamicError> {
+        let description = self.lookup_nested(type_name, enclosing)?;
+        enclosing.push(description.clone());
+        let values = description
+            .message
+            .fields
+            .iter()
+            .map(|field| {
+                self.default_value(
+                    &description,
+                    &field.field_type,
+                    field.default_value.as_ref(),
+                    enclosing,
+                )
+            })
+            .collect::<Result<_, _>>();
+        enclosing.pop();
+        Ok(DynamicMessage {
+            description,
+            values: values?,
+        })
+    }
+
+    fn lookup(&self, type_name: &str) -> Result<Arc<MessageDescription>, DynamicError> {
+        let (package, name) = split_type_name(type_name)?;
+        self.descriptions
+            .get(&format!("{}/msg/{}", package, name))
+            .cloned()
+            .ok_or_else(|| DynamicError::UnknownType(type_name.to_string()))
+    }
+
+    fn default_value(
+        &self,
+        description: &MessageDescription,
+        field_type: &FieldType,
+        default: Option<&ConstantValue>,
+        enclosing: &mut Vec<Arc<MessageDescription>>,
+    ) -> Result<DynamicValue, DynamicError> {
+        Ok(match field_type {
+            FieldType::Primitive(primitive) => primitive_default(*primitive, default),
+            FieldType::String | FieldType::BoundedString(_) => {
+                DynamicValue::String(string_default(default))
+            }
+            FieldType::WString | FieldType::BoundedWString(_) => {
+                DynamicValue::WString(string_default(default))
+            }
+            FieldType::Array { element_type, size } => DynamicValue::Array(match default {
+                Some(ConstantValue::Array(values)) => values
+                    .iter()
+                    .map(|value| {
+                        self.default_value(description, element_type, Some(value), enclosing)
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => (0..*size)
+                    .map(|_| self.default_value(description, element_type, None, enclosing))
+                    .collect::<Result<_, _>>()?,
+            }),
+            FieldType::Sequence { element_type }
+            | FieldType::BoundedSequence { element_type, .. } => {
+                DynamicValue::Array(match default {
+                    Some(ConstantValue::Array(values)) => values
+                        .iter()
+                        .map(|value| {
+                            self.default_value(description, element_type, Some(value), enclosing)
+                        })
+                        .collect::<Result<_, _>>()?,
+                    _ => Vec::new(),
+                })
+            }
+            FieldType::NamespacedType { package, name } => {
+                DynamicValue::Message(self.create_nested(
+                    &description.nested_type_name(package.as_deref(), name),
+                    enclosing,
+                )?)
+            }
+        })
+    }
+
+    /// Decode a message of `type_name`, nested in the types of `enclosing`
+    fn read_message(
+        &self,
+        type_name: &str,
+        reader: &mut CdrReader<'_>,
+        enclosing: &mut Vec<Arc<MessageDescription>>,
+    ) -> Result<DynamicMessage, DynamicError> {
+        let description = self.lookup_nested(type_name, enclosing)?;
+        enclosing.push(description.clone());
+        let values = description
+            .message
+            .fields
+            .iter()
+            .map(|field| self.read_value(&description, &field.field_type, reader, enclosing))
+            .collect::<Result<_, _>>();
+        enclosing.pop();
+        Ok(DynamicMessage {
+            description,
+            values: values?,
+        })
+    }
+
+    fn read_value(
+        &self,
+        description: &MessageDescription,
+        field_type: &FieldType,
+        reader: &mut CdrReader<'_>,
+        enclosing: &mut Vec<Arc<MessageDescription>>,
+    ) -> Result<DynamicValue, DynamicError> {
+        Ok(match field_type {
+            FieldType::Primitive(primitive) => read_primitive(*primitive, reader)?,
+            FieldType::String | FieldType::BoundedString(_) => {
+                DynamicValue::String(String::cdr_deserialize(reader)?)
+            }
+            FieldType::WString | FieldType::BoundedWString(_) => {
+                let len = reader.read_len()?;
+                let units = (0..len)
+                    .map(|_| u16::cdr_deserialize(reader))
+                    .collect::<Result<Vec<_>, _>>()?;
+                DynamicValue::WString(
+                    String::from_utf16(&units).map_err(|_| CdrError::InvalidUtf8)?,
+                )
+            }
+            FieldType::Array { element_type, size } => DynamicValue::Array(
+                (0..*size)
+                    .map(|_| self.read_value(description, element_type, reader, enclosing))
+                    .collect::<Result<_, _>>()?,
+            ),
+            FieldType::Sequence { element_type }
+            | FieldType::BoundedSequence { element_type, .. } => {
+                // Each element reads at least its length prefix, so a type may
+                // contain itself through a sequence
+                let len = reader.read_len()?;
+                DynamicValue::Array(
+                    (0..len)
+                        .map(|_| {
+                            self.read_value(description, element_type, reader, &mut Vec::new())
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            FieldType::NamespacedType { package, name } => {
+                DynamicValue::Message(self.read_message(
+                    &description.nested_type_name(package.as_deref(), name),
+                    reader,
+                    enclosing,
+                )?)
+            }
+        })
+    }
+}
+
+/// Split `pkg/msg/Name` or `pkg/Name` into package and name
+fn split_type_name(type_name: &str) -> Result<(&str, &str), DynamicError> {
+    let parts: Vec<&str> = type_name.split('/').collect();
+    match parts.as_slice() {
+        [package, name] | [package, "msg", name] if !package.is_empty() && !name.is_empty() => {
+            Ok((*package, *name))
+        }
+        _ => Err(DynamicError::InvalidTypeName(type_name.to_string())),
+    }
+}
+
+fn string_default(default: Option<&ConstantValue>) -> String {
+    default
+        .and_then(ConstantValue::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn primitive_default(primitive: PrimitiveType, default: Option<&ConstantValue>) -> DynamicValue {
+    let int = match default {
+        Some(ConstantValue::Integer(i)) => i128::from(*i),
+        Some(ConstantValue::UInteger(u)) => i128::from(*u),
+        Some(ConstantValue::Bool(b)) => i128::from(*b),
+        _ => 0,
+    };
+    let float = match default {
+        Some(ConstantValue::Float(f)) => *f,
+        Some(ConstantValue::Integer(i)) => *i as f64,
+        Some(ConstantValue::UInteger(u)) => *u as f64,
+        _ => 0.0,
+    };
+    match primitive {
+        PrimitiveType::Bool => DynamicValue::Bool(int != 0),
+        PrimitiveType::Byte => DynamicValue::Byte(int as u8),
+        PrimitiveType::Char => DynamicValue::Char(int as u8),
+        PrimitiveType::Int8 => DynamicValue::Int8(int as i8),
+        PrimitiveType::UInt8 => DynamicValue::UInt8(int as u8),
+        PrimitiveType::Int16 => DynamicValue::Int16(int as i16),
+        PrimitiveType::UInt16 => DynamicValue::UInt16(int as u16),
+        PrimitiveType::Int32 => DynamicValue::Int32(int as i32),
+        PrimitiveType::UInt32 => DynamicValue::UInt32(int as u32),
+        PrimitiveType::Int64 => DynamicValue::Int64(int as i64),
+        PrimitiveType::UInt64 => DynamicValue::UInt64(int as u64),
+        PrimitiveType::Float32 => DynamicValue::Float32(float as f32),
+        PrimitiveType::Float64 => DynamicValue::Float64(float),
+    }
+}
+
+/// Check `value` against `field_type`, applying the implicit conversions of `set`
+fn coerce(
+    description: &MessageDescription,
+    field: &str,
+    field_type: &FieldType,
+    value: DynamicValue,
+) -> Result<DynamicValue, DynamicError> {
+    let mismatch = || DynamicError::TypeMismatch {
+        field: field.to_string(),
+        expected: field_type.to_string(),
+    };
+    let check_len = |bound: usize, len: usize| {
+        if len > bound {
+            Err(DynamicError::BoundExceeded {
+                field: field.to_string(),
+                bound,
+                len,
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    match (field_type, value) {
+        (
+            FieldType::Primitive(PrimitiveType::Byte),
+            DynamicValue::UInt8(v) | DynamicValue::Byte(v),
+        ) => Ok(DynamicValue::Byte(v)),
+        (
+            FieldType::Primitive(PrimitiveType::Char),
+            DynamicValue::UInt8(v) | DynamicValue::Char(v),
+        ) => Ok(DynamicValue::Char(v)),
+        (FieldType::Primitive(primitive), value) => {
+            if primitive_default(*primitive, None).same_variant(&value) {
+                Ok(value)
+            } else {
+                Err(mismatch())
+            }
+        }
+        (FieldType::String, DynamicValue::String(s)) => Ok(DynamicValue::String(s)),
+        (FieldType::BoundedString(bound), DynamicValue::String(s)) => {
+            check_len(*bound, s.chars().count())?;
+            Ok(DynamicValue::String(s))
+        }
+        (FieldType::WString, DynamicValue::String(s) | DynamicValue::WString(s)) => {
+            Ok(DynamicValue::WString(s))
+        }
+        (FieldType::BoundedWString(bound), DynamicValue::String(s) | DynamicValue::WString(s)) => {
+            check_len(*bound, s.chars().count())?;
+            Ok(DynamicValue::WString(s))
+        }
+        (FieldType::Array { element_type, size }, DynamicValue::Array(values)) => {
+            if values.len() != *size {
+                return Err(DynamicError::BoundExceeded {
+                    field: field.to_string(),
+                    bound: *size,
+                    len: values.len(),
+                });
+            }
+            coerce_elements(description, field, element_type, values)
+        }
+        (FieldType::Sequence { element_type }, DynamicValue::Array(values)) => {
+            coerce_elements(description, field, element_type, values)
+        }
+        (
+            FieldType::BoundedSequence {
+                element_type,
+                max_size,
+            },
+            DynamicValue::Array(values),
+        ) => {
+            check_len(*max_size, values.len())?;
+            coerce_elements(description, field, element_type, values)
+        }
+        (FieldType::NamespacedType { package, name }, DynamicValue::Message(message)) => {
+            if message.type_name() == description.nested_type_name(package.as_deref(), name) {
+                Ok(DynamicValue::Message(message))
+            } else {
+                Err(mismatch())
+            }
+        }
+        _ => Err(mismatch()),
+    }
+}
+
+fn coerce_elements(
+    description: &MessageDescription,
+    field: &str,
+    element_type: &FieldType,
+    values: Vec<DynamicValue>,
+) -> Result<DynamicValue, DynamicError> {
+    values
+        .into_iter()
+        .map(|value| coerce(description, field, element_type, value))
+        .collect::<Result<_, _>>()
+        .map(DynamicValue::Array)
+}
+
+impl DynamicValue {
+    fn same_variant(&self, other: &DynamicValue) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+fn read_primitive(
+    primitive: PrimitiveType,
+    reader: &mut CdrReader<'_>,
+) -> Result<DynamicValue, CdrError> {
+    Ok(match primitive {
+        PrimitiveType::Bool => DynamicValue::Bool(bool::cdr_deserialize(reader)?),
+        PrimitiveType::Byte => DynamicValue::Byte(u8::cdr_deserialize(reader)?),
+        PrimitiveType::Char => DynamicValue::Char(u8::cdr_deserialize(reader)?),
+        PrimitiveType::Int8 => DynamicValue::Int8(i8::cdr_deserialize(reader)?),
+        PrimitiveType::UInt8 => DynamicValue::UInt8(u8::cdr_deserialize(reader)?),
+        PrimitiveType::Int16 => DynamicValue::Int16(i16::cdr_deserialize(reader)?),
+        PrimitiveType::UInt16 => DynamicValue::UInt16(u16::cdr_deserialize(reader)?),
+        PrimitiveType::Int32 => DynamicValue::Int32(i32::cdr_deserialize(reader)?),
+        PrimitiveType::UInt32 => DynamicValue::UInt32(u32::cdr_deserialize(reader)?),
+        PrimitiveType::Int64 => DynamicValue::Int64(i64::cdr_deserialize(reader)?),
+        PrimitiveType::UInt64 => DynamicValue::UInt64(u64::cdr_deserialize(reader)?),
+        PrimitiveType::Float32 => DynamicValue::Float32(f32::cdr_deserialize(reader)?),
+        PrimitiveType::Float64 => DynamicValue::Float64(f64::cdr_deserialize(reader)?),
+    })
+}
+
+fn write_value(field_type: &FieldType, value: &DynamicValue, writer: &mut CdrWriter) {
+    match value {
+        DynamicValue::Bool(v) => v.cdr_serialize(writer),
+        DynamicValue::Byte(v) | DynamicValue::Char(v) | DynamicValue::UInt8(v) => {
+            v.cdr_serialize(writer)
+        }
+        DynamicValue::Int8(v) => v.cdr_serialize(writer),
+        DynamicValue::Int16(v) => v.cdr_serialize(writer),
+        DynamicValue::UInt16(v) => v.cdr_serialize(writer),
+        DynamicValue::Int32(v) => v.cdr_serialize(writer),
+        DynamicValue::UInt32(v) => v.cdr_serialize(writer),
+        DynamicValue::Int64(v) => v.cdr_serialize(writer),
+        DynamicValue::UInt64(v) => v.cdr_serialize(writer),
+        DynamicValue::Float32(v) => v.cdr_serialize(writer),
+        DynamicValue::Float64(v) => v.cdr_serialize(writer),
+        DynamicValue::String(v) => writer.write_str(v),
+        // Wide strings are carried as UTF-16 code units with a unit count prefix
+        DynamicValue::WString(v) => {
+            let units: Vec<u16> = v.encode_utf16().collect();
+            writer.write_len(units.len());
+            for unit in units {
+                unit.cdr_serialize(writer);
+            }
+        }
+        DynamicValue::Message(message) => message.write_cdr(writer),
+        DynamicValue::Array(values) => {
+            let element_type = match field_type {
+                FieldType::Array { element_type, .. } => element_type,
+                FieldType::Sequence { element_type }
+                | FieldType::BoundedSequence { element_type, .. } => {
+                    writer.write_len(values.len());
+                    element_type
+                }
+                _ => field_type,
+            };
+            for value in values {
+                write_value(element_type, value, writer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cdr::to_cdr;
+
+    fn factory() -> DynamicMessageFactory {
+        let mut factory = DynamicMessageFactory::new();
+        factory
+            .add_definition("builtin_interfaces/msg/Time", "int32 sec\nuint32 nanosec\n")
+            .unwrap();
+        factory
+            .add_definition(
+                "my_msgs/msg/Reading",
+                "builtin_interfaces/Time stamp\nstring<=8 label \"none\"\nfloat64[2] values\nint16[<=3] codes\nuint8 level 7\n",
+            )
+            .unwrap();
+        factory
+    }
+
+    #[test]
+    fn test_create_with_defaults() {
+        let msg = factory().create("my_msgs/Reading").unwrap();
+        assert_eq!(msg.type_name(), "my_msgs/msg/Reading");
+        assert_eq!(msg.get("label"), Some(&DynamicValue::String("none".into())));
+        assert_eq!(msg.get("level"), Some(&DynamicValue::UInt8(7)));
+        assert_eq!(
+            msg.get("values"),
+            Some(&DynamicValue::Array(vec![DynamicValue::Float64(0.0); 2]))
+        );
+        assert_eq!(
+            msg.message("stamp").unwrap().get("sec"),
+            Some(&DynamicValue::Int32(0))
+        );
+        let names: Vec<&str> = msg.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["stamp", "label", "values", "codes", "level"]);
+    }
+
+    #[test]
+    fn test_create_with_array_defaults() {
+        let mut factory = DynamicMessageFactory::new();
+        factory
+            .add_definition(
+                "my_msgs/msg/Gains",
+                "float64[2] gains [1, 0.5]\nstring[] names [\"a\"]\nuint64 mask 18446744073709551615\n",
+            )
+            .unwrap();
+        let msg = factory.create("my_msgs/Gains").unwrap();
+        assert_eq!(msg.get("mask"), Some(&DynamicValue::UInt64(u64::MAX)));
+        assert_eq!(
+            msg.get("gains"),
+            Some(&DynamicValue::Array(vec![
+                DynamicValue::Float64(1.0),
+                DynamicValue::Float64(0.5),
+            ]))
+        );
+        assert_eq!(
+            msg.get("names"),
+            Some(&DynamicValue::Array(vec![DynamicValue::String("a".into())]))
+        );
+    }
+
+    #[test]
+    fn test_set_checks_types_and_bounds() {
+        let mut msg = factory().create("my_msgs/msg/Reading").unwrap();
+
+        msg.set("label", "ok").unwrap();
+        msg.set("codes", vec![1i16, 2]).unwrap();
+        msg.message_mut("stamp").unwrap().set("sec", 12i32).unwrap();
+        assert_eq!(
+            msg.message("stamp").unwrap().get("sec"),
+            Some(&DynamicValue::Int32(12))
+        );
+
+        assert!(matches!(
+            msg.set("level", 1i32),
+            Err(DynamicError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            msg.set("label", "much too long"),
+            Err(DynamicError::BoundExceeded { bound: 8, .. })
+        ));
+        assert!(matches!(
+            msg.set("codes", vec![1i16, 2, 3, 4]),
+            Err(DynamicError::BoundExceeded {
+                bound: 3,
+                len: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            msg.set("values", vec![1.0f64]),
+            Err(DynamicError::BoundExceeded {
+                bound: 2,
+                len: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            msg.set("missing", 1i32),
+            Err(DynamicError::UnknownField { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cdr_matches_static_encoding() {
+        let factory = factory();
+        let mut msg = factory.create("my_msgs/msg/Reading").unwrap();
+        msg.message_mut("stamp").unwrap().set("sec", 5i32).unwrap();
+        msg.set("values", vec![1.5f64, -2.0]).unwrap();
+        msg.set("codes", vec![3i16]).unwrap();
+
+        let expected = to_cdr(&(
+            (5i32, 0u32),
+            "none".to_string(),
+            [1.5f64, -2.0],
+            (vec![3i16], 7u8),
+        ));
+        assert_eq!(msg.to_cdr(), expected);
+
+        let decoded = factory.from_cdr("my_msgs/msg/Reading", &expected).unwrap();
+        assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn test_concatenated_definition() {
+        let separator = "=".repeat(80);
+        let text = format!(
+            "std_msgs/Header header\nstring data\n{}\nMSG: std_msgs/Header\nbuiltin_interfaces/Time stamp\nstring frame_id\n{}\nMSG: builtin_interfaces/Time\nint32 sec\nuint32 nanosec\n",
+            separator, separator
+        );
+        let mut factory = DynamicMessageFactory::new();
+        factory
+            .add_concatenated_definition("my_msgs/msg/Labeled", &text)
+            .unwrap();
+
+        assert!(factory.description("std_msgs/msg/Header").is_some());
+        let msg = factory.create("my_msgs/msg/Labeled").unwrap();
+        assert!(msg.message("header").unwrap().message("stamp").is_some());
+    }
+
+    #[test]
+    fn test_recursive_type() {
+        let mut factory = DynamicMessageFactory::new();
+        factory
+            .add_definition("my_msgs/Node", "int32 id\nmy_msgs/Link link\n")
+            .unwrap();
+        factory
+            .add_definition("my_msgs/Link", "my_msgs/Node[2] ends\n")
+            .unwrap();
+        factory
+            .add_definition("my_msgs/Tree", "int32 id\nmy_msgs/Tree[] children\n")
+            .unwrap();
+
+        let err = DynamicError::RecursiveType("my_msgs/msg/Node".to_string());
+        assert_eq!(factory.create("my_msgs/Node").unwrap_err(), err);
+        assert_eq!(
+            factory
+                .from_cdr("my_msgs/Node", &to_cdr(&0i32))
+                .unwrap_err(),
+            err
+        );
+
+        // Through a sequence the nesting ends with the data
+        let mut child = factory.create("my_msgs/Tree").unwrap();
+        child.set("id", 2i32).unwrap();
+        let mut root = factory.create("my_msgs/Tree").unwrap();
+        root.set("children", vec![DynamicValue::Message(child)])
+            .unwrap();
+        assert_eq!(
+            factory.from_cdr("my_msgs/Tree", &root.to_cdr()).unwrap(),
+            root
+        );
+    }
+
+    #[test]
+    fn test_unknown_nested_type() {
+        let mut factory = DynamicMessageFactory::new();
+        factory
+            .add_definition("my_msgs/Wrapper", "geometry_msgs/Point point\n")
+            .unwrap();
+        assert_eq!(
+            factory.create("my_msgs/Wrapper").unwrap_err(),
+            DynamicError::UnknownType("geometry_msgs/msg/Point".to_string())
+        );
+        assert!(matches!(
+            factory.create("not a type"),
+            Err(DynamicError::InvalidTypeName(_))
+        ));
+    }
+}
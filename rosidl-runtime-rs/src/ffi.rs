@@ -7,6 +7,10 @@
 //! - Proper initialization/finalization
 //!
 //! Most users should use the safe wrappers in `string` and `sequence` modules instead.
+//!
+//! With the `mock-ffi` feature the functions are implemented in Rust instead of
+//! being linked from rosidl_runtime_c, so the crate builds without a ROS
+//! installation and for targets such as `wasm32-unknown-unknown`.
 
 use std::os::raw::c_char;
 
@@ -19,6 +23,15 @@ pub struct rosidl_runtime_c__String {
     pub capacity: usize,
 }
 
+/// C-compatible wide string structure (mirrors rosidl_runtime_c__U16String)
+#[repr(C)]
+#[derive(Debug)]
+pub struct rosidl_runtime_c__U16String {
+    pub data: *mut u16,
+    pub size: usize,
+    pub capacity: usize,
+}
+
 /// C-compatible sequence structure
 ///
 /// This is a generic container matching the layout of all rosidl_runtime_c sequences.
@@ -30,6 +43,12 @@ pub struct SequenceInner<T> {
     pub capacity: usize,
 }
 
+#[cfg(feature = "mock-ffi")]
+mod mock;
+#[cfg(feature = "mock-ffi")]
+pub use mock::*;
+
+#[cfg(not(feature = "mock-ffi"))]
 #[link(name = "rosidl_runtime_c")]
 extern "C" {
     // =========================================================================
@@ -88,6 +107,56 @@ extern "C" {
         output: *mut SequenceInner<rosidl_runtime_c__String>,
     ) -> bool;
 
+    // =========================================================================
+    // Wide string operations
+    // =========================================================================
+
+    /// Initialize a rosidl_runtime_c__U16String structure
+    pub fn rosidl_runtime_c__U16String__init(s: *mut rosidl_runtime_c__U16String) -> bool;
+
+    /// Deallocate the memory of the rosidl_runtime_c__U16String structure
+    pub fn rosidl_runtime_c__U16String__fini(s: *mut rosidl_runtime_c__U16String);
+
+    /// Assign n UTF-16 code units to the rosidl_runtime_c__U16String structure
+    pub fn rosidl_runtime_c__U16String__assignn(
+        s: *mut rosidl_runtime_c__U16String,
+        value: *const u16,
+        n: usize,
+    ) -> bool;
+
+    /// Copy rosidl_runtime_c__U16String structure content
+    pub fn rosidl_runtime_c__U16String__copy(
+        input: *const rosidl_runtime_c__U16String,
+        output: *mut rosidl_runtime_c__U16String,
+    ) -> bool;
+
+    /// Check for rosidl_runtime_c__U16String structure equality
+    pub fn rosidl_runtime_c__U16String__are_equal(
+        lhs: *const rosidl_runtime_c__U16String,
+        rhs: *const rosidl_runtime_c__U16String,
+    ) -> bool;
+
+    // =========================================================================
+    // Wide string sequence operations
+    // =========================================================================
+
+    /// Initialize a rosidl_runtime_c__U16String__Sequence structure
+    pub fn rosidl_runtime_c__U16String__Sequence__init(
+        seq: *mut SequenceInner<rosidl_runtime_c__U16String>,
+        size: usize,
+    ) -> bool;
+
+    /// Deallocate the memory of the wide string sequence structure
+    pub fn rosidl_runtime_c__U16String__Sequence__fini(
+        seq: *mut SequenceInner<rosidl_runtime_c__U16String>,
+    );
+
+    /// Copy rosidl_runtime_c__U16String__Sequence structure content
+    pub fn rosidl_runtime_c__U16String__Sequence__copy(
+        input: *const SequenceInner<rosidl_runtime_c__U16String>,
+        output: *mut SequenceInner<rosidl_runtime_c__U16String>,
+    ) -> bool;
+
     // =========================================================================
     // Primitive sequence operations
     // =========================================================================
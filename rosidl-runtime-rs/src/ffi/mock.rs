@@ -0,0 +1,568 @@
+//! Pure-Rust implementations of the rosidl_runtime_c functions (`mock-ffi` feature)
+//!
+//! Same names, signatures and memory layout as the C library. Memory is owned by
+//! the Rust global allocator, so structures initialized here must also be
+//! finalized here; they are never passed to C code.
+
+// Names and safety contracts are those of rosidl_runtime_c
+#![allow(non_snake_case, clippy::missing_safety_doc)]
+
+use super::{rosidl_runtime_c__String, rosidl_runtime_c__U16String, SequenceInner};
+use std::alloc::{self, Layout};
+use std::os::raw::c_char;
+
+/// Allocate `len` zeroed elements (a dangling pointer when nothing is allocated)
+///
+/// Returns null only if the allocation fails.
+unsafe fn alloc_zeroed<T>(len: usize) -> *mut T {
+    if len == 0 || std::mem::size_of::<T>() == 0 {
+        return std::ptr::NonNull::dangling().as_ptr();
+    }
+    match Layout::array::<T>(len) {
+        Ok(layout) => alloc::alloc_zeroed(layout) as *mut T,
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Free memory from [`alloc_zeroed`]
+unsafe fn dealloc<T>(data: *mut T, len: usize) {
+    if data.is_null() || len == 0 || std::mem::size_of::<T>() == 0 {
+        return;
+    }
+    alloc::dealloc(data as *mut u8, Layout::array::<T>(len).unwrap());
+}
+
+// =========================================================================
+// String operations
+// =========================================================================
+
+/// Initialize a rosidl_runtime_c__String structure
+pub unsafe fn rosidl_runtime_c__String__init(s: *mut rosidl_runtime_c__String) -> bool {
+    if s.is_null() {
+        return false;
+    }
+    let data = alloc_zeroed::<c_char>(1);
+    if data.is_null() {
+        return false;
+    }
+    *s = rosidl_runtime_c__String {
+        data,
+        size: 0,
+        capacity: 1,
+    };
+    true
+}
+
+/// Deallocate the memory of the rosidl_runtime_c__String structure
+pub unsafe fn rosidl_runtime_c__String__fini(s: *mut rosidl_runtime_c__String) {
+    if s.is_null() {
+        return;
+    }
+    dealloc((*s).data, (*s).capacity);
+    (*s).data = std::ptr::null_mut();
+    (*s).size = 0;
+    (*s).capacity = 0;
+}
+
+/// Assign the c string pointer to the rosidl_runtime_c__String structure
+pub unsafe fn rosidl_runtime_c__String__assign(
+    s: *mut rosidl_runtime_c__String,
+    value: *const c_char,
+) -> bool {
+    if value.is_null() {
+        return false;
+    }
+    let n = std::ffi::CStr::from_ptr(value).to_bytes().len();
+    rosidl_runtime_c__String__assignn(s, value, n)
+}
+
+/// Assign the c string pointer of n characters to the rosidl_runtime_c__String structure
+pub unsafe fn rosidl_runtime_c__String__assignn(
+    s: *mut rosidl_runtime_c__String,
+    value: *const c_char,
+    n: usize,
+) -> bool {
+    if s.is_null() || value.is_null() || n == usize::MAX {
+        return false;
+    }
+    let data = alloc_zeroed::<c_char>(n + 1);
+    if data.is_null() {
+        return false;
+    }
+    std::ptr::copy_nonoverlapping(value, data, n);
+    dealloc((*s).data, (*s).capacity);
+    *s = rosidl_runtime_c__String {
+        data,
+        size: n,
+        capacity: n + 1,
+    };
+    true
+}
+
+/// Copy rosidl_runtime_c__String structure content
+pub unsafe fn rosidl_runtime_c__String__copy(
+    input: *const rosidl_runtime_c__String,
+    output: *mut rosidl_runtime_c__String,
+) -> bool {
+    if input.is_null() || output.is_null() {
+        return false;
+    }
+    if std::ptr::eq(input, output) {
+        return true;
+    }
+    rosidl_runtime_c__String__assignn(output, (*input).data, (*input).size)
+}
+
+/// Check for rosidl_runtime_c__String structure equality
+pub unsafe fn rosidl_runtime_c__String__are_equal(
+    lhs: *const rosidl_runtime_c__String,
+    rhs: *const rosidl_runtime_c__String,
+) -> bool {
+    if lhs.is_null() || rhs.is_null() {
+        return false;
+    }
+    string_bytes(&*lhs) == string_bytes(&*rhs)
+}
+
+unsafe fn string_bytes(s: &rosidl_runtime_c__String) -> &[u8] {
+    if s.data.is_null() || s.size == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(s.data as *const u8, s.size)
+    }
+}
+
+// =========================================================================
+// String sequence operations
+// =========================================================================
+
+/// Initialize a rosidl_runtime_c__String__Sequence structure
+pub unsafe fn rosidl_runtime_c__String__Sequence__init(
+    seq: *mut SequenceInner<rosidl_runtime_c__String>,
+    size: usize,
+) -> bool {
+    if seq.is_null() {
+        return false;
+    }
+    let data = alloc_zeroed::<rosidl_runtime_c__String>(size);
+    if data.is_null() {
+        return false;
+    }
+    for i in 0..size {
+        if !rosidl_runtime_c__String__init(data.add(i)) {
+            for j in 0..i {
+                rosidl_runtime_c__String__fini(data.add(j));
+            }
+            dealloc(data, size);
+            return false;
+        }
+    }
+    *seq = SequenceInner {
+        data,
+        size,
+        capacity: size,
+    };
+    true
+}
+
+/// Deallocate the memory of the string sequence structure
+pub unsafe fn rosidl_runtime_c__String__Sequence__fini(
+    seq: *mut SequenceInner<rosidl_runtime_c__String>,
+) {
+    if seq.is_null() || (*seq).data.is_null() {
+        return;
+    }
+    for i in 0..(*seq).capacity {
+        rosidl_runtime_c__String__fini((*seq).data.add(i));
+    }
+    dealloc((*seq).data, (*seq).capacity);
+    (*seq).data = std::ptr::null_mut();
+    (*seq).size = 0;
+    (*seq).capacity = 0;
+}
+
+/// Copy rosidl_runtime_c__String__Sequence structure content
+pub unsafe fn rosidl_runtime_c__String__Sequence__copy(
+    input: *const SequenceInner<rosidl_runtime_c__String>,
+    output: *mut SequenceInner<rosidl_runtime_c__String>,
+) -> bool {
+    if input.is_null() || output.is_null() {
+        return false;
+    }
+    if std::ptr::eq(input, output) {
+        return true;
+    }
+    rosidl_runtime_c__String__Sequence__fini(output);
+    if !rosidl_runtime_c__String__Sequence__init(output, (*input).size) {
+        return false;
+    }
+    for i in 0..(*input).size {
+        if !rosidl_runtime_c__String__copy((*input).data.add(i), (*output).data.add(i)) {
+            return false;
+        }
+    }
+    true
+}
+
+// =========================================================================
+// Wide string operations
+// =========================================================================
+
+/// Initialize a rosidl_runtime_c__U16String structure
+pub unsafe fn rosidl_runtime_c__U16String__init(s: *mut rosidl_runtime_c__U16String) -> bool {
+    if s.is_null() {
+        return false;
+    }
+    let data = alloc_zeroed::<u16>(1);
+    if data.is_null() {
+        return false;
+    }
+    *s = rosidl_runtime_c__U16String {
+        data,
+        size: 0,
+        capacity: 1,
+    };
+    true
+}
+
+/// Deallocate the memory of the rosidl_runtime_c__U16String structure
+pub unsafe fn rosidl_runtime_c__U16String__fini(s: *mut rosidl_runtime_c__U16String) {
+    if s.is_null() {
+        return;
+    }
+    dealloc((*s).data, (*s).capacity);
+    (*s).data = std::ptr::null_mut();
+    (*s).size = 0;
+    (*s).capacity = 0;
+}
+
+/// Assign n UTF-16 code units to the rosidl_runtime_c__U16String structure
+pub unsafe fn rosidl_runtime_c__U16String__assignn(
+    s: *mut rosidl_runtime_c__U16String,
+    value: *const u16,
+    n: usize,
+) -> bool {
+    if s.is_null() || value.is_null() || n == usize::MAX {
+        return false;
+    }
+    let data = alloc_zeroed::<u16>(n + 1);
+    if data.is_null() {
+        return false;
+    }
+    std::ptr::copy_nonoverlapping(value, data, n);
+    dealloc((*s).data, (*s).capacity);
+    *s = rosidl_runtime_c__U16String {
+        data,
+        size: n,
+        capacity: n + 1,
+    };
+    true
+}
+
+/// Copy rosidl_runtime_c__U16String structure content
+pub unsafe fn rosidl_runtime_c__U16String__copy(
+    input: *const rosidl_runtime_c__U16String,
+    output: *mut rosidl_runtime_c__U16String,
+) -> bool {
+    if input.is_null() || output.is_null() {
+        return false;
+    }
+    if std::ptr::eq(input, output) {
+        return true;
+    }
+    rosidl_runtime_c__U16String__assignn(output, (*input).data, (*input).size)
+}
+
+/// Check for rosidl_runtime_c__U16String structure equality
+pub unsafe fn rosidl_runtime_c__U16String__are_equal(
+    lhs: *const rosidl_runtime_c__U16String,
+    rhs: *const rosidl_runtime_c__U16String,
+) -> bool {
+    if lhs.is_null() || rhs.is_null() {
+        return false;
+    }
+    u16string_units(&*lhs) == u16string_units(&*rhs)
+}
+
+unsafe fn u16string_units(s: &rosidl_runtime_c__U16String) -> &[u16] {
+    if s.data.is_null() || s.size == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(s.data, s.size)
+    }
+}
+
+// =========================================================================
+// Wide string sequence operations
+// =========================================================================
+
+/// Initialize a rosidl_runtime_c__U16String__Sequence structure
+pub unsafe fn rosidl_runtime_c__U16String__Sequence__init(
+    seq: *mut SequenceInner<rosidl_runtime_c__U16String>,
+    size: usize,
+) -> bool {
+    if seq.is_null() {
+        return false;
+    }
+    let data = alloc_zeroed::<rosidl_runtime_c__U16String>(size);
+    if data.is_null() {
+        return false;
+    }
+    for i in 0..size {
+        if !rosidl_runtime_c__U16String__init(data.add(i)) {
+            for j in 0..i {
+                rosidl_runtime_c__U16String__fini(data.add(j));
+            }
+            dealloc(data, size);
+            return false;
+        }
+    }
+    *seq = SequenceInner {
+        data,
+        size,
+        capacity: size,
+    };
+    true
+}
+
+/// Deallocate the memory of the wide string sequence structure
+pub unsafe fn rosidl_runtime_c__U16String__Sequence__fini(
+    seq: *mut SequenceInner<rosidl_runtime_c__U16String>,
+) {
+    if seq.is_null() || (*seq).data.is_null() {
+        return;
+    }
+    for i in 0..(*seq).capacity {
+        rosidl_runtime_c__U16String__fini((*seq).data.add(i));
+    }
+    dealloc((*seq).data, (*seq).capacity);
+    (*seq).data = std::ptr::null_mut();
+    (*seq).size = 0;
+    (*seq).capacity = 0;
+}
+
+/// Copy rosidl_runtime_c__U16String__Sequence structure content
+pub unsafe fn rosidl_runtime_c__U16String__Sequence__copy(
+    input: *const SequenceInner<rosidl_runtime_c__U16String>,
+    output: *mut SequenceInner<rosidl_runtime_c__U16String>,
+) -> bool {
+    if input.is_null() || output.is_null() {
+        return false;
+    }
+    if std::ptr::eq(input, output) {
+        return true;
+    }
+    rosidl_runtime_c__U16String__Sequence__fini(output);
+    if !rosidl_runtime_c__U16String__Sequence__init(output, (*input).size) {
+        return false;
+    }
+    for i in 0..(*input).size {
+        if !rosidl_runtime_c__U16String__copy((*input).data.add(i), (*output).data.add(i)) {
+            return false;
+        }
+    }
+    true
+}
+
+// =========================================================================
+// Primitive sequence operations
+// =========================================================================
+
+macro_rules! mock_primitive_sequence {
+    ($t:ty, $init:ident, $fini:ident, $copy:ident) => {
+        pub unsafe fn $init(seq: *mut SequenceInner<$t>, size: usize) -> bool {
+            if seq.is_null() {
+                return false;
+            }
+            let data = alloc_zeroed::<$t>(size);
+            if data.is_null() {
+                return false;
+            }
+            *seq = SequenceInner {
+                data,
+                size,
+                capacity: size,
+            };
+            true
+        }
+
+        pub unsafe fn $fini(seq: *mut SequenceInner<$t>) {
+            if seq.is_null() {
+                return;
+            }
+            dealloc((*seq).data, (*seq).capacity);
+            (*seq).data = std::ptr::null_mut();
+            (*seq).size = 0;
+            (*seq).capacity = 0;
+        }
+
+        pub unsafe fn $copy(
+            input: *const SequenceInner<$t>,
+            output: *mut SequenceInner<$t>,
+        ) -> bool {
+            if input.is_null() || output.is_null() {
+                return false;
+            }
+            if std::ptr::eq(input, output) {
+                return true;
+            }
+            $fini(output);
+            if !$init(output, (*input).size) {
+                return false;
+            }
+            if (*input).size > 0 {
+                std::ptr::copy_nonoverlapping((*input).data, (*output).data, (*input).size);
+            }
+            true
+        }
+    };
+}
+
+mock_primitive_sequence!(
+    f32,
+    rosidl_runtime_c__float__Sequence__init,
+    rosidl_runtime_c__float__Sequence__fini,
+    rosidl_runtime_c__float__Sequence__copy
+);
+mock_primitive_sequence!(
+    f64,
+    rosidl_runtime_c__double__Sequence__init,
+    rosidl_runtime_c__double__Sequence__fini,
+    rosidl_runtime_c__double__Sequence__copy
+);
+mock_primitive_sequence!(
+    i8,
+    rosidl_runtime_c__int8__Sequence__init,
+    rosidl_runtime_c__int8__Sequence__fini,
+    rosidl_runtime_c__int8__Sequence__copy
+);
+mock_primitive_sequence!(
+    u8,
+    rosidl_runtime_c__uint8__Sequence__init,
+    rosidl_runtime_c__uint8__Sequence__fini,
+    rosidl_runtime_c__uint8__Sequence__copy
+);
+mock_primitive_sequence!(
+    i16,
+    rosidl_runtime_c__int16__Sequence__init,
+    rosidl_runtime_c__int16__Sequence__fini,
+    rosidl_runtime_c__int16__Sequence__copy
+);
+mock_primitive_sequence!(
+    u16,
+    rosidl_runtime_c__uint16__Sequence__init,
+    rosidl_runtime_c__uint16__Sequence__fini,
+    rosidl_runtime_c__uint16__Sequence__copy
+);
+mock_primitive_sequence!(
+    i32,
+    rosidl_runtime_c__int32__Sequence__init,
+    rosidl_runtime_c__int32__Sequence__fini,
+    rosidl_runtime_c__int32__Sequence__copy
+);
+mock_primitive_sequence!(
+    u32,
+    rosidl_runtime_c__uint32__Sequence__init,
+    rosidl_runtime_c__uint32__Sequence__fini,
+    rosidl_runtime_c__uint32__Sequence__copy
+);
+mock_primitive_sequence!(
+    i64,
+    rosidl_runtime_c__int64__Sequence__init,
+    rosidl_runtime_c__int64__Sequence__fini,
+    rosidl_runtime_c__int64__Sequence__copy
+);
+mock_primitive_sequence!(
+    u64,
+    rosidl_runtime_c__uint64__Sequence__init,
+    rosidl_runtime_c__uint64__Sequence__fini,
+    rosidl_runtime_c__uint64__Sequence__copy
+);
+mock_primitive_sequence!(
+    bool,
+    rosidl_runtime_c__boolean__Sequence__init,
+    rosidl_runtime_c__boolean__Sequence__fini,
+    rosidl_runtime_c__boolean__Sequence__copy
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::string::StringError;
+    use crate::{BoundedString, BoundedWString, Sequence, String, WString};
+
+    #[test]
+    fn test_mock_string() {
+        let mut s = String::new();
+        assert!(s.is_empty());
+        s.assign("hello").unwrap();
+        s.assign("hello world").unwrap();
+        assert_eq!(s.as_str(), "hello world");
+        let copy = s.clone();
+        assert_eq!(copy.as_str(), "hello world");
+        assert!(copy == s);
+
+        // The bound counts bytes
+        let bounded = BoundedString::<6>::try_from("héllo").unwrap();
+        assert_eq!(bounded.as_str(), "héllo");
+        assert_eq!(bounded.to_string(), "héllo");
+        assert_eq!(
+            BoundedString::<5>::try_from("héllo"),
+            Err(StringError::ExceedsBound { len: 6, bound: 5 })
+        );
+
+        let names = vec!["odom".to_string(), "map".to_string()];
+        let mut seq: Sequence<BoundedString<4>> = names
+            .iter()
+            .map(|n| n.as_str().try_into().unwrap())
+            .collect();
+        let mut copy = seq.clone_messages().unwrap();
+        seq.fini_messages();
+        assert_eq!(copy.to_vec_converted::<std::string::String>(), names);
+        copy.fini_messages();
+    }
+
+    #[test]
+    fn test_mock_wstring() {
+        let s = WString::from("Grüße 🚀");
+        // The rocket is a surrogate pair
+        assert_eq!(s.len(), 8);
+        assert_eq!(s.to_string(), "Grüße 🚀");
+        assert!(s.clone() == s);
+
+        // Unpaired surrogates are replaced when converting to a Rust string
+        let mut lone = WString::new();
+        let units = [0x61, 0xD800];
+        unsafe {
+            super::rosidl_runtime_c__U16String__assignn(lone.as_mut_ffi(), units.as_ptr(), 2);
+        }
+        assert_eq!(std::string::String::from(&lone), "a\u{FFFD}");
+
+        let bounded = BoundedWString::<2>::try_from("ab").unwrap();
+        assert_eq!(bounded.as_slice(), &[0x61, 0x62]);
+        assert_eq!(
+            BoundedWString::<2>::try_from("🚀!"),
+            Err(StringError::ExceedsBound { len: 3, bound: 2 })
+        );
+
+        let words = vec!["ein".to_string(), "zwei".to_string()];
+        let mut seq = Sequence::<WString>::from_slice_converted(&words);
+        let mut copy = seq.clone_messages().unwrap();
+        seq.fini_messages();
+        assert_eq!(copy.to_vec_converted::<std::string::String>(), words);
+        copy.fini_messages();
+
+        let mut seq: Sequence<String> = words.iter().map(|w| w.as_str().into()).collect();
+        assert_eq!(seq.iter().map(|s| s.as_str()).collect::<Vec<_>>(), words);
+        seq.fini_messages();
+    }
+
+    #[test]
+    fn test_mock_sequence() {
+        let mut seq = Sequence::<f64>::new(3).unwrap();
+        assert_eq!(seq.as_slice(), &[0.0, 0.0, 0.0]);
+        seq.as_mut_slice()[1] = 2.5;
+        assert_eq!(seq.as_slice(), &[0.0, 2.5, 0.0]);
+        seq.fini();
+        assert!(seq.is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+//! Random message instances for fuzzing (`arbitrary` feature)
+//!
+//! Generated messages implement [`Arbitrary`] behind their own `arbitrary` feature,
+//! building each field with the helpers below. Values stay within what ROS can
+//! represent: strings contain no NUL characters (C strings end there) and bounded
+//! strings and sequences respect their bound, so every instance is a valid message.
+
+pub use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+/// Longest unbounded string (in characters) or sequence generated, keeping instances small
+pub const MAX_UNBOUNDED_LEN: usize = 32;
+
+/// Arbitrary string without NUL characters, of at most `max_len` bytes
+pub fn string(u: &mut Unstructured<'_>, max_len: Option<usize>) -> Result<std::string::String> {
+    // Built per character: random bytes are rarely valid UTF-8
+    let len = u.int_in_range(0..=max_len.unwrap_or(MAX_UNBOUNDED_LEN))?;
+    let mut s = (0..len)
+        .map(|_| char::arbitrary(u))
+        .collect::<Result<std::string::String>>()?;
+    s.retain(|c| c != '\0');
+    if let Some(max_len) = max_len {
+        while s.len() > max_len {
+            s.pop();
+        }
+    }
+    Ok(s)
+}
+
+/// Arbitrary sequence of at most `max_len` (or [`MAX_UNBOUNDED_LEN`]) elements built by `element`
+pub fn vec<'a, T>(
+    u: &mut Unstructured<'a>,
+    max_len: Option<usize>,
+    mut element: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Vec<T>> {
+    let len = u.int_in_range(0..=max_len.unwrap_or(MAX_UNBOUNDED_LEN))?;
+    (0..len).map(|_| element(u)).collect()
+}
+
+/// Arbitrary array with elements built by `element`
+pub fn array<'a, T, const N: usize>(
+    u: &mut Unstructured<'a>,
+    mut element: impl FnMut(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<[T; N]> {
+    let items = (0..N).map(|_| element(u)).collect::<Result<Vec<T>>>()?;
+    Ok(items
+        .try_into()
+        .unwrap_or_else(|_| unreachable!("exactly {} elements", N)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_values() {
+        let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..20 {
+            let s = string(&mut u, Some(3)).unwrap();
+            assert!(s.len() <= 3 && !s.contains('\0'));
+            let v = vec(&mut u, Some(2), |u| u.arbitrary::<u16>()).unwrap();
+            assert!(v.len() <= 2);
+        }
+        let strings: [std::string::String; 4] = array(&mut u, |u| string(u, None)).unwrap();
+        assert!(strings.iter().all(|s| !s.contains('\0')));
+    }
+}
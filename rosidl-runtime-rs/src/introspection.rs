@@ -0,0 +1,525 @@
+//! Message reflection through `rosidl_typesupport_introspection_c`
+//!
+//! The introspection type support of a message describes its C struct at
+//! runtime: the name, type, offset and bounds of every field, with nested
+//! messages described the same way. Generic tools such as message dumpers or
+//! subscribers to topics of any type read it instead of generated code.
+//!
+//! Bindings generated with `--introspection` implement [`IntrospectionMessage`]
+//! for each RMW message:
+//!
+//! ```ignore
+//! use rosidl_runtime_rs::introspection::IntrospectionMessage;
+//!
+//! for member in std_msgs::ffi::msg::Header::members().members() {
+//!     println!("{}: {:?} at offset {}", member.name(), member.kind(), member.offset());
+//! }
+//! ```
+//!
+//! The structs mirror the layout of ROS 2 Jazzy and later. Humble and Iron lack
+//! `is_key_` and `has_any_key_member_`, so the bindings must not be read there.
+
+use std::ffi::{c_void, CStr};
+use std::fmt;
+use std::os::raw::c_char;
+
+/// Leading fields of `rosidl_message_type_support_t`, shared by all distros
+#[repr(C)]
+#[derive(Debug)]
+pub struct rosidl_message_type_support_t {
+    pub typesupport_identifier: *const c_char,
+    pub data: *const c_void,
+    pub func: *const c_void,
+}
+
+/// C-compatible field description (mirrors rosidl_typesupport_introspection_c__MessageMember)
+#[repr(C)]
+#[derive(Debug)]
+pub struct rosidl_typesupport_introspection_c__MessageMember {
+    pub name_: *const c_char,
+    pub type_id_: u8,
+    pub string_upper_bound_: usize,
+    pub members_: *const rosidl_message_type_support_t,
+    pub is_key_: bool,
+    pub is_array_: bool,
+    pub array_size_: usize,
+    pub is_upper_bound_: bool,
+    pub offset_: u32,
+    pub default_value_: *const c_void,
+    pub size_function: Option<unsafe extern "C" fn(*const c_void) -> usize>,
+    pub get_const_function: Option<unsafe extern "C" fn(*const c_void, usize) -> *const c_void>,
+    pub get_function: Option<unsafe extern "C" fn(*mut c_void, usize) -> *mut c_void>,
+    pub fetch_function: Option<unsafe extern "C" fn(*const c_void, usize, *mut c_void)>,
+    pub assign_function: Option<unsafe extern "C" fn(*mut c_void, usize, *const c_void)>,
+    pub resize_function: Option<unsafe extern "C" fn(*mut c_void, usize) -> bool>,
+}
+
+/// C-compatible message description (mirrors rosidl_typesupport_introspection_c__MessageMembers)
+#[repr(C)]
+#[derive(Debug)]
+pub struct rosidl_typesupport_introspection_c__MessageMembers {
+    pub message_namespace_: *const c_char,
+    pub message_name_: *const c_char,
+    pub member_count_: u32,
+    pub size_of_: usize,
+    pub has_any_key_member_: bool,
+    pub members_: *const rosidl_typesupport_introspection_c__MessageMember,
+    pub init_function: Option<unsafe extern "C" fn(*mut c_void, i32)>,
+    pub fini_function: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// RMW message with an introspection type support, implemented by bindings
+/// generated with `--introspection`
+pub trait IntrospectionMessage {
+    /// Get the `rosidl_typesupport_introspection_c` type support handle
+    fn get_introspection_type_support() -> *const c_void;
+
+    /// Description of the fields of the message
+    fn members() -> Members {
+        // SAFETY: The handle comes from rosidl_typesupport_introspection_c
+        unsafe { Members::from_type_support(Self::get_introspection_type_support()) }
+    }
+}
+
+/// Type of a field, from the `rosidl_typesupport_introspection_c__ROS_TYPE_*` ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Float32,
+    Float64,
+    LongDouble,
+    Char,
+    WChar,
+    Bool,
+    Byte,
+    Uint8,
+    Int8,
+    Uint16,
+    Int16,
+    Uint32,
+    Int32,
+    Uint64,
+    Int64,
+    String,
+    WString,
+    Message,
+    /// Id this crate does not know, from a newer distro
+    Unknown(u8),
+}
+
+impl From<u8> for FieldKind {
+    fn from(type_id: u8) -> Self {
+        match type_id {
+            1 => Self::Float32,
+            2 => Self::Float64,
+            3 => Self::LongDouble,
+            4 => Self::Char,
+            5 => Self::WChar,
+            6 => Self::Bool,
+            7 => Self::Byte,
+            8 => Self::Uint8,
+            9 => Self::Int8,
+            10 => Self::Uint16,
+            11 => Self::Int16,
+            12 => Self::Uint32,
+            13 => Self::Int32,
+            14 => Self::Uint64,
+            15 => Self::Int64,
+            16 => Self::String,
+            17 => Self::WString,
+            18 => Self::Message,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// Fields of a message type
+#[derive(Debug, Clone, Copy)]
+pub struct Members {
+    raw: &'static rosidl_typesupport_introspection_c__MessageMembers,
+}
+
+impl Members {
+    /// Read the description behind an introspection type support handle
+    ///
+    /// # Safety
+    /// `type_support` is a message type support handle of
+    /// `rosidl_typesupport_introspection_c`, whose data lives for the rest of
+    /// the program.
+    pub unsafe fn from_type_support(type_support: *const c_void) -> Self {
+        let type_support = &*(type_support as *const rosidl_message_type_support_t);
+        Self::from_raw(
+            &*(type_support.data as *const rosidl_typesupport_introspection_c__MessageMembers),
+        )
+    }
+
+    /// Wrap a description, such as one built by hand in tests
+    pub fn from_raw(raw: &'static rosidl_typesupport_introspection_c__MessageMembers) -> Self {
+        Self { raw }
+    }
+
+    /// Namespace of the C type, such as `std_msgs__msg`
+    pub fn namespace(&self) -> &'static str {
+        // SAFETY: Introspection data holds NUL-terminated static strings
+        unsafe { c_str(self.raw.message_namespace_) }
+    }
+
+    /// Name of the message, such as `Header`
+    pub fn name(&self) -> &'static str {
+        // SAFETY: Introspection data holds NUL-terminated static strings
+        unsafe { c_str(self.raw.message_name_) }
+    }
+
+    /// Size of the C struct in bytes
+    pub fn size_of(&self) -> usize {
+        self.raw.size_of_
+    }
+
+    /// Fields in definition order
+    pub fn members(&self) -> impl Iterator<Item = Member> {
+        let members: &'static [rosidl_typesupport_introspection_c__MessageMember] =
+            if self.raw.member_count_ == 0 {
+                &[]
+            } else {
+                // SAFETY: `members_` points to `member_count_` static descriptions
+                unsafe {
+                    std::slice::from_raw_parts(self.raw.members_, self.raw.member_count_ as usize)
+                }
+            };
+        members.iter().map(|raw| Member { raw })
+    }
+}
+
+/// Field of a message type
+#[derive(Debug, Clone, Copy)]
+pub struct Member {
+    raw: &'static rosidl_typesupport_introspection_c__MessageMember,
+}
+
+impl Member {
+    /// Name of the field
+    pub fn name(&self) -> &'static str {
+        // SAFETY: Introspection data holds NUL-terminated static strings
+        unsafe { c_str(self.raw.name_) }
+    }
+
+    /// Type of the field, or of its elements for arrays and sequences
+    pub fn kind(&self) -> FieldKind {
+        FieldKind::from(self.raw.type_id_)
+    }
+
+    /// Offset of the field in the C struct
+    pub fn offset(&self) -> usize {
+        self.raw.offset_ as usize
+    }
+
+    /// Bound of a bounded string type, 0 if unbounded
+    pub fn string_upper_bound(&self) -> usize {
+        self.raw.string_upper_bound_
+    }
+
+    /// Whether the field is an array or a sequence
+    pub fn is_array(&self) -> bool {
+        self.raw.is_array_
+    }
+
+    /// Size of an array, bound of a bounded sequence, 0 for unbounded sequences
+    pub fn array_size(&self) -> usize {
+        self.raw.array_size_
+    }
+
+    /// Whether the field is a bounded sequence
+    pub fn is_upper_bound(&self) -> bool {
+        self.raw.is_upper_bound_
+    }
+
+    /// Fields of the nested message of [`FieldKind::Message`] fields
+    pub fn nested(&self) -> Option<Members> {
+        if self.kind() != FieldKind::Message || self.raw.members_.is_null() {
+            return None;
+        }
+        // SAFETY: Nested handles come from the same introspection type support
+        Some(unsafe { Members::from_type_support(self.raw.members_ as *const c_void) })
+    }
+}
+
+/// Field of a generated RMW struct, as its introspection should describe it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedMember {
+    /// Field name of the interface definition
+    pub name: &'static str,
+    pub kind: FieldKind,
+    /// Offset of the field in the Rust struct
+    pub offset: usize,
+    pub is_array: bool,
+    pub array_size: usize,
+    pub is_upper_bound: bool,
+    pub string_upper_bound: usize,
+}
+
+/// Differences between a generated RMW struct and the introspection of its C
+/// struct, such as from bindings generated for another distro
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    /// C type, such as `std_msgs__msg__Header`
+    pub type_name: String,
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} does not match its introspection: {}",
+            self.type_name,
+            self.differences.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for LayoutMismatch {}
+
+/// Check a generated RMW struct of `size` bytes with the `expected` fields against
+/// the introspection `members` of its C struct
+///
+/// Bindings generated with `--introspection` run this in a test of each message.
+pub fn check_layout(
+    members: Members,
+    size: usize,
+    expected: &[ExpectedMember],
+) -> Result<(), LayoutMismatch> {
+    let mut differences = Vec::new();
+    if members.size_of() != size {
+        differences.push(format!(
+            "the struct is {} bytes in C and {} in Rust",
+            members.size_of(),
+            size
+        ));
+    }
+    let mut actual = members.members();
+    for expected in expected {
+        let Some(member) = actual.next() else {
+            differences.push(format!("field `{}` is missing in C", expected.name));
+            continue;
+        };
+        if member.name() != expected.name {
+            differences.push(format!(
+                "field `{}` is `{}` in C",
+                expected.name,
+                member.name()
+            ));
+            continue;
+        }
+        let mut differ = |what: &str, c: &dyn fmt::Debug, rust: &dyn fmt::Debug| {
+            differences.push(format!(
+                "field `{}` has {} {:?} in C and {:?} in Rust",
+                expected.name, what, c, rust
+            ))
+        };
+        if member.kind() != expected.kind {
+            differ("type", &member.kind(), &expected.kind);
+        }
+        if member.offset() != expected.offset {
+            differ("offset", &member.offset(), &expected.offset);
+        }
+        if (
+            member.is_array(),
+            member.array_size(),
+            member.is_upper_bound(),
+        ) != (
+            expected.is_array,
+            expected.array_size,
+            expected.is_upper_bound,
+        ) {
+            differ(
+                "array size",
+                &(
+                    member.is_array(),
+                    member.array_size(),
+                    member.is_upper_bound(),
+                ),
+                &(
+                    expected.is_array,
+                    expected.array_size,
+                    expected.is_upper_bound,
+                ),
+            );
+        }
+        if member.string_upper_bound() != expected.string_upper_bound {
+            differ(
+                "string bound",
+                &member.string_upper_bound(),
+                &expected.string_upper_bound,
+            );
+        }
+    }
+    for member in actual {
+        differences.push(format!("field `{}` is missing in Rust", member.name()));
+    }
+
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(LayoutMismatch {
+            type_name: format!("{}__{}", members.namespace(), members.name()),
+            differences,
+        })
+    }
+}
+
+/// # Safety
+/// `ptr` is null or a NUL-terminated string living for the rest of the program
+unsafe fn c_str(ptr: *const c_char) -> &'static str {
+    if ptr.is_null() {
+        return "";
+    }
+    CStr::from_ptr(ptr).to_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    struct Static<T>(T);
+    // SAFETY: The descriptions are immutable
+    unsafe impl<T> Sync for Static<T> {}
+
+    const fn member(
+        name: &'static CStr,
+        type_id: u8,
+        offset: u32,
+        members: *const rosidl_message_type_support_t,
+    ) -> rosidl_typesupport_introspection_c__MessageMember {
+        rosidl_typesupport_introspection_c__MessageMember {
+            name_: name.as_ptr(),
+            type_id_: type_id,
+            string_upper_bound_: 0,
+            members_: members,
+            is_key_: false,
+            is_array_: false,
+            array_size_: 0,
+            is_upper_bound_: false,
+            offset_: offset,
+            default_value_: ptr::null(),
+            size_function: None,
+            get_const_function: None,
+            get_function: None,
+            fetch_function: None,
+            assign_function: None,
+            resize_function: None,
+        }
+    }
+
+    static TIME_MEMBERS: Static<[rosidl_typesupport_introspection_c__MessageMember; 2]> = Static([
+        member(c"sec", 13, 0, ptr::null()),
+        member(c"nanosec", 12, 4, ptr::null()),
+    ]);
+    static TIME: Static<rosidl_typesupport_introspection_c__MessageMembers> =
+        Static(rosidl_typesupport_introspection_c__MessageMembers {
+            message_namespace_: c"builtin_interfaces__msg".as_ptr(),
+            message_name_: c"Time".as_ptr(),
+            member_count_: 2,
+            size_of_: 8,
+            has_any_key_member_: false,
+            members_: TIME_MEMBERS.0.as_ptr(),
+            init_function: None,
+            fini_function: None,
+        });
+    static TIME_TYPE_SUPPORT: Static<rosidl_message_type_support_t> =
+        Static(rosidl_message_type_support_t {
+            typesupport_identifier: c"rosidl_typesupport_introspection_c".as_ptr(),
+            data: &TIME.0 as *const _ as *const c_void,
+            func: ptr::null(),
+        });
+    static HEADER_MEMBERS: Static<[rosidl_typesupport_introspection_c__MessageMember; 2]> =
+        Static([
+            member(c"stamp", 18, 0, &TIME_TYPE_SUPPORT.0),
+            member(c"frame_id", 16, 8, ptr::null()),
+        ]);
+    static HEADER: Static<rosidl_typesupport_introspection_c__MessageMembers> =
+        Static(rosidl_typesupport_introspection_c__MessageMembers {
+            message_namespace_: c"std_msgs__msg".as_ptr(),
+            message_name_: c"Header".as_ptr(),
+            member_count_: 2,
+            size_of_: 32,
+            has_any_key_member_: false,
+            members_: HEADER_MEMBERS.0.as_ptr(),
+            init_function: None,
+            fini_function: None,
+        });
+
+    #[test]
+    fn test_members() {
+        let header = Members::from_raw(&HEADER.0);
+        assert_eq!(
+            (header.namespace(), header.name()),
+            ("std_msgs__msg", "Header")
+        );
+        let fields: Vec<_> = header
+            .members()
+            .map(|m| (m.name(), m.kind(), m.offset()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("stamp", FieldKind::Message, 0),
+                ("frame_id", FieldKind::String, 8)
+            ]
+        );
+
+        let stamp = header.members().next().unwrap().nested().unwrap();
+        assert_eq!(stamp.name(), "Time");
+        let kinds: Vec<_> = stamp.members().map(|m| m.kind()).collect();
+        assert_eq!(kinds, [FieldKind::Int32, FieldKind::Uint32]);
+        assert!(header.members().nth(1).unwrap().nested().is_none());
+    }
+
+    #[test]
+    fn test_check_layout() {
+        let field = |name, kind, offset| ExpectedMember {
+            name,
+            kind,
+            offset,
+            is_array: false,
+            array_size: 0,
+            is_upper_bound: false,
+            string_upper_bound: 0,
+        };
+        let header = Members::from_raw(&HEADER.0);
+        let stamp = field("stamp", FieldKind::Message, 0);
+        let frame_id = field("frame_id", FieldKind::String, 8);
+        assert_eq!(check_layout(header, 32, &[stamp, frame_id]), Ok(()));
+
+        let err = check_layout(
+            header,
+            24,
+            &[
+                stamp,
+                ExpectedMember {
+                    string_upper_bound: 8,
+                    ..field("frame_id", FieldKind::WString, 4)
+                },
+                field("seq", FieldKind::Uint32, 28),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "std_msgs__msg__Header does not match its introspection: \
+             the struct is 32 bytes in C and 24 in Rust; \
+             field `frame_id` has type String in C and WString in Rust; \
+             field `frame_id` has offset 8 in C and 4 in Rust; \
+             field `frame_id` has string bound 0 in C and 8 in Rust; \
+             field `seq` is missing in C"
+        );
+
+        let err = check_layout(header, 32, &[field("stamp_", FieldKind::Message, 0)]).unwrap_err();
+        assert_eq!(
+            err.differences,
+            [
+                "field `stamp_` is `stamp` in C",
+                "field `frame_id` is missing in Rust"
+            ]
+        );
+    }
+}
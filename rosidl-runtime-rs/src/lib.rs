@@ -3,8 +3,17 @@
 //! This crate provides the core runtime infrastructure for ROS 2 Rust bindings,
 //! including:
 //! - Type traits for message/service/action definitions
-//! - Idiomatic Rust wrappers around ROS C types (String, Sequence)
+//! - Idiomatic Rust wrappers around ROS C types (String, WString, Sequence)
 //! - FFI bindings to rosidl_runtime_c
+//! - Pure-Rust CDR serialization (`cdr` module)
+//! - YAML-like `Display` of messages, as `ros2 topic echo` prints them (`display` module)
+//! - Dynamic messages built from `.msg` text at runtime (`dynamic` module, `dynamic` feature)
+//! - Message reflection through `rosidl_typesupport_introspection_c` (`introspection` module)
+//! - A `ParameterValue` enum for `rcl_interfaces` parameters (`parameter` module)
+//! - Pure-Rust stand-ins for the C functions (`mock-ffi` feature), e.g. for wasm32
+//! - Helpers for `Arbitrary` message instances (`fuzz` module, `arbitrary` feature)
+//! - Type descriptions of interfaces, for their REP-2011 type hashes (`type_description` module)
+//! - Fixed-capacity `BoundedString`/`BoundedVec` for embedded bindings (`bounded` module)
 //!
 //! # Architecture
 //!
@@ -13,13 +22,39 @@
 //! - **FFI layer**: Raw C bindings for per-package code generation
 //!
 //! Most users will use the idiomatic API. Generated package code may use both.
+//!
+//! Without the default `std` feature the crate is `no_std` and only provides the
+//! `bounded` and `type_description` modules.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod bounded;
+#[cfg(feature = "std")]
+pub mod cdr;
+#[cfg(feature = "std")]
+pub mod display;
+#[cfg(feature = "dynamic")]
+pub mod dynamic;
+#[cfg(feature = "std")]
 pub mod ffi;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod introspection;
+#[cfg(feature = "std")]
+pub mod parameter;
+#[cfg(feature = "std")]
 pub mod sequence;
+#[cfg(feature = "std")]
 pub mod string;
+#[cfg(feature = "std")]
 pub mod traits;
+pub mod type_description;
 
 // Re-export commonly used items
-pub use sequence::Sequence;
-pub use string::String;
+#[cfg(feature = "std")]
+pub use sequence::{BoundedSequence, Sequence};
+#[cfg(feature = "std")]
+pub use string::{BoundedString, BoundedWString, String, WString};
+#[cfg(feature = "std")]
 pub use traits::{Action, Message, RmwMessage, SequenceAlloc, SequenceElement, Service};
@@ -0,0 +1,220 @@
+//! Ergonomic ROS 2 parameter values
+//!
+//! `rcl_interfaces/msg/ParameterValue` encodes a tagged union as a `type` byte
+//! plus one field per possible type. [`ParameterValue`] is the same union as a
+//! Rust enum. The generated `rcl_interfaces` crate converts between the two
+//! (`rcl_interfaces::parameter`), and Rust primitives convert into the enum
+//! with `From` and back out with `TryFrom`.
+//!
+//! # Example
+//! ```
+//! use rosidl_runtime_rs::parameter::ParameterValue;
+//!
+//! let value = ParameterValue::from(2.5);
+//! assert_eq!(value.type_name(), "double");
+//! let rate: f64 = value.try_into().unwrap();
+//! assert_eq!(rate, 2.5);
+//! ```
+
+use std::fmt;
+
+// Type ids, matching the constants of rcl_interfaces/msg/ParameterType
+pub const PARAMETER_NOT_SET: u8 = 0;
+pub const PARAMETER_BOOL: u8 = 1;
+pub const PARAMETER_INTEGER: u8 = 2;
+pub const PARAMETER_DOUBLE: u8 = 3;
+pub const PARAMETER_STRING: u8 = 4;
+pub const PARAMETER_BYTE_ARRAY: u8 = 5;
+pub const PARAMETER_BOOL_ARRAY: u8 = 6;
+pub const PARAMETER_INTEGER_ARRAY: u8 = 7;
+pub const PARAMETER_DOUBLE_ARRAY: u8 = 8;
+pub const PARAMETER_STRING_ARRAY: u8 = 9;
+
+/// Value of a ROS 2 parameter
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParameterValue {
+    #[default]
+    NotSet,
+    Bool(bool),
+    Integer(i64),
+    Double(f64),
+    String(String),
+    ByteArray(Vec<u8>),
+    BoolArray(Vec<bool>),
+    IntegerArray(Vec<i64>),
+    DoubleArray(Vec<f64>),
+    StringArray(Vec<String>),
+}
+
+impl ParameterValue {
+    /// Type id as used in `ParameterValue.type` (`PARAMETER_*`)
+    pub fn type_id(&self) -> u8 {
+        match self {
+            ParameterValue::NotSet => PARAMETER_NOT_SET,
+            ParameterValue::Bool(_) => PARAMETER_BOOL,
+            ParameterValue::Integer(_) => PARAMETER_INTEGER,
+            ParameterValue::Double(_) => PARAMETER_DOUBLE,
+            ParameterValue::String(_) => PARAMETER_STRING,
+            ParameterValue::ByteArray(_) => PARAMETER_BYTE_ARRAY,
+            ParameterValue::BoolArray(_) => PARAMETER_BOOL_ARRAY,
+            ParameterValue::IntegerArray(_) => PARAMETER_INTEGER_ARRAY,
+            ParameterValue::DoubleArray(_) => PARAMETER_DOUBLE_ARRAY,
+            ParameterValue::StringArray(_) => PARAMETER_STRING_ARRAY,
+        }
+    }
+
+    /// Type name as printed by `ros2 param describe`
+    pub fn type_name(&self) -> &'static str {
+        type_name(self.type_id())
+    }
+
+    /// Check whether the parameter has a value
+    pub fn is_set(&self) -> bool {
+        !matches!(self, ParameterValue::NotSet)
+    }
+}
+
+fn type_name(type_id: u8) -> &'static str {
+    match type_id {
+        PARAMETER_NOT_SET => "not set",
+        PARAMETER_BOOL => "boolean",
+        PARAMETER_INTEGER => "integer",
+        PARAMETER_DOUBLE => "double",
+        PARAMETER_STRING => "string",
+        PARAMETER_BYTE_ARRAY => "byte array",
+        PARAMETER_BOOL_ARRAY => "boolean array",
+        PARAMETER_INTEGER_ARRAY => "integer array",
+        PARAMETER_DOUBLE_ARRAY => "double array",
+        PARAMETER_STRING_ARRAY => "string array",
+        _ => "unknown",
+    }
+}
+
+/// Error converting a parameter value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParameterError {
+    /// The value holds a different type than requested
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// `ParameterValue.type` is not one of the `PARAMETER_*` ids
+    UnknownType(u8),
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::TypeMismatch { expected, found } => {
+                write!(f, "expected a {} parameter, found {}", expected, found)
+            }
+            ParameterError::UnknownType(type_id) => {
+                write!(f, "unknown parameter type {}", type_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+macro_rules! impl_parameter_value_conversions {
+    ($($t:ty => $variant:ident = $type_id:expr),* $(,)?) => {
+        $(
+            impl From<$t> for ParameterValue {
+                fn from(value: $t) -> Self {
+                    ParameterValue::$variant(value)
+                }
+            }
+
+            impl TryFrom<ParameterValue> for $t {
+                type Error = ParameterError;
+
+                fn try_from(value: ParameterValue) -> Result<Self, Self::Error> {
+                    match value {
+                        ParameterValue::$variant(value) => Ok(value),
+                        other => Err(ParameterError::TypeMismatch {
+                            expected: type_name($type_id),
+                            found: other.type_name(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_parameter_value_conversions!(
+    bool => Bool = PARAMETER_BOOL,
+    i64 => Integer = PARAMETER_INTEGER,
+    f64 => Double = PARAMETER_DOUBLE,
+    String => String = PARAMETER_STRING,
+    Vec<u8> => ByteArray = PARAMETER_BYTE_ARRAY,
+    Vec<bool> => BoolArray = PARAMETER_BOOL_ARRAY,
+    Vec<i64> => IntegerArray = PARAMETER_INTEGER_ARRAY,
+    Vec<f64> => DoubleArray = PARAMETER_DOUBLE_ARRAY,
+    Vec<String> => StringArray = PARAMETER_STRING_ARRAY,
+);
+
+impl From<&str> for ParameterValue {
+    fn from(value: &str) -> Self {
+        ParameterValue::String(value.to_string())
+    }
+}
+
+impl From<i32> for ParameterValue {
+    fn from(value: i32) -> Self {
+        ParameterValue::Integer(value.into())
+    }
+}
+
+impl From<f32> for ParameterValue {
+    fn from(value: f32) -> Self {
+        ParameterValue::Double(value.into())
+    }
+}
+
+impl From<Vec<&str>> for ParameterValue {
+    fn from(values: Vec<&str>) -> Self {
+        ParameterValue::StringArray(values.into_iter().map(str::to_string).collect())
+    }
+}
+
+impl<T: Into<ParameterValue>> From<Option<T>> for ParameterValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(ParameterValue::NotSet, Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitive_round_trip() {
+        let value = ParameterValue::from(42i64);
+        assert_eq!(value.type_id(), PARAMETER_INTEGER);
+        assert_eq!(i64::try_from(value).unwrap(), 42);
+
+        let value = ParameterValue::from(vec!["a", "b"]);
+        assert_eq!(value.type_id(), PARAMETER_STRING_ARRAY);
+        assert_eq!(Vec::<String>::try_from(value).unwrap(), ["a", "b"]);
+
+        assert_eq!(ParameterValue::from(3i32), ParameterValue::Integer(3));
+        assert_eq!(ParameterValue::from(None::<bool>), ParameterValue::NotSet);
+        assert!(!ParameterValue::default().is_set());
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let err = f64::try_from(ParameterValue::from("fast")).unwrap_err();
+        assert_eq!(
+            err,
+            ParameterError::TypeMismatch {
+                expected: "double",
+                found: "string"
+            }
+        );
+        assert_eq!(err.to_string(), "expected a double parameter, found string");
+    }
+}
@@ -3,9 +3,10 @@
 //! Provides a safe, user-friendly API around C sequence types.
 
 use crate::ffi;
-use crate::traits::SequenceElement;
+use crate::traits::SequenceAlloc;
 use std::fmt;
 use std::marker::PhantomData;
+use std::ops::Deref;
 
 /// ROS 2 sequence with automatic memory management
 ///
@@ -28,6 +29,7 @@ use std::marker::PhantomData;
 ///
 /// let back_to_vec: Vec<f64> = seq.into();
 /// ```
+#[repr(transparent)]
 pub struct Sequence<T> {
     inner: ffi::SequenceInner<T>,
     _marker: PhantomData<T>,
@@ -80,6 +82,11 @@ impl<T> Sequence<T> {
         }
     }
 
+    /// Iterate over the elements
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
     /// Get mutable slice view of the sequence
     pub fn as_mut_slice(&mut self) -> &mut [T] {
         if self.inner.data.is_null() || self.inner.size == 0 {
@@ -104,32 +111,76 @@ impl<T> Sequence<T> {
 
     /// Convert to Vec with element conversion
     ///
-    /// Used for sequences of message types that need RMW → idiomatic conversion
+    /// Used for sequences of message and string types that need RMW → idiomatic
+    /// conversion
     pub fn to_vec_converted<U>(&self) -> Vec<U>
     where
-        U: SequenceElement<RmwType = T>,
         for<'a> &'a T: Into<U>,
     {
         self.as_slice().iter().map(|elem| elem.into()).collect()
     }
+}
 
-    /// Create from slice with element conversion
-    ///
-    /// Used for sequences of message types that need idiomatic → RMW conversion
-    pub fn from_slice_converted<U>(_slice: &[U]) -> Self
-    where
-        U: SequenceElement<RmwType = T>,
-        for<'a> &'a U: Into<T>,
-    {
-        // Stub implementation - proper conversion requires type-specific init functions
-        Self {
+// Message sequence operations (uses the generated `<pkg>__msg__<Msg>__Sequence__*` functions)
+impl<T: SequenceAlloc> Sequence<T> {
+    /// Create a sequence of `size` messages, each initialized to its defaults
+    pub fn new_messages(size: usize) -> Result<Self, SequenceError> {
+        let mut seq = Self {
             inner: ffi::SequenceInner {
                 data: std::ptr::null_mut(),
                 size: 0,
                 capacity: 0,
             },
             _marker: PhantomData,
+        };
+
+        if T::sequence_init(&mut seq, size) {
+            Ok(seq)
+        } else {
+            Err(SequenceError::InitializationFailed)
+        }
+    }
+
+    /// Manually drop the messages (call this before the sequence goes out of scope)
+    pub fn fini_messages(&mut self) {
+        T::sequence_fini(self);
+    }
+
+    /// Deep-copy the sequence, including the memory owned by each message
+    pub fn clone_messages(&self) -> Result<Self, SequenceError> {
+        let mut new_seq = Self::new_messages(self.len())?;
+        if !T::sequence_copy(self, &mut new_seq) {
+            return Err(SequenceError::AllocationFailed);
+        }
+        Ok(new_seq)
+    }
+
+    /// Create from slice with element conversion
+    ///
+    /// Used for sequences of message and string types that need idiomatic → RMW
+    /// conversion. The element types need not be related by
+    /// [`SequenceElement`](crate::SequenceElement), so `std::string::String`
+    /// converts to both `String` and `WString` sequences.
+    pub fn from_slice_converted<U>(slice: &[U]) -> Self
+    where
+        for<'a> &'a U: Into<T>,
+    {
+        let mut seq = Self::new_messages(slice.len()).expect("Failed to allocate sequence");
+        for (slot, elem) in seq.as_mut_slice().iter_mut().zip(slice) {
+            *slot = elem.into();
         }
+        seq
+    }
+}
+
+impl<T: SequenceAlloc> FromIterator<T> for Sequence<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elements: Vec<T> = iter.into_iter().collect();
+        let mut seq = Self::new_messages(elements.len()).expect("Failed to allocate sequence");
+        for (slot, elem) in seq.as_mut_slice().iter_mut().zip(elements) {
+            *slot = elem;
+        }
+        seq
     }
 }
 
@@ -200,6 +251,92 @@ impl<T: fmt::Debug> fmt::Debug for Sequence<T> {
     }
 }
 
+/// ROS 2 bounded sequence (`T[<=N]`)
+///
+/// The C struct of a bounded sequence is the one of the unbounded sequence, so
+/// this wraps a [`Sequence`] with the same layout. It dereferences to the
+/// sequence and is created with `try_from`, which fails for more than `N`
+/// elements.
+#[repr(transparent)]
+pub struct BoundedSequence<T, const N: usize> {
+    inner: Sequence<T>,
+}
+
+impl<T, const N: usize> BoundedSequence<T, N> {
+    fn check_bound(len: usize) -> Result<(), SequenceError> {
+        if len > N {
+            return Err(SequenceError::ExceedsBound { len, bound: N });
+        }
+        Ok(())
+    }
+}
+
+impl<T: SequenceAlloc, const N: usize> BoundedSequence<T, N> {
+    /// Create from slice with element conversion, see
+    /// [`Sequence::from_slice_converted`]
+    pub fn try_from_slice_converted<U>(slice: &[U]) -> Result<Self, SequenceError>
+    where
+        for<'a> &'a U: Into<T>,
+    {
+        Self::check_bound(slice.len())?;
+        Ok(Self {
+            inner: Sequence::from_slice_converted(slice),
+        })
+    }
+}
+
+impl<T, const N: usize> Deref for BoundedSequence<T, N> {
+    type Target = Sequence<T>;
+
+    fn deref(&self) -> &Sequence<T> {
+        &self.inner
+    }
+}
+
+impl<T, const N: usize> TryFrom<Sequence<T>> for BoundedSequence<T, N> {
+    type Error = SequenceError;
+
+    fn try_from(inner: Sequence<T>) -> Result<Self, SequenceError> {
+        Self::check_bound(inner.len())?;
+        Ok(Self { inner })
+    }
+}
+
+impl<T: PrimitiveSequence + Clone, const N: usize> TryFrom<Vec<T>> for BoundedSequence<T, N> {
+    type Error = SequenceError;
+
+    fn try_from(vec: Vec<T>) -> Result<Self, SequenceError> {
+        Self::check_bound(vec.len())?;
+        Ok(Self { inner: vec.into() })
+    }
+}
+
+impl<T: PrimitiveSequence + Clone, const N: usize> From<BoundedSequence<T, N>> for Vec<T> {
+    fn from(seq: BoundedSequence<T, N>) -> Self {
+        seq.as_slice().to_vec()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for BoundedSequence<T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for BoundedSequence<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for BoundedSequence<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BoundedSequence<{}>({:?})", N, self.as_slice())
+    }
+}
+
 /// Errors that can occur during sequence operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SequenceError {
@@ -207,6 +344,8 @@ pub enum SequenceError {
     InitializationFailed,
     /// Memory allocation failed
     AllocationFailed,
+    /// Sequence is longer than the bound of a bounded sequence type
+    ExceedsBound { len: usize, bound: usize },
 }
 
 impl fmt::Display for SequenceError {
@@ -214,6 +353,13 @@ impl fmt::Display for SequenceError {
         match self {
             SequenceError::InitializationFailed => write!(f, "Sequence initialization failed"),
             SequenceError::AllocationFailed => write!(f, "Memory allocation failed"),
+            SequenceError::ExceedsBound { len, bound } => {
+                write!(
+                    f,
+                    "Sequence of length {} exceeds its bound of {}",
+                    len, bound
+                )
+            }
         }
     }
 }
@@ -346,3 +492,92 @@ impl_primitive_sequence!(
     rosidl_runtime_c__boolean__Sequence__fini,
     rosidl_runtime_c__boolean__Sequence__copy
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SequenceElement;
+
+    /// RMW message whose sequence functions live on the Rust heap, like the
+    /// ones generated for `<pkg>__msg__<Msg>__Sequence__*`
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct RmwPoint {
+        x: f64,
+        y: f64,
+    }
+
+    struct Point(f64, f64);
+
+    impl SequenceElement for Point {
+        type RmwType = RmwPoint;
+    }
+
+    impl From<&Point> for RmwPoint {
+        fn from(p: &Point) -> Self {
+            RmwPoint { x: p.0, y: p.1 }
+        }
+    }
+
+    impl SequenceAlloc for RmwPoint {
+        fn sequence_init(seq: &mut Sequence<Self>, size: usize) -> bool {
+            let mut data = std::mem::ManuallyDrop::new(vec![RmwPoint::default(); size]);
+            let inner = unsafe { seq.as_mut_ffi() };
+            inner.data = data.as_mut_ptr();
+            inner.size = size;
+            inner.capacity = data.capacity();
+            true
+        }
+
+        fn sequence_fini(seq: &mut Sequence<Self>) {
+            let inner = unsafe { seq.as_mut_ffi() };
+            if !inner.data.is_null() {
+                drop(unsafe { Vec::from_raw_parts(inner.data, inner.size, inner.capacity) });
+            }
+            inner.data = std::ptr::null_mut();
+            inner.size = 0;
+            inner.capacity = 0;
+        }
+
+        fn sequence_copy(in_seq: &Sequence<Self>, out_seq: &mut Sequence<Self>) -> bool {
+            Self::sequence_fini(out_seq);
+            if !Self::sequence_init(out_seq, in_seq.len()) {
+                return false;
+            }
+            out_seq.as_mut_slice().clone_from_slice(in_seq.as_slice());
+            true
+        }
+    }
+
+    #[test]
+    fn test_message_sequence() {
+        let mut seq = Sequence::<RmwPoint>::new_messages(2).unwrap();
+        assert_eq!(seq.as_slice(), &[RmwPoint::default(), RmwPoint::default()]);
+        seq.fini_messages();
+        assert!(seq.is_empty());
+
+        let mut seq =
+            Sequence::<RmwPoint>::from_slice_converted(&[Point(1.0, 2.0), Point(3.0, 4.0)]);
+        let mut copy = seq.clone_messages().unwrap();
+        seq.fini_messages();
+        assert_eq!(
+            copy.as_slice(),
+            &[RmwPoint { x: 1.0, y: 2.0 }, RmwPoint { x: 3.0, y: 4.0 }]
+        );
+        copy.fini_messages();
+    }
+
+    #[test]
+    fn test_bounded_sequence() {
+        let points = [Point(1.0, 2.0), Point(3.0, 4.0)];
+        let seq = BoundedSequence::<RmwPoint, 2>::try_from_slice_converted(&points).unwrap();
+        assert_eq!(seq.len(), 2);
+        assert_eq!(seq.as_slice()[1], RmwPoint { x: 3.0, y: 4.0 });
+        let mut inner = seq.inner;
+        inner.fini_messages();
+
+        assert_eq!(
+            BoundedSequence::<RmwPoint, 1>::try_from_slice_converted(&points).unwrap_err(),
+            SequenceError::ExceedsBound { len: 2, bound: 1 }
+        );
+    }
+}
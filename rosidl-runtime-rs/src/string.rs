@@ -1,10 +1,14 @@
 //! Idiomatic Rust wrapper for ROS 2 strings
 //!
-//! Provides a safe, user-friendly API around the C `rosidl_runtime_c__String` type.
+//! Provides a safe, user-friendly API around the C `rosidl_runtime_c__String` type,
+//! and around `rosidl_runtime_c__U16String` for wide strings.
 
 use crate::ffi;
+use crate::sequence::Sequence;
+use crate::traits::SequenceAlloc;
 use std::ffi::CString;
 use std::fmt;
+use std::ops::Deref;
 
 /// ROS 2 string with automatic memory management
 ///
@@ -21,6 +25,7 @@ use std::fmt;
 ///
 /// s.assign("Updated!").unwrap();
 /// ```
+#[repr(transparent)]
 pub struct String {
     inner: ffi::rosidl_runtime_c__String,
 }
@@ -165,6 +170,349 @@ impl fmt::Debug for String {
     }
 }
 
+/// ROS 2 wide string (`wstring`) with automatic memory management
+///
+/// Safe wrapper around the C `rosidl_runtime_c__U16String`, which stores UTF-16
+/// code units. A `wstring` may hold unpaired surrogates that have no UTF-8
+/// equivalent, so the conversions to [`std::string::String`] (and `Display`) are
+/// lossy: such units become U+FFFD REPLACEMENT CHARACTER.
+///
+/// # Example
+/// ```ignore
+/// use rosidl_runtime_rs::WString;
+///
+/// let s = WString::from("Grüße");
+/// assert_eq!(s.len(), 5);
+/// assert_eq!(s.to_string_lossy(), "Grüße");
+/// ```
+#[repr(transparent)]
+pub struct WString {
+    inner: ffi::rosidl_runtime_c__U16String,
+}
+
+impl WString {
+    /// Create a new empty wide string
+    pub fn new() -> Self {
+        let mut inner = ffi::rosidl_runtime_c__U16String {
+            data: std::ptr::null_mut(),
+            size: 0,
+            capacity: 0,
+        };
+        unsafe {
+            ffi::rosidl_runtime_c__U16String__init(&mut inner);
+        }
+        Self { inner }
+    }
+
+    /// Get the UTF-16 code units (excluding null terminator)
+    pub fn as_slice(&self) -> &[u16] {
+        if self.inner.data.is_null() || self.inner.size == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.inner.data, self.inner.size) }
+    }
+
+    /// Get the length in UTF-16 code units (excluding null terminator)
+    pub fn len(&self) -> usize {
+        self.inner.size
+    }
+
+    /// Check if string is empty
+    pub fn is_empty(&self) -> bool {
+        self.inner.size == 0
+    }
+
+    /// Assign a new value to the string, encoded as UTF-16
+    pub fn assign(&mut self, value: &str) -> Result<(), StringError> {
+        let units: Vec<u16> = value.encode_utf16().collect();
+        unsafe {
+            if ffi::rosidl_runtime_c__U16String__assignn(
+                &mut self.inner,
+                units.as_ptr(),
+                units.len(),
+            ) {
+                Ok(())
+            } else {
+                Err(StringError::AllocationFailed)
+            }
+        }
+    }
+
+    /// Decode the string, replacing unpaired surrogates with U+FFFD
+    pub fn to_string_lossy(&self) -> std::string::String {
+        std::string::String::from_utf16_lossy(self.as_slice())
+    }
+
+    /// Get mutable access to the underlying FFI type
+    ///
+    /// # Safety
+    /// Caller must ensure the FFI type remains valid and properly initialized
+    pub unsafe fn as_mut_ffi(&mut self) -> &mut ffi::rosidl_runtime_c__U16String {
+        &mut self.inner
+    }
+
+    /// Get immutable access to the underlying FFI type
+    pub fn as_ffi(&self) -> &ffi::rosidl_runtime_c__U16String {
+        &self.inner
+    }
+}
+
+impl Default for WString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for WString {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rosidl_runtime_c__U16String__fini(&mut self.inner);
+        }
+    }
+}
+
+impl Clone for WString {
+    fn clone(&self) -> Self {
+        let mut new_string = WString::new();
+        unsafe {
+            ffi::rosidl_runtime_c__U16String__copy(&self.inner, &mut new_string.inner);
+        }
+        new_string
+    }
+}
+
+impl PartialEq for WString {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe { ffi::rosidl_runtime_c__U16String__are_equal(&self.inner, &other.inner) }
+    }
+}
+
+impl Eq for WString {}
+
+impl From<&str> for WString {
+    fn from(s: &str) -> Self {
+        let mut ros_str = WString::new();
+        ros_str
+            .assign(s)
+            .expect("Failed to allocate ROS wide string from Rust string");
+        ros_str
+    }
+}
+
+impl From<std::string::String> for WString {
+    fn from(s: std::string::String) -> Self {
+        WString::from(s.as_str())
+    }
+}
+
+impl From<&std::string::String> for WString {
+    fn from(s: &std::string::String) -> Self {
+        WString::from(s.as_str())
+    }
+}
+
+/// Lossy, see [`WString`]
+impl From<WString> for std::string::String {
+    fn from(s: WString) -> Self {
+        s.to_string_lossy()
+    }
+}
+
+/// Lossy, see [`WString`]
+impl From<&WString> for std::string::String {
+    fn from(s: &WString) -> Self {
+        s.to_string_lossy()
+    }
+}
+
+impl fmt::Display for WString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_lossy())
+    }
+}
+
+impl fmt::Debug for WString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WString({:?})", self.to_string_lossy())
+    }
+}
+
+/// String of at most `N` bytes (`string<=N`)
+///
+/// Same memory layout as [`String`], which it dereferences to. Values are
+/// created with `try_from`, which fails for text longer than the bound. Unlike
+/// [`bounded::BoundedString`](crate::bounded::BoundedString), the text is heap
+/// allocated by `rosidl_runtime_c`.
+#[repr(transparent)]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BoundedString<const N: usize> {
+    inner: String,
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.inner
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedString<N> {
+    type Error = StringError;
+
+    fn try_from(s: &str) -> Result<Self, StringError> {
+        if s.len() > N {
+            return Err(StringError::ExceedsBound {
+                len: s.len(),
+                bound: N,
+            });
+        }
+        Ok(Self {
+            inner: String::from(s),
+        })
+    }
+}
+
+impl<const N: usize> From<&BoundedString<N>> for std::string::String {
+    fn from(s: &BoundedString<N>) -> Self {
+        s.as_str().to_string()
+    }
+}
+
+impl<const N: usize> From<BoundedString<N>> for std::string::String {
+    fn from(s: BoundedString<N>) -> Self {
+        s.as_str().to_string()
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for BoundedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BoundedString<{}>({:?})", N, self.as_str())
+    }
+}
+
+/// Wide string of at most `N` UTF-16 code units (`wstring<=N`)
+///
+/// Same memory layout as [`WString`], which it dereferences to. Values are
+/// created with `try_from`, which fails for text longer than the bound.
+#[repr(transparent)]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BoundedWString<const N: usize> {
+    inner: WString,
+}
+
+impl<const N: usize> Deref for BoundedWString<N> {
+    type Target = WString;
+
+    fn deref(&self) -> &WString {
+        &self.inner
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for BoundedWString<N> {
+    type Error = StringError;
+
+    fn try_from(s: &str) -> Result<Self, StringError> {
+        let len = s.encode_utf16().count();
+        if len > N {
+            return Err(StringError::ExceedsBound { len, bound: N });
+        }
+        Ok(Self {
+            inner: WString::from(s),
+        })
+    }
+}
+
+/// Lossy, see [`WString`]
+impl<const N: usize> From<&BoundedWString<N>> for std::string::String {
+    fn from(s: &BoundedWString<N>) -> Self {
+        s.to_string_lossy()
+    }
+}
+
+/// Lossy, see [`WString`]
+impl<const N: usize> From<BoundedWString<N>> for std::string::String {
+    fn from(s: BoundedWString<N>) -> Self {
+        s.to_string_lossy()
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedWString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl<const N: usize> fmt::Debug for BoundedWString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "BoundedWString<{}>({:?})", N, self.to_string_lossy())
+    }
+}
+
+// Sequences of strings use the rosidl_runtime_c string sequence functions; the
+// wrappers are transparent, so the sequences share the layout of the C ones
+macro_rules! impl_string_sequence_alloc {
+    ([$($generics:tt)*] $rust_type:ty, $c_type:ty, $c_init:ident, $c_fini:ident, $c_copy:ident) => {
+        impl<$($generics)*> SequenceAlloc for $rust_type {
+            fn sequence_init(seq: &mut Sequence<Self>, size: usize) -> bool {
+                // SAFETY: The pointer comes from a mutable reference
+                unsafe { ffi::$c_init(seq.as_mut_ffi() as *mut _ as *mut _, size) }
+            }
+
+            fn sequence_fini(seq: &mut Sequence<Self>) {
+                // SAFETY: The pointer comes from a mutable reference
+                unsafe { ffi::$c_fini(seq.as_mut_ffi() as *mut _ as *mut _) }
+            }
+
+            fn sequence_copy(in_seq: &Sequence<Self>, out_seq: &mut Sequence<Self>) -> bool {
+                let input = in_seq.as_ffi() as *const ffi::SequenceInner<Self>;
+                // SAFETY: Both pointers come from references
+                unsafe {
+                    ffi::$c_copy(
+                        input as *const ffi::SequenceInner<$c_type>,
+                        out_seq.as_mut_ffi() as *mut _ as *mut _,
+                    )
+                }
+            }
+        }
+    };
+}
+
+impl_string_sequence_alloc!(
+    [] String,
+    ffi::rosidl_runtime_c__String,
+    rosidl_runtime_c__String__Sequence__init,
+    rosidl_runtime_c__String__Sequence__fini,
+    rosidl_runtime_c__String__Sequence__copy
+);
+impl_string_sequence_alloc!(
+    [] WString,
+    ffi::rosidl_runtime_c__U16String,
+    rosidl_runtime_c__U16String__Sequence__init,
+    rosidl_runtime_c__U16String__Sequence__fini,
+    rosidl_runtime_c__U16String__Sequence__copy
+);
+impl_string_sequence_alloc!(
+    [const N: usize] BoundedString<N>,
+    ffi::rosidl_runtime_c__String,
+    rosidl_runtime_c__String__Sequence__init,
+    rosidl_runtime_c__String__Sequence__fini,
+    rosidl_runtime_c__String__Sequence__copy
+);
+impl_string_sequence_alloc!(
+    [const N: usize] BoundedWString<N>,
+    ffi::rosidl_runtime_c__U16String,
+    rosidl_runtime_c__U16String__Sequence__init,
+    rosidl_runtime_c__U16String__Sequence__fini,
+    rosidl_runtime_c__U16String__Sequence__copy
+);
+
 /// Errors that can occur during string operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StringError {
@@ -172,6 +520,8 @@ pub enum StringError {
     NulByteInString,
     /// Memory allocation failed
     AllocationFailed,
+    /// String is longer than the bound of a bounded string type
+    ExceedsBound { len: usize, bound: usize },
 }
 
 impl fmt::Display for StringError {
@@ -179,6 +529,9 @@ impl fmt::Display for StringError {
         match self {
             StringError::NulByteInString => write!(f, "String contains null byte"),
             StringError::AllocationFailed => write!(f, "Memory allocation failed"),
+            StringError::ExceedsBound { len, bound } => {
+                write!(f, "String of length {} exceeds its bound of {}", len, bound)
+            }
         }
     }
 }
@@ -93,7 +93,8 @@ pub trait Service {
 
 /// Action definition with goal/result/feedback types
 ///
-/// Actions consist of three message types for asynchronous operations.
+/// Actions consist of three message types for asynchronous operations,
+/// exchanged through two implicit services and a feedback topic.
 pub trait Action {
     /// The goal message type
     type Goal;
@@ -101,6 +102,15 @@ pub trait Action {
     type Result;
     /// The feedback message type
     type Feedback;
+    /// The feedback message published with the ID of its goal
+    type FeedbackMessage;
+    /// The implicit service sending a goal
+    type SendGoalService: Service;
+    /// The implicit service requesting the result of a goal
+    type GetResultService: Service;
+
+    /// Get the type support handle for this action
+    fn get_type_support() -> *const std::ffi::c_void;
 }
 
 // Implement SequenceElement for std::string::String (maps to rosidl_runtime_rs::String)
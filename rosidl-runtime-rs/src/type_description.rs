@@ -0,0 +1,39 @@
+//! Type descriptions of ROS 2 interfaces (REP-2011)
+//!
+//! Mirrors of the `type_description_interfaces` messages as `const`-constructible
+//! structs. Generated crates expose `TYPE_DESCRIPTION` and
+//! `REFERENCED_TYPE_DESCRIPTIONS` of each message in their `type_description`
+//! module, next to the `TYPE_HASH` computed from them.
+//!
+//! This module does not need the `std` feature.
+
+/// Fields of one type (`type_description_interfaces/IndividualTypeDescription`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeDescription {
+    /// Name such as `std_msgs/msg/Header`
+    pub type_name: &'static str,
+    pub fields: &'static [Field],
+}
+
+/// Field of a type (`type_description_interfaces/Field`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub field_type: FieldType,
+    /// Default value as written in the definition, empty without one
+    pub default_value: &'static str,
+}
+
+/// Type of a field (`type_description_interfaces/FieldType`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldType {
+    /// Type id, such as 17 for `string`; arrays add 48, bounded sequences 96 and
+    /// unbounded sequences 144 to the id of their elements
+    pub type_id: u8,
+    /// Size of an array or bound of a bounded sequence, 0 otherwise
+    pub capacity: u64,
+    /// Bound of a bounded string, 0 otherwise
+    pub string_capacity: u64,
+    /// Name of nested types, such as `builtin_interfaces/msg/Time`, empty otherwise
+    pub nested_type_name: &'static str,
+}